@@ -8,11 +8,22 @@
 //! - Permit (gasless approvals) using Ed25519 signatures
 //! - Pausable functionality
 //! - Role-based access control
+//!
+//! Transfers and approvals emit logs through the VM host's log function:
+//! topic 0 is the event signature hash, the addresses are indexed as
+//! further topics and the Borsh-encoded value is the data.
 
 use borsh::{BorshSerialize, BorshDeserialize};
-use merklith_types::{Address, U256};
+use merklith_types::{Address, Ed25519PublicKey, Ed25519Signature, Hash, U256};
+use merklith_vm::HostState;
 use std::collections::HashMap;
 
+/// Signature of the transfer event
+pub const TRANSFER_EVENT: &str = "Transfer(address,address,uint256)";
+
+/// Signature of the approval event
+pub const APPROVAL_EVENT: &str = "Approval(address,address,uint256)";
+
 /// ERC20 Token Contract State
 #[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
 pub struct ERC20Token {
@@ -34,6 +45,8 @@ pub struct ERC20Token {
     pub paused: bool,
     /// Nonces for permit (address -> nonce) (O(1) lookup)
     pub nonces: HashMap<Address, u64>,
+    /// Address the token is deployed at, bound into permit digests
+    pub address: Address,
 }
 
 /// ERC20 Transfer Event
@@ -52,6 +65,40 @@ pub struct ApprovalEvent {
     pub value: U256,
 }
 
+/// Topic of an event signature: its blake3 hash
+pub fn event_topic(signature: &str) -> Hash {
+    Hash::compute(signature.as_bytes())
+}
+
+/// An address as an indexed topic, left-padded to 32 bytes
+pub fn address_topic(address: Address) -> Hash {
+    let mut topic = [0u8; 32];
+    topic[12..].copy_from_slice(address.as_bytes());
+    Hash::from_bytes(topic)
+}
+
+impl TransferEvent {
+    /// Emit the event through the host
+    pub fn emit(&self, host: &mut HostState) -> Result<(), ERC20Error> {
+        host.log(
+            vec![event_topic(TRANSFER_EVENT), address_topic(self.from), address_topic(self.to)],
+            borsh::to_vec(&self.value).unwrap_or_default(),
+        )
+        .map_err(|e| ERC20Error::Host(e.to_string()))
+    }
+}
+
+impl ApprovalEvent {
+    /// Emit the event through the host
+    pub fn emit(&self, host: &mut HostState) -> Result<(), ERC20Error> {
+        host.log(
+            vec![event_topic(APPROVAL_EVENT), address_topic(self.owner), address_topic(self.spender)],
+            borsh::to_vec(&self.value).unwrap_or_default(),
+        )
+        .map_err(|e| ERC20Error::Host(e.to_string()))
+    }
+}
+
 /// ERC20 Token Error Types
 #[derive(Debug, Clone, PartialEq)]
 pub enum ERC20Error {
@@ -73,6 +120,10 @@ pub enum ERC20Error {
     ZeroAddress,
     /// Overflow
     Overflow,
+    /// Permit deadline has passed
+    PermitExpired,
+    /// Host function failed, e.g. out of gas
+    Host(String),
 }
 
 impl std::fmt::Display for ERC20Error {
@@ -87,6 +138,8 @@ impl std::fmt::Display for ERC20Error {
             ERC20Error::NonceAlreadyUsed => write!(f, "Nonce already used"),
             ERC20Error::ZeroAddress => write!(f, "Zero address not allowed"),
             ERC20Error::Overflow => write!(f, "Arithmetic overflow"),
+            ERC20Error::PermitExpired => write!(f, "Permit expired"),
+            ERC20Error::Host(e) => write!(f, "Host error: {}", e),
         }
     }
 }
@@ -106,9 +159,16 @@ impl ERC20Token {
             owner,
             paused: false,
             nonces: HashMap::new(),
+            address: Address::ZERO,
         }
     }
 
+    /// Set the address the token is deployed at
+    pub fn with_address(mut self, address: Address) -> Self {
+        self.address = address;
+        self
+    }
+
     /// Initialize with initial supply (minted to owner)
     pub fn with_initial_supply(
         name: String,
@@ -153,6 +213,39 @@ impl ERC20Token {
         *self.allowances.get(&(owner, spender)).unwrap_or(&U256::ZERO)
     }
 
+    fn emit_transfer(
+        host: &mut HostState,
+        from: Address,
+        to: Address,
+        value: U256,
+    ) -> Result<TransferEvent, ERC20Error> {
+        let event = TransferEvent { from, to, value };
+        event.emit(host)?;
+        Ok(event)
+    }
+
+    fn emit_approval(
+        host: &mut HostState,
+        owner: Address,
+        spender: Address,
+        value: U256,
+    ) -> Result<ApprovalEvent, ERC20Error> {
+        let event = ApprovalEvent { owner, spender, value };
+        event.emit(host)?;
+        Ok(event)
+    }
+
+    /// Update allowance
+    fn set_allowance(&mut self, owner: Address, spender: Address, value: U256) {
+        // Update or remove allowance using HashMap (O(1) operation)
+        if value == U256::ZERO {
+            // Remove zero allowances to save space
+            self.allowances.remove(&(owner, spender));
+        } else {
+            self.allowances.insert((owner, spender), value);
+        }
+    }
+
     /// Transfer tokens (internal)
     fn _transfer(&mut self, from: Address, to: Address, value: U256) -> Result<(), ERC20Error> {
         if self.paused {
@@ -195,18 +288,18 @@ impl ERC20Token {
     }
 
     /// Transfer tokens
-    pub fn transfer(&mut self, from: Address, to: Address, value: U256) -> Result<TransferEvent, ERC20Error> {
+    pub fn transfer(&mut self, host: &mut HostState, from: Address, to: Address, value: U256) -> Result<TransferEvent, ERC20Error> {
         if to == Address::ZERO {
             return Err(ERC20Error::ZeroAddress);
         }
 
         self._transfer(from, to, value)?;
 
-        Ok(TransferEvent { from, to, value })
+        Self::emit_transfer(host, from, to, value)
     }
 
     /// Approve spender
-    pub fn approve(&mut self, owner: Address, spender: Address, value: U256) -> Result<ApprovalEvent, ERC20Error> {
+    pub fn approve(&mut self, host: &mut HostState, owner: Address, spender: Address, value: U256) -> Result<ApprovalEvent, ERC20Error> {
         if spender == Address::ZERO {
             return Err(ERC20Error::ZeroAddress);
         }
@@ -215,20 +308,15 @@ impl ERC20Token {
             return Err(ERC20Error::ContractPaused);
         }
 
-        // Update or remove allowance using HashMap (O(1) operation)
-        if value == U256::ZERO {
-            // Remove zero allowances to save space
-            self.allowances.remove(&(owner, spender));
-        } else {
-            self.allowances.insert((owner, spender), value);
-        }
+        self.set_allowance(owner, spender, value);
 
-        Ok(ApprovalEvent { owner, spender, value })
+        Self::emit_approval(host, owner, spender, value)
     }
 
     /// Transfer from (with allowance)
     pub fn transfer_from(
         &mut self,
+        host: &mut HostState,
         spender: Address,
         from: Address,
         to: Address,
@@ -243,18 +331,15 @@ impl ERC20Token {
             return Err(ERC20Error::InsufficientAllowance);
         }
 
-        // Update allowance
-        let new_allowance = current_allowance - value;
-        self.approve(from, spender, new_allowance)?;
-
-        // Perform transfer
+        // Perform transfer, then spend the allowance
         self._transfer(from, to, value)?;
+        self.set_allowance(from, spender, current_allowance - value);
 
-        Ok(TransferEvent { from, to, value })
+        Self::emit_transfer(host, from, to, value)
     }
 
     /// Mint new tokens (owner only)
-    pub fn mint(&mut self, caller: Address, to: Address, value: U256) -> Result<TransferEvent, ERC20Error> {
+    pub fn mint(&mut self, host: &mut HostState, caller: Address, to: Address, value: U256) -> Result<TransferEvent, ERC20Error> {
         if caller != self.owner {
             return Err(ERC20Error::NotOwner);
         }
@@ -280,15 +365,11 @@ impl ERC20Token {
         self.update_balance(to, to_balance + value)?;
 
         // Emit event (from zero address for minting)
-        Ok(TransferEvent {
-            from: Address::ZERO,
-            to,
-            value,
-        })
+        Self::emit_transfer(host, Address::ZERO, to, value)
     }
 
     /// Burn tokens
-    pub fn burn(&mut self, caller: Address, value: U256) -> Result<TransferEvent, ERC20Error> {
+    pub fn burn(&mut self, host: &mut HostState, caller: Address, value: U256) -> Result<TransferEvent, ERC20Error> {
         if self.paused {
             return Err(ERC20Error::ContractPaused);
         }
@@ -309,16 +390,13 @@ impl ERC20Token {
         self.update_balance(caller, caller_balance - value)?;
 
         // Emit event (to zero address for burning)
-        Ok(TransferEvent {
-            from: caller,
-            to: Address::ZERO,
-            value,
-        })
+        Self::emit_transfer(host, caller, Address::ZERO, value)
     }
 
     /// Burn from (with allowance)
     pub fn burn_from(
         &mut self,
+        host: &mut HostState,
         caller: Address,
         from: Address,
         value: U256,
@@ -328,9 +406,9 @@ impl ERC20Token {
             return Err(ERC20Error::InsufficientAllowance);
         }
 
-        // Update allowance
-        let new_allowance = current_allowance - value;
-        self.approve(from, caller, new_allowance)?;
+        if self.paused {
+            return Err(ERC20Error::ContractPaused);
+        }
 
         // Perform burn
         let from_balance = self.balance_of(from);
@@ -338,17 +416,16 @@ impl ERC20Token {
             return Err(ERC20Error::InsufficientBalance);
         }
 
+        // Update allowance
+        self.set_allowance(from, caller, current_allowance - value);
+
         // Update total supply
         self.total_supply = self.total_supply - value;
 
         // Update balance
         self.update_balance(from, from_balance - value)?;
 
-        Ok(TransferEvent {
-            from,
-            to: Address::ZERO,
-            value,
-        })
+        Self::emit_transfer(host, from, Address::ZERO, value)
     }
 
     /// Pause contract (owner only)
@@ -404,46 +481,52 @@ impl ERC20Token {
         }
     }
 
+    /// Digest `owner` signs to permit `spender` to spend `value` until
+    /// `deadline`. It binds the token address and the owner's current nonce,
+    /// so a permit cannot be replayed or used on another token.
+    pub fn permit_digest(&self, owner: Address, spender: Address, value: U256, deadline: u64) -> Hash {
+        Hash::compute_multi(&[
+            b"MERKLITH_PERMIT",
+            self.address.as_bytes(),
+            owner.as_bytes(),
+            spender.as_bytes(),
+            &value.to_be_bytes(),
+            &self.nonce(owner).to_le_bytes(),
+            &deadline.to_le_bytes(),
+        ])
+    }
+
     /// Permit (gasless approval with signature)
-    /// 
-    /// # SECURITY WARNING
-    /// This is a DEMONSTRATION implementation only. Signature verification is NOT implemented.
-    /// DO NOT use this in production - it allows anyone to approve any address to spend tokens.
-    /// 
-    /// In a production implementation, you must:
-    /// 1. Construct a message hash from (owner, spender, value, deadline, nonce)
-    /// 2. Verify the Ed25519 signature against the owner's public key
-    /// 3. Only proceed if signature is valid
+    ///
+    /// Approves `spender` on behalf of `owner` given the owner's ed25519
+    /// signature over [`ERC20Token::permit_digest`], so a relayer can submit
+    /// the approval and pay its gas. `now` is the block timestamp; permits
+    /// past their deadline are rejected.
+    #[allow(clippy::too_many_arguments)]
     pub fn permit(
         &mut self,
+        host: &mut HostState,
         owner: Address,
         spender: Address,
         value: U256,
         deadline: u64,
-        signature: &[u8],
+        now: u64,
+        public_key: &Ed25519PublicKey,
+        signature: &Ed25519Signature,
     ) -> Result<ApprovalEvent, ERC20Error> {
-        // Check deadline
-        let current_time = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_secs();
-        
-        if current_time > deadline {
-            return Err(ERC20Error::InvalidSignature);
+        if now > deadline {
+            return Err(ERC20Error::PermitExpired);
         }
 
-        // SECURITY: This is a placeholder - signature verification is NOT implemented
-        // In production, you MUST verify the Ed25519 signature here
-        // For now, this function is disabled for security
-        return Err(ERC20Error::InvalidSignature);
+        if public_key.to_address() != owner {
+            return Err(ERC20Error::InvalidSignature);
+        }
+        let digest = self.permit_digest(owner, spender, value, deadline);
+        merklith_crypto::ed25519_verify(public_key, digest.as_bytes(), signature)
+            .map_err(|_| ERC20Error::InvalidSignature)?;
 
-        // TODO: Implement proper signature verification
-        // let nonce = self.use_nonce(owner);
-        // let message = construct_permit_message(owner, spender, value, deadline, nonce);
-        // if !ed25519_verify(&message, signature, &owner) {
-        //     return Err(ERC20Error::InvalidSignature);
-        // }
-        // self.approve(owner, spender, value)
+        self.use_nonce(owner);
+        self.approve(host, owner, spender, value)
     }
 
     /// Transfer ownership (owner only)
@@ -469,6 +552,15 @@ impl ERC20Token {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use merklith_vm::{GasSchedule, GasTracker};
+
+    fn test_host() -> HostState {
+        HostState::new(
+            Address::from_bytes([9u8; 20]),
+            Address::ZERO,
+            GasTracker::with_default_schedule(1_000_000),
+        )
+    }
 
     fn create_test_token() -> ERC20Token {
         let owner = Address::from_bytes([1u8; 20]);
@@ -505,8 +597,9 @@ mod tests {
         let owner = Address::from_bytes([1u8; 20]);
         let recipient = Address::from_bytes([2u8; 20]);
         let mut token = create_test_token();
+        let mut host = test_host();
 
-        let result = token.transfer(owner, recipient, U256::from(1000u64));
+        let result = token.transfer(&mut host, owner, recipient, U256::from(1000u64));
         assert!(result.is_ok());
 
         assert_eq!(token.balance_of(owner), U256::from(999000u64));
@@ -518,8 +611,9 @@ mod tests {
         let owner = Address::from_bytes([1u8; 20]);
         let recipient = Address::from_bytes([2u8; 20]);
         let mut token = create_test_token();
+        let mut host = test_host();
 
-        let result = token.transfer(owner, recipient, U256::from(2000000u64));
+        let result = token.transfer(&mut host, owner, recipient, U256::from(2000000u64));
         assert!(matches!(result, Err(ERC20Error::InsufficientBalance)));
     }
 
@@ -529,14 +623,15 @@ mod tests {
         let spender = Address::from_bytes([2u8; 20]);
         let recipient = Address::from_bytes([3u8; 20]);
         let mut token = create_test_token();
+        let mut host = test_host();
 
         // Approve
-        let result = token.approve(owner, spender, U256::from(5000u64));
+        let result = token.approve(&mut host, owner, spender, U256::from(5000u64));
         assert!(result.is_ok());
         assert_eq!(token.allowance(owner, spender), U256::from(5000u64));
 
         // Transfer from
-        let result = token.transfer_from(spender, owner, recipient, U256::from(3000u64));
+        let result = token.transfer_from(&mut host, spender, owner, recipient, U256::from(3000u64));
         assert!(result.is_ok());
 
         assert_eq!(token.balance_of(owner), U256::from(997000u64));
@@ -544,13 +639,100 @@ mod tests {
         assert_eq!(token.allowance(owner, spender), U256::from(2000u64));
     }
 
+    #[test]
+    fn test_transfer_and_approve_emit_logs() {
+        let owner = Address::from_bytes([1u8; 20]);
+        let spender = Address::from_bytes([2u8; 20]);
+        let recipient = Address::from_bytes([3u8; 20]);
+        let mut token = create_test_token();
+        let mut host = test_host();
+        let schedule = GasSchedule::default();
+
+        token.transfer(&mut host, owner, recipient, U256::from(1000u64)).unwrap();
+        let logs = host.take_logs();
+        assert_eq!(logs.len(), 1);
+        assert_eq!(
+            logs[0].topics,
+            vec![event_topic(TRANSFER_EVENT), address_topic(owner), address_topic(recipient)]
+        );
+        assert_eq!(logs[0].topics[1].as_bytes()[12..], *owner.as_bytes());
+        assert_eq!(U256::try_from_slice(&logs[0].data).unwrap(), U256::from(1000u64));
+        // The host charges for the log like the LOG opcodes
+        assert_eq!(
+            host.gas_tracker.used(),
+            schedule.log_base + schedule.log_per_topic * 3 + schedule.log_per_byte * logs[0].data.len() as u64
+        );
+
+        token.approve(&mut host, owner, spender, U256::from(500u64)).unwrap();
+        let logs = host.take_logs();
+        assert_eq!(
+            logs[0].topics,
+            vec![event_topic(APPROVAL_EVENT), address_topic(owner), address_topic(spender)]
+        );
+        assert_eq!(U256::try_from_slice(&logs[0].data).unwrap(), U256::from(500u64));
+
+        // Spending an allowance logs only the transfer; failures log nothing
+        token.transfer_from(&mut host, spender, owner, recipient, U256::from(200u64)).unwrap();
+        let logs = host.take_logs();
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0].topics[0], event_topic(TRANSFER_EVENT));
+        assert!(token.transfer(&mut host, spender, recipient, U256::from(1u64)).is_err());
+        assert!(host.take_logs().is_empty());
+
+        // A call without gas left for the log fails
+        let mut starved = HostState::new(Address::ZERO, owner, GasTracker::with_default_schedule(100));
+        let result = token.transfer(&mut starved, owner, recipient, U256::from(1u64));
+        assert!(matches!(result, Err(ERC20Error::Host(_))));
+    }
+
+    #[test]
+    fn test_permit() {
+        use merklith_crypto::Keypair;
+
+        let holder = Keypair::from_seed(&[5u8; 32]);
+        let owner = holder.address();
+        let spender = Address::from_bytes([2u8; 20]);
+        let mut token = create_test_token().with_address(Address::from_bytes([9u8; 20]));
+        let mut host = test_host();
+        let value = U256::from(700u64);
+        let deadline = 1_000;
+
+        let signature = holder.sign(token.permit_digest(owner, spender, value, deadline).as_bytes());
+        let public_key = holder.public_key();
+
+        // Expired
+        let result = token.permit(&mut host, owner, spender, value, deadline, deadline + 1, &public_key, &signature);
+        assert_eq!(result.unwrap_err(), ERC20Error::PermitExpired);
+
+        // Signed for a different value
+        let result = token.permit(&mut host, owner, spender, U256::from(701u64), deadline, 10, &public_key, &signature);
+        assert_eq!(result.unwrap_err(), ERC20Error::InvalidSignature);
+
+        // Someone else's key
+        let other = Keypair::from_seed(&[6u8; 32]).public_key();
+        let result = token.permit(&mut host, owner, spender, value, deadline, 10, &other, &signature);
+        assert_eq!(result.unwrap_err(), ERC20Error::InvalidSignature);
+
+        token.permit(&mut host, owner, spender, value, deadline, 10, &public_key, &signature).unwrap();
+        assert_eq!(token.allowance(owner, spender), value);
+        assert_eq!(token.nonce(owner), 1);
+        assert_eq!(host.take_logs()[0].topics[0], event_topic(APPROVAL_EVENT));
+
+        // The nonce moved on, so the same permit cannot be replayed
+        token.approve(&mut host, owner, spender, U256::ZERO).unwrap();
+        let result = token.permit(&mut host, owner, spender, value, deadline, 10, &public_key, &signature);
+        assert_eq!(result.unwrap_err(), ERC20Error::InvalidSignature);
+        assert_eq!(token.allowance(owner, spender), U256::ZERO);
+    }
+
     #[test]
     fn test_mint() {
         let owner = Address::from_bytes([1u8; 20]);
         let recipient = Address::from_bytes([2u8; 20]);
         let mut token = create_test_token();
+        let mut host = test_host();
 
-        let result = token.mint(owner, recipient, U256::from(500000u64));
+        let result = token.mint(&mut host, owner, recipient, U256::from(500000u64));
         assert!(result.is_ok());
 
         assert_eq!(token.total_supply(), U256::from(1500000u64));
@@ -562,8 +744,9 @@ mod tests {
         let owner = Address::from_bytes([1u8; 20]);
         let not_owner = Address::from_bytes([2u8; 20]);
         let mut token = create_test_token();
+        let mut host = test_host();
 
-        let result = token.mint(not_owner, not_owner, U256::from(1000u64));
+        let result = token.mint(&mut host, not_owner, not_owner, U256::from(1000u64));
         assert!(matches!(result, Err(ERC20Error::NotOwner)));
     }
 
@@ -571,8 +754,9 @@ mod tests {
     fn test_burn() {
         let owner = Address::from_bytes([1u8; 20]);
         let mut token = create_test_token();
+        let mut host = test_host();
 
-        let result = token.burn(owner, U256::from(500000u64));
+        let result = token.burn(&mut host, owner, U256::from(500000u64));
         assert!(result.is_ok());
 
         assert_eq!(token.total_supply(), U256::from(500000u64));
@@ -584,6 +768,7 @@ mod tests {
         let owner = Address::from_bytes([1u8; 20]);
         let recipient = Address::from_bytes([2u8; 20]);
         let mut token = create_test_token();
+        let mut host = test_host();
 
         // Pause contract
         let result = token.pause(owner);
@@ -591,7 +776,7 @@ mod tests {
         assert!(token.is_paused());
 
         // Transfer should fail when paused
-        let result = token.transfer(owner, recipient, U256::from(1000u64));
+        let result = token.transfer(&mut host, owner, recipient, U256::from(1000u64));
         assert!(matches!(result, Err(ERC20Error::ContractPaused)));
 
         // Unpause
@@ -600,7 +785,7 @@ mod tests {
         assert!(!token.is_paused());
 
         // Transfer should work now
-        let result = token.transfer(owner, recipient, U256::from(1000u64));
+        let result = token.transfer(&mut host, owner, recipient, U256::from(1000u64));
         assert!(result.is_ok());
     }
 
@@ -6,11 +6,15 @@ pub mod erc20;
 pub mod erc721;
 pub mod bridge;
 pub mod governance;
+pub mod staking;
+pub mod multisig;
 
 pub use erc20::{ERC20Token, TransferEvent, ApprovalEvent, ERC20Error};
 pub use erc721::{ERC721Token, TransferEvent as NFTTransferEvent, ApprovalEvent as NFTApprovalEvent, ERC721Error};
 pub use bridge::{BridgeContract, BridgeEvent, BridgeRequest, BridgeError};
 pub use governance::{GovernanceContract, Proposal, ProposalEvent, VoteEvent, GovernanceError};
+pub use staking::{StakingContract, Stake, StakingError};
+pub use multisig::{MultisigWallet, MultisigTransaction, MultisigError};
 
 /// Contract version
 pub const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
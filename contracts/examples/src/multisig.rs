@@ -0,0 +1,316 @@
+//! Multisig Wallet Example
+//!
+//! M-of-N owners approve a transaction (target, value, data) on chain; once
+//! enough have approved, any owner can execute it.
+//!
+//! Features:
+//! - Owners and threshold fixed at construction
+//! - Per-transaction approval tracking, with revocation before execution
+//! - `Submission`/`Approval`/`Execution` logs, indexed by transaction id
+//! - Each transaction executes at most once
+
+use borsh::{BorshSerialize, BorshDeserialize};
+use merklith_types::{Address, Hash, Log, U256};
+use std::collections::HashSet;
+
+use crate::erc20::{address_topic, event_topic};
+
+/// Signature of the submission event
+pub const SUBMISSION_EVENT: &str = "Submission(uint256,address)";
+
+/// Signature of the approval event
+pub const APPROVAL_EVENT: &str = "Approval(uint256,address)";
+
+/// Signature of the execution event
+pub const EXECUTION_EVENT: &str = "Execution(uint256,address)";
+
+/// Performs an approved call from the wallet: sends `value` to `target`
+/// with `data`.
+pub type Dispatch<'a> = &'a mut dyn FnMut(Address, U256, &[u8]) -> Result<(), MultisigError>;
+
+/// Multisig Wallet State
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
+pub struct MultisigWallet {
+    /// Address the wallet is deployed at
+    pub address: Address,
+    /// Owners allowed to submit, approve and execute
+    pub owners: Vec<Address>,
+    /// Approvals needed to execute
+    pub threshold: usize,
+    /// Submitted transactions, indexed by id
+    pub transactions: Vec<MultisigTransaction>,
+    /// Logs emitted since they were last taken
+    #[borsh(skip)]
+    pub logs: Vec<Log>,
+}
+
+/// A transaction awaiting approval
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct MultisigTransaction {
+    /// Account called
+    pub target: Address,
+    /// Value sent with the call
+    pub value: U256,
+    /// Call data
+    pub data: Vec<u8>,
+    /// Owners who approved
+    pub approvals: HashSet<Address>,
+    /// Whether it has been executed
+    pub executed: bool,
+}
+
+/// Multisig Error Types
+#[derive(Debug, Clone, PartialEq)]
+pub enum MultisigError {
+    /// Caller is not an owner
+    NotOwner,
+    /// No transaction with this id
+    UnknownTransaction(u64),
+    /// Owner already approved the transaction
+    AlreadyApproved,
+    /// Owner has not approved the transaction
+    NotApproved,
+    /// Transaction has already been executed
+    AlreadyExecuted,
+    /// Fewer approvals than the threshold
+    ThresholdNotMet { approvals: usize, threshold: usize },
+    /// Owners or threshold are invalid
+    InvalidConfiguration(String),
+    /// The dispatched call failed
+    CallFailed(String),
+}
+
+impl std::fmt::Display for MultisigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MultisigError::NotOwner => write!(f, "Caller is not an owner"),
+            MultisigError::UnknownTransaction(id) => write!(f, "Unknown transaction {}", id),
+            MultisigError::AlreadyApproved => write!(f, "Transaction already approved by caller"),
+            MultisigError::NotApproved => write!(f, "Transaction not approved by caller"),
+            MultisigError::AlreadyExecuted => write!(f, "Transaction already executed"),
+            MultisigError::ThresholdNotMet { approvals, threshold } => {
+                write!(f, "{} of {} required approvals", approvals, threshold)
+            }
+            MultisigError::InvalidConfiguration(msg) => write!(f, "Invalid configuration: {}", msg),
+            MultisigError::CallFailed(msg) => write!(f, "Call failed: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for MultisigError {}
+
+impl MultisigWallet {
+    /// Create a wallet needing `threshold` of `owners` to approve
+    pub fn new(address: Address, owners: Vec<Address>, threshold: usize) -> Result<Self, MultisigError> {
+        let unique: HashSet<_> = owners.iter().collect();
+        if unique.len() != owners.len() {
+            return Err(MultisigError::InvalidConfiguration("Duplicate owner".to_string()));
+        }
+        if owners.contains(&Address::ZERO) {
+            return Err(MultisigError::InvalidConfiguration("Zero address owner".to_string()));
+        }
+        if threshold == 0 || threshold > owners.len() {
+            return Err(MultisigError::InvalidConfiguration(format!(
+                "Threshold {} with {} owners",
+                threshold,
+                owners.len()
+            )));
+        }
+
+        Ok(Self {
+            address,
+            owners,
+            threshold,
+            transactions: Vec::new(),
+            logs: Vec::new(),
+        })
+    }
+
+    /// Check if `address` is an owner
+    pub fn is_owner(&self, address: Address) -> bool {
+        self.owners.contains(&address)
+    }
+
+    /// Get transaction by id
+    pub fn transaction(&self, id: u64) -> Option<&MultisigTransaction> {
+        self.transactions.get(id as usize)
+    }
+
+    /// Take the logs emitted so far, as the VM does at the end of a call
+    pub fn take_logs(&mut self) -> Vec<Log> {
+        std::mem::take(&mut self.logs)
+    }
+
+    fn emit(&mut self, signature: &str, id: u64, owner: Address) {
+        let topics = vec![event_topic(signature), Hash::from_bytes(U256::from(id).to_be_bytes()), address_topic(owner)];
+        self.logs.push(Log::new(self.address, topics, Vec::new()));
+    }
+
+    fn only_owner(&self, caller: Address) -> Result<(), MultisigError> {
+        if self.is_owner(caller) {
+            Ok(())
+        } else {
+            Err(MultisigError::NotOwner)
+        }
+    }
+
+    fn pending_mut(&mut self, id: u64) -> Result<&mut MultisigTransaction, MultisigError> {
+        let tx = self.transactions.get_mut(id as usize).ok_or(MultisigError::UnknownTransaction(id))?;
+        if tx.executed {
+            return Err(MultisigError::AlreadyExecuted);
+        }
+        Ok(tx)
+    }
+
+    /// Submit a transaction for approval, returning its id
+    pub fn submit(&mut self, caller: Address, target: Address, value: U256, data: Vec<u8>) -> Result<u64, MultisigError> {
+        self.only_owner(caller)?;
+
+        let id = self.transactions.len() as u64;
+        self.transactions.push(MultisigTransaction {
+            target,
+            value,
+            data,
+            approvals: HashSet::new(),
+            executed: false,
+        });
+        self.emit(SUBMISSION_EVENT, id, caller);
+        Ok(id)
+    }
+
+    /// Approve transaction `id`
+    pub fn approve(&mut self, caller: Address, id: u64) -> Result<(), MultisigError> {
+        self.only_owner(caller)?;
+
+        if !self.pending_mut(id)?.approvals.insert(caller) {
+            return Err(MultisigError::AlreadyApproved);
+        }
+        self.emit(APPROVAL_EVENT, id, caller);
+        Ok(())
+    }
+
+    /// Withdraw an approval of transaction `id` before it executes
+    pub fn revoke(&mut self, caller: Address, id: u64) -> Result<(), MultisigError> {
+        self.only_owner(caller)?;
+
+        if !self.pending_mut(id)?.approvals.remove(&caller) {
+            return Err(MultisigError::NotApproved);
+        }
+        Ok(())
+    }
+
+    /// Execute transaction `id` through `dispatch` once it has enough approvals
+    pub fn execute(&mut self, caller: Address, id: u64, dispatch: Dispatch) -> Result<(), MultisigError> {
+        self.only_owner(caller)?;
+
+        let threshold = self.threshold;
+        let tx = self.pending_mut(id)?;
+        if tx.approvals.len() < threshold {
+            return Err(MultisigError::ThresholdNotMet {
+                approvals: tx.approvals.len(),
+                threshold,
+            });
+        }
+
+        // Marked first so the call cannot execute it again
+        tx.executed = true;
+        let (target, value, data) = (tx.target, tx.value, tx.data.clone());
+        if let Err(e) = dispatch(target, value, &data) {
+            // A failed call reverts, leaving the transaction pending
+            if let Some(tx) = self.transactions.get_mut(id as usize) {
+                tx.executed = false;
+            }
+            return Err(e);
+        }
+
+        self.emit(EXECUTION_EVENT, id, caller);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn owners() -> Vec<Address> {
+        (1..=3u8).map(|i| Address::from_bytes([i; 20])).collect()
+    }
+
+    fn create_test_wallet() -> MultisigWallet {
+        MultisigWallet::new(Address::from_bytes([9u8; 20]), owners(), 2).unwrap()
+    }
+
+    #[test]
+    fn test_configuration() {
+        let wallet = Address::from_bytes([9u8; 20]);
+        assert!(MultisigWallet::new(wallet, owners(), 0).is_err());
+        assert!(MultisigWallet::new(wallet, owners(), 4).is_err());
+        let mut duplicated = owners();
+        duplicated.push(duplicated[0]);
+        assert!(MultisigWallet::new(wallet, duplicated, 2).is_err());
+    }
+
+    #[test]
+    fn test_two_of_three() {
+        let owners = owners();
+        let target = Address::from_bytes([7u8; 20]);
+        let mut wallet = create_test_wallet();
+        let mut calls = Vec::new();
+        let mut dispatch = |target: Address, value: U256, data: &[u8]| {
+            calls.push((target, value, data.to_vec()));
+            Ok(())
+        };
+
+        let id = wallet.submit(owners[0], target, U256::from(100u64), vec![0xab]).unwrap();
+        wallet.approve(owners[0], id).unwrap();
+        assert_eq!(wallet.approve(owners[0], id), Err(MultisigError::AlreadyApproved));
+
+        // One approval is not enough
+        assert_eq!(
+            wallet.execute(owners[0], id, &mut dispatch),
+            Err(MultisigError::ThresholdNotMet { approvals: 1, threshold: 2 })
+        );
+
+        wallet.approve(owners[2], id).unwrap();
+        wallet.execute(owners[1], id, &mut dispatch).unwrap();
+        assert!(wallet.transaction(id).unwrap().executed);
+        assert_eq!(wallet.execute(owners[1], id, &mut dispatch), Err(MultisigError::AlreadyExecuted));
+        assert_eq!(calls, vec![(target, U256::from(100u64), vec![0xab])]);
+
+        let logs = wallet.take_logs();
+        assert_eq!(logs.len(), 4);
+        assert_eq!(logs[3].topics[0], event_topic(EXECUTION_EVENT));
+        assert_eq!(logs[3].topics[2], address_topic(owners[1]));
+    }
+
+    #[test]
+    fn test_non_owner_rejected() {
+        let owners = owners();
+        let outsider = Address::from_bytes([8u8; 20]);
+        let mut wallet = create_test_wallet();
+
+        let id = wallet.submit(owners[0], outsider, U256::ZERO, Vec::new()).unwrap();
+        assert_eq!(wallet.approve(outsider, id), Err(MultisigError::NotOwner));
+        assert_eq!(wallet.submit(outsider, outsider, U256::ZERO, Vec::new()), Err(MultisigError::NotOwner));
+        assert!(wallet.transaction(id).unwrap().approvals.is_empty());
+        assert_eq!(wallet.approve(owners[0], 5), Err(MultisigError::UnknownTransaction(5)));
+    }
+
+    #[test]
+    fn test_revoke_and_failed_call() {
+        let owners = owners();
+        let mut wallet = create_test_wallet();
+        let id = wallet.submit(owners[0], owners[1], U256::ONE, Vec::new()).unwrap();
+        wallet.approve(owners[0], id).unwrap();
+        wallet.approve(owners[1], id).unwrap();
+        wallet.revoke(owners[1], id).unwrap();
+        assert_eq!(wallet.revoke(owners[1], id), Err(MultisigError::NotApproved));
+        wallet.approve(owners[1], id).unwrap();
+
+        // A failed call leaves the transaction pending
+        let result = wallet.execute(owners[0], id, &mut |_, _, _| Err(MultisigError::CallFailed("out of gas".to_string())));
+        assert!(matches!(result, Err(MultisigError::CallFailed(_))));
+        assert!(!wallet.transaction(id).unwrap().executed);
+        wallet.execute(owners[0], id, &mut |_, _, _| Ok(())).unwrap();
+    }
+}
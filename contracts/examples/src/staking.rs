@@ -0,0 +1,389 @@
+//! Staking Contract Example
+//!
+//! A reference for validators: stake MERK, earn rewards in proportion to
+//! stake and blocks staked, and unstake once the lock period has passed.
+//!
+//! Features:
+//! - Rewards accrue per block at a fixed rate per staked MERK
+//! - Stakes are locked for `lock_period` blocks after the last deposit
+//! - `Staked`/`Unstaked`/`RewardClaimed` logs, indexed by staker
+//! - Unstaking and claiming run under the VM's reentrancy guard, so a
+//!   payout recipient cannot call back into the contract
+
+use borsh::{BorshSerialize, BorshDeserialize};
+use merklith_types::{Address, Log, U256};
+use merklith_vm::ReentrancyGuard;
+use std::collections::HashMap;
+
+use crate::erc20::{address_topic, event_topic};
+
+/// Signature of the stake event
+pub const STAKED_EVENT: &str = "Staked(address,uint256)";
+
+/// Signature of the unstake event
+pub const UNSTAKED_EVENT: &str = "Unstaked(address,uint256)";
+
+/// Signature of the reward claim event
+pub const REWARD_CLAIMED_EVENT: &str = "RewardClaimed(address,uint256)";
+
+/// Scale of `reward_rate` (1 MERK = 1e18 Spark)
+pub const REWARD_PRECISION: u128 = 1_000_000_000_000_000_000;
+
+/// Sends `amount` to `to` on behalf of the contract. The contract is passed
+/// back in, as a called account could call into it again.
+pub type Payout<'a> = &'a mut dyn FnMut(&mut StakingContract, Address, U256) -> Result<(), StakingError>;
+
+/// Staking Contract State
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
+pub struct StakingContract {
+    /// Address the contract is deployed at
+    pub address: Address,
+    /// Contract owner
+    pub owner: Address,
+    /// Reward per staked MERK per block, in Spark
+    pub reward_rate: U256,
+    /// Blocks a stake stays locked after the last deposit
+    pub lock_period: u64,
+    /// Total staked amount
+    pub total_staked: U256,
+    /// Stakes by staker
+    pub stakes: HashMap<Address, Stake>,
+    /// Rewards funded by the owner and not yet paid out
+    pub reward_pool: U256,
+    /// Logs emitted since they were last taken
+    #[borsh(skip)]
+    pub logs: Vec<Log>,
+    /// Call frames of guarded calls in progress
+    #[borsh(skip)]
+    guard: ReentrancyGuard,
+}
+
+/// A staker's position
+#[derive(Debug, Clone, Default, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct Stake {
+    /// Amount staked
+    pub amount: U256,
+    /// Block of the last deposit
+    pub staked_at: u64,
+    /// Block rewards were last accrued at
+    pub accrued_at: u64,
+    /// Rewards accrued and not yet claimed
+    pub unclaimed: U256,
+}
+
+/// Staking Error Types
+#[derive(Debug, Clone, PartialEq)]
+pub enum StakingError {
+    /// Invalid amount
+    InvalidAmount,
+    /// Caller has no stake, or less than requested
+    InsufficientStake,
+    /// Stake is still locked until the given block
+    StillLocked { unlocks_at: u64 },
+    /// Not enough funded rewards to pay out
+    InsufficientRewards,
+    /// Not the owner
+    NotOwner,
+    /// Payout tried to call back into the contract
+    Reentrancy,
+    /// Paying out failed
+    PayoutFailed(String),
+    /// Overflow
+    Overflow,
+}
+
+impl std::fmt::Display for StakingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StakingError::InvalidAmount => write!(f, "Invalid amount"),
+            StakingError::InsufficientStake => write!(f, "Insufficient stake"),
+            StakingError::StillLocked { unlocks_at } => write!(f, "Stake is locked until block {}", unlocks_at),
+            StakingError::InsufficientRewards => write!(f, "Insufficient rewards funded"),
+            StakingError::NotOwner => write!(f, "Caller is not the owner"),
+            StakingError::Reentrancy => write!(f, "Reentrant call"),
+            StakingError::PayoutFailed(msg) => write!(f, "Payout failed: {}", msg),
+            StakingError::Overflow => write!(f, "Arithmetic overflow"),
+        }
+    }
+}
+
+impl std::error::Error for StakingError {}
+
+/// Log of a `signature(address,uint256)` event
+fn event_log(contract: Address, signature: &str, account: Address, amount: U256) -> Log {
+    Log::new(
+        contract,
+        vec![event_topic(signature), address_topic(account)],
+        borsh::to_vec(&amount).unwrap_or_default(),
+    )
+}
+
+impl StakingContract {
+    /// Create a new staking contract
+    pub fn new(address: Address, owner: Address, reward_rate: U256, lock_period: u64) -> Self {
+        Self {
+            address,
+            owner,
+            reward_rate,
+            lock_period,
+            total_staked: U256::ZERO,
+            stakes: HashMap::new(),
+            reward_pool: U256::ZERO,
+            logs: Vec::new(),
+            guard: ReentrancyGuard::new(),
+        }
+    }
+
+    /// Stake of `staker`
+    pub fn stake_of(&self, staker: Address) -> U256 {
+        self.stakes.get(&staker).map(|s| s.amount).unwrap_or(U256::ZERO)
+    }
+
+    /// Rewards `staker` could claim at `block`
+    pub fn earned(&self, staker: Address, block: u64) -> Result<U256, StakingError> {
+        match self.stakes.get(&staker) {
+            Some(stake) => stake.unclaimed
+                .checked_add(&self.accrual(stake, block)?)
+                .ok_or(StakingError::Overflow),
+            None => Ok(U256::ZERO),
+        }
+    }
+
+    /// Take the logs emitted so far, as the VM does at the end of a call
+    pub fn take_logs(&mut self) -> Vec<Log> {
+        std::mem::take(&mut self.logs)
+    }
+
+    /// Rewards accrued by `stake` since it was last accrued
+    fn accrual(&self, stake: &Stake, block: u64) -> Result<U256, StakingError> {
+        let blocks = U256::from(block.saturating_sub(stake.accrued_at));
+        stake.amount
+            .checked_mul(&self.reward_rate)
+            .and_then(|r| r.checked_mul(&blocks))
+            .map(|r| r / U256::from(REWARD_PRECISION))
+            .ok_or(StakingError::Overflow)
+    }
+
+    /// Move rewards accrued up to `block` into `unclaimed`
+    fn accrue(&mut self, staker: Address, block: u64) -> Result<(), StakingError> {
+        let earned = self.earned(staker, block)?;
+        if let Some(stake) = self.stakes.get_mut(&staker) {
+            stake.unclaimed = earned;
+            stake.accrued_at = block;
+        }
+        Ok(())
+    }
+
+    /// Add to the rewards paid out to stakers (owner only)
+    pub fn fund_rewards(&mut self, caller: Address, amount: U256) -> Result<(), StakingError> {
+        if caller != self.owner {
+            return Err(StakingError::NotOwner);
+        }
+        self.reward_pool = self.reward_pool.checked_add(&amount).ok_or(StakingError::Overflow)?;
+        Ok(())
+    }
+
+    /// Stake `amount` sent with the call at `block`. Restarts the lock.
+    pub fn stake(&mut self, caller: Address, amount: U256, block: u64) -> Result<(), StakingError> {
+        if amount == U256::ZERO {
+            return Err(StakingError::InvalidAmount);
+        }
+
+        self.accrue(caller, block)?;
+        let total_staked = self.total_staked.checked_add(&amount).ok_or(StakingError::Overflow)?;
+        let stake = self.stakes.entry(caller).or_insert_with(|| Stake {
+            accrued_at: block,
+            ..Stake::default()
+        });
+        stake.amount = stake.amount.checked_add(&amount).ok_or(StakingError::Overflow)?;
+        stake.staked_at = block;
+        self.total_staked = total_staked;
+
+        self.logs.push(event_log(self.address, STAKED_EVENT, caller, amount));
+        Ok(())
+    }
+
+    /// Withdraw `amount` of the caller's stake once its lock has passed
+    pub fn unstake(&mut self, caller: Address, amount: U256, block: u64, pay: Payout) -> Result<(), StakingError> {
+        self.non_reentrant(caller, |contract| {
+            let stake = contract.stakes.get(&caller).ok_or(StakingError::InsufficientStake)?;
+            if amount == U256::ZERO {
+                return Err(StakingError::InvalidAmount);
+            }
+            if stake.amount < amount {
+                return Err(StakingError::InsufficientStake);
+            }
+            let unlocks_at = stake.staked_at.saturating_add(contract.lock_period);
+            if block < unlocks_at {
+                return Err(StakingError::StillLocked { unlocks_at });
+            }
+
+            // Effects before the payout
+            contract.accrue(caller, block)?;
+            if let Some(stake) = contract.stakes.get_mut(&caller) {
+                stake.amount -= amount;
+            }
+            contract.total_staked -= amount;
+
+            pay(contract, caller, amount)?;
+            contract.logs.push(event_log(contract.address, UNSTAKED_EVENT, caller, amount));
+            Ok(())
+        })
+    }
+
+    /// Pay out the caller's rewards accrued up to `block`, returning the amount
+    pub fn claim_rewards(&mut self, caller: Address, block: u64, pay: Payout) -> Result<U256, StakingError> {
+        self.non_reentrant(caller, |contract| {
+            let reward = contract.earned(caller, block)?;
+            if reward == U256::ZERO {
+                return Err(StakingError::InvalidAmount);
+            }
+            if reward > contract.reward_pool {
+                return Err(StakingError::InsufficientRewards);
+            }
+
+            // Effects before the payout
+            contract.accrue(caller, block)?;
+            if let Some(stake) = contract.stakes.get_mut(&caller) {
+                stake.unclaimed = U256::ZERO;
+            }
+            contract.reward_pool -= reward;
+
+            pay(contract, caller, reward)?;
+            contract.logs.push(event_log(contract.address, REWARD_CLAIMED_EVENT, caller, reward));
+            Ok(reward)
+        })
+    }
+
+    /// Run `call` in a call frame of this contract, so calling back into a
+    /// guarded method from a payout fails. A failed call is reverted, as it
+    /// would be on chain.
+    fn non_reentrant<T>(
+        &mut self,
+        caller: Address,
+        call: impl FnOnce(&mut Self) -> Result<T, StakingError>,
+    ) -> Result<T, StakingError> {
+        self.guard
+            .enter(*self.address.as_bytes(), *caller.as_bytes(), 0)
+            .map_err(|_| StakingError::Reentrancy)?;
+        let stakes = self.stakes.clone();
+        let (total_staked, reward_pool, log_count) = (self.total_staked, self.reward_pool, self.logs.len());
+
+        let result = call(self);
+        if result.is_err() {
+            self.stakes = stakes;
+            self.total_staked = total_staked;
+            self.reward_pool = reward_pool;
+            self.logs.truncate(log_count);
+        }
+        let _ = self.guard.exit();
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOCK: u64 = 100;
+
+    fn merk(amount: u64) -> U256 {
+        U256::from(amount as u128 * REWARD_PRECISION)
+    }
+
+    /// One Spark per staked MERK per block, funded with 1 MERK of rewards
+    fn create_test_contract() -> StakingContract {
+        let owner = Address::from_bytes([1u8; 20]);
+        let mut contract = StakingContract::new(Address::from_bytes([9u8; 20]), owner, U256::from(1u64), LOCK);
+        contract.fund_rewards(owner, merk(1)).unwrap();
+        contract
+    }
+
+    fn accept(_: &mut StakingContract, _: Address, _: U256) -> Result<(), StakingError> {
+        Ok(())
+    }
+
+    #[test]
+    fn test_stake() {
+        let staker = Address::from_bytes([2u8; 20]);
+        let mut contract = create_test_contract();
+
+        contract.stake(staker, merk(10), 5).unwrap();
+        assert_eq!(contract.stake_of(staker), merk(10));
+        assert_eq!(contract.total_staked, merk(10));
+        assert_eq!(contract.stake(staker, U256::ZERO, 5), Err(StakingError::InvalidAmount));
+
+        let logs = contract.take_logs();
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0].topics, vec![event_topic(STAKED_EVENT), address_topic(staker)]);
+        assert_eq!(U256::try_from_slice(&logs[0].data).unwrap(), merk(10));
+    }
+
+    #[test]
+    fn test_reward_accrual() {
+        let alice = Address::from_bytes([2u8; 20]);
+        let bob = Address::from_bytes([3u8; 20]);
+        let mut contract = create_test_contract();
+
+        contract.stake(alice, merk(10), 0).unwrap();
+        contract.stake(bob, merk(30), 0).unwrap();
+
+        // Proportional to stake and to blocks staked
+        assert_eq!(contract.earned(alice, 50).unwrap(), U256::from(500u64));
+        assert_eq!(contract.earned(bob, 50).unwrap(), U256::from(1_500u64));
+        assert_eq!(contract.earned(alice, 100).unwrap(), U256::from(1_000u64));
+
+        // Topping up keeps what was already earned
+        contract.stake(alice, merk(10), 100).unwrap();
+        assert_eq!(contract.earned(alice, 110).unwrap(), U256::from(1_200u64));
+
+        let claimed = contract.claim_rewards(alice, 110, &mut accept).unwrap();
+        assert_eq!(claimed, U256::from(1_200u64));
+        assert_eq!(contract.earned(alice, 110).unwrap(), U256::ZERO);
+        assert_eq!(contract.reward_pool, merk(1) - U256::from(1_200u64));
+        let logs = contract.take_logs();
+        assert_eq!(logs.last().unwrap().topics[0], event_topic(REWARD_CLAIMED_EVENT));
+    }
+
+    #[test]
+    fn test_early_unstake_rejected() {
+        let staker = Address::from_bytes([2u8; 20]);
+        let mut contract = create_test_contract();
+        contract.stake(staker, merk(10), 10).unwrap();
+
+        let result = contract.unstake(staker, merk(10), 10 + LOCK - 1, &mut accept);
+        assert_eq!(result, Err(StakingError::StillLocked { unlocks_at: 10 + LOCK }));
+        assert_eq!(contract.stake_of(staker), merk(10));
+
+        let mut paid = U256::ZERO;
+        contract
+            .unstake(staker, merk(4), 10 + LOCK, &mut |_, _, amount| {
+                paid = amount;
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(paid, merk(4));
+        assert_eq!(contract.stake_of(staker), merk(6));
+        assert_eq!(contract.total_staked, merk(6));
+        assert_eq!(contract.take_logs().last().unwrap().topics[0], event_topic(UNSTAKED_EVENT));
+    }
+
+    #[test]
+    fn test_reentrant_payout_rejected() {
+        let staker = Address::from_bytes([2u8; 20]);
+        let mut contract = create_test_contract();
+        contract.stake(staker, merk(10), 0).unwrap();
+
+        // The recipient tries to claim again while being paid
+        let mut reenter = |contract: &mut StakingContract, to: Address, _: U256| {
+            contract.claim_rewards(to, 50, &mut accept).map(|_| ())
+        };
+        let result = contract.claim_rewards(staker, 50, &mut reenter);
+        assert_eq!(result, Err(StakingError::Reentrancy));
+
+        // The failed claim left nothing behind
+        assert_eq!(contract.earned(staker, 50).unwrap(), U256::from(500u64));
+        assert_eq!(contract.reward_pool, merk(1));
+        assert!(contract.claim_rewards(staker, 50, &mut accept).is_ok());
+    }
+}
@@ -12,15 +12,20 @@
 //! - Tamper detection with hashes
 //! - Efficient querying
 //! - Export capabilities
+//! - Retention policy with on-disk rotation
+//! - Tamper alarm with periodic self-verification
 
 use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use parking_lot::Mutex;
+use std::thread::JoinHandle;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use serde::{Serialize, Deserialize};
 use sha3::{Sha3_256, Digest};
 
 /// Audit event types
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum AuditEventType {
     // Transaction events
     TransactionSubmitted,
@@ -63,7 +68,7 @@ pub enum AuditEventType {
 }
 
 /// Audit event severity
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum AuditSeverity {
     Info,
     Warning,
@@ -132,10 +137,10 @@ impl AuditEvent {
         let mut hasher = Sha3_256::new();
         
         let data = format!(
-            "{}:{}:{}:{}:{}:{}",
+            "{}:{}:{:?}:{}:{}:{}",
             self.id,
             self.timestamp,
-            format!("{:?}", self.event_type),
+            self.event_type,
             self.actor,
             self.description,
             self.prev_hash
@@ -186,20 +191,64 @@ impl AuditEvent {
     }
 }
 
+/// Retention policy enforced by [`AuditTrail::record`]
+///
+/// Events beyond `max_events`, or older than `max_age_secs`, are removed from
+/// memory in batches of `rotate_batch`. When `rotate_to_disk` is set each batch
+/// is first written to a numbered JSON file in that directory, so the full hash
+/// chain stays verifiable.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AuditRetentionPolicy {
+    /// Maximum number of events kept in memory
+    pub max_events: Option<usize>,
+    /// Maximum age of in-memory events (seconds)
+    pub max_age_secs: Option<u64>,
+    /// Directory receiving rotated events (discarded when `None`)
+    pub rotate_to_disk: Option<PathBuf>,
+    /// Events removed per rotation (defaults to `DEFAULT_ROTATE_BATCH`, capped at `max_events`)
+    #[serde(default)]
+    pub rotate_batch: Option<usize>,
+}
+
+/// Default number of events removed per rotation
+pub const DEFAULT_ROTATE_BATCH: usize = 1000;
+
+impl AuditRetentionPolicy {
+    /// Effective rotation batch size
+    fn batch_size(&self) -> usize {
+        let batch = self.rotate_batch.unwrap_or(DEFAULT_ROTATE_BATCH).max(1);
+        match self.max_events {
+            Some(max) => batch.min(max.max(1)),
+            None => batch,
+        }
+    }
+}
+
+/// Callback invoked when a verification finds tampering
+pub type TamperCallback = Box<dyn Fn(&AuditIntegrityReport) + Send + Sync>;
+
 /// Audit trail for the blockchain
 pub struct AuditTrail {
     /// All events in chronological order
     events: Arc<Mutex<Vec<AuditEvent>>>,
     /// Events indexed by block number
-    events_by_block: Arc<Mutex<HashMap<u64, Vec<String>>>,
+    events_by_block: Arc<Mutex<HashMap<u64, Vec<String>>>>,
     /// Events indexed by transaction hash
-    events_by_tx: Arc<Mutex<HashMap<String, Vec<String>>>,
+    events_by_tx: Arc<Mutex<HashMap<String, Vec<String>>>>,
     /// Events indexed by actor
-    events_by_actor: Arc<Mutex<HashMap<String, Vec<String>>>,
+    events_by_actor: Arc<Mutex<HashMap<String, Vec<String>>>>,
     /// Last event hash (for chain integrity)
     last_hash: Arc<Mutex<String>>,
     /// Event counters
     counters: Arc<Mutex<HashMap<AuditEventType, u64>>>,
+    /// Hash of the last event removed from memory (start of in-memory chain)
+    chain_start: Arc<Mutex<String>>,
+    /// Retention policy
+    retention: AuditRetentionPolicy,
+    /// Tamper alarm
+    on_tamper: Option<TamperCallback>,
+    /// Result of the most recent verification
+    last_verification: Arc<Mutex<Option<AuditIntegrityReport>>>,
 }
 
 impl AuditTrail {
@@ -211,55 +260,84 @@ impl AuditTrail {
             events_by_actor: Arc::new(Mutex::new(HashMap::new())),
             last_hash: Arc::new(Mutex::new(String::new())),
             counters: Arc::new(Mutex::new(HashMap::new())),
+            chain_start: Arc::new(Mutex::new(String::new())),
+            retention: AuditRetentionPolicy::default(),
+            on_tamper: None,
+            last_verification: Arc::new(Mutex::new(None)),
         }
     }
     
+    /// Set the callback invoked when a verification finds a broken link or invalid hash
+    pub fn with_tamper_callback(
+        mut self,
+        on_tamper: impl Fn(&AuditIntegrityReport) + Send + Sync + 'static,
+    ) -> Self {
+        self.on_tamper = Some(Box::new(on_tamper));
+        self
+    }
+    
+    /// Get the result of the most recent verification
+    pub fn last_verification(&self) -> Option<AuditIntegrityReport> {
+        self.last_verification.lock().clone()
+    }
+    
+    /// Set the retention policy
+    pub fn with_retention(mut self, retention: AuditRetentionPolicy) -> Self {
+        self.retention = retention;
+        self
+    }
+    
+    /// Get the retention policy
+    pub fn retention(&self) -> &AuditRetentionPolicy {
+        &self.retention
+    }
+    
     /// Record an event
     pub fn record(&self, mut event: AuditEvent) -> Result<(), AuditError> {
         // Link to previous event
-        let last = self.last_hash.lock().map_err(|_| AuditError::LockError)?;
+        let last = self.last_hash.lock();
         event.prev_hash = last.clone();
         event.hash = event.calculate_hash();
         drop(last);
         
         // Update last hash
-        let mut last = self.last_hash.lock().map_err(|_| AuditError::LockError)?;
+        let mut last = self.last_hash.lock();
         *last = event.hash.clone();
         drop(last);
         
         // Add to main log
-        let mut events = self.events.lock().map_err(|_| AuditError::LockError)?;
+        let mut events = self.events.lock();
         let event_id = event.id.clone();
         events.push(event.clone());
         drop(events);
         
         // Index by block
         if let Some(block_num) = event.block_number {
-            let mut by_block = self.events_by_block.lock().map_err(|_| AuditError::LockError)?;
+            let mut by_block = self.events_by_block.lock();
             by_block
                 .entry(block_num)
-                .or_insert_with(Vec::new)
+                .or_default()
                 .push(event_id.clone());
         }
         
         // Index by transaction
         if let Some(tx_hash) = &event.tx_hash {
-            let mut by_tx = self.events_by_tx.lock().map_err(|_| AuditError::LockError)?;
+            let mut by_tx = self.events_by_tx.lock();
             by_tx
                 .entry(tx_hash.clone())
-                .or_insert_with(Vec::new)
+                .or_default()
                 .push(event_id.clone());
         }
         
         // Index by actor
-        let mut by_actor = self.events_by_actor.lock().map_err(|_| AuditError::LockError)?;
+        let mut by_actor = self.events_by_actor.lock();
         by_actor
             .entry(event.actor.clone())
-            .or_insert_with(Vec::new)
+            .or_default()
             .push(event_id.clone());
         
         // Update counters
-        let mut counters = self.counters.lock().map_err(|_| AuditError::LockError)?;
+        let mut counters = self.counters.lock();
         *counters.entry(event.event_type).or_insert(0) += 1;
         
         // Log critical events immediately
@@ -271,15 +349,88 @@ impl AuditTrail {
                 event.description
             );
         }
+        drop(counters);
+        drop(by_actor);
+        
+        self.enforce_retention()?;
         
         Ok(())
     }
     
+    /// Remove events exceeding the retention policy, rotating them to disk if configured
+    fn enforce_retention(&self) -> Result<usize, AuditError> {
+        let events = self.events.lock();
+        let batch = self.retention.batch_size();
+        
+        // Once over the limit, remove a whole batch rather than one event per record
+        let mut to_remove = match self.retention.max_events {
+            Some(max) if events.len() > max => (events.len() - max + batch - 1).min(events.len()),
+            _ => 0,
+        };
+        
+        // Expired events wait until a full batch has accumulated
+        if let Some(max_age) = self.retention.max_age_secs {
+            let cutoff = current_timestamp().saturating_sub(max_age);
+            let expired = events.iter().take_while(|e| e.timestamp < cutoff).count();
+            if expired >= batch {
+                to_remove = to_remove.max(expired);
+            }
+        }
+        drop(events);
+        
+        self.remove_oldest(to_remove)
+    }
+    
+    /// Remove the `count` oldest events, rotating them to disk if configured
+    fn remove_oldest(&self, count: usize) -> Result<usize, AuditError> {
+        if count == 0 {
+            return Ok(0);
+        }
+        
+        let mut events = self.events.lock();
+        let count = count.min(events.len());
+        
+        // Write before draining so a failed rotation loses nothing
+        if let Some(dir) = &self.retention.rotate_to_disk {
+            write_rotated(dir, &events[..count])?;
+        }
+        
+        let removed: Vec<AuditEvent> = events.drain(..count).collect();
+        drop(events);
+        
+        if let Some(last) = removed.last() {
+            *self.chain_start.lock() = last.hash.clone();
+        }
+        
+        let removed_ids: Vec<String> = removed.into_iter().map(|e| e.id).collect();
+        
+        // Clean up indexes
+        let mut by_block = self.events_by_block.lock();
+        for ids in by_block.values_mut() {
+            ids.retain(|id| !removed_ids.contains(id));
+        }
+        by_block.retain(|_, ids| !ids.is_empty());
+        
+        let mut by_tx = self.events_by_tx.lock();
+        for ids in by_tx.values_mut() {
+            ids.retain(|id| !removed_ids.contains(id));
+        }
+        by_tx.retain(|_, ids| !ids.is_empty());
+        
+        let mut by_actor = self.events_by_actor.lock();
+        for ids in by_actor.values_mut() {
+            ids.retain(|id| !removed_ids.contains(id));
+        }
+        by_actor.retain(|_, ids| !ids.is_empty());
+        
+        Ok(count)
+    }
+    
     /// Get all events
     pub fn get_all_events(&self,
         limit: Option<usize>,
     ) -> Result<Vec<AuditEvent>, AuditError> {
-        let events = self.events.lock().map_err(|_| AuditError::LockError)?;
+        let events = self.events.lock();
         
         let mut result: Vec<AuditEvent> = events.clone();
         
@@ -295,11 +446,11 @@ impl AuditTrail {
         &self,
         block_number: u64,
     ) -> Result<Vec<AuditEvent>, AuditError> {
-        let by_block = self.events_by_block.lock().map_err(|_| AuditError::LockError)?;
+        let by_block = self.events_by_block.lock();
         let event_ids = by_block.get(&block_number).cloned().unwrap_or_default();
         drop(by_block);
         
-        let events = self.events.lock().map_err(|_| AuditError::LockError)?;
+        let events = self.events.lock();
         let result: Vec<AuditEvent> = events
             .iter()
             .filter(|e| event_ids.contains(&e.id))
@@ -314,11 +465,11 @@ impl AuditTrail {
         &self,
         tx_hash: &str,
     ) -> Result<Vec<AuditEvent>, AuditError> {
-        let by_tx = self.events_by_tx.lock().map_err(|_| AuditError::LockError)?;
+        let by_tx = self.events_by_tx.lock();
         let event_ids = by_tx.get(tx_hash).cloned().unwrap_or_default();
         drop(by_tx);
         
-        let events = self.events.lock().map_err(|_| AuditError::LockError)?;
+        let events = self.events.lock();
         let result: Vec<AuditEvent> = events
             .iter()
             .filter(|e| event_ids.contains(&e.id))
@@ -333,11 +484,11 @@ impl AuditTrail {
         &self,
         actor: &str,
     ) -> Result<Vec<AuditEvent>, AuditError> {
-        let by_actor = self.events_by_actor.lock().map_err(|_| AuditError::LockError)?;
+        let by_actor = self.events_by_actor.lock();
         let event_ids = by_actor.get(actor).cloned().unwrap_or_default();
         drop(by_actor);
         
-        let events = self.events.lock().map_err(|_| AuditError::LockError)?;
+        let events = self.events.lock();
         let result: Vec<AuditEvent> = events
             .iter()
             .filter(|e| event_ids.contains(&e.id))
@@ -352,7 +503,7 @@ impl AuditTrail {
         &self,
         event_type: AuditEventType,
     ) -> Result<Vec<AuditEvent>, AuditError> {
-        let events = self.events.lock().map_err(|_| AuditError::LockError)?;
+        let events = self.events.lock();
         let result: Vec<AuditEvent> = events
             .iter()
             .filter(|e| e.event_type == event_type)
@@ -367,44 +518,69 @@ impl AuditTrail {
         &self,
         id: &str,
     ) -> Result<Option<AuditEvent>, AuditError> {
-        let events = self.events.lock().map_err(|_| AuditError::LockError)?;
+        let events = self.events.lock();
         Ok(events.iter().find(|e| e.id == id).cloned())
     }
     
     /// Verify entire audit chain integrity
+    ///
+    /// The report is kept as the last verification result, and the tamper
+    /// callback fires if the chain is invalid.
     pub fn verify_integrity(&self) -> Result<AuditIntegrityReport, AuditError> {
-        let events = self.events.lock().map_err(|_| AuditError::LockError)?;
+        let events = self.events.lock();
+        let chain_start = self.chain_start.lock().clone();
+        let report = verify_chain(&events, &chain_start);
+        drop(events);
         
-        let mut broken_links = Vec::new();
-        let mut invalid_hashes = Vec::new();
-        let mut prev_hash = String::new();
+        *self.last_verification.lock() = Some(report.clone());
         
-        for event in events.iter() {
-            // Check hash integrity
-            if !event.verify() {
-                invalid_hashes.push(event.id.clone());
-            }
-            
-            // Check chain link
-            if event.prev_hash != prev_hash {
-                broken_links.push(event.id.clone());
+        if !report.valid {
+            tracing::error!(
+                "AUDIT TAMPERING DETECTED: {} broken links, {} invalid hashes",
+                report.broken_links.len(),
+                report.invalid_hashes.len()
+            );
+            if let Some(on_tamper) = &self.on_tamper {
+                on_tamper(&report);
             }
-            
-            prev_hash = event.hash.clone();
         }
         
-        Ok(AuditIntegrityReport {
-            total_events: events.len(),
-            valid: invalid_hashes.is_empty() && broken_links.is_empty(),
-            broken_links,
-            invalid_hashes,
+        Ok(report)
+    }
+    
+    /// Verify the trail every `interval` on a background thread
+    ///
+    /// The thread holds only a weak reference and exits once the trail is dropped.
+    pub fn spawn_periodic_verification(
+        trail: &Arc<AuditTrail>,
+        interval: Duration,
+    ) -> JoinHandle<()> {
+        let trail = Arc::downgrade(trail);
+        std::thread::spawn(move || loop {
+            std::thread::sleep(interval);
+            match trail.upgrade() {
+                Some(trail) => {
+                    let _ = trail.verify_integrity();
+                }
+                None => break,
+            }
         })
     }
     
+    /// Verify the chain across rotated files and the in-memory events
+    pub fn verify_full_history(&self) -> Result<AuditIntegrityReport, AuditError> {
+        let mut history = match &self.retention.rotate_to_disk {
+            Some(dir) => load_rotated(dir)?,
+            None => Vec::new(),
+        };
+        history.extend(self.events.lock().iter().cloned());
+        Ok(verify_chain(&history, ""))
+    }
+    
     /// Get statistics
     pub fn get_stats(&self) -> Result<AuditStats, AuditError> {
-        let events = self.events.lock().map_err(|_| AuditError::LockError)?;
-        let counters = self.counters.lock().map_err(|_| AuditError::LockError)?;
+        let events = self.events.lock();
+        let counters = self.counters.lock();
         
         let total_events = events.len();
         
@@ -419,7 +595,7 @@ impl AuditTrail {
             total_events,
             events_by_type: counters.clone(),
             events_by_severity,
-            unique_actors: self.events_by_actor.lock().map_err(|_| AuditError::LockError)?.len(),
+            unique_actors: self.events_by_actor.lock().len(),
             latest_event_timestamp: events.last().map(|e| e.timestamp),
         })
     }
@@ -430,7 +606,7 @@ impl AuditTrail {
         start_time: Option<u64>,
         end_time: Option<u64>,
     ) -> Result<String, AuditError> {
-        let events = self.events.lock().map_err(|_| AuditError::LockError)?;
+        let events = self.events.lock();
         
         let filtered: Vec<&AuditEvent> = events
             .iter()
@@ -448,39 +624,10 @@ impl AuditTrail {
         serde_json::to_string_pretty(&filtered).map_err(|e| AuditError::SerializationError(e.to_string()))
     }
     
-    /// Trim old events (keep last N)
+    /// Trim old events (keep last N), rotating them to disk if configured
     pub fn trim(&self, keep_last: usize) -> Result<usize, AuditError> {
-        let mut events = self.events.lock().map_err(|_| AuditError::LockError)?;
-        
-        if events.len() <= keep_last {
-            return Ok(0);
-        }
-        
-        let to_remove = events.len() - keep_last;
-        let removed_ids: Vec<String> = events[..to_remove].iter().map(|e| e.id.clone()).collect();
-        events.drain(..to_remove);
-        drop(events);
-        
-        // Clean up indexes
-        let mut by_block = self.events_by_block.lock().map_err(|_| AuditError::LockError)?;
-        for ids in by_block.values_mut() {
-            ids.retain(|id| !removed_ids.contains(id));
-        }
-        by_block.retain(|_, ids| !ids.is_empty());
-        
-        let mut by_tx = self.events_by_tx.lock().map_err(|_| AuditError::LockError)?;
-        for ids in by_tx.values_mut() {
-            ids.retain(|id| !removed_ids.contains(id));
-        }
-        by_tx.retain(|_, ids| !ids.is_empty());
-        
-        let mut by_actor = self.events_by_actor.lock().map_err(|_| AuditError::LockError)?;
-        for ids in by_actor.values_mut() {
-            ids.retain(|id| !removed_ids.contains(id));
-        }
-        by_actor.retain(|_, ids| !ids.is_empty());
-        
-        Ok(to_remove)
+        let len = self.events.lock().len();
+        self.remove_oldest(len.saturating_sub(keep_last))
     }
 }
 
@@ -499,6 +646,95 @@ pub struct AuditIntegrityReport {
     pub invalid_hashes: Vec<String>,
 }
 
+/// Check hashes and chain links of `events`, starting from `prev_hash`
+fn verify_chain(events: &[AuditEvent], prev_hash: &str) -> AuditIntegrityReport {
+    let mut broken_links = Vec::new();
+    let mut invalid_hashes = Vec::new();
+    let mut prev_hash = prev_hash.to_string();
+    
+    for event in events {
+        // Check hash integrity
+        if !event.verify() {
+            invalid_hashes.push(event.id.clone());
+        }
+        
+        // Check chain link
+        if event.prev_hash != prev_hash {
+            broken_links.push(event.id.clone());
+        }
+        
+        prev_hash = event.hash.clone();
+    }
+    
+    AuditIntegrityReport {
+        total_events: events.len(),
+        valid: invalid_hashes.is_empty() && broken_links.is_empty(),
+        broken_links,
+        invalid_hashes,
+    }
+}
+
+/// Prefix and extension of rotated audit files
+const ROTATED_PREFIX: &str = "audit-";
+const ROTATED_EXTENSION: &str = ".json";
+
+/// Index of a rotated audit file, parsed from its name
+fn rotated_index(path: &Path) -> Option<u64> {
+    path.file_name()?
+        .to_str()?
+        .strip_prefix(ROTATED_PREFIX)?
+        .strip_suffix(ROTATED_EXTENSION)?
+        .parse()
+        .ok()
+}
+
+/// Rotated audit files in `dir`, oldest first
+fn rotated_files(dir: &Path) -> Result<Vec<(u64, PathBuf)>, AuditError> {
+    let mut files = Vec::new();
+    if !dir.exists() {
+        return Ok(files);
+    }
+    
+    let entries = std::fs::read_dir(dir).map_err(|e| AuditError::IoError(e.to_string()))?;
+    for entry in entries {
+        let path = entry.map_err(|e| AuditError::IoError(e.to_string()))?.path();
+        if let Some(index) = rotated_index(&path) {
+            files.push((index, path));
+        }
+    }
+    files.sort_by_key(|(index, _)| *index);
+    Ok(files)
+}
+
+/// Write `events` to the next rotated file in `dir`
+fn write_rotated(dir: &Path, events: &[AuditEvent]) -> Result<PathBuf, AuditError> {
+    std::fs::create_dir_all(dir).map_err(|e| AuditError::IoError(e.to_string()))?;
+    
+    let next = rotated_files(dir)?
+        .last()
+        .map(|(index, _)| index + 1)
+        .unwrap_or(0);
+    let path = dir.join(format!("{}{:08}{}", ROTATED_PREFIX, next, ROTATED_EXTENSION));
+    
+    let json = serde_json::to_string_pretty(events)
+        .map_err(|e| AuditError::SerializationError(e.to_string()))?;
+    std::fs::write(&path, json).map_err(|e| AuditError::IoError(e.to_string()))?;
+    
+    Ok(path)
+}
+
+/// Load all rotated events from `dir` in chronological order
+pub fn load_rotated(dir: &Path) -> Result<Vec<AuditEvent>, AuditError> {
+    let mut events = Vec::new();
+    for (_, path) in rotated_files(dir)? {
+        let json = std::fs::read_to_string(&path).map_err(|e| AuditError::IoError(e.to_string()))?;
+        let batch: Vec<AuditEvent> = serde_json::from_str(&json)
+            .map_err(|e| AuditError::SerializationError(e.to_string()))?;
+        events.extend(batch);
+    }
+    Ok(events)
+}
+
 /// Audit statistics
 #[derive(Debug, Clone)]
 pub struct AuditStats {
@@ -512,16 +748,16 @@ pub struct AuditStats {
 /// Audit errors
 #[derive(Debug, Clone)]
 pub enum AuditError {
-    LockError,
     SerializationError(String),
+    IoError(String),
     InvalidEvent,
 }
 
 impl std::fmt::Display for AuditError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            AuditError::LockError => write!(f, "Lock poisoned"),
             AuditError::SerializationError(e) => write!(f, "Serialization error: {}", e),
+            AuditError::IoError(e) => write!(f, "I/O error: {}", e),
             AuditError::InvalidEvent => write!(f, "Invalid audit event"),
         }
     }
@@ -651,4 +887,126 @@ mod tests {
         let events = audit.get_events_by_actor("0xuser1").unwrap();
         assert_eq!(events.len(), 1);
     }
+    
+    #[test]
+    fn test_record_after_panic_holding_lock() {
+        let audit = Arc::new(AuditTrail::new());
+        
+        // parking_lot releases the lock on unwind without poisoning it
+        let events = audit.events.clone();
+        let result = std::thread::spawn(move || {
+            let _guard = events.lock();
+            panic!("panic while holding audit lock");
+        })
+        .join();
+        assert!(result.is_err());
+        assert!(!audit.events.is_locked());
+        
+        let recorder = audit.clone();
+        std::thread::spawn(move || {
+            recorder.record(AuditEvent::new(
+                AuditEventType::SuspiciousActivity,
+                "0xuser1".to_string(),
+                "After panic".to_string(),
+                AuditSeverity::Warning,
+            ))
+        })
+        .join()
+        .unwrap()
+        .unwrap();
+        
+        assert_eq!(audit.get_all_events(None).unwrap().len(), 1);
+        assert!(audit.verify_integrity().unwrap().valid);
+    }
+    
+    #[test]
+    fn test_retention_rotates_to_disk() {
+        let dir = std::env::temp_dir().join(format!("merklith-audit-{}", generate_nonce()));
+        let audit = AuditTrail::new().with_retention(AuditRetentionPolicy {
+            max_events: Some(3),
+            max_age_secs: None,
+            rotate_to_disk: Some(dir.clone()),
+            rotate_batch: Some(2),
+        });
+        
+        for i in 0..5 {
+            audit.record(AuditEvent::new(
+                AuditEventType::TransactionSubmitted,
+                "0xuser1".to_string(),
+                format!("Tx {}", i),
+                AuditSeverity::Info,
+            )).unwrap();
+        }
+        
+        // Only the newest events stay in memory
+        let in_memory = audit.get_all_events(None).unwrap();
+        assert_eq!(in_memory.len(), 3);
+        assert_eq!(in_memory[0].description, "Tx 2");
+        assert_eq!(audit.get_events_by_actor("0xuser1").unwrap().len(), 3);
+        assert!(audit.verify_integrity().unwrap().valid);
+        
+        // Older events were rotated, and the chain links across files
+        let rotated = load_rotated(&dir).unwrap();
+        assert_eq!(rotated.len(), 2);
+        assert_eq!(rotated[0].description, "Tx 0");
+        assert_eq!(in_memory[0].prev_hash, rotated[1].hash);
+        
+        let report = audit.verify_full_history().unwrap();
+        assert!(report.valid);
+        assert_eq!(report.total_events, 5);
+        
+        // Rotation writes one file per batch, not one per event
+        assert_eq!(rotated_files(&dir).unwrap().len(), 1);
+        for i in 5..7 {
+            audit.record(AuditEvent::new(
+                AuditEventType::TransactionSubmitted,
+                "0xuser1".to_string(),
+                format!("Tx {}", i),
+                AuditSeverity::Info,
+            )).unwrap();
+        }
+        assert_eq!(rotated_files(&dir).unwrap().len(), 2);
+        assert_eq!(load_rotated(&dir).unwrap().len(), 4);
+        assert!(audit.verify_full_history().unwrap().valid);
+        
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+    
+    #[test]
+    fn test_tamper_callback() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        
+        let alarms = Arc::new(AtomicUsize::new(0));
+        let alarms_clone = alarms.clone();
+        let audit = AuditTrail::new().with_tamper_callback(move |report| {
+            assert!(!report.invalid_hashes.is_empty());
+            alarms_clone.fetch_add(1, Ordering::SeqCst);
+        });
+        
+        for i in 0..3 {
+            audit.record(AuditEvent::new(
+                AuditEventType::TransactionSubmitted,
+                "0xuser1".to_string(),
+                format!("Tx {}", i),
+                AuditSeverity::Info,
+            )).unwrap();
+        }
+        
+        assert!(audit.verify_integrity().unwrap().valid);
+        assert_eq!(alarms.load(Ordering::SeqCst), 0);
+        
+        // Corrupt an event in place
+        audit.events.lock()[1].hash = "0xdeadbeef".to_string();
+        
+        let audit = Arc::new(audit);
+        AuditTrail::spawn_periodic_verification(&audit, Duration::from_millis(10));
+        
+        let deadline = std::time::Instant::now() + Duration::from_secs(5);
+        while alarms.load(Ordering::SeqCst) == 0 && std::time::Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        
+        assert!(alarms.load(Ordering::SeqCst) >= 1);
+        assert!(!audit.last_verification().unwrap().valid);
+    }
 }
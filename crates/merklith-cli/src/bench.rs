@@ -0,0 +1,231 @@
+//! RPC load testing.
+//!
+//! `merklith bench rpc` keeps a number of concurrent workers sending requests
+//! to a node for a fixed time, cycling through the requested methods, and
+//! reports throughput, latency percentiles and error rate per method.
+
+use merklith_crypto::ed25519::Keypair as Ed25519Keypair;
+use merklith_types::{Address, Transaction, U256};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::commands::{encode_signed_transaction, sign_with_key};
+use crate::rpc_client::RpcClient;
+
+/// The write method; every other method is sent as a read.
+pub const WRITE_METHOD: &str = "eth_sendRawTransaction";
+
+/// Settings for one bench run.
+pub struct BenchConfig {
+    /// Node RPC endpoint
+    pub url: String,
+    /// Requests in flight at once
+    pub concurrency: usize,
+    /// How long to keep sending
+    pub duration: Duration,
+    /// Methods to mix, taken in turn by each worker
+    pub methods: Vec<String>,
+    /// Key signing write requests
+    pub private_key: [u8; 32],
+}
+
+/// Results for one method.
+#[derive(Debug, Clone, Default)]
+pub struct MethodStats {
+    /// Requests sent
+    pub requests: usize,
+    /// Requests that failed: transport errors, HTTP errors and JSON-RPC errors
+    pub errors: usize,
+    /// Latency of every request, failed or not
+    latencies: Vec<Duration>,
+}
+
+impl MethodStats {
+    fn record(&mut self, latency: Duration, ok: bool) {
+        self.requests += 1;
+        if !ok {
+            self.errors += 1;
+        }
+        self.latencies.push(latency);
+    }
+
+    fn merge(&mut self, other: MethodStats) {
+        self.requests += other.requests;
+        self.errors += other.errors;
+        self.latencies.extend(other.latencies);
+    }
+
+    /// Fraction of requests that failed.
+    pub fn error_rate(&self) -> f64 {
+        if self.requests == 0 {
+            return 0.0;
+        }
+        self.errors as f64 / self.requests as f64
+    }
+
+    /// Latency below which `percent` of requests completed.
+    pub fn percentile(&self, percent: f64) -> Duration {
+        let mut sorted = self.latencies.clone();
+        sorted.sort_unstable();
+        let index = ((sorted.len() as f64 * percent / 100.0).ceil() as usize).saturating_sub(1);
+        sorted.get(index).copied().unwrap_or_default()
+    }
+}
+
+/// Results of a bench run.
+#[derive(Debug, Clone)]
+pub struct BenchReport {
+    /// Time spent sending
+    pub elapsed: Duration,
+    /// Results per method
+    pub methods: BTreeMap<String, MethodStats>,
+}
+
+impl BenchReport {
+    /// Requests sent across all methods.
+    pub fn total_requests(&self) -> usize {
+        self.methods.values().map(|s| s.requests).sum()
+    }
+
+    /// Requests per second for `stats`.
+    pub fn throughput(&self, stats: &MethodStats) -> f64 {
+        stats.requests as f64 / self.elapsed.as_secs_f64().max(f64::EPSILON)
+    }
+
+    /// Print a table of the results.
+    pub fn print(&self) {
+        println!(
+            "{:<28} {:>9} {:>10} {:>9} {:>9} {:>9} {:>9} {:>8}",
+            "Method", "Requests", "Req/s", "p50", "p90", "p99", "Max", "Errors"
+        );
+        println!("{}", "=".repeat(98));
+        let ms = |d: Duration| format!("{:.1}ms", d.as_secs_f64() * 1000.0);
+        for (method, stats) in &self.methods {
+            println!(
+                "{:<28} {:>9} {:>10.1} {:>9} {:>9} {:>9} {:>9} {:>7.1}%",
+                method,
+                stats.requests,
+                self.throughput(stats),
+                ms(stats.percentile(50.0)),
+                ms(stats.percentile(90.0)),
+                ms(stats.percentile(99.0)),
+                ms(stats.percentile(100.0)),
+                stats.error_rate() * 100.0,
+            );
+        }
+        println!(
+            "\n{} requests in {:.1}s ({:.1} req/s)",
+            self.total_requests(),
+            self.elapsed.as_secs_f64(),
+            self.total_requests() as f64 / self.elapsed.as_secs_f64().max(f64::EPSILON)
+        );
+    }
+}
+
+/// Signs transfers for write requests. Nonces are handed out one send at a
+/// time so concurrent writes never leave a gap.
+struct Writer {
+    private_key: [u8; 32],
+    chain_id: u64,
+    gas_price: U256,
+    to: Address,
+    nonce: tokio::sync::Mutex<u64>,
+}
+
+/// Run the bench described by `config`.
+pub async fn run(config: BenchConfig) -> anyhow::Result<BenchReport> {
+    if config.methods.is_empty() {
+        anyhow::bail!("No methods to bench");
+    }
+    let client = RpcClient::new(config.url.clone());
+    let sender = Ed25519Keypair::from_seed(&config.private_key).address();
+
+    let writer = if config.methods.iter().any(|m| m == WRITE_METHOD) {
+        Some(Arc::new(Writer {
+            private_key: config.private_key,
+            chain_id: client.chain_id().await?,
+            gas_price: client.gas_price().await?,
+            to: Address::from_bytes([0xbe; 20]),
+            nonce: tokio::sync::Mutex::new(client.get_transaction_count(&sender).await?),
+        }))
+    } else {
+        None
+    };
+
+    let http = reqwest::Client::new();
+    let methods = Arc::new(config.methods);
+    let started = Instant::now();
+    let deadline = started + config.duration;
+    let workers: Vec<_> = (0..config.concurrency.max(1))
+        .map(|worker| {
+            let http = http.clone();
+            let url = config.url.clone();
+            let methods = methods.clone();
+            let writer = writer.clone();
+            tokio::spawn(async move {
+                let mut stats: BTreeMap<String, MethodStats> = BTreeMap::new();
+                // Workers start at different methods so the mix is even from the start
+                for method in methods.iter().cycle().skip(worker % methods.len()) {
+                    if Instant::now() >= deadline {
+                        break;
+                    }
+                    let sent = Instant::now();
+                    let ok = match (method.as_str(), &writer) {
+                        (WRITE_METHOD, Some(writer)) => send_write(&http, &url, writer).await,
+                        _ => send(&http, &url, method, read_params(method, &sender)).await,
+                    };
+                    stats.entry(method.clone()).or_default().record(sent.elapsed(), ok);
+                }
+                stats
+            })
+        })
+        .collect();
+
+    let mut methods: BTreeMap<String, MethodStats> = BTreeMap::new();
+    for worker in workers {
+        for (method, stats) in worker.await? {
+            methods.entry(method).or_default().merge(stats);
+        }
+    }
+    Ok(BenchReport { elapsed: started.elapsed(), methods })
+}
+
+/// Parameters for a read of `method`, querying `address` where one is needed.
+fn read_params(method: &str, address: &Address) -> Value {
+    let address = format!("0x{}", hex::encode(address.as_bytes()));
+    match method {
+        "eth_getBalance" | "eth_getTransactionCount" | "eth_getCode" => json!([address, "latest"]),
+        "eth_getBlockByNumber" => json!(["latest", false]),
+        "eth_call" | "eth_estimateGas" => json!([{ "to": address, "data": "0x" }]),
+        _ => json!([]),
+    }
+}
+
+/// Send one request, returning whether it succeeded.
+async fn send(http: &reqwest::Client, url: &str, method: &str, params: Value) -> bool {
+    let request = json!({ "jsonrpc": "2.0", "method": method, "params": params, "id": 1 });
+    let response = match http.post(url).json(&request).send().await {
+        Ok(response) if response.status().is_success() => response,
+        _ => return false,
+    };
+    match response.json::<Value>().await {
+        Ok(body) => body.get("error").is_none() && body.get("result").is_some(),
+        Err(_) => false,
+    }
+}
+
+/// Sign and send a transfer with the writer's next nonce.
+async fn send_write(http: &reqwest::Client, url: &str, writer: &Writer) -> bool {
+    let mut nonce = writer.nonce.lock().await;
+    let tx = Transaction::new(writer.chain_id, *nonce, Some(writer.to), U256::ONE, 21000, writer.gas_price, U256::ZERO);
+    let Ok(raw) = encode_signed_transaction(&sign_with_key(&writer.private_key, tx)) else {
+        return false;
+    };
+    let ok = send(http, url, WRITE_METHOD, json!([raw])).await;
+    if ok {
+        *nonce += 1;
+    }
+    ok
+}
@@ -13,7 +13,7 @@ use std::path::PathBuf;
 use std::str::FromStr;
 
 use crate::config::CliConfig;
-use crate::keystore::Keystore;
+use crate::keystore::{Keystore, UnlockedSession};
 use crate::output::*;
 use crate::rpc_client::RpcClient;
 
@@ -76,6 +76,10 @@ pub enum Commands {
     /// Configuration
     #[command(subcommand)]
     Config(ConfigCommands),
+
+    /// Load testing
+    #[command(subcommand)]
+    Bench(BenchCommands),
 }
 
 /// Wallet commands.
@@ -96,6 +100,9 @@ pub enum WalletCommands {
         /// Wallet name
         #[arg(short, long)]
         name: Option<String>,
+        /// Refuse the import unless the key derives this address
+        #[arg(long)]
+        expect_address: Option<String>,
     },
     /// Export wallet (WARNING: exposes private key)
     Export {
@@ -149,6 +156,33 @@ pub enum TxCommands {
         /// From address (uses default if not specified)
         #[arg(short, long)]
         from: Option<String>,
+        /// Sign and print the transaction without broadcasting it
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Build and sign a transaction offline, without contacting a node
+    Build {
+        /// To address
+        to: String,
+        /// Amount in MERK
+        amount: String,
+        /// Sender nonce
+        #[arg(long)]
+        nonce: u64,
+        /// Gas price (optional)
+        #[arg(long)]
+        gas_price: Option<u64>,
+        /// Gas limit
+        #[arg(short, long, default_value = "21000")]
+        gas_limit: u64,
+        /// From address (uses default if not specified)
+        #[arg(short, long)]
+        from: Option<String>,
+    },
+    /// Broadcast a signed transaction built with `tx build`
+    Broadcast {
+        /// Signed transaction (hex)
+        raw: String,
     },
     /// Get transaction details
     Get {
@@ -221,6 +255,12 @@ pub enum ContractCommands {
         /// Gas limit
         #[arg(short, long)]
         gas_limit: Option<u64>,
+        /// From address (uses default if not specified)
+        #[arg(short, long)]
+        from: Option<String>,
+        /// Sign and print the transaction without broadcasting it
+        #[arg(long)]
+        dry_run: bool,
     },
     /// Get contract bytecode
     Code {
@@ -243,8 +283,12 @@ pub enum NodeCommands {
     },
     /// Stop local node
     Stop,
-    /// Get node status
-    Status,
+    /// Get node status from the status file in the data directory
+    Status {
+        /// Data directory of the node
+        #[arg(short, long, default_value = "./data")]
+        data_dir: PathBuf,
+    },
     /// Stream logs
     Logs {
         /// Number of lines to show
@@ -283,6 +327,26 @@ pub enum ConfigCommands {
     },
 }
 
+/// Bench commands.
+#[derive(Subcommand)]
+pub enum BenchCommands {
+    /// Send concurrent RPC requests and report throughput and latency
+    Rpc {
+        /// Requests in flight at once
+        #[arg(short, long, default_value = "10")]
+        concurrency: usize,
+        /// Seconds to keep sending
+        #[arg(short, long, default_value = "10")]
+        duration: u64,
+        /// Method to send; repeat to mix several. eth_sendRawTransaction sends signed transfers
+        #[arg(short, long = "method", default_value = "eth_blockNumber")]
+        methods: Vec<String>,
+        /// Private key (hex) signing transfers; defaults to the first devnet account
+        #[arg(long)]
+        private_key: Option<String>,
+    },
+}
+
 #[derive(clap::ValueEnum, Clone)]
 pub enum Shell {
     Bash,
@@ -293,20 +357,21 @@ pub enum Shell {
 }
 
 /// Execute a CLI command.
-pub async fn execute(cmd: Commands, rpc: Option<String>) -> anyhow::Result<()> {
+pub async fn execute(cmd: Commands, rpc: Option<String>, chain_id: Option<u64>) -> anyhow::Result<()> {
     let config = CliConfig::load()?;
     let rpc_url = rpc.unwrap_or(config.rpc_url.clone());
-    let client = RpcClient::new(rpc_url);
+    let client = RpcClient::new(rpc_url.clone());
 
     match cmd {
         Commands::Wallet(cmd) => execute_wallet(cmd, &config).await,
         Commands::Account(cmd) => execute_account(cmd, &client, &config).await,
-        Commands::Tx(cmd) => execute_tx(cmd, &client, &config).await,
+        Commands::Tx(cmd) => execute_tx(cmd, &client, &config, chain_id).await,
         Commands::Query(cmd) => execute_query(cmd, &client).await,
-        Commands::Contract(cmd) => execute_contract(cmd, &client).await,
+        Commands::Contract(cmd) => execute_contract(cmd, &client, &config).await,
         Commands::Node(cmd) => execute_node(cmd).await,
         Commands::Config(cmd) => execute_config(cmd).await,
         Commands::Explorer { rpc } => execute_explorer(rpc, &config).await,
+        Commands::Bench(cmd) => execute_bench(cmd, rpc_url).await,
     }
 }
 
@@ -368,7 +433,7 @@ async fn execute_wallet(cmd: WalletCommands, config: &CliConfig) -> anyhow::Resu
             }
         }
 
-        WalletCommands::Import { private_key, name } => {
+        WalletCommands::Import { private_key, name, expect_address } => {
             let name = name.unwrap_or_else(|| "imported".to_string());
             
             // Parse private key
@@ -384,6 +449,10 @@ async fn execute_wallet(cmd: WalletCommands, config: &CliConfig) -> anyhow::Resu
             let keypair = Ed25519Keypair::from_seed(&private_key_array);
             let address = keypair.address();
             
+            if let Some(expected) = expect_address {
+                verify_derived_address(&address, &expected)?;
+            }
+            
             let password = Password::new()
                 .with_prompt("Set password")
                 .with_confirmation("Confirm password", "Passwords don't match")
@@ -540,7 +609,8 @@ async fn execute_account(cmd: AccountCommands, client: &RpcClient, config: &CliC
             println!("{}", "Account Balances:".bold());
             println!("{}", "=".repeat(60));
             
-            let mut total_balance = U256::ZERO;
+            // None once the sum no longer fits in a U256
+            let mut total_balance = Some(U256::ZERO);
             
             for wallet in wallets {
                 match client.get_balance(&wallet.address).await {
@@ -552,7 +622,7 @@ async fn execute_account(cmd: AccountCommands, client: &RpcClient, config: &CliC
                         );
                         println!("    Address: {}", wallet.address.to_string().bright_cyan());
                         println!("    Balance: {}", format_merk(&balance).bright_green());
-                        total_balance = total_balance + balance;
+                        total_balance = total_balance.and_then(|total| total.checked_add(&balance));
                     }
                     Err(e) => {
                         println!("  {} - Error: {}", wallet.name.red(), e);
@@ -562,7 +632,10 @@ async fn execute_account(cmd: AccountCommands, client: &RpcClient, config: &CliC
             }
             
             println!("{}", "=".repeat(60));
-            println!("Total Balance: {}", format_merk(&total_balance).bright_yellow().bold());
+            match total_balance {
+                Some(total) => println!("Total Balance: {}", format_merk(&total).bright_yellow().bold()),
+                None => println!("Total Balance: {}", "overflows U256".red().bold()),
+            }
         }
 
         AccountCommands::Nonce { address } => {
@@ -577,30 +650,16 @@ async fn execute_account(cmd: AccountCommands, client: &RpcClient, config: &CliC
 }
 
 /// Execute transaction commands.
-async fn execute_tx(cmd: TxCommands, client: &RpcClient, config: &CliConfig) -> anyhow::Result<()> {
+async fn execute_tx(
+    cmd: TxCommands,
+    client: &RpcClient,
+    config: &CliConfig,
+    chain_id: Option<u64>,
+) -> anyhow::Result<()> {
     match cmd {
-        TxCommands::Send { to, amount, gas_price, gas_limit, from } => {
+        TxCommands::Send { to, amount, gas_price, gas_limit, from, dry_run } => {
             let to_addr = parse_address(&to)?;
-            
-            // Get sender address
-            let sender_addr = match from {
-                Some(addr_str) => parse_address(&addr_str)?,
-                None => {
-                    // Try to use default account
-                    let keystore_dir = config.keystore_path();
-                    let keystore = Keystore::new(keystore_dir)?;
-                    
-                    match keystore.get_default() {
-                        Some(entry) => entry.address,
-                        None => {
-                            print_error("No sender specified and no default account set");
-                            print_info("Set a default account with: merklith wallet create");
-                            print_info("Or use --from flag: merklith tx send 0x... 1.0 --from 0x...");
-                            std::process::exit(1);
-                        }
-                    }
-                }
-            };
+            let sender_addr = resolve_sender(from, config)?;
 
             // Convert amount to wei using string parsing for precision
             let value = parse_amount_to_wei(&amount)?;
@@ -611,27 +670,34 @@ async fn execute_tx(cmd: TxCommands, client: &RpcClient, config: &CliConfig) ->
             println!("Gas Price: {} Gwei", gas_price / 1_000_000_000);
             println!("Gas Limit: {}", gas_limit);
             
-            // Get password to sign
-            let keystore_dir = config.keystore_path();
-            let keystore = Keystore::new(keystore_dir)?;
-            
-            let password = Password::new()
-                .with_prompt("Enter wallet password to sign transaction")
-                .interact()?;
-            
-            // Load private key
-            let private_key = match keystore.load_wallet(&sender_addr, &password) {
-                Ok(key) => key,
-                Err(_) => {
-                    anyhow::bail!("Failed to decrypt wallet. Wrong password?");
-                }
-            };
-            
-            // Create and sign transaction
-            let keypair = Ed25519Keypair::from_seed(&private_key);
-            let nonce = client.get_transaction_count(&sender_addr).await?;
-            let chain_id = client.chain_id().await?;
-            
+            let signed_tx = sign_transaction(
+                client,
+                config,
+                sender_addr,
+                to_addr,
+                value,
+                gas_limit,
+                gas_price,
+                Vec::new(),
+            ).await?;
+            let tx_hex = encode_signed_transaction(&signed_tx)?;
+
+            if dry_run {
+                print_dry_run(&signed_tx, &tx_hex);
+            } else {
+                broadcast_transaction(client, &tx_hex).await;
+            }
+        }
+
+        TxCommands::Build { to, amount, nonce, gas_price, gas_limit, from } => {
+            let chain_id = chain_id.ok_or_else(|| {
+                anyhow::anyhow!("--chain-id is required to build a transaction offline")
+            })?;
+            let to_addr = parse_address(&to)?;
+            let sender_addr = resolve_sender(from, config)?;
+            let value = parse_amount_to_wei(&amount)?;
+            let gas_price = gas_price.unwrap_or(1_000_000_000);
+
             let tx = Transaction::new(
                 chain_id,
                 nonce,
@@ -641,26 +707,20 @@ async fn execute_tx(cmd: TxCommands, client: &RpcClient, config: &CliConfig) ->
                 U256::from(gas_price),
                 U256::ZERO,
             );
-            
-            let (signature, public_key) = keypair.sign_transaction(&tx);
-            let signed_tx = SignedTransaction::new(tx, signature, public_key);
-            
-            // Serialize signed transaction to hex
-            let tx_bytes = borsh::to_vec(&signed_tx)?;
-            let tx_hex = format!("0x{}", hex::encode(&tx_bytes));
-            
-            // Send transaction
-            match client.send_raw_transaction(&tx_hex).await {
-                Ok(tx_hash) => {
-                    print_success("Transaction sent successfully!");
-                    println!("Transaction Hash: {}", tx_hash.to_string().bright_green());
-                    println!("\nView transaction:");
-                    println!("  merklith tx get {}", tx_hash.to_string().bright_cyan());
-                }
-                Err(e) => {
-                    print_error(&format!("Failed to send transaction: {}", e));
-                }
-            }
+            let private_key = unlock_wallet(config, &sender_addr)?;
+            let signed_tx = sign_with_key(&private_key, tx);
+            let tx_hex = encode_signed_transaction(&signed_tx)?;
+
+            print_signed_transaction(&signed_tx);
+            println!("\nRaw transaction:");
+            println!("{}", tx_hex);
+            print_info("Submit it from an online machine with: merklith tx broadcast <hex>");
+        }
+
+        TxCommands::Broadcast { raw } => {
+            let signed_tx = decode_signed_transaction(&raw)?;
+            print_signed_transaction(&signed_tx);
+            broadcast_transaction(client, &encode_signed_transaction(&signed_tx)?).await;
         }
 
         TxCommands::Get { hash } => {
@@ -767,7 +827,7 @@ async fn execute_query(cmd: QueryCommands, client: &RpcClient) -> anyhow::Result
 }
 
 /// Execute contract commands.
-async fn execute_contract(cmd: ContractCommands, client: &RpcClient) -> anyhow::Result<()> {
+async fn execute_contract(cmd: ContractCommands, client: &RpcClient, config: &CliConfig) -> anyhow::Result<()> {
     match cmd {
         ContractCommands::Deploy { bytecode, args, gas_limit } => {
             let code = std::fs::read(bytecode)?;
@@ -803,22 +863,38 @@ async fn execute_contract(cmd: ContractCommands, client: &RpcClient) -> anyhow::
             }
         }
 
-        ContractCommands::Send { address, data, value, gas_limit } => {
+        ContractCommands::Send { address, data, value, gas_limit, from, dry_run } => {
             let addr = parse_address(&address)?;
             let val = parse_amount_to_wei(&value)?;
+            let input = hex::decode(data.trim_start_matches("0x"))?;
+            let sender_addr = resolve_sender(from, config)?;
+            let gas_limit = gas_limit.unwrap_or(config.gas_limit);
 
             println!("Sending {} to contract {}", 
                 format_merk(&val).bright_yellow(),
                 format_address(&addr)
             );
+            println!("From: {}", format_address(&sender_addr));
             println!("Data: {}", data.bright_cyan());
-            
-            if let Some(gas) = gas_limit {
-                println!("Gas limit: {}", gas);
-            }
+            println!("Gas limit: {}", gas_limit);
 
-            // TODO: Send transaction
-            print_info("Contract interaction not yet implemented");
+            let signed_tx = sign_transaction(
+                client,
+                config,
+                sender_addr,
+                addr,
+                val,
+                gas_limit,
+                config.gas_price,
+                input,
+            ).await?;
+            let tx_hex = encode_signed_transaction(&signed_tx)?;
+
+            if dry_run {
+                print_dry_run(&signed_tx, &tx_hex);
+            } else {
+                broadcast_transaction(client, &tx_hex).await;
+            }
         }
 
         ContractCommands::Code { address } => {
@@ -863,10 +939,46 @@ async fn execute_node(cmd: NodeCommands) -> anyhow::Result<()> {
             print_info("Use Ctrl+C if running in foreground");
         }
 
-        NodeCommands::Status => {
+        NodeCommands::Status { data_dir } => {
+            let path = data_dir.join(NODE_STATUS_FILE);
+            let contents = match std::fs::read(&path) {
+                Ok(contents) => contents,
+                Err(_) => {
+                    print_warning(&format!("No status file at {:?}; is the node running?", path));
+                    return Ok(());
+                }
+            };
+            let status: serde_json::Value = serde_json::from_slice(&contents)
+                .map_err(|e| anyhow::anyhow!("Invalid status file {:?}: {}", path, e))?;
+            let field = |key: &str| match &status[key] {
+                serde_json::Value::Null => "-".to_string(),
+                value => value.to_string(),
+            };
+
             println!("{}", "Node Status".bold());
             println!("{}", "=".repeat(50));
-            println!("Status: {}", "Not implemented".yellow());
+            println!("PID:          {}", field("pid"));
+            println!("Block height: {}", field("block_height"));
+            println!("Peers:        {}", field("peer_count"));
+            println!("Syncing:      {}", field("syncing"));
+            println!("Uptime:       {}s", field("uptime_secs"));
+            println!("RPC port:     {}", field("rpc_port"));
+            println!("P2P port:     {}", field("p2p_port"));
+
+            // The node refreshes the file every few seconds and removes it on shutdown,
+            // so an old snapshot means the node exited without cleaning up
+            let now = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0);
+            if let Some(updated_at) = status["updated_at"].as_u64() {
+                if now.saturating_sub(updated_at) > NODE_STATUS_STALE_SECS {
+                    print_warning(&format!(
+                        "Status is {}s old; the node may have stopped unexpectedly",
+                        now.saturating_sub(updated_at)
+                    ));
+                }
+            }
         }
 
         NodeCommands::Logs { lines, follow } => {
@@ -880,6 +992,49 @@ async fn execute_node(cmd: NodeCommands) -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Execute bench commands.
+async fn execute_bench(cmd: BenchCommands, rpc_url: String) -> anyhow::Result<()> {
+    match cmd {
+        BenchCommands::Rpc { concurrency, duration, methods, private_key } => {
+            let private_key = match private_key {
+                Some(key) => {
+                    let key_bytes = hex::decode(key.trim_start_matches("0x").trim_start_matches("0X"))?;
+                    <[u8; 32]>::try_from(key_bytes.as_slice()).map_err(|_| {
+                        anyhow::anyhow!("Invalid private key length: expected 64 hex chars (32 bytes)")
+                    })?
+                }
+                None => merklith_core::state_machine::State::devnet_accounts()[0].to_bytes(),
+            };
+
+            println!(
+                "Benchmarking {} for {}s with {} concurrent requests: {}",
+                rpc_url.bright_yellow(),
+                duration,
+                concurrency,
+                methods.join(", ")
+            );
+            let report = crate::bench::run(crate::bench::BenchConfig {
+                url: rpc_url,
+                concurrency,
+                duration: std::time::Duration::from_secs(duration),
+                methods,
+                private_key,
+            })
+            .await?;
+            println!();
+            report.print();
+        }
+    }
+
+    Ok(())
+}
+
+/// Status file written by `merklith-node` into its data directory.
+const NODE_STATUS_FILE: &str = "node-status.json";
+
+/// Age after which a status snapshot is considered stale.
+const NODE_STATUS_STALE_SECS: u64 = 30;
+
 /// Execute config commands.
 async fn execute_config(cmd: ConfigCommands) -> anyhow::Result<()> {
     let mut config = CliConfig::load()?;
@@ -962,6 +1117,118 @@ async fn execute_config(cmd: ConfigCommands) -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Sender given with `--from`, or the keystore's default account.
+fn resolve_sender(from: Option<String>, config: &CliConfig) -> anyhow::Result<Address> {
+    if let Some(addr_str) = from {
+        return parse_address(&addr_str);
+    }
+
+    let keystore = Keystore::new(config.keystore_path())?;
+    match keystore.get_default() {
+        Some(entry) => Ok(entry.address),
+        None => {
+            print_error("No sender specified and no default account set");
+            print_info("Set a default account with: merklith wallet create");
+            print_info("Or use --from flag: merklith tx send 0x... 1.0 --from 0x...");
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Prompt for the wallet password and sign a transaction from `sender`.
+/// The nonce and chain ID are fetched from the node.
+#[allow(clippy::too_many_arguments)]
+async fn sign_transaction(
+    client: &RpcClient,
+    config: &CliConfig,
+    sender: Address,
+    to: Address,
+    value: U256,
+    gas_limit: u64,
+    gas_price: u64,
+    data: Vec<u8>,
+) -> anyhow::Result<SignedTransaction> {
+    let private_key = unlock_wallet(config, &sender)?;
+    let nonce = client.get_transaction_count(&sender).await?;
+    let chain_id = client.chain_id().await?;
+    
+    let tx = Transaction::new(
+        chain_id,
+        nonce,
+        Some(to),
+        value,
+        gas_limit,
+        U256::from(gas_price),
+        U256::ZERO,
+    )
+    .with_data(data);
+    
+    Ok(sign_with_key(&private_key, tx))
+}
+
+/// Keys unlocked by this process, relocked after the configured idle time
+fn unlocked_session(config: &CliConfig) -> &'static std::sync::Mutex<UnlockedSession> {
+    static SESSION: std::sync::OnceLock<std::sync::Mutex<UnlockedSession>> = std::sync::OnceLock::new();
+    SESSION.get_or_init(|| std::sync::Mutex::new(UnlockedSession::new(config.unlock_timeout())))
+}
+
+/// Private key of `sender`: the key unlocked earlier if it has not been idle
+/// too long, otherwise the wallet is decrypted with a freshly prompted password.
+fn unlock_wallet(config: &CliConfig, sender: &Address) -> anyhow::Result<zeroize::Zeroizing<[u8; 32]>> {
+    let keystore = Keystore::new(config.keystore_path())?;
+    let mut session = unlocked_session(config).lock().unwrap_or_else(|e| e.into_inner());
+    session
+        .signing_key(&keystore, sender, || {
+            Ok(Password::new()
+                .with_prompt("Enter wallet password to sign transaction")
+                .interact()?)
+        })
+        .map_err(|_| anyhow::anyhow!("Failed to decrypt wallet. Wrong password?"))
+}
+
+/// Sign `tx` with a private key. Needs no network access.
+pub(crate) fn sign_with_key(private_key: &[u8; 32], tx: Transaction) -> SignedTransaction {
+    let keypair = Ed25519Keypair::from_seed(private_key);
+    let (signature, public_key) = keypair.sign_transaction(&tx);
+    SignedTransaction::new(tx, signature, public_key)
+}
+
+/// Serialize a signed transaction to the hex accepted by `eth_sendRawTransaction`.
+pub(crate) fn encode_signed_transaction(signed_tx: &SignedTransaction) -> anyhow::Result<String> {
+    let tx_bytes = borsh::to_vec(signed_tx)?;
+    Ok(format!("0x{}", hex::encode(&tx_bytes)))
+}
+
+/// Parse the hex produced by [`encode_signed_transaction`].
+pub(crate) fn decode_signed_transaction(raw: &str) -> anyhow::Result<SignedTransaction> {
+    let bytes = hex::decode(raw.trim().trim_start_matches("0x"))?;
+    borsh::from_slice(&bytes)
+        .map_err(|e| anyhow::anyhow!("Invalid signed transaction: {}", e))
+}
+
+/// Submit a signed transaction and report the result.
+async fn broadcast_transaction(client: &RpcClient, tx_hex: &str) {
+    match client.send_raw_transaction(tx_hex).await {
+        Ok(tx_hash) => {
+            print_success("Transaction sent successfully!");
+            println!("Transaction Hash: {}", tx_hash.to_string().bright_green());
+            println!("\nView transaction:");
+            println!("  merklith tx get {}", tx_hash.to_string().bright_cyan());
+        }
+        Err(e) => {
+            print_error(&format!("Failed to send transaction: {}", e));
+        }
+    }
+}
+
+/// Print a signed transaction that was not broadcast.
+fn print_dry_run(signed_tx: &SignedTransaction, tx_hex: &str) {
+    print_warning("Dry run: the transaction was signed but NOT sent");
+    print_signed_transaction(signed_tx);
+    println!("\nRaw transaction:");
+    println!("{}", tx_hex);
+}
+
 /// Parse address string.
 fn parse_address(s: &str) -> anyhow::Result<Address> {
     let s = s.trim_start_matches("0x");
@@ -974,6 +1241,19 @@ fn parse_address(s: &str) -> anyhow::Result<Address> {
     Ok(Address::from_bytes(addr))
 }
 
+/// Check that an imported key derives the expected address.
+pub(crate) fn verify_derived_address(derived: &Address, expected: &str) -> anyhow::Result<()> {
+    let expected = parse_address(expected)?;
+    if *derived != expected {
+        anyhow::bail!(
+            "Private key does not match expected address: key derives {}, expected {}",
+            format_address(derived),
+            format_address(&expected)
+        );
+    }
+    Ok(())
+}
+
 /// Parse hash string.
 fn parse_hash(s: &str) -> anyhow::Result<merklith_types::Hash> {
     let s = s.trim_start_matches("0x");
@@ -20,6 +20,13 @@ pub struct CliConfig {
     pub keystore_dir: PathBuf,
     /// Default account
     pub default_account: Option<String>,
+    /// Seconds an unlocked wallet stays usable without being used
+    #[serde(default = "default_unlock_timeout_secs")]
+    pub unlock_timeout_secs: u64,
+}
+
+fn default_unlock_timeout_secs() -> u64 {
+    crate::keystore::DEFAULT_UNLOCK_TIMEOUT.as_secs()
 }
 
 impl Default for CliConfig {
@@ -34,6 +41,7 @@ impl Default for CliConfig {
                 .join(".merklith")
                 .join("keystore"),
             default_account: None,
+            unlock_timeout_secs: default_unlock_timeout_secs(),
         }
     }
 }
@@ -79,6 +87,11 @@ impl CliConfig {
     pub fn keystore_path(&self) -> PathBuf {
         self.keystore_dir.clone()
     }
+
+    /// Idle time before unlocked wallets are locked again.
+    pub fn unlock_timeout(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.unlock_timeout_secs)
+    }
 }
 
 #[cfg(test)]
@@ -1,7 +1,8 @@
 //! Keystore for encrypted wallet storage.
 //!
 //! Wallets are encrypted with AES-256-GCM using a key derived from the password
-//! with Argon2id.
+//! with Argon2id. An [`UnlockedSession`] can keep decrypted keys between
+//! operations; they are zeroized once left idle for too long.
 
 use merklith_crypto::keystore::{encrypt_keystore, decrypt_keystore};
 use merklith_types::Address;
@@ -9,6 +10,11 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use zeroize::Zeroizing;
+
+/// Idle time after which an unlocked session forgets its keys
+pub const DEFAULT_UNLOCK_TIMEOUT: Duration = Duration::from_secs(5 * 60);
 
 /// Keystore entry metadata
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -167,4 +173,77 @@ impl Keystore {
     pub fn has_wallet(&self, address: &Address) -> bool {
         self.entries.contains_key(address)
     }
-}
\ No newline at end of file
+}
+
+/// A decrypted key and when it was last used
+struct UnlockedKey {
+    secret: Zeroizing<[u8; 32]>,
+    last_used: Instant,
+}
+
+/// Decrypted keys kept between operations.
+///
+/// A key not used for `idle_timeout` is zeroized and the password must be
+/// entered again.
+pub struct UnlockedSession {
+    idle_timeout: Duration,
+    keys: HashMap<Address, UnlockedKey>,
+}
+
+impl UnlockedSession {
+    /// Create an empty session
+    pub fn new(idle_timeout: Duration) -> Self {
+        Self {
+            idle_timeout,
+            keys: HashMap::new(),
+        }
+    }
+
+    /// Whether `address` has a key that has not timed out
+    pub fn is_unlocked(&self, address: &Address) -> bool {
+        self.keys
+            .get(address)
+            .is_some_and(|key| key.last_used.elapsed() < self.idle_timeout)
+    }
+
+    /// Private key for `address`. Uses the unlocked key if it is still fresh,
+    /// otherwise asks `prompt` for the password and decrypts the wallet.
+    pub fn signing_key(
+        &mut self,
+        keystore: &Keystore,
+        address: &Address,
+        prompt: impl FnOnce() -> anyhow::Result<String>,
+    ) -> anyhow::Result<Zeroizing<[u8; 32]>> {
+        self.lock_expired();
+
+        if let Some(key) = self.keys.get_mut(address) {
+            key.last_used = Instant::now();
+            return Ok(key.secret.clone());
+        }
+
+        let password = Zeroizing::new(prompt()?);
+        let secret = Zeroizing::new(keystore.load_wallet(address, &password)?);
+        self.keys.insert(*address, UnlockedKey {
+            secret: secret.clone(),
+            last_used: Instant::now(),
+        });
+        Ok(secret)
+    }
+
+    /// Zeroize keys that have been idle for longer than the timeout
+    pub fn lock_expired(&mut self) {
+        let idle_timeout = self.idle_timeout;
+        self.keys.retain(|_, key| key.last_used.elapsed() < idle_timeout);
+    }
+
+    /// Zeroize every key
+    pub fn lock_all(&mut self) {
+        self.keys.clear();
+    }
+}
+
+impl Default for UnlockedSession {
+    fn default() -> Self {
+        Self::new(DEFAULT_UNLOCK_TIMEOUT)
+    }
+}
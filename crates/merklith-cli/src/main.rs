@@ -8,7 +8,9 @@ pub mod output;
 pub mod config;
 pub mod keystore;
 pub mod explorer;
-pub mod tests;
+pub mod bench;
+#[cfg(test)]
+mod tests;
 
 use clap::Parser;
 use colored::Colorize;
@@ -24,7 +26,7 @@ async fn main() -> anyhow::Result<()> {
     // Execute command
     let cmd = cli.command;
     let _rpc = cli.rpc; // Extract before dropping cli
-    if let Err(e) = commands::execute(cmd, _rpc).await {
+    if let Err(e) = commands::execute(cmd, _rpc, cli.chain_id).await {
         eprintln!("{}", format!("Error: {}", e).red());
         std::process::exit(1);
     }
@@ -2,7 +2,7 @@
 //!
 //! Pretty printing for CLI commands.
 
-use merklith_types::{Address, Hash, U256};
+use merklith_types::{Address, Hash, SignedTransaction, U256};
 use colored::Colorize;
 use tabled::{Table, Tabled};
 
@@ -105,6 +105,29 @@ pub fn print_transaction_receipt(receipt: serde_json::Value) {
     }
 }
 
+/// Print a decoded signed transaction.
+pub fn print_signed_transaction(signed_tx: &SignedTransaction) {
+    let tx = &signed_tx.tx;
+    let to = tx.to
+        .map(|to| format!("0x{}", hex::encode(to.as_bytes())))
+        .unwrap_or_else(|| "(contract creation)".to_string());
+
+    println!("{}", "Signed Transaction".bold());
+    println!("{}", "=".repeat(50));
+    println!("Hash:      {}", format_hash(&signed_tx.hash()).bright_cyan());
+    println!("From:      0x{}", hex::encode(signed_tx.sender().as_bytes()));
+    println!("To:        {}", to);
+    println!("Value:     {} ({} wei)", format_merk(&tx.value).bright_yellow(), tx.value);
+    println!("Nonce:     {}", tx.nonce);
+    println!("Chain ID:  {}", tx.chain_id);
+    println!("Gas Limit: {}", tx.gas_limit);
+    println!("Gas Price: {} wei", tx.max_fee_per_gas);
+    println!("Max Fee:   {}", format_merk(&signed_tx.max_cost().saturating_sub(&tx.value)).bright_yellow());
+    if !tx.data.is_empty() {
+        println!("Data:      0x{}", hex::encode(&tx.data));
+    }
+}
+
 /// Print balance table.
 pub fn print_balance_table(balances: &[(String, U256)]) {
     #[derive(Tabled)]
@@ -30,6 +30,7 @@ mod tests {
             gas_limit: 200_000,
             keystore_dir: temp_dir.path().join("keystore"),
             default_account: Some("test_account".to_string()),
+            unlock_timeout_secs: 60,
         };
         
         // Test toml serialization
@@ -39,7 +40,7 @@ mod tests {
     }
 }
 
-use crate::keystore::Keystore;
+use crate::keystore::{Keystore, UnlockedSession};
 use merklith_types::Address;
 use merklith_crypto::ed25519::Keypair;
 
@@ -125,4 +126,167 @@ mod keystore_tests {
         let result = keystore.load_wallet(&address, "wrong");
         assert!(result.is_err());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_unlocked_session_relocks_after_timeout() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut keystore = Keystore::new(temp_dir.path().to_path_buf()).unwrap();
+        let keypair = Keypair::generate();
+        let address = keypair.address();
+        keystore.save_wallet("test", address, &keypair.to_bytes(), "pass", false).unwrap();
+
+        let mut session = UnlockedSession::new(std::time::Duration::from_millis(300));
+        let mut prompts = 0;
+        let mut prompt = || {
+            prompts += 1;
+            Ok("pass".to_string())
+        };
+
+        let key = session.signing_key(&keystore, &address, &mut prompt).unwrap();
+        assert_eq!(*key, keypair.to_bytes());
+        assert!(session.is_unlocked(&address));
+
+        // Used again within the timeout: no prompt
+        session.signing_key(&keystore, &address, &mut prompt).unwrap();
+
+        std::thread::sleep(std::time::Duration::from_millis(400));
+        assert!(!session.is_unlocked(&address));
+
+        // A stale key is not reused: the password is asked for again
+        let key = session.signing_key(&keystore, &address, &mut prompt).unwrap();
+        assert_eq!(*key, keypair.to_bytes());
+        assert_eq!(prompts, 2);
+    }
+}
+
+#[cfg(test)]
+mod command_tests {
+    use super::*;
+    use crate::commands::{
+        decode_signed_transaction, encode_signed_transaction, sign_with_key, verify_derived_address,
+    };
+    use crate::rpc_client::RpcClient;
+    use merklith_sdk::test_utils::mock_rpc;
+    use merklith_types::{SignedTransaction, Transaction, U256};
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_import_expected_address() {
+        let derived = Keypair::from_seed(&[1u8; 32]).address();
+        assert!(verify_derived_address(&derived, "0x83561adb398fd87f8e7ed8331bff2fcb945733cc").is_ok());
+
+        // A key pasted for the wrong account is refused
+        let other = Keypair::from_seed(&[2u8; 32]).address();
+        assert!(verify_derived_address(&other, "0x83561adb398fd87f8e7ed8331bff2fcb945733cc").is_err());
+    }
+
+    #[test]
+    fn test_dry_run_hex_decodes_to_signed_transaction() {
+        let keypair = Keypair::generate();
+        let tx = Transaction::new(
+            1337,
+            4,
+            Some(Address::from_bytes([7u8; 20])),
+            U256::from(1000u64),
+            21000,
+            U256::from(1_000_000_000u64),
+            U256::ZERO,
+        )
+        .with_data(vec![0xab, 0xcd]);
+        let (signature, public_key) = keypair.sign_transaction(&tx);
+        let signed_tx = SignedTransaction::new(tx, signature, public_key);
+
+        let tx_hex = encode_signed_transaction(&signed_tx).unwrap();
+        assert!(tx_hex.starts_with("0x"));
+
+        let bytes = hex::decode(&tx_hex[2..]).unwrap();
+        let decoded: SignedTransaction = borsh::from_slice(&bytes).unwrap();
+        assert_eq!(decoded, signed_tx);
+        assert_eq!(decoded.sender(), keypair.address());
+    }
+
+    #[tokio::test]
+    async fn test_offline_build_then_broadcast() {
+        let keypair = Keypair::generate();
+        let tx = Transaction::new(
+            42,
+            9,
+            Some(Address::from_bytes([3u8; 20])),
+            U256::from(5u64),
+            21000,
+            U256::from(1_000_000_000u64),
+            U256::ZERO,
+        );
+
+        // Offline: only the key, nonce and chain ID are needed
+        let signed_tx = sign_with_key(&keypair.to_bytes(), tx);
+        let tx_hex = encode_signed_transaction(&signed_tx).unwrap();
+        assert_eq!(signed_tx.tx.nonce, 9);
+        assert_eq!(signed_tx.tx.chain_id, 42);
+
+        // Online: broadcast the hex as-is
+        let received = Arc::new(Mutex::new(None));
+        let log = received.clone();
+        let url = mock_rpc(move |method, params| {
+            assert_eq!(method, "eth_sendRawTransaction");
+            let signed_tx = decode_signed_transaction(params[0].as_str().unwrap()).unwrap();
+            let tx_hash = signed_tx.hash().to_string();
+            *log.lock().unwrap() = Some(signed_tx);
+            Ok(serde_json::json!(tx_hash))
+        })
+        .await;
+
+        let client = RpcClient::new(url);
+        let decoded = decode_signed_transaction(&tx_hex).unwrap();
+        let tx_hash = client
+            .send_raw_transaction(&encode_signed_transaction(&decoded).unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(received.lock().unwrap().take(), Some(signed_tx.clone()));
+        assert_eq!(tx_hash, signed_tx.hash());
+    }
+}
+
+#[cfg(test)]
+mod bench_tests {
+    use crate::bench::{run, BenchConfig, WRITE_METHOD};
+    use merklith_core::state_machine::State;
+    use merklith_rpc::{RpcServer, RpcServerConfig};
+    use merklith_txpool::TransactionPool;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_bench_in_process_server() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let state = Arc::new(State::with_path(temp_dir.path().to_path_buf()));
+        let chain_id = state.chain_id();
+        let addr = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        let config = RpcServerConfig { http_addr: addr, ..RpcServerConfig::default() };
+        let pool = Arc::new(TransactionPool::default());
+        let mut server = RpcServer::new(config, state.clone(), chain_id).with_pool(pool.clone());
+        server.start().await.unwrap();
+
+        let sender = &State::devnet_accounts()[0];
+        let report = run(BenchConfig {
+            url: format!("http://{}", addr),
+            concurrency: 4,
+            duration: Duration::from_millis(500),
+            methods: vec!["eth_blockNumber".to_string(), "eth_getBalance".to_string(), WRITE_METHOD.to_string()],
+            private_key: sender.to_bytes(),
+        })
+        .await
+        .unwrap();
+
+        report.print();
+        assert_eq!(report.methods.len(), 3);
+        for stats in report.methods.values() {
+            assert!(stats.requests > 0);
+            assert_eq!(stats.errors, 0);
+            assert!(stats.percentile(50.0) <= stats.percentile(99.0));
+        }
+        // Every signed write was admitted to the pool, in nonce order
+        assert_eq!(pool.pending_count_for(&sender.address()), report.methods[WRITE_METHOD].requests as usize);
+    }
+}
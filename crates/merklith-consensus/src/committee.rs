@@ -5,10 +5,8 @@
 //! 2. VRF proofs (verifiable randomness)
 //! 3. Stake-weighted sampling
 
-use std::collections::HashSet;
-use merklith_types::{Address, Hash};
-use merklith_crypto::vrf::VrfProof;
-use crate::poc::{calculate_poc_score, ContributionMetrics, ValidatorInfo, PocConfig};
+use merklith_types::{Address, Ed25519Signature};
+use crate::poc::{calculate_poc_score, ValidatorInfo, PocConfig};
 use crate::error::ConsensusError;
 
 /// Committee configuration.
@@ -45,8 +43,8 @@ pub struct CommitteeMember {
     pub address: Address,
     /// PoC score
     pub poc_score: f64,
-    /// VRF proof for this selection
-    pub vrf_proof: VrfProof,
+    /// VRF proof for this selection, as in `merklith_crypto::VRFOutput`
+    pub vrf_proof: Ed25519Signature,
     /// VRF output (determines position)
     pub vrf_output: [u8; 32],
     /// Stake weight
@@ -58,7 +56,7 @@ impl CommitteeMember {
     pub fn new(
         address: Address,
         poc_score: f64,
-        vrf_proof: VrfProof,
+        vrf_proof: Ed25519Signature,
         vrf_output: [u8; 32],
         stake_weight: f64,
     ) -> Self {
@@ -110,7 +108,7 @@ impl Committee {
 
     /// Sort members by VRF output (deterministic ordering).
     pub fn sort(&mut self) {
-        self.members.sort_by(|a, b| a.sorting_key().cmp(&b.sorting_key()));
+        self.members.sort_by_key(|member| member.sorting_key());
     }
 
     /// Get the proposer for a given slot.
@@ -197,7 +195,7 @@ pub fn select_committee(
     }
 
     let mut committee = Committee::new(epoch, seed);
-    let mut candidates: Vec<(ValidatorInfo, f64, VrfProof, [u8; 32])> = Vec::new();
+    let mut candidates: Vec<(ValidatorInfo, f64, Ed25519Signature, [u8; 32])> = Vec::new();
 
     // Calculate PoC scores and collect candidates
     for validator in validators {
@@ -232,8 +230,7 @@ pub fn select_committee(
         vrf_output.copy_from_slice(input_hash.as_bytes());
         
         // Create a dummy VRF proof (in production, this would be actual crypto)
-        let vrf_proof = VrfProof::from_bytes(&[0u8; 64])
-            .map_err(|e| ConsensusError::CryptoError(e.to_string()))?;
+        let vrf_proof = Ed25519Signature::default();
 
         // Calculate selection weight (PoC score * stake)
         let stake_weight = poc_score * committee_config.poc_boost_factor;
@@ -280,7 +277,7 @@ pub fn select_committee(
         let member = CommitteeMember::new(
             validator.address,
             poc_score,
-            vrf_proof.clone(),
+            *vrf_proof,
             *vrf_output,
             *stake_weight,
         );
@@ -303,6 +300,7 @@ pub fn select_committee(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::poc::ContributionMetrics;
     use merklith_types::Address;
     use merklith_types::U256;
 
@@ -314,7 +312,7 @@ mod tests {
             let address = Address::from_bytes(addr);
             
             validators.push(
-                ValidatorInfo::new(address, U256::from(1_000_000_000u128))
+                ValidatorInfo::new(address, U256::from(100_000_000_000_000_000_000u128)) // 100 MERK
                     .with_epochs_active(20)
                     .with_contribution(ContributionMetrics::new().with_uptime(95.0))
             );
@@ -358,8 +356,8 @@ mod tests {
             let member = CommitteeMember::new(
                 Address::from_bytes(addr),
                 0.5,
-                VrfProof::from_bytes(&[0u8; 64]).unwrap(),
-                [i as u8; 32],
+                Ed25519Signature::default(),
+                [i; 32],
                 1.0,
             );
             committee.add_member(member);
@@ -382,7 +380,7 @@ mod tests {
         let member = CommitteeMember::new(
             address,
             0.5,
-            VrfProof::from_bytes(&[0u8; 64]).unwrap(),
+            Ed25519Signature::default(),
             [1u8; 32],
             1.0,
         );
@@ -425,8 +423,8 @@ mod tests {
             let member = CommitteeMember::new(
                 Address::from_bytes(addr),
                 0.5,
-                VrfProof::from_bytes(&[0u8; 64]).unwrap(),
-                [i as u8; 32],
+                Ed25519Signature::default(),
+                [i; 32],
                 i as f64, // Weights: 1, 2, 3
             );
             committee.add_member(member);
@@ -2,18 +2,32 @@
 //!
 //! Validators are selected based on their contributions to the network.
 
-use std::collections::HashMap;
+use merklith_types::{BLSPublicKey, BLSSignature, BlockHeader, ChainConfig, Ed25519PublicKey};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+
+pub mod committee;
+pub mod error;
+#[path = "poc.rs"]
+mod scoring;
 
 pub mod validator {
-    pub use super::{Validator, ValidatorSet};
+    pub use super::{EpochSnapshot, Validator, ValidatorSet};
 }
 
 pub mod poc {
-    pub use super::{Contribution, ContributionTracker, PoCScore, ContributionType};
+    pub use super::{
+        Contribution, ContributionTracker, ContributionWeights, DecayMode, PoCScore, ContributionType,
+        RankingEntry, RankingSnapshot,
+    };
+    pub use super::scoring::*;
 }
 
 pub mod attestation {
-    pub use super::{Attestation, AttestationPool, AttestationStatus};
+    pub use super::{
+        proof_of_possession_message, AggregateAttestation, Attestation, AttestationPool, AttestationStatus,
+        ATTESTATION_SIGNING_DOMAIN, PROOF_OF_POSSESSION_DOMAIN,
+    };
 }
 
 /// Validator information
@@ -30,6 +44,7 @@ pub enum ConsensusError {
     InvalidSignature,
     NotValidator,
     InsufficientContribution,
+    InvalidAttestation(String),
 }
 
 impl std::fmt::Display for ConsensusError {
@@ -39,12 +54,16 @@ impl std::fmt::Display for ConsensusError {
             ConsensusError::InvalidSignature => write!(f, "Invalid signature"),
             ConsensusError::NotValidator => write!(f, "Not a validator"),
             ConsensusError::InsufficientContribution => write!(f, "Insufficient contribution score"),
+            ConsensusError::InvalidAttestation(e) => write!(f, "Invalid attestation: {}", e),
         }
     }
 }
 
 impl std::error::Error for ConsensusError {}
 
+/// Bytes of block data served per point of data-availability score
+pub const DATA_AVAILABILITY_BYTES_PER_POINT: u64 = 1024;
+
 /// Types of contributions that earn PoC score
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum ContributionType {
@@ -66,7 +85,7 @@ pub struct Contribution {
 }
 
 /// PoC score for a validator
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct PoCScore {
     pub total: u64,
     pub block_production: u64,
@@ -145,26 +164,147 @@ pub struct ContributionPercentages {
     pub data_availability: f64,
 }
 
+/// Score awarded per contribution of each type
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ContributionWeights {
+    pub block_production: u64,
+    pub attestation: u64,
+    pub tx_relay: u64,
+    pub peer_discovery: u64,
+    /// Per started `DATA_AVAILABILITY_BYTES_PER_POINT` bytes served
+    pub data_availability: u64,
+}
+
+impl Default for ContributionWeights {
+    fn default() -> Self {
+        Self {
+            block_production: 100,
+            attestation: 10,
+            tx_relay: 1,
+            peer_discovery: 5,
+            data_availability: 1,
+        }
+    }
+}
+
+/// One contributor's standing in a [`RankingSnapshot`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RankingEntry {
+    /// 1-based position, highest score first
+    pub rank: usize,
+    pub address: merklith_types::Address,
+    pub score: PoCScore,
+}
+
+/// Contribution standings frozen at a point in time, for reward payouts
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RankingSnapshot {
+    /// Unix time the snapshot was taken
+    pub timestamp: u64,
+    /// Sum of all contributors' total scores
+    pub total_score: u64,
+    pub entries: Vec<RankingEntry>,
+}
+
+impl RankingSnapshot {
+    /// Split `pool` between contributors in proportion to their total score.
+    ///
+    /// Each share is rounded down. The remainder left by rounding (less than
+    /// one unit per contributor) goes to the top-ranked contributor, so the
+    /// allocations always sum to exactly `pool`. Returns nothing if no one
+    /// has a score.
+    pub fn allocate_rewards(&self, pool: merklith_types::U256) -> Vec<(merklith_types::Address, merklith_types::U256)> {
+        use merklith_types::U256;
+
+        if self.total_score == 0 {
+            return Vec::new();
+        }
+        let total = U256::from(self.total_score);
+        let mut allocations: Vec<_> = self.entries
+            .iter()
+            .map(|entry| {
+                let score = U256::from(entry.score.total());
+                let share = match pool.checked_mul(&score) {
+                    Some(product) => product / total,
+                    // Only reachable for pools near U256::MAX
+                    None => pool / total * score,
+                };
+                (entry.address, share)
+            })
+            .collect();
+
+        let allocated = allocations
+            .iter()
+            .fold(U256::ZERO, |sum, (_, share)| sum.saturating_add(share));
+        if let Some((_, top)) = allocations.first_mut() {
+            *top = top.saturating_add(&pool.saturating_sub(&allocated));
+        }
+        allocations
+    }
+}
+
+/// When contribution scores decay
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecayMode {
+    /// Scores drop by 10% every `interval` blocks
+    Blocks { interval: u64 },
+    /// Scores halve every `half_life_secs` of wall-clock time
+    Time { half_life_secs: u64 },
+}
+
 /// Tracks contributions for PoC consensus
 #[derive(Debug, Clone)]
 pub struct ContributionTracker {
+    weights: ContributionWeights,
     scores: HashMap<merklith_types::Address, PoCScore>,
     contribution_history: Vec<Contribution>,
     last_decay_block: u64,
-    decay_interval: u64,
+    decay_mode: DecayMode,
+    /// Start of the current half-life in time mode; 0 until the first contribution
+    last_decay_time: u64,
+    /// Peers already credited to a discoverer
+    discovered_peers: HashSet<String>,
 }
 
 impl ContributionTracker {
     pub fn new() -> Self {
         Self {
+            weights: ContributionWeights::default(),
             scores: HashMap::new(),
             contribution_history: Vec::new(),
             last_decay_block: 0,
-            decay_interval: 1000,
+            decay_mode: DecayMode::Blocks { interval: 1000 },
+            last_decay_time: 0,
+            discovered_peers: HashSet::new(),
         }
     }
     
+    /// Use `weights` instead of the default per-type scores
+    pub fn with_weights(mut self, weights: ContributionWeights) -> Self {
+        self.weights = weights;
+        self
+    }
+    
+    pub fn weights(&self) -> &ContributionWeights {
+        &self.weights
+    }
+    
+    /// Halve scores every `half_life_secs` of wall-clock time instead of
+    /// decaying every 1000 blocks
+    pub fn with_time_decay(mut self, half_life_secs: u64) -> Self {
+        self.decay_mode = DecayMode::Time { half_life_secs };
+        self
+    }
+    
+    pub fn decay_mode(&self) -> DecayMode {
+        self.decay_mode
+    }
+    
     pub fn record_contribution(&mut self, contribution: Contribution) {
+        if self.last_decay_time == 0 {
+            self.last_decay_time = contribution.timestamp;
+        }
         let score = self.scores.entry(contribution.contributor).or_default();
         score.add_contribution(contribution.contribution_type, contribution.weight);
         self.contribution_history.push(contribution);
@@ -175,36 +315,51 @@ impl ContributionTracker {
     }
     
     pub fn record_block_production(&mut self, proposer: merklith_types::Address, block_number: u64) {
-        self.record_contribution(Contribution {
-            contributor: proposer,
-            contribution_type: ContributionType::BlockProduction,
-            weight: 100,
-            block_number,
-            timestamp: std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .map(|d| d.as_secs())
-                .unwrap_or(0),
-        });
+        self.record(proposer, ContributionType::BlockProduction, self.weights.block_production, block_number);
     }
     
     pub fn record_attestation(&mut self, attester: merklith_types::Address, block_number: u64) {
-        self.record_contribution(Contribution {
-            contributor: attester,
-            contribution_type: ContributionType::Attestation,
-            weight: 10,
-            block_number,
-            timestamp: std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .map(|d| d.as_secs())
-                .unwrap_or(0),
-        });
+        self.record(attester, ContributionType::Attestation, self.weights.attestation, block_number);
     }
     
     pub fn record_tx_relay(&mut self, relayer: merklith_types::Address, block_number: u64) {
+        self.record(relayer, ContributionType::TransactionRelay, self.weights.tx_relay, block_number);
+    }
+    
+    /// Record block data served to peers; the weight is scaled by the number
+    /// of started `DATA_AVAILABILITY_BYTES_PER_POINT` bytes
+    pub fn record_data_availability(&mut self, provider: merklith_types::Address, block_number: u64, bytes_served: u64) {
+        let weight = bytes_served
+            .div_ceil(DATA_AVAILABILITY_BYTES_PER_POINT)
+            .saturating_mul(self.weights.data_availability);
+        if weight == 0 {
+            return;
+        }
+        self.record(provider, ContributionType::DataAvailability, weight, block_number);
+    }
+    
+    /// Credit `discoverer` for introducing `peer_id`. Each peer earns score
+    /// once; returns false if it was already credited.
+    pub fn record_peer_discovery(&mut self, discoverer: merklith_types::Address, peer_id: &str, block_number: u64) -> bool {
+        if !self.discovered_peers.insert(peer_id.to_string()) {
+            return false;
+        }
+        self.record(discoverer, ContributionType::PeerDiscovery, self.weights.peer_discovery, block_number);
+        true
+    }
+    
+    /// Record a contribution made now
+    fn record(
+        &mut self,
+        contributor: merklith_types::Address,
+        contribution_type: ContributionType,
+        weight: u64,
+        block_number: u64,
+    ) {
         self.record_contribution(Contribution {
-            contributor: relayer,
-            contribution_type: ContributionType::TransactionRelay,
-            weight: 1,
+            contributor,
+            contribution_type,
+            weight,
             block_number,
             timestamp: std::time::SystemTime::now()
                 .duration_since(std::time::UNIX_EPOCH)
@@ -214,12 +369,43 @@ impl ContributionTracker {
     }
     
     pub fn maybe_decay(&mut self, current_block: u64) {
-        if current_block >= self.last_decay_block + self.decay_interval {
-            for score in self.scores.values_mut() {
-                score.decay(9, 10);
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        self.maybe_decay_at(current_block, now);
+    }
+    
+    /// Apply any decay due at `current_block` and wall-clock time `now` (seconds)
+    pub fn maybe_decay_at(&mut self, current_block: u64, now: u64) {
+        match self.decay_mode {
+            DecayMode::Blocks { interval } => {
+                if current_block >= self.last_decay_block + interval {
+                    for score in self.scores.values_mut() {
+                        score.decay(9, 10);
+                    }
+                    self.last_decay_block = current_block;
+                    self.contribution_history.retain(|c| c.block_number > current_block.saturating_sub(10000));
+                }
+            }
+            DecayMode::Time { half_life_secs } => {
+                if half_life_secs == 0 || self.last_decay_time == 0 {
+                    return;
+                }
+                // Whole half-lives only; the remainder carries over to the next call
+                let half_lives = now.saturating_sub(self.last_decay_time) / half_life_secs;
+                if half_lives == 0 {
+                    return;
+                }
+                for score in self.scores.values_mut() {
+                    for _ in 0..half_lives.min(64) {
+                        score.decay(1, 2);
+                    }
+                }
+                self.last_decay_time += half_lives * half_life_secs;
+                let horizon = now.saturating_sub(half_life_secs.saturating_mul(10));
+                self.contribution_history.retain(|c| c.timestamp > horizon);
             }
-            self.last_decay_block = current_block;
-            self.contribution_history.retain(|c| c.block_number > current_block.saturating_sub(10000));
         }
     }
     
@@ -234,6 +420,29 @@ impl ContributionTracker {
     pub fn total_contributions(&self) -> u64 {
         self.scores.values().map(|s| s.total()).sum()
     }
+    
+    /// Copy of the current standings, highest score first (ties by address)
+    pub fn snapshot_rankings(&self) -> RankingSnapshot {
+        let mut scores: Vec<_> = self.scores.iter()
+            .map(|(addr, score)| (*addr, score.clone()))
+            .collect();
+        scores.sort_by(|a, b| {
+            b.1.total().cmp(&a.1.total()).then_with(|| a.0.as_bytes().cmp(b.0.as_bytes()))
+        });
+        
+        RankingSnapshot {
+            timestamp: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            total_score: self.total_contributions(),
+            entries: scores
+                .into_iter()
+                .enumerate()
+                .map(|(i, (address, score))| RankingEntry { rank: i + 1, address, score })
+                .collect(),
+        }
+    }
 }
 
 impl Default for ContributionTracker {
@@ -250,6 +459,22 @@ pub enum AttestationStatus {
     Rejected,
 }
 
+/// Domain tag prefixed to attestation signing messages; distinct from
+/// `merklith_types::TX_SIGNING_DOMAIN`.
+pub const ATTESTATION_SIGNING_DOMAIN: &[u8] = b"merklith-attestation-v1";
+
+/// Domain tag of the proof of possession an attester signs over its own BLS
+/// public key, so it cannot register a key derived from others' keys
+pub const PROOF_OF_POSSESSION_DOMAIN: &[u8] = b"merklith-bls-pop-v1";
+
+/// Message an attester signs to prove it holds the secret key of `public_key`
+pub fn proof_of_possession_message(public_key: &BLSPublicKey) -> Vec<u8> {
+    let mut msg = Vec::new();
+    msg.extend_from_slice(PROOF_OF_POSSESSION_DOMAIN);
+    msg.extend_from_slice(public_key.as_bytes());
+    msg
+}
+
 /// A committee attestation for a block
 #[derive(Debug, Clone)]
 pub struct Attestation {
@@ -282,10 +507,85 @@ impl Attestation {
     }
     
     pub fn signing_message(&self) -> Vec<u8> {
-        let mut msg = Vec::new();
-        msg.extend_from_slice(&self.block_number.to_le_bytes());
-        msg.extend_from_slice(&self.block_hash);
-        msg
+        attestation_signing_message(self.block_number, &self.block_hash)
+    }
+}
+
+fn attestation_signing_message(block_number: u64, block_hash: &[u8; 32]) -> Vec<u8> {
+    let mut msg = Vec::new();
+    msg.extend_from_slice(ATTESTATION_SIGNING_DOMAIN);
+    msg.extend_from_slice(&block_number.to_le_bytes());
+    msg.extend_from_slice(block_hash);
+    msg
+}
+
+/// A block's attestations in compact form: one BLS signature standing in for
+/// every signer, plus a bitfield over the committee marking who signed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AggregateAttestation {
+    pub block_number: u64,
+    pub block_hash: [u8; 32],
+    pub signature: BLSSignature,
+    /// Bit `i` (least significant first) is set if committee member `i` signed
+    pub bitfield: Vec<u8>,
+}
+
+impl AggregateAttestation {
+    pub fn signing_message(&self) -> Vec<u8> {
+        attestation_signing_message(self.block_number, &self.block_hash)
+    }
+
+    /// Whether committee member `index` signed
+    pub fn has_signed(&self, index: usize) -> bool {
+        self.bitfield.get(index / 8).is_some_and(|byte| byte & (1 << (index % 8)) != 0)
+    }
+
+    pub fn signer_count(&self) -> usize {
+        self.bitfield.iter().map(|byte| byte.count_ones() as usize).sum()
+    }
+
+    /// Check the signature against the public keys the bitfield selects
+    /// from `committee`
+    pub fn verify(&self, committee: &[BLSPublicKey]) -> Result<(), ConsensusError> {
+        if self.bitfield.len() != committee.len().div_ceil(8) {
+            return Err(ConsensusError::InvalidAttestation(format!(
+                "bitfield covers {} bytes, committee of {} needs {}",
+                self.bitfield.len(),
+                committee.len(),
+                committee.len().div_ceil(8)
+            )));
+        }
+        if (committee.len()..self.bitfield.len() * 8).any(|index| self.has_signed(index)) {
+            return Err(ConsensusError::InvalidAttestation("bitfield marks signers outside the committee".to_string()));
+        }
+        let signers: Vec<BLSPublicKey> = committee
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| self.has_signed(*index))
+            .map(|(_, public_key)| public_key.clone())
+            .collect();
+        merklith_crypto::bls_verify_aggregate(&signers, &self.signing_message(), &self.signature)
+            .map_err(|_| ConsensusError::InvalidSignature)
+    }
+
+    /// Store in a block header in place of individual signatures
+    pub fn apply_to(&self, header: &mut BlockHeader) {
+        header.attestation_aggregate = self.signature.clone();
+        header.attestation_bitmap = self.bitfield.clone();
+        header.attestation_count = self.signer_count() as u32;
+    }
+
+    /// Read back the aggregate `header` carries for block `block_number`
+    pub fn from_header(header: &BlockHeader, block_number: u64, block_hash: [u8; 32]) -> Option<Self> {
+        if header.attestation_count == 0 {
+            return None;
+        }
+        Some(Self {
+            block_number,
+            block_hash,
+            signature: header.attestation_aggregate.clone(),
+            bitfield: header.attestation_bitmap.clone(),
+        })
     }
 }
 
@@ -293,16 +593,22 @@ impl Attestation {
 #[derive(Debug, Clone, Default)]
 pub struct AttestationPool {
     attestations: HashMap<u64, Vec<Attestation>>,
+    /// Sealed blocks' attestations in compact form, replacing the individual ones
+    aggregates: HashMap<u64, AggregateAttestation>,
     finalized_blocks: HashMap<u64, [u8; 32]>,
     finality_threshold: usize,
+    /// Attesting committee in bitfield order, with each member's BLS key
+    committee: Vec<(merklith_types::Address, BLSPublicKey)>,
 }
 
 impl AttestationPool {
     pub fn new() -> Self {
         Self {
             attestations: HashMap::new(),
+            aggregates: HashMap::new(),
             finalized_blocks: HashMap::new(),
             finality_threshold: 2,
+            committee: Vec::new(),
         }
     }
     
@@ -310,11 +616,114 @@ impl AttestationPool {
         self.finality_threshold = threshold;
         self
     }
+
+    /// Aggregate attestations over `committee`, given as each member's
+    /// address, BLS key and proof of possession of that key; a member's
+    /// position is its bit in the bitfield
+    pub fn with_committee(
+        mut self,
+        committee: Vec<(merklith_types::Address, BLSPublicKey, BLSSignature)>,
+    ) -> Result<Self, ConsensusError> {
+        for (address, public_key, proof) in committee {
+            self.register(address, public_key, &proof)?;
+        }
+        Ok(self)
+    }
+
+    /// Add `address` to the end of the committee once `proof` shows it holds
+    /// the secret key of `public_key`
+    pub fn register(
+        &mut self,
+        address: merklith_types::Address,
+        public_key: BLSPublicKey,
+        proof: &BLSSignature,
+    ) -> Result<(), ConsensusError> {
+        merklith_crypto::bls_verify(&public_key, &proof_of_possession_message(&public_key), proof)
+            .map_err(|_| ConsensusError::InvalidAttestation(format!("{} has no valid proof of possession", address)))?;
+        if self.committee.iter().any(|(member, _)| *member == address) {
+            return Err(ConsensusError::InvalidAttestation(format!("{} is already registered", address)));
+        }
+        self.committee.push((address, public_key));
+        Ok(())
+    }
+
+    pub fn committee(&self) -> &[(merklith_types::Address, BLSPublicKey)] {
+        &self.committee
+    }
+
+    /// Combine the committee's BLS signatures on `block_hash` into one
+    /// aggregate. Attestations from outside the committee or for another
+    /// hash are left out.
+    pub fn aggregate(&self, block_number: u64, block_hash: [u8; 32]) -> Result<AggregateAttestation, ConsensusError> {
+        let mut bitfield = vec![0u8; self.committee.len().div_ceil(8)];
+        let mut signatures = Vec::new();
+        for attestation in self.attestations.get(&block_number).into_iter().flatten() {
+            if attestation.block_hash != block_hash {
+                continue;
+            }
+            let Some(index) = self.committee.iter().position(|(address, _)| *address == attestation.attester) else {
+                continue;
+            };
+            let signature = BLSSignature::from_bytes(&attestation.signature)
+                .map_err(|e| ConsensusError::InvalidAttestation(format!("{}: {}", attestation.attester, e)))?;
+            bitfield[index / 8] |= 1 << (index % 8);
+            signatures.push(signature);
+        }
+
+        let signature = merklith_crypto::bls_aggregate_signatures(&signatures)
+            .map_err(|e| ConsensusError::InvalidAttestation(e.to_string()))?;
+        Ok(AggregateAttestation { block_number, block_hash, signature, bitfield })
+    }
+
+    /// Verify `aggregate` against the committee
+    pub fn verify_aggregate(&self, aggregate: &AggregateAttestation) -> Result<(), ConsensusError> {
+        let public_keys: Vec<BLSPublicKey> = self.committee.iter().map(|(_, key)| key.clone()).collect();
+        aggregate.verify(&public_keys)
+    }
+
+    /// Verify `aggregate` and keep it in place of its block's individual
+    /// attestations, returning the committee members it counts
+    pub fn add_aggregate(&mut self, aggregate: AggregateAttestation) -> Result<Vec<merklith_types::Address>, ConsensusError> {
+        self.verify_aggregate(&aggregate)?;
+        let signers = self.committee
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| aggregate.has_signed(*index))
+            .map(|(_, (address, _))| *address)
+            .collect();
+        self.attestations.remove(&aggregate.block_number);
+        self.aggregates.insert(aggregate.block_number, aggregate);
+        Ok(signers)
+    }
+
+    /// Whether `attestation` is signed with its attester's registered key
+    pub fn verify_signature(&self, attestation: &Attestation) -> bool {
+        let Some((_, public_key)) = self.committee.iter().find(|(address, _)| *address == attestation.attester) else {
+            return false;
+        };
+        BLSSignature::from_bytes(&attestation.signature)
+            .map_err(|_| merklith_crypto::CryptoError::InvalidSignature)
+            .and_then(|signature| merklith_crypto::bls_verify(public_key, &attestation.signing_message(), &signature))
+            .is_ok()
+    }
+
+    /// Compact attestations kept for `block_number`
+    pub fn get_aggregate(&self, block_number: u64) -> Option<&AggregateAttestation> {
+        self.aggregates.get(&block_number)
+    }
     
+    /// Add an attestation signed by a committee member with its registered
+    /// key. Attestations from outside the committee, with a bad signature,
+    /// repeated, or for a finalized or sealed block are refused.
     pub fn add_attestation(&mut self, attestation: Attestation) -> bool {
         let block_number = attestation.block_number;
         
-        if self.finalized_blocks.contains_key(&block_number) {
+        if self.finalized_blocks.contains_key(&block_number) || self.aggregates.contains_key(&block_number) {
+            return false;
+        }
+
+        if !self.verify_signature(&attestation) {
+            tracing::debug!("Refused attestation from {} with a bad signature", attestation.attester);
             return false;
         }
         
@@ -331,15 +740,21 @@ impl AttestationPool {
     }
     
     pub fn check_finality(&mut self, block_number: u64, block_hash: [u8; 32]) -> bool {
+        self.check_finality_with(block_number, block_hash, self.finality_threshold)
+    }
+    
+    /// Finalize `block_number` once it has `threshold` attestations
+    pub fn check_finality_with(&mut self, block_number: u64, block_hash: [u8; 32], threshold: usize) -> bool {
         if self.finalized_blocks.contains_key(&block_number) {
             return true;
         }
         
-        let count = self.attestations.get(&block_number)
-            .map(|v| v.len())
-            .unwrap_or(0);
+        let count = match self.aggregates.get(&block_number) {
+            Some(aggregate) if aggregate.block_hash != block_hash => 0,
+            _ => self.get_attestation_count(block_number),
+        };
         
-        if count >= self.finality_threshold {
+        if count >= threshold {
             for att in self.attestations.entry(block_number).or_default() {
                 att.status = AttestationStatus::Finalized;
             }
@@ -354,7 +769,11 @@ impl AttestationPool {
         self.finalized_blocks.contains_key(&block_number)
     }
     
+    /// Attestations for `block_number`, counting each signer of a sealed aggregate
     pub fn get_attestation_count(&self, block_number: u64) -> usize {
+        if let Some(aggregate) = self.aggregates.get(&block_number) {
+            return aggregate.signer_count();
+        }
         self.attestations.get(&block_number).map(|v| v.len()).unwrap_or(0)
     }
     
@@ -380,14 +799,39 @@ impl AttestationPool {
     
     pub fn prune_old_attestations(&mut self, current_block: u64, keep_blocks: u64) {
         self.attestations.retain(|&block_num, _| block_num + keep_blocks >= current_block);
+        self.aggregates.retain(|&block_num, _| block_num + keep_blocks >= current_block);
     }
 }
 
+/// Blocks per epoch, matching `BlockHeader::epoch`
+pub const DEFAULT_EPOCH_LENGTH: u64 = 1000;
+
+/// Validators active in an epoch, by default
+pub const DEFAULT_ACTIVE_SET_SIZE: usize = 100;
+
+/// Domain tag hashed with the block number into the committee selection seed
+pub const COMMITTEE_SEED_DOMAIN: &[u8] = b"merklith-committee-v1";
+
+/// An epoch's frozen active set, kept across restarts
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EpochSnapshot {
+    pub epoch: u64,
+    /// Active validators with their PoC scores at rotation, highest first
+    pub active: Vec<(merklith_types::Address, u64)>,
+}
+
 /// Validator set with PoC scoring
 #[derive(Debug, Clone)]
 pub struct ValidatorSet {
     validators: HashMap<merklith_types::Address, u64>,
     contribution_tracker: ContributionTracker,
+    /// Epoch of the last rotation, `None` until the first
+    epoch: Option<u64>,
+    /// Active validators for `epoch` with their PoC scores at rotation,
+    /// highest first
+    active: Vec<(merklith_types::Address, u64)>,
+    /// Jailed validators and the block at which each is released
+    jailed: HashMap<merklith_types::Address, u64>,
 }
 
 impl ValidatorSet {
@@ -395,6 +839,9 @@ impl ValidatorSet {
         Self {
             validators: HashMap::new(),
             contribution_tracker: ContributionTracker::new(),
+            epoch: None,
+            active: Vec::new(),
+            jailed: HashMap::new(),
         }
     }
 
@@ -410,8 +857,155 @@ impl ValidatorSet {
         self.validators.len()
     }
 
+    /// Freeze the active set for `epoch`: the `top_n` registered validators
+    /// by PoC score, then stake (ties by address). Registrations and scores
+    /// changing mid-epoch take effect at the next rotation.
+    pub fn rotate_for_epoch(&mut self, epoch: u64, top_n: usize) -> Vec<merklith_types::Address> {
+        let mut ranked: Vec<_> = self.validators.iter()
+            .map(|(addr, stake)| (*addr, self.contribution_tracker.get_score(addr).total(), *stake))
+            .collect();
+        ranked.sort_by(|a, b| b.1.cmp(&a.1).then(b.2.cmp(&a.2)).then(a.0.cmp(&b.0)));
+
+        self.epoch = Some(epoch);
+        self.active = ranked.into_iter().take(top_n).map(|(addr, score, _)| (addr, score)).collect();
+        self.active_validators()
+    }
+
+    /// Epoch of the last rotation
+    pub fn epoch(&self) -> Option<u64> {
+        self.epoch
+    }
+
+    /// The current epoch's frozen active set, None before the first rotation
+    pub fn epoch_snapshot(&self) -> Option<EpochSnapshot> {
+        self.epoch.map(|epoch| EpochSnapshot { epoch, active: self.active.clone() })
+    }
+
+    /// Resume an epoch frozen before a restart
+    pub fn restore_epoch(&mut self, snapshot: EpochSnapshot) {
+        self.epoch = Some(snapshot.epoch);
+        self.active = snapshot.active;
+    }
+
+    /// Validators frozen for the current epoch, empty before the first rotation
+    pub fn active_validators(&self) -> Vec<merklith_types::Address> {
+        self.active.iter().map(|(addr, _)| *addr).collect()
+    }
+
+    /// Whether `address` may propose and attest this epoch. Before the first
+    /// rotation every registered validator may.
+    pub fn is_active(&self, address: &merklith_types::Address) -> bool {
+        if self.is_jailed(address) {
+            return false;
+        }
+        if self.epoch.is_none() {
+            return self.is_validator(address);
+        }
+        self.active.iter().any(|(addr, _)| addr == address)
+    }
+
+    /// Exclude `address` from proposing and attesting until block `release_at`
+    pub fn jail(&mut self, address: merklith_types::Address, release_at: u64) {
+        self.jailed.insert(address, release_at);
+    }
+
+    /// Lift a jailing, returning whether `address` was jailed
+    pub fn unjail(&mut self, address: &merklith_types::Address) -> bool {
+        self.jailed.remove(address).is_some()
+    }
+
+    pub fn is_jailed(&self, address: &merklith_types::Address) -> bool {
+        self.jailed.contains_key(address)
+    }
+
+    /// Block at which a jailed validator is released
+    pub fn jailed_until(&self, address: &merklith_types::Address) -> Option<u64> {
+        self.jailed.get(address).copied()
+    }
+
+    /// `size` members of the epoch's active set for `block_number`, drawn by
+    /// [`committee::select_committee`] weighted by PoC score and stake, with
+    /// a fresh seed each block
+    pub fn select_committee(&self, block_number: u64, size: usize) -> Vec<merklith_types::Address> {
+        let mut members = if self.epoch.is_some() {
+            self.active_validators()
+        } else {
+            let mut all: Vec<_> = self.validators.keys().copied().collect();
+            all.sort();
+            all
+        };
+        members.retain(|addr| !self.is_jailed(addr));
+        if members.is_empty() || size == 0 {
+            return Vec::new();
+        }
+
+        let max_stake = members.iter().map(|addr| self.validators[addr]).max().unwrap_or(0).max(1);
+        let candidates: Vec<_> = members
+            .iter()
+            .map(|addr| {
+                let score = self.contribution_tracker.get_score(addr);
+                let contribution = poc::ContributionMetrics {
+                    tx_count: score.relayed_txs,
+                    blocks_proposed: score.block_production,
+                    attestations: score.attestations,
+                    ..Default::default()
+                };
+                poc::ValidatorInfo::new(*addr, merklith_types::U256::from(self.validators[addr]))
+                    .with_contribution(contribution)
+            })
+            .collect();
+        // Registration already vetted stakes; weigh them against the largest
+        let poc_config = poc::PocConfig {
+            min_stake: merklith_types::U256::ZERO,
+            max_effective_stake: merklith_types::U256::from(max_stake),
+            ..Default::default()
+        };
+        let committee_config = committee::CommitteeConfig {
+            target_size: size,
+            max_size: size,
+            min_size: 1,
+            min_poc_score: 0.0,
+            ..Default::default()
+        };
+        let mut seed_input = COMMITTEE_SEED_DOMAIN.to_vec();
+        seed_input.extend_from_slice(&block_number.to_le_bytes());
+        let seed = *blake3::hash(&seed_input).as_bytes();
+        let epoch = self.epoch.unwrap_or(0);
+        match committee::select_committee(epoch, &candidates, seed, &poc_config, &committee_config) {
+            Ok(committee) => committee.members.iter().map(|member| member.address).collect(),
+            Err(e) => {
+                tracing::warn!("Committee selection for block {} failed: {}", block_number, e);
+                Vec::new()
+            }
+        }
+    }
+
+    /// Proposer for `block_number` from the frozen set, weighted by the
+    /// scores it was frozen with
+    fn select_active_proposer(&self, block_number: u64) -> Option<merklith_types::Address> {
+        let eligible: Vec<_> = self.active.iter().filter(|(addr, _)| !self.is_jailed(addr)).collect();
+        if eligible.is_empty() {
+            return None;
+        }
+        let total: u64 = eligible.iter().map(|(_, score)| score).sum();
+        if total == 0 {
+            return Some(eligible[(block_number % eligible.len() as u64) as usize].0);
+        }
+        let target = block_number % total;
+        let mut cumulative = 0u64;
+        eligible.into_iter()
+            .find(|(_, score)| {
+                cumulative += score;
+                cumulative > target
+            })
+            .map(|(addr, _)| *addr)
+    }
+
     pub fn select_proposer(&self, block_number: u64) -> Option<merklith_types::Address> {
-        let validators: Vec<_> = self.validators.keys().cloned().collect();
+        if self.epoch.is_some() {
+            return self.select_active_proposer(block_number);
+        }
+        let validators: Vec<_> = self.validators.keys().filter(|addr| !self.is_jailed(addr)).cloned().collect();
         if validators.is_empty() {
             return None;
         }
@@ -420,11 +1014,17 @@ impl ValidatorSet {
     }
     
     pub fn select_proposer_poc(&self, block_number: u64) -> Option<merklith_types::Address> {
-        if self.validators.is_empty() {
+        if self.epoch.is_some() {
+            return self.select_active_proposer(block_number);
+        }
+        let eligible: Vec<_> = self.validators.keys().filter(|addr| !self.is_jailed(addr)).collect();
+        if eligible.is_empty() {
             return None;
         }
         
-        let total_contrib = self.contribution_tracker.total_contributions();
+        let total_contrib: u64 = eligible.iter()
+            .map(|addr| self.contribution_tracker.get_score(addr).total())
+            .sum();
         
         if total_contrib == 0 {
             return self.select_proposer(block_number);
@@ -433,15 +1033,15 @@ impl ValidatorSet {
         let mut cumulative = 0u64;
         let target = block_number % total_contrib.max(1);
         
-        for (addr, _) in &self.validators {
+        for addr in &eligible {
             let score = self.contribution_tracker.get_score(addr).total();
             cumulative += score;
             if cumulative > target {
-                return Some(*addr);
+                return Some(**addr);
             }
         }
         
-        self.validators.keys().next().copied()
+        eligible.first().map(|addr| **addr)
     }
     
     pub fn contribution_tracker(&self) -> &ContributionTracker {
@@ -463,12 +1063,65 @@ impl Default for ValidatorSet {
     }
 }
 
+/// When offline validators are jailed and released
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JailConfig {
+    /// Recent slots kept per validator
+    pub window: usize,
+    /// Missed slots within the window that jail a validator
+    pub max_missed: usize,
+    /// Blocks a jailed validator sits out before it is released
+    pub cooldown_blocks: u64,
+}
+
+impl Default for JailConfig {
+    fn default() -> Self {
+        Self {
+            window: 100,
+            max_missed: 50,
+            cooldown_blocks: 1000,
+        }
+    }
+}
+
+/// A change in a validator's jail status, matching the audit log's
+/// `ValidatorJailed` and `ValidatorUnjailed` events
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JailEvent {
+    Jailed {
+        validator: merklith_types::Address,
+        block_number: u64,
+        missed_slots: usize,
+        release_at: u64,
+    },
+    Unjailed {
+        validator: merklith_types::Address,
+        block_number: u64,
+    },
+}
+
+/// Receives every jail status change, e.g. to record it in the audit log
+pub type JailHook = Box<dyn Fn(&JailEvent) + Send + Sync>;
+
 /// PoC consensus engine
 pub struct ConsensusEngine {
     validator_set: ValidatorSet,
     block_time: u64,
     min_contribution_score: u64,
     attestation_pool: AttestationPool,
+    epoch_length: u64,
+    active_set_size: usize,
+    jail_config: JailConfig,
+    /// Recent slots per validator, `true` where it took part
+    liveness: HashMap<merklith_types::Address, VecDeque<bool>>,
+    jail_hook: Option<JailHook>,
+    /// Chain parameters, with upgrades applied per height; the fixed
+    /// finality threshold applies without them
+    chain_config: Option<ChainConfig>,
+    /// Where the frozen active set is kept across restarts
+    epoch_file: Option<std::path::PathBuf>,
+    /// Keys proposers sign their block headers with
+    proposer_keys: HashMap<merklith_types::Address, Ed25519PublicKey>,
 }
 
 impl ConsensusEngine {
@@ -478,31 +1131,179 @@ impl ConsensusEngine {
             block_time,
             min_contribution_score: 10,
             attestation_pool: AttestationPool::new(),
+            epoch_length: DEFAULT_EPOCH_LENGTH,
+            active_set_size: DEFAULT_ACTIVE_SET_SIZE,
+            jail_config: JailConfig::default(),
+            liveness: HashMap::new(),
+            jail_hook: None,
+            chain_config: None,
+            epoch_file: None,
+            proposer_keys: HashMap::new(),
         }
     }
     
+    pub fn with_jailing(mut self, config: JailConfig) -> Self {
+        self.jail_config = config;
+        self
+    }
+
+    /// Report jailings and releases to `hook`
+    pub fn with_jail_hook(mut self, hook: impl Fn(&JailEvent) + Send + Sync + 'static) -> Self {
+        self.jail_hook = Some(Box::new(hook));
+        self
+    }
+    
+    /// Rotate to the top `active_set_size` validators every `epoch_length` blocks
+    pub fn with_epochs(mut self, epoch_length: u64, active_set_size: usize) -> Self {
+        self.epoch_length = epoch_length.max(1);
+        self.active_set_size = active_set_size;
+        self
+    }
+
+    pub fn epoch_length(&self) -> u64 {
+        self.epoch_length
+    }
+
+    /// Keep the frozen active set in `path` at every rotation, resuming the
+    /// epoch already stored there
+    pub fn with_epoch_file(mut self, path: impl Into<std::path::PathBuf>) -> Self {
+        let path = path.into();
+        match std::fs::read_to_string(&path) {
+            Ok(json) => match serde_json::from_str::<EpochSnapshot>(&json) {
+                Ok(snapshot) => {
+                    tracing::info!("Resuming epoch {} with {} active validators", snapshot.epoch, snapshot.active.len());
+                    self.validator_set.restore_epoch(snapshot);
+                }
+                Err(e) => tracing::warn!("Ignoring invalid epoch file {}: {}", path.display(), e),
+            },
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => tracing::warn!("Failed to read epoch file {}: {}", path.display(), e),
+        }
+        self.epoch_file = Some(path);
+        self
+    }
+
+    fn save_epoch(&self) -> Result<(), String> {
+        let (Some(path), Some(snapshot)) = (&self.epoch_file, self.validator_set.epoch_snapshot()) else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        let json = serde_json::to_string_pretty(&snapshot).map_err(|e| e.to_string())?;
+        // Write then rename, so a crash never leaves a torn file
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, json).map_err(|e| e.to_string())?;
+        std::fs::rename(&tmp, path).map_err(|e| e.to_string())
+    }
+
+    /// Rotate the validator set if `block_number` opens a new epoch,
+    /// returning the new epoch
+    pub fn maybe_rotate(&mut self, block_number: u64) -> Option<u64> {
+        let epoch = block_number / self.epoch_length;
+        if block_number % self.epoch_length != 0 || self.validator_set.epoch() >= Some(epoch) {
+            return None;
+        }
+        let active = self.validator_set.rotate_for_epoch(epoch, self.active_set_size);
+        tracing::info!("Epoch {} begins at block {} with {} active validators", epoch, block_number, active.len());
+        if let Err(e) = self.save_epoch() {
+            tracing::warn!("Failed to save epoch {}: {}", epoch, e);
+        }
+        Some(epoch)
+    }
+
+    /// Accept blocks from `address` signed with `public_key`
+    pub fn with_proposer_key(mut self, address: merklith_types::Address, public_key: Ed25519PublicKey) -> Self {
+        self.proposer_keys.insert(address, public_key);
+        self
+    }
+
     pub fn with_min_contribution(mut self, min_score: u64) -> Self {
         self.min_contribution_score = min_score;
         self
     }
     
+    pub fn with_contribution_weights(mut self, weights: ContributionWeights) -> Self {
+        let tracker = std::mem::take(self.validator_set.contribution_tracker_mut());
+        *self.validator_set.contribution_tracker_mut() = tracker.with_weights(weights);
+        self
+    }
+    
     pub fn with_finality_threshold(mut self, threshold: usize) -> Self {
-        self.attestation_pool = AttestationPool::new().with_threshold(threshold);
+        self.attestation_pool = std::mem::take(&mut self.attestation_pool).with_threshold(threshold);
+        self
+    }
+
+    /// Follow `config`'s consensus parameters, including the upgrades
+    /// scheduled in it
+    pub fn with_chain_config(mut self, config: ChainConfig) -> Self {
+        self.chain_config = Some(config);
         self
     }
 
+    /// Attestations that finalize `block_number`: the threshold share of its
+    /// committee under the parameters active at that height
+    pub fn finality_threshold(&self, block_number: u64) -> usize {
+        match &self.chain_config {
+            Some(config) => {
+                let config = config.at_height(block_number);
+                let committee = self.committee(block_number, config.committee_size as usize);
+                (config.attestation_threshold(committee.len() as u32) as usize).max(1)
+            }
+            None => self.attestation_pool.finality_threshold,
+        }
+    }
+
+    /// Attest with `committee`, each member giving its address, BLS key and
+    /// proof of possession of that key
+    pub fn with_attestation_committee(
+        mut self,
+        committee: Vec<(merklith_types::Address, BLSPublicKey, BLSSignature)>,
+    ) -> Result<Self, ConsensusError> {
+        self.attestation_pool = std::mem::take(&mut self.attestation_pool).with_committee(committee)?;
+        Ok(self)
+    }
+
+    /// Add an attester to the committee once `proof` shows it holds the
+    /// secret key of `public_key`
+    pub fn register_attester(
+        &mut self,
+        address: merklith_types::Address,
+        public_key: BLSPublicKey,
+        proof: &BLSSignature,
+    ) -> Result<(), ConsensusError> {
+        self.attestation_pool.register(address, public_key, proof)
+    }
+
+    /// Check that `block` comes from the proposer scheduled for its slot,
+    /// signed over its header with that proposer's key
     pub fn validate_block(&self, block: &merklith_types::Block) -> Result<(), ConsensusError> {
-        if !self.validator_set.is_validator(&block.header.proposer) {
+        let header = &block.header;
+        if !self.validator_set.is_active(&header.proposer) {
             return Err(ConsensusError::NotValidator);
         }
-
-        Ok(())
+        if self.next_proposer(header.number) != Some(header.proposer) {
+            return Err(ConsensusError::InvalidBlock(format!(
+                "{} is not the proposer scheduled for block #{}",
+                header.proposer, header.number
+            )));
+        }
+        let public_key = self.proposer_keys.get(&header.proposer).ok_or_else(|| {
+            ConsensusError::InvalidBlock(format!("No signing key known for proposer {}", header.proposer))
+        })?;
+        merklith_crypto::ed25519_verify(public_key, header.signing_hash().as_bytes(), &header.proposer_signature)
+            .map_err(|_| ConsensusError::InvalidBlock("Bad proposer signature".to_string()))
     }
 
     pub fn next_proposer(&self, block_number: u64) -> Option<merklith_types::Address> {
         self.validator_set.select_proposer_poc(block_number)
     }
 
+    /// Attesting committee of `size` for `block_number`
+    pub fn committee(&self, block_number: u64, size: usize) -> Vec<merklith_types::Address> {
+        self.validator_set.select_committee(block_number, size)
+    }
+
     pub fn block_time(&self) -> u64 {
         self.block_time
     }
@@ -510,11 +1311,114 @@ impl ConsensusEngine {
     pub fn record_block_production(&mut self, proposer: merklith_types::Address, block_number: u64) {
         self.validator_set.contribution_tracker_mut()
             .record_block_production(proposer, block_number);
+        self.record_liveness(proposer, block_number, true);
     }
     
     pub fn record_attestation(&mut self, attester: merklith_types::Address, block_number: u64) {
         self.validator_set.contribution_tracker_mut()
             .record_attestation(attester, block_number);
+        self.record_liveness(attester, block_number, true);
+    }
+
+    /// Account for block `block_number` as it landed in the chain: the
+    /// scheduled proposer missed its slot unless it made the block, and
+    /// committee members absent from the parent attestation the block
+    /// carries missed theirs. Call before crediting the block elsewhere,
+    /// so the schedule is the one the block was made under.
+    pub fn observe_block(
+        &mut self,
+        block_number: u64,
+        proposer: merklith_types::Address,
+        parent_attestation: Option<&AggregateAttestation>,
+    ) {
+        if let Some(scheduled) = self.next_proposer(block_number).filter(|scheduled| *scheduled != proposer) {
+            self.record_missed_slot(scheduled, block_number);
+        }
+        if let Some(aggregate) = parent_attestation {
+            let absent: Vec<_> = self.attestation_pool.committee()
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| !aggregate.has_signed(*index))
+                .map(|(_, (address, _))| *address)
+                .collect();
+            for member in absent {
+                self.record_missed_slot(member, block_number);
+            }
+        }
+        self.record_block_production(proposer, block_number);
+    }
+
+    /// Record that `validator` missed its proposal or attestation slot at
+    /// `block_number`, jailing it once it misses too many in the window
+    pub fn record_missed_slot(&mut self, validator: merklith_types::Address, block_number: u64) {
+        self.record_liveness(validator, block_number, false);
+    }
+
+    fn record_liveness(&mut self, validator: merklith_types::Address, block_number: u64, online: bool) {
+        self.release_jailed(block_number);
+        if self.validator_set.is_jailed(&validator) {
+            return;
+        }
+
+        let window = self.jail_config.window.max(1);
+        let slots = self.liveness.entry(validator).or_default();
+        slots.push_back(online);
+        while slots.len() > window {
+            slots.pop_front();
+        }
+        let missed_slots = slots.iter().filter(|online| !**online).count();
+        if missed_slots < self.jail_config.max_missed.max(1) {
+            return;
+        }
+
+        self.liveness.remove(&validator);
+        let release_at = block_number + self.jail_config.cooldown_blocks;
+        self.validator_set.jail(validator, release_at);
+        tracing::warn!("Validator {} jailed until block {} after missing {} slots", validator, release_at, missed_slots);
+        self.emit(JailEvent::Jailed { validator, block_number, missed_slots, release_at });
+    }
+
+    /// Release validators whose cooldown has passed by `block_number`
+    pub fn release_jailed(&mut self, block_number: u64) -> Vec<merklith_types::Address> {
+        let mut released: Vec<_> = self.validator_set.jailed.iter()
+            .filter(|(_, release_at)| **release_at <= block_number)
+            .map(|(addr, _)| *addr)
+            .collect();
+        released.sort();
+        for validator in &released {
+            self.unjail(*validator, block_number);
+        }
+        released
+    }
+
+    /// Release a jailed validator early on a fresh attestation: one of a
+    /// block at or after its jailing, signed with its registered BLS key.
+    /// The caller checks the attested hash against its chain. Returns
+    /// whether the attester was released.
+    pub fn prove_liveness(&mut self, attestation: &Attestation) -> bool {
+        let Some(release_at) = self.validator_set.jailed_until(&attestation.attester) else {
+            return false;
+        };
+        let jailed_at = release_at.saturating_sub(self.jail_config.cooldown_blocks);
+        if attestation.block_number < jailed_at || !self.attestation_pool.verify_signature(attestation) {
+            return false;
+        }
+        self.unjail(attestation.attester, attestation.block_number)
+    }
+
+    fn unjail(&mut self, validator: merklith_types::Address, block_number: u64) -> bool {
+        if !self.validator_set.unjail(&validator) {
+            return false;
+        }
+        tracing::info!("Validator {} unjailed at block {}", validator, block_number);
+        self.emit(JailEvent::Unjailed { validator, block_number });
+        true
+    }
+
+    fn emit(&self, event: JailEvent) {
+        if let Some(hook) = &self.jail_hook {
+            hook(&event);
+        }
     }
     
     pub fn add_attestation(&mut self, attestation: Attestation) -> bool {
@@ -527,8 +1431,33 @@ impl ConsensusEngine {
         result
     }
     
+    /// Aggregate the attestations collected for a block, keeping the compact
+    /// form in their place, e.g. to carry in the next block
+    pub fn seal_attestations(&mut self, block_number: u64, block_hash: [u8; 32]) -> Result<AggregateAttestation, ConsensusError> {
+        let aggregate = self.attestation_pool.aggregate(block_number, block_hash)?;
+        self.attestation_pool.add_aggregate(aggregate.clone())?;
+        self.check_finality(block_number, block_hash);
+        Ok(aggregate)
+    }
+
+    /// Verify an aggregate carried by an imported block against the committee
+    pub fn verify_aggregate(&self, aggregate: &AggregateAttestation) -> Result<(), ConsensusError> {
+        self.attestation_pool.verify_aggregate(aggregate)
+    }
+
+    /// Accept an imported block's aggregate, crediting each signer
+    pub fn import_aggregate(&mut self, aggregate: AggregateAttestation) -> Result<(), ConsensusError> {
+        let (block_number, block_hash) = (aggregate.block_number, aggregate.block_hash);
+        for signer in self.attestation_pool.add_aggregate(aggregate)? {
+            self.record_attestation(signer, block_number);
+        }
+        self.check_finality(block_number, block_hash);
+        Ok(())
+    }
+    
     pub fn check_finality(&mut self, block_number: u64, block_hash: [u8; 32]) -> bool {
-        self.attestation_pool.check_finality(block_number, block_hash)
+        let threshold = self.finality_threshold(block_number);
+        self.attestation_pool.check_finality_with(block_number, block_hash, threshold)
     }
     
     pub fn is_finalized(&self, block_number: u64) -> bool {
@@ -559,6 +1488,27 @@ impl ConsensusEngine {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use merklith_crypto::BLSKeypair;
+
+    /// Committee member `i`: its address and BLS key
+    fn attester(i: u8) -> (merklith_types::Address, BLSKeypair) {
+        (merklith_types::Address::from_bytes([i; 20]), BLSKeypair::from_bytes(&[i; 32]).unwrap())
+    }
+
+    /// `attester(i)` as registered, with its proof of possession
+    fn registration(i: u8) -> (merklith_types::Address, BLSPublicKey, BLSSignature) {
+        let (address, key) = attester(i);
+        let proof = key.sign(&proof_of_possession_message(&key.public_key()));
+        (address, key.public_key(), proof)
+    }
+
+    /// `attester(i)`'s signed attestation of `block_hash`
+    fn signed_attestation(i: u8, block_number: u64, block_hash: [u8; 32]) -> Attestation {
+        let (address, key) = attester(i);
+        let mut attestation = Attestation::new(block_number, block_hash, address, Vec::new());
+        attestation.signature = key.sign(&attestation.signing_message()).as_bytes().to_vec();
+        attestation
+    }
 
     #[test]
     fn test_validator_set() {
@@ -570,6 +1520,294 @@ mod tests {
         assert_eq!(set.len(), 1);
     }
 
+    #[test]
+    fn test_signing_domains_separate() {
+        use merklith_crypto::{ed25519_verify, Keypair};
+        use merklith_types::{Address, Transaction, U256};
+
+        let keypair = Keypair::from_seed(&[3u8; 32]);
+        let tx = Transaction::new(1, 0, Some(Address::ZERO), U256::ONE, 21_000, U256::ONE, U256::ZERO);
+        let attestation = Attestation::new(7, *tx.signing_hash().as_bytes(), keypair.address(), Vec::new());
+
+        let tx_message = tx.signing_hash();
+        let attestation_message = attestation.signing_message();
+        assert!(attestation_message.starts_with(ATTESTATION_SIGNING_DOMAIN));
+
+        let (tx_signature, public_key) = keypair.sign_transaction(&tx);
+        let attestation_signature = keypair.sign(&attestation_message);
+        assert!(ed25519_verify(&public_key, tx_message.as_bytes(), &tx_signature).is_ok());
+        assert!(ed25519_verify(&public_key, &attestation_message, &attestation_signature).is_ok());
+
+        assert!(ed25519_verify(&public_key, &attestation_message, &tx_signature).is_err());
+        assert!(ed25519_verify(&public_key, tx_message.as_bytes(), &attestation_signature).is_err());
+    }
+
+    #[test]
+    fn test_epoch_rotation() {
+        let mut set = ValidatorSet::new();
+        let addr1 = merklith_types::Address::from_bytes([1u8; 20]);
+        let addr2 = merklith_types::Address::from_bytes([2u8; 20]);
+        let addr3 = merklith_types::Address::from_bytes([3u8; 20]);
+        set.add_validator(addr1, 1000);
+        set.add_validator(addr2, 1000);
+        set.add_validator(addr3, 500);
+        set.contribution_tracker_mut().record_block_production(addr1, 0);
+        set.contribution_tracker_mut().record_attestation(addr2, 0);
+
+        let mut engine = ConsensusEngine::new(set, 2).with_epochs(10, 2);
+        assert_eq!(engine.maybe_rotate(0), Some(0));
+        assert_eq!(engine.validator_set().active_validators(), vec![addr1, addr2]);
+
+        // addr3 overtakes both mid-epoch, but the frozen set stands
+        for block in 3..6 {
+            engine.record_block_production(addr3, block);
+        }
+        assert_eq!(engine.maybe_rotate(5), None);
+        assert!(!engine.validator_set().is_active(&addr3));
+        for block in 5..10 {
+            assert_ne!(engine.next_proposer(block), Some(addr3));
+            assert!(!engine.committee(block, 2).contains(&addr3));
+        }
+
+        // The next boundary picks it up
+        assert_eq!(engine.maybe_rotate(10), Some(1));
+        assert_eq!(engine.validator_set().active_validators(), vec![addr3, addr1]);
+        assert!(engine.validator_set().is_active(&addr3));
+        assert!(!engine.validator_set().is_active(&addr2));
+        assert_eq!(engine.maybe_rotate(10), None);
+    }
+
+    #[test]
+    fn test_epoch_survives_restart() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let epoch_file = temp_dir.path().join("consensus").join("epoch.json");
+        let addr1 = merklith_types::Address::from_bytes([1u8; 20]);
+        let addr2 = merklith_types::Address::from_bytes([2u8; 20]);
+        let validators = || {
+            let mut set = ValidatorSet::new();
+            set.add_validator(addr1, 1000);
+            set.add_validator(addr2, 1000);
+            set
+        };
+
+        let mut engine = ConsensusEngine::new(validators(), 2).with_epochs(10, 1).with_epoch_file(&epoch_file);
+        engine.record_block_production(addr2, 1);
+        assert_eq!(engine.maybe_rotate(10), Some(1));
+        assert_eq!(engine.validator_set().active_validators(), vec![addr2]);
+
+        // Scores are gone after a restart, but the frozen set is not
+        let mut restarted = ConsensusEngine::new(validators(), 2).with_epochs(10, 1).with_epoch_file(&epoch_file);
+        assert_eq!(restarted.validator_set().epoch_snapshot(), engine.validator_set().epoch_snapshot());
+        assert!(!restarted.validator_set().is_active(&addr1));
+        assert_eq!(restarted.maybe_rotate(10), None);
+    }
+
+    #[test]
+    fn test_offline_validator_jailed() {
+        let mut set = ValidatorSet::new();
+        let online = merklith_types::Address::from_bytes([1u8; 20]);
+        let offline = merklith_types::Address::from_bytes([2u8; 20]);
+        set.add_validator(online, 1000);
+        set.add_validator(offline, 1000);
+
+        let events = std::sync::Arc::new(parking_lot::Mutex::new(Vec::new()));
+        let recorded = events.clone();
+        let mut engine = ConsensusEngine::new(set, 2)
+            .with_jailing(JailConfig { window: 10, max_missed: 3, cooldown_blocks: 20 })
+            .with_jail_hook(move |event| recorded.lock().push(event.clone()))
+            .with_attestation_committee(vec![registration(1), registration(2)])
+            .unwrap();
+
+        for block in 1..=3 {
+            assert!(!engine.validator_set().is_jailed(&offline));
+            engine.record_block_production(online, block);
+            engine.record_missed_slot(offline, block);
+        }
+        assert!(engine.validator_set().is_jailed(&offline));
+        assert_eq!(
+            *events.lock(),
+            vec![JailEvent::Jailed { validator: offline, block_number: 3, missed_slots: 3, release_at: 23 }]
+        );
+        for block in 4..23 {
+            assert_eq!(engine.next_proposer(block), Some(online));
+            assert_eq!(engine.committee(block, 2), vec![online]);
+            engine.record_block_production(online, block);
+        }
+
+        // Released once the cooldown has passed
+        engine.record_block_production(online, 23);
+        assert!(!engine.validator_set().is_jailed(&offline));
+        assert!(engine.validator_set().is_active(&offline));
+        assert_eq!(events.lock()[1], JailEvent::Unjailed { validator: offline, block_number: 23 });
+
+        // Or early, on a fresh signed attestation
+        for block in 24..27 {
+            engine.record_missed_slot(offline, block);
+        }
+        assert!(engine.validator_set().is_jailed(&offline));
+        let mut unsigned = signed_attestation(2, 27, [27; 32]);
+        unsigned.signature = Vec::new();
+        assert!(!engine.prove_liveness(&unsigned));
+        assert!(!engine.prove_liveness(&signed_attestation(2, 25, [25; 32])));
+        assert!(engine.prove_liveness(&signed_attestation(2, 27, [27; 32])));
+        assert!(!engine.validator_set().is_jailed(&offline));
+        assert!(!engine.prove_liveness(&signed_attestation(2, 28, [28; 32])));
+    }
+
+    #[test]
+    fn test_validate_block_checks_proposer() {
+        let keys: Vec<_> = (1..=2u8).map(|i| merklith_crypto::Keypair::from_seed(&[i; 32])).collect();
+        let mut set = ValidatorSet::new();
+        for key in &keys {
+            set.add_validator(key.address(), 1000);
+        }
+        let engine = ConsensusEngine::new(set, 2).with_proposer_key(keys[0].address(), keys[0].public_key());
+        let block_by = |key: &merklith_crypto::Keypair| {
+            let mut header = merklith_types::BlockHeader::new(merklith_types::Hash::ZERO, 1, 1, 30_000_000, key.address());
+            header.proposer_signature = key.sign(header.signing_hash().as_bytes());
+            merklith_types::Block::new(header, Vec::new())
+        };
+
+        let scheduled = engine.next_proposer(1).unwrap();
+        let (on_turn, off_turn) = if scheduled == keys[0].address() { (&keys[0], &keys[1]) } else { (&keys[1], &keys[0]) };
+        assert!(engine.validate_block(&block_by(off_turn)).is_err());
+
+        let mut forged = block_by(on_turn);
+        forged.header.timestamp += 1;
+        assert!(engine.validate_block(&forged).is_err());
+        let mut unsigned = block_by(on_turn);
+        unsigned.header.proposer_signature = merklith_types::Ed25519Signature::default();
+        assert!(engine.validate_block(&unsigned).is_err());
+
+        // Only a proposer with a known key gets through
+        assert_eq!(engine.validate_block(&block_by(on_turn)).is_ok(), on_turn.address() == keys[0].address());
+        let engine = engine.with_proposer_key(keys[1].address(), keys[1].public_key());
+        assert!(engine.validate_block(&block_by(on_turn)).is_ok());
+    }
+
+    #[test]
+    fn test_missed_slots_follow_chain() {
+        let mut set = ValidatorSet::new();
+        for i in 1..=2u8 {
+            set.add_validator(merklith_types::Address::from_bytes([i; 20]), 1000);
+        }
+        let mut engine = ConsensusEngine::new(set, 2)
+            .with_jailing(JailConfig { window: 10, max_missed: 3, cooldown_blocks: 20 })
+            .with_attestation_committee(vec![registration(1), registration(2)])
+            .unwrap();
+        let (online, _) = attester(1);
+        let (offline, _) = attester(2);
+
+        // Blocks by the online validator, carrying only its attestations
+        let mut block = 1;
+        while !engine.validator_set().is_jailed(&offline) {
+            assert!(block <= 6, "still not jailed at block {}", block);
+            let parent = [block as u8 - 1; 32];
+            let mut pool = AttestationPool::new().with_committee(vec![registration(1), registration(2)]).unwrap();
+            assert!(pool.add_attestation(signed_attestation(1, block - 1, parent)));
+            let aggregate = pool.aggregate(block - 1, parent).unwrap();
+            engine.observe_block(block, online, Some(&aggregate));
+            assert!(!engine.validator_set().is_jailed(&online));
+            block += 1;
+        }
+        // Every block counts its missed attestation, and some its missed proposal
+        assert!(block <= 4);
+    }
+
+    #[test]
+    fn test_finality_threshold_follows_upgrades() {
+        use merklith_types::chain_config::upgrades;
+
+        let mut set = ValidatorSet::new();
+        for i in 1..=10u8 {
+            set.add_validator(merklith_types::Address::from_bytes([i; 20]), 1000);
+        }
+        let mut config = ChainConfig::mainnet().with_upgrade(upgrades::SUPERMAJORITY_FINALITY, 50);
+        config.committee_size = 10;
+        let mut engine = ConsensusEngine::new(set, 2)
+            .with_chain_config(config)
+            .with_attestation_committee((1..=10).map(registration).collect())
+            .unwrap();
+
+        assert_eq!(engine.finality_threshold(49), 7);
+        assert_eq!(engine.finality_threshold(50), 8);
+
+        // Seven of ten finalize before the upgrade but not after it
+        for block_number in [49, 50] {
+            for i in 1..=7u8 {
+                assert!(engine.add_attestation(signed_attestation(i, block_number, [block_number as u8; 32])));
+            }
+        }
+        assert!(engine.check_finality(49, [49; 32]));
+        assert!(!engine.check_finality(50, [50; 32]));
+    }
+
+    #[test]
+    fn test_aggregate_attestation() {
+        let block_hash = [9u8; 32];
+        let committee: Vec<_> = (1..=10).map(registration).collect();
+        let mut pool = AttestationPool::new().with_committee(committee).unwrap();
+        for i in 1..=10 {
+            assert!(pool.add_attestation(signed_attestation(i, 4, block_hash)));
+        }
+
+        let aggregate = pool.aggregate(4, block_hash).unwrap();
+        assert_eq!(aggregate.signer_count(), 10);
+        assert_eq!(aggregate.bitfield, vec![0xff, 0x03]);
+        pool.verify_aggregate(&aggregate).unwrap();
+
+        // Carried in the header as one signature and a bitfield
+        let mut header = BlockHeader::default();
+        aggregate.apply_to(&mut header);
+        assert_eq!(header.attestation_count, 10);
+        let restored = AggregateAttestation::from_header(&header, 4, block_hash).unwrap();
+        pool.verify_aggregate(&restored).unwrap();
+
+        // Claiming a member did not sign, or that an outsider did, fails
+        let mut dropped = aggregate.clone();
+        dropped.bitfield[0] &= !0x01;
+        assert!(pool.verify_aggregate(&dropped).is_err());
+        let mut padded = aggregate.clone();
+        padded.bitfield[1] |= 0x04;
+        assert!(pool.verify_aggregate(&padded).is_err());
+
+        // Once kept, the aggregate stands in for the individual signatures
+        assert!(pool.add_aggregate(dropped).is_err());
+        assert_eq!(pool.add_aggregate(aggregate.clone()).unwrap().len(), 10);
+        assert!(pool.get_attestations(4).is_empty());
+        assert_eq!(pool.get_attestation_count(4), 10);
+        assert_eq!(pool.get_aggregate(4), Some(&aggregate));
+        assert!(!pool.add_attestation(signed_attestation(1, 4, block_hash)));
+    }
+
+    #[test]
+    fn test_attestations_must_be_signed_by_registered_keys() {
+        let mut pool = AttestationPool::new().with_committee(vec![registration(1)]).unwrap();
+
+        // A key is only registered with a proof of possession by its holder
+        let (address, key) = attester(2);
+        let (_, _, borrowed_proof) = registration(3);
+        assert!(pool.register(address, key.public_key(), &borrowed_proof).is_err());
+        let (_, public_key, proof) = registration(2);
+        pool.register(address, public_key, &proof).unwrap();
+        assert!(pool.register(address, key.public_key(), &proof).is_err());
+        assert_eq!(pool.committee().len(), 2);
+
+        // Unsigned, forged or outside attestations are refused
+        let block_hash = [7u8; 32];
+        let (member, _) = attester(1);
+        assert!(!pool.add_attestation(Attestation::new(1, block_hash, member, vec![1, 2, 3])));
+        let mut forged = signed_attestation(2, 1, block_hash);
+        forged.attester = member;
+        assert!(!pool.add_attestation(forged));
+        assert!(!pool.add_attestation(signed_attestation(3, 1, block_hash)));
+        let mut replayed = signed_attestation(1, 1, [8u8; 32]);
+        replayed.block_hash = block_hash;
+        assert!(!pool.add_attestation(replayed));
+        assert!(pool.add_attestation(signed_attestation(1, 1, block_hash)));
+        assert_eq!(pool.get_attestation_count(1), 1);
+    }
+
     #[test]
     fn test_select_proposer() {
         let mut set = ValidatorSet::new();
@@ -612,6 +1850,127 @@ mod tests {
         assert_eq!(score.total(), 210);
     }
     
+    #[test]
+    fn test_data_availability_contribution() {
+        let mut tracker = ContributionTracker::new();
+        let addr = merklith_types::Address::from_bytes([1u8; 20]);
+        
+        tracker.record_data_availability(addr, 5, 0);
+        assert_eq!(tracker.get_score(&addr).total(), 0);
+        
+        tracker.record_data_availability(addr, 5, 3000);
+        tracker.record_data_availability(addr, 6, 1024);
+        
+        let score = tracker.get_score(&addr);
+        assert_eq!(score.data_availability, 4);
+        assert_eq!(score.total(), 4);
+        assert_eq!(tracker.total_contributions(), 4);
+    }
+    
+    #[test]
+    fn test_peer_discovery_counted_once() {
+        let mut tracker = ContributionTracker::new();
+        let discoverer = merklith_types::Address::from_bytes([1u8; 20]);
+        let other = merklith_types::Address::from_bytes([2u8; 20]);
+        
+        assert!(tracker.record_peer_discovery(discoverer, "10.0.0.7:30303", 1));
+        let score = tracker.get_score(&discoverer).discovered_peers;
+        assert!(score > 0);
+        
+        // Re-announcing the same peer earns nothing, whoever announces it
+        assert!(!tracker.record_peer_discovery(discoverer, "10.0.0.7:30303", 2));
+        assert!(!tracker.record_peer_discovery(other, "10.0.0.7:30303", 2));
+        assert_eq!(tracker.get_score(&discoverer).discovered_peers, score);
+        assert_eq!(tracker.get_score(&other).total(), 0);
+        
+        assert!(tracker.record_peer_discovery(discoverer, "10.0.0.8:30303", 3));
+        assert_eq!(tracker.get_score(&discoverer).discovered_peers, score * 2);
+    }
+    
+    #[test]
+    fn test_custom_weights() {
+        let addr = merklith_types::Address::from_bytes([1u8; 20]);
+        let weights = ContributionWeights {
+            attestation: 25,
+            ..ContributionWeights::default()
+        };
+        
+        let mut tracker = ContributionTracker::new().with_weights(weights.clone());
+        tracker.record_attestation(addr, 1);
+        tracker.record_block_production(addr, 1);
+        assert_eq!(tracker.get_score(&addr).attestations, 25);
+        assert_eq!(tracker.get_score(&addr).block_production, 100);
+        
+        let mut engine = ConsensusEngine::new(ValidatorSet::new(), 2).with_contribution_weights(weights);
+        engine.record_attestation(addr, 1);
+        assert_eq!(engine.validator_set().get_validator_score(&addr).attestations, 25);
+    }
+    
+    #[test]
+    fn test_time_decay() {
+        let addr = merklith_types::Address::from_bytes([1u8; 20]);
+        let mut tracker = ContributionTracker::new().with_time_decay(100);
+        tracker.record_contribution(Contribution {
+            contributor: addr,
+            contribution_type: ContributionType::BlockProduction,
+            weight: 800,
+            block_number: 1,
+            timestamp: 1_000,
+        });
+        
+        // Block count does not matter, only elapsed time
+        tracker.maybe_decay_at(5_000, 1_050);
+        assert_eq!(tracker.get_score(&addr).total(), 800);
+        
+        tracker.maybe_decay_at(5_001, 1_100);
+        assert_eq!(tracker.get_score(&addr).total(), 400);
+        
+        // Two more half-lives; the extra 50s carry over
+        tracker.maybe_decay_at(5_002, 1_350);
+        assert_eq!(tracker.get_score(&addr).total(), 100);
+        tracker.maybe_decay_at(5_003, 1_399);
+        assert_eq!(tracker.get_score(&addr).total(), 100);
+        tracker.maybe_decay_at(5_004, 1_400);
+        assert_eq!(tracker.get_score(&addr).total(), 50);
+    }
+    
+    #[test]
+    fn test_ranking_snapshot_allocations() {
+        let mut tracker = ContributionTracker::new();
+        let addr1 = merklith_types::Address::from_bytes([1u8; 20]);
+        let addr2 = merklith_types::Address::from_bytes([2u8; 20]);
+        let addr3 = merklith_types::Address::from_bytes([3u8; 20]);
+        
+        tracker.record_tx_relay(addr3, 1);
+        tracker.record_block_production(addr1, 1);
+        tracker.record_attestation(addr2, 1);
+        
+        let snapshot = tracker.snapshot_rankings();
+        assert_eq!(snapshot.total_score, 111);
+        let ranked: Vec<_> = snapshot.entries.iter().map(|e| (e.rank, e.address)).collect();
+        assert_eq!(ranked, vec![(1, addr1), (2, addr2), (3, addr3)]);
+        
+        // Later contributions do not change the snapshot
+        tracker.record_block_production(addr3, 2);
+        assert_eq!(snapshot.entries[2].score.total(), 1);
+        
+        let json = serde_json::to_string(&snapshot).unwrap();
+        let decoded: RankingSnapshot = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, snapshot);
+        
+        // 1000 * 100/111 = 900.9, 1000 * 10/111 = 90.09, 1000 * 1/111 = 9.009;
+        // the unit lost to rounding goes to the top contributor
+        let pool = merklith_types::U256::from(1000u64);
+        let allocations = snapshot.allocate_rewards(pool);
+        assert_eq!(allocations, vec![
+            (addr1, merklith_types::U256::from(901u64)),
+            (addr2, merklith_types::U256::from(90u64)),
+            (addr3, merklith_types::U256::from(9u64)),
+        ]);
+        let sum = allocations.iter().fold(merklith_types::U256::ZERO, |acc, (_, a)| acc + *a);
+        assert_eq!(sum, pool);
+    }
+    
     #[test]
     fn test_poc_proposer_selection() {
         let mut set = ValidatorSet::new();
@@ -645,16 +2004,14 @@ mod tests {
     
     #[test]
     fn test_attestation_pool() {
-        let mut pool = AttestationPool::new().with_threshold(2);
-        let addr1 = merklith_types::Address::from_bytes([1u8; 20]);
-        let addr2 = merklith_types::Address::from_bytes([2u8; 20]);
+        let mut pool = AttestationPool::new()
+            .with_threshold(2)
+            .with_committee(vec![registration(1), registration(2)])
+            .unwrap();
         let block_hash = [5u8; 32];
         
-        let att1 = Attestation::new(1, block_hash, addr1, vec![1, 2, 3]);
-        let att2 = Attestation::new(1, block_hash, addr2, vec![4, 5, 6]);
-        
-        assert!(pool.add_attestation(att1));
-        assert!(pool.add_attestation(att2));
+        assert!(pool.add_attestation(signed_attestation(1, 1, block_hash)));
+        assert!(pool.add_attestation(signed_attestation(2, 1, block_hash)));
         assert_eq!(pool.get_attestation_count(1), 2);
         
         assert!(pool.check_finality(1, block_hash));
@@ -663,15 +2020,11 @@ mod tests {
     
     #[test]
     fn test_attestation_duplicate_rejected() {
-        let mut pool = AttestationPool::new();
-        let addr = merklith_types::Address::from_bytes([1u8; 20]);
+        let mut pool = AttestationPool::new().with_committee(vec![registration(1)]).unwrap();
         let block_hash = [1u8; 32];
         
-        let att1 = Attestation::new(1, block_hash, addr, vec![1, 2, 3]);
-        let att2 = Attestation::new(1, block_hash, addr, vec![4, 5, 6]);
-        
-        assert!(pool.add_attestation(att1));
-        assert!(!pool.add_attestation(att2));
+        assert!(pool.add_attestation(signed_attestation(1, 1, block_hash)));
+        assert!(!pool.add_attestation(signed_attestation(1, 1, block_hash)));
     }
     
     #[test]
@@ -685,14 +2038,14 @@ mod tests {
         set.add_validator(addr2, 1000);
         set.add_validator(addr3, 1000);
         
-        let mut engine = ConsensusEngine::new(set, 2).with_finality_threshold(2);
+        let mut engine = ConsensusEngine::new(set, 2)
+            .with_finality_threshold(2)
+            .with_attestation_committee((1..=3).map(registration).collect())
+            .unwrap();
         let block_hash = [42u8; 32];
         
-        let att1 = Attestation::new(1, block_hash, addr1, vec![1]);
-        let att2 = Attestation::new(1, block_hash, addr2, vec![2]);
-        
-        engine.add_attestation(att1);
-        engine.add_attestation(att2);
+        engine.add_attestation(signed_attestation(1, 1, block_hash));
+        engine.add_attestation(signed_attestation(2, 1, block_hash));
         
         assert!(engine.check_finality(1, block_hash));
         assert!(engine.is_finalized(1));
@@ -702,4 +2055,43 @@ mod tests {
         assert_eq!(score1.attestations, 10);
         assert_eq!(score2.attestations, 10);
     }
+
+    #[test]
+    fn test_sealed_aggregate_imported_by_another_engine() {
+        let validators = || {
+            let mut set = ValidatorSet::new();
+            for i in 1..=3u8 {
+                set.add_validator(merklith_types::Address::from_bytes([i; 20]), 1000);
+            }
+            set
+        };
+        let engine = || {
+            ConsensusEngine::new(validators(), 2)
+                .with_finality_threshold(2)
+                .with_attestation_committee((1..=3).map(registration).collect())
+                .unwrap()
+        };
+        let block_hash = [6u8; 32];
+
+        // The producer seals what it collected
+        let mut producer = engine();
+        assert!(producer.seal_attestations(5, block_hash).is_err());
+        producer.add_attestation(signed_attestation(1, 5, block_hash));
+        producer.add_attestation(signed_attestation(3, 5, block_hash));
+        let aggregate = producer.seal_attestations(5, block_hash).unwrap();
+        assert!(producer.is_finalized(5));
+
+        // An importer verifies it and credits both signers
+        let mut importer = engine();
+        let mut tampered = aggregate.clone();
+        tampered.bitfield[0] = 0x03;
+        assert!(importer.verify_aggregate(&tampered).is_err());
+        assert!(importer.import_aggregate(tampered).is_err());
+        assert!(!importer.is_finalized(5));
+        importer.import_aggregate(aggregate).unwrap();
+        assert!(importer.is_finalized(5));
+        assert_eq!(importer.attestation_count(5), 2);
+        let (signer, _) = attester(3);
+        assert_eq!(importer.validator_set().get_validator_score(&signer).attestations, 10);
+    }
 }
@@ -21,13 +21,15 @@ pub struct BlockBuilder {
 
 impl BlockBuilder {
     /// Create a new block builder.
+    ///
+    /// Upgrades active at the new block's height are applied to `config`.
     pub fn new(
         parent: &BlockHeader,
         config: ChainConfig,
     ) -> Self {
         Self {
             parent: parent.clone(),
-            config,
+            config: config.at_height(parent.number + 1),
             pending_txs: Vec::new(),
             receipts: Vec::new(),
             gas_used: 0,
@@ -42,6 +44,11 @@ impl BlockBuilder {
         tx: SignedTransaction,
         receipt: TransactionReceipt,
     ) -> Result<(), BuilderError> {
+        // Reject transactions past their deadline
+        if tx.tx.is_expired(self.parent.number + 1) {
+            return Err(BuilderError::Expired);
+        }
+
         // Check gas limit
         if self.gas_used + receipt.gas_used > self.config.gas_limit {
             return Err(BuilderError::GasLimitExceeded);
@@ -139,6 +146,7 @@ pub enum BuilderError {
     GasLimitExceeded,
     InvalidTransaction,
     StateError,
+    Expired,
 }
 
 impl std::fmt::Display for BuilderError {
@@ -147,6 +155,7 @@ impl std::fmt::Display for BuilderError {
             BuilderError::GasLimitExceeded => write!(f, "Gas limit exceeded"),
             BuilderError::InvalidTransaction => write!(f, "Invalid transaction"),
             BuilderError::StateError => write!(f, "State error"),
+            BuilderError::Expired => write!(f, "Transaction expired"),
         }
     }
 }
@@ -206,4 +215,53 @@ mod tests {
         assert_eq!(builder.tx_count(), 1);
         assert_eq!(builder.gas_used(), 21000);
     }
+
+    #[test]
+    fn test_expired_transaction_rejected() {
+        let parent = BlockHeader::new(Hash::ZERO, 10, 1000, 30000000, Address::ZERO);
+        let mut builder = BlockBuilder::new(&parent, ChainConfig::mainnet());
+
+        let make = |valid_until: u64| {
+            let tx = SignedTransaction::new(
+                merklith_types::Transaction::new(
+                    1, 0, Some(Address::ZERO), U256::ZERO, 21000,
+                    U256::from(10u64), U256::from(1u64),
+                ).with_valid_until_block(valid_until),
+                merklith_types::Ed25519Signature::from_bytes([0u8; 64]),
+                merklith_types::Ed25519PublicKey::from_bytes([0u8; 32]),
+            );
+            let receipt = TransactionReceipt::new(
+                tx.hash(), 0, Hash::ZERO, 11, Address::ZERO, None, true, 21000,
+            );
+            (tx, receipt)
+        };
+
+        // Building block 11: a deadline of 10 has passed, 11 is still fine
+        let (tx, receipt) = make(10);
+        assert_eq!(builder.add_transaction(tx, receipt), Err(BuilderError::Expired));
+        let (tx, receipt) = make(11);
+        builder.add_transaction(tx, receipt).unwrap();
+        assert_eq!(builder.tx_count(), 1);
+    }
+
+    #[test]
+    fn test_fee_upgrade_activation() {
+        let config = ChainConfig::mainnet()
+            .with_upgrade(merklith_types::chain_config::upgrades::FAST_FEE_ADJUST, 100);
+
+        let base_fee_after = |parent_number: u64| {
+            let mut parent = BlockHeader::new(Hash::ZERO, parent_number, 1000, 30000000, Address::ZERO);
+            parent.base_fee_per_gas = U256::from(1000u64);
+            parent.gas_used = 30_000_000;
+            BlockBuilder::new(&parent, config.clone())
+                .finalize(Address::ZERO, 1001, Vec::new())
+                .unwrap()
+                .header
+                .base_fee_per_gas
+        };
+
+        // Block 99 uses the 5% cap, block 100 the upgraded 12% cap
+        assert_eq!(base_fee_after(98), U256::from(1050u64));
+        assert_eq!(base_fee_after(99), U256::from(1120u64));
+    }
 }
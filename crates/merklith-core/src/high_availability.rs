@@ -4,8 +4,10 @@
 //! Ensures the node stays online and handles failures gracefully.
 
 use std::collections::HashMap;
+use std::net::SocketAddr;
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
 use tokio::time::interval;
 use tracing::{info, warn, error};
 
@@ -336,34 +338,86 @@ impl Clone for RecoverySystem {
 }
 
 /// Node clustering support
+///
+/// Leader election uses static priorities plus heartbeats: the node with the
+/// highest priority among itself and its live peers is the leader (ties are
+/// broken by the lexicographically smallest node id). A peer whose heartbeat
+/// is older than `failover_timeout` is considered dead, so a standby takes
+/// over at most `failover_timeout` after the leader goes silent.
+///
+/// Heartbeats travel over plain TCP, separate from P2P gossip: every member
+/// listens on its configured address and keeps one connection open to each
+/// peer's `address`, writing its node id once per `heartbeat_interval`.
 pub struct ClusterManager {
     node_id: String,
+    priority: u32,
     peers: Arc<Mutex<Vec<ClusterPeer>>>,
     heartbeat_interval: Duration,
+    failover_timeout: Duration,
 }
 
 #[derive(Debug, Clone)]
 pub struct ClusterPeer {
     pub id: String,
     pub address: String,
+    pub priority: u32,
     pub last_heartbeat: Instant,
     pub healthy: bool,
 }
 
 impl ClusterManager {
     pub fn new(node_id: impl Into<String>) -> Self {
+        Self::with_priority(node_id, 0)
+    }
+
+    pub fn with_priority(node_id: impl Into<String>, priority: u32) -> Self {
         Self {
             node_id: node_id.into(),
+            priority,
             peers: Arc::new(Mutex::new(Vec::new())),
             heartbeat_interval: Duration::from_secs(5),
+            failover_timeout: Duration::from_secs(15),
         }
     }
 
+    /// Set how long a silent peer is still trusted before standbys take over
+    pub fn with_failover_timeout(mut self, timeout: Duration) -> Self {
+        self.failover_timeout = timeout;
+        self
+    }
+
+    /// Set heartbeat broadcast interval
+    pub fn with_heartbeat_interval(mut self, interval: Duration) -> Self {
+        self.heartbeat_interval = interval;
+        self
+    }
+
+    /// Get local node id
+    pub fn node_id(&self) -> &str {
+        &self.node_id
+    }
+
+    /// Get local election priority
+    pub fn priority(&self) -> u32 {
+        self.priority
+    }
+
     /// Add peer to cluster
     pub fn add_peer(&self, id: impl Into<String>, address: impl Into<String>) {
+        self.add_peer_with_priority(id, address, 0);
+    }
+
+    /// Add peer to cluster with an election priority
+    pub fn add_peer_with_priority(
+        &self,
+        id: impl Into<String>,
+        address: impl Into<String>,
+        priority: u32,
+    ) {
         let peer = ClusterPeer {
             id: id.into(),
             address: address.into(),
+            priority,
             last_heartbeat: Instant::now(),
             healthy: true,
         };
@@ -375,21 +429,15 @@ impl ClusterManager {
 
     /// Update peer heartbeat
     pub fn update_heartbeat(&self, peer_id: &str) {
-        if let Ok(mut peers) = self.peers.lock() {
-            if let Some(peer) = peers.iter_mut().find(|p| p.id == peer_id) {
-                peer.last_heartbeat = Instant::now();
-                peer.healthy = true;
-            }
-        }
+        touch_peer(&self.peers, peer_id);
     }
 
     /// Get healthy peers
     pub fn get_healthy_peers(&self) -> Vec<ClusterPeer> {
         if let Ok(peers) = self.peers.lock() {
-            let cutoff = Instant::now() - Duration::from_secs(15);
             peers
                 .iter()
-                .filter(|p| p.last_heartbeat > cutoff && p.healthy)
+                .filter(|p| p.last_heartbeat.elapsed() < self.failover_timeout && p.healthy)
                 .cloned()
                 .collect()
         } else {
@@ -402,38 +450,128 @@ impl ClusterManager {
         self.peers.lock().map(|p| p.len()).unwrap_or(0)
     }
 
-    /// Start heartbeat loop
-    pub fn start_heartbeat(
-        &self,
-        broadcast_fn: impl Fn(String) + Send + 'static,
-    ) {
+    /// Get the id of the currently elected leader
+    pub fn current_leader(&self) -> String {
+        let mut leader = (self.priority, self.node_id.clone());
+        for peer in self.get_healthy_peers() {
+            if outranks(peer.priority, &peer.id, leader.0, &leader.1) {
+                leader = (peer.priority, peer.id);
+            }
+        }
+        leader.1
+    }
+
+    /// Check if this node is the elected leader and should produce blocks
+    pub fn is_leader(&self) -> bool {
+        self.current_leader() == self.node_id
+    }
+
+    /// Accept peer heartbeats on `addr`, one node id per line on each
+    /// connection. Returns the bound address.
+    pub async fn listen_heartbeats(&self, addr: SocketAddr) -> std::io::Result<SocketAddr> {
+        let listener = tokio::net::TcpListener::bind(addr).await?;
+        let local_addr = listener.local_addr()?;
         let peers = Arc::clone(&self.peers);
+
+        tokio::spawn(async move {
+            loop {
+                let stream = match listener.accept().await {
+                    Ok((stream, _)) => stream,
+                    Err(e) => {
+                        warn!("Cluster heartbeat accept failed: {}", e);
+                        continue;
+                    }
+                };
+                let peers = Arc::clone(&peers);
+                tokio::spawn(async move {
+                    let mut lines = tokio::io::BufReader::new(stream).lines();
+                    while let Ok(Some(node_id)) = lines.next_line().await {
+                        touch_peer(&peers, node_id.trim());
+                    }
+                });
+            }
+        });
+
+        Ok(local_addr)
+    }
+
+    /// Start sending heartbeats to every peer's address, each over its own
+    /// long-lived connection that is reopened after a failed write. Aborting
+    /// the returned handle stops all of them.
+    pub fn start_heartbeat(&self) -> tokio::task::JoinHandle<()> {
+        let peers = Arc::clone(&self.peers);
+        let addresses: Vec<String> = self
+            .peers
+            .lock()
+            .map(|peers| peers.iter().map(|p| p.address.clone()).collect())
+            .unwrap_or_default();
         let node_id = self.node_id.clone();
         let heartbeat_interval = self.heartbeat_interval;
+        let failover_timeout = self.failover_timeout;
 
         tokio::spawn(async move {
-            let mut ticker = tokio::time::interval(heartbeat_interval);
+            // Dropped with this task, which aborts every sender
+            let mut senders = tokio::task::JoinSet::new();
+            for address in addresses {
+                senders.spawn(send_heartbeats(address, format!("{}\n", node_id), heartbeat_interval));
+            }
 
+            let mut ticker = tokio::time::interval(heartbeat_interval);
             loop {
                 ticker.tick().await;
 
-                // Broadcast heartbeat
-                broadcast_fn(node_id.clone());
-
                 // Mark stale peers as unhealthy
                 if let Ok(mut peers) = peers.lock() {
-                    let cutoff = Instant::now() - Duration::from_secs(15);
                     for peer in peers.iter_mut() {
-                        if peer.last_heartbeat < cutoff {
+                        if peer.last_heartbeat.elapsed() >= failover_timeout {
                             peer.healthy = false;
                         }
                     }
                 }
             }
-        });
+        })
+    }
+}
+
+/// Record a heartbeat from `peer_id`, if it is a known member
+fn touch_peer(peers: &Mutex<Vec<ClusterPeer>>, peer_id: &str) {
+    if let Ok(mut peers) = peers.lock() {
+        if let Some(peer) = peers.iter_mut().find(|p| p.id == peer_id) {
+            peer.last_heartbeat = Instant::now();
+            peer.healthy = true;
+        }
+    }
+}
+
+/// Write `line` to `address` every `heartbeat_interval`, keeping the
+/// connection open between heartbeats
+async fn send_heartbeats(address: String, line: String, heartbeat_interval: Duration) {
+    let mut ticker = tokio::time::interval(heartbeat_interval);
+    let mut connection: Option<tokio::net::TcpStream> = None;
+
+    loop {
+        ticker.tick().await;
+
+        if connection.is_none() {
+            match tokio::time::timeout(heartbeat_interval, tokio::net::TcpStream::connect(&address)).await {
+                Ok(Ok(stream)) => connection = Some(stream),
+                _ => continue,
+            }
+        }
+        if let Some(stream) = connection.as_mut() {
+            if stream.write_all(line.as_bytes()).await.is_err() {
+                warn!("Cluster heartbeat to {} failed, reconnecting", address);
+                connection = None;
+            }
+        }
     }
 }
 
+/// Election order: higher priority wins, ties go to the smaller node id
+fn outranks(priority: u32, id: &str, other_priority: u32, other_id: &str) -> bool {
+    priority > other_priority || (priority == other_priority && id < other_id)
+}
+
 /// Comprehensive HA manager
 pub struct HighAvailabilityManager {
     health_monitor: Arc<HealthMonitor>,
@@ -565,4 +703,69 @@ mod tests {
         let healthy = cluster.get_healthy_peers();
         assert_eq!(healthy.len(), 2);
     }
+
+    #[test]
+    fn test_cluster_leader_failover() {
+        let timeout = Duration::from_millis(50);
+        let primary = ClusterManager::with_priority("node-a", 10).with_failover_timeout(timeout);
+        let standby = ClusterManager::with_priority("node-b", 5).with_failover_timeout(timeout);
+
+        primary.add_peer_with_priority("node-b", "127.0.0.1:30304", 5);
+        standby.add_peer_with_priority("node-a", "127.0.0.1:30303", 10);
+
+        assert!(primary.is_leader());
+        assert!(!standby.is_leader());
+        assert_eq!(standby.current_leader(), "node-a");
+
+        // Primary stops heartbeating; standby keeps hearing nothing
+        std::thread::sleep(Duration::from_millis(80));
+        assert!(standby.is_leader());
+
+        // Primary comes back and reclaims leadership
+        standby.update_heartbeat("node-a");
+        assert!(!standby.is_leader());
+    }
+
+    #[tokio::test]
+    async fn test_follower_takes_over_when_heartbeats_stop() {
+        let timeout = Duration::from_millis(300);
+        let interval = Duration::from_millis(50);
+        let leader = ClusterManager::with_priority("node-a", 10)
+            .with_heartbeat_interval(interval)
+            .with_failover_timeout(timeout);
+        let follower = ClusterManager::with_priority("node-b", 5)
+            .with_heartbeat_interval(interval)
+            .with_failover_timeout(timeout);
+
+        let leader_addr = leader.listen_heartbeats("127.0.0.1:0".parse().unwrap()).await.unwrap();
+        let follower_addr = follower.listen_heartbeats("127.0.0.1:0".parse().unwrap()).await.unwrap();
+        leader.add_peer_with_priority("node-b", follower_addr.to_string(), 5);
+        follower.add_peer_with_priority("node-a", leader_addr.to_string(), 10);
+        let leader_heartbeat = leader.start_heartbeat();
+        let follower_heartbeat = follower.start_heartbeat();
+
+        // Heartbeats keep the leader alive well past the failover timeout
+        tokio::time::sleep(timeout * 2).await;
+        assert!(leader.is_leader());
+        assert!(!follower.is_leader());
+
+        // The leader goes silent; the follower takes over
+        leader_heartbeat.abort();
+        tokio::time::sleep(timeout * 2).await;
+        assert!(follower.is_leader());
+        assert_eq!(follower.current_leader(), "node-b");
+
+        follower_heartbeat.abort();
+    }
+
+    #[test]
+    fn test_cluster_leader_tie_break() {
+        let a = ClusterManager::new("node-a");
+        let b = ClusterManager::new("node-b");
+        a.add_peer("node-b", "127.0.0.1:30304");
+        b.add_peer("node-a", "127.0.0.1:30303");
+
+        assert!(a.is_leader());
+        assert!(!b.is_leader());
+    }
 }
@@ -6,6 +6,7 @@ pub mod fee_market;
 pub mod block_builder;
 pub mod state;
 pub mod state_machine;
+pub mod wal;
 pub mod high_availability;
 pub mod performance;
 
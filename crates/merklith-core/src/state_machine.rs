@@ -1,13 +1,73 @@
 //! State Machine - Real blockchain state transitions with persistence
 
-use merklith_types::{Address, U256, Hash, Transaction};
-use std::collections::HashMap;
+use merklith_types::{
+    Address, Block, ChainConfig, GenesisConfig, U256, Hash, SignedTransaction, SponsoredTransaction, TransactionType,
+};
+use merklith_storage::{CommitPolicy, PruningConfig};
+use crate::wal::WriteAheadLog;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::path::PathBuf;
 use std::fs;
 use std::str::FromStr;
-use parking_lot::RwLock;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::Instant;
+use parking_lot::{Mutex, RwLock, RwLockUpgradableReadGuard};
 use serde::{Deserialize, Serialize};
 
+/// Intrinsic gas charged for a value transfer
+pub const TRANSFER_GAS: u64 = 21_000;
+
+/// Gas charged per recipient of a multisend, on top of [`TRANSFER_GAS`]
+pub const MULTISEND_GAS_PER_RECIPIENT: u64 = 9_000;
+
+/// Intrinsic gas of a multisend paying `recipients` addresses
+pub fn multisend_gas(recipients: usize) -> u64 {
+    TRANSFER_GAS + MULTISEND_GAS_PER_RECIPIENT * recipients as u64
+}
+
+/// Published mnemonic the devnet accounts are derived from. Anyone can
+/// recompute these keys: never fund them on a real network.
+pub const DEVNET_MNEMONIC: &str = "test test test test test test test test test test test junk";
+
+/// Number of pre-funded devnet accounts
+pub const DEVNET_ACCOUNT_COUNT: usize = 10;
+
+/// Maximum future-nonce transactions held per sender
+pub const MAX_QUEUED_PER_SENDER: usize = 16;
+
+/// Gas burned, at the base fee, to hold a transaction in the delayed queue
+pub const SCHEDULE_GAS: u64 = 21_000;
+
+/// Most recent blocks that [`State::revert_to`] can unwind
+pub const MAX_REVERT_DEPTH: u64 = 128;
+
+fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Storage key holding the address allowed to upgrade a contract's code.
+///
+/// The admin is stored right-aligned in the 32-byte word.
+pub fn admin_slot() -> [u8; 32] {
+    *blake3::hash(b"merklith.proxy.admin").as_bytes()
+}
+
+/// Blocks whose logs are kept for subscribers that fall behind
+pub const LOG_FEED_CAPACITY: usize = 1024;
+
+/// A block entering the chain, or leaving it on a revert, with the receipts
+/// whose logs subscribers see added or removed
+#[derive(Debug, Clone)]
+pub struct LogUpdate {
+    pub block: BlockInfo,
+    pub receipts: Vec<BlockReceipt>,
+    /// Set when the block was reverted: its logs no longer happened
+    pub removed: bool,
+}
+
 /// Block production result
 #[derive(Debug, Clone)]
 pub struct BlockProductionResult {
@@ -15,6 +75,60 @@ pub struct BlockProductionResult {
     pub block_hash: [u8; 32],
     pub transactions_count: usize,
     pub validator_reward: U256,
+    pub fees: FeeDistribution,
+}
+
+/// Where the gas fees of executed transactions went
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FeeDistribution {
+    /// Credited to the block proposer
+    pub to_proposer: U256,
+    /// Credited to the treasury address
+    pub to_treasury: U256,
+    /// Removed from circulation
+    pub burned: U256,
+}
+
+impl FeeDistribution {
+    /// Split the fee of a transaction that used `gas_used` at `effective_gas_price`.
+    ///
+    /// `fee_burn_pct` of the base fee portion is burned; `fee_treasury_pct` of
+    /// whatever is left goes to the treasury and the rest to the proposer.
+    pub fn split(
+        config: &ChainConfig,
+        base_fee: &U256,
+        effective_gas_price: &U256,
+        gas_used: u64,
+    ) -> Self {
+        let gas_used = U256::from(gas_used);
+        let fee = effective_gas_price.saturating_mul(&gas_used);
+        let burned = base_fee.saturating_mul(&gas_used)
+            .saturating_mul(&U256::from(config.fee_burn_pct.min(100) as u64))
+            / U256::from(100u64);
+        let remaining = fee.saturating_sub(&burned);
+        let to_treasury = remaining
+            .saturating_mul(&U256::from(config.fee_treasury_pct.min(100) as u64))
+            / U256::from(100u64);
+
+        Self {
+            to_proposer: remaining.saturating_sub(&to_treasury),
+            to_treasury,
+            burned,
+        }
+    }
+
+    /// Total fee paid by the sender
+    pub fn total(&self) -> U256 {
+        self.to_proposer
+            .saturating_add(&self.to_treasury)
+            .saturating_add(&self.burned)
+    }
+
+    fn accumulate(&mut self, other: &FeeDistribution) {
+        self.to_proposer = self.to_proposer.saturating_add(&other.to_proposer);
+        self.to_treasury = self.to_treasury.saturating_add(&other.to_treasury);
+        self.burned = self.burned.saturating_add(&other.burned);
+    }
 }
 
 /// State machine errors
@@ -24,6 +138,15 @@ pub enum StateError {
     InvalidNonce,
     InvalidTransaction(String),
     InvalidBlock(String),
+    SupplyMismatch { tracked: U256, actual: U256 },
+    /// Crediting the account would exceed `U256::MAX`
+    BalanceOverflow(Address),
+    /// Caller is not allowed to perform the operation
+    Unauthorized(Address),
+    /// Contract code failed validation
+    InvalidCode(String),
+    /// Loaded state contradicts itself
+    Inconsistent(String),
 }
 
 impl std::fmt::Display for StateError {
@@ -33,6 +156,13 @@ impl std::fmt::Display for StateError {
             StateError::InvalidNonce => write!(f, "Invalid nonce"),
             StateError::InvalidTransaction(msg) => write!(f, "Invalid transaction: {}", msg),
             StateError::InvalidBlock(msg) => write!(f, "Invalid block: {}", msg),
+            StateError::SupplyMismatch { tracked, actual } => {
+                write!(f, "Supply mismatch: tracked {}, balances sum to {}", tracked, actual)
+            }
+            StateError::BalanceOverflow(address) => write!(f, "Balance overflow for {}", address),
+            StateError::Unauthorized(address) => write!(f, "{} is not authorized", address),
+            StateError::InvalidCode(msg) => write!(f, "Invalid code: {}", msg),
+            StateError::Inconsistent(msg) => write!(f, "Inconsistent state: {}", msg),
         }
     }
 }
@@ -47,10 +177,119 @@ pub struct BlockInfo {
     pub parent_hash: [u8; 32],
     pub timestamp: u64,
     pub tx_count: usize,
+    /// Commitment to all accounts after the block
+    #[serde(default)]
+    pub state_root: [u8; 32],
+    /// Commitment to the block's transaction hashes, in order
+    #[serde(default)]
+    pub transactions_root: [u8; 32],
+    #[serde(default)]
+    pub proposer: Address,
+    #[serde(default)]
+    pub gas_used: u64,
+    #[serde(default)]
+    pub gas_limit: u64,
+    /// Coins minted to the proposer, hex like account balances; not part of the hash
+    #[serde(default)]
+    pub reward: String,
+    /// Committee attestation of the parent block; not part of the hash
+    #[serde(default)]
+    pub parent_attestation: Option<ParentAttestation>,
 }
 
-/// Account state in the blockchain
+/// A block's committee attestations in compact form: one aggregate BLS
+/// signature and a bitfield over the committee marking who signed
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ParentAttestation {
+    pub signature: Vec<u8>,
+    pub bitfield: Vec<u8>,
+}
+
+impl BlockInfo {
+    /// Coins minted to the proposer by this block
+    pub fn reward(&self) -> U256 {
+        U256::from_str(&self.reward).unwrap_or(U256::ZERO)
+    }
+    
+    /// Block hash: blake3 over the canonical header encoding.
+    ///
+    /// Fields are encoded in a fixed order, integers little-endian. Every
+    /// node derives the hash from these fields alone, so changing the
+    /// encoding is a network upgrade.
+    pub fn compute_hash(&self) -> [u8; 32] {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(&self.parent_hash);
+        hasher.update(&self.number.to_le_bytes());
+        hasher.update(&self.timestamp.to_le_bytes());
+        hasher.update(&self.state_root);
+        hasher.update(&self.transactions_root);
+        hasher.update(self.proposer.as_bytes());
+        hasher.update(&self.gas_used.to_le_bytes());
+        hasher.update(&self.gas_limit.to_le_bytes());
+        *hasher.finalize().as_bytes()
+    }
+}
+
+/// Commitment to an ordered list of transactions
+pub fn transactions_root(transactions: &[SignedTransaction]) -> [u8; 32] {
+    let mut hasher = blake3::Hasher::new();
+    for tx in transactions {
+        hasher.update(tx.hash().as_bytes());
+    }
+    *hasher.finalize().as_bytes()
+}
+
+/// Outcome of a transaction included in a produced block
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockReceipt {
+    pub tx_hash: Hash,
+    pub from: Address,
+    pub to: Option<Address>,
+    /// False if the transaction was included but could not be applied
+    pub success: bool,
+    pub gas_used: u64,
+    /// Gas used by this and all earlier transactions in the block
+    pub cumulative_gas_used: u64,
+    pub effective_gas_price: U256,
+    /// Events emitted by the transaction, in emission order
+    #[serde(default)]
+    pub logs: Vec<EventLog>,
+}
+
+/// Event emitted by a contract during a mined transaction
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EventLog {
+    /// Contract that emitted the event
+    pub address: Address,
+    pub topics: Vec<Hash>,
+    pub data: Vec<u8>,
+    pub block_number: u64,
+    /// Index of the emitting transaction in its block
+    pub tx_index: usize,
+    /// Position of the log among all logs of the block
+    pub log_index: usize,
+}
+
+/// Predicted outcome of a transaction run against pending state
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimulationResult {
+    pub success: bool,
+    pub gas_used: u64,
+    /// Why the transaction would fail
+    pub error: Option<String>,
+    /// Block the simulation assumed the transaction lands in
+    pub block_number: u64,
+}
+
+/// What a successfully applied transaction cost and emitted
+struct TxOutcome {
+    fees: FeeDistribution,
+    gas_used: u64,
+    logs: Vec<merklith_vm::runtime::LogEntry>,
+}
+
+/// Account state in the blockchain
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Account {
     pub balance: String,  // hex string
     pub nonce: u64,
@@ -78,6 +317,15 @@ impl Account {
         // U256's LowerHex already adds 0x prefix
         self.balance = format!("{:x}", balance);
     }
+    
+    /// Storage decoded into 32-byte keys and values; malformed entries are skipped
+    pub fn storage_words(&self) -> HashMap<[u8; 32], [u8; 32]> {
+        let word = |s: &String| -> Option<[u8; 32]> { hex::decode(s).ok()?.try_into().ok() };
+        self.storage
+            .iter()
+            .filter_map(|(key, value)| Some((word(key)?, word(value)?)))
+            .collect()
+    }
 }
 
 /// Persistent state
@@ -88,8 +336,106 @@ struct StateData {
     #[serde(default)]
     block_hash: String,
     total_supply: String,
+    /// Absent in state files written before supply tracking was fixed
+    #[serde(default)]
+    total_burned: Option<String>,
     #[serde(default)]
     blocks: Vec<BlockInfo>,
+    #[serde(default)]
+    chain_id: u64,
+    #[serde(default)]
+    bodies: BTreeMap<u64, Vec<String>>,
+    #[serde(default)]
+    receipts: BTreeMap<u64, Vec<BlockReceipt>>,
+    /// Borsh-encoded signed transactions per block, hex
+    #[serde(default)]
+    transactions: BTreeMap<u64, Vec<String>>,
+    #[serde(default)]
+    snapshots: BTreeMap<u64, HashMap<String, Account>>,
+    /// Hex code of each contract by the block it took effect at
+    #[serde(default)]
+    code_history: HashMap<String, BTreeMap<u64, String>>,
+    /// Borsh-encoded delayed transactions by execution block, hex
+    #[serde(default)]
+    delayed: BTreeMap<u64, Vec<String>>,
+    /// State root of `accounts`, absent in files written before it was recorded
+    #[serde(default)]
+    state_root: Option<String>,
+    /// Sequence number of the last write-ahead log record this file includes
+    #[serde(default)]
+    wal_seq: u64,
+}
+
+/// A state transition appended to the write-ahead log, replayed on startup
+/// when the state file does not include it yet. Transactions are
+/// Borsh-encoded hex as in [`StateData`].
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+enum WalRecord {
+    Transfer { from: String, to: String, amount: String },
+    Sponsored { tx: String, proposer: String },
+    ProduceBlock {
+        validator: String,
+        transactions: Vec<String>,
+        is_heartbeat: bool,
+        stamp: BlockStamp,
+    },
+    IncrementBlock { stamp: BlockStamp },
+    AddBlock { number: u64, hash: String, parent_hash: String, timestamp: u64 },
+    Attest { number: u64, signature: String, bitfield: String },
+    Schedule { tx: String },
+    CancelDelayed { tx: String },
+}
+
+/// A [`WalRecord`] numbered in the order it was logged
+#[derive(Debug, Serialize, Deserialize)]
+struct WalEntry<R> {
+    seq: u64,
+    #[serde(flatten)]
+    record: R,
+}
+
+/// Header fields chosen when a block is made, fixed on replay so the
+/// replayed block hashes the same
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct BlockStamp {
+    timestamp: u64,
+    gas_limit: u64,
+    /// Hash of the signed header an imported block came with, which the
+    /// block keeps in place of one computed here
+    #[serde(default)]
+    hash: Option<[u8; 32]>,
+}
+
+/// What is needed to take back one block: the accounts it changed and the
+/// supply as they stood after the block before, and the delayed
+/// transactions it executed
+#[derive(Debug, Clone, Default)]
+struct BlockUndo {
+    /// Previous account, `None` if the block created it
+    accounts: Vec<(Address, Option<Account>)>,
+    total_supply: U256,
+    total_burned: U256,
+    /// Delayed transactions by the block they were due at
+    delayed: BTreeMap<u64, Vec<SignedTransaction>>,
+}
+
+/// Accounts changed since the last recorded block, as they stood after it,
+/// and the supply at that block
+#[derive(Debug, Default)]
+struct PendingUndo {
+    accounts: HashMap<Address, Option<Account>>,
+    total_supply: U256,
+    total_burned: U256,
+}
+
+impl PendingUndo {
+    /// Keep `addresses` as they are in `accounts` unless already changed
+    fn note(&mut self, accounts: &HashMap<Address, Account>, addresses: &[Address]) {
+        for address in addresses {
+            self.accounts.entry(*address).or_insert_with(|| accounts.get(address).cloned());
+        }
+    }
 }
 
 /// Blockchain state with persistence
@@ -99,7 +445,52 @@ pub struct State {
     block_number: RwLock<u64>,
     block_hash: RwLock<Hash>,
     total_supply: RwLock<U256>,
+    /// Fees burned since genesis
+    total_burned: RwLock<U256>,
     blocks: RwLock<Vec<BlockInfo>>,
+    /// Transaction hashes per block, subject to pruning
+    bodies: RwLock<BTreeMap<u64, Vec<String>>>,
+    /// Receipts per block, in transaction order; pruned with the bodies
+    receipts: RwLock<BTreeMap<u64, Vec<BlockReceipt>>>,
+    /// Signed transactions per block, in block order; pruned with the bodies
+    transactions: RwLock<BTreeMap<u64, Vec<SignedTransaction>>>,
+    /// Blocks in which each address emitted logs; rebuilt from receipts on load
+    log_index: RwLock<HashMap<Address, BTreeSet<u64>>>,
+    /// Account snapshots taken every `snapshot_interval` blocks
+    snapshots: RwLock<BTreeMap<u64, HashMap<Address, Account>>>,
+    /// Code of each contract by the block it took effect at
+    code_history: RwLock<HashMap<Address, BTreeMap<u64, Vec<u8>>>>,
+    /// Signed transactions held until the block they execute in
+    delayed: RwLock<BTreeMap<u64, Vec<SignedTransaction>>>,
+    /// Undo records of the last `MAX_REVERT_DEPTH` blocks since startup
+    undo: RwLock<BTreeMap<u64, BlockUndo>>,
+    /// Changes not yet in an undo record, taken by the next recorded block
+    pending_undo: RwLock<PendingUndo>,
+    pruning: PruningConfig,
+    genesis: GenesisConfig,
+    genesis_hash: Hash,
+    /// Chain id the persisted state was created for
+    chain_id: RwLock<u64>,
+    /// Transactions waiting for a nonce gap to be filled (not persisted)
+    queued: RwLock<HashMap<Address, BTreeMap<u64, SignedTransaction>>>,
+    /// Gas limit this node's blocks move towards (not persisted)
+    gas_limit_target: RwLock<Option<u64>>,
+    /// When mutations are written to disk (not persisted)
+    commit_policy: RwLock<CommitPolicy>,
+    /// Blocks until a block is final; reaching it forces a flush (not persisted)
+    finality_threshold: RwLock<Option<u64>>,
+    /// Head height and time of the last write to disk
+    last_flush: Mutex<(u64, Instant)>,
+    /// State root recorded in the state file when it was loaded or last written
+    persisted_root: Mutex<Option<[u8; 32]>>,
+    /// Transitions applied since the last flush
+    wal: WriteAheadLog,
+    /// Blocks added and reverted, for log subscribers (not persisted)
+    log_feed: tokio::sync::broadcast::Sender<LogUpdate>,
+    /// Set while the WAL is replayed, which must not log or flush
+    replaying: AtomicBool,
+    /// Sequence number of the last WAL record logged or replayed
+    wal_seq: AtomicU64,
     path: PathBuf,
 }
 
@@ -109,63 +500,471 @@ impl State {
     }
     
     pub fn with_path(path: PathBuf) -> Self {
-        let mut accounts = HashMap::new();
-        
-        // Devnet: 8 pre-funded accounts with 1,000,000 MERK each
-        let genesis_accounts: Vec<&str> = vec![
-            "0x742d35Cc6634C0532925a3b844Bc9e7595f0bEb0",
-            "0x8ba1f109551bD432803012645Ac136ddd64DBA72",
-            "0xdD870fA1b7C4700F2BD7f44238821C26f7392148",
-            "0xAb5801a7D398351b8bE11C439e05C5B3259aeC9B",
-            "0x1aB489E589De6E2F9c9b6B9e2F2b1a4c3d5E6F78",
-            "0x2Bc5901A6E4984628Bf12C539f06D5b3369eD0C1",
-            "0x3Cd601A7E5985739Bf13D54A107d5b4479fE1D2E",
-            "0x4DE710A8E6A96849Cf15D54B208e6C548aF2E3F4",
-        ];
-        
+        Self::with_pruning(path, PruningConfig::default())
+    }
+    
+    /// Create state that keeps history according to `pruning`
+    pub fn with_pruning(path: PathBuf, pruning: PruningConfig) -> Self {
+        Self::with_genesis(path, Self::devnet_genesis(), pruning)
+    }
+    
+    /// Devnet genesis: [`DEVNET_ACCOUNT_COUNT`] accounts derived from
+    /// [`DEVNET_MNEMONIC`], pre-funded with 1,000,000 MERK each
+    pub fn devnet_genesis() -> GenesisConfig {
         // 1,000,000 MERK in Sparks (1 MERK = 10^18 Spark)
         let initial_balance = U256::from(1_000_000u128) * U256::from(1_000_000_000_000_000_000u128);
-        let balance_hex = format!("{:x}", initial_balance);  // Without 0x prefix, LowerHex adds it
-        
-        for addr in genesis_accounts {
-            if let Ok(address) = parse_address(addr) {
-                accounts.insert(address, Account {
-                    balance: balance_hex.clone(),
-                    nonce: 0,
-                    code: vec![],
-                    storage: HashMap::new(),
-                });
+        
+        let mut genesis = GenesisConfig::devnet();
+        for keypair in Self::devnet_accounts() {
+            genesis.add_alloc(keypair.address(), initial_balance);
+        }
+        genesis
+    }
+    
+    /// Keypairs of the pre-funded devnet accounts, in allocation order
+    pub fn devnet_accounts() -> Vec<merklith_crypto::Keypair> {
+        (0..DEVNET_ACCOUNT_COUNT as u32)
+            .map(|index| {
+                merklith_crypto::keypair_from_mnemonic(DEVNET_MNEMONIC, &merklith_crypto::derivation_path(index))
+                    .expect("devnet mnemonic and derivation path are valid")
+            })
+            .collect()
+    }
+    
+    /// Create state seeded from `genesis`.
+    ///
+    /// Persisted state that fails [`State::verify_consistency`] is logged and
+    /// used anyway; nodes should start through [`State::open`] instead.
+    pub fn with_genesis(path: PathBuf, genesis: GenesisConfig, pruning: PruningConfig) -> Self {
+        let (state, check) = Self::init(path, genesis, pruning);
+        if let Err(e) = check {
+            tracing::error!("Persisted state failed its consistency check: {}", e);
+        }
+        state
+    }
+    
+    /// Create state seeded from `genesis`, failing if the persisted state is
+    /// not internally consistent
+    pub fn open(path: PathBuf, genesis: GenesisConfig, pruning: PruningConfig) -> Result<Self, StateError> {
+        let (state, check) = Self::init(path, genesis, pruning);
+        check.map(|_| state)
+    }
+    
+    /// Load or create the state, checking whatever was loaded before the
+    /// write-ahead log is replayed on top of it
+    fn init(path: PathBuf, genesis: GenesisConfig, pruning: PruningConfig) -> (Self, Result<(), StateError>) {
+        let accounts = genesis_accounts(&genesis);
+        let mut code_history = HashMap::new();
+        let mut initial_supply = U256::ZERO;
+        
+        for alloc in &genesis.alloc {
+            if let Some(code) = alloc.code.clone().filter(|c| !c.is_empty()) {
+                code_history.insert(alloc.address, BTreeMap::from([(0, code)]));
             }
+            initial_supply = initial_supply.saturating_add(&alloc.balance);
         }
         
+        let genesis_hash = genesis.genesis_hash();
+        let chain_id = genesis.chain_config.chain_id;
+        
         let state = Self {
             accounts: RwLock::new(accounts),
             block_number: RwLock::new(0),
-            block_hash: RwLock::new(Hash::ZERO),
-            total_supply: RwLock::new(initial_balance * U256::from(8u64)),
+            block_hash: RwLock::new(genesis_hash),
+            total_supply: RwLock::new(initial_supply),
+            total_burned: RwLock::new(U256::ZERO),
             blocks: RwLock::new(Vec::new()),
+            bodies: RwLock::new(BTreeMap::new()),
+            receipts: RwLock::new(BTreeMap::new()),
+            transactions: RwLock::new(BTreeMap::new()),
+            log_index: RwLock::new(HashMap::new()),
+            snapshots: RwLock::new(BTreeMap::new()),
+            code_history: RwLock::new(code_history),
+            delayed: RwLock::new(BTreeMap::new()),
+            undo: RwLock::new(BTreeMap::new()),
+            pending_undo: RwLock::new(PendingUndo::default()),
+            pruning,
+            genesis,
+            genesis_hash,
+            chain_id: RwLock::new(chain_id),
+            queued: RwLock::new(HashMap::new()),
+            gas_limit_target: RwLock::new(None),
+            commit_policy: RwLock::new(CommitPolicy::default()),
+            finality_threshold: RwLock::new(None),
+            last_flush: Mutex::new((0, Instant::now())),
+            persisted_root: Mutex::new(None),
+            wal: WriteAheadLog::new(path.join("state.wal")),
+            log_feed: tokio::sync::broadcast::channel(LOG_FEED_CAPACITY).0,
+            replaying: AtomicBool::new(false),
+            wal_seq: AtomicU64::new(0),
             path,
         };
         
         // Try to load from disk
-        if let Err(e) = state.load() {
-            tracing::info!("Could not load state: {}, using genesis", e);
-            // Create genesis block
-            state.add_genesis_block();
+        let check = match state.load() {
+            Ok(()) => state.verify_consistency(),
+            Err(e) => {
+                tracing::info!("Could not load state: {}, using genesis", e);
+                // Create genesis block
+                state.add_genesis_block();
+                Ok(())
+            }
+        };
+        // Blocks from before startup cannot be reverted
+        *state.pending_undo.write() = PendingUndo {
+            accounts: HashMap::new(),
+            total_supply: state.total_supply(),
+            total_burned: state.total_burned(),
+        };
+        if check.is_ok() {
+            state.replay_wal();
         }
+        *state.last_flush.lock() = (state.block_number(), Instant::now());
         
-        state
+        (state, check)
     }
     
     fn add_genesis_block(&self) {
         let genesis = BlockInfo {
             number: 0,
-            hash: [0u8; 32],
+            hash: *self.genesis_hash.as_bytes(),
             parent_hash: [0u8; 32],
-            timestamp: 0,
+            timestamp: self.genesis.timestamp,
             tx_count: 0,
+            state_root: self.state_root(),
+            transactions_root: transactions_root(&[]),
+            proposer: Address::ZERO,
+            gas_used: 0,
+            gas_limit: self.genesis.chain_config.gas_limit,
+            reward: String::new(),
+            parent_attestation: None,
         };
         self.blocks.write().push(genesis);
+        self.record_block(0, Vec::new(), Vec::new());
+    }
+    
+    /// Store the block body, receipts and snapshot, then drop whatever the
+    /// pruning policy no longer retains
+    fn record_block(&self, number: u64, transactions: Vec<SignedTransaction>, receipts: Vec<BlockReceipt>) {
+        let tx_hashes = transactions
+            .iter()
+            .map(|tx| hex::encode(tx.hash().as_bytes()))
+            .collect();
+        self.bodies.write().insert(number, tx_hashes);
+        self.transactions.write().insert(number, transactions);
+        self.index_logs(number, &receipts);
+        self.receipts.write().insert(number, receipts);
+        
+        if self.pruning.is_snapshot_height(number) {
+            let accounts = self.accounts.read().clone();
+            self.snapshots.write().insert(number, accounts);
+        }
+        
+        self.bodies.write().retain(|n, _| self.pruning.keeps_body(*n, number));
+        self.receipts.write().retain(|n, _| self.pruning.keeps_body(*n, number));
+        self.transactions.write().retain(|n, _| self.pruning.keeps_body(*n, number));
+        let mut log_index = self.log_index.write();
+        for blocks in log_index.values_mut() {
+            blocks.retain(|n| self.pruning.keeps_body(*n, number));
+        }
+        log_index.retain(|_, blocks| !blocks.is_empty());
+        drop(log_index);
+        
+        let mut snapshots = self.snapshots.write();
+        let heights: Vec<u64> = snapshots.keys().copied().collect();
+        for height in self.pruning.prunable_snapshots(&heights, number) {
+            snapshots.remove(&height);
+        }
+        drop(snapshots);
+        
+        self.record_undo(number);
+    }
+    
+    /// Keep what block `number` changed since the previous block was
+    /// recorded, including changes made between the two blocks
+    fn record_undo(&self, number: u64) {
+        let mut pending = self.pending_undo.write();
+        let taken = std::mem::replace(&mut *pending, PendingUndo {
+            accounts: HashMap::new(),
+            total_supply: self.total_supply(),
+            total_burned: self.total_burned(),
+        });
+        drop(pending);
+        
+        let mut undo = self.undo.write();
+        undo.insert(number, BlockUndo {
+            accounts: taken.accounts.into_iter().collect(),
+            total_supply: taken.total_supply,
+            total_burned: taken.total_burned,
+            delayed: BTreeMap::new(),
+        });
+        let kept = undo.split_off(&number.saturating_sub(MAX_REVERT_DEPTH - 1));
+        *undo = kept;
+    }
+    
+    /// Unwind the chain to block `height`, as when a reorg replaces the
+    /// blocks above it.
+    ///
+    /// Accounts, nonces included, and supply go back to how they stood after
+    /// `height`, so changes made between blocks are undone too. Delayed
+    /// transactions the reverted blocks executed are scheduled again. Only
+    /// the last `MAX_REVERT_DEPTH` blocks recorded since startup can be
+    /// reverted. Returns the other transactions of the reverted blocks,
+    /// oldest first, for the pool to take back.
+    pub fn revert_to(&self, height: u64) -> Result<Vec<SignedTransaction>, StateError> {
+        let mut block_number = self.block_number.write();
+        let head = *block_number;
+        if height > head {
+            return Err(StateError::InvalidBlock(format!("Block {} is beyond head {}", height, head)));
+        }
+        let mut undo = self.undo.write();
+        if (height + 1..=head).any(|n| !undo.contains_key(&n)) {
+            return Err(StateError::InvalidBlock(format!(
+                "Cannot revert to block {}: only the last {} blocks recorded since startup can be reverted",
+                height, MAX_REVERT_DEPTH
+            )));
+        }
+        let reverted = undo.split_off(&(height + 1));
+        
+        let mut rescheduled = HashSet::new();
+        {
+            let mut accounts = self.accounts.write();
+            let mut pending = self.pending_undo.write();
+            let mut total_supply = self.total_supply.write();
+            let mut total_burned = self.total_burned.write();
+            let mut delayed = self.delayed.write();
+            
+            // Back to the head block first, then one block at a time
+            let since_head = BlockUndo {
+                accounts: std::mem::take(&mut pending.accounts).into_iter().collect(),
+                total_supply: pending.total_supply,
+                total_burned: pending.total_burned,
+                delayed: BTreeMap::new(),
+            };
+            for block in std::iter::once(since_head).chain(reverted.into_values().rev()) {
+                for (address, account) in block.accounts {
+                    match account {
+                        Some(account) => accounts.insert(address, account),
+                        None => accounts.remove(&address),
+                    };
+                }
+                *total_supply = block.total_supply;
+                *total_burned = block.total_burned;
+                for (due, txs) in block.delayed {
+                    rescheduled.extend(txs.iter().map(|tx| tx.hash()));
+                    delayed.entry(due).or_default().extend(txs);
+                }
+            }
+            pending.total_supply = *total_supply;
+            pending.total_burned = *total_burned;
+        }
+        
+        let reverted_blocks: Vec<BlockInfo> = {
+            let mut blocks = self.blocks.write();
+            let reverted = blocks.iter().filter(|b| b.number > height).cloned().collect();
+            blocks.retain(|b| b.number <= height);
+            if let Some(block) = blocks.last() {
+                *self.block_hash.write() = Hash::from_bytes(block.hash);
+            }
+            *block_number = height;
+            reverted
+        };
+        drop(undo);
+        drop(block_number);
+        
+        self.bodies.write().split_off(&(height + 1));
+        let mut reverted_receipts = self.receipts.write().split_off(&(height + 1));
+        // Newest first, undoing the blocks in the order they are taken back
+        for block in reverted_blocks.into_iter().rev() {
+            let receipts = reverted_receipts.remove(&block.number).unwrap_or_default();
+            self.publish_logs(block, &receipts, true);
+        }
+        self.snapshots.write().split_off(&(height + 1));
+        let transactions: Vec<SignedTransaction> = self
+            .transactions
+            .write()
+            .split_off(&(height + 1))
+            .into_values()
+            .flatten()
+            .filter(|tx| !rescheduled.contains(&tx.hash()))
+            .collect();
+        let mut log_index = self.log_index.write();
+        for blocks in log_index.values_mut() {
+            blocks.split_off(&(height + 1));
+        }
+        log_index.retain(|_, blocks| !blocks.is_empty());
+        drop(log_index);
+        let mut code_history = self.code_history.write();
+        for history in code_history.values_mut() {
+            history.split_off(&(height + 1));
+        }
+        code_history.retain(|_, history| !history.is_empty());
+        drop(code_history);
+        
+        // The write-ahead log cannot express a revert, so write it out now
+        self.flush()
+            .map_err(|e| StateError::Inconsistent(format!("Reverted to block {} but failed to persist state: {}", height, e)))?;
+        tracing::info!("Reverted {} blocks to #{}", head - height, height);
+        Ok(transactions)
+    }
+    
+    /// Blocks as they are added to or reverted from the chain, with their
+    /// receipts. A subscriber too slow to keep up with the last
+    /// `LOG_FEED_CAPACITY` blocks misses the oldest ones.
+    pub fn subscribe_logs(&self) -> tokio::sync::broadcast::Receiver<LogUpdate> {
+        self.log_feed.subscribe()
+    }
+    
+    fn publish_logs(&self, block: BlockInfo, receipts: &[BlockReceipt], removed: bool) {
+        if self.log_feed.receiver_count() > 0 {
+            let _ = self.log_feed.send(LogUpdate { block, receipts: receipts.to_vec(), removed });
+        }
+    }
+    
+    /// Note which addresses emitted logs in block `number`
+    fn index_logs(&self, number: u64, receipts: &[BlockReceipt]) {
+        let mut log_index = self.log_index.write();
+        for log in receipts.iter().flat_map(|r| &r.logs) {
+            log_index.entry(log.address).or_default().insert(number);
+        }
+    }
+    
+    /// Logs emitted in block `number`, in emission order
+    pub fn logs_in_block(&self, number: u64) -> Vec<EventLog> {
+        self.receipts
+            .read()
+            .get(&number)
+            .map(|receipts| receipts.iter().flat_map(|r| r.logs.iter().cloned()).collect())
+            .unwrap_or_default()
+    }
+    
+    /// Logs emitted by `address` in blocks `from..=to`
+    pub fn logs_for_address(&self, address: &Address, from: u64, to: u64) -> Vec<EventLog> {
+        let blocks: Vec<u64> = match self.log_index.read().get(address) {
+            Some(blocks) if from <= to => blocks.range(from..=to).copied().collect(),
+            _ => return Vec::new(),
+        };
+        blocks
+            .into_iter()
+            .flat_map(|number| self.logs_in_block(number))
+            .filter(|log| log.address == *address)
+            .collect()
+    }
+    
+    /// Genesis this state was seeded from
+    pub fn genesis(&self) -> &GenesisConfig {
+        &self.genesis
+    }
+    
+    /// Hash identifying the chain this state belongs to
+    pub fn genesis_hash(&self) -> Hash {
+        self.genesis_hash
+    }
+    
+    /// Chain id of this state. For state loaded from disk this is the chain
+    /// it was originally created for, which may differ from the genesis.
+    pub fn chain_id(&self) -> u64 {
+        *self.chain_id.read()
+    }
+    
+    /// Total coins in circulation: genesis allocations plus rewards minus burned fees
+    pub fn total_supply(&self) -> U256 {
+        *self.total_supply.read()
+    }
+    
+    /// Fees burned since genesis
+    pub fn total_burned(&self) -> U256 {
+        *self.total_burned.read()
+    }
+    
+    /// Check that account balances add up to the tracked supply
+    pub fn verify_supply(&self) -> Result<(), StateError> {
+        let accounts = self.accounts.read();
+        let actual = accounts
+            .values()
+            .fold(U256::ZERO, |acc, a| acc.saturating_add(&a.get_balance()));
+        let tracked = *self.total_supply.read();
+        
+        if actual != tracked {
+            return Err(StateError::SupplyMismatch { tracked, actual });
+        }
+        Ok(())
+    }
+    
+    /// Check that loaded state agrees with itself: block headers run without
+    /// gaps from genesis to the head, balances add up to the tracked supply
+    /// (burned fees are already deducted from both), and the state root
+    /// recorded with the file matches the accounts.
+    ///
+    /// The root is only recorded on flush, so this is meant for freshly loaded
+    /// or flushed state.
+    pub fn verify_consistency(&self) -> Result<(), StateError> {
+        let head = *self.block_number.read();
+        let head_hash = *self.block_hash.read();
+        {
+            let blocks = self.blocks.read();
+            let mut parent: Option<&BlockInfo> = None;
+            for (index, block) in blocks.iter().enumerate() {
+                if block.number != index as u64 {
+                    return Err(StateError::Inconsistent(format!(
+                        "Block #{} stored at position {}", block.number, index
+                    )));
+                }
+                if let Some(parent) = parent {
+                    if block.parent_hash != parent.hash {
+                        return Err(StateError::Inconsistent(format!(
+                            "Block #{} does not link to block #{}", block.number, parent.number
+                        )));
+                    }
+                }
+                parent = Some(block);
+            }
+            match blocks.last() {
+                Some(last) if last.number == head && last.hash == *head_hash.as_bytes() => {}
+                Some(last) => {
+                    return Err(StateError::Inconsistent(format!(
+                        "Head is block #{} but the last stored block is #{}", head, last.number
+                    )));
+                }
+                None => return Err(StateError::Inconsistent("No blocks stored".to_string())),
+            }
+        }
+        
+        self.verify_supply()?;
+        
+        if let Some(recorded) = *self.persisted_root.lock() {
+            let actual = self.state_root();
+            if actual != recorded {
+                return Err(StateError::Inconsistent(format!(
+                    "State root 0x{} does not match recorded 0x{}",
+                    hex::encode(actual),
+                    hex::encode(recorded)
+                )));
+            }
+        }
+        Ok(())
+    }
+    
+    /// Pruning policy in effect
+    pub fn pruning_config(&self) -> &PruningConfig {
+        &self.pruning
+    }
+    
+    /// Transaction hashes of a block, if its body is still retained
+    pub fn block_body(&self, number: u64) -> Option<Vec<String>> {
+        self.bodies.read().get(&number).cloned()
+    }
+    
+    /// Heights of the retained state snapshots
+    pub fn snapshot_heights(&self) -> Vec<u64> {
+        self.snapshots.read().keys().copied().collect()
+    }
+    
+    /// Account balance as of a retained snapshot
+    pub fn snapshot_balance(&self, height: u64, address: &Address) -> Option<U256> {
+        let snapshots = self.snapshots.read();
+        let accounts = snapshots.get(&height)?;
+        Some(accounts.get(address).map(|a| a.get_balance()).unwrap_or(U256::ZERO))
     }
     
     /// Get account balance
@@ -182,8 +981,95 @@ impl State {
     
     /// Transfer tokens between accounts
     pub fn transfer(&self, from: &Address, to: &Address, amount: U256) -> Result<Hash, String> {
-        let mut accounts = self.accounts.write();
+        let tx_hash = {
+            let mut accounts = self.accounts.write();
+            self.pending_undo.write().note(&accounts, &[*from, *to]);
+            self.apply_transfer(&mut accounts, from, to, amount)?
+        };
+        self.log(&WalRecord::Transfer {
+            from: hex::encode(from),
+            to: hex::encode(to),
+            amount: format!("{:x}", amount),
+        });
+        
+        // Persist after releasing the lock: persist() takes its own read locks
+        self.persist()
+            .map_err(|e| format!("Transfer succeeded but failed to persist state: {}", e))?;
+        
+        Ok(tx_hash)
+    }
+    
+    /// Apply a sponsored transaction on top of the current state.
+    ///
+    /// Both signatures are checked first: the user's over the intent and
+    /// the sponsor's over the signed intent. Value moves from the user and
+    /// gas is charged to the sponsor, with fees split as in a block.
+    pub fn apply_sponsored(&self, tx: &SponsoredTransaction, proposer: &Address) -> Result<Hash, String> {
+        verify_sponsored(tx)?;
+        
+        let block_number = self.block_number() + 1;
+        let config = self.genesis.chain_config.at_height(block_number);
+        {
+            let mut accounts = self.accounts.write();
+            let mut pending = self.pending_undo.write();
+            let sponsor = tx.sponsor();
+            let outcome = self.apply_transaction(
+                &mut accounts,
+                &mut pending.accounts,
+                &tx.inner,
+                Some(&sponsor),
+                proposer,
+                &config,
+                block_number,
+            )?;
+            self.adjust_supply(U256::ZERO, outcome.fees.burned);
+        }
+        self.log(&WalRecord::Sponsored {
+            tx: borsh::to_vec(tx).map(hex::encode).unwrap_or_default(),
+            proposer: hex::encode(proposer),
+        });
+        
+        self.persist()
+            .map_err(|e| format!("Sponsored transaction applied but failed to persist state: {}", e))?;
+        Ok(tx.hash())
+    }
+    
+    /// Debit the sum of `transfers` from `from` once, bump its nonce and
+    /// credit each recipient. On error the caller restores the touched accounts.
+    fn apply_multisend(
+        &self,
+        accounts: &mut HashMap<Address, Account>,
+        from: &Address,
+        transfers: &[(Address, U256)],
+    ) -> Result<(), String> {
+        let mut total = U256::ZERO;
+        for (_, amount) in transfers {
+            total = total
+                .checked_add(amount)
+                .ok_or_else(|| "Multisend total overflows".to_string())?;
+        }
+        let sender = accounts.entry(*from).or_default();
+        let balance = sender.get_balance();
+        if balance < total {
+            return Err(format!("Insufficient balance: have {}, need {}", balance, total));
+        }
+        sender.set_balance(balance - total);
+        sender.nonce += 1;
         
+        for (recipient, amount) in transfers {
+            credit(accounts, recipient, *amount).map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+    
+    /// Move `amount` from `from` to `to` within an already locked account map
+    fn apply_transfer(
+        &self,
+        accounts: &mut HashMap<Address, Account>,
+        from: &Address,
+        to: &Address,
+        amount: U256,
+    ) -> Result<Hash, String> {
         // Get sender state in a single read to ensure consistency
         let (sender_balance, sender_nonce) = accounts.get(from)
             .map(|a| (a.get_balance(), a.nonce))
@@ -194,6 +1080,16 @@ impl State {
             return Err(format!("Insufficient balance: have {}, need {}", sender_balance, amount));
         }
         
+        // Check the receiver can hold the amount before touching either account
+        let receiver_balance = if from == to {
+            sender_balance - amount
+        } else {
+            accounts.get(to).map(|a| a.get_balance()).unwrap_or(U256::ZERO)
+        };
+        let new_receiver_balance = receiver_balance
+            .checked_add(&amount)
+            .ok_or_else(|| StateError::BalanceOverflow(*to).to_string())?;
+        
         // Compute tx hash before modifying
         let new_nonce = sender_nonce + 1;
         let tx_hash = self.compute_tx_hash(from, to, amount, new_nonce);
@@ -204,14 +1100,9 @@ impl State {
             sender.nonce = new_nonce;
         }
         
-        // Get receiver balance AFTER sender update (from updated HashMap)
-        let receiver_balance = accounts.get(to)
-            .map(|a| a.get_balance())
-            .unwrap_or(U256::ZERO);
-        
         // Update receiver
         if let Some(receiver) = accounts.get_mut(to) {
-            receiver.set_balance(receiver_balance + amount);
+            receiver.set_balance(new_receiver_balance);
         } else {
             accounts.insert(*to, Account {
                 balance: format!("{:x}", amount),
@@ -221,14 +1112,6 @@ impl State {
             });
         }
         
-        // Persist to disk BEFORE releasing lock to prevent race conditions
-        if let Err(e) = self.persist() {
-            drop(accounts);
-            return Err(format!("Transfer succeeded but failed to persist state: {}", e));
-        }
-        
-        drop(accounts);
-        
         Ok(tx_hash)
     }
     
@@ -242,37 +1125,108 @@ impl State {
         *self.block_hash.read()
     }
     
+    /// Make blocks produced by this node move the gas limit towards
+    /// `target`, within the bounds the chain allows per block. `None`
+    /// follows block utilization.
+    pub fn set_gas_limit_target(&self, target: Option<u64>) {
+        *self.gas_limit_target.write() = target;
+    }
+    
+    /// Write state to disk as `policy` allows instead of after every mutation.
+    /// Unflushed changes are lost on a crash; call [`State::flush`] on shutdown.
+    pub fn set_commit_policy(&self, policy: CommitPolicy) {
+        *self.commit_policy.write() = policy;
+    }
+    
+    /// Blocks until a block is final. Whatever the commit policy, state is
+    /// flushed as each block becomes final, so none is final before it is
+    /// on disk.
+    pub fn set_finality_threshold(&self, threshold: Option<u64>) {
+        *self.finality_threshold.write() = threshold;
+    }
+    
+    /// Gas limit of the next block, derived from the latest block's limit and usage.
+    ///
+    /// Heartbeat blocks carry no transactions and say nothing about demand,
+    /// so the limit stays where they left it unless the proposer targets
+    /// another one.
+    pub fn next_gas_limit(&self) -> u64 {
+        let blocks = self.blocks.read();
+        let config = self.genesis.chain_config.at_height(blocks.last().map_or(0, |b| b.number) + 1);
+        let target = *self.gas_limit_target.read();
+        match blocks.last().filter(|parent| parent.gas_limit > 0) {
+            Some(parent) if parent.tx_count == 0 => {
+                config.next_gas_limit(parent.gas_limit, parent.gas_used, Some(target.unwrap_or(parent.gas_limit)))
+            }
+            Some(parent) => config.next_gas_limit(parent.gas_limit, parent.gas_used, target),
+            None => config.gas_limit,
+        }
+    }
+    
+    /// Check the timestamp of the next block: after genesis, so no block
+    /// precedes the chain's start, and not before its parent
+    pub fn check_block_timestamp(&self, timestamp: u64) -> Result<(), StateError> {
+        let genesis = self.genesis.timestamp;
+        if timestamp <= genesis {
+            return Err(StateError::InvalidBlock(format!(
+                "Block timestamp {} is not after genesis timestamp {}",
+                timestamp, genesis
+            )));
+        }
+        let parent = self.blocks.read().last().map_or(genesis, |b| b.timestamp);
+        if timestamp < parent {
+            return Err(StateError::InvalidBlock(format!(
+                "Block timestamp {} is before parent timestamp {}",
+                timestamp, parent
+            )));
+        }
+        Ok(())
+    }
+    
     /// Increment block number (called when block is produced)
     /// Returns the new block hash
     pub fn increment_block(&self) -> [u8; 32] {
-        let (new_hash, _block_info) = {
-            let mut block = self.block_number.write();
+        self.increment_block_stamped(None)
+    }
+    
+    /// `increment_block`, with the header fields of a replayed block
+    fn increment_block_stamped(&self, stamp: Option<BlockStamp>) -> [u8; 32] {
+        let state_root = self.state_root();
+        let stamp = stamp.unwrap_or_else(|| BlockStamp { timestamp: unix_now(), gas_limit: self.next_gas_limit(), hash: None });
+        let gas_limit = stamp.gas_limit;
+        let (new_hash, block_info) = {
+            let mut block = self.block_number.write();
             let mut hash = self.block_hash.write();
             let mut blocks = self.blocks.write();
             
             *block += 1;
             let parent = *hash;
             
-            // Compute new block hash using blake3
-            let new_hash = self.compute_block_hash(*block, parent.as_bytes());
-            *hash = Hash::from_bytes(new_hash);
-            
-            // Store block info
-            let block_info = BlockInfo {
+            let mut block_info = BlockInfo {
                 number: *block,
-                hash: new_hash,
+                hash: [0u8; 32],
                 parent_hash: *parent.as_bytes(),
-                timestamp: std::time::SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .map(|d| d.as_secs())
-                    .unwrap_or(0),
+                timestamp: stamp.timestamp,
                 tx_count: 0,
+                state_root,
+                transactions_root: transactions_root(&[]),
+                proposer: Address::ZERO,
+                gas_used: 0,
+                gas_limit,
+                reward: String::new(),
+                parent_attestation: None,
             };
+            block_info.hash = block_info.compute_hash();
+            let new_hash = block_info.hash;
+            *hash = Hash::from_bytes(new_hash);
             blocks.push(block_info.clone());
             
             (new_hash, block_info)
         };
         
+        self.record_block(block_info.number, Vec::new(), Vec::new());
+        self.log(&WalRecord::IncrementBlock { stamp });
+        
         // Persist (outside of lock scope)
         let _ = self.persist();
         
@@ -283,7 +1237,7 @@ impl State {
     /// 
     /// Block reward structure:
     /// - Base reward: Fixed amount for producing a block
-    /// - Tx fees: Variable based on transaction gas used
+    /// - Tx fees: Paid by senders and split per `FeeDistribution::split`
     /// - Activity bonus: Extra reward if network is active (has transactions)
     /// 
     /// Strategy:
@@ -293,23 +1247,49 @@ impl State {
     pub fn produce_block(
         &self,
         validator: &Address,
-        transactions: Vec<Transaction>,
+        transactions: Vec<SignedTransaction>,
+        is_heartbeat: bool,
+    ) -> Result<BlockProductionResult, StateError> {
+        self.produce_block_stamped(validator, transactions, is_heartbeat, None, None)
+    }
+    
+    /// `produce_block`, with the header fields of a replayed or imported
+    /// block. With `parent` set the block is refused unless it still
+    /// extends that head once the chain is locked.
+    fn produce_block_stamped(
+        &self,
+        validator: &Address,
+        mut transactions: Vec<SignedTransaction>,
         is_heartbeat: bool,
+        stamp: Option<BlockStamp>,
+        parent: Option<Hash>,
     ) -> Result<BlockProductionResult, StateError> {
+        let submitted: Vec<String> = transactions
+            .iter()
+            .filter_map(|tx| borsh::to_vec(tx).ok())
+            .map(hex::encode)
+            .collect();
+        
         // Acquire write lock early to prevent race conditions
         let mut block_number_guard = self.block_number.write();
         let block_number = *block_number_guard + 1;
+        if parent.is_some_and(|parent| parent != *self.block_hash.read()) {
+            return Err(StateError::InvalidBlock(format!("Block #{} does not extend the current head", block_number)));
+        }
+        let stamp = stamp.unwrap_or_else(|| BlockStamp { timestamp: unix_now(), gas_limit: self.next_gas_limit(), hash: None });
+        self.check_block_timestamp(stamp.timestamp)?;
+        
+        // Delayed transactions due at this height run first
+        let due_delayed = self.take_due_delayed(block_number);
+        let mut due: Vec<SignedTransaction> = due_delayed.values().flatten().cloned().collect();
+        if !due.is_empty() {
+            due.append(&mut transactions);
+            transactions = due;
+        }
         
         // Calculate rewards
         let base_reward = U256::from(2_000_000_000_000_000_000u128); // 2 MERK
         
-        // Transaction fees calculation (using max_fee_per_gas) with overflow protection
-        let tx_fees: U256 = transactions.iter()
-            .filter_map(|tx| {
-                tx.max_fee_per_gas.checked_mul(&U256::from(tx.gas_limit))
-            })
-            .fold(U256::ZERO, |acc, fee| acc.saturating_add(&fee));
-        
         // Activity bonus: Extra 1 MERK if we have transactions
         let activity_bonus = if !transactions.is_empty() {
             U256::from(1_000_000_000_000_000_000u128) // 1 MERK bonus
@@ -324,106 +1304,369 @@ impl State {
             U256::ONE
         };
         
-        let total_reward = (base_reward + tx_fees + activity_bonus) * heartbeat_multiplier;
+        let total_reward = (base_reward + activity_bonus) * heartbeat_multiplier;
         
         // Execute transactions
-        for tx in &transactions {
-            if let Some(to) = tx.to {
-                match self.transfer(&self.get_sender(tx), &to, tx.value) {
-                    Ok(_) => {},
+        let config = self.genesis.chain_config.at_height(block_number);
+        let gas_limit = stamp.gas_limit;
+        let mut fees = FeeDistribution::default();
+        let mut receipts = Vec::with_capacity(transactions.len());
+        
+        // The block is applied to a staged copy and swapped in with the new
+        // head, so readers see the accounts before or after the whole block,
+        // never part of it. The upgradable lock keeps other writers out
+        // while letting readers through.
+        let accounts_guard = self.accounts.upgradable_read();
+        let mut staged = accounts_guard.clone();
+        let mut touched = HashMap::new();
+        {
+            let accounts = &mut staged;
+            
+            // Mint the reward first: if it cannot be credited nothing has changed yet
+            touched.insert(*validator, accounts.get(validator).cloned());
+            credit(accounts, validator, total_reward)?;
+            
+            let base_fee = config.min_base_fee;
+            let mut cumulative_gas_used = 0u64;
+            let mut log_count = 0usize;
+            for tx in &transactions {
+                let gas_left = gas_limit.saturating_sub(cumulative_gas_used);
+                let result = if tx.tx.gas_limit > gas_left {
+                    Err(format!("Gas limit {} exceeds the {} left in the block", tx.tx.gas_limit, gas_left))
+                } else {
+                    self.apply_transaction(accounts, &mut touched, tx, None, validator, &config, block_number)
+                };
+                let (success, gas_used, logs) = match result {
+                    Ok(outcome) => {
+                        fees.accumulate(&outcome.fees);
+                        (true, outcome.gas_used, outcome.logs)
+                    }
                     Err(e) => {
                         tracing::warn!("Transaction failed in block production: {}", e);
                         // Continue with other transactions
+                        (false, 0, Vec::new())
                     }
-                }
+                };
+                cumulative_gas_used += gas_used;
+                let tx_index = receipts.len();
+                let logs = logs
+                    .into_iter()
+                    .map(|entry| {
+                        let log = EventLog {
+                            address: entry.address,
+                            topics: entry.topics.into_iter().map(Hash::from_bytes).collect(),
+                            data: entry.data.to_vec(),
+                            block_number,
+                            tx_index,
+                            log_index: log_count,
+                        };
+                        log_count += 1;
+                        log
+                    })
+                    .collect();
+                receipts.push(BlockReceipt {
+                    tx_hash: tx.hash(),
+                    from: tx.sender(),
+                    to: tx.tx.to,
+                    success,
+                    gas_used,
+                    cumulative_gas_used,
+                    effective_gas_price: tx.effective_gas_price(&base_fee),
+                    logs,
+                });
             }
         }
         
-        // Mint reward to validator
-        self.mint_to_validator(validator, total_reward)?;
-        
         // Create and store block - inline increment_block logic to avoid race conditions
-        let new_hash = {
+        let state_root = accounts_root(&staged);
+        let block_info = {
+            let mut accounts = RwLockUpgradableReadGuard::upgrade(accounts_guard);
             let mut hash = self.block_hash.write();
             let mut blocks = self.blocks.write();
             
+            *accounts = staged;
+            let mut pending = self.pending_undo.write();
+            for (address, account) in touched {
+                pending.accounts.entry(address).or_insert(account);
+            }
+            drop(pending);
+            // Supply moves while balances are still locked
+            self.adjust_supply(total_reward, fees.burned);
+            
             // Increment block number
             *block_number_guard += 1;
             let parent = *hash;
             
-            // Compute new block hash using blake3
-            let new_hash = self.compute_block_hash(*block_number_guard, parent.as_bytes());
-            *hash = Hash::from_bytes(new_hash);
-            
-            // Store block info
-            let block_info = BlockInfo {
+            let mut block_info = BlockInfo {
                 number: *block_number_guard,
-                hash: new_hash,
+                hash: [0u8; 32],
                 parent_hash: *parent.as_bytes(),
-                timestamp: std::time::SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .map(|d| d.as_secs())
-                    .unwrap_or(0),
+                timestamp: stamp.timestamp,
                 tx_count: transactions.len(),
+                state_root,
+                transactions_root: transactions_root(&transactions),
+                proposer: *validator,
+                gas_used: receipts.last().map_or(0, |r| r.cumulative_gas_used),
+                gas_limit,
+                reward: format!("{:x}", total_reward),
+                parent_attestation: None,
             };
-            blocks.push(block_info);
+            block_info.hash = stamp.hash.unwrap_or_else(|| block_info.compute_hash());
+            *hash = Hash::from_bytes(block_info.hash);
+            blocks.push(block_info.clone());
             
-            new_hash
+            block_info
         };
+        let new_hash = block_info.hash;
+        drop(block_number_guard);
+        
+        let transactions_count = transactions.len();
+        self.publish_logs(block_info, &receipts, false);
+        self.record_block(block_number, transactions, receipts);
+        if let Some(undo) = self.undo.write().get_mut(&block_number) {
+            undo.delayed = due_delayed;
+        }
+        self.log(&WalRecord::ProduceBlock {
+            validator: hex::encode(validator),
+            transactions: submitted,
+            is_heartbeat,
+            stamp,
+        });
         
         // Persist (outside of lock scope)
         let _ = self.persist();
         
         tracing::info!(
-            "Block #{} produced by {}: {} txs, reward: {} MERK (base: {}, fees: {}, bonus: {})",
+            "Block #{} produced by {}: {} txs, reward: {} MERK (base: {}, bonus: {}), fees: {} Spark ({} burned)",
             block_number,
             hex::encode(validator),
-            transactions.len(),
+            transactions_count,
             total_reward / U256::from(1_000_000_000_000_000_000u128),
             base_reward / U256::from(1_000_000_000_000_000_000u128),
-            tx_fees / U256::from(1_000_000_000_000_000_000u128),
-            activity_bonus / U256::from(1_000_000_000_000_000_000u128)
+            activity_bonus / U256::from(1_000_000_000_000_000_000u128),
+            fees.total(),
+            fees.burned
         );
         
         Ok(BlockProductionResult {
             block_number,
             block_hash: new_hash,
-            transactions_count: transactions.len(),
+            transactions_count,
             validator_reward: total_reward,
+            fees,
         })
     }
     
-    /// Get sender from transaction (simplified - should verify signature)
-    fn get_sender(&self, tx: &Transaction) -> Address {
-        // In a real implementation, recover sender from signature
-        // For now, return a dummy address
-        Address::from_bytes([0u8; 20])
+    /// Run `tx` as if it were included in the next block after `pending`.
+    ///
+    /// Pending transactions are applied in order to a copy of the accounts,
+    /// skipping those that fail as block production does. Nothing is written
+    /// back, so the committed state is unchanged.
+    pub fn simulate_pending(
+        &self,
+        pending: &[SignedTransaction],
+        tx: &SignedTransaction,
+    ) -> SimulationResult {
+        let mut accounts = self.accounts.read().clone();
+        let block_number = self.block_number() + 1;
+        let config = self.genesis.chain_config.at_height(block_number);
+        
+        for pending_tx in pending {
+            let _ = self.apply_transaction(&mut accounts, &mut HashMap::new(), pending_tx, None, &Address::ZERO, &config, block_number);
+        }
+        
+        match self.apply_transaction(&mut accounts, &mut HashMap::new(), tx, None, &Address::ZERO, &config, block_number) {
+            Ok(outcome) => SimulationResult {
+                success: true,
+                gas_used: outcome.gas_used,
+                error: None,
+                block_number,
+            },
+            Err(e) => SimulationResult {
+                success: false,
+                gas_used: 0,
+                error: Some(e),
+                block_number,
+            },
+        }
     }
     
-    /// Mint new coins to validator as block reward
-    fn mint_to_validator(&self, validator: &Address, amount: U256) -> Result<(), StateError> {
-        let mut accounts = self.accounts.write();
+    /// Execute a signed transfer inside a block, charging its gas fee and
+    /// paying the proposer and treasury their shares. A failed transaction
+    /// leaves every account untouched.
+    ///
+    /// Accounts the transaction may change are added to `touched` as they
+    /// stood before it, unless already there, for the block's undo record.
+    ///
+    /// The base fee is the chain's `min_base_fee`; State does not run the
+    /// dynamic fee market yet.
+    #[allow(clippy::too_many_arguments)]
+    fn apply_transaction(
+        &self,
+        accounts: &mut HashMap<Address, Account>,
+        touched: &mut HashMap<Address, Option<Account>>,
+        tx: &SignedTransaction,
+        sponsor: Option<&Address>,
+        proposer: &Address,
+        config: &ChainConfig,
+        block_number: u64,
+    ) -> Result<TxOutcome, String> {
+        let mut changed = vec![
+            tx.sender(),
+            tx.tx.to.unwrap_or(Address::ZERO),
+            sponsor.copied().unwrap_or(Address::ZERO),
+            *proposer,
+            config.treasury_address,
+        ];
+        if let Ok(transfers) = tx.tx.multisend_transfers() {
+            changed.extend(transfers.iter().map(|(recipient, _)| *recipient));
+        }
+        let saved: Vec<(Address, Option<Account>)> = changed
+            .iter()
+            .map(|address| (*address, accounts.get(address).cloned()))
+            .collect();
+        for (address, account) in &saved {
+            touched.entry(*address).or_insert_with(|| account.clone());
+        }
         
-        // Get or create validator account
-        let validator_account = accounts.entry(*validator).or_insert_with(|| Account {
-            balance: "0x0".to_string(),
-            nonce: 0,
-            code: vec![],
-            storage: HashMap::new(),
-        });
+        let result = self.execute_transaction(accounts, tx, sponsor, proposer, config, block_number);
+        if result.is_err() {
+            for (address, account) in saved {
+                match account {
+                    Some(account) => accounts.insert(address, account),
+                    None => accounts.remove(&address),
+                };
+            }
+        }
+        result
+    }
+    
+    /// Run `tx`, charging gas to `sponsor` if given and to the sender otherwise
+    fn execute_transaction(
+        &self,
+        accounts: &mut HashMap<Address, Account>,
+        tx: &SignedTransaction,
+        sponsor: Option<&Address>,
+        proposer: &Address,
+        config: &ChainConfig,
+        block_number: u64,
+    ) -> Result<TxOutcome, String> {
+        let sender = tx.sender();
+        if (tx.tx.tx_type == TransactionType::Sponsored) != sponsor.is_some() {
+            return Err("Sponsored intents are only valid when submitted by a sponsor".to_string());
+        }
+        let payer = sponsor.copied().unwrap_or(sender);
+        
+        // A multisend pays its recipients instead of calling `to`
+        let transfers = match tx.tx.tx_type {
+            TransactionType::Multisend => Some(tx.tx.multisend_transfers().map_err(|e| e.to_string())?),
+            _ => None,
+        };
+        let to = match transfers {
+            Some(_) => Address::ZERO,
+            None => tx.tx.to
+                .ok_or_else(|| "Contract creation is not supported in block production".to_string())?,
+        };
+        let intrinsic_gas = transfers.as_ref().map_or(TRANSFER_GAS, |t| multisend_gas(t.len()));
+        
+        let (balance, nonce) = accounts.get(&sender)
+            .map(|a| (a.get_balance(), a.nonce))
+            .unwrap_or((U256::ZERO, 0));
+        if tx.tx.nonce != nonce {
+            return Err(format!("Invalid nonce: expected {}, got {}", nonce, tx.tx.nonce));
+        }
+        if tx.tx.is_expired(block_number) {
+            return Err(format!("Transaction expired after block {:?}", tx.tx.valid_until_block));
+        }
+        if tx.tx.is_premature(block_number) {
+            return Err(format!("Transaction is delayed until block {:?}", tx.tx.execute_after_block));
+        }
+        if tx.tx.gas_limit < intrinsic_gas {
+            return Err(format!("Gas limit {} below intrinsic gas {}", tx.tx.gas_limit, intrinsic_gas));
+        }
+        
+        let base_fee = config.min_base_fee;
+        if tx.tx.max_fee_per_gas < base_fee {
+            return Err(format!("Max fee per gas {} below base fee {}", tx.tx.max_fee_per_gas, base_fee));
+        }
+        
+        // Calls into a contract run its code; a revert fails the transaction
+        let (code, storage) = match transfers {
+            Some(_) => Default::default(),
+            None => accounts
+                .get(&to)
+                .map(|a| (a.code.clone(), a.storage_words()))
+                .unwrap_or_default(),
+        };
+        let (gas_used, logs, storage_writes) = if transfers.is_some() {
+            (intrinsic_gas, Vec::new(), HashMap::new())
+        } else if code.is_empty() {
+            (TRANSFER_GAS, Vec::new(), HashMap::new())
+        } else {
+            let result = call_contract(tx, to, code, storage, block_number)?;
+            (result.gas_used.max(TRANSFER_GAS), result.logs, result.state_changes.storage)
+        };
+        
+        let fees = FeeDistribution::split(config, &base_fee, &tx.effective_gas_price(&base_fee), gas_used);
+        let payer_balance = if payer == sender {
+            let total_cost = tx.tx.value
+                .checked_add(&fees.total())
+                .ok_or_else(|| format!("Cost of value {} plus fees overflows", tx.tx.value))?;
+            if balance < total_cost {
+                return Err(format!("Insufficient balance: have {}, need {}", balance, total_cost));
+            }
+            balance
+        } else {
+            let sponsor_balance = accounts.get(&payer).map(|a| a.get_balance()).unwrap_or(U256::ZERO);
+            if sponsor_balance < fees.total() {
+                return Err(format!("Sponsor cannot cover fees: have {}, need {}", sponsor_balance, fees.total()));
+            }
+            sponsor_balance
+        };
         
-        let current_balance = validator_account.get_balance();
-        validator_account.set_balance(current_balance + amount);
+        // Debit the fee, then move the value (which also bumps the nonce)
+        if let Some(account) = accounts.get_mut(&payer) {
+            account.set_balance(payer_balance - fees.total());
+        }
+        match &transfers {
+            Some(transfers) => self.apply_multisend(accounts, &sender, transfers)?,
+            None => {
+                self.apply_transfer(accounts, &sender, &to, tx.tx.value)?;
+            }
+        }
+        // The contract is in the rollback set, so its writes undo with the rest
+        if let Some(contract) = accounts.get_mut(&to) {
+            for ((_, key), value) in storage_writes {
+                match value {
+                    Some(value) => contract.storage.insert(hex::encode(key), hex::encode(value)),
+                    None => contract.storage.remove(&hex::encode(key)),
+                };
+            }
+        }
+        credit(accounts, proposer, fees.to_proposer).map_err(|e| e.to_string())?;
+        credit(accounts, &config.treasury_address, fees.to_treasury).map_err(|e| e.to_string())?;
         
-        // Update total supply
+        Ok(TxOutcome { fees, gas_used, logs })
+    }
+    
+    /// Record minted and burned coins. Callers hold the accounts write lock
+    /// so balances and supply change together.
+    fn adjust_supply(&self, minted: U256, burned: U256) {
         let mut total_supply = self.total_supply.write();
-        *total_supply += amount;
+        *total_supply = total_supply.saturating_add(&minted).saturating_sub(&burned);
         
-        Ok(())
+        if !burned.is_zero() {
+            let mut total_burned = self.total_burned.write();
+            *total_burned = total_burned.saturating_add(&burned);
+        }
     }
     
     /// Add a block from network sync
     pub fn add_block(&self, number: u64, hash: [u8; 32], parent_hash: [u8; 32]) -> bool {
+        self.add_block_at(number, hash, parent_hash, unix_now())
+    }
+    
+    fn add_block_at(&self, number: u64, hash: [u8; 32], parent_hash: [u8; 32], timestamp: u64) -> bool {
         let current = *self.block_number.read();
         
         // Only accept if it extends our chain
@@ -447,19 +1690,186 @@ impl State {
                 number,
                 hash,
                 parent_hash,
-                timestamp: std::time::SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .map(|d| d.as_secs())
-                    .unwrap_or(0),
+                timestamp,
                 tx_count: 0,
+                state_root: [0u8; 32],
+                transactions_root: transactions_root(&[]),
+                proposer: Address::ZERO,
+                gas_used: 0,
+                gas_limit: 0,
+                reward: String::new(),
+                parent_attestation: None,
             });
         }
         
+        self.record_block(number, Vec::new(), Vec::new());
+        self.log(&WalRecord::AddBlock {
+            number,
+            hash: hex::encode(hash),
+            parent_hash: hex::encode(parent_hash),
+            timestamp,
+        });
+        
         let _ = self.persist();
         tracing::info!("Added block #{} from network", number);
         true
     }
     
+    /// Execute a block produced elsewhere on top of the current head.
+    ///
+    /// The block must extend the head, carry a timestamp and gas limit the
+    /// chain allows, commit to its transactions, and every transaction must
+    /// be correctly signed and execute. Otherwise it is refused and nothing
+    /// changes. The block is known by its header's hash.
+    pub fn import_block(&self, block: &Block) -> Result<BlockProductionResult, StateError> {
+        let header = &block.header;
+        let number = self.block_number() + 1;
+        if header.number != number {
+            return Err(StateError::InvalidBlock(format!("Expected block #{}, got #{}", number, header.number)));
+        }
+        let head = self.block_hash();
+        if header.parent_hash != head {
+            return Err(StateError::InvalidBlock(format!(
+                "Parent {} is not the current head {}",
+                header.parent_hash, head
+            )));
+        }
+        self.check_block_timestamp(header.timestamp)?;
+        if *header.transactions_root.as_bytes() != transactions_root(&block.transactions) {
+            return Err(StateError::InvalidBlock("Transactions root does not match the transactions".to_string()));
+        }
+
+        let config = self.genesis.chain_config.at_height(number);
+        let (lower, upper) = match self.blocks.read().last().filter(|parent| parent.gas_limit > 0) {
+            Some(parent) => config.gas_limit_bounds(parent.gas_limit),
+            None => (config.gas_limit, config.gas_limit),
+        };
+        if header.gas_limit < lower || header.gas_limit > upper {
+            return Err(StateError::InvalidBlock(format!(
+                "Gas limit {} outside the allowed {}..={}",
+                header.gas_limit, lower, upper
+            )));
+        }
+
+        // Dry run on a copy, after the delayed transactions the block will run first
+        let mut accounts = self.accounts.read().clone();
+        let due: Vec<SignedTransaction> = self.delayed.read().range(..=number).flat_map(|(_, txs)| txs.clone()).collect();
+        for tx in &due {
+            let _ = self.apply_transaction(&mut accounts, &mut HashMap::new(), tx, None, &header.proposer, &config, number);
+        }
+        let mut gas_used = 0u64;
+        for tx in &block.transactions {
+            merklith_crypto::ed25519_verify(&tx.public_key, tx.tx.signing_hash().as_bytes(), &tx.signature)
+                .map_err(|_| StateError::InvalidTransaction(format!("Bad signature on {}", tx.hash())))?;
+            let gas_left = header.gas_limit.saturating_sub(gas_used);
+            if tx.tx.gas_limit > gas_left {
+                return Err(StateError::InvalidBlock(format!("Transactions exceed the gas limit of {}", header.gas_limit)));
+            }
+            let outcome = self.apply_transaction(&mut accounts, &mut HashMap::new(), tx, None, &header.proposer, &config, number)
+                .map_err(|e| StateError::InvalidTransaction(format!("{}: {}", tx.hash(), e)))?;
+            gas_used += outcome.gas_used;
+        }
+
+        let stamp = BlockStamp {
+            timestamp: header.timestamp,
+            gas_limit: header.gas_limit,
+            hash: Some(*block.hash().as_bytes()),
+        };
+        self.produce_block_stamped(&header.proposer, block.transactions.clone(), false, Some(stamp), Some(head))
+    }
+    
+    /// Keep the committee's attestation of block `number - 1` with block
+    /// `number`. Returns false if the block is not retained.
+    pub fn attach_attestation(&self, number: u64, attestation: ParentAttestation) -> bool {
+        {
+            let mut blocks = self.blocks.write();
+            let Some(block) = blocks.iter_mut().find(|b| b.number == number) else {
+                return false;
+            };
+            block.parent_attestation = Some(attestation.clone());
+        }
+        self.log(&WalRecord::Attest {
+            number,
+            signature: hex::encode(&attestation.signature),
+            bitfield: hex::encode(&attestation.bitfield),
+        });
+        let _ = self.persist();
+        true
+    }
+    
+    /// Receipts of block `number` in transaction order, or None if the block
+    /// is unknown or its receipts were pruned
+    pub fn block_receipts(&self, number: u64) -> Option<Vec<BlockReceipt>> {
+        self.receipts.read().get(&number).cloned()
+    }
+    
+    /// Find a mined transaction by hash: its block number, index in the
+    /// block and the transaction. Scans retained bodies, newest first.
+    pub fn mined_transaction(&self, hash: &Hash) -> Option<(u64, usize, SignedTransaction)> {
+        let needle = hex::encode(hash.as_bytes());
+        let (number, index) = self.bodies.read().iter().rev().find_map(|(number, hashes)| {
+            hashes.iter().position(|h| *h == needle).map(|index| (*number, index))
+        })?;
+        let tx = self.transactions.read().get(&number)?.get(index).cloned()?;
+        Some((number, index, tx))
+    }
+    
+    /// Re-execute a mined transaction with step tracing.
+    ///
+    /// The state before the transaction is rebuilt from the newest snapshot
+    /// below its block by replaying the retained blocks in between, then the
+    /// transactions ahead of it in its own block, crediting each block's
+    /// reward first as production did. Without such a snapshot the replay
+    /// starts from the genesis allocation. Plain transfers return a result
+    /// with an empty trace.
+    pub fn trace_transaction(&self, hash: &Hash) -> Result<merklith_vm::ExecutionResult, String> {
+        let (number, index, tx) = self
+            .mined_transaction(hash)
+            .ok_or_else(|| format!("Transaction {} not found", hash))?;
+        
+        let (base, mut accounts) = self
+            .snapshots
+            .read()
+            .range(..number)
+            .next_back()
+            .map(|(height, accounts)| (*height, accounts.clone()))
+            .unwrap_or_else(|| (0, genesis_accounts(&self.genesis)));
+        
+        for height in base + 1..=number {
+            let block_txs = self
+                .transactions
+                .read()
+                .get(&height)
+                .cloned()
+                .ok_or_else(|| format!("Block {} is no longer retained", height))?;
+            let block = self
+                .get_block(height)
+                .ok_or_else(|| format!("Block {} not found", height))?;
+            let proposer = block.proposer;
+            credit(&mut accounts, &proposer, block.reward()).map_err(|e| e.to_string())?;
+            let config = self.genesis.chain_config.at_height(height);
+            let count = if height == number { index } else { block_txs.len() };
+            for replayed in &block_txs[..count] {
+                let _ = self.apply_transaction(&mut accounts, &mut HashMap::new(), replayed, None, &proposer, &config, height);
+            }
+        }
+        
+        let Some(to) = tx.tx.to else {
+            return Ok(merklith_vm::ExecutionResult::success(bytes::Bytes::new(), TRANSFER_GAS));
+        };
+        let (code, storage) = accounts
+            .get(&to)
+            .map(|a| (a.code.clone(), a.storage_words()))
+            .unwrap_or_default();
+        if code.is_empty() {
+            return Ok(merklith_vm::ExecutionResult::success(bytes::Bytes::new(), TRANSFER_GAS));
+        }
+        
+        let vm = merklith_vm::MerklithVM::new().map_err(|e| format!("Failed to create VM: {}", e))?;
+        vm.execute(contract_context(&tx, to, code, storage, number).with_tracing())
+            .map_err(|e| format!("Contract execution failed: {}", e))
+    }
+    
     /// Get block by number
     pub fn get_block(&self, number: u64) -> Option<BlockInfo> {
         let blocks = self.blocks.read();
@@ -471,16 +1881,172 @@ impl State {
         let blocks = self.blocks.read();
         blocks.iter().any(|b| &b.hash == hash)
     }
+
+    /// Number of the block with given hash
+    pub fn block_number_by_hash(&self, hash: &[u8; 32]) -> Option<u64> {
+        let blocks = self.blocks.read();
+        blocks.iter().find(|b| &b.hash == hash).map(|b| b.number)
+    }
+
+    /// Commitment to every account: blake3 over accounts sorted by address,
+    /// each with its nonce, balance, code hash and sorted storage
+    pub fn state_root(&self) -> [u8; 32] {
+        accounts_root(&self.accounts.read())
+    }
     
-    fn compute_block_hash(&self, number: u64, parent_hash: &[u8; 32]) -> [u8; 32] {
-        let mut hasher = blake3::Hasher::new();
-        hasher.update(parent_hash);
-        hasher.update(&number.to_le_bytes());
-        hasher.update(&std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .map(|d| d.as_secs())
-            .unwrap_or(0).to_le_bytes());
-        *hasher.finalize().as_bytes()
+    /// Hold a signed transaction until its `execute_after_block`, when block
+    /// production includes it ahead of pool transactions. The sender pays
+    /// [`SCHEDULE_GAS`] at the base fee up front, burned whether or not the
+    /// transaction later runs.
+    pub fn schedule_transaction(&self, tx: SignedTransaction) -> Result<Hash, String> {
+        let target = tx.tx.execute_after_block
+            .ok_or_else(|| "Transaction has no execute_after_block".to_string())?;
+        let next_block = self.block_number() + 1;
+        if target <= next_block {
+            return Err(format!("Delayed transaction must target a block after {}, got {}", next_block, target));
+        }
+        self.check_chain_id(&tx)?;
+        merklith_crypto::ed25519_verify(&tx.public_key, tx.tx.signing_hash().as_bytes(), &tx.signature)
+            .map_err(|e| format!("Invalid signature: {}", e))?;
+        
+        let hash = tx.hash();
+        let sender = tx.sender();
+        let fee = self.genesis.chain_config.at_height(next_block).min_base_fee * U256::from(SCHEDULE_GAS);
+        {
+            let mut accounts = self.accounts.write();
+            let mut delayed = self.delayed.write();
+            let pending = delayed.values().flatten();
+            if pending.clone().any(|d| d.hash() == hash) {
+                return Err(format!("Transaction {} is already scheduled", hash));
+            }
+            if pending.filter(|d| d.sender() == sender).count() >= MAX_QUEUED_PER_SENDER {
+                return Err(format!("Too many delayed transactions for {}", sender));
+            }
+            let balance = accounts.get(&sender).map(|a| a.get_balance()).unwrap_or(U256::ZERO);
+            if balance < fee {
+                return Err(format!("Insufficient balance for scheduling fee: have {}, need {}", balance, fee));
+            }
+            self.pending_undo.write().note(&accounts, &[sender]);
+            if let Some(account) = accounts.get_mut(&sender) {
+                account.set_balance(balance - fee);
+            }
+            self.adjust_supply(U256::ZERO, fee);
+            self.log(&WalRecord::Schedule { tx: borsh::to_vec(&tx).map(hex::encode).unwrap_or_default() });
+            delayed.entry(target).or_default().push(tx);
+        }
+        
+        self.persist()
+            .map_err(|e| format!("Transaction scheduled but failed to persist state: {}", e))?;
+        Ok(hash)
+    }
+    
+    /// Drop a delayed transaction before it executes. `cancel` must be a
+    /// `CancelDelayed` transaction signed by the delayed transaction's sender.
+    pub fn cancel_delayed(&self, cancel: &SignedTransaction) -> Result<Hash, String> {
+        if cancel.tx.tx_type != TransactionType::CancelDelayed {
+            return Err("Not a cancel transaction".to_string());
+        }
+        let target: [u8; 32] = cancel.tx.data.as_slice().try_into()
+            .map_err(|_| "Cancel data must be a 32-byte transaction hash".to_string())?;
+        let target = Hash::from_bytes(target);
+        self.check_chain_id(cancel)?;
+        merklith_crypto::ed25519_verify(&cancel.public_key, cancel.tx.signing_hash().as_bytes(), &cancel.signature)
+            .map_err(|e| format!("Invalid signature: {}", e))?;
+        
+        {
+            let mut delayed = self.delayed.write();
+            let (block, index) = delayed
+                .iter()
+                .find_map(|(block, txs)| txs.iter().position(|tx| tx.hash() == target).map(|i| (*block, i)))
+                .ok_or_else(|| format!("No delayed transaction {}", target))?;
+            let txs = delayed.get_mut(&block).expect("block found above");
+            if txs[index].sender() != cancel.sender() {
+                return Err("Only the sender can cancel a delayed transaction".to_string());
+            }
+            txs.remove(index);
+            if txs.is_empty() {
+                delayed.remove(&block);
+            }
+        }
+        self.log(&WalRecord::CancelDelayed { tx: borsh::to_vec(cancel).map(hex::encode).unwrap_or_default() });
+        
+        self.persist()
+            .map_err(|e| format!("Transaction cancelled but failed to persist state: {}", e))?;
+        Ok(cancel.hash())
+    }
+    
+    /// Reject transactions signed for another chain
+    fn check_chain_id(&self, tx: &SignedTransaction) -> Result<(), String> {
+        let chain_id = self.chain_id();
+        if tx.tx.chain_id != chain_id {
+            return Err(format!("Invalid chain ID: expected {}, got {}", chain_id, tx.tx.chain_id));
+        }
+        Ok(())
+    }
+    
+    /// Delayed transactions by the block they execute in
+    pub fn delayed_transactions(&self) -> BTreeMap<u64, Vec<SignedTransaction>> {
+        self.delayed.read().clone()
+    }
+    
+    /// Check if any delayed transaction is due at or before `block_number`
+    pub fn has_delayed_due(&self, block_number: u64) -> bool {
+        self.delayed.read().range(..=block_number).next().is_some()
+    }
+    
+    /// Remove and return delayed transactions due at or before `block_number`
+    fn take_due_delayed(&self, block_number: u64) -> BTreeMap<u64, Vec<SignedTransaction>> {
+        let mut delayed = self.delayed.write();
+        let later = delayed.split_off(&(block_number + 1));
+        std::mem::replace(&mut *delayed, later)
+    }
+    
+    /// Hold a transaction whose nonce is ahead of its sender's until the gap is filled
+    pub fn queue_transaction(&self, tx: SignedTransaction) -> Result<(), String> {
+        let sender = tx.sender();
+        let mut queued = self.queued.write();
+        let pending = queued.entry(sender).or_default();
+        
+        if !pending.contains_key(&tx.tx.nonce) && pending.len() >= MAX_QUEUED_PER_SENDER {
+            return Err(format!("Too many queued transactions for {}", sender));
+        }
+        pending.insert(tx.tx.nonce, tx);
+        Ok(())
+    }
+    
+    /// Take the queued transaction of `sender` with `nonce`, if any
+    pub fn take_queued(&self, sender: &Address, nonce: u64) -> Option<SignedTransaction> {
+        let mut queued = self.queued.write();
+        let pending = queued.get_mut(sender)?;
+        let tx = pending.remove(&nonce);
+        // Entries below the new nonce can never execute
+        pending.retain(|n, _| *n > nonce);
+        if pending.is_empty() {
+            queued.remove(sender);
+        }
+        tx
+    }
+    
+    /// Whether a transaction with `hash` is waiting for a nonce gap
+    pub fn is_queued(&self, hash: &Hash) -> bool {
+        self.queued
+            .read()
+            .values()
+            .any(|pending| pending.values().any(|tx| tx.hash() == *hash))
+    }
+    
+    /// Every queued transaction, in nonce order per sender
+    pub fn queued_transactions(&self) -> Vec<SignedTransaction> {
+        self.queued
+            .read()
+            .values()
+            .flat_map(|pending| pending.values().cloned())
+            .collect()
+    }
+    
+    /// Number of queued transactions for `sender`
+    pub fn queued_count(&self, sender: &Address) -> usize {
+        self.queued.read().get(sender).map(|q| q.len()).unwrap_or(0)
     }
     
     /// Get all accounts (for debugging)
@@ -489,6 +2055,55 @@ impl State {
         accounts.iter().map(|(k, v)| (*k, v.get_balance())).collect()
     }
     
+    /// Move the balance of every account below `threshold` to `beneficiary`
+    /// and delete it. Contracts and accounts that have sent a transaction
+    /// (non-zero nonce) are never swept.
+    ///
+    /// Returns the number of accounts removed and the total collected.
+    pub fn sweep_dust(&self, threshold: U256, beneficiary: &Address) -> Result<(usize, U256), String> {
+        let (swept, collected) = {
+            let mut accounts = self.accounts.write();
+            
+            let dust: Vec<Address> = accounts
+                .iter()
+                .filter(|(addr, account)| {
+                    *addr != beneficiary
+                        && account.nonce == 0
+                        && account.code.is_empty()
+                        && account.get_balance() < threshold
+                })
+                .map(|(addr, _)| *addr)
+                .collect();
+            
+            let mut collected = U256::ZERO;
+            for addr in &dust {
+                if let Some(account) = accounts.get(addr) {
+                    collected = collected
+                        .checked_add(&account.get_balance())
+                        .ok_or_else(|| StateError::BalanceOverflow(*beneficiary).to_string())?;
+                }
+            }
+            let mut pending = self.pending_undo.write();
+            pending.note(&accounts, &dust);
+            pending.note(&accounts, &[*beneficiary]);
+            drop(pending);
+            credit(&mut accounts, beneficiary, collected).map_err(|e| e.to_string())?;
+            for addr in &dust {
+                accounts.remove(addr);
+            }
+            
+            (dust.len(), collected)
+        };
+        
+        if swept > 0 {
+            tracing::info!("Swept {} dust accounts ({} Spark) to {}", swept, collected, beneficiary);
+            self.flush()
+                .map_err(|e| format!("Sweep succeeded but failed to persist state: {}", e))?;
+        }
+        
+        Ok((swept, collected))
+    }
+    
     /// Deploy a contract
     pub fn deploy_contract(&self, from: &Address, code: Vec<u8>) -> Result<Address, String> {
         let mut accounts = self.accounts.write();
@@ -497,6 +2112,7 @@ impl State {
         
         // Compute contract address from sender + nonce
         let contract_addr = self.compute_contract_address(from, nonce);
+        self.pending_undo.write().note(&accounts, &[*from, contract_addr]);
         
         // Increment sender nonce
         if let Some(sender) = accounts.get_mut(from) {
@@ -504,6 +2120,7 @@ impl State {
         }
         
         // Create contract account
+        let recorded = code.clone();
         accounts.insert(contract_addr, Account {
             balance: "0x0".to_string(),
             nonce: 0,
@@ -512,28 +2129,107 @@ impl State {
         });
         
         drop(accounts);
+        self.record_code(&contract_addr, recorded);
         
-        // Persist
-        let _ = self.persist();
+        // Not logged to the WAL, so written through whatever the commit policy
+        let _ = self.flush();
         
         tracing::info!("Deployed contract at {}", hex::encode(contract_addr));
         Ok(contract_addr)
     }
     
+    /// Replace the code of the contract at `address`, keeping its storage and balance.
+    ///
+    /// Only the admin recorded under [`admin_slot`] may upgrade, and the new
+    /// code must be valid WASM.
+    pub fn upgrade_code(&self, address: &Address, new_code: Vec<u8>, authorizer: &Address) -> Result<(), StateError> {
+        merklith_vm::validate_wasm(&new_code).map_err(|e| StateError::InvalidCode(e.to_string()))?;
+        
+        let mut accounts = self.accounts.write();
+        self.pending_undo.write().note(&accounts, &[*address]);
+        let account = accounts
+            .get_mut(address)
+            .filter(|a| !a.code.is_empty())
+            .ok_or_else(|| StateError::InvalidTransaction(format!("No contract at {}", address)))?;
+        
+        let admin = account
+            .storage_words()
+            .get(&admin_slot())
+            .map(|word| Address::from_bytes(word[12..].try_into().expect("20-byte slice")));
+        if admin != Some(*authorizer) || *authorizer == Address::ZERO {
+            return Err(StateError::Unauthorized(*authorizer));
+        }
+        
+        account.code = new_code.clone();
+        drop(accounts);
+        self.record_code(address, new_code);
+        let _ = self.flush();
+        
+        tracing::info!("Upgraded contract at {} by {}", address, authorizer);
+        Ok(())
+    }
+    
+    /// Note that `address` runs `code` from the current block on
+    fn record_code(&self, address: &Address, code: Vec<u8>) {
+        let block = *self.block_number.read();
+        self.code_history.write().entry(*address).or_default().insert(block, code);
+    }
+    
+    /// Code of `address` as of block `block`.
+    ///
+    /// Empty before the contract was deployed. Blocks past the head or outside
+    /// the pruning window are an error.
+    pub fn code_at(&self, address: &Address, block: u64) -> Result<Vec<u8>, StateError> {
+        let head = *self.block_number.read();
+        if block > head {
+            return Err(StateError::InvalidBlock(format!("Block {} is beyond head {}", block, head)));
+        }
+        if !self.pruning.keeps_body(block, head) {
+            return Err(StateError::InvalidBlock(format!(
+                "Block {} is outside the retention window (oldest is {})",
+                block,
+                self.pruning.window_start(head)
+            )));
+        }
+        
+        match self.code_history.read().get(address) {
+            Some(history) => Ok(history.range(..=block).next_back().map(|(_, code)| code.clone()).unwrap_or_default()),
+            // State written before code history was kept
+            None => Ok(self.get_code(address)),
+        }
+    }
+    
     /// Get contract code
     pub fn get_code(&self, address: &Address) -> Vec<u8> {
         let accounts = self.accounts.read();
         accounts.get(address).map(|a| a.code.clone()).unwrap_or_default()
     }
     
+    /// Code of several accounts read at one block, with that block's number
+    pub fn get_codes(&self, addresses: &[Address]) -> (u64, Vec<Vec<u8>>) {
+        let block_number = self.block_number.read();
+        let accounts = self.accounts.read();
+        let codes = addresses
+            .iter()
+            .map(|address| accounts.get(address).map(|a| a.code.clone()).unwrap_or_default())
+            .collect();
+        (*block_number, codes)
+    }
+    
     /// Set contract storage
     pub fn set_storage(&self, address: &Address, key: [u8; 32], value: [u8; 32]) {
         let mut accounts = self.accounts.write();
+        self.pending_undo.write().note(&accounts, &[*address]);
         if let Some(account) = accounts.get_mut(address) {
             account.storage.insert(hex::encode(key), hex::encode(value));
         }
         drop(accounts);
-        let _ = self.persist();
+        let _ = self.flush();
+    }
+    
+    /// All storage of `address`
+    pub fn storage_snapshot(&self, address: &Address) -> HashMap<[u8; 32], [u8; 32]> {
+        self.accounts.read().get(address).map(|a| a.storage_words()).unwrap_or_default()
     }
     
     /// Get contract storage
@@ -553,11 +2249,12 @@ impl State {
     /// Increment nonce for an address
     pub fn increment_nonce(&self, address: &Address) {
         let mut accounts = self.accounts.write();
+        self.pending_undo.write().note(&accounts, &[*address]);
         if let Some(account) = accounts.get_mut(address) {
             account.nonce += 1;
         }
         drop(accounts);
-        let _ = self.persist();
+        let _ = self.flush();
     }
     
     fn compute_contract_address(&self, from: &Address, nonce: u64) -> Address {
@@ -568,11 +2265,144 @@ impl State {
         Address::from_slice(&hash.as_bytes()[12..]).unwrap_or(Address::ZERO)
     }
     
-    /// Persist state to disk
+    /// Persist state to disk if the commit policy says a flush is due, or a
+    /// block became final since the last one
     fn persist(&self) -> Result<(), String> {
+        if self.replaying.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+        let due = {
+            let (flushed_block, flushed_at) = *self.last_flush.lock();
+            let blocks = self.block_number().saturating_sub(flushed_block);
+            let finalized = self.finality_threshold.read().is_some_and(|threshold| blocks >= threshold);
+            finalized || self.commit_policy.read().is_due(blocks, flushed_at.elapsed())
+        };
+        if due {
+            self.flush()
+        } else {
+            Ok(())
+        }
+    }
+    
+    /// Write state to disk now, whatever the commit policy
+    pub fn flush(&self) -> Result<(), String> {
+        let block_number = self.block_number();
+        self.write_to_disk()?;
+        *self.last_flush.lock() = (block_number, Instant::now());
+        self.wal.truncate()
+    }
+    
+    /// Append `record` to the WAL when the commit policy defers flushes.
+    /// Failing to log only costs durability, so it is not an error.
+    fn log(&self, record: &WalRecord) {
+        if self.replaying.load(Ordering::Relaxed) || *self.commit_policy.read() == CommitPolicy::EveryWrite {
+            return;
+        }
+        let seq = self.wal_seq.fetch_add(1, Ordering::SeqCst) + 1;
+        if let Err(e) = self.wal.append(&WalEntry { seq, record }) {
+            tracing::warn!("Failed to append to the write-ahead log: {}", e);
+        }
+    }
+    
+    /// Re-apply transitions logged after the last flush, then flush them.
+    ///
+    /// Records the state file already includes, left behind by a crash
+    /// between writing it and truncating the log, are skipped.
+    fn replay_wal(&self) {
+        let entries: Vec<WalEntry<WalRecord>> = match self.wal.read() {
+            Ok(entries) => entries,
+            Err(e) => {
+                tracing::warn!("Could not read the write-ahead log: {}", e);
+                return;
+            }
+        };
+        let persisted = self.wal_seq.load(Ordering::SeqCst);
+        let entries: Vec<_> = entries.into_iter().filter(|entry| entry.seq > persisted).collect();
+        if entries.is_empty() {
+            // Nothing left to replay, but the log may still hold included records
+            if let Err(e) = self.wal.truncate() {
+                tracing::warn!("Failed to truncate the write-ahead log: {}", e);
+            }
+            return;
+        }
+        
+        self.replaying.store(true, Ordering::Relaxed);
+        let count = entries.len();
+        for entry in entries {
+            if let Err(e) = self.apply_wal_record(entry.record) {
+                tracing::warn!("Failed to replay WAL record: {}", e);
+            }
+            self.wal_seq.store(entry.seq, Ordering::SeqCst);
+        }
+        self.replaying.store(false, Ordering::Relaxed);
+        
+        tracing::info!("Replayed {} write-ahead log records", count);
+        if let Err(e) = self.flush() {
+            tracing::warn!("Failed to flush replayed state: {}", e);
+        }
+    }
+    
+    fn apply_wal_record(&self, record: WalRecord) -> Result<(), String> {
+        let address = |s: &str| parse_address(&format!("0x{}", s)).map_err(|e| e.to_string());
+        let transaction = |s: &str| -> Result<SignedTransaction, String> {
+            let bytes = hex::decode(s).map_err(|e| e.to_string())?;
+            borsh::from_slice(&bytes).map_err(|e| e.to_string())
+        };
+        let bytes32 = |s: &str| -> Result<[u8; 32], String> {
+            hex::decode(s).map_err(|e| e.to_string())?
+                .try_into()
+                .map_err(|_| "Expected 32 bytes".to_string())
+        };
+        match record {
+            WalRecord::Transfer { from, to, amount } => {
+                let amount = U256::from_str(&amount).map_err(|e| e.to_string())?;
+                self.transfer(&address(&from)?, &address(&to)?, amount).map(|_| ())
+            }
+            WalRecord::Sponsored { tx, proposer } => {
+                let bytes = hex::decode(tx).map_err(|e| e.to_string())?;
+                let tx: SponsoredTransaction = borsh::from_slice(&bytes).map_err(|e| e.to_string())?;
+                self.apply_sponsored(&tx, &address(&proposer)?).map(|_| ())
+            }
+            WalRecord::ProduceBlock { validator, transactions, is_heartbeat, stamp } => {
+                let transactions = transactions.iter().map(|tx| transaction(tx)).collect::<Result<_, _>>()?;
+                self.produce_block_stamped(&address(&validator)?, transactions, is_heartbeat, Some(stamp), None)
+                    .map(|_| ())
+                    .map_err(|e| e.to_string())
+            }
+            WalRecord::IncrementBlock { stamp } => {
+                self.increment_block_stamped(Some(stamp));
+                Ok(())
+            }
+            WalRecord::AddBlock { number, hash, parent_hash, timestamp } => {
+                if self.add_block_at(number, bytes32(&hash)?, bytes32(&parent_hash)?, timestamp) {
+                    Ok(())
+                } else {
+                    Err(format!("Block #{} no longer extends the chain", number))
+                }
+            }
+            WalRecord::Attest { number, signature, bitfield } => {
+                let attestation = ParentAttestation {
+                    signature: hex::decode(signature).map_err(|e| e.to_string())?,
+                    bitfield: hex::decode(bitfield).map_err(|e| e.to_string())?,
+                };
+                if self.attach_attestation(number, attestation) {
+                    Ok(())
+                } else {
+                    Err(format!("Block #{} is not in the chain", number))
+                }
+            }
+            WalRecord::Schedule { tx } => self.schedule_transaction(transaction(&tx)?).map(|_| ()),
+            WalRecord::CancelDelayed { tx } => self.cancel_delayed(&transaction(&tx)?).map(|_| ()),
+        }
+    }
+    
+    fn write_to_disk(&self) -> Result<(), String> {
         fs::create_dir_all(&self.path).map_err(|e| e.to_string())?;
+        // Read before the state: every record numbered so far is already applied
+        let wal_seq = self.wal_seq.load(Ordering::SeqCst);
         
         let accounts = self.accounts.read();
+        let state_root = accounts_root(&accounts);
         let accounts_map: HashMap<String, Account> = accounts
             .iter()
             .map(|(k, v)| (hex::encode(k), v.clone()))
@@ -580,18 +2410,80 @@ impl State {
         
         let blocks = self.blocks.read();
         
+        let snapshots = self
+            .snapshots
+            .read()
+            .iter()
+            .map(|(height, accounts)| {
+                let accounts = accounts
+                    .iter()
+                    .map(|(k, v)| (hex::encode(k), v.clone()))
+                    .collect();
+                (*height, accounts)
+            })
+            .collect();
+        
+        let transactions = self
+            .transactions
+            .read()
+            .iter()
+            .map(|(number, txs)| {
+                let encoded = txs
+                    .iter()
+                    .filter_map(|tx| borsh::to_vec(tx).ok())
+                    .map(hex::encode)
+                    .collect();
+                (*number, encoded)
+            })
+            .collect();
+        
+        let code_history = self
+            .code_history
+            .read()
+            .iter()
+            .map(|(address, history)| {
+                let history = history.iter().map(|(block, code)| (*block, hex::encode(code))).collect();
+                (hex::encode(address), history)
+            })
+            .collect();
+        
+        let delayed = self
+            .delayed
+            .read()
+            .iter()
+            .map(|(number, txs)| {
+                let encoded = txs
+                    .iter()
+                    .filter_map(|tx| borsh::to_vec(tx).ok())
+                    .map(hex::encode)
+                    .collect();
+                (*number, encoded)
+            })
+            .collect();
+        
         let data = StateData {
             accounts: accounts_map,
             block_number: *self.block_number.read(),
             block_hash: hex::encode(self.block_hash.read().as_bytes()),
-            total_supply: format!("0x{}", *self.total_supply.read()),
+            total_supply: format!("{:x}", *self.total_supply.read()),
+            total_burned: Some(format!("{:x}", *self.total_burned.read())),
             blocks: blocks.clone(),
+            chain_id: *self.chain_id.read(),
+            bodies: self.bodies.read().clone(),
+            receipts: self.receipts.read().clone(),
+            transactions,
+            snapshots,
+            code_history,
+            delayed,
+            state_root: Some(hex::encode(state_root)),
+            wal_seq,
         };
         
         let json = serde_json::to_string_pretty(&data).map_err(|e| e.to_string())?;
         let file = self.path.join("state.json");
         fs::write(&file, json).map_err(|e| e.to_string())?;
         
+        *self.persisted_root.lock() = Some(state_root);
         tracing::debug!("State persisted to {:?}", file);
         Ok(())
     }
@@ -616,6 +2508,24 @@ impl State {
         }
         
         *self.block_number.write() = data.block_number;
+        self.wal_seq.store(data.wal_seq, Ordering::SeqCst);
+        *self.persisted_root.lock() = data
+            .state_root
+            .and_then(|root| hex::decode(root).ok())
+            .and_then(|root| root.try_into().ok());
+        
+        // Older files wrote total_supply in decimal behind a 0x prefix; rebuild it from balances
+        match data.total_burned {
+            Some(burned) => {
+                *self.total_supply.write() = U256::from_str(&data.total_supply).unwrap_or(U256::ZERO);
+                *self.total_burned.write() = U256::from_str(&burned).unwrap_or(U256::ZERO);
+            }
+            None => {
+                *self.total_supply.write() = accounts
+                    .values()
+                    .fold(U256::ZERO, |acc, a| acc.saturating_add(&a.get_balance()));
+            }
+        }
         
         // Load block hash
         if let Ok(hash_bytes) = hex::decode(&data.block_hash) {
@@ -628,6 +2538,66 @@ impl State {
         
         // Load blocks
         *self.blocks.write() = data.blocks;
+        if data.chain_id != 0 {
+            *self.chain_id.write() = data.chain_id;
+        }
+        *self.bodies.write() = data.bodies;
+        let mut log_index = self.log_index.write();
+        log_index.clear();
+        for (number, receipts) in &data.receipts {
+            for log in receipts.iter().flat_map(|r| &r.logs) {
+                log_index.entry(log.address).or_default().insert(*number);
+            }
+        }
+        drop(log_index);
+        *self.receipts.write() = data.receipts;
+        *self.transactions.write() = data
+            .transactions
+            .into_iter()
+            .map(|(number, encoded)| {
+                let txs = encoded
+                    .iter()
+                    .filter_map(|tx| hex::decode(tx).ok())
+                    .filter_map(|bytes| borsh::from_slice(&bytes).ok())
+                    .collect();
+                (number, txs)
+            })
+            .collect();
+        *self.snapshots.write() = data
+            .snapshots
+            .into_iter()
+            .map(|(height, accounts)| {
+                let accounts = accounts
+                    .into_iter()
+                    .filter_map(|(k, v)| parse_address(&format!("0x{}", k)).ok().map(|a| (a, v)))
+                    .collect();
+                (height, accounts)
+            })
+            .collect();
+        *self.code_history.write() = data
+            .code_history
+            .into_iter()
+            .filter_map(|(k, history)| {
+                let address = parse_address(&format!("0x{}", k)).ok()?;
+                let history = history
+                    .into_iter()
+                    .filter_map(|(block, code)| hex::decode(code).ok().map(|c| (block, c)))
+                    .collect();
+                Some((address, history))
+            })
+            .collect();
+        *self.delayed.write() = data
+            .delayed
+            .into_iter()
+            .map(|(number, encoded)| {
+                let txs = encoded
+                    .iter()
+                    .filter_map(|tx| hex::decode(tx).ok())
+                    .filter_map(|bytes| borsh::from_slice(&bytes).ok())
+                    .collect();
+                (number, txs)
+            })
+            .collect();
         
         tracing::info!("Loaded state from disk: {} accounts, block {}", accounts.len(), data.block_number);
         Ok(())
@@ -724,6 +2694,123 @@ pub struct StorageStats {
     pub chain_integrity: String,
 }
 
+/// Add `amount` to `address`, creating the account if needed
+/// Accounts as allocated at genesis
+fn genesis_accounts(genesis: &GenesisConfig) -> HashMap<Address, Account> {
+    genesis
+        .alloc
+        .iter()
+        .map(|alloc| {
+            let storage = alloc
+                .storage
+                .iter()
+                .flatten()
+                .map(|(key, value)| (hex::encode(key.as_bytes()), hex::encode(value)))
+                .collect();
+            (alloc.address, Account {
+                balance: format!("{:x}", alloc.balance),  // Without 0x prefix, LowerHex adds it
+                nonce: 0,
+                code: alloc.code.clone().unwrap_or_default(),
+                storage,
+            })
+        })
+        .collect()
+}
+
+fn credit(accounts: &mut HashMap<Address, Account>, address: &Address, amount: U256) -> Result<(), StateError> {
+    if amount.is_zero() {
+        return Ok(());
+    }
+    let balance = accounts.get(address).map(|a| a.get_balance()).unwrap_or(U256::ZERO);
+    let new_balance = balance
+        .checked_add(&amount)
+        .ok_or(StateError::BalanceOverflow(*address))?;
+    accounts.entry(*address).or_default().set_balance(new_balance);
+    Ok(())
+}
+
+/// Check the user signed the intent and the sponsor signed the signed intent
+pub fn verify_sponsored(tx: &SponsoredTransaction) -> Result<(), String> {
+    if tx.inner.tx.tx_type != TransactionType::Sponsored {
+        return Err("Inner transaction is not a sponsored intent".to_string());
+    }
+    merklith_crypto::ed25519_verify(
+        &tx.inner.public_key,
+        tx.inner.tx.signing_hash().as_bytes(),
+        &tx.inner.signature,
+    )
+    .map_err(|e| format!("Invalid intent signature: {}", e))?;
+    merklith_crypto::ed25519_verify(
+        &tx.sponsor_public_key,
+        SponsoredTransaction::sponsor_signing_hash(&tx.inner).as_bytes(),
+        &tx.sponsor_signature,
+    )
+    .map_err(|e| format!("Invalid sponsor signature: {}", e))
+}
+
+/// Run the code at `to` for a mined transaction
+fn call_contract(
+    tx: &SignedTransaction,
+    to: Address,
+    code: Vec<u8>,
+    storage: HashMap<[u8; 32], [u8; 32]>,
+    block_number: u64,
+) -> Result<merklith_vm::ExecutionResult, String> {
+    let vm = merklith_vm::MerklithVM::new().map_err(|e| format!("Failed to create VM: {}", e))?;
+    let ctx = contract_context(tx, to, code, storage, block_number);
+    let result = vm.execute(ctx).map_err(|e| format!("Contract execution failed: {}", e))?;
+    if !result.success {
+        return Err("Contract execution failed".to_string());
+    }
+    Ok(result)
+}
+
+/// VM context for running the code at `to` on behalf of `tx`
+fn contract_context(
+    tx: &SignedTransaction,
+    to: Address,
+    code: Vec<u8>,
+    storage: HashMap<[u8; 32], [u8; 32]>,
+    block_number: u64,
+) -> merklith_vm::ExecutionContext {
+    use merklith_vm::ExecutionContext;
+    
+    ExecutionContext {
+        code: bytes::Bytes::from(code),
+        ..ExecutionContext::new_call(
+            to,
+            tx.sender(),
+            tx.sender(),
+            tx.tx.gas_limit,
+            bytes::Bytes::from(tx.tx.data.clone()),
+        )
+    }
+    .with_storage(storage)
+    .with_value(tx.tx.value)
+    .with_chain_id(tx.tx.chain_id)
+    .with_block_info(block_number, 0, [0u8; 32])
+}
+
+/// See [`State::state_root`]
+fn accounts_root(accounts: &HashMap<Address, Account>) -> [u8; 32] {
+    let mut sorted: Vec<_> = accounts.iter().collect();
+    sorted.sort_by_key(|(address, _)| **address);
+    
+    let mut hasher = blake3::Hasher::new();
+    for (address, account) in sorted {
+        hasher.update(address.as_bytes());
+        hasher.update(&account.nonce.to_le_bytes());
+        hasher.update(&account.get_balance().to_le_bytes());
+        hasher.update(blake3::hash(&account.code).as_bytes());
+        let storage: BTreeMap<_, _> = account.storage_words().into_iter().collect();
+        for (key, value) in storage {
+            hasher.update(&key);
+            hasher.update(&value);
+        }
+    }
+    *hasher.finalize().as_bytes()
+}
+
 fn parse_address(s: &str) -> Result<Address, String> {
     let s = s.strip_prefix("0x").unwrap_or(s);
     let bytes = hex::decode(s).map_err(|e: hex::FromHexError| e.to_string())?;
@@ -746,6 +2833,1192 @@ mod tests {
         assert_eq!(state.block_number(), 0);
     }
     
+    #[test]
+    fn test_genesis_hash() {
+        let temp_dir = std::env::temp_dir().join(format!("merklith_genesis_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        
+        let state = State::with_path(temp_dir.clone());
+        let expected = State::devnet_genesis().genesis_hash();
+        assert_eq!(state.genesis_hash(), expected);
+        assert_eq!(state.get_block(0).unwrap().hash, *expected.as_bytes());
+        
+        let _ = std::fs::remove_dir_all(&temp_dir);
+    }
+    
+    #[test]
+    fn test_genesis_timestamp() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let proposer = Address::from_bytes([0xAA; 20]);
+        let open = |timestamp: u64, dir: &str| {
+            let mut genesis = GenesisConfig::devnet();
+            genesis.timestamp = timestamp;
+            State::with_genesis(temp_dir.path().join(dir), genesis, PruningConfig::archive())
+        };
+
+        // A chain that has not started yet cannot produce blocks
+        let start = unix_now() + 3600;
+        let pending = open(start, "pending");
+        assert_eq!(pending.get_block(0).unwrap().timestamp, start);
+        assert!(matches!(
+            pending.produce_block(&proposer, Vec::new(), false),
+            Err(StateError::InvalidBlock(_))
+        ));
+        assert_eq!(pending.block_number(), 0);
+
+        // Block 1 must be later than genesis, even when replayed
+        let start = unix_now() - 60;
+        let started = open(start, "started");
+        assert_eq!(started.get_block(0).unwrap().timestamp, start);
+        let at_genesis = BlockStamp { timestamp: start, gas_limit: started.next_gas_limit(), hash: None };
+        assert!(started.produce_block_stamped(&proposer, Vec::new(), false, Some(at_genesis), None).is_err());
+        started.produce_block(&proposer, Vec::new(), false).unwrap();
+        assert!(started.get_block(1).unwrap().timestamp > start);
+
+        // Genesis timestamps are part of the chain's identity
+        assert_ne!(pending.genesis_hash(), started.genesis_hash());
+    }
+    
+    #[test]
+    fn test_fee_distribution() {
+        use merklith_types::{Ed25519PublicKey, Ed25519Signature, Transaction};
+        
+        let temp_dir = std::env::temp_dir().join(format!("merklith_fees_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        
+        let public_key = Ed25519PublicKey::from_bytes([7u8; 32]);
+        let sender = public_key.to_address();
+        let proposer = Address::from_bytes([0xAA; 20]);
+        let treasury = Address::from_bytes([0xBB; 20]);
+        let recipient = Address::from_bytes([0xCC; 20]);
+        
+        let mut genesis = GenesisConfig::devnet();
+        genesis.chain_config.fee_burn_pct = 100;
+        genesis.chain_config.fee_treasury_pct = 50;
+        genesis.chain_config.treasury_address = treasury;
+        genesis.chain_config.min_base_fee = U256::from(10u64);
+        genesis.add_alloc(sender, U256::from(1_000_000_000u64));
+        let initial_supply = U256::from(1_000_000_000u64);
+        
+        let state = State::with_genesis(temp_dir.clone(), genesis, PruningConfig::archive());
+        assert_eq!(state.total_supply(), initial_supply);
+        
+        // Base fee 10, tip 4: 210,000 burned, 84,000 split between treasury and proposer
+        let txs: Vec<SignedTransaction> = (0..3)
+            .map(|nonce| {
+                let tx = Transaction::new(
+                    state.chain_id(),
+                    nonce,
+                    Some(recipient),
+                    U256::from(100u64),
+                    TRANSFER_GAS,
+                    U256::from(20u64),
+                    U256::from(4u64),
+                );
+                SignedTransaction::new(tx, Ed25519Signature::from_bytes([0u8; 64]), public_key)
+            })
+            .collect();
+        
+        let result = state.produce_block(&proposer, txs, false).unwrap();
+        assert_eq!(result.fees.burned, U256::from(3 * 210_000u64));
+        assert_eq!(result.fees.to_treasury, U256::from(3 * 42_000u64));
+        assert_eq!(result.fees.to_proposer, U256::from(3 * 42_000u64));
+        
+        assert_eq!(state.balance(&recipient), U256::from(300u64));
+        assert_eq!(state.balance(&treasury), U256::from(3 * 42_000u64));
+        assert_eq!(
+            state.balance(&proposer),
+            result.validator_reward + U256::from(3 * 42_000u64)
+        );
+        assert_eq!(
+            state.balance(&sender),
+            initial_supply - U256::from(300u64) - result.fees.total()
+        );
+        assert_eq!(
+            state.total_supply(),
+            initial_supply + result.validator_reward - result.fees.burned
+        );
+        
+        let _ = std::fs::remove_dir_all(&temp_dir);
+    }
+    
+    #[test]
+    fn test_supply_accounting() {
+        use merklith_types::{Ed25519PublicKey, Ed25519Signature, Transaction};
+        
+        let temp_dir = std::env::temp_dir().join(format!("merklith_supply_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        
+        let public_key = Ed25519PublicKey::from_bytes([9u8; 32]);
+        let sender = public_key.to_address();
+        let proposer = Address::from_bytes([0xAA; 20]);
+        
+        let mut genesis = GenesisConfig::devnet();
+        genesis.chain_config.fee_burn_pct = 50;
+        genesis.add_alloc(sender, U256::from(10_000_000u64));
+        
+        let state = State::with_genesis(temp_dir.clone(), genesis.clone(), PruningConfig::archive());
+        let genesis_supply = state.total_supply();
+        state.verify_supply().unwrap();
+        
+        // Reward only
+        let minted_first = state.produce_block(&proposer, vec![], false).unwrap().validator_reward;
+        state.verify_supply().unwrap();
+        assert_eq!(state.total_supply(), genesis_supply + minted_first);
+        
+        // Reward plus burned base fee
+        let tx = Transaction::new(
+            state.chain_id(),
+            0,
+            Some(proposer),
+            U256::from(5u64),
+            TRANSFER_GAS,
+            U256::from(2u64),
+            U256::ONE,
+        );
+        let tx = SignedTransaction::new(tx, Ed25519Signature::from_bytes([0u8; 64]), public_key);
+        let result = state.produce_block(&proposer, vec![tx], false).unwrap();
+        assert!(!result.fees.burned.is_zero());
+        state.verify_supply().unwrap();
+        assert_eq!(state.total_burned(), result.fees.burned);
+        assert_eq!(
+            state.total_supply(),
+            genesis_supply + minted_first + result.validator_reward - result.fees.burned
+        );
+        
+        // Tracked supply survives a restart
+        let expected = state.total_supply();
+        drop(state);
+        let reloaded = State::with_genesis(temp_dir.clone(), genesis, PruningConfig::archive());
+        assert_eq!(reloaded.total_supply(), expected);
+        assert_eq!(reloaded.total_burned(), result.fees.burned);
+        reloaded.verify_supply().unwrap();
+        
+        let _ = std::fs::remove_dir_all(&temp_dir);
+    }
+    
+    #[test]
+    fn test_block_applied_atomically() {
+        use merklith_types::{Ed25519PublicKey, Ed25519Signature, Transaction};
+        use std::sync::atomic::{AtomicBool, Ordering};
+        use std::sync::Arc;
+        
+        let temp_dir = std::env::temp_dir().join(format!("merklith_atomic_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        
+        let public_key = Ed25519PublicKey::from_bytes([7u8; 32]);
+        let sender = public_key.to_address();
+        let proposer = Address::from_bytes([0xAA; 20]);
+        let recipients: Vec<Address> = (1..=20u8).map(|i| Address::from_bytes([i; 20])).collect();
+        
+        let mut genesis = GenesisConfig::devnet();
+        genesis.add_alloc(sender, U256::from(100_000_000u64));
+        let state = Arc::new(State::with_genesis(temp_dir.clone(), genesis, PruningConfig::archive()));
+        
+        let transactions = recipients
+            .iter()
+            .enumerate()
+            .map(|(nonce, to)| {
+                let tx = Transaction::new(state.chain_id(), nonce as u64, Some(*to), U256::ONE, TRANSFER_GAS, U256::ONE, U256::ZERO);
+                SignedTransaction::new(tx, Ed25519Signature::from_bytes([0u8; 64]), public_key)
+            })
+            .collect();
+        
+        // Readers see either none or all of the block's transfers
+        let done = Arc::new(AtomicBool::new(false));
+        let reader = {
+            let (state, done, recipients) = (state.clone(), done.clone(), recipients.clone());
+            std::thread::spawn(move || {
+                let mut seen = std::collections::HashSet::new();
+                while !done.load(Ordering::Acquire) {
+                    let accounts = state.all_accounts();
+                    let paid = recipients
+                        .iter()
+                        .filter(|r| accounts.iter().any(|(a, balance)| a == *r && !balance.is_zero()))
+                        .count();
+                    seen.insert(paid);
+                }
+                seen
+            })
+        };
+        
+        state.produce_block(&proposer, transactions, false).unwrap();
+        done.store(true, Ordering::Release);
+        let seen = reader.join().unwrap();
+        assert!(seen.iter().all(|paid| *paid == 0 || *paid == recipients.len()), "saw {:?}", seen);
+        assert!(recipients.iter().all(|r| state.balance(r) == U256::ONE));
+        assert_eq!(state.get_block(1).unwrap().state_root, state.state_root());
+        state.verify_supply().unwrap();
+        
+        let _ = std::fs::remove_dir_all(&temp_dir);
+    }
+    
+    #[test]
+    fn test_mined_transactions_persist() {
+        use merklith_types::{Ed25519PublicKey, Ed25519Signature, Transaction};
+        
+        let temp_dir = std::env::temp_dir().join(format!("merklith_mined_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        
+        let public_key = Ed25519PublicKey::from_bytes([9u8; 32]);
+        let mut genesis = GenesisConfig::devnet();
+        genesis.add_alloc(public_key.to_address(), U256::from(1_000_000u64));
+        
+        let state = State::with_genesis(temp_dir.clone(), genesis.clone(), PruningConfig::archive());
+        let tx = Transaction::new(
+            state.chain_id(), 0, Some(Address::from_bytes([1u8; 20])), U256::ONE, TRANSFER_GAS, U256::ONE, U256::ZERO,
+        );
+        let tx = SignedTransaction::new(tx, Ed25519Signature::from_bytes([0u8; 64]), public_key);
+        let hash = tx.hash();
+        let result = state.produce_block(&Address::from_bytes([0xAA; 20]), vec![tx.clone()], false).unwrap();
+        assert!(state.mined_transaction(&Hash::compute(b"unknown")).is_none());
+        drop(state);
+        
+        let reloaded = State::with_genesis(temp_dir.clone(), genesis, PruningConfig::archive());
+        assert_eq!(reloaded.mined_transaction(&hash), Some((result.block_number, 0, tx)));
+
+        let _ = std::fs::remove_dir_all(&temp_dir);
+    }
+
+    #[test]
+    fn test_commit_every_n_blocks() {
+        let temp_dir = std::env::temp_dir().join(format!("merklith_commit_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        let persisted_height = || {
+            let json = std::fs::read_to_string(temp_dir.join("state.json")).unwrap_or_default();
+            serde_json::from_str::<serde_json::Value>(&json).ok()
+                .and_then(|data| data["block_number"].as_u64())
+        };
+
+        let state = State::with_path(temp_dir.clone());
+        state.flush().unwrap();
+        state.set_commit_policy(CommitPolicy::EveryNBlocks(5));
+        for _ in 0..4 {
+            state.increment_block();
+        }
+        assert_eq!(persisted_height(), Some(0));
+        state.increment_block();
+        assert_eq!(persisted_height(), Some(5));
+
+        // Mutations between flushes stay in memory until shutdown flushes them
+        state.increment_block();
+        state.increment_block();
+        assert_eq!(persisted_height(), Some(5));
+        state.flush().unwrap();
+        assert_eq!(persisted_height(), Some(7));
+
+        // A block becoming final is flushed whatever the policy
+        state.set_commit_policy(CommitPolicy::Interval(std::time::Duration::from_secs(3600)));
+        state.set_finality_threshold(Some(3));
+        state.increment_block();
+        state.increment_block();
+        assert_eq!(persisted_height(), Some(7));
+        state.increment_block();
+        assert_eq!(persisted_height(), Some(10));
+
+        let _ = std::fs::remove_dir_all(&temp_dir);
+    }
+
+    #[test]
+    fn test_open_detects_tampered_state() {
+        let temp_dir = std::env::temp_dir().join(format!("merklith_tamper_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        let genesis = State::devnet_genesis();
+        let state = State::open(temp_dir.clone(), genesis.clone(), PruningConfig::archive()).unwrap();
+        state.produce_block(&Address::ZERO, vec![], true).unwrap();
+        state.produce_block(&Address::ZERO, vec![], true).unwrap();
+        state.flush().unwrap();
+        drop(state);
+        assert!(State::open(temp_dir.clone(), genesis.clone(), PruningConfig::archive()).unwrap().verify_consistency().is_ok());
+
+        let file = temp_dir.join("state.json");
+        let original: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&file).unwrap()).unwrap();
+        let account = State::devnet_accounts()[0].address();
+        let account = hex::encode(account.as_bytes());
+        let tamper = |edit: &dyn Fn(&mut serde_json::Value)| {
+            let mut data = original.clone();
+            edit(&mut data);
+            std::fs::write(&file, serde_json::to_string(&data).unwrap()).unwrap();
+            State::open(temp_dir.clone(), genesis.clone(), PruningConfig::archive()).unwrap_err()
+        };
+
+        let error = tamper(&|data| {
+            data["blocks"].as_array_mut().unwrap().remove(1);
+        });
+        assert!(error.to_string().contains("stored at position 1"), "{}", error);
+
+        let error = tamper(&|data| data["accounts"][&account]["balance"] = serde_json::json!("0x1"));
+        assert!(matches!(error, StateError::SupplyMismatch { .. }), "{}", error);
+
+        let error = tamper(&|data| data["accounts"][&account]["nonce"] = serde_json::json!(42));
+        assert!(error.to_string().contains("does not match recorded"), "{}", error);
+
+        let _ = std::fs::remove_dir_all(&temp_dir);
+    }
+
+    #[test]
+    fn test_wal_recovers_unflushed_state() {
+        use merklith_types::{Ed25519PublicKey, Ed25519Signature, Transaction};
+
+        let temp_dir = std::env::temp_dir().join(format!("merklith_wal_state_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&temp_dir);
+
+        let public_key = Ed25519PublicKey::from_bytes([11u8; 32]);
+        let sender = public_key.to_address();
+        let recipient = Address::from_bytes([1u8; 20]);
+        let mut genesis = GenesisConfig::devnet();
+        genesis.add_alloc(sender, U256::from(1_000_000u64));
+
+        let state = State::with_genesis(temp_dir.clone(), genesis.clone(), PruningConfig::archive());
+        state.set_commit_policy(CommitPolicy::EveryNBlocks(100));
+        state.transfer(&sender, &recipient, U256::from(500u64)).unwrap();
+        let tx = Transaction::new(
+            state.chain_id(), 0, Some(recipient), U256::from(7u64), TRANSFER_GAS, U256::ONE, U256::ZERO,
+        );
+        let tx = SignedTransaction::new(tx, Ed25519Signature::from_bytes([0u8; 64]), public_key);
+        state.produce_block(&Address::from_bytes([0xAA; 20]), vec![tx], false).unwrap();
+        state.increment_block();
+
+        let head = state.block_number();
+        let head_hash = state.get_block(head).unwrap().hash;
+        let state_root = state.state_root();
+        let balances = (state.balance(&sender), state.balance(&recipient));
+        // Crash: nothing has been flushed since genesis
+        drop(state);
+
+        let recovered = State::with_genesis(temp_dir.clone(), genesis, PruningConfig::archive());
+        assert_eq!(recovered.block_number(), head);
+        assert_eq!(recovered.get_block(head).unwrap().hash, head_hash);
+        assert_eq!(recovered.state_root(), state_root);
+        assert_eq!((recovered.balance(&sender), recovered.balance(&recipient)), balances);
+        assert!(!temp_dir.join("state.wal").exists());
+
+        let _ = std::fs::remove_dir_all(&temp_dir);
+    }
+
+    #[test]
+    fn test_parent_attestation_kept_with_block() {
+        let temp_dir = std::env::temp_dir().join(format!("merklith_attestation_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&temp_dir);
+
+        let genesis = GenesisConfig::devnet();
+        let state = State::with_genesis(temp_dir.clone(), genesis.clone(), PruningConfig::archive());
+        state.set_commit_policy(CommitPolicy::EveryNBlocks(100));
+        state.produce_block(&Address::from_bytes([0xAA; 20]), vec![], true).unwrap();
+        let head_hash = state.get_block(1).unwrap().hash;
+
+        let attestation = ParentAttestation { signature: vec![7u8; 96], bitfield: vec![0b101] };
+        assert!(state.attach_attestation(1, attestation.clone()));
+        assert!(!state.attach_attestation(2, attestation.clone()));
+        assert_eq!(state.get_block(1).unwrap().parent_attestation, Some(attestation.clone()));
+        // Not part of the block hash
+        assert_eq!(state.get_block(1).unwrap().compute_hash(), head_hash);
+        drop(state);
+
+        let recovered = State::with_genesis(temp_dir.clone(), genesis, PruningConfig::archive());
+        assert_eq!(recovered.get_block(1).unwrap().parent_attestation, Some(attestation));
+
+        let _ = std::fs::remove_dir_all(&temp_dir);
+    }
+
+    #[test]
+    fn test_wal_skips_flushed_records() {
+        use merklith_crypto::Keypair;
+        use merklith_types::Transaction;
+
+        let temp_dir = std::env::temp_dir().join(format!("merklith_wal_flushed_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        let sender = Keypair::from_seed(&[12u8; 32]);
+        let recipient = Address::from_bytes([1u8; 20]);
+        let mut genesis = GenesisConfig::devnet();
+        genesis.add_alloc(sender.address(), U256::from(1_000_000_000_000_000_000u128));
+
+        let state = State::with_genesis(temp_dir.clone(), genesis.clone(), PruningConfig::archive());
+        state.set_commit_policy(CommitPolicy::EveryNBlocks(100));
+        state.transfer(&sender.address(), &recipient, U256::from(500u64)).unwrap();
+        state.produce_block(&Address::ZERO, vec![], true).unwrap();
+        let mut tx = Transaction::new(state.chain_id(), 0, Some(recipient), U256::ONE, TRANSFER_GAS, U256::ONE, U256::ZERO);
+        tx.execute_after_block = Some(10);
+        let (signature, public_key) = sender.sign_transaction(&tx);
+        state.schedule_transaction(SignedTransaction::new(tx, signature, public_key)).unwrap();
+
+        // Crash after the flush wrote the state file but before it truncated the log
+        let wal = std::fs::read(temp_dir.join("state.wal")).unwrap();
+        state.flush().unwrap();
+        std::fs::write(temp_dir.join("state.wal"), &wal).unwrap();
+        let head = state.block_number();
+        let balances = (state.balance(&sender.address()), state.balance(&recipient));
+        drop(state);
+
+        let recovered = State::with_genesis(temp_dir.clone(), genesis.clone(), PruningConfig::archive());
+        assert_eq!(recovered.block_number(), head);
+        assert_eq!((recovered.balance(&sender.address()), recovered.balance(&recipient)), balances);
+        assert_eq!(recovered.delayed_transactions().len(), 1);
+        assert!(!temp_dir.join("state.wal").exists());
+
+        // Records logged after the flush are still replayed, and numbering carries on
+        recovered.set_commit_policy(CommitPolicy::EveryNBlocks(100));
+        recovered.transfer(&sender.address(), &recipient, U256::from(5u64)).unwrap();
+        drop(recovered);
+        let recovered = State::with_genesis(temp_dir.clone(), genesis, PruningConfig::archive());
+        assert_eq!(recovered.balance(&recipient), balances.1 + U256::from(5u64));
+
+        let _ = std::fs::remove_dir_all(&temp_dir);
+    }
+    
+    #[test]
+    fn test_contract_logs() {
+        use merklith_types::{Ed25519PublicKey, Ed25519Signature, Transaction};
+        
+        let temp_dir = std::env::temp_dir().join(format!("merklith_logs_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        
+        let public_key = Ed25519PublicKey::from_bytes([10u8; 32]);
+        let sender = public_key.to_address();
+        let mut genesis = GenesisConfig::devnet();
+        genesis.add_alloc(sender, U256::from(1_000_000u64));
+        let state = State::with_genesis(temp_dir.clone(), genesis.clone(), PruningConfig::archive());
+        
+        // PUSH1 0x01 (topic); PUSH2 0xbeef (data); LOG1; STOP
+        let contract = state
+            .deploy_contract(&sender, vec![0x60, 0x01, 0x61, 0xbe, 0xef, 0xa1, 0x00])
+            .unwrap();
+        let tx = Transaction::new(
+            state.chain_id(), state.nonce(&sender), Some(contract), U256::ZERO, 100_000, U256::ONE, U256::ZERO,
+        );
+        let tx = SignedTransaction::new(tx, Ed25519Signature::from_bytes([0u8; 64]), public_key);
+        let block = state.produce_block(&Address::from_bytes([0xAA; 20]), vec![tx], false).unwrap().block_number;
+        
+        let receipt = &state.block_receipts(block).unwrap()[0];
+        assert!(receipt.success);
+        assert!(receipt.gas_used > TRANSFER_GAS);
+        assert_eq!(receipt.logs.len(), 1);
+        
+        let log = &receipt.logs[0];
+        assert_eq!(log.address, contract);
+        assert_eq!(log.topics[0].as_bytes()[31], 1);
+        assert_eq!(log.data, vec![0xbe, 0xef]);
+        assert_eq!((log.block_number, log.tx_index, log.log_index), (block, 0, 0));
+        
+        assert_eq!(state.logs_in_block(block), vec![log.clone()]);
+        assert_eq!(state.logs_for_address(&contract, 0, block), vec![log.clone()]);
+        assert!(state.logs_for_address(&contract, block + 1, block + 10).is_empty());
+        assert!(state.logs_for_address(&sender, 0, block).is_empty());
+        
+        // The address index is rebuilt from stored receipts
+        let log = log.clone();
+        drop(state);
+        let reloaded = State::with_genesis(temp_dir.clone(), genesis, PruningConfig::archive());
+        assert_eq!(reloaded.logs_for_address(&contract, block, block), vec![log]);
+        
+        let _ = std::fs::remove_dir_all(&temp_dir);
+    }
+    
+    #[test]
+    fn test_reverted_logs_are_published_removed() {
+        use merklith_types::{Ed25519PublicKey, Ed25519Signature, Transaction};
+        
+        let temp_dir = std::env::temp_dir().join(format!("merklith_log_feed_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        
+        let public_key = Ed25519PublicKey::from_bytes([10u8; 32]);
+        let sender = public_key.to_address();
+        let validator = Address::from_bytes([0xAA; 20]);
+        let mut genesis = GenesisConfig::devnet();
+        genesis.add_alloc(sender, U256::from(1_000_000u64));
+        let state = State::with_genesis(temp_dir.clone(), genesis, PruningConfig::archive());
+        
+        // PUSH1 0x01 (topic); PUSH2 0xbeef (data); LOG1; STOP
+        let contract = state
+            .deploy_contract(&sender, vec![0x60, 0x01, 0x61, 0xbe, 0xef, 0xa1, 0x00])
+            .unwrap();
+        state.produce_block(&validator, vec![], true).unwrap();
+        
+        let mut feed = state.subscribe_logs();
+        let tx = Transaction::new(
+            state.chain_id(), state.nonce(&sender), Some(contract), U256::ZERO, 100_000, U256::ONE, U256::ZERO,
+        );
+        let tx = SignedTransaction::new(tx, Ed25519Signature::from_bytes([0u8; 64]), public_key);
+        let block = state.produce_block(&validator, vec![tx], false).unwrap();
+        
+        let added = feed.try_recv().unwrap();
+        assert!(!added.removed);
+        assert_eq!(added.block.hash, block.block_hash);
+        assert_eq!(added.receipts[0].logs[0].address, contract);
+        
+        state.revert_to(block.block_number - 1).unwrap();
+        let removed = feed.try_recv().unwrap();
+        assert!(removed.removed);
+        assert_eq!(removed.block.hash, block.block_hash);
+        assert_eq!(removed.receipts[0].logs, added.receipts[0].logs);
+        assert!(feed.try_recv().is_err());
+        
+        let _ = std::fs::remove_dir_all(&temp_dir);
+    }
+    
+    #[test]
+    fn test_upgrade_code() {
+        let temp_dir = std::env::temp_dir().join(format!("merklith_upgrade_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        
+        let deployer = Address::from_bytes([1u8; 20]);
+        let admin = Address::from_bytes([2u8; 20]);
+        let mut genesis = GenesisConfig::devnet();
+        genesis.add_alloc(deployer, U256::from(1_000u64));
+        let state = State::with_genesis(temp_dir.clone(), genesis, PruningConfig::archive());
+        
+        // Empty module, and the same with a custom section "v2!"
+        let v1 = vec![0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00];
+        let v2 = [v1.clone(), vec![0x00, 0x04, 0x03, b'v', b'2', b'!']].concat();
+        
+        let contract = state.deploy_contract(&deployer, v1.clone()).unwrap();
+        let mut admin_word = [0u8; 32];
+        admin_word[12..].copy_from_slice(admin.as_bytes());
+        state.set_storage(&contract, admin_slot(), admin_word);
+        state.set_storage(&contract, [7u8; 32], [9u8; 32]);
+        
+        assert!(matches!(
+            state.upgrade_code(&contract, v2.clone(), &deployer),
+            Err(StateError::Unauthorized(_))
+        ));
+        assert!(matches!(
+            state.upgrade_code(&contract, vec![0x60, 0x01], &admin),
+            Err(StateError::InvalidCode(_))
+        ));
+        assert_eq!(state.get_code(&contract), v1);
+        
+        state.upgrade_code(&contract, v2.clone(), &admin).unwrap();
+        assert_eq!(state.get_code(&contract), v2);
+        assert_eq!(state.get_storage(&contract, [7u8; 32]), Some([9u8; 32]));
+        assert_eq!(state.get_storage(&contract, admin_slot()), Some(admin_word));
+        
+        // Accounts without code cannot be upgraded
+        assert!(state.upgrade_code(&deployer, v2, &admin).is_err());
+        
+        let _ = std::fs::remove_dir_all(&temp_dir);
+    }
+    
+    #[test]
+    fn test_sponsored_transaction() {
+        use merklith_crypto::Keypair;
+        use merklith_types::Transaction;
+        
+        let temp_dir = std::env::temp_dir().join(format!("merklith_sponsored_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        
+        let user = Keypair::from_seed(&[1u8; 32]);
+        let sponsor = Keypair::from_seed(&[2u8; 32]);
+        let recipient = Address::from_bytes([3u8; 20]);
+        let mut genesis = GenesisConfig::devnet();
+        let base_fee = genesis.chain_config.min_base_fee;
+        genesis.add_alloc(user.address(), U256::from(1_000u64));
+        genesis.add_alloc(sponsor.address(), U256::from(1_000_000_000_000u64) * base_fee);
+        let state = State::with_genesis(temp_dir.clone(), genesis.clone(), PruningConfig::default());
+        
+        let mut intent = Transaction::new(
+            genesis.chain_config.chain_id, 0, Some(recipient), U256::from(400u64),
+            TRANSFER_GAS, base_fee, U256::ZERO,
+        );
+        intent.tx_type = TransactionType::Sponsored;
+        let (signature, public_key) = user.sign_transaction(&intent);
+        let inner = SignedTransaction::new(intent, signature, public_key);
+        let sponsor_signature = sponsor.sign(SponsoredTransaction::sponsor_signing_hash(&inner).as_bytes());
+        let sponsored = SponsoredTransaction::new(inner, sponsor_signature, sponsor.public_key());
+        
+        // Tampering with the signed intent invalidates it
+        let mut tampered = sponsored.clone();
+        tampered.inner.tx.value = U256::from(900u64);
+        assert!(state.apply_sponsored(&tampered, &Address::ZERO).unwrap_err().contains("intent signature"));
+        
+        // The intent alone cannot be submitted without a sponsor
+        let config = genesis.chain_config.at_height(1);
+        let mut accounts = state.accounts.read().clone();
+        assert!(state.apply_transaction(&mut accounts, &mut HashMap::new(), &sponsored.inner, None, &Address::ZERO, &config, 1).is_err());
+        
+        let sponsor_before = state.balance(&sponsor.address());
+        state.apply_sponsored(&sponsored, &Address::ZERO).unwrap();
+        
+        // The user pays only the value; the sponsor pays the gas
+        assert_eq!(state.balance(&user.address()), U256::from(600u64));
+        assert_eq!(state.balance(&recipient), U256::from(400u64));
+        assert_eq!(state.nonce(&user.address()), 1);
+        let fee = base_fee * U256::from(TRANSFER_GAS);
+        assert_eq!(state.balance(&sponsor.address()), sponsor_before - fee);
+        state.verify_supply().unwrap();
+        
+        // The intent's nonce is spent, so it cannot be replayed
+        assert!(state.apply_sponsored(&sponsored, &Address::ZERO).is_err());
+        
+        let _ = std::fs::remove_dir_all(&temp_dir);
+    }
+    
+    #[test]
+    fn test_multisend_atomic() {
+        use merklith_crypto::Keypair;
+        use merklith_types::Transaction;
+        
+        let temp_dir = std::env::temp_dir().join(format!("merklith_multisend_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        
+        let sender = Keypair::from_seed(&[1u8; 32]);
+        let validator = Address::from_bytes([9u8; 20]);
+        let mut genesis = GenesisConfig::devnet();
+        let base_fee = genesis.chain_config.min_base_fee;
+        let gas = multisend_gas(5);
+        let fee = base_fee * U256::from(gas);
+        genesis.add_alloc(sender.address(), fee + U256::from(1_000u64));
+        let chain_id = genesis.chain_config.chain_id;
+        let state = State::with_genesis(temp_dir.clone(), genesis, PruningConfig::default());
+        
+        let recipients: Vec<Address> = (1..=5u8).map(|i| Address::from_bytes([i; 20])).collect();
+        let sign = |amounts: &[u64]| {
+            let transfers: Vec<(Address, U256)> = recipients
+                .iter()
+                .zip(amounts)
+                .map(|(r, a)| (*r, U256::from(*a)))
+                .collect();
+            let tx = Transaction::multisend(chain_id, 0, &transfers, gas, base_fee, U256::ZERO).unwrap();
+            let (signature, public_key) = sender.sign_transaction(&tx);
+            SignedTransaction::new(tx, signature, public_key)
+        };
+        let before = state.balance(&sender.address());
+        
+        // The fifth transfer would overdraw, so nothing moves
+        let overdraw = sign(&[100, 200, 300, 400, 500]);
+        state.produce_block(&validator, vec![overdraw], false).unwrap();
+        assert!(!state.block_receipts(1).unwrap()[0].success);
+        assert_eq!(state.balance(&sender.address()), before);
+        assert_eq!(state.nonce(&sender.address()), 0);
+        for recipient in &recipients {
+            assert_eq!(state.balance(recipient), U256::ZERO);
+        }
+        
+        let payout = sign(&[100, 200, 300, 200, 100]);
+        state.produce_block(&validator, vec![payout], false).unwrap();
+        let receipt = &state.block_receipts(2).unwrap()[0];
+        assert!(receipt.success);
+        assert_eq!(receipt.gas_used, gas);
+        assert_eq!(state.balance(&recipients[3]), U256::from(200u64));
+        assert_eq!(state.nonce(&sender.address()), 1);
+        assert_eq!(state.balance(&sender.address()), before - fee - U256::from(900u64));
+        state.verify_supply().unwrap();
+        
+        let _ = std::fs::remove_dir_all(&temp_dir);
+    }
+    
+    #[test]
+    fn test_gas_limit_follows_utilization() {
+        use merklith_crypto::Keypair;
+        use merklith_types::Transaction;
+        
+        let temp_dir = std::env::temp_dir().join(format!("merklith_gas_limit_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        let sender = Keypair::from_seed(&[1u8; 32]);
+        let validator = Address::from_bytes([9u8; 20]);
+        let mut genesis = GenesisConfig::devnet();
+        // Room for exactly two transfers, with a 50% utilization target
+        genesis.chain_config.gas_limit = 2 * TRANSFER_GAS;
+        genesis.chain_config.gas_target = TRANSFER_GAS;
+        genesis.chain_config.min_gas_limit = 2 * TRANSFER_GAS;
+        genesis.add_alloc(sender.address(), U256::from(1_000_000_000_000_000_000u128));
+        let base_fee = genesis.chain_config.min_base_fee;
+        let state = State::with_genesis(temp_dir.clone(), genesis, PruningConfig::default());
+        let transfer = |nonce: u64| {
+            let tx = Transaction::new(state.chain_id(), nonce, Some(validator), U256::ONE, TRANSFER_GAS, base_fee, U256::ZERO);
+            let (signature, public_key) = sender.sign_transaction(&tx);
+            SignedTransaction::new(tx, signature, public_key)
+        };
+        
+        // Sustained full blocks raise the limit by at most 1/1024 each
+        let mut limit = 2 * TRANSFER_GAS;
+        for number in 1..=4u64 {
+            let nonce = state.nonce(&sender.address());
+            let mut txs = vec![transfer(nonce), transfer(nonce + 1)];
+            if number == 4 {
+                // A transfer that no longer fits is rejected
+                txs.push(transfer(nonce + 2));
+            }
+            state.produce_block(&validator, txs, false).unwrap();
+            let block = state.get_block(number).unwrap();
+            assert_eq!(block.gas_limit, limit);
+            assert_eq!(block.gas_used, 2 * TRANSFER_GAS);
+            let next = state.next_gas_limit();
+            assert!(next > limit && next <= limit + limit / 1024);
+            limit = next;
+        }
+        assert!(!state.block_receipts(4).unwrap()[2].success);
+        
+        // Heartbeat blocks leave it alone
+        for number in 5..=10u64 {
+            state.produce_block(&validator, vec![], true).unwrap();
+            assert_eq!(state.get_block(number).unwrap().gas_limit, limit);
+            assert_eq!(state.next_gas_limit(), limit);
+        }
+        
+        // A proposer target is approached within the same bounds
+        state.set_gas_limit_target(Some(100 * TRANSFER_GAS));
+        assert_eq!(state.next_gas_limit(), limit + limit / 1024);
+        state.set_gas_limit_target(Some(0));
+        assert_eq!(state.next_gas_limit(), limit - limit / 1024);
+        
+        let _ = std::fs::remove_dir_all(&temp_dir);
+    }
+    
+    #[test]
+    fn test_trace_transaction() {
+        use merklith_types::Transaction;
+        
+        let temp_dir = std::env::temp_dir().join(format!("merklith_trace_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        let state = State::with_pruning(temp_dir.clone(), PruningConfig::default().with_snapshot_interval(1));
+        let sender = &State::devnet_accounts()[0];
+        let validator = Address::from_bytes([9u8; 20]);
+        
+        // SSTORE 0x2a -> 1; SSTORE 0x07 -> 2; STOP
+        let code = vec![0x60, 0x2a, 0x60, 0x01, 0x55, 0x60, 0x07, 0x60, 0x02, 0x55, 0x00];
+        let contract = state.deploy_contract(&sender.address(), code).unwrap();
+        state.produce_block(&validator, vec![], true).unwrap();
+        
+        let tx = Transaction::new(
+            state.chain_id(),
+            state.nonce(&sender.address()),
+            Some(contract),
+            U256::ZERO,
+            100_000,
+            U256::from(1_000_000_000u64),
+            U256::ZERO,
+        );
+        let (signature, public_key) = sender.sign_transaction(&tx);
+        let signed = SignedTransaction::new(tx, signature, public_key);
+        state.produce_block(&validator, vec![signed.clone()], false).unwrap();
+        assert!(state.block_receipts(2).unwrap()[0].success);
+        
+        let mut slot = [0u8; 32];
+        slot[31] = 2;
+        let mut seven = [0u8; 32];
+        seven[31] = 7;
+        assert_eq!(state.get_storage(&contract, slot), Some(seven));
+        
+        let result = state.trace_transaction(&signed.hash()).unwrap();
+        assert!(result.success);
+        let writes: Vec<_> = result
+            .trace
+            .iter()
+            .filter_map(|step| match step.storage {
+                Some(merklith_vm::StorageAccess::Write { key, value }) => Some((key[31], value[31])),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(writes, vec![(1, 0x2a), (2, 0x07)]);
+        assert_eq!(result.trace.last().unwrap().op, "STOP");
+        assert!(result.trace.iter().all(|step| step.gas_cost > 0 || step.op == "STOP"));
+        
+        assert!(state.trace_transaction(&Hash::ZERO).is_err());
+        let _ = std::fs::remove_dir_all(&temp_dir);
+    }
+    
+    #[test]
+    fn test_trace_replays_rewards_from_genesis() {
+        use merklith_crypto::Keypair;
+        use merklith_types::Transaction;
+        
+        let temp_dir = std::env::temp_dir().join(format!("merklith_trace_genesis_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        // Counter: SLOAD 1; ADD 1; SSTORE 1; STOP
+        let contract = Address::from_bytes([0xc0; 20]);
+        let mut genesis = State::devnet_genesis();
+        genesis.add_system_contract(contract, vec![0x60, 0x01, 0x54, 0x60, 0x01, 0x01, 0x60, 0x01, 0x55, 0x00], None);
+        let state = State::with_genesis(temp_dir.clone(), genesis, PruningConfig::archive().with_snapshot_interval(0));
+        assert!(state.snapshot_heights().is_empty());
+        
+        let call = |keypair: &Keypair| {
+            let tx = Transaction::new(
+                state.chain_id(),
+                state.nonce(&keypair.address()),
+                Some(contract),
+                U256::ZERO,
+                100_000,
+                U256::from(1_000_000_000u64),
+                U256::ZERO,
+            );
+            let (signature, public_key) = keypair.sign_transaction(&tx);
+            SignedTransaction::new(tx, signature, public_key)
+        };
+        
+        // The validator can only pay for its call out of its block reward
+        let validator = Keypair::from_seed(&[9u8; 32]);
+        state.produce_block(&validator.address(), vec![], false).unwrap();
+        state.produce_block(&validator.address(), vec![call(&validator)], false).unwrap();
+        assert!(state.block_receipts(2).unwrap()[0].success);
+        let mut slot = [0u8; 32];
+        slot[31] = 1;
+        let counter = state.get_storage(&contract, slot).unwrap();
+        assert_ne!(counter, [0u8; 32]);
+        
+        let signed = call(&State::devnet_accounts()[0]);
+        state.produce_block(&validator.address(), vec![signed.clone()], false).unwrap();
+        
+        let result = state.trace_transaction(&signed.hash()).unwrap();
+        let reads: Vec<_> = result
+            .trace
+            .iter()
+            .filter_map(|step| match step.storage {
+                Some(merklith_vm::StorageAccess::Read { value, .. }) => Some(value),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(reads, vec![counter]);
+        
+        let _ = std::fs::remove_dir_all(&temp_dir);
+    }
+    
+    #[test]
+    fn test_revert_restores_nonces() {
+        use merklith_types::Transaction;
+        
+        let temp_dir = std::env::temp_dir().join(format!("merklith_revert_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        let state = State::with_pruning(temp_dir.clone(), PruningConfig::archive());
+        let sender = &State::devnet_accounts()[0];
+        let recipient = Address::from_bytes([2u8; 20]);
+        let validator = Address::from_bytes([9u8; 20]);
+        
+        state.produce_block(&validator, vec![], true).unwrap();
+        let balance = state.balance(&sender.address());
+        let supply = state.total_supply();
+        
+        let tx = Transaction::new(state.chain_id(), 0, Some(recipient), U256::from(500u64), TRANSFER_GAS, U256::from(1_000_000_000u64), U256::ZERO);
+        let (signature, public_key) = sender.sign_transaction(&tx);
+        let signed = SignedTransaction::new(tx, signature, public_key);
+        state.produce_block(&validator, vec![signed.clone()], false).unwrap();
+        // Only the accounts the block touched are kept to undo it
+        let untouched = State::devnet_accounts()[1].address();
+        let undo = state.undo.read();
+        assert!(undo[&2].accounts.iter().any(|(address, _)| *address == recipient));
+        assert!(!undo[&2].accounts.iter().any(|(address, _)| *address == untouched));
+        drop(undo);
+        // A transfer outside any block after the reverted one goes too
+        state.transfer(&sender.address(), &recipient, U256::from(7u64)).unwrap();
+        assert_eq!(state.nonce(&sender.address()), 2);
+        
+        assert!(state.revert_to(3).is_err());
+        assert_eq!(state.revert_to(1).unwrap(), vec![signed.clone()]);
+        assert_eq!(state.block_number(), 1);
+        assert_eq!(state.block_hash(), Hash::from_bytes(state.get_block(1).unwrap().hash));
+        assert_eq!(state.nonce(&sender.address()), 0);
+        assert_eq!(state.balance(&sender.address()), balance);
+        assert_eq!(state.balance(&recipient), U256::ZERO);
+        assert_eq!(state.total_supply(), supply);
+        assert!(state.block_receipts(2).is_none());
+        state.verify_supply().unwrap();
+        
+        // The rolled-back nonce is what a restart sees, and it can be used again
+        drop(state);
+        let state = State::with_pruning(temp_dir.clone(), PruningConfig::archive());
+        assert_eq!(state.block_number(), 1);
+        assert_eq!(state.nonce(&sender.address()), 0);
+        state.produce_block(&validator, vec![signed], false).unwrap();
+        assert!(state.block_receipts(2).unwrap()[0].success);
+        assert_eq!(state.nonce(&sender.address()), 1);
+        
+        // Blocks from before the restart stay put
+        assert!(state.revert_to(0).is_err());
+        let _ = std::fs::remove_dir_all(&temp_dir);
+    }
+    
+    #[test]
+    fn test_delayed_transaction() {
+        use merklith_crypto::Keypair;
+        use merklith_types::Transaction;
+        
+        let temp_dir = std::env::temp_dir().join(format!("merklith_delayed_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        
+        let sender = Keypair::from_seed(&[1u8; 32]);
+        let recipient = Address::from_bytes([2u8; 20]);
+        let validator = Address::from_bytes([9u8; 20]);
+        let mut genesis = GenesisConfig::devnet();
+        let base_fee = genesis.chain_config.min_base_fee;
+        genesis.add_alloc(sender.address(), U256::from(1_000_000u64) * base_fee);
+        let chain_id = genesis.chain_config.chain_id;
+        let state = State::with_genesis(temp_dir.clone(), genesis.clone(), PruningConfig::default());
+        let sign = |tx: Transaction| {
+            let (signature, public_key) = sender.sign_transaction(&tx);
+            SignedTransaction::new(tx, signature, public_key)
+        };
+        
+        state.produce_block(&validator, vec![], true).unwrap();
+        let n = state.block_number();
+        
+        let transfer = |nonce: u64, target: u64| {
+            sign(Transaction::new(chain_id, nonce, Some(recipient), U256::from(500u64), TRANSFER_GAS, base_fee, U256::ZERO)
+                .with_execute_after_block(target))
+        };
+        let scheduled = transfer(0, n + 3);
+        let balance = state.balance(&sender.address());
+        let hash = state.schedule_transaction(scheduled.clone()).unwrap();
+        assert!(state.schedule_transaction(scheduled.clone()).is_err());
+        assert!(state.schedule_transaction(transfer(0, n + 1)).is_err());
+        
+        // Scheduling is paid for, so the queue cannot be filled for free
+        let schedule_fee = base_fee * U256::from(SCHEDULE_GAS);
+        assert_eq!(state.balance(&sender.address()), balance - schedule_fee);
+        state.verify_supply().unwrap();
+        
+        // A second one is cancelled by its sender before it runs
+        let cancelled = transfer(1, n + 4);
+        let cancelled_hash = state.schedule_transaction(cancelled).unwrap();
+        let stranger = Keypair::from_seed(&[3u8; 32]);
+        let wrong_chain = sign(Transaction::cancel_delayed(chain_id + 1, cancelled_hash));
+        assert!(state.cancel_delayed(&wrong_chain).unwrap_err().contains("chain ID"));
+        let cancel_tx = Transaction::cancel_delayed(chain_id, cancelled_hash);
+        let (signature, public_key) = stranger.sign_transaction(&cancel_tx);
+        assert!(state.cancel_delayed(&SignedTransaction::new(cancel_tx.clone(), signature, public_key)).is_err());
+        state.cancel_delayed(&sign(cancel_tx)).unwrap();
+        
+        // The queue survives a restart
+        drop(state);
+        let state = State::with_genesis(temp_dir.clone(), genesis, PruningConfig::default());
+        assert_eq!(state.delayed_transactions().get(&(n + 3)).map(Vec::len), Some(1));
+        assert!(!state.delayed_transactions().contains_key(&(n + 4)));
+        
+        // Included too early through the pool, it fails
+        state.produce_block(&validator, vec![scheduled], false).unwrap();
+        assert!(!state.block_receipts(n + 1).unwrap()[0].success);
+        assert!(!state.has_delayed_due(n + 2));
+        state.produce_block(&validator, vec![], true).unwrap();
+        assert_eq!(state.balance(&recipient), U256::ZERO);
+        
+        assert!(state.has_delayed_due(n + 3));
+        state.produce_block(&validator, vec![], true).unwrap();
+        assert_eq!(state.block_number(), n + 3);
+        let receipts = state.block_receipts(n + 3).unwrap();
+        assert_eq!(receipts.len(), 1);
+        assert!(receipts[0].success);
+        assert_eq!(receipts[0].tx_hash, hash);
+        assert_eq!(state.balance(&recipient), U256::from(500u64));
+        assert!(state.delayed_transactions().is_empty());
+        
+        state.produce_block(&validator, vec![], true).unwrap();
+        assert!(state.block_receipts(n + 4).unwrap().is_empty());
+        assert_eq!(state.balance(&recipient), U256::from(500u64));
+        
+        let _ = std::fs::remove_dir_all(&temp_dir);
+    }
+    
+    #[test]
+    fn test_block_hash_deterministic() {
+        let block = BlockInfo {
+            number: 7,
+            hash: [0u8; 32],
+            parent_hash: [1u8; 32],
+            timestamp: 1_700_000_000,
+            tx_count: 0,
+            state_root: [2u8; 32],
+            transactions_root: [3u8; 32],
+            proposer: Address::from_bytes([4u8; 20]),
+            gas_used: 21_000,
+            gas_limit: 30_000_000,
+            reward: String::new(),
+            parent_attestation: None,
+        };
+        let hash = block.compute_hash();
+        assert_eq!(block.clone().compute_hash(), hash);
+        
+        // The stored hash and tx count are not part of the preimage
+        let mut same = block.clone();
+        same.hash = [9u8; 32];
+        same.tx_count = 3;
+        assert_eq!(same.compute_hash(), hash);
+        
+        let changes: Vec<fn(&mut BlockInfo)> = vec![
+            |b| b.parent_hash[0] ^= 1,
+            |b| b.number += 1,
+            |b| b.timestamp += 1,
+            |b| b.state_root[0] ^= 1,
+            |b| b.transactions_root[0] ^= 1,
+            |b| b.proposer = Address::from_bytes([5u8; 20]),
+            |b| b.gas_used += 1,
+            |b| b.gas_limit += 1,
+        ];
+        for change in changes {
+            let mut changed = block.clone();
+            change(&mut changed);
+            assert_ne!(changed.compute_hash(), hash);
+        }
+        
+        // Produced blocks carry the hash of their own header
+        let temp_dir = std::env::temp_dir().join(format!("merklith_block_hash_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        let state = State::with_path(temp_dir.clone());
+        let validator = Address::from_bytes([6u8; 20]);
+        let result = state.produce_block(&validator, vec![], true).unwrap();
+        let produced = state.get_block(result.block_number).unwrap();
+        assert_eq!(produced.hash, result.block_hash);
+        assert_eq!(produced.compute_hash(), produced.hash);
+        assert_eq!(produced.proposer, validator);
+        assert_eq!(produced.state_root, state.state_root());
+        assert_eq!(produced.transactions_root, transactions_root(&[]));
+        assert_eq!(state.block_hash().as_bytes(), &produced.hash);
+        
+        let _ = std::fs::remove_dir_all(&temp_dir);
+    }
+    
+    #[test]
+    fn test_code_at() {
+        let temp_dir = std::env::temp_dir().join(format!("merklith_code_at_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        
+        let deployer = Address::from_bytes([1u8; 20]);
+        let admin = Address::from_bytes([2u8; 20]);
+        let mut genesis = GenesisConfig::devnet();
+        genesis.add_alloc(deployer, U256::from(1_000u64));
+        let state = State::with_genesis(temp_dir.clone(), genesis.clone(), PruningConfig::full(10));
+        
+        let v1 = vec![0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00];
+        let v2 = [v1.clone(), vec![0x00, 0x04, 0x03, b'v', b'2', b'!']].concat();
+        
+        state.increment_block();
+        let contract = state.deploy_contract(&deployer, v1.clone()).unwrap();
+        let mut admin_word = [0u8; 32];
+        admin_word[12..].copy_from_slice(admin.as_bytes());
+        state.set_storage(&contract, admin_slot(), admin_word);
+        state.increment_block();
+        state.increment_block();
+        state.upgrade_code(&contract, v2.clone(), &admin).unwrap();
+        state.increment_block();
+        
+        assert_eq!(state.code_at(&contract, 0).unwrap(), Vec::<u8>::new());
+        assert_eq!(state.code_at(&contract, 1).unwrap(), v1);
+        assert_eq!(state.code_at(&contract, 2).unwrap(), v1);
+        assert_eq!(state.code_at(&contract, 3).unwrap(), v2);
+        assert_eq!(state.code_at(&contract, 4).unwrap(), v2);
+        assert!(matches!(state.code_at(&contract, 5), Err(StateError::InvalidBlock(_))));
+        
+        // History survives a restart
+        drop(state);
+        let state = State::with_genesis(temp_dir.clone(), genesis, PruningConfig::full(10));
+        assert_eq!(state.code_at(&contract, 2).unwrap(), v1);
+        
+        // Heights pruned from the window are refused
+        for _ in 0..10 {
+            state.increment_block();
+        }
+        assert!(matches!(state.code_at(&contract, 1), Err(StateError::InvalidBlock(_))));
+        assert_eq!(state.code_at(&contract, 14).unwrap(), v2);
+        
+        let _ = std::fs::remove_dir_all(&temp_dir);
+    }
+    
+    #[test]
+    fn test_balance_overflow() {
+        use merklith_types::{Ed25519PublicKey, Ed25519Signature, Transaction};
+        
+        let temp_dir = std::env::temp_dir().join(format!("merklith_overflow_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        
+        let public_key = Ed25519PublicKey::from_bytes([8u8; 32]);
+        let sender = public_key.to_address();
+        let full = Address::from_bytes([0xF0; 20]);
+        let validator = Address::from_bytes([0xF1; 20]);
+        let treasury = Address::from_bytes([0xF2; 20]);
+        
+        let mut genesis = GenesisConfig::devnet();
+        genesis.chain_config.treasury_address = treasury;
+        genesis.chain_config.fee_burn_pct = 0;
+        genesis.chain_config.fee_treasury_pct = 100;
+        genesis.add_alloc(sender, U256::from(1_000_000u64));
+        genesis.add_alloc(full, U256::MAX);
+        let state = State::with_genesis(temp_dir.clone(), genesis, PruningConfig::archive());
+        
+        // Transfer into a full account fails cleanly and changes nothing
+        let err = state.transfer(&sender, &full, U256::ONE).unwrap_err();
+        assert!(err.contains("overflow"), "{}", err);
+        assert_eq!(state.balance(&full), U256::MAX);
+        assert_eq!(state.balance(&sender), U256::from(1_000_000u64));
+        assert_eq!(state.nonce(&sender), 0);
+        
+        // Fill the treasury: the fee credit of the next transaction overflows
+        state.transfer(&full, &treasury, U256::MAX).unwrap();
+        let tx = Transaction::new(
+            state.chain_id(), 0, Some(validator), U256::from(5u64), TRANSFER_GAS, U256::ONE, U256::ZERO,
+        );
+        let tx = SignedTransaction::new(tx, Ed25519Signature::from_bytes([0u8; 64]), public_key);
+        let result = state.produce_block(&validator, vec![tx], false).unwrap();
+        assert_eq!(result.fees.total(), U256::ZERO);
+        assert!(!state.block_receipts(result.block_number).unwrap()[0].success);
+        assert_eq!(state.balance(&sender), U256::from(1_000_000u64));
+        assert_eq!(state.balance(&validator), result.validator_reward);
+        
+        // A validator that cannot take the reward produces no block
+        let height = state.block_number();
+        let result = state.produce_block(&treasury, vec![], false);
+        assert!(matches!(result, Err(StateError::BalanceOverflow(address)) if address == treasury));
+        assert_eq!(state.block_number(), height);
+        assert_eq!(state.balance(&treasury), U256::MAX);
+        
+        let _ = std::fs::remove_dir_all(&temp_dir);
+    }
+    
+    #[test]
+    fn test_sweep_dust() {
+        let temp_dir = std::env::temp_dir().join(format!("merklith_dust_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        
+        let dust_a = Address::from_bytes([1u8; 20]);
+        let dust_b = Address::from_bytes([2u8; 20]);
+        let large = Address::from_bytes([3u8; 20]);
+        let contract = Address::from_bytes([4u8; 20]);
+        let sender = Address::from_bytes([5u8; 20]);
+        let beneficiary = Address::from_bytes([6u8; 20]);
+        
+        let mut genesis = GenesisConfig::devnet();
+        genesis.add_alloc(dust_a, U256::from(10u64));
+        genesis.add_alloc(dust_b, U256::from(20u64));
+        genesis.add_alloc(large, U256::from(1_000u64));
+        genesis.add_alloc(contract, U256::from(5u64));
+        genesis.alloc.last_mut().unwrap().code = Some(vec![0x00, 0x61, 0x73, 0x6d]);
+        genesis.add_alloc(sender, U256::from(50u64));
+        
+        let state = State::with_genesis(temp_dir.clone(), genesis, PruningConfig::archive());
+        state.transfer(&sender, &large, U256::from(1u64)).unwrap();
+        let supply = state.total_supply();
+        
+        let (swept, collected) = state.sweep_dust(U256::from(100u64), &beneficiary).unwrap();
+        assert_eq!(swept, 2);
+        assert_eq!(collected, U256::from(30u64));
+        assert_eq!(state.balance(&beneficiary), U256::from(30u64));
+        
+        let remaining: Vec<Address> = state.all_accounts().into_iter().map(|(a, _)| a).collect();
+        assert!(!remaining.contains(&dust_a));
+        assert!(!remaining.contains(&dust_b));
+        assert!(remaining.contains(&large));
+        assert!(remaining.contains(&contract));
+        assert!(remaining.contains(&sender));
+        
+        assert_eq!(state.total_supply(), supply);
+        state.verify_supply().unwrap();
+        
+        let _ = std::fs::remove_dir_all(&temp_dir);
+    }
+    
+    #[test]
+    fn test_devnet_accounts_match_genesis() {
+        let accounts = State::devnet_accounts();
+        let genesis = State::devnet_genesis();
+        assert_eq!(accounts.len(), DEVNET_ACCOUNT_COUNT);
+        assert_eq!(genesis.alloc.len(), DEVNET_ACCOUNT_COUNT);
+        for (keypair, alloc) in accounts.iter().zip(&genesis.alloc) {
+            assert_eq!(keypair.address(), alloc.address);
+            assert!(alloc.balance > U256::ZERO);
+        }
+
+        // Derivation is reproducible from the published mnemonic
+        let first = merklith_crypto::keypair_from_mnemonic(DEVNET_MNEMONIC, "m/44'/7331'/0'/0'/0'").unwrap();
+        assert_eq!(first.to_bytes(), accounts[0].to_bytes());
+        assert_ne!(accounts[0].address(), accounts[1].address());
+    }
+
     #[test]
     fn test_transfer() {
         // Use temp directory for test
@@ -754,7 +4027,7 @@ mod tests {
         
         let state = State::with_path(temp_dir.clone());
         
-        let from = parse_address("0x742d35Cc6634C0532925a3b844Bc9e7595f0bEb0").unwrap();
+        let from = State::devnet_accounts()[0].address();
         let to = parse_address("0x0000000000000000000000000000000000000001").unwrap();
         
         let initial = state.balance(&from);
@@ -776,4 +4049,62 @@ mod tests {
         // Cleanup
         let _ = std::fs::remove_dir_all(&temp_dir);
     }
+    
+    fn pruned_state(name: &str, pruning: PruningConfig) -> (State, PathBuf) {
+        let temp_dir = std::env::temp_dir()
+            .join(format!("merklith_pruning_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        
+        let state = State::with_pruning(temp_dir.clone(), pruning);
+        for _ in 0..30 {
+            state.increment_block();
+        }
+        (state, temp_dir)
+    }
+    
+    #[test]
+    fn test_pruning_archive_keeps_everything() {
+        let pruning = PruningConfig::archive().with_snapshot_interval(5);
+        let (state, temp_dir) = pruned_state("archive", pruning);
+        
+        assert!(state.block_body(1).is_some());
+        assert_eq!(state.snapshot_heights(), vec![0, 5, 10, 15, 20, 25, 30]);
+        
+        let _ = std::fs::remove_dir_all(&temp_dir);
+    }
+    
+    #[test]
+    fn test_pruning_full_drops_old_bodies() {
+        let pruning = PruningConfig::full(10).with_snapshot_interval(5);
+        let (state, temp_dir) = pruned_state("full", pruning);
+        
+        assert!(state.block_body(20).is_none());
+        assert!(state.block_body(21).is_some());
+        assert_eq!(state.snapshot_heights(), vec![0, 5, 10, 15, 20, 25, 30]);
+        // Headers are never pruned
+        assert!(state.get_block(1).is_some());
+        
+        let _ = std::fs::remove_dir_all(&temp_dir);
+    }
+    
+    #[test]
+    fn test_pruning_fast_keeps_finalized_snapshot() {
+        let pruning = PruningConfig::fast(10).with_snapshot_interval(5);
+        let (state, temp_dir) = pruned_state("fast", pruning.clone());
+        
+        assert!(state.block_body(20).is_none());
+        assert!(state.block_body(21).is_some());
+        assert_eq!(state.snapshot_heights(), vec![20, 25, 30]);
+        
+        let genesis = State::devnet_accounts()[0].address();
+        assert_eq!(state.snapshot_balance(20, &genesis), Some(state.balance(&genesis)));
+        
+        // Retained history survives a restart
+        drop(state);
+        let reloaded = State::with_pruning(temp_dir.clone(), pruning);
+        assert_eq!(reloaded.snapshot_heights(), vec![20, 25, 30]);
+        assert!(reloaded.block_body(30).is_some());
+        
+        let _ = std::fs::remove_dir_all(&temp_dir);
+    }
 }
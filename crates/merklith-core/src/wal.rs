@@ -0,0 +1,99 @@
+//! Write-ahead log for state transitions not yet flushed to disk
+//!
+//! Records are appended as JSON lines and synced before the call that
+//! applied them returns. After a flush the log is truncated, so on restart
+//! it holds the transitions the state file is missing, plus the ones it
+//! already includes if the process died between the two. Callers number
+//! their records to tell those apart.
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+
+/// Append-only log file of serialized records
+#[derive(Debug, Clone)]
+pub struct WriteAheadLog {
+    path: PathBuf,
+}
+
+impl WriteAheadLog {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    /// Append `record` and sync it to disk
+    pub fn append<T: Serialize>(&self, record: &T) -> Result<(), String> {
+        let mut line = serde_json::to_string(record).map_err(|e| e.to_string())?;
+        line.push('\n');
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| e.to_string())?;
+        file.write_all(line.as_bytes()).map_err(|e| e.to_string())?;
+        file.sync_data().map_err(|e| e.to_string())
+    }
+
+    /// Records in the order they were appended. A torn final line from a
+    /// crash mid-append is dropped.
+    pub fn read<T: DeserializeOwned>(&self) -> Result<Vec<T>, String> {
+        let file = match fs::File::open(&self.path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.to_string()),
+        };
+        let mut records = Vec::new();
+        for line in BufReader::new(file).lines() {
+            let line = line.map_err(|e| e.to_string())?;
+            match serde_json::from_str(&line) {
+                Ok(record) => records.push(record),
+                Err(e) => {
+                    tracing::warn!("Stopping WAL replay at unreadable record: {}", e);
+                    break;
+                }
+            }
+        }
+        Ok(records)
+    }
+
+    /// Drop every record, once the state they describe is on disk
+    pub fn truncate(&self) -> Result<(), String> {
+        match fs::remove_file(&self.path) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_append_read_truncate() {
+        let dir = std::env::temp_dir().join(format!("merklith_wal_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let wal = WriteAheadLog::new(dir.join("state.wal"));
+        assert!(wal.read::<u64>().unwrap().is_empty());
+
+        wal.append(&1u64).unwrap();
+        wal.append(&2u64).unwrap();
+        assert_eq!(wal.read::<u64>().unwrap(), vec![1, 2]);
+
+        // A record cut short by a crash is ignored
+        let mut file = OpenOptions::new().append(true).open(dir.join("state.wal")).unwrap();
+        file.write_all(b"3").unwrap();
+        file.write_all(b"{\"torn").unwrap();
+        assert_eq!(wal.read::<u64>().unwrap(), vec![1, 2]);
+
+        wal.truncate().unwrap();
+        assert!(wal.read::<u64>().unwrap().is_empty());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
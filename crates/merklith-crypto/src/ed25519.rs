@@ -1,6 +1,6 @@
 use crate::error::CryptoError;
 use merklith_types::{Address, Ed25519PublicKey, Ed25519Signature, Transaction};
-use ed25519_dalek::{Signer, Verifier};
+use ed25519_dalek::Signer;
 use rand::rngs::OsRng;
 use std::fmt;
 use zeroize::Zeroize;
@@ -77,15 +77,54 @@ impl Drop for Keypair {
     }
 }
 
+/// Group order `l` of the ed25519 base point, little-endian.
+const GROUP_ORDER: [u8; 32] = [
+    0xed, 0xd3, 0xf5, 0x5c, 0x1a, 0x63, 0x12, 0x58, 0xd6, 0x9c, 0xf7, 0xa2, 0xde, 0xf9, 0xde, 0x14,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10,
+];
+
+/// Whether the little-endian scalar `s` is reduced below the group order.
+fn is_canonical_scalar(s: &[u8]) -> bool {
+    for (byte, order) in s.iter().rev().zip(GROUP_ORDER.iter().rev()) {
+        if byte != order {
+            return byte < order;
+        }
+    }
+    false
+}
+
+/// Parse a public key and signature, rejecting the all-zero and low-order
+/// keys and non-canonical `s` values that would make signatures forgeable
+/// or malleable.
+fn parse_strict(
+    public_key: &Ed25519PublicKey,
+    signature: &Ed25519Signature,
+) -> Result<(ed25519_dalek::VerifyingKey, ed25519_dalek::Signature), CryptoError> {
+    if public_key.is_zero() {
+        return Err(CryptoError::WeakPublicKey);
+    }
+    let pk = ed25519_dalek::VerifyingKey::from_bytes(public_key.as_bytes())
+        .map_err(|_| CryptoError::InvalidPublicKey)?;
+    if pk.is_weak() {
+        return Err(CryptoError::WeakPublicKey);
+    }
+    if !is_canonical_scalar(&signature.as_bytes()[32..]) {
+        return Err(CryptoError::NonCanonicalSignature);
+    }
+    Ok((pk, ed25519_dalek::Signature::from_bytes(signature.as_bytes())))
+}
+
 /// Verify an ed25519 signature.
+///
+/// Weak public keys and non-canonical signatures are rejected with their own
+/// errors; the signature itself is checked with strict verification.
 pub fn verify(
     public_key: &Ed25519PublicKey,
     message: &[u8],
     signature: &Ed25519Signature,
 ) -> Result<(), CryptoError> {
-    let pk = ed25519_dalek::VerifyingKey::from_bytes(public_key.as_bytes())?;
-    let sig = ed25519_dalek::Signature::from_bytes(signature.as_bytes());
-    pk.verify(message, &sig)?;
+    let (pk, sig) = parse_strict(public_key, signature)?;
+    pk.verify_strict(message, &sig)?;
     Ok(())
 }
 
@@ -99,9 +138,10 @@ pub fn batch_verify(
     let mut public_keys: Vec<ed25519_dalek::VerifyingKey> = Vec::with_capacity(items.len());
 
     for (pk, msg, sig) in items {
+        let (pk, sig) = parse_strict(pk, sig)?;
         messages.push(msg);
-        signatures.push(ed25519_dalek::Signature::from_bytes(sig.as_bytes()));
-        public_keys.push(ed25519_dalek::VerifyingKey::from_bytes(pk.as_bytes())?);
+        signatures.push(sig);
+        public_keys.push(pk);
     }
 
     ed25519_dalek::verify_batch(&messages, &signatures, &public_keys)
@@ -161,6 +201,54 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_zero_public_key_rejected() {
+        use ed25519_dalek::Verifier;
+
+        // The all-zero key is a point of order 4: with R the identity and s = 0
+        // a quarter of all messages "verify" under plain verification
+        let zero = ed25519_dalek::VerifyingKey::from_bytes(&[0u8; 32]).unwrap();
+        let mut forged = [0u8; 64];
+        forged[0] = 1;
+        let message = (0u32..256)
+            .map(|i| i.to_le_bytes())
+            .find(|m| zero.verify(m, &ed25519_dalek::Signature::from_bytes(&forged)).is_ok())
+            .expect("a forgeable message");
+
+        let result = verify(
+            &Ed25519PublicKey::from_bytes([0u8; 32]),
+            &message,
+            &Ed25519Signature::from_bytes(forged),
+        );
+        assert_eq!(result, Err(CryptoError::WeakPublicKey));
+    }
+
+    #[test]
+    fn test_non_canonical_signature_rejected() {
+        let keypair = Keypair::from_seed(&[7u8; 32]);
+        let message = b"malleable";
+        let signature = keypair.sign(message);
+        assert!(verify(&keypair.public_key(), message, &signature).is_ok());
+
+        // s + l is the same scalar mod l, so it would verify if not rejected
+        let mut bytes = *signature.as_bytes();
+        let mut carry = 0u16;
+        for (byte, order) in bytes[32..].iter_mut().zip(GROUP_ORDER) {
+            let sum = *byte as u16 + order as u16 + carry;
+            *byte = sum as u8;
+            carry = sum >> 8;
+        }
+        let malleated = Ed25519Signature::from_bytes(bytes);
+        assert_eq!(
+            verify(&keypair.public_key(), message, &malleated),
+            Err(CryptoError::NonCanonicalSignature)
+        );
+        assert_eq!(
+            batch_verify(&[(keypair.public_key(), message.to_vec(), malleated)]),
+            Err(CryptoError::NonCanonicalSignature)
+        );
+    }
+
     #[test]
     fn test_batch_verify() {
         let keypairs: Vec<Keypair> = (0..10).map(|_| Keypair::generate()).collect();
@@ -15,6 +15,12 @@ pub enum CryptoError {
     #[error("Signature verification failed")]
     VerificationFailed,
 
+    #[error("Weak public key: identity or low-order point")]
+    WeakPublicKey,
+
+    #[error("Non-canonical signature encoding")]
+    NonCanonicalSignature,
+
     #[error("BLS aggregation failed: {0}")]
     BLSAggregationError(String),
 
@@ -7,6 +7,7 @@
 //! - VRF (Verifiable Random Function)
 //! - Merkle trees and proofs
 //! - Encrypted keystore
+//! - Mnemonic (BIP-39 / SLIP-0010) key derivation
 
 pub mod ed25519;
 pub mod bls;
@@ -14,6 +15,7 @@ pub mod hash;
 pub mod vrf;
 pub mod merkle;
 pub mod keystore;
+pub mod mnemonic;
 pub mod error;
 
 pub use ed25519::{Keypair, verify as ed25519_verify, batch_verify as ed25519_batch_verify};
@@ -24,4 +26,5 @@ pub use bls::{
 pub use vrf::{VRFOutput, vrf_prove, vrf_verify, vrf_output_to_index};
 pub use merkle::{MerkleTree, MerkleProof, merkle_hash_pair};
 pub use keystore::{encrypt_keystore, decrypt_keystore, create_keystore, check_keystore};
+pub use mnemonic::{derivation_path, derive_keypair, keypair_from_mnemonic, mnemonic_to_seed};
 pub use error::CryptoError;
@@ -0,0 +1,120 @@
+//! Mnemonic key derivation.
+//!
+//! Seeds follow BIP-39 (PBKDF2-HMAC-SHA512 over the phrase) and Ed25519 keys
+//! are derived from the seed with SLIP-0010, which only supports hardened
+//! path segments. The phrase is not checked against the BIP-39 word list;
+//! any phrase of a valid length derives a seed.
+
+use crate::ed25519::Keypair;
+use crate::error::CryptoError;
+use hmac::{Hmac, Mac};
+use sha2::Sha512;
+
+type HmacSha512 = Hmac<Sha512>;
+
+/// SLIP-0044 coin type used in Merklith derivation paths.
+pub const MERKLITH_COIN_TYPE: u32 = 7331;
+
+/// PBKDF2 rounds mandated by BIP-39.
+const PBKDF2_ROUNDS: u32 = 2048;
+
+/// Offset marking a hardened path segment.
+const HARDENED: u32 = 0x8000_0000;
+
+/// Derivation path of account `index`: `m/44'/7331'/{index}'/0'/0'`.
+pub fn derivation_path(index: u32) -> String {
+    format!("m/44'/{}'/{}'/0'/0'", MERKLITH_COIN_TYPE, index)
+}
+
+/// Derive the 64-byte BIP-39 seed of `mnemonic`.
+pub fn mnemonic_to_seed(mnemonic: &str, passphrase: &str) -> Result<[u8; 64], CryptoError> {
+    let words: Vec<&str> = mnemonic.split_whitespace().collect();
+    if !matches!(words.len(), 12 | 15 | 18 | 21 | 24) {
+        return Err(CryptoError::InvalidMnemonic);
+    }
+    let phrase = words.join(" ");
+    let salt = format!("mnemonic{}", passphrase);
+
+    let mut seed = [0u8; 64];
+    pbkdf2::pbkdf2::<HmacSha512>(phrase.as_bytes(), salt.as_bytes(), PBKDF2_ROUNDS, &mut seed)
+        .map_err(|e| CryptoError::KeyDerivationFailed(e.to_string()))?;
+    Ok(seed)
+}
+
+/// Derive the Ed25519 keypair at `path` (e.g. `m/44'/7331'/0'/0'/0'`) from `seed`.
+pub fn derive_keypair(seed: &[u8], path: &str) -> Result<Keypair, CryptoError> {
+    let (mut key, mut chain_code) = hmac_split(b"ed25519 seed", &[seed])?;
+
+    let mut segments = path.split('/');
+    if segments.next() != Some("m") {
+        return Err(CryptoError::KeyDerivationFailed(format!("path must start with 'm': {}", path)));
+    }
+    for segment in segments {
+        let index = segment
+            .strip_suffix('\'')
+            .and_then(|index| index.parse::<u32>().ok())
+            .filter(|index| *index < HARDENED)
+            .ok_or_else(|| {
+                CryptoError::KeyDerivationFailed(format!(
+                    "Ed25519 only supports hardened segments, got '{}'",
+                    segment
+                ))
+            })?;
+        (key, chain_code) = hmac_split(&chain_code, &[&[0u8], &key, &(index | HARDENED).to_be_bytes()])?;
+    }
+
+    Ok(Keypair::from_seed(&key))
+}
+
+/// Derive the keypair at `path` from a mnemonic phrase without a passphrase.
+pub fn keypair_from_mnemonic(mnemonic: &str, path: &str) -> Result<Keypair, CryptoError> {
+    derive_keypair(&mnemonic_to_seed(mnemonic, "")?, path)
+}
+
+/// HMAC-SHA512 `parts` under `key` and split the output into key and chain code.
+fn hmac_split(key: &[u8], parts: &[&[u8]]) -> Result<([u8; 32], [u8; 32]), CryptoError> {
+    let mut mac = HmacSha512::new_from_slice(key)
+        .map_err(|e| CryptoError::KeyDerivationFailed(e.to_string()))?;
+    for part in parts {
+        mac.update(part);
+    }
+    let output = mac.finalize().into_bytes();
+
+    let mut left = [0u8; 32];
+    let mut right = [0u8; 32];
+    left.copy_from_slice(&output[..32]);
+    right.copy_from_slice(&output[32..]);
+    Ok((left, right))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bip39_seed_vector() {
+        let mnemonic = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        let seed = mnemonic_to_seed(mnemonic, "TREZOR").unwrap();
+        assert_eq!(
+            hex::encode(seed),
+            "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04"
+        );
+        assert!(mnemonic_to_seed("abandon about", "").is_err());
+    }
+
+    #[test]
+    fn test_slip10_vector() {
+        let seed = hex::decode("000102030405060708090a0b0c0d0e0f").unwrap();
+        assert_eq!(
+            hex::encode(derive_keypair(&seed, "m").unwrap().to_bytes()),
+            "2b4be7f19ee27bbf30c667b642d5f4aa69fd169872f8fc3059c08ebae2eb19e7"
+        );
+        assert_eq!(
+            hex::encode(derive_keypair(&seed, "m/0'").unwrap().to_bytes()),
+            "68e0fe46dfb67e368c75379acec591dad19df3cde26e63b93a8e704f1dade7a3"
+        );
+        // Non-hardened segments cannot be derived for Ed25519
+        assert!(derive_keypair(&seed, "m/0").is_err());
+        assert!(derive_keypair(&seed, "0'").is_err());
+    }
+}
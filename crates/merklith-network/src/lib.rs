@@ -16,6 +16,10 @@ pub enum NetworkError {
     ConnectionFailed(String),
     SendFailed(String),
     ParseError(String),
+    /// Frame encoded with a protocol version this node does not speak
+    VersionMismatch { expected: u8, got: u8 },
+    /// Peer is on a chain with a different genesis
+    GenesisMismatch { expected: merklith_types::Hash, got: merklith_types::Hash },
 }
 
 impl std::fmt::Display for NetworkError {
@@ -25,17 +29,27 @@ impl std::fmt::Display for NetworkError {
             NetworkError::ConnectionFailed(s) => write!(f, "Connection: {}", s),
             NetworkError::SendFailed(s) => write!(f, "Send: {}", s),
             NetworkError::ParseError(s) => write!(f, "Parse: {}", s),
+            NetworkError::VersionMismatch { expected, got } => {
+                write!(f, "Protocol version mismatch: expected {}, got {}", expected, got)
+            }
+            NetworkError::GenesisMismatch { expected, got } => {
+                write!(f, "Genesis mismatch: expected {}, got {}", expected, got)
+            }
         }
     }
 }
 
 impl std::error::Error for NetworkError {}
 
+/// Version of the P2P wire format, sent as the first byte of every frame.
+/// Bump it whenever `P2PMessage` changes shape.
+pub const PROTOCOL_VERSION: u8 = 3;
+
 /// P2P Message types
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum P2PMessage {
-    /// Handshake from new peer
-    Handshake { node_id: String, listen_port: u16 },
+    /// Handshake from new peer, naming the genesis of the chain it follows
+    Handshake { node_id: String, listen_port: u16, genesis_hash: [u8; 32] },
     /// New block announcement
     NewBlock { number: u64, hash: Vec<u8>, parent_hash: Vec<u8> },
     /// New transaction announcement
@@ -48,6 +62,38 @@ pub enum P2PMessage {
     Ping,
     /// Pong
     Pong,
+    /// A listening peer the sender, identified by its validator address, vouches for
+    RecommendPeer { address: String, recommended_by: [u8; 20] },
+}
+
+impl P2PMessage {
+    /// Serialize into a frame: the protocol version byte followed by the
+    /// bincode-encoded message
+    pub fn encode(&self) -> Result<Vec<u8>, NetworkError> {
+        let body = bincode::serialize(self)
+            .map_err(|e| NetworkError::ParseError(e.to_string()))?;
+        let mut frame = Vec::with_capacity(body.len() + 1);
+        frame.push(PROTOCOL_VERSION);
+        frame.extend_from_slice(&body);
+        Ok(frame)
+    }
+
+    /// Parse a frame produced by [`P2PMessage::encode`].
+    ///
+    /// Frames from another protocol version are rejected before the body
+    /// is deserialized.
+    pub fn decode(frame: &[u8]) -> Result<Self, NetworkError> {
+        let (&version, body) = frame
+            .split_first()
+            .ok_or_else(|| NetworkError::ParseError("Empty frame".to_string()))?;
+        if version != PROTOCOL_VERSION {
+            return Err(NetworkError::VersionMismatch {
+                expected: PROTOCOL_VERSION,
+                got: version,
+            });
+        }
+        bincode::deserialize(body).map_err(|e| NetworkError::ParseError(e.to_string()))
+    }
 }
 
 /// Block data for network transmission
@@ -59,21 +105,44 @@ pub struct BlockData {
     pub transactions: Vec<u8>,
 }
 
+/// Most blocks returned for a single `GetBlocks` request
+pub const MAX_BLOCKS_PER_REQUEST: u64 = 128;
+
+/// Time a recommended peer has to answer our handshake
+pub const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Looks up `count` blocks starting at `from` to answer peers' `GetBlocks`
+pub type BlockSource = Arc<dyn Fn(u64, u64) -> Vec<BlockData> + Send + Sync>;
+
 /// Network event
 #[derive(Debug, Clone)]
 pub enum NetworkEvent {
     PeerConnected { peer_id: String, address: String },
     PeerDisconnected { peer_id: String },
-    NewBlock { hash: merklith_types::Hash, number: u64, parent_hash: [u8; 32] },
+    /// A block announced by `peer_id`
+    NewBlock { peer_id: String, hash: merklith_types::Hash, number: u64, parent_hash: [u8; 32] },
     NewTransaction { hash: merklith_types::Hash },
     MessageReceived { from: String, data: Vec<u8> },
-    SyncProgress { current: u64, target: u64 },
+    SyncProgress { peer_id: String, current: u64, target: u64 },
+    /// Block data was sent to a peer in answer to `GetBlocks`
+    DataServed { block_number: u64, bytes_served: u64 },
+    /// A peer recommended by `recommended_by` answered our handshake with
+    /// a valid one of its own
+    PeerDiscovered { peer_id: String, recommended_by: merklith_types::Address },
+    /// An inbound peer's handshake checked out; it listens at `address`
+    PeerListening { peer_id: String, address: String },
+    /// A peer vouched for the listening peer at `address`
+    PeerRecommended { address: String, recommended_by: merklith_types::Address },
 }
 
 /// Network command
 #[derive(Debug, Clone)]
 pub enum NetworkCommand {
     Connect { address: String },
+    /// Connect to a peer another node told us about, crediting the discoverer
+    ConnectRecommended { address: String, recommended_by: merklith_types::Address },
+    /// Tell every other peer about the listening peer at `address`
+    RecommendPeer { address: String, recommended_by: merklith_types::Address },
     BroadcastBlock { number: u64, hash: [u8; 32], parent_hash: [u8; 32] },
     BroadcastTransaction { hash: [u8; 32] },
     Shutdown,
@@ -87,6 +156,9 @@ pub struct NetworkConfig {
     pub listen_port: u16,
     pub bootstrap_peers: Vec<String>,
     pub max_peers: usize,
+    /// Genesis of the local chain; peers on another chain are disconnected.
+    /// Any peer is accepted when unset.
+    pub genesis_hash: Option<merklith_types::Hash>,
 }
 
 impl NetworkConfig {
@@ -97,6 +169,7 @@ impl NetworkConfig {
             listen_port: 30303,
             bootstrap_peers: vec![],
             max_peers: 50,
+            genesis_hash: None,
         }
     }
     
@@ -110,6 +183,12 @@ impl NetworkConfig {
         self.bootstrap_peers = peers;
         self
     }
+    
+    /// Only stay connected to peers whose handshake names this genesis
+    pub fn with_genesis_hash(mut self, genesis_hash: merklith_types::Hash) -> Self {
+        self.genesis_hash = Some(genesis_hash);
+        self
+    }
 }
 
 /// Connected peer info
@@ -120,6 +199,40 @@ struct Peer {
     _port: u16,
 }
 
+/// A connected peer as reported over RPC
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeerInfo {
+    pub id: String,
+    pub address: String,
+}
+
+/// Read access to the set of connected peers
+pub trait PeerSource: Send + Sync {
+    fn connected_peers(&self) -> usize;
+    fn peers(&self) -> Vec<PeerInfo>;
+}
+
+/// Shared view of a node's peer table that stays live after the node is
+/// moved into its own task
+#[derive(Clone)]
+pub struct PeerHandle {
+    peers: Arc<RwLock<HashMap<String, Peer>>>,
+}
+
+impl PeerSource for PeerHandle {
+    fn connected_peers(&self) -> usize {
+        self.peers.read().len()
+    }
+
+    fn peers(&self) -> Vec<PeerInfo> {
+        let mut peers: Vec<_> = self.peers.read().iter()
+            .map(|(id, peer)| PeerInfo { id: id.clone(), address: peer.address.clone() })
+            .collect();
+        peers.sort_by(|a, b| a.id.cmp(&b.id));
+        peers
+    }
+}
+
 /// Real P2P network node
 pub struct NetworkNode {
     local_id: String,
@@ -130,6 +243,8 @@ pub struct NetworkNode {
     peers: Arc<RwLock<HashMap<String, Peer>>>,
     running: Arc<RwLock<bool>>,
     pending_connections: Vec<String>,
+    block_source: Option<BlockSource>,
+    genesis_hash: Option<merklith_types::Hash>,
 }
 
 impl NetworkNode {
@@ -145,20 +260,30 @@ impl NetworkNode {
             peers: Arc::new(RwLock::new(HashMap::new())),
             running: Arc::new(RwLock::new(false)),
             pending_connections: config.bootstrap_peers,
+            block_source: None,
+            genesis_hash: config.genesis_hash,
         };
         
         (node, cmd_tx)
     }
     
+    /// Serve `GetBlocks` requests from `source`. Without one, requests are ignored.
+    pub fn with_block_source(mut self, source: BlockSource) -> Self {
+        self.block_source = Some(source);
+        self
+    }
+    
     pub async fn start(&mut self) -> Result<(), NetworkError> {
         *self.running.write() = true;
         
         // Start TCP listener in background
         let listen_addr = self.listen_addr.clone();
-        let local_id = self.local_id.clone();
         let peers = self.peers.clone();
         let event_tx = self.event_tx.clone();
         let running = self.running.clone();
+        let block_source = self.block_source.clone();
+        let genesis_hash = self.genesis_hash;
+        let handshake = self.handshake();
         
         tokio::spawn(async move {
             if let Ok(addr) = listen_addr.parse::<std::net::SocketAddr>() {
@@ -169,14 +294,13 @@ impl NetworkNode {
                         tokio::select! {
                             accept_result = listener.accept() => {
                                 match accept_result {
-                                    Ok((stream, addr)) => {
+                                    Ok((mut stream, addr)) => {
                                         let peer_id = format!("peer_{}", rand::random::<u32>());
                                         
                                         // Send handshake
-                                        let _handshake = P2PMessage::Handshake {
-                                            node_id: local_id.clone(),
-                                            listen_port: 30303,
-                                        };
+                                        if let Ok(data) = handshake.encode() {
+                                            let _ = stream.write_all(&data).await;
+                                        }
                                         
                                         peers.write().insert(peer_id.clone(), Peer {
                                             _id: peer_id.clone(),
@@ -185,14 +309,20 @@ impl NetworkNode {
                                         });
                                         
                                         let _ = event_tx.send(NetworkEvent::PeerConnected {
-                                            peer_id,
+                                            peer_id: peer_id.clone(),
                                             address: addr.to_string(),
                                         }).await;
                                         
                                         tracing::info!("Peer connected from {}", addr);
                                         
                                         // Handle incoming messages from this peer
-                                        Self::handle_peer_stream(stream, event_tx.clone(), running.clone());
+                                        Self::handle_peer_stream(
+                                            stream,
+                                            PeerContext { id: peer_id, peers: peers.clone(), genesis_hash },
+                                            event_tx.clone(),
+                                            running.clone(),
+                                            block_source.clone(),
+                                        );
                                     }
                                     Err(e) => {
                                         tracing::debug!("Accept error: {}", e);
@@ -225,6 +355,10 @@ impl NetworkNode {
     fn start_command_handler(&mut self) {
         let peers = self.peers.clone();
         let running = self.running.clone();
+        let event_tx = self.event_tx.clone();
+        let handshake = self.handshake();
+        let local_id = self.local_id.clone();
+        let genesis_hash = self.genesis_hash;
         let mut cmd_rx = std::mem::replace(&mut self.cmd_rx, mpsc::channel(1).1);
         
         tokio::spawn(async move {
@@ -239,7 +373,7 @@ impl NetworkNode {
                                     parent_hash: parent_hash.to_vec(),
                                 };
                                 
-                                if let Ok(data) = bincode::serialize(&msg) {
+                                if let Ok(data) = msg.encode() {
                                     let peers_list: Vec<_> = peers.read().iter()
                                         .map(|(k, v)| (k.clone(), v.address.clone()))
                                         .collect();
@@ -260,7 +394,7 @@ impl NetworkNode {
                             NetworkCommand::BroadcastTransaction { hash } => {
                                 let msg = P2PMessage::NewTransaction { hash: hash.to_vec() };
                                 
-                                if let Ok(data) = bincode::serialize(&msg) {
+                                if let Ok(data) = msg.encode() {
                                     let peers_list: Vec<_> = peers.read().iter()
                                         .map(|(k, v)| (k.clone(), v.address.clone()))
                                         .collect();
@@ -285,6 +419,48 @@ impl NetworkNode {
                                     tracing::info!("Connected to peer at {}", address);
                                 }
                             }
+                            NetworkCommand::ConnectRecommended { address, recommended_by } => {
+                                let Ok(mut stream) = TcpStream::connect(&address).await else {
+                                    tracing::debug!("Recommended peer {} unreachable", address);
+                                    continue;
+                                };
+                                let Ok(data) = handshake.encode() else { continue };
+                                if stream.write_all(&data).await.is_err() {
+                                    continue;
+                                }
+                                if let Err(e) = await_handshake(&mut stream, &local_id, genesis_hash, HANDSHAKE_TIMEOUT).await {
+                                    tracing::debug!("Recommended peer {} rejected: {}", address, e);
+                                    continue;
+                                }
+                                peers.write().insert(address.clone(), Peer {
+                                    _id: address.clone(),
+                                    address: address.clone(),
+                                    _port: address.parse().map(|a: std::net::SocketAddr| a.port()).unwrap_or(30303),
+                                });
+                                tracing::info!("Connected to peer at {} (recommended by {})", address, recommended_by);
+                                let _ = event_tx.send(NetworkEvent::PeerDiscovered {
+                                    peer_id: address,
+                                    recommended_by,
+                                }).await;
+                            }
+                            NetworkCommand::RecommendPeer { address, recommended_by } => {
+                                let msg = P2PMessage::RecommendPeer {
+                                    address: address.clone(),
+                                    recommended_by: *recommended_by.as_bytes(),
+                                };
+                                if let Ok(data) = msg.encode() {
+                                    let peers_list: Vec<_> = peers.read().values()
+                                        .map(|v| v.address.clone())
+                                        .filter(|peer_addr| *peer_addr != address)
+                                        .collect();
+
+                                    for peer_addr in peers_list {
+                                        if let Ok(mut stream) = TcpStream::connect(&peer_addr).await {
+                                            let _ = stream.write_all(&data).await;
+                                        }
+                                    }
+                                }
+                            }
                             _ => {}
                         }
                     }
@@ -294,10 +470,21 @@ impl NetworkNode {
         });
     }
     
+    /// Handshake announcing this node and its chain
+    fn handshake(&self) -> P2PMessage {
+        P2PMessage::Handshake {
+            node_id: self.local_id.clone(),
+            listen_port: self.listen_port,
+            genesis_hash: self.genesis_hash.map_or([0u8; 32], |hash| *hash.as_bytes()),
+        }
+    }
+    
     fn handle_peer_stream(
         mut stream: TcpStream,
+        peer: PeerContext,
         event_tx: mpsc::Sender<NetworkEvent>,
         running: Arc<RwLock<bool>>,
+        block_source: Option<BlockSource>,
     ) {
         tokio::spawn(async move {
             let mut buf = [0u8; 4096];
@@ -308,39 +495,75 @@ impl NetworkNode {
                         match read_result {
                             Ok(0) => break, // Connection closed
                             Ok(n) => {
-                                if let Ok(msg) = bincode::deserialize::<P2PMessage>(&buf[..n]) {
-                                    match msg {
-                                        P2PMessage::NewBlock { number, hash, parent_hash } => {
-                                            if hash.len() == 32 && parent_hash.len() == 32 {
-                                                let mut h = [0u8; 32];
-                                                let mut ph = [0u8; 32];
-                                                h.copy_from_slice(&hash);
-                                                ph.copy_from_slice(&parent_hash);
-                                                let _ = event_tx.send(NetworkEvent::NewBlock {
-                                                    hash: merklith_types::Hash::from_bytes(h),
-                                                    number,
-                                                    parent_hash: ph,
-                                                }).await;
-                                                tracing::debug!("Received block #{} from peer", number);
-                                            }
+                                let msg = match P2PMessage::decode(&buf[..n]) {
+                                    Ok(msg) => msg,
+                                    Err(e @ NetworkError::VersionMismatch { .. }) => {
+                                        tracing::warn!("Disconnecting peer: {}", e);
+                                        break;
+                                    }
+                                    Err(e) => {
+                                        tracing::debug!("Dropping malformed frame: {}", e);
+                                        continue;
+                                    }
+                                };
+                                match msg {
+                                    P2PMessage::Handshake { node_id, listen_port, genesis_hash } => {
+                                        if let Err(e) = check_genesis(peer.genesis_hash, genesis_hash) {
+                                            tracing::warn!("Disconnecting peer {}: {}", node_id, e);
+                                            break;
                                         }
-                                        P2PMessage::NewTransaction { hash } => {
-                                            if hash.len() == 32 {
-                                                let mut h = [0u8; 32];
-                                                h.copy_from_slice(&hash);
-                                                let _ = event_tx.send(NetworkEvent::NewTransaction {
-                                                    hash: merklith_types::Hash::from_bytes(h),
+                                        if let Ok(remote) = stream.peer_addr() {
+                                            let _ = event_tx.send(NetworkEvent::PeerListening {
+                                                peer_id: peer.id.clone(),
+                                                address: std::net::SocketAddr::new(remote.ip(), listen_port).to_string(),
+                                            }).await;
+                                        }
+                                    }
+                                    P2PMessage::RecommendPeer { address, recommended_by } => {
+                                        let _ = event_tx.send(NetworkEvent::PeerRecommended {
+                                            address,
+                                            recommended_by: merklith_types::Address::from_bytes(recommended_by),
+                                        }).await;
+                                    }
+                                    P2PMessage::NewBlock { number, hash, parent_hash }
+                                        if hash.len() == 32 && parent_hash.len() == 32 => {
+                                        let mut h = [0u8; 32];
+                                        let mut ph = [0u8; 32];
+                                        h.copy_from_slice(&hash);
+                                        ph.copy_from_slice(&parent_hash);
+                                        let _ = event_tx.send(NetworkEvent::NewBlock {
+                                            peer_id: peer.id.clone(),
+                                            hash: merklith_types::Hash::from_bytes(h),
+                                            number,
+                                            parent_hash: ph,
+                                        }).await;
+                                        tracing::debug!("Received block #{} from peer", number);
+                                    }
+                                    P2PMessage::NewTransaction { hash } if hash.len() == 32 => {
+                                        let mut h = [0u8; 32];
+                                        h.copy_from_slice(&hash);
+                                        let _ = event_tx.send(NetworkEvent::NewTransaction {
+                                            hash: merklith_types::Hash::from_bytes(h),
+                                        }).await;
+                                    }
+                                    P2PMessage::GetBlocks { from, count } => {
+                                        let Some(source) = &block_source else { continue };
+                                        if let Some(frame) = serve_blocks(source, from, count) {
+                                            if stream.write_all(&frame).await.is_ok() {
+                                                let _ = event_tx.send(NetworkEvent::DataServed {
+                                                    block_number: from,
+                                                    bytes_served: frame.len() as u64,
                                                 }).await;
                                             }
                                         }
-                                        P2PMessage::Ping => {
-                                            let pong = P2PMessage::Pong;
-                                            if let Ok(data) = bincode::serialize(&pong) {
-                                                let _ = stream.write_all(&data).await;
-                                            }
+                                    }
+                                    P2PMessage::Ping => {
+                                        let pong = P2PMessage::Pong;
+                                        if let Ok(data) = pong.encode() {
+                                            let _ = stream.write_all(&data).await;
                                         }
-                                        _ => {}
                                     }
+                                    _ => {}
                                 }
                             }
                             Err(_) => break,
@@ -349,12 +572,16 @@ impl NetworkNode {
                     _ = tokio::time::sleep(Duration::from_secs(30)) => {
                         // Send ping to keep connection alive
                         let ping = P2PMessage::Ping;
-                        if let Ok(data) = bincode::serialize(&ping) {
+                        if let Ok(data) = ping.encode() {
                             let _ = stream.write_all(&data).await;
                         }
                     }
                 }
             }
+            
+            if peer.peers.write().remove(&peer.id).is_some() {
+                let _ = event_tx.send(NetworkEvent::PeerDisconnected { peer_id: peer.id }).await;
+            }
         });
     }
     
@@ -365,13 +592,7 @@ impl NetworkNode {
         let peer_id = format!("peer_{}", rand::random::<u32>());
         
         // Send handshake
-        let handshake = P2PMessage::Handshake {
-            node_id: self.local_id.clone(),
-            listen_port: self.listen_port,
-        };
-        
-        let data = bincode::serialize(&handshake)
-            .map_err(|e| NetworkError::ParseError(e.to_string()))?;
+        let data = self.handshake().encode()?;
         
         let mut stream_clone = stream;
         stream_clone.write_all(&data).await
@@ -400,7 +621,7 @@ impl NetworkNode {
             parent_hash: parent_hash.to_vec(),
         };
         
-        if let Ok(data) = bincode::serialize(&msg) {
+        if let Ok(data) = msg.encode() {
             let peers = self.peers.read();
             for (peer_id, peer) in peers.iter() {
                 // Try to send to each peer
@@ -423,6 +644,11 @@ impl NetworkNode {
         self.peers.read().len()
     }
     
+    /// Handle for reading the peer table from other components
+    pub fn peer_handle(&self) -> PeerHandle {
+        PeerHandle { peers: self.peers.clone() }
+    }
+    
     pub fn local_id(&self) -> &str {
         &self.local_id
     }
@@ -432,6 +658,61 @@ impl NetworkNode {
     }
 }
 
+/// An accepted connection, as its stream handler sees it
+struct PeerContext {
+    id: String,
+    peers: Arc<RwLock<HashMap<String, Peer>>>,
+    /// Genesis the peer's handshake must name
+    genesis_hash: Option<merklith_types::Hash>,
+}
+
+/// Check the genesis a peer's handshake names against the local one
+fn check_genesis(local: Option<merklith_types::Hash>, remote: [u8; 32]) -> Result<(), NetworkError> {
+    match local {
+        Some(expected) if *expected.as_bytes() != remote => Err(NetworkError::GenesisMismatch {
+            expected,
+            got: merklith_types::Hash::from_bytes(remote),
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Wait up to `timeout` for the peer's handshake reply and check it names our
+/// genesis and is not our own. Returns the peer's node id.
+async fn await_handshake(
+    stream: &mut TcpStream,
+    local_id: &str,
+    genesis_hash: Option<merklith_types::Hash>,
+    timeout: Duration,
+) -> Result<String, NetworkError> {
+    let mut buf = [0u8; 4096];
+    let n = tokio::time::timeout(timeout, stream.read(&mut buf))
+        .await
+        .map_err(|_| NetworkError::ConnectionFailed("no handshake reply".to_string()))?
+        .map_err(|e| NetworkError::Io(e.to_string()))?;
+    if n == 0 {
+        return Err(NetworkError::ConnectionFailed("closed before handshake".to_string()));
+    }
+    let P2PMessage::Handshake { node_id, genesis_hash: remote, .. } = P2PMessage::decode(&buf[..n])? else {
+        return Err(NetworkError::ParseError("expected a handshake".to_string()));
+    };
+    check_genesis(genesis_hash, remote)?;
+    if node_id == local_id {
+        return Err(NetworkError::ConnectionFailed("connected to ourselves".to_string()));
+    }
+    Ok(node_id)
+}
+
+/// Encode the `Blocks` reply to a `GetBlocks` request, or `None` if there is
+/// nothing to send
+fn serve_blocks(source: &BlockSource, from: u64, count: u64) -> Option<Vec<u8>> {
+    let blocks = source(from, count.min(MAX_BLOCKS_PER_REQUEST));
+    if blocks.is_empty() {
+        return None;
+    }
+    P2PMessage::Blocks { blocks }.encode().ok()
+}
+
 // Compatibility stubs
 pub mod behaviour {
     pub use super::{NetworkConfig, P2PMessage};
@@ -455,7 +736,208 @@ pub mod peer_manager {
 }
 
 pub mod sync {
+    use parking_lot::Mutex;
+    use std::collections::HashMap;
+
     #[derive(Debug, Clone)]
     pub struct SyncConfig;
     impl Default for SyncConfig { fn default() -> Self { Self } }
+
+    /// Progress of a node catching up with its peers, as reported by `eth_syncing`
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct SyncProgress {
+        /// Local height when the node fell behind
+        pub starting_block: u64,
+        /// Local height now
+        pub current_block: u64,
+        /// Highest block announced by a peer
+        pub highest_block: u64,
+    }
+
+    /// Tracks the head block each connected peer has announced against the
+    /// local height.
+    #[derive(Debug, Default)]
+    pub struct SyncTracker {
+        inner: Mutex<SyncTrackerInner>,
+    }
+
+    #[derive(Debug, Default)]
+    struct SyncTrackerInner {
+        /// Latest head announced by each peer
+        heads: HashMap<String, u64>,
+        /// Set while behind: the local height when the node fell behind
+        starting_block: Option<u64>,
+    }
+
+    impl SyncTrackerInner {
+        fn highest_block(&self) -> u64 {
+            self.heads.values().copied().max().unwrap_or(0)
+        }
+    }
+
+    impl SyncTracker {
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        /// Record `peer`'s head block, seen while the local chain is at `local_height`
+        pub fn observe_peer_head(&self, peer: &str, number: u64, local_height: u64) {
+            let mut inner = self.inner.lock();
+            let head = inner.heads.entry(peer.to_string()).or_default();
+            *head = (*head).max(number);
+            if number > local_height && inner.starting_block.is_none() {
+                inner.starting_block = Some(local_height);
+            }
+        }
+
+        /// Forget a disconnected peer's head
+        pub fn remove_peer(&self, peer: &str) {
+            self.inner.lock().heads.remove(peer);
+        }
+
+        /// Highest block announced by a connected peer
+        pub fn highest_block(&self) -> u64 {
+            self.inner.lock().highest_block()
+        }
+
+        /// Progress while behind the highest known peer block; None once caught up
+        pub fn progress(&self, local_height: u64) -> Option<SyncProgress> {
+            let mut inner = self.inner.lock();
+            let highest_block = inner.highest_block();
+            if local_height >= highest_block {
+                inner.starting_block = None;
+                return None;
+            }
+            let starting_block = *inner.starting_block.get_or_insert(local_height);
+            Some(SyncProgress {
+                starting_block,
+                current_block: local_height,
+                highest_block,
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sync_tracker() {
+        let tracker = sync::SyncTracker::new();
+        assert_eq!(tracker.progress(0), None);
+
+        tracker.observe_peer_head("a", 100, 10);
+        tracker.observe_peer_head("b", 80, 12);
+        let progress = tracker.progress(40).unwrap();
+        assert_eq!(progress.starting_block, 10);
+        assert_eq!(progress.current_block, 40);
+        assert_eq!(progress.highest_block, 100);
+
+        // Caught up: synced until a peer is ahead again
+        assert_eq!(tracker.progress(100), None);
+        tracker.observe_peer_head("b", 150, 100);
+        assert_eq!(tracker.progress(120).unwrap().starting_block, 100);
+
+        // A disconnected peer's head no longer counts
+        tracker.remove_peer("b");
+        assert_eq!(tracker.highest_block(), 100);
+        assert_eq!(tracker.progress(120), None);
+        tracker.remove_peer("a");
+        assert_eq!(tracker.highest_block(), 0);
+    }
+
+    #[test]
+    fn test_frame_roundtrip() {
+        let msg = P2PMessage::NewTransaction { hash: vec![7u8; 32] };
+        let frame = msg.encode().unwrap();
+        assert_eq!(frame[0], PROTOCOL_VERSION);
+
+        match P2PMessage::decode(&frame).unwrap() {
+            P2PMessage::NewTransaction { hash } => assert_eq!(hash, vec![7u8; 32]),
+            other => panic!("unexpected message: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_unknown_version_rejected() {
+        let mut frame = P2PMessage::Ping.encode().unwrap();
+        frame[0] = PROTOCOL_VERSION.wrapping_add(1);
+
+        assert!(matches!(
+            P2PMessage::decode(&frame),
+            Err(NetworkError::VersionMismatch { expected: PROTOCOL_VERSION, got }) if got == PROTOCOL_VERSION.wrapping_add(1)
+        ));
+        assert!(matches!(P2PMessage::decode(&[]), Err(NetworkError::ParseError(_))));
+    }
+
+    #[test]
+    fn test_genesis_checked() {
+        let ours = merklith_types::Hash::from_bytes([1u8; 32]);
+        let frame = P2PMessage::Handshake { node_id: "b".to_string(), listen_port: 30303, genesis_hash: [2u8; 32] }
+            .encode()
+            .unwrap();
+        let P2PMessage::Handshake { genesis_hash, .. } = P2PMessage::decode(&frame).unwrap() else {
+            panic!("expected a handshake");
+        };
+
+        assert!(matches!(
+            check_genesis(Some(ours), genesis_hash),
+            Err(NetworkError::GenesisMismatch { expected, .. }) if expected == ours
+        ));
+        assert!(check_genesis(Some(ours), [1u8; 32]).is_ok());
+        assert!(check_genesis(None, genesis_hash).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_await_handshake() {
+        let ours = merklith_types::Hash::from_bytes([1u8; 32]);
+        // A peer that answers with `reply`, or stays silent
+        let answer = |reply: Option<P2PMessage>| async move {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = listener.local_addr().unwrap();
+            tokio::spawn(async move {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                if let Some(reply) = reply {
+                    stream.write_all(&reply.encode().unwrap()).await.unwrap();
+                }
+                tokio::time::sleep(Duration::from_secs(1)).await;
+            });
+            let mut stream = TcpStream::connect(addr).await.unwrap();
+            await_handshake(&mut stream, "a", Some(ours), Duration::from_millis(200)).await
+        };
+        let handshake = |node_id: &str, genesis_hash: [u8; 32]| {
+            Some(P2PMessage::Handshake { node_id: node_id.to_string(), listen_port: 30303, genesis_hash })
+        };
+
+        assert_eq!(answer(handshake("b", [1u8; 32])).await.unwrap(), "b");
+        assert!(matches!(answer(handshake("b", [2u8; 32])).await, Err(NetworkError::GenesisMismatch { .. })));
+        assert!(answer(handshake("a", [1u8; 32])).await.is_err());
+        assert!(answer(Some(P2PMessage::Ping)).await.is_err());
+        assert!(answer(None).await.is_err());
+    }
+
+    #[test]
+    fn test_serve_blocks() {
+        let source: BlockSource = Arc::new(|from, count| {
+            (from..from + count)
+                .filter(|n| *n <= 10)
+                .map(|number| BlockData {
+                    number,
+                    hash: vec![number as u8; 32],
+                    parent_hash: vec![0u8; 32],
+                    transactions: vec![],
+                })
+                .collect()
+        });
+
+        let frame = serve_blocks(&source, 9, 1000).unwrap();
+        match P2PMessage::decode(&frame).unwrap() {
+            P2PMessage::Blocks { blocks } => {
+                assert_eq!(blocks.iter().map(|b| b.number).collect::<Vec<_>>(), vec![9, 10]);
+            }
+            other => panic!("unexpected message: {:?}", other),
+        }
+        assert!(serve_blocks(&source, 11, 5).is_none());
+    }
 }
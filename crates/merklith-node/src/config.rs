@@ -3,6 +3,10 @@
 //! Handles loading and validation of node configuration from
 //! config files and command-line arguments.
 
+use merklith_consensus::ContributionWeights;
+use merklith_storage::{CommitPolicy, PruningConfig};
+use merklith_txpool::{OrderingPolicy, DEFAULT_MAX_TX_SIZE};
+use merklith_types::ChainConfig;
 use serde::{Deserialize, Serialize};
 use std::net::SocketAddr;
 use std::path::PathBuf;
@@ -26,6 +30,12 @@ pub struct NodeConfig {
     pub metrics: MetricsConfig,
     /// Logging configuration
     pub logging: LoggingConfig,
+    /// HA cluster configuration
+    #[serde(default)]
+    pub cluster: ClusterConfig,
+    /// Transaction pool configuration
+    #[serde(default)]
+    pub txpool: TxPoolConfig,
 }
 
 impl Default for NodeConfig {
@@ -39,6 +49,8 @@ impl Default for NodeConfig {
             storage: StorageConfig::default(),
             metrics: MetricsConfig::default(),
             logging: LoggingConfig::default(),
+            cluster: ClusterConfig::default(),
+            txpool: TxPoolConfig::default(),
         }
     }
 }
@@ -75,6 +87,16 @@ impl NodeConfig {
         Ok(())
     }
 
+    /// Switch to a single-validator devnet: this node seals blocks as soon as
+    /// transactions arrive and does not join the P2P network.
+    pub fn enable_dev_mode(&mut self) {
+        self.consensus.dev_mode = true;
+        self.consensus.validator = true;
+        self.consensus.block_time = 0;
+        self.consensus.finality_threshold = Some(1);
+        self.network.enabled = false;
+    }
+
     /// Validate configuration.
     pub fn validate(&self) -> anyhow::Result<()> {
         // Validate network config
@@ -82,6 +104,20 @@ impl NodeConfig {
             anyhow::bail!("P2P port cannot be 0");
         }
 
+        if self.consensus.chain_id == 0 {
+            anyhow::bail!("Chain ID cannot be 0");
+        }
+
+        // Dev accounts have published keys, so they must never exist on a real chain
+        let devnet_chain_id = ChainConfig::devnet().chain_id;
+        if self.consensus.dev_mode && self.consensus.chain_id != devnet_chain_id {
+            anyhow::bail!(
+                "Dev mode requires the devnet chain ID {}, got {}",
+                devnet_chain_id,
+                self.consensus.chain_id
+            );
+        }
+
         // Validate RPC config
         if self.rpc.http_enabled && self.rpc.http_port == 0 {
             anyhow::bail!("RPC HTTP port cannot be 0");
@@ -140,6 +176,74 @@ pub struct RpcConfig {
     pub max_body_size: usize,
     /// Rate limit (requests per second)
     pub rate_limit: Option<u32>,
+    /// Bearer token for admin RPC methods (e.g. `merklith_sweepDust`)
+    #[serde(default)]
+    pub admin_token: Option<String>,
+    /// Requests slower than this (milliseconds) are logged as warnings
+    #[serde(default = "default_slow_request_ms")]
+    pub slow_request_ms: u64,
+    /// Maximum concurrent connections
+    #[serde(default = "default_max_connections")]
+    pub max_connections: u32,
+    /// Seconds allowed to receive a request's headers and body
+    #[serde(default = "default_read_timeout_secs")]
+    pub read_timeout_secs: u64,
+    /// Seconds a connection may sit without traffic before it is closed
+    #[serde(default = "default_idle_timeout_secs")]
+    pub idle_timeout_secs: u64,
+    /// Only serve these methods (names or namespaces like `eth_*`); all when unset
+    #[serde(default)]
+    pub enabled_methods: Option<Vec<String>>,
+    /// Never serve these methods or namespaces, e.g. `["debug_*", "merklith_createWallet"]`
+    #[serde(default)]
+    pub disabled_methods: Vec<String>,
+    /// Deepest array/object nesting accepted in a request body
+    #[serde(default = "default_max_json_depth")]
+    pub max_json_depth: usize,
+    /// Most elements accepted in any single JSON array or object
+    #[serde(default = "default_max_json_elements")]
+    pub max_json_elements: usize,
+    /// Most calls accepted in one JSON-RPC batch
+    #[serde(default = "default_max_batch_size")]
+    pub max_batch_size: usize,
+    /// Largest return data, in bytes, a contract call may produce
+    #[serde(default = "default_max_return_data")]
+    pub max_return_data: usize,
+    /// Addresses whose transactions are refused, one per line; re-read when the file changes
+    #[serde(default)]
+    pub blocklist_file: Option<PathBuf>,
+}
+
+fn default_slow_request_ms() -> u64 {
+    1000
+}
+
+fn default_max_connections() -> u32 {
+    1000
+}
+
+fn default_read_timeout_secs() -> u64 {
+    10
+}
+
+fn default_idle_timeout_secs() -> u64 {
+    60
+}
+
+fn default_max_json_depth() -> usize {
+    64
+}
+
+fn default_max_json_elements() -> usize {
+    10_000
+}
+
+fn default_max_batch_size() -> usize {
+    100
+}
+
+fn default_max_return_data() -> usize {
+    merklith_vm::MAX_RETURN_DATA_BYTES
 }
 
 impl Default for RpcConfig {
@@ -159,6 +263,18 @@ impl Default for RpcConfig {
             cors: true,
             max_body_size: 10,
             rate_limit: None,
+            admin_token: None,
+            slow_request_ms: default_slow_request_ms(),
+            max_connections: default_max_connections(),
+            read_timeout_secs: default_read_timeout_secs(),
+            idle_timeout_secs: default_idle_timeout_secs(),
+            enabled_methods: None,
+            disabled_methods: Vec::new(),
+            max_json_depth: default_max_json_depth(),
+            max_json_elements: default_max_json_elements(),
+            max_batch_size: default_max_batch_size(),
+            max_return_data: default_max_return_data(),
+            blocklist_file: None,
         }
     }
 }
@@ -174,6 +290,10 @@ pub struct ConsensusConfig {
     pub validator: bool,
     /// Validator key file
     pub validator_key: Option<PathBuf>,
+    /// BLS secret key file (hex) this validator attests blocks with; without
+    /// it the validator does not join the attestation committee
+    #[serde(default)]
+    pub bls_key: Option<PathBuf>,
     /// Minimum stake (in MERK)
     pub min_stake: u64,
     /// Max consecutive empty blocks before increasing block time
@@ -182,6 +302,36 @@ pub struct ConsensusConfig {
     pub empty_block_timeout: Option<u64>,
     /// Finality threshold (number of blocks to consider final)
     pub finality_threshold: Option<u32>,
+    /// PoC score per contribution type
+    #[serde(default)]
+    pub contribution_weights: ContributionWeights,
+    /// Single-validator devnet with pre-funded accounts and instant block production
+    #[serde(default)]
+    pub dev_mode: bool,
+    /// Block gas limit this validator's blocks move towards, within the
+    /// per-block bounds of the chain (None follows utilization)
+    #[serde(default)]
+    pub gas_limit_target: Option<u64>,
+    /// Unix time the chain starts at (None keeps the network's default).
+    /// Part of the genesis hash, so every node on a chain must agree on it.
+    #[serde(default)]
+    pub genesis_timestamp: Option<u64>,
+    /// Pending transactions that make a validator seal a block before the
+    /// heartbeat is due
+    #[serde(default = "default_min_txs_to_produce")]
+    pub min_txs_to_produce: usize,
+    /// Seconds a validator goes without a block before sealing an empty
+    /// heartbeat block
+    #[serde(default = "default_max_empty_interval")]
+    pub max_empty_interval: u64,
+}
+
+fn default_min_txs_to_produce() -> usize {
+    1
+}
+
+fn default_max_empty_interval() -> u64 {
+    3600
 }
 
 impl Default for ConsensusConfig {
@@ -191,10 +341,17 @@ impl Default for ConsensusConfig {
             block_time: 12, // 12 seconds - Bitcoin/Ethereum arası optimal
             validator: false,
             validator_key: None,
+            bls_key: None,
             min_stake: 0, // Devnet: no minimum
             max_empty_blocks: Some(2), // Skip 2 empty blocks max
             empty_block_timeout: Some(60), // 60s timeout for heartbeat
             finality_threshold: Some(1), // PoC: single block finality
+            contribution_weights: ContributionWeights::default(),
+            dev_mode: false,
+            gas_limit_target: None,
+            genesis_timestamp: None,
+            min_txs_to_produce: default_min_txs_to_produce(),
+            max_empty_interval: default_max_empty_interval(),
         }
     }
 }
@@ -208,6 +365,15 @@ pub struct StorageConfig {
     pub cache_size: usize,
     /// Enable compression
     pub compression: bool,
+    /// History retention policy
+    #[serde(default)]
+    pub pruning: PruningConfig,
+    /// How often state is flushed to disk; a block becoming final is always flushed
+    #[serde(default)]
+    pub commit_policy: CommitPolicy,
+    /// Transactions saved with `merklith_exportMempool`, re-admitted on startup
+    #[serde(default)]
+    pub mempool_file: Option<PathBuf>,
 }
 
 impl Default for StorageConfig {
@@ -216,6 +382,28 @@ impl Default for StorageConfig {
             db_path: PathBuf::from("./data/db"),
             cache_size: 512,
             compression: true,
+            pruning: PruningConfig::default(),
+            commit_policy: CommitPolicy::default(),
+            mempool_file: None,
+        }
+    }
+}
+
+/// Transaction pool configuration.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TxPoolConfig {
+    /// Order in which pending transactions go into blocks
+    pub ordering: OrderingPolicy,
+    /// Largest accepted transaction, in borsh-encoded bytes
+    pub max_tx_size: usize,
+}
+
+impl Default for TxPoolConfig {
+    fn default() -> Self {
+        Self {
+            ordering: OrderingPolicy::default(),
+            max_tx_size: DEFAULT_MAX_TX_SIZE,
         }
     }
 }
@@ -264,6 +452,59 @@ impl Default for LoggingConfig {
     }
 }
 
+/// HA cluster configuration.
+///
+/// When enabled, only the elected leader among the configured peers runs
+/// the block-production loop; standbys take over once the leader's
+/// heartbeats stop for `failover_timeout_secs`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClusterConfig {
+    /// Enable leader election
+    pub enabled: bool,
+    /// Cluster-unique id of this node
+    pub node_id: String,
+    /// Election priority (higher wins)
+    pub priority: u32,
+    /// Where this node accepts heartbeats from the other members
+    #[serde(default = "default_cluster_listen_addr")]
+    pub listen_addr: SocketAddr,
+    /// Other cluster members
+    pub peers: Vec<ClusterPeerConfig>,
+    /// Heartbeat broadcast interval (seconds)
+    pub heartbeat_interval_secs: u64,
+    /// Time without heartbeats before a peer is considered dead (seconds)
+    pub failover_timeout_secs: u64,
+}
+
+impl Default for ClusterConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            node_id: "merklith-node".to_string(),
+            priority: 0,
+            listen_addr: default_cluster_listen_addr(),
+            peers: vec![],
+            heartbeat_interval_secs: 5,
+            failover_timeout_secs: 15,
+        }
+    }
+}
+
+fn default_cluster_listen_addr() -> SocketAddr {
+    SocketAddr::from(([0, 0, 0, 0], 30400))
+}
+
+/// A configured cluster member.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClusterPeerConfig {
+    /// Peer node id
+    pub id: String,
+    /// Address the peer accepts heartbeats on (its `listen_addr`)
+    pub address: String,
+    /// Peer election priority
+    pub priority: u32,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -288,6 +529,33 @@ mod tests {
         assert!(config.validate().is_err());
     }
 
+    #[test]
+    fn test_dev_mode_requires_devnet_chain_id() {
+        let mut config = NodeConfig::default();
+        config.enable_dev_mode();
+        assert!(config.validate().is_ok());
+        assert!(config.consensus.validator);
+        assert!(!config.network.enabled);
+
+        config.consensus.chain_id = 1;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_txpool_config() {
+        let config: NodeConfig = toml::from_str(&toml::to_string(&NodeConfig::default()).unwrap()).unwrap();
+        assert_eq!(config.txpool.ordering, OrderingPolicy::Fifo);
+
+        let mut table: toml::Table = toml::to_string(&NodeConfig::default()).unwrap().parse().unwrap();
+        table.insert(
+            "txpool".to_string(),
+            toml::Value::Table(toml::toml! { ordering = "fair_round_robin" max_tx_size = 4096 }),
+        );
+        let config: NodeConfig = table.try_into().unwrap();
+        assert_eq!(config.txpool.ordering, OrderingPolicy::FairRoundRobin);
+        assert_eq!(config.txpool.max_tx_size, 4096);
+    }
+
     #[test]
     fn test_config_serialization() {
         let config = NodeConfig::default();
@@ -0,0 +1,20 @@
+//! `--dev` devnet support.
+//!
+//! Dev mode runs a single validator that seals a block as soon as a
+//! transaction arrives. The devnet genesis funds accounts derived from a
+//! published mnemonic, so their keys are printed on startup for use with
+//! wallets and scripts.
+
+use merklith_core::state_machine::{State, DEVNET_MNEMONIC};
+
+/// Print the pre-funded devnet accounts and their private keys.
+pub fn print_dev_accounts() {
+    println!("Dev accounts (1,000,000 MERK each, DO NOT use these keys on a real network):");
+    println!("Mnemonic: {}", DEVNET_MNEMONIC);
+    println!();
+    for (i, keypair) in State::devnet_accounts().iter().enumerate() {
+        println!("  ({}) {}", i, keypair.address());
+        println!("      Private key: 0x{}", hex::encode(keypair.to_bytes()));
+    }
+    println!();
+}
@@ -5,7 +5,9 @@
 
 pub mod node;
 pub mod config;
+pub mod dev;
 pub mod metrics;
+pub mod status;
 pub mod telemetry;
 
 use clap::Parser;
@@ -46,13 +48,25 @@ struct Args {
     #[arg(long)]
     bootstrap: Option<String>,
 
-    /// Log level
+    /// Log level or per-module directives, e.g. "merklith-network=debug,info"
     #[arg(short, long, default_value = "info")]
     log_level: String,
 
     /// Enable metrics
     #[arg(long)]
     metrics: bool,
+
+    /// Run a single-validator devnet with pre-funded accounts
+    #[arg(long)]
+    dev: bool,
+
+    /// Re-admit transactions saved with merklith_exportMempool
+    #[arg(long, value_name = "FILE")]
+    import_mempool: Option<PathBuf>,
+
+    /// Unix time the chain starts at; no block is produced before it
+    #[arg(long, value_name = "SECONDS")]
+    genesis_timestamp: Option<u64>,
 }
 
 #[tokio::main]
@@ -84,7 +98,16 @@ async fn main() -> anyhow::Result<()> {
     config.consensus.validator = args.validator;
     config.consensus.chain_id = args.chain_id;
     config.metrics.enabled = args.metrics;
-    
+    if args.dev {
+        config.enable_dev_mode();
+    }
+    if args.import_mempool.is_some() {
+        config.storage.mempool_file = args.import_mempool;
+    }
+    if args.genesis_timestamp.is_some() {
+        config.consensus.genesis_timestamp = args.genesis_timestamp;
+    }
+
     // Parse bootstrap peers
     if let Some(bootstrap) = &args.bootstrap {
         config.network.bootstrap_nodes = bootstrap
@@ -106,6 +129,11 @@ async fn main() -> anyhow::Result<()> {
     info!("  RPC port: {}", config.rpc.http_addr.port());
     info!("  P2P port: {}", config.network.p2p_port);
     info!("  Validator mode: {}", config.consensus.validator);
+    info!("  Dev mode: {}", config.consensus.dev_mode);
+
+    if config.consensus.dev_mode {
+        dev::print_dev_accounts();
+    }
 
     // Create and start node
     let (mut node, _shutdown) = node::MerklithNode::new(config).await?;
@@ -154,5 +182,9 @@ mod tests {
         assert_eq!(args.rpc_port, 8545);
         assert_eq!(args.chain_id, 42);
         assert!(!args.validator);
+        assert!(!args.dev);
+
+        let args = Args::parse_from(["merklith-node", "--dev"]);
+        assert!(args.dev);
     }
 }
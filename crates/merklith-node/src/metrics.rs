@@ -100,6 +100,11 @@ impl Metrics {
         }))
     }
 
+    /// Registry the node exports; other components register their metrics here.
+    pub fn registry(&self) -> &Registry {
+        &self.registry
+    }
+
     /// Export metrics in Prometheus text format.
     pub fn export(&self,
     ) -> anyhow::Result<String> {
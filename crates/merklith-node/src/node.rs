@@ -1,17 +1,28 @@
 //! Full node implementation.
 
-use merklith_core::state_machine::State;
-use merklith_network::{NetworkNode, NetworkEvent, NetworkCommand, NetworkConfig};
-use merklith_rpc::{RpcServer, RpcServerConfig};
+use merklith_audit::{AuditEvent, AuditEventType, AuditRetentionPolicy, AuditSeverity, AuditTrail};
+use merklith_core::high_availability::ClusterManager;
+use merklith_consensus::{proof_of_possession_message, Attestation, ConsensusEngine, ContributionTracker, JailEvent, ValidatorSet};
+use merklith_core::state_machine::{ParentAttestation, State};
+use merklith_crypto::BLSKeypair;
+use merklith_network::sync::SyncTracker;
+use merklith_network::{BlockData, BlockSource, NetworkNode, PeerHandle, PeerSource, NetworkEvent, NetworkCommand};
+use merklith_rpc::{Faucet, RpcMetrics, RpcServer, RpcServerConfig};
 use merklith_storage::state_db::StateDB;
-use merklith_txpool::pool::TransactionPool;
-use merklith_types::U256;
+use merklith_txpool::pool::{RemovalReason, TransactionPool};
+use merklith_txpool::{ComplianceScreener, ScreeningDenial, ValidationPipeline};
+use merklith_types::{SignedTransaction, U256};
+use std::collections::HashSet;
 use std::sync::Arc;
-use tokio::sync::{mpsc, Mutex, RwLock};
+use std::time::Instant;
+use tokio::sync::{mpsc, RwLock};
+use tokio::task::JoinHandle;
 use tokio::time::{interval, Duration};
 use tracing::{info, warn};
 
 use crate::config::NodeConfig;
+use crate::metrics::{Metrics, MetricsServer};
+use crate::status::{NodeStatus, STATUS_INTERVAL_SECS};
 
 /// Node state.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -37,6 +48,86 @@ impl NodeState {
     }
 }
 
+/// Audit events kept in memory before older ones rotate to disk
+pub const AUDIT_MEMORY_EVENTS: usize = 10_000;
+
+/// Record a blocklist denial as an `AccessDenied` audit event
+fn audit_denial(audit: &AuditTrail, denial: &ScreeningDenial) {
+    let event = AuditEvent::new(
+        AuditEventType::AccessDenied,
+        denial.sender.to_string(),
+        format!("{:?} {} is blocklisted", denial.role, denial.address),
+        AuditSeverity::Warning,
+    )
+    .with_tx(denial.tx_hash.clone())
+    .with_data("address", serde_json::json!(denial.address.to_string()))
+    .with_data("role", serde_json::json!(format!("{:?}", denial.role)));
+    if let Err(e) = audit.record(event) {
+        warn!("Failed to record audit event: {}", e);
+    }
+}
+
+/// Record a validator's jailing or release as an audit event
+fn audit_jail(audit: &AuditTrail, event: &JailEvent) {
+    let event = match *event {
+        JailEvent::Jailed { validator, block_number, missed_slots, release_at } => AuditEvent::new(
+            AuditEventType::ValidatorJailed,
+            validator.to_string(),
+            format!("Jailed after missing {} slots, until block #{}", missed_slots, release_at),
+            AuditSeverity::Warning,
+        )
+        .with_block(block_number)
+        .with_data("missed_slots", serde_json::json!(missed_slots))
+        .with_data("release_at", serde_json::json!(release_at)),
+        JailEvent::Unjailed { validator, block_number } => AuditEvent::new(
+            AuditEventType::ValidatorUnjailed,
+            validator.to_string(),
+            "Released from jail".to_string(),
+            AuditSeverity::Info,
+        )
+        .with_block(block_number),
+    };
+    if let Err(e) = audit.record(event) {
+        warn!("Failed to record audit event: {}", e);
+    }
+}
+
+/// What the block-production loop does on a tick
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProductionDecision {
+    /// Enough work is pending: seal a block now
+    Produce,
+    /// Nothing to do for too long: seal a block anyway to keep finality moving
+    Heartbeat,
+    /// Skip this tick
+    Wait,
+}
+
+/// When a validator seals a block. Empty blocks are suppressed until
+/// `max_empty_interval` has passed since the last block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProductionTrigger {
+    /// Pending transactions that seal a block straight away
+    pub min_txs_to_produce: usize,
+    /// Longest gap between blocks while too few transactions are pending
+    pub max_empty_interval: Duration,
+}
+
+impl ProductionTrigger {
+    /// Decide for a tick with `pending_txs` in the pool, `since_last_block`
+    /// after the previous block. Delayed transactions falling due always
+    /// seal a block.
+    pub fn decide(&self, pending_txs: usize, delayed_due: bool, since_last_block: Duration) -> ProductionDecision {
+        if delayed_due || (pending_txs > 0 && pending_txs >= self.min_txs_to_produce) {
+            ProductionDecision::Produce
+        } else if since_last_block >= self.max_empty_interval {
+            ProductionDecision::Heartbeat
+        } else {
+            ProductionDecision::Wait
+        }
+    }
+}
+
 /// The Merklith full node.
 pub struct MerklithNode {
     /// Node configuration
@@ -46,13 +137,36 @@ pub struct MerklithNode {
     /// Blockchain state (real state machine)
     pub chain_state: Arc<State>,
     /// Transaction pool
-    pub tx_pool: Arc<Mutex<TransactionPool>>,
+    pub tx_pool: Arc<TransactionPool>,
     /// Network node
     pub network: Option<NetworkNode>,
     /// RPC server
     pub rpc_server: Option<RpcServer>,
     /// Network command sender
     pub network_cmd: Option<mpsc::Sender<NetworkCommand>>,
+    /// HA cluster leader election (None when clustering is disabled)
+    pub cluster: Option<Arc<ClusterManager>>,
+    /// Prometheus metrics (None when metrics are disabled)
+    pub metrics: Option<Arc<Metrics>>,
+    /// Proof-of-Contribution scores earned by this node's work
+    pub contributions: Arc<parking_lot::RwLock<ContributionTracker>>,
+    /// Connected peer table, shared with RPC (None until the network starts)
+    pub peers: Option<PeerHandle>,
+    /// Highest block announced by peers, for `eth_syncing`
+    pub sync: Arc<SyncTracker>,
+    /// Tamper-evident record of refused transactions and validator actions
+    pub audit: Arc<AuditTrail>,
+    /// Consensus rules and attestation pool, shared by block production
+    /// and block submission over RPC
+    pub consensus: Arc<parking_lot::RwLock<ConsensusEngine>>,
+    /// BLS key this validator attests blocks with (None outside the committee)
+    attestation_key: Option<Arc<BLSKeypair>>,
+    /// When the node was created, for uptime reporting
+    pub started_at: Instant,
+    /// Task refreshing the status file
+    status_task: Option<JoinHandle<()>>,
+    /// Sends this node's cluster heartbeats; aborted on shutdown
+    cluster_heartbeat: Option<JoinHandle<()>>,
     /// Shutdown signal
     pub shutdown: mpsc::Receiver<()>,
 }
@@ -67,13 +181,95 @@ impl MerklithNode {
         // Initialize state database
         let state_db = Arc::new(StateDB::new(&config.storage.db_path)?);
 
-        // Initialize transaction pool
-        let tx_pool_config = merklith_txpool::pool::PoolConfig::default();
-        let tx_pool = Arc::new(Mutex::new(TransactionPool::new(tx_pool_config)));
-
         // Initialize blockchain state (real state machine) with proper data directory
         let state_path = config.data_dir.join("state");
-        let chain_state = Arc::new(State::with_path(state_path));
+        let mut genesis = State::devnet_genesis();
+        genesis.chain_config.chain_id = config.consensus.chain_id;
+        if let Some(timestamp) = config.consensus.genesis_timestamp {
+            genesis.timestamp = timestamp;
+        }
+
+        // Initialize transaction pool; nothing below the chain's base fee can be included
+        let tx_pool_config = merklith_txpool::pool::PoolConfig {
+            min_gas_price: genesis.chain_config.min_base_fee,
+            block_gas_limit: genesis.chain_config.gas_limit,
+            ordering: config.txpool.ordering,
+            max_tx_size: config.txpool.max_tx_size,
+            ..Default::default()
+        };
+        let audit = Arc::new(AuditTrail::new().with_retention(AuditRetentionPolicy {
+            max_events: Some(AUDIT_MEMORY_EVENTS),
+            rotate_to_disk: Some(config.data_dir.join("audit")),
+            ..Default::default()
+        }));
+        let mut tx_pool = TransactionPool::new(tx_pool_config);
+        if let Some(path) = &config.rpc.blocklist_file {
+            let denials = audit.clone();
+            let screener = ComplianceScreener::from_file(path)
+                .map_err(|e| anyhow::anyhow!(e))?
+                .with_call_data_screening()
+                .with_audit(move |denial| audit_denial(&denials, denial));
+            info!("Screening transactions against blocklist {}", path.display());
+            let validation = ValidationPipeline::default().with_validator(screener);
+            tx_pool = tx_pool.with_validation(validation);
+        }
+        let tx_pool = Arc::new(tx_pool);
+        let chain_state = Arc::new(State::open(
+            state_path,
+            genesis,
+            config.storage.pruning.clone(),
+        )?);
+        chain_state.set_gas_limit_target(config.consensus.gas_limit_target);
+        chain_state.set_commit_policy(config.storage.commit_policy);
+        chain_state.set_finality_threshold(config.consensus.finality_threshold.map(u64::from));
+        
+        // Refuse to run on state created for another chain
+        verify_state_chain_id(config.consensus.chain_id, chain_state.chain_id())?;
+
+        // Re-admit transactions saved before a restart
+        if let Some(path) = &config.storage.mempool_file {
+            let contents = std::fs::read_to_string(path)
+                .map_err(|e| anyhow::anyhow!("Failed to read mempool file '{}': {}", path.display(), e))?;
+            let raw_txs: Vec<String> = serde_json::from_str(&contents)
+                .map_err(|e| anyhow::anyhow!("Invalid mempool file '{}': {}", path.display(), e))?;
+            let imported = merklith_rpc::import_mempool(&raw_txs, &chain_state, &tx_pool, config.consensus.chain_id);
+            info!(
+                "Imported mempool from {}: {} admitted, {} queued, {} dropped",
+                path.display(),
+                imported.admitted,
+                imported.queued,
+                imported.dropped
+            );
+        }
+
+        // Initialize HA cluster membership
+        let cluster = if config.cluster.enabled {
+            let manager = ClusterManager::with_priority(
+                config.cluster.node_id.clone(),
+                config.cluster.priority,
+            )
+            .with_heartbeat_interval(Duration::from_secs(config.cluster.heartbeat_interval_secs))
+            .with_failover_timeout(Duration::from_secs(config.cluster.failover_timeout_secs));
+            for peer in &config.cluster.peers {
+                manager.add_peer_with_priority(peer.id.clone(), peer.address.clone(), peer.priority);
+            }
+            Some(Arc::new(manager))
+        } else {
+            None
+        };
+
+        let contributions = Arc::new(parking_lot::RwLock::new(
+            ContributionTracker::new().with_weights(config.consensus.contribution_weights.clone()),
+        ));
+
+        let attestation_key = config.consensus.bls_key.as_ref()
+            .filter(|_| config.consensus.validator)
+            .map(|path| load_bls_key(path))
+            .transpose()?
+            .map(Arc::new);
+        let consensus = Arc::new(parking_lot::RwLock::new(
+            Self::consensus_engine(&config, &chain_state, attestation_key.as_deref(), audit.clone())?,
+        ));
 
         // Create shutdown channel
         let (shutdown_tx, shutdown_rx) = mpsc::channel(1);
@@ -86,6 +282,17 @@ impl MerklithNode {
             network: None,
             rpc_server: None,
             network_cmd: None,
+            cluster,
+            metrics: None,
+            contributions,
+            peers: None,
+            sync: Arc::new(SyncTracker::new()),
+            audit,
+            consensus,
+            attestation_key,
+            started_at: Instant::now(),
+            status_task: None,
+            cluster_heartbeat: None,
             shutdown: shutdown_rx,
         };
 
@@ -98,6 +305,11 @@ impl MerklithNode {
         
         *self.node_state.write().await = NodeState::Starting;
 
+        // Start metrics first so other components can register with it
+        if self.config.metrics.enabled {
+            self.start_metrics()?;
+        }
+
         // Start network if enabled
         if self.config.network.enabled {
             self.start_network().await?;
@@ -108,11 +320,24 @@ impl MerklithNode {
             self.start_rpc().await?;
         }
 
+        // Exchange heartbeats with the configured cluster peers
+        if let Some(cluster) = &self.cluster {
+            let listen_addr = cluster.listen_heartbeats(self.config.cluster.listen_addr).await?;
+            self.cluster_heartbeat = Some(cluster.start_heartbeat());
+            info!(
+                "HA cluster enabled (node {}, priority {}, heartbeats on {})",
+                cluster.node_id(),
+                cluster.priority(),
+                listen_addr
+            );
+        }
+
         // Start block production loop (with network broadcast)
         let network_cmd = self.network_cmd.clone();
         self.start_block_production(network_cmd).await;
 
         *self.node_state.write().await = NodeState::Running;
+        self.start_status_file();
         info!("Merklith node started successfully");
 
         Ok(())
@@ -132,14 +357,20 @@ impl MerklithNode {
         let network_config = merklith_network::NetworkConfig::new(
             format!("node_{}", rand::random::<u64>())
         ).with_port(p2p_port)
-         .with_bootstrap(bootstrap_peers);
+         .with_bootstrap(bootstrap_peers)
+         .with_genesis_hash(self.chain_state.genesis_hash());
 
         let (network, cmd_sender) = NetworkNode::new(network_config, event_tx);
-        self.network = Some(network);
+        let peers = network.peer_handle();
+        self.peers = Some(peers.clone());
+        self.network = Some(network.with_block_source(self.block_source()));
         self.network_cmd = Some(cmd_sender.clone());
         
         // Clone for event handler
         let chain_state = self.chain_state.clone();
+        let contributions = self.contributions.clone();
+        let validator_address = Self::validator_address(&self.config);
+        let sync = self.sync.clone();
 
         // Spawn network event handler
         tokio::spawn(async move {
@@ -150,8 +381,9 @@ impl MerklithNode {
                     }
                     NetworkEvent::PeerDisconnected { peer_id } => {
                         info!("❌ Peer disconnected: {}", peer_id);
+                        sync.remove_peer(&peer_id);
                     }
-                    NetworkEvent::NewBlock { hash, number, parent_hash } => {
+                    NetworkEvent::NewBlock { peer_id, hash, number, parent_hash } => {
                         let hash_bytes: [u8; 32] = *hash.as_bytes();
                         
                         // Check if we already have this block
@@ -162,6 +394,7 @@ impl MerklithNode {
                         
                         // Get current block number
                         let current = chain_state.block_number();
+                        sync.observe_peer_head(&peer_id, number, current);
                         
                         if number == current + 1 {
                             // Try to add the block (verifies parent hash)
@@ -179,9 +412,43 @@ impl MerklithNode {
                     NetworkEvent::NewTransaction { hash } => {
                         tracing::debug!("📝 Received transaction: {}", hex::encode(hash));
                     }
-                    NetworkEvent::SyncProgress { current, target } => {
+                    NetworkEvent::SyncProgress { peer_id, current, target } => {
+                        sync.observe_peer_head(&peer_id, target, chain_state.block_number());
                         info!("🔄 Syncing: {} / {} blocks", current, target);
                     }
+                    NetworkEvent::PeerDiscovered { peer_id, recommended_by } => {
+                        info!("🔎 Discovered peer {} via {}", peer_id, recommended_by);
+                        contributions.write().record_peer_discovery(
+                            recommended_by,
+                            &peer_id,
+                            chain_state.block_number(),
+                        );
+                    }
+                    NetworkEvent::PeerListening { peer_id, address } => {
+                        // Vouch for the new peer so the rest of the network can reach it
+                        tracing::debug!("Peer {} listens at {}", peer_id, address);
+                        let _ = cmd_sender.send(NetworkCommand::RecommendPeer {
+                            address,
+                            recommended_by: validator_address,
+                        }).await;
+                    }
+                    NetworkEvent::PeerRecommended { address, recommended_by } => {
+                        let known = peers.peers().iter().any(|peer| peer.address == address);
+                        if !known {
+                            let _ = cmd_sender.send(NetworkCommand::ConnectRecommended {
+                                address,
+                                recommended_by,
+                            }).await;
+                        }
+                    }
+                    NetworkEvent::DataServed { block_number, bytes_served } => {
+                        tracing::debug!("📤 Served {} bytes of block data from #{}", bytes_served, block_number);
+                        contributions.write().record_data_availability(
+                            validator_address,
+                            block_number,
+                            bytes_served,
+                        );
+                    }
                     _ => {}
                 }
             }
@@ -200,6 +467,97 @@ impl MerklithNode {
         Ok(())
     }
 
+    /// Consensus rules for blocks produced here or submitted by external
+    /// validators: the genesis validators, signing blocks with their genesis
+    /// keys, and this node's own, which joins the attestation committee when
+    /// it has a BLS key. Jailings and releases go to the audit trail.
+    fn consensus_engine(
+        config: &NodeConfig,
+        chain_state: &State,
+        attestation_key: Option<&BLSKeypair>,
+        audit: Arc<AuditTrail>,
+    ) -> anyhow::Result<ConsensusEngine> {
+        let mut validators = ValidatorSet::new();
+        let mut proposer_keys = Vec::new();
+        for validator in &chain_state.genesis().validators {
+            validators.add_validator(validator.address, u64::try_from(validator.stake).unwrap_or(u64::MAX));
+            proposer_keys.push((validator.address, validator.ed25519_public_key));
+        }
+        if config.consensus.validator {
+            validators.add_validator(Self::validator_address(config), config.consensus.min_stake);
+        }
+        let mut engine = ConsensusEngine::new(validators, config.consensus.block_time)
+            .with_chain_config(chain_state.genesis().chain_config.clone())
+            .with_epoch_file(config.data_dir.join("consensus").join("epoch.json"))
+            .with_jail_hook(move |event| audit_jail(&audit, event));
+        for (address, public_key) in proposer_keys {
+            engine = engine.with_proposer_key(address, public_key);
+        }
+        if let Some(key) = attestation_key {
+            let public_key = key.public_key();
+            let proof = key.sign(&proof_of_possession_message(&public_key));
+            engine.register_attester(Self::validator_address(config), public_key, &proof)
+                .map_err(|e| anyhow::anyhow!("Failed to join the attestation committee: {}", e))?;
+        }
+        Ok(engine)
+    }
+
+    fn validator_address(config: &NodeConfig) -> merklith_types::Address {
+        config.consensus.validator_key.as_ref()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|hex_str| hex::decode(hex_str.trim()).ok())
+            .and_then(|bytes| {
+                if bytes.len() == 20 {
+                    let mut addr = [0u8; 20];
+                    addr.copy_from_slice(&bytes);
+                    Some(merklith_types::Address::from_bytes(addr))
+                } else {
+                    None
+                }
+            })
+            .unwrap_or_else(|| {
+                // Default validator address for devnet
+                merklith_types::Address::from_bytes([0xABu8; 20])
+            })
+    }
+
+    /// Serve peers' `GetBlocks` requests from the local chain.
+    fn block_source(&self) -> BlockSource {
+        let chain_state = self.chain_state.clone();
+        Arc::new(move |from, count| {
+            (from..from.saturating_add(count))
+                .map_while(|number| chain_state.get_block(number))
+                .map(|block| BlockData {
+                    number: block.number,
+                    hash: block.hash.to_vec(),
+                    parent_hash: block.parent_hash.to_vec(),
+                    // Transaction hashes, 32 bytes each
+                    transactions: chain_state.block_body(block.number)
+                        .unwrap_or_default()
+                        .iter()
+                        .filter_map(|hash| hash.parse::<merklith_types::Hash>().ok())
+                        .flat_map(|hash| *hash.as_bytes())
+                        .collect(),
+                })
+                .collect()
+        })
+    }
+
+    /// Start the Prometheus metrics endpoint.
+    fn start_metrics(&mut self) -> anyhow::Result<()> {
+        let metrics = Metrics::new()?;
+        let server = MetricsServer::new(self.config.metrics.addr, metrics.clone());
+        tokio::spawn(async move {
+            if let Err(e) = server.start().await {
+                tracing::error!("Metrics server error: {}", e);
+            }
+        });
+
+        info!("Metrics server started on {}", self.config.metrics.addr);
+        self.metrics = Some(metrics);
+        Ok(())
+    }
+
     /// Start the RPC server.
     async fn start_rpc(
         &mut self,
@@ -216,15 +574,49 @@ impl MerklithNode {
             },
             cors: self.config.rpc.cors,
             max_body_size: self.config.rpc.max_body_size as u32 * 1024 * 1024,
-            max_connections: 1000,
+            max_connections: self.config.rpc.max_connections,
             rate_limit: self.config.rpc.rate_limit,
+            admin_token: self.config.rpc.admin_token.clone(),
+            slow_request_threshold: Duration::from_millis(self.config.rpc.slow_request_ms),
+            read_timeout: Duration::from_secs(self.config.rpc.read_timeout_secs),
+            idle_timeout: Duration::from_secs(self.config.rpc.idle_timeout_secs),
+            enabled_methods: self.config.rpc.enabled_methods.as_ref()
+                .map(|methods| methods.iter().cloned().collect()),
+            disabled_methods: self.config.rpc.disabled_methods.iter().cloned().collect(),
+            max_json_depth: self.config.rpc.max_json_depth,
+            max_json_elements: self.config.rpc.max_json_elements,
+            max_batch_size: self.config.rpc.max_batch_size,
+            max_return_data: self.config.rpc.max_return_data,
         };
 
         let mut rpc_server = RpcServer::new(
             rpc_config, 
             self.chain_state.clone(),
             self.config.consensus.chain_id,
-        );
+        )
+        .with_pool(self.tx_pool.clone())
+        .with_contributions(self.contributions.clone())
+        .with_sync(self.sync.clone());
+        if let Some(peers) = &self.peers {
+            rpc_server = rpc_server.with_peers(Arc::new(peers.clone()));
+        }
+        if let Some(metrics) = &self.metrics {
+            rpc_server = rpc_server.with_metrics(RpcMetrics::new(metrics.registry())?);
+        }
+        if merklith_rpc::faucet::is_devnet(self.config.consensus.chain_id) {
+            let faucet = Faucet::devnet();
+            info!("Devnet faucet enabled, paying from {}", faucet.address());
+            rpc_server = rpc_server.with_faucet(Arc::new(faucet));
+        }
+        rpc_server = rpc_server.with_consensus(self.consensus.clone());
+        if let Some(cmd) = &self.network_cmd {
+            rpc_server = rpc_server.with_network(cmd.clone());
+        }
+        verify_chain_id(
+            self.config.consensus.chain_id,
+            rpc_server.chain_id(),
+            self.chain_state.chain_id(),
+        )?;
         
         rpc_server.start().await?;
 
@@ -256,39 +648,37 @@ impl MerklithNode {
         
         // Time constants
         const MIN_BLOCK_TIME: u64 = 12;           // Min 12 saniye (hızlı ama spam değil)
-        const HEARTBEAT_INTERVAL: u64 = 3600;      // Saatte 1 block (60*60)
-        const MAX_EMPTY_SKIP: u32 = 5;             // 5 boş block atla max
+        // How often an idle producer checks the pool again
+        const POLL_INTERVAL_MS: u64 = 1000;
+        const DEV_POLL_INTERVAL_MS: u64 = 100;
+
+        // Dev mode seals a block as soon as a transaction arrives
+        let dev_mode = self.config.consensus.dev_mode;
+        let min_block_time = if dev_mode { 0 } else { MIN_BLOCK_TIME };
+        let poll_interval = Duration::from_millis(if dev_mode { DEV_POLL_INTERVAL_MS } else { POLL_INTERVAL_MS });
         
         let node_state = self.node_state.clone();
         let chain_state = self.chain_state.clone();
         let tx_pool = self.tx_pool.clone();
-        let validator_address = self.config.consensus.validator_key.as_ref()
-            .and_then(|path| std::fs::read_to_string(path).ok())
-            .and_then(|hex_str| hex::decode(hex_str.trim()).ok())
-            .and_then(|bytes| {
-                if bytes.len() == 20 {
-                    let mut addr = [0u8; 20];
-                    addr.copy_from_slice(&bytes);
-                    Some(merklith_types::Address::from_bytes(addr))
-                } else {
-                    None
-                }
-            })
-            .unwrap_or_else(|| {
-                // Default validator address for devnet
-                merklith_types::Address::from_bytes([0xABu8; 20])
-            });
+        let cluster = self.cluster.clone();
+        let contributions = self.contributions.clone();
+        let consensus = self.consensus.clone();
+        let attestation_key = self.attestation_key.clone();
+        let validator_address = Self::validator_address(&self.config);
+        let trigger = ProductionTrigger {
+            min_txs_to_produce: self.config.consensus.min_txs_to_produce,
+            max_empty_interval: Duration::from_secs(self.config.consensus.max_empty_interval),
+        };
 
         tokio::spawn(async move {
             let mut last_block_time = std::time::Instant::now();
             let mut empty_count = 0u32;
-            let mut last_heartbeat = std::time::Instant::now();
             
             loop {
                 // Wait minimum block time
                 let elapsed = last_block_time.elapsed().as_secs();
-                if elapsed < MIN_BLOCK_TIME {
-                    tokio::time::sleep(Duration::from_secs(MIN_BLOCK_TIME - elapsed)).await;
+                if elapsed < min_block_time {
+                    tokio::time::sleep(Duration::from_secs(min_block_time - elapsed)).await;
                 }
                 
                 // Check if we're still running
@@ -296,59 +686,87 @@ impl MerklithNode {
                     break;
                 }
 
+                // Standbys wait until the leader's heartbeat stops
+                if let Some(cluster) = &cluster {
+                    if !cluster.is_leader() {
+                        tokio::time::sleep(Duration::from_secs(1)).await;
+                        continue;
+                    }
+                }
+
                 // Check transaction pool
-                let pool = tx_pool.lock().await;
-                let pending_txs = pool.get_pending(1000);
+                let expired = tx_pool.drop_expired(chain_state.block_number() + 1);
+                if !expired.is_empty() {
+                    tracing::debug!("Dropped {} expired transactions", expired.len());
+                }
+                let pending_txs = fill_block(tx_pool.get_pending(1000), chain_state.next_gas_limit());
                 let tx_count = pending_txs.len();
-                drop(pool);
                 
                 // Decision: Block üretmeli miyiz?
-                let should_produce = if tx_count > 0 {
-                    // Transaction varsa: Hemen üret (ama MIN_BLOCK_TIME kadar beklemiş olmalı)
-                    true
-                } else {
-                    // Transaction yoksa: Saatte 1 block (heartbeat)
-                    let time_since_heartbeat = last_heartbeat.elapsed().as_secs();
-                    if time_since_heartbeat >= HEARTBEAT_INTERVAL {
-                        empty_count += 1;
-                        true // Saat doldu, heartbeat block üret
-                    } else {
-                        // Henüz saat dolmadı, boş block üretme
-                        empty_count += 1;
-                        if empty_count <= MAX_EMPTY_SKIP {
-                            // İlk 5 boş block'u atla (loglama yok)
-                            continue;
-                        }
-                        // 5'ten sonra her 10'da bir logla
-                        if empty_count % 10 == 0 {
-                            tracing::debug!(
-                                "Waiting for transactions or heartbeat... ({} empty, {}s until heartbeat)",
-                                empty_count,
-                                HEARTBEAT_INTERVAL - time_since_heartbeat
-                            );
-                        }
-                        continue;
+                let delayed_due = chain_state.has_delayed_due(chain_state.block_number() + 1);
+                let since_last_block = last_block_time.elapsed();
+                if trigger.decide(tx_count, delayed_due, since_last_block) == ProductionDecision::Wait {
+                    empty_count += 1;
+                    if empty_count % 10 == 0 {
+                        tracing::debug!(
+                            "Waiting for transactions or heartbeat... ({} pending, {}s until heartbeat)",
+                            tx_count,
+                            trigger.max_empty_interval.saturating_sub(since_last_block).as_secs()
+                        );
                     }
-                };
+                    tokio::time::sleep(poll_interval).await;
+                    continue;
+                }
                 
-                if !should_produce {
+                // No block may precede the chain's configured start
+                let now = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map_or(0, |d| d.as_secs());
+                let chain_start = chain_state.genesis().timestamp;
+                if now <= chain_start {
+                    tracing::debug!("Waiting for chain start in {}s", chain_start - now);
+                    tokio::time::sleep(poll_interval).await;
                     continue;
                 }
                 
                 // Reset counters
                 last_block_time = std::time::Instant::now();
-                if tx_count == 0 {
-                    last_heartbeat = std::time::Instant::now();
-                }
                 empty_count = 0;
 
                 // Get parent hash
+                let parent_number = chain_state.block_number();
                 let parent_hash = *chain_state.block_hash().as_bytes();
+
+                // A block opening an epoch is made under the new active set
+                consensus.write().maybe_rotate(parent_number + 1);
                 
                 // Produce block with reward
                 let is_heartbeat = tx_count == 0;
+                let included: Vec<merklith_types::Hash> = pending_txs.iter()
+                    .map(|tx| tx.hash())
+                    .collect();
                 match chain_state.produce_block(&validator_address, pending_txs, is_heartbeat) {
                     Ok(result) => {
+                        contributions.write().record_block_production(validator_address, result.block_number);
+                        finish_production(
+                            &consensus,
+                            &chain_state,
+                            attestation_key.as_deref(),
+                            validator_address,
+                            (parent_number, parent_hash),
+                            (result.block_number, result.block_hash),
+                        );
+
+                        // Executed or rejected, these transactions leave the pool
+                        for hash in &included {
+                            let reason = if chain_state.mined_transaction(hash).is_some() {
+                                RemovalReason::Mined
+                            } else {
+                                RemovalReason::Dropped
+                            };
+                            tx_pool.remove_transaction(&hash.to_string(), reason);
+                        }
+                        
                         let reward_merk = result.validator_reward / U256::from(1_000_000_000_000_000_000u128);
                         
                         if tx_count > 0 {
@@ -361,9 +779,10 @@ impl MerklithNode {
                             );
                         } else {
                             info!(
-                                "~ Heartbeat #{}: Empty | Security reward: {} MERK | Next in ~1h",
+                                "~ Heartbeat #{}: Empty | Security reward: {} MERK | Next in ~{}s",
                                 result.block_number,
-                                reward_merk
+                                reward_merk,
+                                trigger.max_empty_interval.as_secs()
                             );
                         }
                         
@@ -411,6 +830,11 @@ impl MerklithNode {
         info!("Shutting down Merklith node...");
         *self.node_state.write().await = NodeState::ShuttingDown;
 
+        // Go silent so a standby takes over
+        if let Some(task) = self.cluster_heartbeat.take() {
+            task.abort();
+        }
+
         // Stop RPC server
         if let Some(rpc) = self.rpc_server.take() {
             info!("Stopping RPC server...");
@@ -427,6 +851,21 @@ impl MerklithNode {
             network.shutdown();
         }
 
+        // Write out state the commit policy has not flushed yet
+        if let Err(e) = self.chain_state.flush() {
+            warn!("Failed to flush state: {}", e);
+        }
+
+        // Stop refreshing the status file and remove it so tooling sees the node is gone
+        if let Some(task) = self.status_task.take() {
+            task.abort();
+            // Wait for the abort so an in-flight write cannot recreate the file
+            let _ = task.await;
+        }
+        if let Err(e) = NodeStatus::remove(&self.config.data_dir) {
+            warn!("Failed to remove status file: {}", e);
+        }
+
         *self.node_state.write().await = NodeState::Stopped;
         info!("Merklith node stopped");
     }
@@ -445,6 +884,171 @@ impl MerklithNode {
     pub async fn is_healthy(&self) -> bool {
         matches!(self.state().await, NodeState::Running | NodeState::Syncing)
     }
+
+    /// Snapshot of the node for the status file.
+    pub async fn status(&self) -> NodeStatus {
+        status_snapshot(
+            &self.config,
+            &self.chain_state,
+            self.peers.as_ref().map_or(0, |peers| peers.connected_peers()),
+            *self.node_state.read().await,
+            self.started_at,
+        )
+    }
+
+    /// Periodically write the status file to the data directory.
+    fn start_status_file(&mut self) {
+        let config = self.config.clone();
+        let chain_state = self.chain_state.clone();
+        let peers = self.peers.clone();
+        let node_state = self.node_state.clone();
+        let started_at = self.started_at;
+
+        self.status_task = Some(tokio::spawn(async move {
+            let mut ticker = interval(Duration::from_secs(STATUS_INTERVAL_SECS));
+            loop {
+                ticker.tick().await;
+                let status = status_snapshot(
+                    &config,
+                    &chain_state,
+                    peers.as_ref().map_or(0, |peers| peers.connected_peers()),
+                    *node_state.read().await,
+                    started_at,
+                );
+                if let Err(e) = status.write(&config.data_dir) {
+                    warn!("Failed to write status file: {}", e);
+                }
+            }
+        }));
+    }
+}
+
+/// The pending transactions that fit under `gas_limit`, in pool order.
+///
+/// Whatever does not fit is left for later blocks, along with the later
+/// nonces of its sender.
+/// Read a hex-encoded BLS secret key
+fn load_bls_key(path: &std::path::Path) -> anyhow::Result<BLSKeypair> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("Failed to read BLS key '{}': {}", path.display(), e))?;
+    let secret: [u8; 32] = hex::decode(contents.trim().trim_start_matches("0x"))
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| anyhow::anyhow!("BLS key '{}' is not 32 hex-encoded bytes", path.display()))?;
+    BLSKeypair::from_bytes(&secret).map_err(|e| anyhow::anyhow!("Invalid BLS key '{}': {}", path.display(), e))
+}
+
+/// Consensus work after this node produced `block` on `parent`: carry the
+/// committee's attestations of the parent with the block as one aggregate,
+/// account the block's slots, and attest the block with this validator's BLS key
+fn finish_production(
+    consensus: &parking_lot::RwLock<ConsensusEngine>,
+    chain_state: &State,
+    attestation_key: Option<&BLSKeypair>,
+    validator: merklith_types::Address,
+    parent: (u64, [u8; 32]),
+    block: (u64, [u8; 32]),
+) {
+    let mut consensus = consensus.write();
+    let aggregate = match consensus.seal_attestations(parent.0, parent.1) {
+        Ok(aggregate) => {
+            chain_state.attach_attestation(block.0, ParentAttestation {
+                signature: aggregate.signature.as_bytes().to_vec(),
+                bitfield: aggregate.bitfield.clone(),
+            });
+            Some(aggregate)
+        }
+        Err(e) => {
+            tracing::debug!("No attestations of block #{} to carry: {}", parent.0, e);
+            None
+        }
+    };
+    consensus.observe_block(block.0, validator, aggregate.as_ref());
+
+    if let Some(key) = attestation_key {
+        let mut attestation = Attestation::new(block.0, block.1, validator, Vec::new());
+        attestation.signature = key.sign(&attestation.signing_message()).as_bytes().to_vec();
+        if consensus.add_attestation(attestation) {
+            consensus.check_finality(block.0, block.1);
+        }
+    }
+}
+
+fn fill_block(pending: Vec<SignedTransaction>, gas_limit: u64) -> Vec<SignedTransaction> {
+    let mut gas_left = gas_limit;
+    let mut deferred = HashSet::new();
+    pending
+        .into_iter()
+        .filter(|tx| {
+            let sender = tx.sender();
+            if deferred.contains(&sender) {
+                return false;
+            }
+            match gas_left.checked_sub(tx.tx.gas_limit) {
+                Some(left) => {
+                    gas_left = left;
+                    true
+                }
+                None => {
+                    deferred.insert(sender);
+                    false
+                }
+            }
+        })
+        .collect()
+}
+
+fn status_snapshot(
+    config: &NodeConfig,
+    chain_state: &State,
+    peer_count: usize,
+    state: NodeState,
+    started_at: Instant,
+) -> NodeStatus {
+    let rpc_port = if config.rpc.http_enabled {
+        Some(config.rpc.http_addr.port())
+    } else if config.rpc.ws_enabled {
+        Some(config.rpc.ws_addr.port())
+    } else {
+        None
+    };
+    NodeStatus {
+        pid: std::process::id(),
+        block_height: chain_state.block_number(),
+        peer_count,
+        syncing: state == NodeState::Syncing,
+        uptime_secs: started_at.elapsed().as_secs(),
+        rpc_port,
+        p2p_port: config.network.enabled.then_some(config.network.p2p_port),
+        updated_at: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+    }
+}
+
+/// Fail fast unless consensus, RPC and the chain state agree on a non-zero chain id.
+pub fn verify_chain_id(consensus: u64, rpc: u64, genesis: u64) -> anyhow::Result<()> {
+    verify_state_chain_id(consensus, genesis)?;
+    if rpc != consensus {
+        anyhow::bail!("RPC chain ID {} does not match consensus chain ID {}", rpc, consensus);
+    }
+    Ok(())
+}
+
+/// Fail fast unless the loaded chain state belongs to the configured non-zero chain id.
+pub fn verify_state_chain_id(consensus: u64, state: u64) -> anyhow::Result<()> {
+    if consensus == 0 {
+        anyhow::bail!("Chain ID cannot be 0");
+    }
+    if state != consensus {
+        anyhow::bail!(
+            "Chain state belongs to chain ID {} but consensus is configured for {}",
+            state,
+            consensus
+        );
+    }
+    Ok(())
 }
 
 #[cfg(test)]
@@ -470,6 +1074,313 @@ mod tests {
         assert!(node.is_healthy().await);
     }
 
+    #[test]
+    fn test_empty_blocks_wait_for_heartbeat() {
+        let trigger = ProductionTrigger {
+            min_txs_to_produce: 2,
+            max_empty_interval: Duration::from_secs(60),
+        };
+
+        // An empty pool produces nothing until the heartbeat interval
+        for secs in [0, 12, 30, 59] {
+            assert_eq!(trigger.decide(0, false, Duration::from_secs(secs)), ProductionDecision::Wait);
+        }
+        assert_eq!(trigger.decide(0, false, Duration::from_secs(60)), ProductionDecision::Heartbeat);
+
+        // Too few transactions wait with it; enough, or a delayed one due, seal a block at once
+        assert_eq!(trigger.decide(1, false, Duration::from_secs(30)), ProductionDecision::Wait);
+        assert_eq!(trigger.decide(2, false, Duration::from_secs(0)), ProductionDecision::Produce);
+        assert_eq!(trigger.decide(0, true, Duration::from_secs(0)), ProductionDecision::Produce);
+    }
+
+    #[test]
+    fn test_fill_block_skips_what_does_not_fit() {
+        let accounts = State::devnet_accounts();
+        let tx = |from: usize, nonce: u64, gas_limit: u64| {
+            let tx = merklith_types::Transaction::new(1337, nonce, Some(accounts[3].address()), U256::ONE, gas_limit, U256::ONE, U256::ZERO);
+            let (signature, public_key) = accounts[from].sign_transaction(&tx);
+            SignedTransaction::new(tx, signature, public_key)
+        };
+        let pending = vec![tx(0, 0, 21_000), tx(1, 0, 80_000), tx(2, 0, 21_000), tx(1, 1, 21_000), tx(0, 1, 21_000)];
+
+        // The oversized transaction is left out without blocking the ones after it,
+        // but its sender's next nonce waits with it
+        let filled: Vec<_> = fill_block(pending.clone(), 70_000).iter().map(|t| t.hash()).collect();
+        assert_eq!(filled, vec![pending[0].hash(), pending[2].hash(), pending[4].hash()]);
+        assert_eq!(fill_block(pending.clone(), 1_000_000).len(), 5);
+    }
+
+    #[test]
+    fn test_verify_chain_id() {
+        assert!(verify_chain_id(1337, 1337, 1337).is_ok());
+        assert!(verify_chain_id(0, 0, 0).is_err());
+        assert!(verify_chain_id(1337, 1, 1337).is_err());
+        assert!(verify_chain_id(1337, 1337, 1).is_err());
+        assert!(verify_state_chain_id(1337, 1337).is_ok());
+        assert!(verify_state_chain_id(1337, 42).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_node_rejects_mismatched_chain_state() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut config = NodeConfig {
+            data_dir: temp_dir.path().to_path_buf(),
+            ..Default::default()
+        };
+        config.storage.db_path = temp_dir.path().join("db");
+
+        // Create state for chain 1337, then restart the node as chain 42
+        let (node, _shutdown) = MerklithNode::new(config.clone()).await.unwrap();
+        node.chain_state.increment_block();
+        drop(node);
+
+        config.consensus.chain_id = 42;
+        assert!(MerklithNode::new(config).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_status_file_lifecycle() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut config = NodeConfig {
+            data_dir: temp_dir.path().to_path_buf(),
+            ..Default::default()
+        };
+        config.storage.db_path = temp_dir.path().join("db");
+        config.network.enabled = false;
+        config.rpc.http_enabled = false;
+        config.rpc.ws_enabled = false;
+        config.metrics.enabled = false;
+
+        let (mut node, _shutdown) = MerklithNode::new(config).await.unwrap();
+        node.start().await.unwrap();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        let status = NodeStatus::read(temp_dir.path()).unwrap();
+        assert_eq!(status.pid, std::process::id());
+        assert_eq!(status.peer_count, 0);
+        assert!(!status.syncing);
+        assert_eq!(status.rpc_port, None);
+        assert_eq!(status.p2p_port, None);
+
+        node.shutdown().await;
+        assert!(NodeStatus::read(temp_dir.path()).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_flushes_deferred_state() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut config = NodeConfig {
+            data_dir: temp_dir.path().to_path_buf(),
+            ..Default::default()
+        };
+        config.storage.db_path = temp_dir.path().join("db");
+        config.storage.commit_policy = merklith_storage::CommitPolicy::EveryNBlocks(100);
+        config.consensus.finality_threshold = None;
+        config.network.enabled = false;
+        config.rpc.http_enabled = false;
+        config.rpc.ws_enabled = false;
+        config.metrics.enabled = false;
+        let state_file = temp_dir.path().join("state").join("state.json");
+        let persisted_height = || {
+            let json = std::fs::read_to_string(&state_file).unwrap_or_default();
+            serde_json::from_str::<serde_json::Value>(&json).ok()
+                .and_then(|data| data["block_number"].as_u64())
+        };
+
+        let (mut node, _shutdown) = MerklithNode::new(config).await.unwrap();
+        for _ in 0..3 {
+            node.chain_state.produce_block(&merklith_types::Address::ZERO, vec![], true).unwrap();
+        }
+        assert_ne!(persisted_height(), Some(3));
+
+        node.shutdown().await;
+        assert_eq!(persisted_height(), Some(3));
+    }
+
+    #[test]
+    fn test_produced_blocks_carry_parent_attestations() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let key_file = temp_dir.path().join("bls.key");
+        std::fs::write(&key_file, hex::encode([9u8; 32])).unwrap();
+        let mut config = NodeConfig {
+            data_dir: temp_dir.path().to_path_buf(),
+            ..Default::default()
+        };
+        config.consensus.validator = true;
+        config.consensus.bls_key = Some(key_file.clone());
+        let key = load_bls_key(&key_file).unwrap();
+        let state = State::with_path(temp_dir.path().join("state"));
+        let engine = MerklithNode::consensus_engine(&config, &state, Some(&key), Arc::new(AuditTrail::new())).unwrap();
+        let consensus = parking_lot::RwLock::new(engine);
+        let validator = MerklithNode::validator_address(&config);
+        assert_eq!(consensus.read().attestation_pool().committee().len(), 1);
+
+        let produce = || {
+            let parent = (state.block_number(), *state.block_hash().as_bytes());
+            let result = state.produce_block(&validator, vec![], true).unwrap();
+            finish_production(&consensus, &state, Some(&key), validator, parent, (result.block_number, result.block_hash));
+            result
+        };
+
+        // Genesis has no attestations to carry; block 1 is attested once produced
+        let first = produce();
+        assert_eq!(state.get_block(1).unwrap().parent_attestation, None);
+        assert_eq!(consensus.read().attestation_count(1), 1);
+
+        // Block 2 carries the aggregate for block 1, which verifies
+        produce();
+        let carried = state.get_block(2).unwrap().parent_attestation.unwrap();
+        assert_eq!(carried.bitfield, vec![0x01]);
+        let aggregate = merklith_consensus::AggregateAttestation {
+            block_number: 1,
+            block_hash: first.block_hash,
+            signature: merklith_types::BLSSignature::from_bytes(&carried.signature).unwrap(),
+            bitfield: carried.bitfield,
+        };
+        consensus.read().verify_aggregate(&aggregate).unwrap();
+        assert!(consensus.read().is_finalized(1));
+    }
+
+    #[test]
+    fn test_status_reports_ws_port_without_http() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let state = State::with_path(temp_dir.path().to_path_buf());
+        let mut config = NodeConfig::default();
+        config.rpc.http_enabled = false;
+        config.rpc.ws_addr = "127.0.0.1:9546".parse().unwrap();
+
+        let status = status_snapshot(&config, &state, 0, NodeState::Running, Instant::now());
+        assert_eq!(status.rpc_port, Some(9546));
+    }
+
+    #[tokio::test]
+    async fn test_dev_mode_mines_on_transaction() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut config = NodeConfig {
+            data_dir: temp_dir.path().to_path_buf(),
+            ..Default::default()
+        };
+        config.storage.db_path = temp_dir.path().join("db");
+        config.rpc.http_enabled = false;
+        config.rpc.ws_enabled = false;
+        config.metrics.enabled = false;
+        config.enable_dev_mode();
+        config.validate().unwrap();
+
+        let (mut node, _shutdown) = MerklithNode::new(config).await.unwrap();
+        node.start().await.unwrap();
+        assert!(node.is_healthy().await);
+        assert_eq!(node.current_block().await, 0);
+
+        let accounts = State::devnet_accounts();
+        let recipient = accounts[1].address();
+        let initial = node.chain_state.balance(&recipient);
+        assert!(node.chain_state.balance(&accounts[0].address()) > U256::ZERO);
+
+        let tx = merklith_types::Transaction::new(
+            node.chain_state.chain_id(),
+            0,
+            Some(recipient),
+            U256::from(1_000u64),
+            21_000,
+            U256::from(1_000_000_000u64),
+            U256::from(1u64),
+        );
+        let (signature, public_key) = accounts[0].sign_transaction(&tx);
+        node.tx_pool
+            .add_transaction(merklith_types::SignedTransaction::new(tx, signature, public_key))
+            .unwrap();
+
+        for _ in 0..50 {
+            if node.current_block().await > 0 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        assert_eq!(node.current_block().await, 1);
+        assert_eq!(
+            node.chain_state.balance(&recipient),
+            initial + U256::from(1_000u64)
+        );
+
+        node.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_blocklist_denials_are_audited() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let sanctioned = merklith_types::Address::from_bytes([0x5a; 20]);
+        let list = temp_dir.path().join("blocklist.txt");
+        std::fs::write(&list, format!("0x{}\n", sanctioned.to_hex())).unwrap();
+        let mut config = NodeConfig {
+            data_dir: temp_dir.path().to_path_buf(),
+            ..Default::default()
+        };
+        config.storage.db_path = temp_dir.path().join("db");
+        config.rpc.blocklist_file = Some(list);
+        let (node, _shutdown) = MerklithNode::new(config).await.unwrap();
+
+        let sender = &State::devnet_accounts()[0];
+        let tx = merklith_types::Transaction::new(
+            node.chain_state.chain_id(),
+            0,
+            Some(sanctioned),
+            U256::from(1_000u64),
+            21_000,
+            U256::from(1_000_000_000u64),
+            U256::ZERO,
+        );
+        let (signature, public_key) = sender.sign_transaction(&tx);
+        let tx = SignedTransaction::new(tx, signature, public_key);
+        let ctx = merklith_txpool::ValidationContext {
+            state: &node.chain_state,
+            chain_id: node.chain_state.chain_id(),
+            min_gas_price: U256::ZERO,
+            max_tx_size: usize::MAX,
+        };
+        assert!(node.tx_pool.validation().validate(&tx, &ctx).is_err());
+
+        let events = node.audit.get_events_by_type(AuditEventType::AccessDenied).unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].actor, sender.address().to_string());
+        assert_eq!(events[0].tx_hash, Some(tx.hash().to_string()));
+        assert_eq!(events[0].data["address"], serde_json::json!(sanctioned.to_string()));
+        assert!(node.audit.verify_integrity().unwrap().valid);
+    }
+
+    #[tokio::test]
+    async fn test_jailings_are_audited() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut config = NodeConfig {
+            data_dir: temp_dir.path().to_path_buf(),
+            ..Default::default()
+        };
+        config.storage.db_path = temp_dir.path().join("db");
+        let (node, _shutdown) = MerklithNode::new(config).await.unwrap();
+
+        let offline = merklith_types::Address::from_bytes([0x0f; 20]);
+        let release_at = {
+            let mut consensus = node.consensus.write();
+            for block in 1..=50 {
+                consensus.record_missed_slot(offline, block);
+            }
+            let release_at = consensus.validator_set().jailed_until(&offline).unwrap();
+            consensus.release_jailed(release_at);
+            release_at
+        };
+
+        let jailed = node.audit.get_events_by_type(AuditEventType::ValidatorJailed).unwrap();
+        assert_eq!(jailed.len(), 1);
+        assert_eq!(jailed[0].actor, offline.to_string());
+        assert_eq!(jailed[0].block_number, Some(50));
+        assert_eq!(jailed[0].data["missed_slots"], serde_json::json!(50));
+        assert_eq!(jailed[0].data["release_at"], serde_json::json!(release_at));
+        let released = node.audit.get_events_by_type(AuditEventType::ValidatorUnjailed).unwrap();
+        assert_eq!(released.len(), 1);
+        assert_eq!(released[0].block_number, Some(release_at));
+        assert!(node.audit.verify_integrity().unwrap().valid);
+    }
+
     #[test]
     fn test_node_state_is_active() {
         assert!(NodeState::Running.is_active());
@@ -0,0 +1,100 @@
+//! Node status file.
+//!
+//! The node periodically writes a small JSON snapshot of its state to the data
+//! directory so external tooling (`merklith node status`, supervisors, scripts)
+//! can inspect it without going through RPC.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Name of the status file inside the data directory.
+pub const STATUS_FILE_NAME: &str = "node-status.json";
+
+/// How often the status file is refreshed.
+pub const STATUS_INTERVAL_SECS: u64 = 5;
+
+/// Snapshot of the running node.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NodeStatus {
+    /// Process id of the node
+    pub pid: u32,
+    /// Latest block number
+    pub block_height: u64,
+    /// Connected peers
+    pub peer_count: usize,
+    /// Whether the node is catching up with the network
+    pub syncing: bool,
+    /// Seconds since the node started
+    pub uptime_secs: u64,
+    /// HTTP RPC port, or the WebSocket port when only WebSocket RPC is enabled
+    /// (None when RPC is disabled)
+    pub rpc_port: Option<u16>,
+    /// P2P listen port (None when networking is disabled)
+    pub p2p_port: Option<u16>,
+    /// Unix timestamp of this snapshot
+    pub updated_at: u64,
+}
+
+impl NodeStatus {
+    /// Path of the status file for `data_dir`.
+    pub fn path(data_dir: &Path) -> PathBuf {
+        data_dir.join(STATUS_FILE_NAME)
+    }
+
+    /// Write the status file atomically.
+    ///
+    /// The snapshot goes to a temporary file first and is renamed into place,
+    /// so readers never observe a partially written file.
+    pub fn write(&self, data_dir: &Path) -> anyhow::Result<()> {
+        std::fs::create_dir_all(data_dir)?;
+        let path = Self::path(data_dir);
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_vec_pretty(self)?)?;
+        std::fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+
+    /// Read the status file from `data_dir`.
+    pub fn read(data_dir: &Path) -> anyhow::Result<Self> {
+        let bytes = std::fs::read(Self::path(data_dir))?;
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+
+    /// Remove the status file, ignoring a missing file.
+    pub fn remove(data_dir: &Path) -> anyhow::Result<()> {
+        match std::fs::remove_file(Self::path(data_dir)) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_roundtrip() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let status = NodeStatus {
+            pid: 42,
+            block_height: 7,
+            peer_count: 3,
+            syncing: false,
+            uptime_secs: 60,
+            rpc_port: Some(8545),
+            p2p_port: None,
+            updated_at: 1_700_000_000,
+        };
+
+        status.write(dir.path()).unwrap();
+        assert_eq!(NodeStatus::read(dir.path()).unwrap(), status);
+        // No temporary file is left behind
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+
+        NodeStatus::remove(dir.path()).unwrap();
+        assert!(NodeStatus::read(dir.path()).is_err());
+        assert!(NodeStatus::remove(dir.path()).is_ok());
+    }
+}
@@ -3,18 +3,53 @@
 //! Sets up structured logging with tracing and optional JSON output.
 
 use std::sync::Mutex;
+use tracing_subscriber::filter::LevelFilter;
 use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
 
 // Global guard storage to prevent file handle leak
 // The guard must live for the entire program duration
 static LOG_GUARD: Mutex<Option<tracing_appender::non_blocking::WorkerGuard>> = Mutex::new(None);
 
+/// Level applied to modules without a directive when none is given.
+pub const DEFAULT_LOG_LEVEL: &str = "info";
+
+/// Build a filter from `RUST_LOG`-style directives.
+///
+/// Accepts a global level (`debug`), module-scoped directives
+/// (`merklith-network=debug,merklith-consensus=trace`) or both. Crate names
+/// may use hyphens; they are matched against the underscored tracing
+/// targets. Without a global level, other modules log at
+/// [`DEFAULT_LOG_LEVEL`].
+pub fn build_filter(directives: &str) -> anyhow::Result<EnvFilter> {
+    let mut has_global = false;
+    let mut normalized: Vec<String> = directives
+        .split(',')
+        .map(str::trim)
+        .filter(|d| !d.is_empty())
+        .map(|directive| match directive.split_once('=') {
+            Some((target, level)) => format!("{}={}", target.replace('-', "_"), level),
+            None if directive.parse::<LevelFilter>().is_ok() => {
+                has_global = true;
+                directive.to_string()
+            }
+            None => directive.replace('-', "_"),
+        })
+        .collect();
+    if !has_global {
+        normalized.insert(0, DEFAULT_LOG_LEVEL.to_string());
+    }
+
+    Ok(EnvFilter::try_new(normalized.join(","))?)
+}
+
 /// Initialize telemetry (logging and tracing).
+///
+/// `log_level` takes the directives described in [`build_filter`].
 pub fn init_telemetry(
     log_level: &str,
     json_format: bool,
 ) -> anyhow::Result<()> {
-    let filter = EnvFilter::try_new(log_level)?;
+    let filter = build_filter(log_level)?;
 
     if json_format {
         // JSON format for production
@@ -38,7 +73,7 @@ pub fn init_telemetry_with_file(
     log_level: &str,
     log_file: &std::path::Path,
 ) -> anyhow::Result<()> {
-    let filter = EnvFilter::try_new(log_level)?;
+    let filter = build_filter(log_level)?;
     
     let file = std::fs::OpenOptions::new()
         .create(true)
@@ -71,4 +106,26 @@ mod tests {
         // Just test the function exists
         let _ = init_telemetry("info", false);
     }
+
+    #[test]
+    fn test_build_filter_directives() {
+        let filter = build_filter("merklith-network=debug, merklith-consensus=trace,warn").unwrap();
+        let rendered = filter.to_string();
+        assert!(rendered.contains("merklith_network=debug"));
+        assert!(rendered.contains("merklith_consensus=trace"));
+        assert!(rendered.split(',').any(|d| d == "warn"));
+        assert!(!rendered.split(',').any(|d| d == DEFAULT_LOG_LEVEL));
+        assert_eq!(filter.max_level_hint(), Some(LevelFilter::TRACE));
+
+        // Module directives alone keep everything else at the default level
+        let rendered = build_filter("merklith-network=debug").unwrap().to_string();
+        assert!(rendered.split(',').any(|d| d == DEFAULT_LOG_LEVEL));
+
+        // A bare level is a global level, as before
+        let filter = build_filter("debug").unwrap();
+        assert_eq!(filter.to_string(), "debug");
+        assert_eq!(build_filter("").unwrap().to_string(), DEFAULT_LOG_LEVEL);
+
+        assert!(build_filter("merklith-network=loud").is_err());
+    }
 }
@@ -0,0 +1,133 @@
+//! Block parameter parsing shared by every method that takes a block
+
+use merklith_core::state_machine::State;
+use serde_json::Value;
+
+use crate::{invalid_param, parse_bytes32, parse_u64, JsonRpcError};
+
+/// A block reference as accepted by block and state-query methods
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockParam {
+    Latest,
+    Pending,
+    Safe,
+    Finalized,
+    Earliest,
+    Number(u64),
+    Hash([u8; 32]),
+}
+
+impl BlockParam {
+    /// Parse a tag, a hex height, or a `{blockHash}` / `{blockNumber}`
+    /// object. `null` means `latest`.
+    pub fn parse(value: &Value) -> Result<Self, JsonRpcError> {
+        match value {
+            Value::Null => Ok(Self::Latest),
+            Value::String(tag) => match tag.as_str() {
+                "latest" => Ok(Self::Latest),
+                "pending" => Ok(Self::Pending),
+                "safe" => Ok(Self::Safe),
+                "finalized" => Ok(Self::Finalized),
+                "earliest" => Ok(Self::Earliest),
+                number => parse_u64(number)
+                    .map(Self::Number)
+                    .map_err(|_| invalid_param("block", format!("Invalid block tag: {}", number))),
+            },
+            Value::Object(fields) => {
+                if let Some(hash) = fields.get("blockHash") {
+                    let hash = hash.as_str().unwrap_or_default();
+                    return parse_bytes32(hash)
+                        .map(Self::Hash)
+                        .map_err(|_| invalid_param("block", format!("Invalid block hash: {}", hash)));
+                }
+                match fields.get("blockNumber") {
+                    Some(number @ Value::String(_)) => Self::parse(number),
+                    _ => Err(invalid_param("block", "Block object needs blockHash or blockNumber")),
+                }
+            }
+            other => Err(invalid_param("block", format!("Invalid block parameter: {}", other))),
+        }
+    }
+
+    /// Height this parameter refers to. Heights are not checked against the
+    /// head; a hash this node has no block for is an error.
+    pub fn resolve(self, state: &State) -> Result<u64, JsonRpcError> {
+        match self {
+            // State keeps no finality checkpoint of its own; produced blocks are final
+            Self::Latest | Self::Pending | Self::Safe | Self::Finalized => Ok(state.block_number()),
+            Self::Earliest => Ok(0),
+            Self::Number(number) => Ok(number),
+            Self::Hash(hash) => state
+                .block_number_by_hash(&hash)
+                .ok_or_else(|| unknown_block(&format!("0x{}", hex::encode(hash)))),
+        }
+    }
+
+    /// Parse `value` and resolve it against `state`
+    pub fn from_value(value: &Value, state: &State) -> Result<u64, JsonRpcError> {
+        Self::parse(value)?.resolve(state)
+    }
+}
+
+pub(crate) fn unknown_block(block: &str) -> JsonRpcError {
+    JsonRpcError {
+        code: -32000,
+        message: format!("Unknown block: {}", block),
+        data: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tags() {
+        let parse = |value: Value| BlockParam::parse(&value).unwrap();
+        assert_eq!(parse(Value::Null), BlockParam::Latest);
+        assert_eq!(parse(serde_json::json!("latest")), BlockParam::Latest);
+        assert_eq!(parse(serde_json::json!("pending")), BlockParam::Pending);
+        assert_eq!(parse(serde_json::json!("safe")), BlockParam::Safe);
+        assert_eq!(parse(serde_json::json!("finalized")), BlockParam::Finalized);
+        assert_eq!(parse(serde_json::json!("earliest")), BlockParam::Earliest);
+        assert_eq!(parse(serde_json::json!("0x1f")), BlockParam::Number(31));
+        assert_eq!(parse(serde_json::json!({ "blockNumber": "0x2" })), BlockParam::Number(2));
+        let hash = format!("0x{}", "ab".repeat(32));
+        assert_eq!(parse(serde_json::json!({ "blockHash": hash })), BlockParam::Hash([0xab; 32]));
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        for value in [
+            serde_json::json!("soon"),
+            serde_json::json!({ "blockHash": "0x1234" }),
+            serde_json::json!({ "blockNumber": { "blockNumber": "0x1" } }),
+            serde_json::json!({}),
+            serde_json::json!(true),
+        ] {
+            let error = BlockParam::parse(&value).unwrap_err();
+            assert_eq!(error.code, -32602);
+            assert_eq!(error.data, Some(serde_json::json!({ "param": "block" })));
+        }
+    }
+
+    #[test]
+    fn test_resolve() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let state = State::with_path(temp_dir.path().to_path_buf());
+        state.produce_block(&merklith_types::Address::ZERO, vec![], true).unwrap();
+        state.produce_block(&merklith_types::Address::ZERO, vec![], true).unwrap();
+        let head = state.block_number();
+
+        for tag in [BlockParam::Latest, BlockParam::Pending, BlockParam::Safe, BlockParam::Finalized] {
+            assert_eq!(tag.resolve(&state).unwrap(), head);
+        }
+        assert_eq!(BlockParam::Earliest.resolve(&state).unwrap(), 0);
+        assert_eq!(BlockParam::Number(99).resolve(&state).unwrap(), 99);
+        assert_eq!(BlockParam::Hash(state.get_block(1).unwrap().hash).resolve(&state).unwrap(), 1);
+
+        let error = BlockParam::Hash([0xab; 32]).resolve(&state).unwrap_err();
+        assert_eq!(error.code, -32000);
+        assert!(error.message.starts_with("Unknown block"));
+    }
+}
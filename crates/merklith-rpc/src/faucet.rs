@@ -0,0 +1,216 @@
+//! Devnet faucet
+//!
+//! `merklith_faucet(address)` sends a fixed amount from a pre-funded devnet
+//! account. Each address and each client IP is limited to a few requests per
+//! window so the faucet cannot be drained. Other chains refuse the method.
+
+use std::net::IpAddr;
+
+use merklith_core::state_machine::{State, DEVNET_ACCOUNT_COUNT};
+use merklith_crypto::Keypair;
+use merklith_txpool::TransactionPool;
+use merklith_types::{Address, ChainConfig, Hash, SignedTransaction, Transaction, U256};
+use parking_lot::Mutex;
+use serde_json::Value;
+
+use crate::security::{SecurityError, SecurityManager};
+use crate::{invalid_param, parse_address, pending_nonce, process_raw_transaction, JsonRpcError, JsonRpcRequest, JsonRpcResponse};
+
+/// Sent per request unless configured otherwise: 100 MERK
+pub const DEFAULT_FAUCET_AMOUNT: U256 = U256::from_u128(100 * 1_000_000_000_000_000_000);
+
+/// Requests allowed per address and per IP in each window, by default
+pub const DEFAULT_FAUCET_REQUESTS: usize = 1;
+
+/// Rate limit window, by default
+pub const DEFAULT_FAUCET_WINDOW_SECS: u64 = 60;
+
+/// Whether `chain_id` is a devnet, the only chains a faucet may serve
+pub fn is_devnet(chain_id: u64) -> bool {
+    chain_id == ChainConfig::devnet().chain_id
+}
+
+/// Pays out test funds from a devnet account
+pub struct Faucet {
+    keypair: Keypair,
+    amount: U256,
+    limits: SecurityManager,
+    /// Held while a payout is signed and submitted, so concurrent requests
+    /// do not sign with the same nonce
+    sending: Mutex<()>,
+}
+
+impl Faucet {
+    /// A faucet paying from `keypair`
+    pub fn new(keypair: Keypair) -> Self {
+        Self {
+            keypair,
+            amount: DEFAULT_FAUCET_AMOUNT,
+            limits: SecurityManager::with_custom_rate_limit(DEFAULT_FAUCET_REQUESTS, DEFAULT_FAUCET_WINDOW_SECS),
+            sending: Mutex::new(()),
+        }
+    }
+
+    /// A faucet paying from the last pre-funded devnet account, leaving the
+    /// others to developers
+    pub fn devnet() -> Self {
+        let accounts = State::devnet_accounts();
+        Self::new(accounts[DEVNET_ACCOUNT_COUNT - 1].clone())
+    }
+
+    /// Send `amount` per request
+    pub fn with_amount(mut self, amount: U256) -> Self {
+        self.amount = amount;
+        self
+    }
+
+    /// Allow `max_requests` per address and per IP every `window_secs`
+    pub fn with_rate_limit(mut self, max_requests: usize, window_secs: u64) -> Self {
+        self.limits = SecurityManager::with_custom_rate_limit(max_requests, window_secs);
+        self
+    }
+
+    /// Account the faucet pays from
+    pub fn address(&self) -> Address {
+        self.keypair.address()
+    }
+
+    /// Amount sent per request
+    pub fn amount(&self) -> U256 {
+        self.amount
+    }
+
+    /// Send the faucet amount to `to`, returning the transfer's hash
+    pub fn drip(
+        &self,
+        to: Address,
+        ip: Option<IpAddr>,
+        state: &State,
+        pool: Option<&TransactionPool>,
+        chain_id: u64,
+    ) -> Result<Hash, JsonRpcError> {
+        if !is_devnet(chain_id) {
+            return Err(faucet_disabled(chain_id));
+        }
+
+        let rate_limited = |e: SecurityError| JsonRpcError {
+            code: -32005,
+            message: format!("Faucet {}, try again later", e.to_string().to_lowercase()),
+            data: None,
+        };
+        self.limits.check_address_rate(&to).map_err(rate_limited)?;
+        if let Some(ip) = ip {
+            self.limits.check_rpc_rate(&ip.to_string()).map_err(rate_limited)?;
+        }
+
+        let _sending = self.sending.lock();
+        let from = self.keypair.address();
+        let max_fee_per_gas = pool.map_or(U256::ONE, TransactionPool::min_gas_price).max(U256::ONE);
+        let tx = Transaction::new(chain_id, pending_nonce(state, pool, &from), Some(to), self.amount, 21000, max_fee_per_gas, U256::ZERO);
+        let (signature, public_key) = self.keypair.sign_transaction(&tx);
+        let signed = SignedTransaction::new(tx, signature, public_key);
+        let raw = borsh::to_vec(&signed).map_err(|e| JsonRpcError { code: -32603, message: e.to_string(), data: None })?;
+
+        let hash = process_raw_transaction(&format!("0x{}", hex::encode(raw)), state, pool, chain_id)?;
+        tracing::info!("Faucet sent {} to {}", self.amount, to);
+        Ok(hash)
+    }
+}
+
+fn faucet_disabled(chain_id: u64) -> JsonRpcError {
+    JsonRpcError {
+        code: -32601,
+        message: format!(
+            "Faucet is only available on devnet (chain id {}), this node serves chain id {}",
+            ChainConfig::devnet().chain_id,
+            chain_id
+        ),
+        data: None,
+    }
+}
+
+/// Serve `merklith_faucet(address)` for a client at `ip`
+pub(crate) fn handle_faucet(
+    req: &JsonRpcRequest,
+    state: &State,
+    pool: Option<&TransactionPool>,
+    faucet: Option<&Faucet>,
+    chain_id: u64,
+    ip: Option<IpAddr>,
+) -> JsonRpcResponse {
+    let result = faucet
+        .ok_or_else(|| faucet_disabled(chain_id))
+        .and_then(|faucet| {
+            let address = req.params.first()
+                .and_then(|v| v.as_str())
+                .and_then(|s| parse_address(s).ok())
+                .ok_or_else(|| invalid_param("address", "Expected a hex or bech32 address"))?;
+            faucet.drip(address, ip, state, pool, chain_id)
+        });
+    match result {
+        Ok(hash) => JsonRpcResponse {
+            jsonrpc: "2.0".to_string(),
+            result: Some(Value::String(format!("0x{}", hex::encode(hash.as_bytes())))),
+            error: None,
+            id: req.id.clone(),
+        },
+        Err(e) => JsonRpcResponse {
+            jsonrpc: "2.0".to_string(),
+            result: None,
+            error: Some(e),
+            id: req.id.clone(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    fn request(address: &Address) -> JsonRpcRequest {
+        JsonRpcRequest {
+            jsonrpc: "2.0".to_string(),
+            method: "merklith_faucet".to_string(),
+            params: vec![Value::String(format!("0x{}", address.to_hex()))],
+            id: Some(serde_json::json!(1)),
+        }
+    }
+
+    #[test]
+    fn test_faucet_rate_limited() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let genesis = State::devnet_genesis();
+        let chain_id = genesis.chain_config.chain_id;
+        let state = Arc::new(State::with_genesis(
+            temp_dir.path().to_path_buf(),
+            genesis,
+            merklith_storage::PruningConfig::archive(),
+        ));
+        let faucet = Faucet::devnet();
+        let ip: IpAddr = "10.0.0.1".parse().unwrap();
+        let alice = Address::from_bytes([0xa1; 20]);
+        let bob = Address::from_bytes([0xb0; 20]);
+
+        let pool = TransactionPool::default();
+        let funded = handle_faucet(&request(&alice), &state, Some(&pool), Some(&faucet), chain_id, Some(ip));
+        assert!(funded.error.is_none(), "{:?}", funded.error);
+        state.produce_block(&Address::from_bytes([0x77; 20]), pool.get_pending(10), false).unwrap();
+        assert_eq!(state.balance(&alice), DEFAULT_FAUCET_AMOUNT);
+
+        // A rapid second request for the same address is refused
+        let again = handle_faucet(&request(&alice), &state, None, Some(&faucet), chain_id, None);
+        assert_eq!(again.error.unwrap().code, -32005);
+        // So is another address from the same IP
+        let same_ip = handle_faucet(&request(&bob), &state, None, Some(&faucet), chain_id, Some(ip));
+        assert_eq!(same_ip.error.unwrap().code, -32005);
+        assert_eq!(state.balance(&alice), DEFAULT_FAUCET_AMOUNT);
+        assert_eq!(state.balance(&bob), U256::ZERO);
+
+        // Never on other chains, configured or not
+        let mainnet = ChainConfig::mainnet().chain_id;
+        let error = handle_faucet(&request(&bob), &state, None, Some(&faucet), mainnet, None).error.unwrap();
+        assert!(error.message.contains("only available on devnet"));
+        assert!(handle_faucet(&request(&bob), &state, None, None, chain_id, None).error.is_some());
+    }
+}
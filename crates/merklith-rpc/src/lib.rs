@@ -2,15 +2,32 @@
 //!
 //! This implements the Merklith-specific RPC API with Ethereum compatibility
 
+use std::collections::HashSet;
 use std::net::SocketAddr;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::sync::Mutex;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use merklith_consensus::{ConsensusEngine, ContributionTracker, PoCScore};
 use merklith_core::state_machine::State;
+use merklith_network::sync::SyncTracker;
+use merklith_network::{NetworkCommand, PeerSource};
+use merklith_txpool::TransactionPool;
+use merklith_vm::MerklithVM;
 
+pub mod block_param;
 pub mod security;
+pub mod metrics;
+pub mod mempool;
+pub mod faucet;
+pub mod submit;
+pub mod subscriptions;
+pub use block_param::BlockParam;
 pub use security::{SecurityManager, SecurityError, RateLimiter, ReplayProtection, InputValidator};
+pub use metrics::RpcMetrics;
+pub use mempool::{export_mempool, import_mempool, MempoolImport};
+pub use faucet::Faucet;
 
 /// RPC configuration
 #[derive(Debug, Clone)]
@@ -22,6 +39,28 @@ pub struct RpcServerConfig {
     pub max_body_size: u32,
     pub max_connections: u32,
     pub rate_limit: Option<u32>,
+    /// Bearer token for admin methods; admin methods are disabled when unset
+    pub admin_token: Option<String>,
+    /// Requests slower than this are logged at `warn`
+    pub slow_request_threshold: Duration,
+    /// Time allowed to receive request headers and body (slow-loris guard)
+    pub read_timeout: Duration,
+    /// Connections that move no bytes either way for this long are closed
+    pub idle_timeout: Duration,
+    /// When set, only these methods are served. Entries are method names or
+    /// whole namespaces such as `debug_*`
+    pub enabled_methods: Option<HashSet<String>>,
+    /// Methods or namespaces that are never served; takes precedence over
+    /// `enabled_methods`
+    pub disabled_methods: HashSet<String>,
+    /// Deepest array/object nesting accepted in a request body
+    pub max_json_depth: usize,
+    /// Most elements accepted in any single JSON array or object
+    pub max_json_elements: usize,
+    /// Most calls accepted in one JSON-RPC batch
+    pub max_batch_size: usize,
+    /// Largest return data a contract call may produce
+    pub max_return_data: usize,
 }
 
 impl Default for RpcServerConfig {
@@ -38,6 +77,16 @@ impl Default for RpcServerConfig {
             max_body_size: 10 * 1024 * 1024,
             max_connections: 100,
             rate_limit: None,
+            admin_token: None,
+            slow_request_threshold: Duration::from_secs(1),
+            read_timeout: Duration::from_secs(10),
+            idle_timeout: Duration::from_secs(60),
+            enabled_methods: None,
+            disabled_methods: HashSet::new(),
+            max_json_depth: 64,
+            max_json_elements: 10_000,
+            max_batch_size: 100,
+            max_return_data: merklith_vm::MAX_RETURN_DATA_BYTES,
         }
     }
 }
@@ -68,45 +117,307 @@ pub struct JsonRpcResponse {
 pub struct JsonRpcError {
     pub code: i32,
     pub message: String,
+    /// Extra detail: revert data, expected/got nonce, offending parameter
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
 }
 
+/// Methods that require `Authorization: Bearer <admin_token>`
+pub const ADMIN_METHODS: &[&str] = &["merklith_sweepDust", "merklith_submitBlock"];
+
 /// RPC Server
 pub struct RpcServer {
     config: RpcServerConfig,
     state: Arc<State>,
     chain_id: u64,
-    shutdown_tx: Option<tokio::sync::oneshot::Sender<()>>,
+    metrics: Option<RpcMetrics>,
+    pool: Option<Arc<TransactionPool>>,
+    contributions: Option<Arc<parking_lot::RwLock<ContributionTracker>>>,
+    /// Contract VM shared by every request, created once at startup
+    vm: Option<Arc<MerklithVM>>,
+    /// Peer head tracking for `eth_syncing`; reported as synced when unset
+    sync: Option<Arc<SyncTracker>>,
+    /// Connected peers for `net_peerCount`; reported as none when unset
+    peers: Option<Arc<dyn PeerSource>>,
+    /// Serves `merklith_faucet`; refused when unset
+    faucet: Option<Arc<Faucet>>,
+    /// Checks blocks for `merklith_submitBlock`; refused when unset
+    consensus: Option<Arc<parking_lot::RwLock<ConsensusEngine>>>,
+    /// Where accepted submitted blocks are broadcast from
+    network: Option<tokio::sync::mpsc::Sender<NetworkCommand>>,
+    shutdown_tx: Option<tokio::sync::watch::Sender<()>>,
+}
+
+/// Per-connection settings shared by every request handler
+#[derive(Clone)]
+struct ServiceContext {
+    chain_id: u64,
+    admin_token: Option<Arc<str>>,
+    metrics: Option<RpcMetrics>,
+    pool: Option<Arc<TransactionPool>>,
+    contributions: Option<Arc<parking_lot::RwLock<ContributionTracker>>>,
+    vm: Arc<MerklithVM>,
+    sync: Option<Arc<SyncTracker>>,
+    peers: Option<Arc<dyn PeerSource>>,
+    faucet: Option<Arc<Faucet>>,
+    consensus: Option<Arc<parking_lot::RwLock<ConsensusEngine>>>,
+    network: Option<tokio::sync::mpsc::Sender<NetworkCommand>>,
+    slow_request_threshold: Duration,
+    max_body_size: usize,
+    read_timeout: Duration,
+    enabled_methods: Option<Arc<HashSet<String>>>,
+    disabled_methods: Arc<HashSet<String>>,
+    max_json_depth: usize,
+    max_json_elements: usize,
+    max_batch_size: usize,
+}
+
+/// Caps the number of open connections
+#[derive(Clone)]
+struct ConnectionLimiter {
+    permits: Arc<tokio::sync::Semaphore>,
+}
+
+impl ConnectionLimiter {
+    fn new(max_connections: usize) -> Self {
+        Self { permits: Arc::new(tokio::sync::Semaphore::new(max_connections)) }
+    }
+
+    /// A permit to hold for the lifetime of a connection, or None when full
+    fn try_acquire(&self) -> Option<tokio::sync::OwnedSemaphorePermit> {
+        self.permits.clone().try_acquire_owned().ok()
+    }
+}
+
+/// Connection that fails once no bytes have moved either way for `timeout`,
+/// so idle keep-alive and stalled connections give back their slot
+struct IdleTimeout<IO> {
+    io: IO,
+    timeout: Duration,
+    deadline: std::pin::Pin<Box<tokio::time::Sleep>>,
+}
+
+impl<IO> IdleTimeout<IO> {
+    fn new(io: IO, timeout: Duration) -> Self {
+        Self { io, timeout, deadline: Box::pin(tokio::time::sleep(timeout)) }
+    }
+
+    fn get_ref(&self) -> &IO {
+        &self.io
+    }
+
+    /// Push the deadline back on progress; fail a stalled operation once it passes
+    fn track<T>(
+        &mut self,
+        cx: &mut std::task::Context<'_>,
+        poll: std::task::Poll<std::io::Result<T>>,
+    ) -> std::task::Poll<std::io::Result<T>> {
+        use std::future::Future;
+        use std::task::Poll;
+
+        match poll {
+            Poll::Ready(result) => {
+                self.deadline.as_mut().reset(tokio::time::Instant::now() + self.timeout);
+                Poll::Ready(result)
+            }
+            Poll::Pending => match self.deadline.as_mut().poll(cx) {
+                Poll::Ready(()) => Poll::Ready(Err(std::io::Error::new(
+                    std::io::ErrorKind::TimedOut,
+                    "connection idle",
+                ))),
+                Poll::Pending => Poll::Pending,
+            },
+        }
+    }
+}
+
+impl<IO: tokio::io::AsyncRead + Unpin> tokio::io::AsyncRead for IdleTimeout<IO> {
+    fn poll_read(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        buf: &mut tokio::io::ReadBuf<'_>,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        let this = self.get_mut();
+        let poll = std::pin::Pin::new(&mut this.io).poll_read(cx, buf);
+        this.track(cx, poll)
+    }
+}
+
+impl<IO: tokio::io::AsyncWrite + Unpin> tokio::io::AsyncWrite for IdleTimeout<IO> {
+    fn poll_write(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        buf: &[u8],
+    ) -> std::task::Poll<std::io::Result<usize>> {
+        let this = self.get_mut();
+        let poll = std::pin::Pin::new(&mut this.io).poll_write(cx, buf);
+        this.track(cx, poll)
+    }
+
+    fn poll_flush(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        let this = self.get_mut();
+        let poll = std::pin::Pin::new(&mut this.io).poll_flush(cx);
+        this.track(cx, poll)
+    }
+
+    fn poll_shutdown(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        std::pin::Pin::new(&mut self.get_mut().io).poll_shutdown(cx)
+    }
 }
 
 impl RpcServer {
     pub fn new(config: RpcServerConfig, state: Arc<State>, chain_id: u64) -> Self {
-        Self { config, state, chain_id, shutdown_tx: None }
+        Self { config, state, chain_id, metrics: None, pool: None, contributions: None, vm: None, sync: None, peers: None, faucet: None, consensus: None, network: None, shutdown_tx: None }
+    }
+
+    /// Run contract calls on `vm` instead of creating one at startup
+    pub fn with_vm(mut self, vm: Arc<MerklithVM>) -> Self {
+        self.vm = Some(vm);
+        self
+    }
+
+    /// VM used for contract calls, once the server has started
+    pub fn vm(&self) -> Option<&Arc<MerklithVM>> {
+        self.vm.as_ref()
+    }
+
+    /// Serve pending transactions from `pool` alongside mined ones
+    pub fn with_pool(mut self, pool: Arc<TransactionPool>) -> Self {
+        self.pool = Some(pool);
+        self
+    }
+
+    /// Serve PoC standings from the node's contribution tracker
+    pub fn with_contributions(mut self, contributions: Arc<parking_lot::RwLock<ContributionTracker>>) -> Self {
+        self.contributions = Some(contributions);
+        self
+    }
+
+    /// Report catch-up progress from the node's sync tracker
+    pub fn with_sync(mut self, sync: Arc<SyncTracker>) -> Self {
+        self.sync = Some(sync);
+        self
+    }
+
+    /// Report connected peers from the P2P layer
+    pub fn with_peers(mut self, peers: Arc<dyn PeerSource>) -> Self {
+        self.peers = Some(peers);
+        self
+    }
+
+    /// Pay out test funds through `merklith_faucet`; only served on devnet
+    pub fn with_faucet(mut self, faucet: Arc<Faucet>) -> Self {
+        self.faucet = Some(faucet);
+        self
+    }
+
+    /// Accept blocks from external validators through `merklith_submitBlock`,
+    /// checked by `consensus`
+    pub fn with_consensus(mut self, consensus: Arc<parking_lot::RwLock<ConsensusEngine>>) -> Self {
+        self.consensus = Some(consensus);
+        self
+    }
+
+    /// Broadcast accepted submitted blocks through the P2P layer
+    pub fn with_network(mut self, network: tokio::sync::mpsc::Sender<NetworkCommand>) -> Self {
+        self.network = Some(network);
+        self
+    }
+
+    /// Record per-method request counts and latencies
+    pub fn with_metrics(mut self, metrics: RpcMetrics) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Chain id served by `eth_chainId` / `net_version`
+    pub fn chain_id(&self) -> u64 {
+        self.chain_id
     }
 
+    /// Start serving. Fails if the contract VM cannot be initialized, so a
+    /// broken VM stops the node instead of failing every contract call.
     pub async fn start(&mut self) -> anyhow::Result<()> {
         let addr = self.config.http_addr;
         let state = self.state.clone();
-        let chain_id = self.chain_id;
+        let vm = match &self.vm {
+            Some(vm) => vm.clone(),
+            None => {
+                let vm = MerklithVM::new()
+                    .map_err(|e| anyhow::anyhow!("Failed to initialize contract VM: {}", e))?
+                    .with_max_return_data(self.config.max_return_data);
+                self.vm.insert(Arc::new(vm)).clone()
+            }
+        };
+        let context = ServiceContext {
+            chain_id: self.chain_id,
+            admin_token: self.config.admin_token.as_deref().map(Arc::from),
+            metrics: self.metrics.clone(),
+            pool: self.pool.clone(),
+            contributions: self.contributions.clone(),
+            vm,
+            sync: self.sync.clone(),
+            peers: self.peers.clone(),
+            faucet: self.faucet.clone(),
+            consensus: self.consensus.clone(),
+            network: self.network.clone(),
+            slow_request_threshold: self.config.slow_request_threshold,
+            max_body_size: self.config.max_body_size as usize,
+            read_timeout: self.config.read_timeout,
+            enabled_methods: self.config.enabled_methods.clone().map(Arc::new),
+            disabled_methods: Arc::new(self.config.disabled_methods.clone()),
+            max_json_depth: self.config.max_json_depth,
+            max_json_elements: self.config.max_json_elements,
+            max_batch_size: self.config.max_batch_size,
+        };
+        let limiter = ConnectionLimiter::new(self.config.max_connections as usize);
         
-        let (shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel();
+        let (shutdown_tx, shutdown_rx) = tokio::sync::watch::channel(());
         self.shutdown_tx = Some(shutdown_tx);
+        if let Some(ws_addr) = self.config.ws_addr {
+            let ws_addr = subscriptions::serve(ws_addr, state.clone(), shutdown_rx.clone()).await?;
+            tracing::info!("Merklith WebSocket subscriptions listening on {}", ws_addr);
+        }
+
+        let mut listener = hyper::server::conn::AddrIncoming::bind(&addr)?;
+        let idle_timeout = self.config.idle_timeout;
+        let incoming = hyper::server::accept::poll_fn(move |cx| {
+            hyper::server::accept::Accept::poll_accept(std::pin::Pin::new(&mut listener), cx)
+                .map(|conn| conn.map(|conn| conn.map(|conn| IdleTimeout::new(conn, idle_timeout))))
+        });
 
-        let server = hyper::Server::bind(&addr).serve(hyper::service::make_service_fn(move |_| {
+        let server = hyper::Server::builder(incoming)
+            .http1_header_read_timeout(self.config.read_timeout)
+            .serve(hyper::service::make_service_fn(move |conn: &IdleTimeout<hyper::server::conn::AddrStream>| {
+            let remote_ip = conn.get_ref().remote_addr().ip();
             let state = state.clone();
-            let chain_id = chain_id;
+            let context = context.clone();
+            // Held by the service until the connection closes
+            let permit = limiter.try_acquire();
             async move {
                 Ok::<_, hyper::Error>(hyper::service::service_fn(move |req| {
                     let state = state.clone();
-                    let chain_id = chain_id;
+                    let context = context.clone();
+                    let accepted = permit.is_some();
                     async move {
-                        handle_rpc_request(req, state, chain_id).await
+                        if !accepted {
+                            return Ok(too_many_connections());
+                        }
+                        handle_rpc_request(req, state, context, Some(remote_ip)).await
                     }
                 }))
             }
         }));
 
-        let server = server.with_graceful_shutdown(async {
-            let _ = shutdown_rx.await;
+        let mut shutdown_rx = shutdown_rx;
+        let server = server.with_graceful_shutdown(async move {
+            let _ = shutdown_rx.changed().await;
         });
 
         tokio::spawn(async move {
@@ -123,7 +434,8 @@ impl RpcServer {
 async fn handle_rpc_request(
     req: hyper::Request<hyper::Body>,
     state: Arc<State>,
-    chain_id: u64,
+    context: ServiceContext,
+    remote_ip: Option<std::net::IpAddr>,
 ) -> Result<hyper::Response<hyper::Body>, hyper::Error> {
     // Handle CORS preflight requests
     if req.method() == hyper::Method::OPTIONS {
@@ -150,26 +462,134 @@ async fn handle_rpc_request(
         return Ok(response);
     }
 
-    let body_bytes = hyper::body::to_bytes(req.into_body()).await?;
-    let rpc_req: JsonRpcRequest = match serde_json::from_slice(&body_bytes) {
-        Ok(r) => r,
-        Err(e) => {
-            // Build response safely without expect
-            let response = hyper::Response::builder()
-                .status(hyper::StatusCode::BAD_REQUEST)
-                .header("Access-Control-Allow-Origin", "*")
-                .body(hyper::Body::from(format!("Invalid JSON: {}", e)))
-                .unwrap_or_else(|_| {
-                    // If even the fallback fails, return a minimal valid response
-                    hyper::Response::new(hyper::Body::from("Invalid JSON"))
-                });
-            return Ok(response);
+    let authorization = req
+        .headers()
+        .get(hyper::header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+
+    let declared_length = req
+        .headers()
+        .get(hyper::header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<usize>().ok());
+    if declared_length.is_some_and(|len| len > context.max_body_size) {
+        return Ok(payload_too_large(context.max_body_size));
+    }
+
+    let body_bytes = match tokio::time::timeout(
+        context.read_timeout,
+        read_body_limited(req.into_body(), context.max_body_size),
+    ).await {
+        Ok(Ok(Some(bytes))) => bytes,
+        Ok(Ok(None)) => return Ok(payload_too_large(context.max_body_size)),
+        Ok(Err(e)) => return Err(e),
+        Err(_) => {
+            return Ok(hyper::Response::builder()
+                .status(hyper::StatusCode::REQUEST_TIMEOUT)
+                .header("Connection", "close")
+                .body(hyper::Body::from("Request body not received in time"))
+                .unwrap_or_else(|_| hyper::Response::new(hyper::Body::empty())));
+        }
+    };
+    if let Err(message) = check_json_complexity(
+        &body_bytes,
+        context.max_json_depth,
+        context.max_json_elements,
+    ) {
+        return Ok(invalid_request(message));
+    }
+
+    let respond = |rpc_req: &JsonRpcRequest| {
+        if !is_method_enabled(
+            &rpc_req.method,
+            context.enabled_methods.as_deref(),
+            &context.disabled_methods,
+        ) {
+            return JsonRpcResponse {
+                jsonrpc: "2.0".to_string(),
+                result: None,
+                error: Some(JsonRpcError {
+                    code: -32601,
+                    message: format!("Method disabled: {}", rpc_req.method),
+                    data: None,
+                }),
+                id: rpc_req.id.clone(),
+            };
+        }
+        // Rate limited by client IP, which the other methods never see
+        if rpc_req.method == "merklith_faucet" {
+            return faucet::handle_faucet(
+                rpc_req,
+                &state,
+                context.pool.as_deref(),
+                context.faucet.as_deref(),
+                context.chain_id,
+                remote_ip,
+            );
+        }
+        if !is_authorized(&rpc_req.method, authorization.as_deref(), context.admin_token.as_deref()) {
+            return JsonRpcResponse {
+                jsonrpc: "2.0".to_string(),
+                result: None,
+                error: Some(JsonRpcError {
+                    code: -32004,
+                    message: "Unauthorized".to_string(),
+                    data: None,
+                }),
+                id: rpc_req.id.clone(),
+            };
+        }
+        if rpc_req.method == "merklith_submitBlock" {
+            submit::handle_submit_block(
+                rpc_req,
+                &state,
+                context.pool.as_deref(),
+                context.consensus.as_deref(),
+                context.contributions.as_deref(),
+                context.network.as_ref(),
+            )
+        } else {
+            dispatch(
+                rpc_req,
+                state.clone(),
+                context.pool.as_deref(),
+                context.contributions.as_deref(),
+                context.chain_id,
+                &context.vm,
+                context.sync.as_deref(),
+                context.peers.as_deref(),
+                context.metrics.as_ref(),
+                context.slow_request_threshold,
+            )
         }
     };
 
-    let response = handle_method(&rpc_req, state, chain_id);
+    // A JSON array is a batch; its responses are returned in request order
+    let is_batch = body_bytes.iter().find(|b| !b.is_ascii_whitespace()) == Some(&b'[');
+    let body = if is_batch {
+        match serde_json::from_slice::<Vec<JsonRpcRequest>>(&body_bytes) {
+            // Empty and oversized batches get a single error, not an array
+            Ok(requests) if requests.is_empty() => {
+                serde_json::to_string(&invalid_batch("Empty batch")).unwrap_or_default()
+            }
+            Ok(requests) if requests.len() > context.max_batch_size => {
+                let message = format!("Batch of {} calls exceeds limit of {}", requests.len(), context.max_batch_size);
+                serde_json::to_string(&invalid_batch(&message)).unwrap_or_default()
+            }
+            Ok(requests) => {
+                let responses: Vec<JsonRpcResponse> = requests.iter().map(respond).collect();
+                serde_json::to_string(&responses).unwrap_or_default()
+            }
+            Err(e) => return Ok(invalid_json(e)),
+        }
+    } else {
+        match serde_json::from_slice::<JsonRpcRequest>(&body_bytes) {
+            Ok(rpc_req) => serde_json::to_string(&respond(&rpc_req)).unwrap_or_default(),
+            Err(e) => return Ok(invalid_json(e)),
+        }
+    };
 
-    let body = serde_json::to_string(&response).unwrap_or_default();
     Ok(hyper::Response::builder()
         .status(hyper::StatusCode::OK)
         .header("Content-Type", "application/json")
@@ -184,7 +604,215 @@ async fn handle_rpc_request(
         }))
 }
 
-fn handle_method(req: &JsonRpcRequest, state: Arc<State>, chain_id: u64) -> JsonRpcResponse {
+/// Read at most `limit` bytes of `body`; None if it is longer
+async fn read_body_limited(
+    mut body: hyper::Body,
+    limit: usize,
+) -> Result<Option<bytes::Bytes>, hyper::Error> {
+    use hyper::body::HttpBody;
+
+    let mut buf = bytes::BytesMut::new();
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk?;
+        if buf.len() + chunk.len() > limit {
+            return Ok(None);
+        }
+        buf.extend_from_slice(&chunk);
+    }
+    Ok(Some(buf.freeze()))
+}
+
+/// Check `body` against nesting and size limits by scanning its bytes, so an
+/// over-complex payload is rejected before serde materializes any of it
+fn check_json_complexity(body: &[u8], max_depth: usize, max_elements: usize) -> Result<(), String> {
+    // Separating commas seen in each open array/object
+    let mut open: Vec<usize> = Vec::new();
+    let mut in_string = false;
+    let mut escaped = false;
+    for &byte in body {
+        if in_string {
+            match byte {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match byte {
+            b'"' => in_string = true,
+            b'[' | b'{' => {
+                open.push(0);
+                if open.len() > max_depth {
+                    return Err(format!("JSON nested deeper than {} levels", max_depth));
+                }
+            }
+            b']' | b'}' => {
+                open.pop();
+            }
+            b',' => {
+                if let Some(commas) = open.last_mut() {
+                    *commas += 1;
+                    if *commas >= max_elements {
+                        return Err(format!("JSON array or object has more than {} elements", max_elements));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// A `-32600` JSON-RPC error for a request rejected before parsing
+fn invalid_request(message: String) -> hyper::Response<hyper::Body> {
+    let response = JsonRpcResponse {
+        jsonrpc: "2.0".to_string(),
+        result: None,
+        error: Some(JsonRpcError { code: -32600, message, data: None }),
+        id: None,
+    };
+    hyper::Response::builder()
+        .status(hyper::StatusCode::BAD_REQUEST)
+        .header("Content-Type", "application/json")
+        .header("Access-Control-Allow-Origin", "*")
+        .body(hyper::Body::from(serde_json::to_string(&response).unwrap_or_default()))
+        .unwrap_or_else(|_| hyper::Response::new(hyper::Body::from("Invalid request")))
+}
+
+fn invalid_batch(message: &str) -> JsonRpcResponse {
+    JsonRpcResponse {
+        jsonrpc: "2.0".to_string(),
+        result: None,
+        error: Some(JsonRpcError {
+            code: -32600,
+            message: format!("Invalid request: {}", message),
+            data: None,
+        }),
+        id: None,
+    }
+}
+
+fn invalid_json(e: serde_json::Error) -> hyper::Response<hyper::Body> {
+    // Build response safely without expect
+    hyper::Response::builder()
+        .status(hyper::StatusCode::BAD_REQUEST)
+        .header("Access-Control-Allow-Origin", "*")
+        .body(hyper::Body::from(format!("Invalid JSON: {}", e)))
+        .unwrap_or_else(|_| {
+            // If even the fallback fails, return a minimal valid response
+            hyper::Response::new(hyper::Body::from("Invalid JSON"))
+        })
+}
+
+fn payload_too_large(limit: usize) -> hyper::Response<hyper::Body> {
+    hyper::Response::builder()
+        .status(hyper::StatusCode::PAYLOAD_TOO_LARGE)
+        .header("Access-Control-Allow-Origin", "*")
+        .header("Connection", "close")
+        .body(hyper::Body::from(format!("Request body exceeds {} bytes", limit)))
+        .unwrap_or_else(|_| hyper::Response::new(hyper::Body::empty()))
+}
+
+fn too_many_connections() -> hyper::Response<hyper::Body> {
+    hyper::Response::builder()
+        .status(hyper::StatusCode::SERVICE_UNAVAILABLE)
+        .header("Access-Control-Allow-Origin", "*")
+        .header("Connection", "close")
+        .body(hyper::Body::from("Too many connections"))
+        .unwrap_or_else(|_| hyper::Response::new(hyper::Body::empty()))
+}
+
+/// Run a request, timing it. Only the method name is logged, never params.
+#[allow(clippy::too_many_arguments)]
+fn dispatch(
+    req: &JsonRpcRequest,
+    state: Arc<State>,
+    pool: Option<&TransactionPool>,
+    contributions: Option<&parking_lot::RwLock<ContributionTracker>>,
+    chain_id: u64,
+    vm: &MerklithVM,
+    sync: Option<&SyncTracker>,
+    peers: Option<&dyn PeerSource>,
+    metrics: Option<&RpcMetrics>,
+    slow_request_threshold: Duration,
+) -> JsonRpcResponse {
+    let span = tracing::debug_span!("rpc", method = %req.method);
+    let _enter = span.enter();
+
+    let started = Instant::now();
+    let response = handle_method(req, state, pool, contributions, chain_id, vm, sync, peers);
+    let elapsed = started.elapsed();
+
+    // Arbitrary method names must not become metric labels
+    let method = match &response.error {
+        Some(e) if e.code == -32601 => metrics::UNKNOWN_METHOD,
+        _ => req.method.as_str(),
+    };
+    if let Some(metrics) = metrics {
+        metrics.observe(method, elapsed);
+    }
+
+    if elapsed > slow_request_threshold {
+        tracing::warn!("Slow RPC request: {} took {:?}", method, elapsed);
+    } else {
+        tracing::debug!("RPC {} took {:?}", method, elapsed);
+    }
+
+    response
+}
+
+/// Whether a caller presenting `authorization` may invoke `method`
+fn is_authorized(method: &str, authorization: Option<&str>, admin_token: Option<&str>) -> bool {
+    if !ADMIN_METHODS.contains(&method) {
+        return true;
+    }
+
+    match (authorization.and_then(|h| h.strip_prefix("Bearer ")), admin_token) {
+        (Some(given), Some(expected)) => {
+            // Compare without short-circuiting on the first differing byte
+            given.len() == expected.len()
+                && given
+                    .bytes()
+                    .zip(expected.bytes())
+                    .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+                    == 0
+        }
+        _ => false,
+    }
+}
+
+/// Whether `method` passes the configured allow and deny lists
+fn is_method_enabled(
+    method: &str,
+    enabled: Option<&HashSet<String>>,
+    disabled: &HashSet<String>,
+) -> bool {
+    // `debug_*` matches every method in the `debug` namespace
+    let matches = |list: &HashSet<String>| {
+        list.contains(method)
+            || method
+                .split_once('_')
+                .is_some_and(|(namespace, _)| list.contains(&format!("{}_*", namespace)))
+    };
+
+    if matches(disabled) {
+        return false;
+    }
+    enabled.map_or(true, matches)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn handle_method(
+    req: &JsonRpcRequest,
+    state: Arc<State>,
+    pool: Option<&TransactionPool>,
+    contributions: Option<&parking_lot::RwLock<ContributionTracker>>,
+    chain_id: u64,
+    vm: &MerklithVM,
+    sync: Option<&SyncTracker>,
+    peers: Option<&dyn PeerSource>,
+) -> JsonRpcResponse {
     match req.method.as_str() {
         // === Chain Info ===
         "merklith_chainId" => JsonRpcResponse {
@@ -238,9 +866,29 @@ fn handle_method(req: &JsonRpcRequest, state: Arc<State>, chain_id: u64) -> Json
             }
         },
         
+        "merklith_getPendingNonce" => {
+            let addr_str = req.params.first()
+                .and_then(|v| v.as_str())
+                .unwrap_or("");
+            match parse_address(addr_str) {
+                Ok(addr) => JsonRpcResponse {
+                    jsonrpc: "2.0".to_string(),
+                    result: Some(Value::String(format!("0x{:x}", pending_nonce(&state, pool, &addr)))),
+                    error: None,
+                    id: req.id.clone(),
+                },
+                Err(_) => JsonRpcResponse {
+                    jsonrpc: "2.0".to_string(),
+                    result: None,
+                    error: Some(invalid_param("address", "Invalid address")),
+                    id: req.id.clone(),
+                },
+            }
+        },
+
         "merklith_sendRawTransaction" => {
             let raw_tx = req.params.first().and_then(|v| v.as_str()).unwrap_or("");
-            match process_raw_transaction(raw_tx, &state, chain_id) {
+            match process_raw_transaction(raw_tx, &state, pool, chain_id) {
                 Ok(hash) => JsonRpcResponse {
                     jsonrpc: "2.0".to_string(),
                     result: Some(Value::String(format!("0x{}", hex::encode(hash.as_bytes())))),
@@ -250,10 +898,7 @@ fn handle_method(req: &JsonRpcRequest, state: Arc<State>, chain_id: u64) -> Json
                 Err(e) => JsonRpcResponse {
                     jsonrpc: "2.0".to_string(),
                     result: None,
-                    error: Some(JsonRpcError {
-                        code: -32602,
-                        message: e,
-                    }),
+                    error: Some(e),
                     id: req.id.clone(),
                 },
             }
@@ -278,10 +923,7 @@ fn handle_method(req: &JsonRpcRequest, state: Arc<State>, chain_id: u64) -> Json
                         JsonRpcResponse {
                             jsonrpc: "2.0".to_string(),
                             result: None,
-                            error: Some(JsonRpcError {
-                                code: -32001,
-                                message: format!("Invalid nonce: expected {}, got {}", expected_nonce, nonce),
-                            }),
+                            error: Some(nonce_error(expected_nonce, nonce)),
                             id: req.id.clone(),
                         }
                     } else {
@@ -306,10 +948,7 @@ fn handle_method(req: &JsonRpcRequest, state: Arc<State>, chain_id: u64) -> Json
                                 return JsonRpcResponse {
                                     jsonrpc: "2.0".to_string(),
                                     result: None,
-                                    error: Some(JsonRpcError {
-                                        code: -32602,
-                                        message: "Invalid signature length".to_string(),
-                                    }),
+                                    error: Some(invalid_param("signature", "Invalid signature length")),
                                     id: req.id.clone(),
                                 };
                             }
@@ -320,10 +959,7 @@ fn handle_method(req: &JsonRpcRequest, state: Arc<State>, chain_id: u64) -> Json
                                 return JsonRpcResponse {
                                     jsonrpc: "2.0".to_string(),
                                     result: None,
-                                    error: Some(JsonRpcError {
-                                        code: -32602,
-                                        message: "Invalid public key length".to_string(),
-                                    }),
+                                    error: Some(invalid_param("publicKey", "Invalid public key length")),
                                     id: req.id.clone(),
                                 };
                             }
@@ -349,6 +985,7 @@ fn handle_method(req: &JsonRpcRequest, state: Arc<State>, chain_id: u64) -> Json
                                         error: Some(JsonRpcError {
                                             code: -32000,
                                             message: e,
+                                            data: None,
                                         }),
                                         id: req.id.clone(),
                                     }
@@ -360,6 +997,7 @@ fn handle_method(req: &JsonRpcRequest, state: Arc<State>, chain_id: u64) -> Json
                                 error: Some(JsonRpcError {
                                     code: -32002,
                                     message: format!("Invalid signature: {}", e),
+                                    data: None,
                                 }),
                                 id: req.id.clone(),
                             }
@@ -372,6 +1010,7 @@ fn handle_method(req: &JsonRpcRequest, state: Arc<State>, chain_id: u64) -> Json
                     error: Some(JsonRpcError {
                         code: -32602,
                         message: "Invalid params (need: from, to, amount, nonce, signature[64 bytes], pubkey[32 bytes])".to_string(),
+                        data: None,
                     }),
                     id: req.id.clone(),
                 }
@@ -388,6 +1027,7 @@ fn handle_method(req: &JsonRpcRequest, state: Arc<State>, chain_id: u64) -> Json
                 error: Some(JsonRpcError {
                     code: -32603,
                     message: "Method disabled for security: Use merklith_sendSignedTransaction with pre-signed transactions instead".to_string(),
+                    data: None,
                 }),
                 id: req.id.clone(),
             }
@@ -416,6 +1056,7 @@ fn handle_method(req: &JsonRpcRequest, state: Arc<State>, chain_id: u64) -> Json
                     error: Some(JsonRpcError {
                         code: -32602,
                         message: "Signature required: params = [from, to, amount, nonce, signature, pubkey]".to_string(),
+                        data: None,
                     }),
                     id: req.id.clone(),
                 };
@@ -433,10 +1074,7 @@ fn handle_method(req: &JsonRpcRequest, state: Arc<State>, chain_id: u64) -> Json
                                     return JsonRpcResponse {
                                         jsonrpc: "2.0".to_string(),
                                         result: None,
-                                        error: Some(JsonRpcError {
-                                            code: -32001,
-                                            message: format!("Invalid nonce: expected {}, got {}", expected_nonce, nonce),
-                                        }),
+                                        error: Some(nonce_error(expected_nonce, nonce)),
                                         id: req.id.clone(),
                                     };
                                 }
@@ -465,10 +1103,7 @@ fn handle_method(req: &JsonRpcRequest, state: Arc<State>, chain_id: u64) -> Json
                                                 return JsonRpcResponse {
                                                     jsonrpc: "2.0".to_string(),
                                                     result: None,
-                                                    error: Some(JsonRpcError {
-                                                        code: -32602,
-                                                        message: "Invalid signature length".to_string(),
-                                                    }),
+                                                    error: Some(invalid_param("signature", "Invalid signature length")),
                                                     id: req.id.clone(),
                                                 };
                                             }
@@ -479,10 +1114,7 @@ fn handle_method(req: &JsonRpcRequest, state: Arc<State>, chain_id: u64) -> Json
                                                 return JsonRpcResponse {
                                                     jsonrpc: "2.0".to_string(),
                                                     result: None,
-                                                    error: Some(JsonRpcError {
-                                                        code: -32602,
-                                                        message: "Invalid public key length".to_string(),
-                                                    }),
+                                                    error: Some(invalid_param("publicKey", "Invalid public key length")),
                                                     id: req.id.clone(),
                                                 };
                                             }
@@ -497,6 +1129,7 @@ fn handle_method(req: &JsonRpcRequest, state: Arc<State>, chain_id: u64) -> Json
                                                     error: Some(JsonRpcError {
                                                         code: -32002,
                                                         message: format!("Invalid signature: {}", e),
+                                                        data: None,
                                                     }),
                                                     id: req.id.clone(),
                                                 };
@@ -510,6 +1143,7 @@ fn handle_method(req: &JsonRpcRequest, state: Arc<State>, chain_id: u64) -> Json
                                             error: Some(JsonRpcError {
                                                 code: -32602,
                                                 message: "Invalid signature or public key format".to_string(),
+                                                data: None,
                                             }),
                                             id: req.id.clone(),
                                         };
@@ -520,10 +1154,7 @@ fn handle_method(req: &JsonRpcRequest, state: Arc<State>, chain_id: u64) -> Json
                                 return JsonRpcResponse {
                                     jsonrpc: "2.0".to_string(),
                                     result: None,
-                                    error: Some(JsonRpcError {
-                                        code: -32602,
-                                        message: "Invalid nonce format".to_string(),
-                                    }),
+                                    error: Some(invalid_param("nonce", "Invalid nonce format")),
                                     id: req.id.clone(),
                                 };
                             }
@@ -548,6 +1179,7 @@ fn handle_method(req: &JsonRpcRequest, state: Arc<State>, chain_id: u64) -> Json
                                 error: Some(JsonRpcError {
                                     code: -32000,
                                     message: e,
+                                    data: None,
                                 }),
                                 id: req.id.clone(),
                             }
@@ -562,6 +1194,7 @@ fn handle_method(req: &JsonRpcRequest, state: Arc<State>, chain_id: u64) -> Json
                         error: Some(JsonRpcError {
                             code: -32602,
                             message: "Invalid params".to_string(),
+                            data: None,
                         }),
                         id: req.id.clone(),
                     }
@@ -592,7 +1225,7 @@ fn handle_method(req: &JsonRpcRequest, state: Arc<State>, chain_id: u64) -> Json
         
         "merklith_syncing" => JsonRpcResponse {
             jsonrpc: "2.0".to_string(),
-            result: Some(Value::Bool(false)),
+            result: Some(syncing_to_json(&state, sync)),
             error: None,
             id: req.id.clone(),
         },
@@ -615,10 +1248,15 @@ fn handle_method(req: &JsonRpcRequest, state: Arc<State>, chain_id: u64) -> Json
         },
         
         "merklith_getBlockByNumber" => {
-            let block_num = req.params.first()
-                .and_then(|v| v.as_str())
-                .and_then(|s| if s == "latest" { Some(state.block_number()) } else { u64::from_str_radix(s.trim_start_matches("0x"), 16).ok() })
-                .unwrap_or(state.block_number());
+            let block_num = match BlockParam::from_value(req.params.first().unwrap_or(&Value::Null), &state) {
+                Ok(number) => number,
+                Err(e) => return JsonRpcResponse {
+                    jsonrpc: "2.0".to_string(),
+                    result: None,
+                    error: Some(e),
+                    id: req.id.clone(),
+                },
+            };
             
             match state.get_block(block_num) {
                 Some(block) => {
@@ -628,8 +1266,8 @@ fn handle_method(req: &JsonRpcRequest, state: Arc<State>, chain_id: u64) -> Json
                         "parentHash": format!("0x{}", hex::encode(block.parent_hash)),
                         "nonce": "0x0000000000000000",
                         "transactions": [],
-                        "gasLimit": "0x1c9c380",
-                        "gasUsed": "0x0",
+                        "gasLimit": format!("0x{:x}", block.gas_limit),
+                        "gasUsed": format!("0x{:x}", block.gas_used),
                         "timestamp": format!("0x{:x}", block.timestamp),
                     });
                     JsonRpcResponse {
@@ -685,10 +1323,15 @@ fn handle_method(req: &JsonRpcRequest, state: Arc<State>, chain_id: u64) -> Json
         },
         
         "merklith_getBlockInfo" => {
-            let block_num = req.params.first()
-                .and_then(|v| v.as_str())
-                .and_then(|s| if s == "latest" { Some(state.block_number()) } else { u64::from_str_radix(s.trim_start_matches("0x"), 16).ok() })
-                .unwrap_or(state.block_number());
+            let block_num = match BlockParam::from_value(req.params.first().unwrap_or(&Value::Null), &state) {
+                Ok(number) => number,
+                Err(e) => return JsonRpcResponse {
+                    jsonrpc: "2.0".to_string(),
+                    result: None,
+                    error: Some(e),
+                    id: req.id.clone(),
+                },
+            };
             
             match state.get_block(block_num) {
                 Some(block) => {
@@ -712,6 +1355,7 @@ fn handle_method(req: &JsonRpcRequest, state: Arc<State>, chain_id: u64) -> Json
                     error: Some(JsonRpcError {
                         code: -32001,
                         message: format!("Block {} not found", block_num),
+                        data: None,
                     }),
                     id: req.id.clone(),
                 }
@@ -764,6 +1408,10 @@ fn handle_method(req: &JsonRpcRequest, state: Arc<State>, chain_id: u64) -> Json
                 "blockNumber": format!("0x{:x}", block_number),
                 "blockHash": format!("0x{}", hex::encode(block_hash.as_bytes())),
                 "accounts": state.all_accounts().len(),
+                "totalSupply": format!("{:x}", state.total_supply()),
+                "gasLimit": state.get_block(block_number).map(|block| format!("0x{:x}", block.gas_limit)),
+                "nextGasLimit": format!("0x{:x}", state.next_gas_limit()),
+                "peerCount": peers.map_or(0, |p| p.connected_peers()),
             });
             
             JsonRpcResponse {
@@ -774,18 +1422,178 @@ fn handle_method(req: &JsonRpcRequest, state: Arc<State>, chain_id: u64) -> Json
             }
         },
         
-        "merklith_createAttestation" => {
-            let private_key_str = req.params.get(0).and_then(|v| v.as_str()).unwrap_or("");
-            let block_num_str = req.params.get(1).and_then(|v| v.as_str()).unwrap_or("0");
+        "merklith_getPeers" => {
+            let result: Vec<Value> = peers
+                .map(|p| p.peers())
+                .unwrap_or_default()
+                .into_iter()
+                .map(|peer| serde_json::json!({ "id": peer.id, "address": peer.address }))
+                .collect();
+            JsonRpcResponse {
+                jsonrpc: "2.0".to_string(),
+                result: Some(Value::Array(result)),
+                error: None,
+                id: req.id.clone(),
+            }
+        },
+
+        "merklith_getContribution" => {
+            let addr_str = req.params.first()
+                .and_then(|v| v.as_str())
+                .unwrap_or("");
+            match parse_address(addr_str) {
+                Ok(addr) => {
+                    // Addresses without contributions get zeros and rank 0
+                    let (score, rank) = contributions
+                        .map(|tracker| {
+                            let snapshot = tracker.read().snapshot_rankings();
+                            snapshot.entries
+                                .into_iter()
+                                .find(|entry| entry.address == addr)
+                                .map(|entry| (entry.score, entry.rank))
+                                .unwrap_or_default()
+                        })
+                        .unwrap_or_default();
+                    JsonRpcResponse {
+                        jsonrpc: "2.0".to_string(),
+                        result: Some(contribution_to_json(&addr, &score, rank)),
+                        error: None,
+                        id: req.id.clone(),
+                    }
+                }
+                Err(_) => JsonRpcResponse {
+                    jsonrpc: "2.0".to_string(),
+                    result: None,
+                    error: Some(invalid_param("address", "Invalid address")),
+                    id: req.id.clone(),
+                },
+            }
+        },
+
+        "merklith_getTopContributors" => {
+            let n = req.params.first()
+                .and_then(|v| v.as_u64().or_else(|| v.as_str().and_then(|s| parse_u64(s).ok())))
+                .unwrap_or(10)
+                .min(100) as usize;
+            let leaderboard: Vec<Value> = contributions
+                .map(|tracker| tracker.read().snapshot_rankings().entries)
+                .unwrap_or_default()
+                .iter()
+                .take(n)
+                .map(|entry| contribution_to_json(&entry.address, &entry.score, entry.rank))
+                .collect();
+            JsonRpcResponse {
+                jsonrpc: "2.0".to_string(),
+                result: Some(Value::Array(leaderboard)),
+                error: None,
+                id: req.id.clone(),
+            }
+        },
+
+        "merklith_getTotalSupply" => {
+            let result = serde_json::json!({
+                "totalSupply": format!("{:x}", state.total_supply()),
+                "burned": format!("{:x}", state.total_burned()),
+            });
             
-            let pk_hex = private_key_str.strip_prefix("0x").unwrap_or(private_key_str);
+            JsonRpcResponse {
+                jsonrpc: "2.0".to_string(),
+                result: Some(result),
+                error: None,
+                id: req.id.clone(),
+            }
+        },
+        
+        "merklith_sweepDust" => {
+            // Admin only: params = [threshold, beneficiary]
+            let threshold_str = req.params.first().and_then(|v| v.as_str()).unwrap_or("");
+            let beneficiary_str = req.params.get(1).and_then(|v| v.as_str()).unwrap_or("");
             
-            match (hex::decode(pk_hex), parse_u64(block_num_str)) {
-                (Ok(pk_bytes), Ok(block_num)) if pk_bytes.len() == 32 => {
-                    match state.get_block(block_num) {
-                        Some(block) => {
-                            use merklith_crypto::Keypair;
-                            use merklith_crypto::bls::BLSKeypair;
+            match (parse_u256(threshold_str), parse_address(beneficiary_str)) {
+                (Ok(threshold), Ok(beneficiary)) => match state.sweep_dust(threshold, &beneficiary) {
+                    Ok((swept, collected)) => JsonRpcResponse {
+                        jsonrpc: "2.0".to_string(),
+                        result: Some(serde_json::json!({
+                            "swept": swept,
+                            "collected": format!("{:x}", collected),
+                        })),
+                        error: None,
+                        id: req.id.clone(),
+                    },
+                    Err(e) => JsonRpcResponse {
+                        jsonrpc: "2.0".to_string(),
+                        result: None,
+                        error: Some(JsonRpcError {
+                            code: -32000,
+                            message: e,
+                            data: None,
+                        }),
+                        id: req.id.clone(),
+                    },
+                },
+                _ => JsonRpcResponse {
+                    jsonrpc: "2.0".to_string(),
+                    result: None,
+                    error: Some(invalid_param("threshold, beneficiary", "Invalid params: expected [threshold, beneficiary]")),
+                    id: req.id.clone(),
+                },
+            }
+        },
+        
+        "merklith_getChainConfig" => JsonRpcResponse {
+            jsonrpc: "2.0".to_string(),
+            result: Some(serde_json::to_value(&state.genesis().chain_config).unwrap()),
+            error: None,
+            id: req.id.clone(),
+        },
+        
+        "merklith_getGenesis" => {
+            let genesis = state.genesis();
+            let alloc: Vec<_> = genesis.alloc.iter()
+                .map(|a| serde_json::json!({
+                    "address": format!("0x{}", hex::encode(a.address)),
+                    "balance": format!("{:x}", a.balance),
+                    "code": a.code.as_ref().map(|c| format!("0x{}", hex::encode(c))),
+                }))
+                .collect();
+            let validators: Vec<_> = genesis.validators.iter()
+                .map(|v| serde_json::json!({
+                    "address": format!("0x{}", hex::encode(v.address)),
+                    "stake": format!("{:x}", v.stake),
+                    "blsPublicKey": format!("0x{}", hex::encode(v.bls_public_key.as_bytes())),
+                    "ed25519PublicKey": format!("0x{}", hex::encode(v.ed25519_public_key.as_bytes())),
+                }))
+                .collect();
+            
+            let result = serde_json::json!({
+                "hash": format!("0x{}", hex::encode(state.genesis_hash().as_bytes())),
+                "chainId": format!("0x{:x}", genesis.chain_config.chain_id),
+                "timestamp": format!("0x{:x}", genesis.timestamp),
+                "extraData": format!("0x{}", hex::encode(&genesis.extra_data)),
+                "alloc": alloc,
+                "validators": validators,
+            });
+            
+            JsonRpcResponse {
+                jsonrpc: "2.0".to_string(),
+                result: Some(result),
+                error: None,
+                id: req.id.clone(),
+            }
+        },
+        
+        "merklith_createAttestation" => {
+            let private_key_str = req.params.get(0).and_then(|v| v.as_str()).unwrap_or("");
+            let block_num_str = req.params.get(1).and_then(|v| v.as_str()).unwrap_or("0");
+            
+            let pk_hex = private_key_str.strip_prefix("0x").unwrap_or(private_key_str);
+            
+            match (hex::decode(pk_hex), parse_u64(block_num_str)) {
+                (Ok(pk_bytes), Ok(block_num)) if pk_bytes.len() == 32 => {
+                    match state.get_block(block_num) {
+                        Some(block) => {
+                            use merklith_crypto::Keypair;
+                            use merklith_crypto::bls::BLSKeypair;
                             
                             let ed_keypair = Keypair::from_seed(&pk_bytes.as_slice().try_into().unwrap());
                             let attester = ed_keypair.address();
@@ -799,6 +1607,7 @@ fn handle_method(req: &JsonRpcRequest, state: Arc<State>, chain_id: u64) -> Json
                                     error: Some(JsonRpcError {
                                         code: -32003,
                                         message: format!("BLS key error: {}", e),
+                                        data: None,
                                     }),
                                     id: req.id.clone(),
                                 }
@@ -830,6 +1639,7 @@ fn handle_method(req: &JsonRpcRequest, state: Arc<State>, chain_id: u64) -> Json
                             error: Some(JsonRpcError {
                                 code: -32001,
                                 message: format!("Block {} not found", block_num),
+                                data: None,
                             }),
                             id: req.id.clone(),
                         }
@@ -841,6 +1651,7 @@ fn handle_method(req: &JsonRpcRequest, state: Arc<State>, chain_id: u64) -> Json
                     error: Some(JsonRpcError {
                         code: -32602,
                         message: "Invalid params (need: privateKey[32 bytes], blockNumber)".to_string(),
+                        data: None,
                     }),
                     id: req.id.clone(),
                 }
@@ -857,10 +1668,7 @@ fn handle_method(req: &JsonRpcRequest, state: Arc<State>, chain_id: u64) -> Json
                 return JsonRpcResponse {
                     jsonrpc: "2.0".to_string(),
                     result: None,
-                    error: Some(JsonRpcError {
-                        code: -32602,
-                        message: "Bytecode exceeds maximum size of 24KB (EIP-170)".to_string(),
-                    }),
+                    error: Some(invalid_param("bytecode", "Bytecode exceeds maximum size of 24KB (EIP-170)")),
                     id: req.id.clone(),
                 };
             }
@@ -871,10 +1679,7 @@ fn handle_method(req: &JsonRpcRequest, state: Arc<State>, chain_id: u64) -> Json
                     Err(_) => return JsonRpcResponse {
                         jsonrpc: "2.0".to_string(),
                         result: None,
-                        error: Some(JsonRpcError {
-                            code: -32602,
-                            message: "Invalid bytecode".to_string(),
-                        }),
+                        error: Some(invalid_param("bytecode", "Invalid bytecode")),
                         id: req.id.clone(),
                     }
                 }
@@ -899,6 +1704,7 @@ fn handle_method(req: &JsonRpcRequest, state: Arc<State>, chain_id: u64) -> Json
                             error: Some(JsonRpcError {
                                 code: -32000,
                                 message: e,
+                                data: None,
                             }),
                             id: req.id.clone(),
                         }
@@ -910,6 +1716,7 @@ fn handle_method(req: &JsonRpcRequest, state: Arc<State>, chain_id: u64) -> Json
                     error: Some(JsonRpcError {
                         code: -32602,
                         message: "Invalid address".to_string(),
+                        data: None,
                     }),
                     id: req.id.clone(),
                 }
@@ -937,6 +1744,7 @@ fn handle_method(req: &JsonRpcRequest, state: Arc<State>, chain_id: u64) -> Json
                     error: Some(JsonRpcError {
                         code: -32602,
                         message: "Invalid address".to_string(),
+                        data: None,
                     }),
                     id: req.id.clone(),
                 }
@@ -963,6 +1771,7 @@ fn handle_method(req: &JsonRpcRequest, state: Arc<State>, chain_id: u64) -> Json
                     error: Some(JsonRpcError {
                         code: -32602,
                         message: "Invalid params".to_string(),
+                        data: None,
                     }),
                     id: req.id.clone(),
                 }
@@ -979,10 +1788,7 @@ fn handle_method(req: &JsonRpcRequest, state: Arc<State>, chain_id: u64) -> Json
                 return JsonRpcResponse {
                     jsonrpc: "2.0".to_string(),
                     result: None,
-                    error: Some(JsonRpcError {
-                        code: -32602,
-                        message: "Call data exceeds maximum size of 128KB".to_string(),
-                    }),
+                    error: Some(invalid_param("data", "Call data exceeds maximum size of 128KB")),
                     id: req.id.clone(),
                 };
             }
@@ -997,7 +1803,7 @@ fn handle_method(req: &JsonRpcRequest, state: Arc<State>, chain_id: u64) -> Json
                     };
                     
                     // Execute in VM
-                    match execute_contract(&code, &input) {
+                    match execute_contract(vm, &code, &input) {
                         Ok(result) => JsonRpcResponse {
                             jsonrpc: "2.0".to_string(),
                             result: Some(Value::String(format!("0x{}", hex::encode(&result)))),
@@ -1007,10 +1813,7 @@ fn handle_method(req: &JsonRpcRequest, state: Arc<State>, chain_id: u64) -> Json
                         Err(e) => JsonRpcResponse {
                             jsonrpc: "2.0".to_string(),
                             result: None,
-                            error: Some(JsonRpcError {
-                                code: -32000,
-                                message: e,
-                            }),
+                            error: Some(e),
                             id: req.id.clone(),
                         }
                     }
@@ -1018,12 +1821,118 @@ fn handle_method(req: &JsonRpcRequest, state: Arc<State>, chain_id: u64) -> Json
                 Err(_) => JsonRpcResponse {
                     jsonrpc: "2.0".to_string(),
                     result: None,
-                    error: Some(JsonRpcError {
-                        code: -32602,
-                        message: "Invalid address".to_string(),
-                    }),
+                    error: Some(invalid_param("to", "Invalid address")),
+                    id: req.id.clone(),
+                }
+            }
+        },
+        
+        "merklith_multicall" => {
+            // params = [[{to, data}, ...], requireSuccess]
+            const MAX_MULTICALL_CALLS: usize = 100;
+            let calls = req.params.first().and_then(|v| v.as_array()).cloned().unwrap_or_default();
+            let require_success = req.params.get(1).and_then(|v| v.as_bool()).unwrap_or(false);
+            
+            if calls.is_empty() || calls.len() > MAX_MULTICALL_CALLS {
+                return JsonRpcResponse {
+                    jsonrpc: "2.0".to_string(),
+                    result: None,
+                    error: Some(invalid_param("calls", format!("Expected between 1 and {} calls", MAX_MULTICALL_CALLS))),
                     id: req.id.clone(),
+                };
+            }
+            
+            let mut targets = Vec::with_capacity(calls.len());
+            let mut inputs = Vec::with_capacity(calls.len());
+            for (i, call) in calls.iter().enumerate() {
+                let to_str = call.get("to").and_then(|v| v.as_str()).unwrap_or("");
+                let data_str = call.get("data").and_then(|v| v.as_str()).unwrap_or("0x");
+                match (parse_address(to_str), hex::decode(data_str.strip_prefix("0x").unwrap_or(data_str))) {
+                    (Ok(to), Ok(input)) => {
+                        targets.push(to);
+                        inputs.push(input);
+                    }
+                    _ => {
+                        return JsonRpcResponse {
+                            jsonrpc: "2.0".to_string(),
+                            result: None,
+                            error: Some(invalid_param("calls", format!("Invalid call at index {}", i))),
+                            id: req.id.clone(),
+                        };
+                    }
+                }
+            }
+            
+            // Every call reads code from the same block
+            let (block_number, codes) = state.get_codes(&targets);
+            let mut results = Vec::with_capacity(calls.len());
+            for (i, (code, input)) in codes.iter().zip(&inputs).enumerate() {
+                match execute_contract(vm, code, input) {
+                    Ok(data) => results.push(serde_json::json!({
+                        "success": true,
+                        "returnData": format!("0x{}", hex::encode(&data)),
+                    })),
+                    Err(mut e) if require_success => {
+                        e.message = format!("Call {} failed: {}", i, e.message);
+                        return JsonRpcResponse {
+                            jsonrpc: "2.0".to_string(),
+                            result: None,
+                            error: Some(e),
+                            id: req.id.clone(),
+                        };
+                    }
+                    Err(e) => results.push(serde_json::json!({
+                        "success": false,
+                        "returnData": e.data.unwrap_or_else(|| Value::String("0x".to_string())),
+                    })),
+                }
+            }
+            
+            tracing::debug!("Multicall of {} calls at block {}", results.len(), block_number);
+            JsonRpcResponse {
+                jsonrpc: "2.0".to_string(),
+                result: Some(Value::Array(results)),
+                error: None,
+                id: req.id.clone(),
+            }
+        },
+        
+        "merklith_simulatePending" => {
+            // params: [rawTransaction] - run on top of pooled transactions, unlike eth_call
+            let raw_tx = req.params.first().and_then(|v| v.as_str()).unwrap_or("");
+            match decode_raw_transaction(raw_tx, chain_id, max_tx_size(pool)) {
+                Ok(signed_tx) => {
+                    let pending = pool.map(|pool| pool.get_pending(usize::MAX)).unwrap_or_default();
+                    let outcome = state.simulate_pending(&pending, &signed_tx);
+                    JsonRpcResponse {
+                        jsonrpc: "2.0".to_string(),
+                        result: Some(serde_json::json!({
+                            "success": outcome.success,
+                            "gasUsed": format!("0x{:x}", outcome.gas_used),
+                            "error": outcome.error,
+                            "blockNumber": format!("0x{:x}", outcome.block_number),
+                        })),
+                        error: None,
+                        id: req.id.clone(),
+                    }
                 }
+                Err(e) => JsonRpcResponse {
+                    jsonrpc: "2.0".to_string(),
+                    result: None,
+                    error: Some(e),
+                    id: req.id.clone(),
+                },
+            }
+        },
+        
+        "merklith_exportMempool" => {
+            // Pooled and queued transactions as raw hex, for re-import after a restart
+            let exported = export_mempool(&state, pool);
+            JsonRpcResponse {
+                jsonrpc: "2.0".to_string(),
+                result: Some(serde_json::json!(exported)),
+                error: None,
+                id: req.id.clone(),
             }
         },
         
@@ -1067,7 +1976,7 @@ fn handle_method(req: &JsonRpcRequest, state: Arc<State>, chain_id: u64) -> Json
 
         "eth_syncing" => JsonRpcResponse {
             jsonrpc: "2.0".to_string(),
-            result: Some(Value::Bool(false)),
+            result: Some(syncing_to_json(&state, sync)),
             error: None,
             id: req.id.clone(),
         },
@@ -1122,38 +2031,58 @@ fn handle_method(req: &JsonRpcRequest, state: Arc<State>, chain_id: u64) -> Json
         // --- Account Methods ---
 
         "eth_getBalance" => {
-            // params: [address, block_tag] - block_tag ignored
+            // params: [address, block]
             let addr_str = req.params.first()
                 .and_then(|v| v.as_str())
                 .unwrap_or("");
-            let balance = if let Ok(addr) = parse_address(addr_str) {
-                state.balance(&addr)
-            } else {
-                U256::ZERO
-            };
-            JsonRpcResponse {
-                jsonrpc: "2.0".to_string(),
-                result: Some(Value::String(format!("{:x}", balance))),
-                error: None,
-                id: req.id.clone(),
+            let balance = resolve_block(req.params.get(1), &state)
+                .and_then(|number| require_head(number, &state))
+                .map(|()| match parse_address(addr_str) {
+                    Ok(addr) => state.balance(&addr),
+                    Err(_) => U256::ZERO,
+                });
+            match balance {
+                Ok(balance) => JsonRpcResponse {
+                    jsonrpc: "2.0".to_string(),
+                    result: Some(Value::String(format!("{:x}", balance))),
+                    error: None,
+                    id: req.id.clone(),
+                },
+                Err(e) => JsonRpcResponse {
+                    jsonrpc: "2.0".to_string(),
+                    result: None,
+                    error: Some(e),
+                    id: req.id.clone(),
+                },
             }
         },
 
         "eth_getTransactionCount" => {
-            // params: [address, block_tag] - block_tag ignored
+            // params: [address, block] - "pending" counts pooled transactions
             let addr_str = req.params.first()
                 .and_then(|v| v.as_str())
                 .unwrap_or("");
-            let nonce = if let Ok(addr) = parse_address(addr_str) {
-                state.nonce(&addr)
-            } else {
-                0
-            };
-            JsonRpcResponse {
-                jsonrpc: "2.0".to_string(),
-                result: Some(Value::String(format!("0x{:x}", nonce))),
-                error: None,
-                id: req.id.clone(),
+            let pending = req.params.get(1).and_then(|v| v.as_str()) == Some("pending");
+            let nonce = resolve_block(req.params.get(1), &state)
+                .and_then(|number| require_head(number, &state))
+                .map(|()| match parse_address(addr_str) {
+                    Ok(addr) if pending => pending_nonce(&state, pool, &addr),
+                    Ok(addr) => state.nonce(&addr),
+                    Err(_) => 0,
+                });
+            match nonce {
+                Ok(nonce) => JsonRpcResponse {
+                    jsonrpc: "2.0".to_string(),
+                    result: Some(Value::String(format!("0x{:x}", nonce))),
+                    error: None,
+                    id: req.id.clone(),
+                },
+                Err(e) => JsonRpcResponse {
+                    jsonrpc: "2.0".to_string(),
+                    result: None,
+                    error: Some(e),
+                    id: req.id.clone(),
+                },
             }
         },
 
@@ -1171,35 +2100,49 @@ fn handle_method(req: &JsonRpcRequest, state: Arc<State>, chain_id: u64) -> Json
         },
 
         "eth_getCode" => {
-            // params: [address, block_tag] - block_tag ignored
+            // params: [address, block] - past blocks read the code at that block
             let addr_str = req.params.first()
                 .and_then(|v| v.as_str())
                 .unwrap_or("");
-            match parse_address(addr_str) {
-                Ok(addr) => {
-                    let code = state.get_code(&addr);
-                    JsonRpcResponse {
-                        jsonrpc: "2.0".to_string(),
-                        result: Some(Value::String(format!("0x{}", hex::encode(&code)))),
-                        error: None,
-                        id: req.id.clone(),
-                    }
-                }
-                Err(_) => JsonRpcResponse {
+            let head = state.block_number();
+            let code = match (parse_address(addr_str), resolve_block(req.params.get(1), &state)) {
+                (_, Err(e)) => Err(e),
+                (Err(_), _) => Ok(Vec::new()),
+                (Ok(addr), Ok(number)) if number == head => Ok(state.get_code(&addr)),
+                (Ok(addr), Ok(number)) => state
+                    .code_at(&addr, number)
+                    .map_err(|e| JsonRpcError { code: -32000, message: e.to_string(), data: None }),
+            };
+            match code {
+                Ok(code) => JsonRpcResponse {
                     jsonrpc: "2.0".to_string(),
-                    result: Some(Value::String("0x".to_string())),
+                    result: Some(Value::String(format!("0x{}", hex::encode(&code)))),
                     error: None,
                     id: req.id.clone(),
-                }
+                },
+                Err(e) => JsonRpcResponse {
+                    jsonrpc: "2.0".to_string(),
+                    result: None,
+                    error: Some(e),
+                    id: req.id.clone(),
+                },
             }
         },
 
         "eth_getStorageAt" => {
-            // params: [address, slot, block_tag] - block_tag ignored
+            // params: [address, slot, block]
             let addr_str = req.params.get(0).and_then(|v| v.as_str()).unwrap_or("");
             let key_str = req.params.get(1).and_then(|v| v.as_str()).unwrap_or("0x0000000000000000000000000000000000000000000000000000000000000000");
-            match (parse_address(addr_str), parse_bytes32(key_str)) {
-                (Ok(addr), Ok(key)) => {
+            let block = resolve_block(req.params.get(2), &state)
+                .and_then(|number| require_head(number, &state));
+            match (block, parse_address(addr_str), parse_bytes32(key_str)) {
+                (Err(e), _, _) => JsonRpcResponse {
+                    jsonrpc: "2.0".to_string(),
+                    result: None,
+                    error: Some(e),
+                    id: req.id.clone(),
+                },
+                (Ok(()), Ok(addr), Ok(key)) => {
                     let value = state.get_storage(&addr, key).unwrap_or([0u8; 32]);
                     JsonRpcResponse {
                         jsonrpc: "2.0".to_string(),
@@ -1221,12 +2164,15 @@ fn handle_method(req: &JsonRpcRequest, state: Arc<State>, chain_id: u64) -> Json
 
         "eth_getBlockByNumber" => {
             // params: [block_number, full_transactions]
-            let block_num = req.params.first()
-                .and_then(|v| v.as_str())
-                .and_then(|s| if s == "latest" || s == "pending" { Some(state.block_number()) }
-                          else if s == "earliest" { Some(0) }
-                          else { u64::from_str_radix(s.trim_start_matches("0x"), 16).ok() })
-                .unwrap_or(state.block_number());
+            let block_num = match BlockParam::from_value(req.params.first().unwrap_or(&Value::Null), &state) {
+                Ok(number) => number,
+                Err(e) => return JsonRpcResponse {
+                    jsonrpc: "2.0".to_string(),
+                    result: None,
+                    error: Some(e),
+                    id: req.id.clone(),
+                },
+            };
 
             match state.get_block(block_num) {
                 Some(block) => {
@@ -1237,15 +2183,15 @@ fn handle_method(req: &JsonRpcRequest, state: Arc<State>, chain_id: u64) -> Json
                         "nonce": "0x0000000000000000",
                         "sha3Uncles": "0x1dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d49347",
                         "logsBloom": format!("0x{}", "00".repeat(256)),
-                        "transactionsRoot": "0x56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421",
-                        "stateRoot": "0x0000000000000000000000000000000000000000000000000000000000000000",
-                        "miner": "0x0000000000000000000000000000000000000000",
+                        "transactionsRoot": format!("0x{}", hex::encode(block.transactions_root)),
+                        "stateRoot": format!("0x{}", hex::encode(block.state_root)),
+                        "miner": format!("0x{}", block.proposer.to_hex()),
                         "difficulty": "0x0",
                         "totalDifficulty": "0x0",
                         "extraData": "0x",
                         "size": "0x3e8",
-                        "gasLimit": "0x1c9c380",
-                        "gasUsed": "0x0",
+                        "gasLimit": format!("0x{:x}", block.gas_limit),
+                        "gasUsed": format!("0x{:x}", block.gas_used),
                         "timestamp": format!("0x{:x}", block.timestamp),
                         "transactions": [],
                         "uncles": []
@@ -1284,10 +2230,15 @@ fn handle_method(req: &JsonRpcRequest, state: Arc<State>, chain_id: u64) -> Json
         },
 
         "eth_getBlockTransactionCountByNumber" => {
-            let block_num = req.params.first()
-                .and_then(|v| v.as_str())
-                .and_then(|s| if s == "latest" { Some(state.block_number()) } else { u64::from_str_radix(s.trim_start_matches("0x"), 16).ok() })
-                .unwrap_or(state.block_number());
+            let block_num = match BlockParam::from_value(req.params.first().unwrap_or(&Value::Null), &state) {
+                Ok(number) => number,
+                Err(e) => return JsonRpcResponse {
+                    jsonrpc: "2.0".to_string(),
+                    result: None,
+                    error: Some(e),
+                    id: req.id.clone(),
+                },
+            };
             let tx_count = state.get_block(block_num).map(|b| b.tx_count).unwrap_or(0);
             JsonRpcResponse {
                 jsonrpc: "2.0".to_string(),
@@ -1325,10 +2276,7 @@ fn handle_method(req: &JsonRpcRequest, state: Arc<State>, chain_id: u64) -> Json
                         return JsonRpcResponse {
                             jsonrpc: "2.0".to_string(),
                             result: None,
-                            error: Some(JsonRpcError {
-                                code: -32001,
-                                message: format!("Invalid nonce: expected {}, got {}", expected_nonce, nonce),
-                            }),
+                            error: Some(nonce_error(expected_nonce, nonce)),
                             id: req.id.clone(),
                         };
                     }
@@ -1341,6 +2289,7 @@ fn handle_method(req: &JsonRpcRequest, state: Arc<State>, chain_id: u64) -> Json
                             error: Some(JsonRpcError {
                                 code: -32602,
                                 message: "Signature required: provide 'signature' and 'publicKey' in transaction object".to_string(),
+                                data: None,
                             }),
                             id: req.id.clone(),
                         };
@@ -1370,10 +2319,7 @@ fn handle_method(req: &JsonRpcRequest, state: Arc<State>, chain_id: u64) -> Json
                                         return JsonRpcResponse {
                                             jsonrpc: "2.0".to_string(),
                                             result: None,
-                                            error: Some(JsonRpcError {
-                                                code: -32602,
-                                                message: "Invalid signature length".to_string(),
-                                            }),
+                                            error: Some(invalid_param("signature", "Invalid signature length")),
                                             id: req.id.clone(),
                                         };
                                     }
@@ -1384,10 +2330,7 @@ fn handle_method(req: &JsonRpcRequest, state: Arc<State>, chain_id: u64) -> Json
                                         return JsonRpcResponse {
                                             jsonrpc: "2.0".to_string(),
                                             result: None,
-                                            error: Some(JsonRpcError {
-                                                code: -32602,
-                                                message: "Invalid public key length".to_string(),
-                                            }),
+                                            error: Some(invalid_param("publicKey", "Invalid public key length")),
                                             id: req.id.clone(),
                                         };
                                     }
@@ -1400,6 +2343,7 @@ fn handle_method(req: &JsonRpcRequest, state: Arc<State>, chain_id: u64) -> Json
                                         error: Some(JsonRpcError {
                                             code: -32002,
                                             message: format!("Invalid signature: {}", e),
+                                            data: None,
                                         }),
                                         id: req.id.clone(),
                                     };
@@ -1412,6 +2356,7 @@ fn handle_method(req: &JsonRpcRequest, state: Arc<State>, chain_id: u64) -> Json
                                     error: Some(JsonRpcError {
                                         code: -32002,
                                         message: "Invalid signature or public key format".to_string(),
+                                        data: None,
                                     }),
                                     id: req.id.clone(),
                                 };
@@ -1434,6 +2379,7 @@ fn handle_method(req: &JsonRpcRequest, state: Arc<State>, chain_id: u64) -> Json
                             error: Some(JsonRpcError {
                                 code: -32000,
                                 message: e,
+                                data: None,
                             }),
                             id: req.id.clone(),
                         }
@@ -1445,15 +2391,34 @@ fn handle_method(req: &JsonRpcRequest, state: Arc<State>, chain_id: u64) -> Json
                     error: Some(JsonRpcError {
                         code: -32602,
                         message: "Invalid params".to_string(),
+                        data: None,
                     }),
                     id: req.id.clone(),
                 }
             }
         },
 
+        "merklith_sendSponsoredTransaction" => {
+            let raw_tx = req.params.first().and_then(|v| v.as_str()).unwrap_or("");
+            match process_sponsored_transaction(raw_tx, &state, pool, chain_id) {
+                Ok(hash) => JsonRpcResponse {
+                    jsonrpc: "2.0".to_string(),
+                    result: Some(Value::String(format!("0x{}", hex::encode(hash.as_bytes())))),
+                    error: None,
+                    id: req.id.clone(),
+                },
+                Err(e) => JsonRpcResponse {
+                    jsonrpc: "2.0".to_string(),
+                    result: None,
+                    error: Some(e),
+                    id: req.id.clone(),
+                },
+            }
+        },
+
         "eth_sendRawTransaction" => {
             let raw_tx = req.params.first().and_then(|v| v.as_str()).unwrap_or("");
-            match process_raw_transaction(raw_tx, &state, chain_id) {
+            match process_raw_transaction(raw_tx, &state, pool, chain_id) {
                 Ok(hash) => JsonRpcResponse {
                     jsonrpc: "2.0".to_string(),
                     result: Some(Value::String(format!("0x{}", hex::encode(hash.as_bytes())))),
@@ -1463,32 +2428,29 @@ fn handle_method(req: &JsonRpcRequest, state: Arc<State>, chain_id: u64) -> Json
                 Err(e) => JsonRpcResponse {
                     jsonrpc: "2.0".to_string(),
                     result: None,
-                    error: Some(JsonRpcError {
-                        code: -32602,
-                        message: e,
-                    }),
+                    error: Some(e),
                     id: req.id.clone(),
                 },
             }
         },
 
         "eth_getTransactionByHash" => {
-            let tx_hash = req.params.first()
-                .and_then(|v| v.as_str())
-                .unwrap_or("");
-            let result = serde_json::json!({
-                "hash": tx_hash,
-                "nonce": "0x0",
-                "blockHash": null,
-                "blockNumber": null,
-                "transactionIndex": "0x0",
-                "from": "0x0000000000000000000000000000000000000000",
-                "to": "0x0000000000000000000000000000000000000000",
-                "value": "0x0",
-                "gas": "0x5208",
-                "gasPrice": "0x3b9aca00",
-                "input": "0x"
-            });
+            // Pending transactions come from the pool, mined ones from stored blocks
+            let result = match req.params.first().and_then(|v| v.as_str()).map(merklith_types::Hash::from_str) {
+                Some(Ok(hash)) => {
+                    let pending = pool.and_then(|pool| pool.get_transaction(&hash.to_string()));
+                    match pending {
+                        Some(tx) => transaction_to_json(&tx, None),
+                        None => state
+                            .mined_transaction(&hash)
+                            .and_then(|(number, index, tx)| {
+                                state.get_block(number).map(|block| transaction_to_json(&tx, Some((&block, index))))
+                            })
+                            .unwrap_or(Value::Null),
+                    }
+                }
+                _ => Value::Null,
+            };
             JsonRpcResponse {
                 jsonrpc: "2.0".to_string(),
                 result: Some(result),
@@ -1497,6 +2459,29 @@ fn handle_method(req: &JsonRpcRequest, state: Arc<State>, chain_id: u64) -> Json
             }
         },
 
+        "merklith_txStatus" => {
+            // params: [hash] - pool position and inclusion estimate for pending transactions
+            let (status, position) = match req.params.first().and_then(|v| v.as_str()).map(merklith_types::Hash::from_str) {
+                Some(Ok(hash)) => match pool.and_then(|pool| pool.position(&hash.to_string())) {
+                    Some(position) => ("pending", Some(position)),
+                    None if state.is_queued(&hash) => ("queued", None),
+                    None if state.mined_transaction(&hash).is_some() => ("mined", None),
+                    None => ("unknown", None),
+                },
+                _ => ("unknown", None),
+            };
+            JsonRpcResponse {
+                jsonrpc: "2.0".to_string(),
+                result: Some(serde_json::json!({
+                    "status": status,
+                    "position": position.map(|p| p.rank),
+                    "estimatedBlocks": position.map(|p| p.estimated_blocks),
+                })),
+                error: None,
+                id: req.id.clone(),
+            }
+        },
+
         "eth_getTransactionReceipt" => {
             let tx_hash = req.params.first()
                 .and_then(|v| v.as_str())
@@ -1523,10 +2508,40 @@ fn handle_method(req: &JsonRpcRequest, state: Arc<State>, chain_id: u64) -> Json
             }
         },
 
-        // --- Contract Methods ---
-
-        "eth_call" => {
-            // params: [{to, data, ...}, block_tag]
+        "eth_getBlockReceipts" | "merklith_getBlockReceipts" => {
+            // params: [block_tag]
+            let block_num = match BlockParam::from_value(req.params.first().unwrap_or(&Value::Null), &state) {
+                Ok(number) => number,
+                Err(e) => return JsonRpcResponse {
+                    jsonrpc: "2.0".to_string(),
+                    result: None,
+                    error: Some(e),
+                    id: req.id.clone(),
+                },
+            };
+
+            let result = match (state.get_block(block_num), state.block_receipts(block_num)) {
+                (Some(block), Some(receipts)) => Value::Array(
+                    receipts
+                        .iter()
+                        .enumerate()
+                        .map(|(index, receipt)| receipt_to_json(receipt, index, &block))
+                        .collect(),
+                ),
+                _ => Value::Null,
+            };
+            JsonRpcResponse {
+                jsonrpc: "2.0".to_string(),
+                result: Some(result),
+                error: None,
+                id: req.id.clone(),
+            }
+        },
+
+        // --- Contract Methods ---
+
+        "eth_call" => {
+            // params: [{to, data, ...}, block_tag]
             let tx_obj = req.params.first().unwrap_or(&Value::Null);
             let to_str = tx_obj.get("to").and_then(|v| v.as_str()).unwrap_or("");
             let data_str = tx_obj.get("data")
@@ -1542,7 +2557,7 @@ fn handle_method(req: &JsonRpcRequest, state: Arc<State>, chain_id: u64) -> Json
                     } else {
                         vec![]
                     };
-                    match execute_contract(&code, &input) {
+                    match execute_contract(vm, &code, &input) {
                         Ok(result) => JsonRpcResponse {
                             jsonrpc: "2.0".to_string(),
                             result: Some(Value::String(format!("0x{}", hex::encode(&result)))),
@@ -1552,10 +2567,7 @@ fn handle_method(req: &JsonRpcRequest, state: Arc<State>, chain_id: u64) -> Json
                         Err(e) => JsonRpcResponse {
                             jsonrpc: "2.0".to_string(),
                             result: None,
-                            error: Some(JsonRpcError {
-                                code: -32000,
-                                message: e,
-                            }),
+                            error: Some(e),
                             id: req.id.clone(),
                         }
                     }
@@ -1569,6 +2581,99 @@ fn handle_method(req: &JsonRpcRequest, state: Arc<State>, chain_id: u64) -> Json
             }
         },
 
+        "eth_createAccessList" => {
+            // params: [{from, to, data}, block_tag]
+            let tx_obj = req.params.first().unwrap_or(&Value::Null);
+            let from_str = tx_obj.get("from").and_then(|v| v.as_str());
+            let to_str = tx_obj.get("to").and_then(|v| v.as_str()).unwrap_or("");
+            let data_str = tx_obj.get("data")
+                .or_else(|| tx_obj.get("input"))
+                .and_then(|v| v.as_str())
+                .unwrap_or("0x");
+
+            let from = match from_str.map(parse_address) {
+                None => Ok(Address::ZERO),
+                Some(from) => from.map_err(|_| invalid_param("from", "Invalid address")),
+            };
+            let to = parse_address(to_str).map_err(|_| invalid_param("to", "Invalid address"));
+            let input = hex::decode(data_str.strip_prefix("0x").unwrap_or(data_str))
+                .map_err(|_| invalid_param("data", "Invalid hex"));
+
+            let result = match (from, to, input) {
+                (Ok(from), Ok(to), Ok(input)) => {
+                    let code = state.get_code(&to);
+                    if code.is_empty() {
+                        // Plain transfers touch no storage
+                        Ok(serde_json::json!({
+                            "accessList": [],
+                            "gasUsed": format!("0x{:x}", merklith_core::state_machine::TRANSFER_GAS),
+                        }))
+                    } else {
+                        run_contract(vm, from, to, &code, state.storage_snapshot(&to), &input).map(|result| {
+                            let access_list: Vec<Value> = result
+                                .access_list
+                                .iter()
+                                .map(|entry| serde_json::json!({
+                                    "address": format!("0x{}", entry.address.to_hex()),
+                                    "storageKeys": entry.storage_keys.iter().map(|k| k.to_string()).collect::<Vec<_>>(),
+                                }))
+                                .collect();
+                            serde_json::json!({
+                                "accessList": access_list,
+                                "gasUsed": format!("0x{:x}", result.gas_used),
+                            })
+                        })
+                    }
+                }
+                (Err(e), _, _) | (_, Err(e), _) | (_, _, Err(e)) => Err(e),
+            };
+            match result {
+                Ok(result) => JsonRpcResponse {
+                    jsonrpc: "2.0".to_string(),
+                    result: Some(result),
+                    error: None,
+                    id: req.id.clone(),
+                },
+                Err(e) => JsonRpcResponse {
+                    jsonrpc: "2.0".to_string(),
+                    result: None,
+                    error: Some(e),
+                    id: req.id.clone(),
+                },
+            }
+        },
+
+        // --- Debug Methods ---
+
+        "debug_traceTransaction" => {
+            // params: [hash] - replays a mined transaction with step tracing.
+            // Public nodes should turn this off with `disabled_methods = ["debug_*"]`.
+            let result = match req.params.first().and_then(|v| v.as_str()).map(merklith_types::Hash::from_str) {
+                Some(Ok(hash)) => state.trace_transaction(&hash).map(|result| trace_to_json(&result)).map_err(|e| {
+                    JsonRpcError {
+                        code: -32000,
+                        message: e,
+                        data: None,
+                    }
+                }),
+                _ => Err(invalid_param("hash", "Invalid transaction hash")),
+            };
+            match result {
+                Ok(result) => JsonRpcResponse {
+                    jsonrpc: "2.0".to_string(),
+                    result: Some(result),
+                    error: None,
+                    id: req.id.clone(),
+                },
+                Err(e) => JsonRpcResponse {
+                    jsonrpc: "2.0".to_string(),
+                    result: None,
+                    error: Some(e),
+                    id: req.id.clone(),
+                },
+            }
+        },
+
         // --- Web3/Net Methods ---
 
         "web3_clientVersion" => JsonRpcResponse {
@@ -1612,7 +2717,7 @@ fn handle_method(req: &JsonRpcRequest, state: Arc<State>, chain_id: u64) -> Json
 
         "net_peerCount" => JsonRpcResponse {
             jsonrpc: "2.0".to_string(),
-            result: Some(Value::String("0x0".to_string())),
+            result: Some(Value::String(format!("0x{:x}", peers.map_or(0, |p| p.connected_peers())))),
             error: None,
             id: req.id.clone(),
         },
@@ -1623,6 +2728,7 @@ fn handle_method(req: &JsonRpcRequest, state: Arc<State>, chain_id: u64) -> Json
             error: Some(JsonRpcError {
                 code: -32601,
                 message: format!("Method not found: {}", req.method),
+                data: None,
             }),
             id: req.id.clone(),
         },
@@ -1632,6 +2738,146 @@ fn handle_method(req: &JsonRpcRequest, state: Arc<State>, chain_id: u64) -> Json
 use merklith_types::{Address, U256};
 use std::str::FromStr;
 
+/// Render an execution trace in the shape of Geth's struct logger
+/// `eth_syncing` result: progress while behind the highest known peer block, else false
+fn syncing_to_json(state: &State, sync: Option<&SyncTracker>) -> Value {
+    match sync.and_then(|sync| sync.progress(state.block_number())) {
+        Some(progress) => serde_json::json!({
+            "startingBlock": format!("0x{:x}", progress.starting_block),
+            "currentBlock": format!("0x{:x}", progress.current_block),
+            "highestBlock": format!("0x{:x}", progress.highest_block),
+        }),
+        None => Value::Bool(false),
+    }
+}
+
+fn trace_to_json(result: &merklith_vm::ExecutionResult) -> Value {
+    use merklith_vm::StorageAccess;
+
+    let struct_logs: Vec<Value> = result
+        .trace
+        .iter()
+        .map(|step| {
+            let storage = step.storage.map(|access| {
+                let (kind, key, value) = match access {
+                    StorageAccess::Read { key, value } => ("read", key, value),
+                    StorageAccess::Write { key, value } => ("write", key, value),
+                };
+                serde_json::json!({
+                    "type": kind,
+                    "key": format!("0x{}", hex::encode(key)),
+                    "value": format!("0x{}", hex::encode(value)),
+                })
+            });
+            serde_json::json!({
+                "pc": step.pc,
+                "op": step.op,
+                "gas": step.gas,
+                "gasCost": step.gas_cost,
+                "depth": 1,
+                "stackDepth": step.stack_depth,
+                "storage": storage,
+            })
+        })
+        .collect();
+    serde_json::json!({
+        "gas": result.gas_used,
+        "failed": !result.success,
+        "returnValue": format!("0x{}", hex::encode(&result.data)),
+        "structLogs": struct_logs,
+    })
+}
+
+/// Render a transaction; `location` is its block and index once mined
+fn transaction_to_json(
+    signed: &merklith_types::SignedTransaction,
+    location: Option<(&merklith_core::state_machine::BlockInfo, usize)>,
+) -> Value {
+    let tx = &signed.tx;
+    serde_json::json!({
+        "hash": format!("0x{}", hex::encode(signed.hash().as_bytes())),
+        "nonce": format!("0x{:x}", tx.nonce),
+        "blockHash": location.map(|(block, _)| format!("0x{}", hex::encode(block.hash))),
+        "blockNumber": location.map(|(block, _)| format!("0x{:x}", block.number)),
+        "transactionIndex": location.map(|(_, index)| format!("0x{:x}", index)),
+        "from": format!("0x{}", signed.sender().to_hex()),
+        "to": tx.to.map(|to| format!("0x{}", to.to_hex())),
+        "value": format!("{:x}", tx.value),
+        "gas": format!("0x{:x}", tx.gas_limit),
+        "gasPrice": format!("{:x}", tx.max_fee_per_gas),
+        "maxFeePerGas": format!("{:x}", tx.max_fee_per_gas),
+        "maxPriorityFeePerGas": format!("{:x}", tx.max_priority_fee_per_gas),
+        "input": format!("0x{}", hex::encode(&tx.data)),
+        "chainId": format!("0x{:x}", tx.chain_id)
+    })
+}
+
+/// Render a stored receipt in the `eth_getTransactionReceipt` shape
+fn receipt_to_json(
+    receipt: &merklith_core::state_machine::BlockReceipt,
+    index: usize,
+    block: &merklith_core::state_machine::BlockInfo,
+) -> Value {
+    serde_json::json!({
+        "transactionHash": format!("0x{}", hex::encode(receipt.tx_hash.as_bytes())),
+        "transactionIndex": format!("0x{:x}", index),
+        "blockHash": format!("0x{}", hex::encode(block.hash)),
+        "blockNumber": format!("0x{:x}", block.number),
+        "from": format!("0x{}", receipt.from.to_hex()),
+        "to": receipt.to.map(|to| format!("0x{}", to.to_hex())),
+        "cumulativeGasUsed": format!("0x{:x}", receipt.cumulative_gas_used),
+        "gasUsed": format!("0x{:x}", receipt.gas_used),
+        "effectiveGasPrice": format!("{:x}", receipt.effective_gas_price),
+        "contractAddress": null,
+        "logs": receipt.logs.iter().map(|log| log_to_json(log, block)).collect::<Vec<_>>(),
+        "logsBloom": format!("0x{}", "00".repeat(256)),
+        "status": if receipt.success { "0x1" } else { "0x0" }
+    })
+}
+
+/// Render a stored event log in the `eth_getLogs` shape
+fn contribution_to_json(address: &Address, score: &PoCScore, rank: usize) -> Value {
+    let percentages = score.get_percentages();
+    let percent = |f: fn(&merklith_consensus::ContributionPercentages) -> f64| {
+        percentages.as_ref().map_or(0.0, f)
+    };
+    serde_json::json!({
+        "address": format!("0x{}", address.to_hex()),
+        "rank": rank,
+        "total": score.total(),
+        "breakdown": {
+            "blockProduction": score.block_production,
+            "attestations": score.attestations,
+            "relayedTxs": score.relayed_txs,
+            "discoveredPeers": score.discovered_peers,
+            "dataAvailability": score.data_availability,
+        },
+        "percentages": {
+            "blockProduction": percent(|p| p.block_production),
+            "attestations": percent(|p| p.attestations),
+            "relayedTxs": percent(|p| p.relayed_txs),
+            "discoveredPeers": percent(|p| p.discovered_peers),
+            "dataAvailability": percent(|p| p.data_availability),
+        },
+    })
+}
+
+fn log_to_json(
+    log: &merklith_core::state_machine::EventLog,
+    block: &merklith_core::state_machine::BlockInfo,
+) -> Value {
+    serde_json::json!({
+        "address": format!("0x{}", log.address.to_hex()),
+        "topics": log.topics.iter().map(|t| t.to_string()).collect::<Vec<_>>(),
+        "data": format!("0x{}", hex::encode(&log.data)),
+        "blockNumber": format!("0x{:x}", log.block_number),
+        "blockHash": format!("0x{}", hex::encode(block.hash)),
+        "logIndex": format!("0x{:x}", log.log_index),
+        "transactionIndex": format!("0x{:x}", log.tx_index),
+        "removed": false
+    })
+}
+
 fn parse_address(s: &str) -> Result<Address, ()> {
     Address::from_str(s).map_err(|_| ())
 }
@@ -1686,51 +2932,280 @@ fn parse_bytes32(s: &str) -> Result<[u8; 32], ()> {
     Ok(arr)
 }
 
-fn process_raw_transaction(raw_tx: &str, state: &State, chain_id: u64) -> Result<merklith_types::Hash, String> {
-    let raw = raw_tx.strip_prefix("0x").unwrap_or(raw_tx);
-    if raw.is_empty() {
-        return Err("Empty raw transaction".to_string());
+/// Resolve the block parameter of a state query to a height, which must not
+/// be past the head. A missing parameter means `latest`.
+fn resolve_block(param: Option<&Value>, state: &State) -> Result<u64, JsonRpcError> {
+    let number = BlockParam::from_value(param.unwrap_or(&Value::Null), state)?;
+    if number > state.block_number() {
+        return Err(block_param::unknown_block(&format!("0x{:x}", number)));
     }
+    Ok(number)
+}
 
-    let bytes = hex::decode(raw).map_err(|_| "Invalid raw transaction hex".to_string())?;
-    let signed_tx: merklith_types::SignedTransaction = borsh::from_slice(&bytes)
-        .map_err(|_| "Invalid raw transaction payload (expected borsh SignedTransaction)".to_string())?;
+/// Balances, nonces and storage are only kept for the head block
+fn require_head(number: u64, state: &State) -> Result<(), JsonRpcError> {
+    let head = state.block_number();
+    if number == head {
+        Ok(())
+    } else {
+        Err(JsonRpcError {
+            code: -32000,
+            message: format!("State at block {} is not available, only at head {}", number, head),
+            data: None,
+        })
+    }
+}
 
-    if signed_tx.tx.chain_id != chain_id {
-        return Err(format!(
-            "Invalid chain_id: expected {}, got {}",
-            chain_id, signed_tx.tx.chain_id
-        ));
+fn process_raw_transaction(
+    raw_tx: &str,
+    state: &State,
+    pool: Option<&TransactionPool>,
+    chain_id: u64,
+) -> Result<merklith_types::Hash, JsonRpcError> {
+    let invalid = |message: String| invalid_param("rawTransaction", message);
+    let signed_tx = decode_signed_transaction(raw_tx, max_tx_size(pool))?;
+    validate_transaction(&signed_tx, state, pool, chain_id)?;
+
+    // Cancels pay no gas; they only remove a delayed transaction
+    if signed_tx.tx.tx_type == merklith_types::TransactionType::CancelDelayed {
+        return state.cancel_delayed(&signed_tx).map_err(invalid);
+    }
+
+    // Delayed transactions wait in the state's queue until their block
+    if signed_tx.tx.execute_after_block.is_some() {
+        return state.schedule_transaction(signed_tx).map_err(invalid);
+    }
+
+    if signed_tx.tx.to.is_none() {
+        return Err(invalid("Contract creation raw tx is not supported by RPC yet".to_string()));
     }
+    // Everything else is executed, and charged gas, when a block includes it
+    let pool = pool.ok_or_else(|| JsonRpcError {
+        code: -32000,
+        message: "Transaction pool not available".to_string(),
+        data: None,
+    })?;
 
-    let to = signed_tx.tx.to.ok_or_else(|| "Contract creation raw tx is not supported by RPC yet".to_string())?;
     let from = signed_tx.sender();
-    let expected_nonce = state.nonce(&from);
-    if signed_tx.tx.nonce != expected_nonce {
-        return Err(format!(
-            "Invalid nonce: expected {}, got {}",
-            expected_nonce, signed_tx.tx.nonce
-        ));
+    let expected_nonce = pending_nonce(state, Some(pool), &from);
+    if signed_tx.tx.nonce > expected_nonce {
+        let mut error = nonce_error(expected_nonce, signed_tx.tx.nonce);
+        // Future nonces wait for the gap to be filled instead of being dropped
+        match state.queue_transaction(signed_tx) {
+            Ok(()) => error.message.push_str(" (queued)"),
+            Err(e) => error.message = format!("{}; {}", error.message, e),
+        }
+        return Err(error);
+    }
+
+    let hash = signed_tx.hash();
+    pool.add_transaction(signed_tx).map_err(pool_error)?;
+    promote_queued(state, pool, &from);
+    Ok(hash)
+}
+
+/// Apply a sponsored transaction: the user's signed intent plus the sponsor's
+/// signature authorizing it to pay the gas
+fn process_sponsored_transaction(
+    raw_tx: &str,
+    state: &State,
+    pool: Option<&TransactionPool>,
+    chain_id: u64,
+) -> Result<merklith_types::Hash, JsonRpcError> {
+    let invalid = |message: String| invalid_param("rawTransaction", message);
+    let bytes = decode_raw_hex(raw_tx, max_tx_size(pool))?;
+    let sponsored: merklith_types::SponsoredTransaction = borsh::from_slice(&bytes)
+        .map_err(|_| invalid("Invalid raw transaction payload (expected borsh SponsoredTransaction)".to_string()))?;
+
+    let intent = &sponsored.inner.tx;
+    if intent.chain_id != chain_id {
+        return Err(invalid(format!("Invalid chain ID: expected {}, got {}", chain_id, intent.chain_id)));
+    }
+    let min_gas_price = pool.map_or(merklith_types::U256::ZERO, TransactionPool::min_gas_price);
+    if intent.max_fee_per_gas < min_gas_price {
+        return Err(JsonRpcError {
+            code: -32000,
+            message: format!("max fee per gas {} below minimum {}", intent.max_fee_per_gas, min_gas_price),
+            data: None,
+        });
+    }
+
+    state.apply_sponsored(&sponsored, &merklith_types::Address::ZERO).map_err(invalid)
+}
+
+/// Map a pool refusal onto the same codes as the acceptance rules
+fn pool_error(e: merklith_txpool::PoolError) -> JsonRpcError {
+    match e {
+        merklith_txpool::PoolError::Underpriced { .. } | merklith_txpool::PoolError::ReplacementUnderpriced { .. } => {
+            JsonRpcError { code: -32000, message: e.to_string(), data: None }
+        }
+        e => invalid_param("rawTransaction", e.to_string()),
+    }
+}
+
+/// Run the pool's acceptance rules on `tx`, or the default ones without a pool
+fn validate_transaction(
+    tx: &merklith_types::SignedTransaction,
+    state: &State,
+    pool: Option<&TransactionPool>,
+    chain_id: u64,
+) -> Result<(), JsonRpcError> {
+    use merklith_txpool::{ValidationContext, ValidationError, ValidationPipeline};
+
+    let ctx = ValidationContext {
+        state,
+        chain_id,
+        min_gas_price: pool.map_or(merklith_types::U256::ZERO, TransactionPool::min_gas_price),
+        max_tx_size: max_tx_size(pool),
+    };
+    let result = match pool {
+        Some(pool) => pool.validation().validate(tx, &ctx),
+        None => ValidationPipeline::default().validate(tx, &ctx),
+    };
+    result.map_err(|e| match e {
+        ValidationError::NonceTooLow { expected, got } => nonce_error(expected, got),
+        ValidationError::Underpriced { .. } => JsonRpcError { code: -32000, message: e.to_string(), data: None },
+        e => invalid_param("rawTransaction", e.to_string()),
+    })
+}
+
+/// Decode a borsh-encoded signed transaction and check its chain id and signature
+fn decode_raw_transaction(
+    raw_tx: &str,
+    chain_id: u64,
+    max_size: usize,
+) -> Result<merklith_types::SignedTransaction, JsonRpcError> {
+    let invalid = |message: String| invalid_param("rawTransaction", message);
+    let signed_tx = decode_signed_transaction(raw_tx, max_size)?;
+
+    if signed_tx.tx.chain_id != chain_id {
+        return Err(invalid(format!(
+            "Invalid chain_id: expected {}, got {}",
+            chain_id, signed_tx.tx.chain_id
+        )));
     }
 
     let signing_hash = signed_tx.tx.signing_hash();
     merklith_crypto::ed25519_verify(&signed_tx.public_key, signing_hash.as_bytes(), &signed_tx.signature)
-        .map_err(|e| format!("Invalid signature: {}", e))?;
+        .map_err(|e| invalid(format!("Invalid signature: {}", e)))?;
+
+    Ok(signed_tx)
+}
+
+/// Decode a borsh-encoded signed transaction, refusing oversized input
+/// before decoding it
+fn decode_signed_transaction(
+    raw_tx: &str,
+    max_size: usize,
+) -> Result<merklith_types::SignedTransaction, JsonRpcError> {
+    let bytes = decode_raw_hex(raw_tx, max_size)?;
+    borsh::from_slice(&bytes).map_err(|_| {
+        invalid_param("rawTransaction", "Invalid raw transaction payload (expected borsh SignedTransaction)")
+    })
+}
+
+/// Hex-decode a raw transaction no larger than `max_size` bytes
+fn decode_raw_hex(raw_tx: &str, max_size: usize) -> Result<Vec<u8>, JsonRpcError> {
+    let invalid = |message: String| invalid_param("rawTransaction", message);
+
+    let raw = raw_tx.strip_prefix("0x").unwrap_or(raw_tx);
+    if raw.is_empty() {
+        return Err(invalid("Empty raw transaction".to_string()));
+    }
+    // Checked on the hex before anything is decoded
+    let size = raw.len().div_ceil(2);
+    if size > max_size {
+        return Err(invalid(format!(
+            "Transaction too large: {} bytes exceeds maximum {}",
+            size, max_size
+        )));
+    }
 
-    state.transfer(&from, &to, signed_tx.tx.value)
+    hex::decode(raw).map_err(|_| invalid("Invalid raw transaction hex".to_string()))
 }
 
-fn execute_contract(code: &[u8], input: &[u8]) -> Result<Vec<u8>, String> {
-    use merklith_vm::{MerklithVM, ExecutionContext};
+/// Largest raw transaction accepted, in bytes
+fn max_tx_size(pool: Option<&TransactionPool>) -> usize {
+    pool.map_or(merklith_txpool::DEFAULT_MAX_TX_SIZE, TransactionPool::max_tx_size)
+}
+
+/// Next nonce for `address` once its executable pooled transactions are included
+fn pending_nonce(state: &State, pool: Option<&TransactionPool>, address: &Address) -> u64 {
+    let nonce = state.nonce(address);
+    pool.map_or(nonce, |pool| pool.next_nonce(address, nonce))
+}
+
+/// Move queued transactions of `sender` that became ready into the pool
+fn promote_queued(state: &State, pool: &TransactionPool, sender: &Address) {
+    while let Some(queued) = state.take_queued(sender, pending_nonce(state, Some(pool), sender)) {
+        let nonce = queued.tx.nonce;
+        let result = if queued.tx.is_expired(state.block_number() + 1) {
+            Err("expired while queued".to_string())
+        } else {
+            pool.add_transaction(queued).map(|_| ()).map_err(|e| e.to_string())
+        };
+        if let Err(e) = result {
+            tracing::warn!("Queued transaction {} from {} dropped: {}", nonce, sender, e);
+            break;
+        }
+    }
+}
+
+/// Error for a nonce that differs from the sender's next nonce.
+/// Wallets drop on "too low" (already used) and wait on "too high".
+fn nonce_error(expected: u64, got: u64) -> JsonRpcError {
+    let (code, message) = if got < expected {
+        (-32010, format!("nonce too low: expected {}, got {}", expected, got))
+    } else {
+        (-32011, format!("nonce too high: expected {}, got {}", expected, got))
+    };
+    JsonRpcError {
+        code,
+        message,
+        data: Some(serde_json::json!({ "expected": expected, "got": got })),
+    }
+}
+
+/// `-32602` naming the offending parameter in `data`
+fn invalid_param(param: &str, message: impl Into<String>) -> JsonRpcError {
+    JsonRpcError {
+        code: -32602,
+        message: message.into(),
+        data: Some(serde_json::json!({ "param": param })),
+    }
+}
+
+/// Run `code` read-only. Failures are `-32000`; reverts carry the revert data in `data`.
+fn execute_contract(vm: &MerklithVM, code: &[u8], input: &[u8]) -> Result<Vec<u8>, JsonRpcError> {
+    run_contract(
+        vm,
+        merklith_types::Address::ZERO,
+        merklith_types::Address::ZERO,
+        code,
+        std::collections::HashMap::new(),
+        input,
+    )
+    .map(|result| result.data.to_vec())
+}
+
+/// Run `code` as contract `to` called by `from`, returning the full result
+fn run_contract(
+    vm: &MerklithVM,
+    from: merklith_types::Address,
+    to: merklith_types::Address,
+    code: &[u8],
+    storage: std::collections::HashMap<[u8; 32], [u8; 32]>,
+    input: &[u8],
+) -> Result<merklith_vm::ExecutionResult, JsonRpcError> {
+    use merklith_vm::ExecutionContext;
     use bytes::Bytes;
     
-    let vm = MerklithVM::new()
-        .map_err(|e| format!("Failed to create VM: {}", e))?;
+    let execution_error = |message: String| JsonRpcError { code: -32000, message, data: None };
     
     let ctx = ExecutionContext::new_call(
-        merklith_types::Address::ZERO,
-        merklith_types::Address::ZERO,
-        merklith_types::Address::ZERO,
+        to,
+        from,
+        from,
         1_000_000,
         Bytes::copy_from_slice(input),
     );
@@ -1738,12 +3213,18 @@ fn execute_contract(code: &[u8], input: &[u8]) -> Result<Vec<u8>, String> {
     let ctx = ExecutionContext {
         code: Bytes::copy_from_slice(code),
         ..ctx
-    };
+    }
+    .with_storage(storage);
     
     match vm.execute(ctx) {
-        Ok(result) if result.success => Ok(result.data.to_vec()),
-        Ok(result) => Err(format!("Contract execution failed")),
-        Err(e) => Err(format!("VM execution error: {}", e)),
+        Ok(result) if result.success => Ok(result),
+        Ok(_) => Err(execution_error("Contract execution failed".to_string())),
+        Err(merklith_vm::VmError::Reverted { reason }) => Err(JsonRpcError {
+            code: -32000,
+            message: "execution reverted".to_string(),
+            data: Some(Value::String(format!("0x{}", hex::encode(reason.unwrap_or_default())))),
+        }),
+        Err(e) => Err(execution_error(format!("VM execution error: {}", e))),
     }
 }
 
@@ -1752,6 +3233,11 @@ mod tests {
     use super::*;
     use merklith_types::{Address, U256};
 
+    fn test_vm() -> &'static MerklithVM {
+        static VM: std::sync::OnceLock<MerklithVM> = std::sync::OnceLock::new();
+        VM.get_or_init(|| MerklithVM::new().unwrap())
+    }
+
     #[test]
     fn test_rpc_config_default() {
         let config = RpcServerConfig::default();
@@ -1862,8 +3348,1270 @@ mod tests {
         let error = JsonRpcError {
             code: -32601,
             message: "Method not found".to_string(),
+            data: None,
         };
         assert_eq!(error.code, -32601);
         assert_eq!(error.message, "Method not found");
     }
+
+    #[test]
+    fn test_get_genesis() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let state = Arc::new(State::with_path(temp_dir.path().to_path_buf()));
+        let request = JsonRpcRequest {
+            jsonrpc: "2.0".to_string(),
+            method: "merklith_getGenesis".to_string(),
+            params: vec![],
+            id: Some(serde_json::json!(1)),
+        };
+
+        let response = handle_method(&request, state.clone(), None, None, 1337, test_vm(), None, None);
+        let result = response.result.unwrap();
+        assert_eq!(
+            result["hash"],
+            format!("0x{}", hex::encode(state.genesis_hash().as_bytes()))
+        );
+        assert_eq!(
+            result["alloc"].as_array().unwrap().len(),
+            merklith_core::state_machine::DEVNET_ACCOUNT_COUNT
+        );
+    }
+
+    #[test]
+    fn test_get_chain_config() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let state = Arc::new(State::with_path(temp_dir.path().to_path_buf()));
+        let request = JsonRpcRequest {
+            jsonrpc: "2.0".to_string(),
+            method: "merklith_getChainConfig".to_string(),
+            params: vec![],
+            id: Some(serde_json::json!(1)),
+        };
+
+        let result = handle_method(&request, state, None, None, 1337, test_vm(), None, None).result.unwrap();
+        assert_eq!(result["chain_id"], 1337);
+        assert_eq!(result["gas_limit"], 30_000_000);
+    }
+
+    #[test]
+    fn test_get_total_supply() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let state = Arc::new(State::with_path(temp_dir.path().to_path_buf()));
+        let request = JsonRpcRequest {
+            jsonrpc: "2.0".to_string(),
+            method: "merklith_getTotalSupply".to_string(),
+            params: vec![],
+            id: Some(serde_json::json!(1)),
+        };
+
+        let result = handle_method(&request, state.clone(), None, None, 1337, test_vm(), None, None).result.unwrap();
+        assert_eq!(result["totalSupply"], format!("{:x}", state.total_supply()));
+        assert_eq!(result["burned"], format!("{:x}", U256::ZERO));
+    }
+
+    fn raw_transfer(keypair: &merklith_crypto::Keypair, nonce: u64, to: Address) -> String {
+        let tx = merklith_types::Transaction::new(
+            1337, nonce, Some(to), U256::from(1u64), 21000, U256::ONE, U256::ZERO,
+        );
+        let (signature, public_key) = keypair.sign_transaction(&tx);
+        let signed = merklith_types::SignedTransaction::new(tx, signature, public_key);
+        format!("0x{}", hex::encode(borsh::to_vec(&signed).unwrap()))
+    }
+
+    fn funded_state(dir: &std::path::Path, keypair: &merklith_crypto::Keypair) -> Arc<State> {
+        let mut genesis = State::devnet_genesis();
+        genesis.chain_config.chain_id = 1337;
+        genesis.add_alloc(keypair.address(), U256::from(1_000_000u64));
+        Arc::new(State::with_genesis(
+            dir.to_path_buf(),
+            genesis,
+            merklith_storage::PruningConfig::archive(),
+        ))
+    }
+
+    /// Include everything pending in the next block, as the node's producer does
+    fn mine(state: &State, pool: &TransactionPool) {
+        let txs = pool.get_pending(usize::MAX);
+        for tx in &txs {
+            pool.remove_transaction(&tx.hash().to_string(), merklith_txpool::RemovalReason::Mined);
+        }
+        state.produce_block(&Address::from_bytes([0x77; 20]), txs, false).unwrap();
+    }
+
+    #[test]
+    fn test_get_block_receipts() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let keypair = merklith_crypto::Keypair::from_seed(&[4u8; 32]);
+        let state = funded_state(temp_dir.path(), &keypair);
+        let txs: Vec<merklith_types::SignedTransaction> = [Address::from_bytes([1u8; 20]), Address::from_bytes([2u8; 20])]
+            .iter()
+            .enumerate()
+            .map(|(nonce, to)| {
+                let tx = merklith_types::Transaction::new(
+                    1337, nonce as u64, Some(*to), U256::from(1u64), 21000, U256::ONE, U256::ZERO,
+                );
+                let (signature, public_key) = keypair.sign_transaction(&tx);
+                merklith_types::SignedTransaction::new(tx, signature, public_key)
+            })
+            .collect();
+        let hashes: Vec<String> = txs.iter().map(|tx| format!("0x{}", hex::encode(tx.hash().as_bytes()))).collect();
+        let produced = state.produce_block(&Address::from_bytes([0xAA; 20]), txs, false).unwrap();
+
+        for method in ["eth_getBlockReceipts", "merklith_getBlockReceipts"] {
+            let request = JsonRpcRequest {
+                jsonrpc: "2.0".to_string(),
+                method: method.to_string(),
+                params: vec![serde_json::json!(format!("0x{:x}", produced.block_number))],
+                id: Some(serde_json::json!(1)),
+            };
+            let receipts = handle_method(&request, state.clone(), None, None, 1337, test_vm(), None, None).result.unwrap();
+            let receipts = receipts.as_array().unwrap();
+            assert_eq!(receipts.len(), 2);
+            for (index, receipt) in receipts.iter().enumerate() {
+                assert_eq!(receipt["transactionHash"], hashes[index]);
+                assert_eq!(receipt["transactionIndex"], format!("0x{:x}", index));
+                assert_eq!(receipt["status"], "0x1");
+                assert_eq!(receipt["cumulativeGasUsed"], format!("0x{:x}", 21000 * (index + 1)));
+            }
+        }
+
+        let unknown = JsonRpcRequest {
+            jsonrpc: "2.0".to_string(),
+            method: "merklith_getBlockReceipts".to_string(),
+            params: vec![serde_json::json!("0x64")],
+            id: Some(serde_json::json!(1)),
+        };
+        assert_eq!(handle_method(&unknown, state, None, None, 1337, test_vm(), None, None).result, Some(Value::Null));
+    }
+
+    #[test]
+    fn test_get_transaction_by_hash() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let keypair = merklith_crypto::Keypair::from_seed(&[5u8; 32]);
+        let state = funded_state(temp_dir.path(), &keypair);
+        let pool = TransactionPool::default();
+        let sign = |nonce: u64| {
+            let tx = merklith_types::Transaction::new(
+                1337, nonce, Some(Address::from_bytes([1u8; 20])), U256::from(1u64), 21000, U256::ONE, U256::ZERO,
+            );
+            let (signature, public_key) = keypair.sign_transaction(&tx);
+            merklith_types::SignedTransaction::new(tx, signature, public_key)
+        };
+        let lookup = |hash: String| {
+            let request = JsonRpcRequest {
+                jsonrpc: "2.0".to_string(),
+                method: "eth_getTransactionByHash".to_string(),
+                params: vec![Value::String(hash)],
+                id: Some(serde_json::json!(1)),
+            };
+            handle_method(&request, state.clone(), Some(&pool), None, 1337, test_vm(), None, None).result.unwrap()
+        };
+
+        let mined = sign(0);
+        let mined_hash = mined.hash().to_string();
+        let produced = state.produce_block(&Address::from_bytes([0xAA; 20]), vec![mined], false).unwrap();
+        let pending = sign(1);
+        let pending_hash = pool.add_transaction(pending).unwrap();
+
+        let result = lookup(pending_hash.clone());
+        assert_eq!(result["hash"], pending_hash);
+        assert_eq!(result["nonce"], "0x1");
+        assert!(result["blockHash"].is_null());
+        assert!(result["blockNumber"].is_null());
+
+        let result = lookup(mined_hash.clone());
+        assert_eq!(result["hash"], mined_hash);
+        assert_eq!(result["blockNumber"], format!("0x{:x}", produced.block_number));
+        assert_eq!(result["blockHash"], format!("0x{}", hex::encode(produced.block_hash)));
+        assert_eq!(result["transactionIndex"], "0x0");
+        assert_eq!(result["from"], format!("0x{}", keypair.address().to_hex()));
+
+        assert!(lookup(format!("0x{}", "ab".repeat(32))).is_null());
+    }
+
+    #[test]
+    fn test_send_sponsored_transaction() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let user = merklith_crypto::Keypair::from_seed(&[4u8; 32]);
+        let sponsor = merklith_crypto::Keypair::from_seed(&[5u8; 32]);
+        let to = Address::from_bytes([9u8; 20]);
+        let mut genesis = State::devnet_genesis();
+        genesis.chain_config.chain_id = 1337;
+        let base_fee = genesis.chain_config.min_base_fee;
+        genesis.add_alloc(user.address(), U256::from(100u64));
+        genesis.add_alloc(sponsor.address(), U256::from(1_000_000u64) * base_fee);
+        let state = Arc::new(State::with_genesis(
+            temp_dir.path().to_path_buf(),
+            genesis,
+            merklith_storage::PruningConfig::archive(),
+        ));
+        let sponsored = |chain_id: u64| {
+            let mut intent = merklith_types::Transaction::new(
+                chain_id, 0, Some(to), U256::from(100u64), 21000, base_fee, U256::ZERO,
+            );
+            intent.tx_type = merklith_types::TransactionType::Sponsored;
+            let (signature, public_key) = user.sign_transaction(&intent);
+            let inner = merklith_types::SignedTransaction::new(intent, signature, public_key);
+            let sponsor_signature = sponsor.sign(
+                merklith_types::SponsoredTransaction::sponsor_signing_hash(&inner).as_bytes(),
+            );
+            let tx = merklith_types::SponsoredTransaction::new(inner, sponsor_signature, sponsor.public_key());
+            format!("0x{}", hex::encode(borsh::to_vec(&tx).unwrap()))
+        };
+        let send = |raw: String| {
+            let request = JsonRpcRequest {
+                jsonrpc: "2.0".to_string(),
+                method: "merklith_sendSponsoredTransaction".to_string(),
+                params: vec![Value::String(raw)],
+                id: Some(serde_json::json!(1)),
+            };
+            handle_method(&request, state.clone(), None, None, 1337, test_vm(), None, None)
+        };
+
+        assert!(send(sponsored(42)).error.unwrap().message.contains("chain ID"));
+
+        // The user spends its whole balance on the value; the sponsor pays the gas
+        assert!(send(sponsored(1337)).error.is_none());
+        assert_eq!(state.balance(&user.address()), U256::ZERO);
+        assert_eq!(state.balance(&to), U256::from(100u64));
+        assert!(state.balance(&sponsor.address()) < U256::from(1_000_000u64) * base_fee);
+    }
+
+    #[test]
+    fn test_raw_transaction_nonce_errors() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let keypair = merklith_crypto::Keypair::from_seed(&[3u8; 32]);
+        let sender = keypair.address();
+        let to = Address::from_bytes([9u8; 20]);
+        let state = funded_state(temp_dir.path(), &keypair);
+        let pool = TransactionPool::default();
+        let send = |raw: String| {
+            let request = JsonRpcRequest {
+                jsonrpc: "2.0".to_string(),
+                method: "merklith_sendRawTransaction".to_string(),
+                params: vec![Value::String(raw)],
+                id: Some(serde_json::json!(1)),
+            };
+            handle_method(&request, state.clone(), Some(&pool), None, 1337, test_vm(), None, None)
+        };
+
+        // Admitted to the pool, not executed
+        assert!(send(raw_transfer(&keypair, 0, to)).error.is_none());
+        assert_eq!(pool.size(), 1);
+        assert_eq!(state.nonce(&sender), 0);
+        mine(&state, &pool);
+        assert_eq!(state.nonce(&sender), 1);
+
+        // Reusing a nonce
+        let error = send(raw_transfer(&keypair, 0, to)).error.unwrap();
+        assert_eq!(error.code, -32010);
+        assert!(error.message.contains("nonce too low"));
+
+        // Skipping ahead is reported but kept
+        let error = send(raw_transfer(&keypair, 2, to)).error.unwrap();
+        assert_eq!(error.code, -32011);
+        assert!(error.message.contains("nonce too high"));
+        assert_eq!(state.queued_count(&sender), 1);
+
+        // Filling the gap moves the queued transaction into the pool too
+        assert!(send(raw_transfer(&keypair, 1, to)).error.is_none());
+        assert_eq!(state.queued_count(&sender), 0);
+        assert_eq!(pool.pending_count_for(&sender), 2);
+        mine(&state, &pool);
+        assert_eq!(state.nonce(&sender), 3);
+        assert_eq!(state.balance(&to), U256::from(3u64));
+
+        // Without a pool there is nowhere to hold it
+        let raw = raw_transfer(&keypair, 3, to);
+        let error = process_raw_transaction(&raw, &state, None, 1337).unwrap_err();
+        assert!(error.message.contains("pool not available"));
+    }
+
+    #[test]
+    fn test_raw_transaction_underpriced() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let keypair = merklith_crypto::Keypair::from_seed(&[6u8; 32]);
+        let state = funded_state(temp_dir.path(), &keypair);
+        let pool = TransactionPool::default();
+        let tx = merklith_types::Transaction::new(
+            1337, 0, Some(Address::from_bytes([9u8; 20])), U256::from(1u64), 21000, U256::ZERO, U256::ZERO,
+        );
+        let (signature, public_key) = keypair.sign_transaction(&tx);
+        let signed = merklith_types::SignedTransaction::new(tx, signature, public_key);
+        let zero_fee = format!("0x{}", hex::encode(borsh::to_vec(&signed).unwrap()));
+
+        let error = process_raw_transaction(&zero_fee, &state, Some(&pool), 1337).unwrap_err();
+        assert_eq!(error.code, -32000);
+        assert!(error.message.contains("underpriced"));
+        assert_eq!(state.nonce(&keypair.address()), 0);
+
+        let at_floor = raw_transfer(&keypair, 0, Address::from_bytes([9u8; 20]));
+        assert!(process_raw_transaction(&at_floor, &state, Some(&pool), 1337).is_ok());
+    }
+
+    #[test]
+    fn test_raw_transaction_size_limit() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let keypair = merklith_crypto::Keypair::from_seed(&[6u8; 32]);
+        let state = funded_state(temp_dir.path(), &keypair);
+        let raw_with_data = |len: usize| {
+            let tx = merklith_types::Transaction::new(
+                1337, 0, Some(Address::from_bytes([9u8; 20])), U256::from(1u64), 21000, U256::ONE, U256::ZERO,
+            )
+            .with_data(vec![0u8; len]);
+            let (signature, public_key) = keypair.sign_transaction(&tx);
+            let signed = merklith_types::SignedTransaction::new(tx, signature, public_key);
+            format!("0x{}", hex::encode(borsh::to_vec(&signed).unwrap()))
+        };
+        let base = (raw_with_data(0).len() - 2) / 2;
+        let pool = TransactionPool::new(merklith_txpool::PoolConfig {
+            max_tx_size: base + 512,
+            ..Default::default()
+        });
+
+        let error = process_raw_transaction(&raw_with_data(513), &state, Some(&pool), 1337).unwrap_err();
+        assert_eq!(error.code, -32602);
+        assert!(error.message.contains("too large"));
+        assert_eq!(state.nonce(&keypair.address()), 0);
+
+        assert!(process_raw_transaction(&raw_with_data(512), &state, Some(&pool), 1337).is_ok());
+    }
+
+    #[test]
+    fn test_simulate_pending() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let funder = merklith_crypto::Keypair::from_seed(&[8u8; 32]);
+        let spender = merklith_crypto::Keypair::from_seed(&[9u8; 32]);
+        let state = funded_state(temp_dir.path(), &funder);
+        let pool = TransactionPool::default();
+        let sign = |keypair: &merklith_crypto::Keypair, to: Address, value: u64| {
+            let tx = merklith_types::Transaction::new(
+                1337, 0, Some(to), U256::from(value), 21000, U256::ONE, U256::ZERO,
+            );
+            let (signature, public_key) = keypair.sign_transaction(&tx);
+            merklith_types::SignedTransaction::new(tx, signature, public_key)
+        };
+
+        // The spender is only funded by a transaction still in the pool
+        pool.add_transaction(sign(&funder, spender.address(), 100_000)).unwrap();
+        let spend = sign(&spender, Address::from_bytes([5u8; 20]), 1_000);
+        let raw = format!("0x{}", hex::encode(borsh::to_vec(&spend).unwrap()));
+
+        let simulate = |pool: Option<&TransactionPool>| {
+            let request = JsonRpcRequest {
+                jsonrpc: "2.0".to_string(),
+                method: "merklith_simulatePending".to_string(),
+                params: vec![Value::String(raw.clone())],
+                id: Some(serde_json::json!(1)),
+            };
+            handle_method(&request, state.clone(), pool, None, 1337, test_vm(), None, None).result.unwrap()
+        };
+
+        let pending = simulate(Some(&pool));
+        assert_eq!(pending["success"], true);
+        assert_eq!(pending["gasUsed"], "0x5208");
+        assert!(pending["error"].is_null());
+        assert_eq!(pending["blockNumber"], "0x1");
+
+        // Against committed state alone the spender cannot pay
+        let committed = simulate(None);
+        assert_eq!(committed["success"], false);
+        assert!(committed["error"].as_str().unwrap().contains("Insufficient balance"));
+
+        // Simulation leaves committed state untouched
+        assert_eq!(state.balance(&spender.address()), U256::ZERO);
+        assert_eq!(state.nonce(&funder.address()), 0);
+    }
+
+    #[test]
+    fn test_get_code_at_block() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let keypair = merklith_crypto::Keypair::from_seed(&[10u8; 32]);
+        let state = funded_state(temp_dir.path(), &keypair);
+        let deployer = keypair.address();
+        let admin = Address::from_bytes([2u8; 20]);
+        let get_code = |address: Address, block: &str| {
+            let request = JsonRpcRequest {
+                jsonrpc: "2.0".to_string(),
+                method: "eth_getCode".to_string(),
+                params: vec![serde_json::json!(address), serde_json::json!(block)],
+                id: Some(serde_json::json!(1)),
+            };
+            handle_method(&request, state.clone(), None, None, 1337, test_vm(), None, None)
+        };
+
+        let v1 = vec![0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00];
+        let v2 = [v1.clone(), vec![0x00, 0x04, 0x03, b'v', b'2', b'!']].concat();
+        state.increment_block();
+        let contract = state.deploy_contract(&deployer, v1.clone()).unwrap();
+        let mut admin_word = [0u8; 32];
+        admin_word[12..].copy_from_slice(admin.as_bytes());
+        state.set_storage(&contract, merklith_core::state_machine::admin_slot(), admin_word);
+        state.increment_block();
+        state.upgrade_code(&contract, v2.clone(), &admin).unwrap();
+
+        let hex_code = |code: &[u8]| Some(Value::String(format!("0x{}", hex::encode(code))));
+        assert_eq!(get_code(contract, "0x0").result, hex_code(&[]));
+        assert_eq!(get_code(contract, "0x1").result, hex_code(&v1));
+        assert_eq!(get_code(contract, "0x2").result, hex_code(&v2));
+        assert_eq!(get_code(contract, "latest").result, hex_code(&v2));
+        assert_eq!(get_code(contract, "0x9").error.unwrap().code, -32000);
+        assert_eq!(get_code(contract, "soon").error.unwrap().code, -32602);
+    }
+
+    #[test]
+    fn test_block_methods_share_block_params() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let state = Arc::new(State::with_path(temp_dir.path().to_path_buf()));
+        state.produce_block(&Address::ZERO, vec![], true).unwrap();
+        state.produce_block(&Address::ZERO, vec![], true).unwrap();
+        let call = |method: &str, block: Value| {
+            let request = JsonRpcRequest {
+                jsonrpc: "2.0".to_string(),
+                method: method.to_string(),
+                params: vec![block],
+                id: Some(serde_json::json!(1)),
+            };
+            handle_method(&request, state.clone(), None, None, 1337, test_vm(), None, None)
+        };
+        let first_hash = format!("0x{}", hex::encode(state.get_block(1).unwrap().hash));
+
+        for method in [
+            "eth_getBlockByNumber",
+            "merklith_getBlockByNumber",
+            "merklith_getBlockInfo",
+            "eth_getBlockTransactionCountByNumber",
+            "eth_getBlockReceipts",
+        ] {
+            let earliest = call(method, serde_json::json!("earliest"));
+            assert_eq!(earliest.result, call(method, serde_json::json!("0x0")).result, "{}", method);
+            let pending = call(method, serde_json::json!("pending"));
+            assert_eq!(pending.result, call(method, serde_json::json!("latest")).result, "{}", method);
+            let by_hash = call(method, serde_json::json!({ "blockHash": first_hash }));
+            assert_eq!(by_hash.result, call(method, serde_json::json!("0x1")).result, "{}", method);
+
+            let error = call(method, serde_json::json!("soon")).error.unwrap();
+            assert_eq!(error.code, -32602, "{}", method);
+            assert_eq!(error.message, "Invalid block tag: soon");
+        }
+        assert_eq!(call("merklith_getBlockByNumber", serde_json::json!("earliest")).result.unwrap()["number"], "0x0");
+    }
+
+    #[test]
+    fn test_deployed_code_verifies_against_source() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let keypair = merklith_crypto::Keypair::from_seed(&[10u8; 32]);
+        let state = funded_state(temp_dir.path(), &keypair);
+        let source = r#"(module (func (export "answer") (result i32) i32.const 42))"#;
+        let contract = state.deploy_contract(&keypair.address(), wat::parse_str(source).unwrap()).unwrap();
+
+        // The code the web API fetches before verifying a source
+        let request = JsonRpcRequest {
+            jsonrpc: "2.0".to_string(),
+            method: "eth_getCode".to_string(),
+            params: vec![serde_json::json!(contract), serde_json::json!("latest")],
+            id: Some(serde_json::json!(1)),
+        };
+        let code = handle_method(&request, state.clone(), None, None, 1337, test_vm(), None, None).result.unwrap();
+        let code = hex::decode(code.as_str().unwrap().trim_start_matches("0x")).unwrap();
+
+        let verified = merklith_vm::verify_source("wat", source, &code).unwrap();
+        assert_eq!(verified.code_hash, merklith_types::Hash::compute(&state.get_code(&contract)));
+        assert!(matches!(
+            merklith_vm::verify_source("wat", &source.replace("42", "7"), &code),
+            Err(merklith_vm::VerifyError::Mismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_state_query_block_params() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let keypair = merklith_crypto::Keypair::from_seed(&[10u8; 32]);
+        let state = funded_state(temp_dir.path(), &keypair);
+        state.produce_block(&Address::ZERO, vec![], true).unwrap();
+        state.produce_block(&Address::ZERO, vec![], true).unwrap();
+        let head = state.block_number();
+        let first_hash = format!("0x{}", hex::encode(state.get_block(1).unwrap().hash));
+        let head_hash = format!("0x{}", hex::encode(state.get_block(head).unwrap().hash));
+        let address = serde_json::json!(keypair.address());
+        let call = |method: &str, params: Vec<Value>| {
+            let request = JsonRpcRequest {
+                jsonrpc: "2.0".to_string(),
+                method: method.to_string(),
+                params,
+                id: Some(serde_json::json!(1)),
+            };
+            handle_method(&request, state.clone(), None, None, 1337, test_vm(), None, None)
+        };
+
+        assert_eq!(resolve_block(None, &state).unwrap(), head);
+        assert_eq!(resolve_block(Some(&serde_json::json!("earliest")), &state).unwrap(), 0);
+        assert_eq!(resolve_block(Some(&serde_json::json!({ "blockHash": first_hash })), &state).unwrap(), 1);
+
+        let balance = Some(Value::String(format!("{:x}", state.balance(&keypair.address()))));
+        for block in [
+            serde_json::json!("latest"),
+            serde_json::json!("pending"),
+            serde_json::json!("safe"),
+            serde_json::json!("finalized"),
+            serde_json::json!(format!("0x{:x}", head)),
+            serde_json::json!({ "blockHash": head_hash }),
+            serde_json::json!({ "blockNumber": format!("0x{:x}", head) }),
+        ] {
+            assert_eq!(call("eth_getBalance", vec![address.clone(), block.clone()]).result, balance);
+            assert_eq!(call("eth_getTransactionCount", vec![address.clone(), block.clone()]).result.unwrap(), "0x0");
+            let slot = serde_json::json!(format!("0x{}", "00".repeat(32)));
+            assert!(call("eth_getStorageAt", vec![address.clone(), slot, block.clone()]).error.is_none());
+            assert_eq!(call("eth_getCode", vec![address.clone(), block]).result.unwrap(), "0x");
+        }
+
+        // Only the head's balances are kept
+        let past = call("eth_getBalance", vec![address.clone(), serde_json::json!("earliest")]).error.unwrap();
+        assert_eq!(past.code, -32000);
+        assert!(past.message.contains("not available"));
+
+        let unknown_hash = serde_json::json!({ "blockHash": format!("0x{}", "ab".repeat(32)) });
+        let error = call("eth_getBalance", vec![address.clone(), unknown_hash]).error.unwrap();
+        assert_eq!(error.code, -32000);
+        assert!(error.message.contains("Unknown block"));
+        let error = call("eth_getCode", vec![address.clone(), serde_json::json!("0x99")]).error.unwrap();
+        assert!(error.message.contains("Unknown block"));
+        assert_eq!(call("eth_getBalance", vec![address.clone(), serde_json::json!("soon")]).error.unwrap().code, -32602);
+        assert_eq!(call("eth_getBalance", vec![address, serde_json::json!({})]).error.unwrap().code, -32602);
+    }
+
+    #[test]
+    fn test_tx_status() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let keypair = merklith_crypto::Keypair::from_seed(&[10u8; 32]);
+        let state = funded_state(temp_dir.path(), &keypair);
+        let pool = TransactionPool::default();
+        let sign = |nonce: u64| {
+            let tx = merklith_types::Transaction::new(
+                1337, nonce, Some(Address::from_bytes([1u8; 20])), U256::from(1u64), 21000, U256::ONE, U256::ZERO,
+            );
+            let (signature, public_key) = keypair.sign_transaction(&tx);
+            merklith_types::SignedTransaction::new(tx, signature, public_key)
+        };
+        let status = |hash: String| {
+            let request = JsonRpcRequest {
+                jsonrpc: "2.0".to_string(),
+                method: "merklith_txStatus".to_string(),
+                params: vec![Value::String(hash)],
+                id: Some(serde_json::json!(1)),
+            };
+            handle_method(&request, state.clone(), Some(&pool), None, 1337, test_vm(), None, None).result.unwrap()
+        };
+
+        let mined = sign(0);
+        let mined_hash = mined.hash().to_string();
+        state.produce_block(&Address::from_bytes([0xAA; 20]), vec![mined], false).unwrap();
+        let pending_hash = pool.add_transaction(sign(1)).unwrap();
+        let queued = sign(3);
+        let queued_hash = queued.hash().to_string();
+        state.queue_transaction(queued).unwrap();
+
+        let result = status(pending_hash);
+        assert_eq!(result["status"], "pending");
+        assert_eq!(result["position"], 0);
+        assert_eq!(result["estimatedBlocks"], 1);
+
+        assert_eq!(status(mined_hash)["status"], "mined");
+        assert_eq!(status(queued_hash)["status"], "queued");
+        let unknown = status(format!("0x{}", "ab".repeat(32)));
+        assert_eq!(unknown["status"], "unknown");
+        assert!(unknown["position"].is_null());
+    }
+
+    #[test]
+    fn test_pending_nonce() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let keypair = merklith_crypto::Keypair::from_seed(&[7u8; 32]);
+        let state = funded_state(temp_dir.path(), &keypair);
+        let pool = TransactionPool::default();
+        let sender = format!("0x{}", keypair.address().to_hex());
+
+        for nonce in 0..2 {
+            let tx = merklith_types::Transaction::new(
+                1337, nonce, Some(Address::from_bytes([9u8; 20])), U256::from(1u64), 21000, U256::ONE, U256::ZERO,
+            );
+            let (signature, public_key) = keypair.sign_transaction(&tx);
+            pool.add_transaction(merklith_types::SignedTransaction::new(tx, signature, public_key)).unwrap();
+        }
+
+        let call = |method: &str, params: Vec<Value>| {
+            let request = JsonRpcRequest {
+                jsonrpc: "2.0".to_string(),
+                method: method.to_string(),
+                params,
+                id: Some(serde_json::json!(1)),
+            };
+            handle_method(&request, state.clone(), Some(&pool), None, 1337, test_vm(), None, None).result.unwrap()
+        };
+
+        assert_eq!(call("eth_getTransactionCount", vec![serde_json::json!(sender), serde_json::json!("latest")]), "0x0");
+        assert_eq!(call("eth_getTransactionCount", vec![serde_json::json!(sender), serde_json::json!("pending")]), "0x2");
+        assert_eq!(call("merklith_getPendingNonce", vec![serde_json::json!(sender)]), "0x2");
+    }
+
+    #[test]
+    fn test_contribution_methods() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let state = Arc::new(State::with_path(temp_dir.path().to_path_buf()));
+        let validator = Address::from_bytes([1u8; 20]);
+        let attester = Address::from_bytes([2u8; 20]);
+        let tracker = parking_lot::RwLock::new(ContributionTracker::new());
+        {
+            let mut tracker = tracker.write();
+            tracker.record_block_production(validator, 1);
+            tracker.record_attestation(validator, 1);
+            tracker.record_attestation(attester, 1);
+        }
+
+        let call = |method: &str, params: Vec<Value>| {
+            let request = JsonRpcRequest {
+                jsonrpc: "2.0".to_string(),
+                method: method.to_string(),
+                params,
+                id: Some(serde_json::json!(1)),
+            };
+            handle_method(&request, state.clone(), None, Some(&tracker), 1337, test_vm(), None, None).result.unwrap()
+        };
+        let hex = |addr: &Address| format!("0x{}", addr.to_hex());
+
+        let standing = call("merklith_getContribution", vec![serde_json::json!(hex(&validator))]);
+        assert_eq!(standing["rank"], 1);
+        assert_eq!(standing["total"], 110);
+        assert_eq!(standing["breakdown"]["blockProduction"], 100);
+        assert_eq!(standing["breakdown"]["attestations"], 10);
+        let pct = standing["percentages"]["blockProduction"].as_f64().unwrap();
+        assert!((pct - 100.0 * 100.0 / 110.0).abs() < 1e-9);
+
+        let nobody = call("merklith_getContribution", vec![serde_json::json!(hex(&Address::from_bytes([9u8; 20])))]);
+        assert_eq!(nobody["rank"], 0);
+        assert_eq!(nobody["total"], 0);
+        assert_eq!(nobody["percentages"]["attestations"], 0.0);
+
+        let top = call("merklith_getTopContributors", vec![serde_json::json!(5)]);
+        let top = top.as_array().unwrap();
+        assert_eq!(top.len(), 2);
+        assert_eq!(top[0]["address"], hex(&validator));
+        assert_eq!(top[1]["address"], hex(&attester));
+        assert_eq!(top[1]["rank"], 2);
+        assert_eq!(call("merklith_getTopContributors", vec![serde_json::json!(1)]).as_array().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_create_access_list() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let state = Arc::new(State::with_path(temp_dir.path().to_path_buf()));
+        let deployer = State::devnet_accounts()[0].address();
+
+        // SLOAD slot 1, SLOAD slot 2, STOP
+        let contract = state
+            .deploy_contract(&deployer, vec![0x60, 0x01, 0x54, 0x60, 0x02, 0x54, 0x00])
+            .unwrap();
+        let mut slot = [0u8; 32];
+        slot[31] = 1;
+        state.set_storage(&contract, slot, [7u8; 32]);
+
+        let request = JsonRpcRequest {
+            jsonrpc: "2.0".to_string(),
+            method: "eth_createAccessList".to_string(),
+            params: vec![
+                serde_json::json!({"from": deployer.to_string(), "to": contract.to_string(), "data": "0x"}),
+                serde_json::json!("latest"),
+            ],
+            id: Some(serde_json::json!(1)),
+        };
+        let result = handle_method(&request, state, None, None, 1337, test_vm(), None, None).result.unwrap();
+
+        let access_list = result["accessList"].as_array().unwrap();
+        assert_eq!(access_list.len(), 1);
+        assert_eq!(access_list[0]["address"], format!("0x{}", contract.to_hex()));
+        let keys = access_list[0]["storageKeys"].as_array().unwrap();
+        assert_eq!(keys.len(), 2);
+        assert_eq!(keys[0], format!("0x{}01", "00".repeat(31)));
+        assert_eq!(keys[1], format!("0x{}02", "00".repeat(31)));
+        let gas_used = u64::from_str_radix(result["gasUsed"].as_str().unwrap().trim_start_matches("0x"), 16).unwrap();
+        assert!(gas_used >= 21_000 + 2 * 2100);
+    }
+
+    #[test]
+    fn test_multicall() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let state = Arc::new(State::with_path(temp_dir.path().to_path_buf()));
+        let deployer = State::devnet_accounts()[0].address();
+
+        // PUSH1 0x2a; echo calldata; PUSH1 0x07 REVERT
+        let answer = state.deploy_contract(&deployer, vec![0x60, 0x2a, 0x00, 0x00]).unwrap();
+        let echo = state.deploy_contract(&deployer, vec![0x35, 0x00, 0x00, 0x00]).unwrap();
+        let reverts = state.deploy_contract(&deployer, vec![0x60, 0x07, 0xfd, 0x00]).unwrap();
+
+        let calls = serde_json::json!([
+            {"to": format!("0x{}", hex::encode(answer)), "data": "0x"},
+            {"to": format!("0x{}", hex::encode(reverts)), "data": "0x"},
+            {"to": format!("0x{}", hex::encode(echo)), "data": "0xbeef"},
+        ]);
+        let request = |require_success: bool| JsonRpcRequest {
+            jsonrpc: "2.0".to_string(),
+            method: "merklith_multicall".to_string(),
+            params: vec![calls.clone(), Value::Bool(require_success)],
+            id: Some(serde_json::json!(1)),
+        };
+
+        let result = handle_method(&request(false), state.clone(), None, None, 1337, test_vm(), None, None).result.unwrap();
+        let results = result.as_array().unwrap();
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0]["success"], true);
+        assert_eq!(results[0]["returnData"], "0x2a");
+        assert_eq!(results[1]["success"], false);
+        assert_eq!(results[1]["returnData"], "0x07");
+        assert_eq!(results[2]["success"], true);
+        assert_eq!(results[2]["returnData"], "0xbeef");
+
+        let error = handle_method(&request(true), state, None, None, 1337, test_vm(), None, None).error.unwrap();
+        assert_eq!(error.code, -32000);
+        assert!(error.message.starts_with("Call 1 failed"));
+        assert_eq!(error.data, Some(Value::String("0x07".to_string())));
+    }
+
+    #[test]
+    fn test_call_return_data_limit() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let state = Arc::new(State::with_path(temp_dir.path().to_path_buf()));
+        let deployer = State::devnet_accounts()[0].address();
+        // Returns its calldata
+        let echo = state.deploy_contract(&deployer, vec![0x35, 0x00, 0x00, 0x00]).unwrap();
+        let vm = MerklithVM::new().unwrap().with_max_return_data(32);
+        let call = |data: String| JsonRpcRequest {
+            jsonrpc: "2.0".to_string(),
+            method: "eth_call".to_string(),
+            params: vec![serde_json::json!({ "to": format!("0x{}", hex::encode(echo)), "data": data })],
+            id: Some(serde_json::json!(1)),
+        };
+
+        let small = format!("0x{}", "ab".repeat(32));
+        let result = handle_method(&call(small.clone()), state.clone(), None, None, 1337, &vm, None, None).result.unwrap();
+        assert_eq!(result, Value::String(small));
+
+        let error = handle_method(&call(format!("0x{}", "ab".repeat(33))), state, None, None, 1337, &vm, None, None).error.unwrap();
+        assert_eq!(error.code, -32000);
+        assert!(error.message.contains("Return data too large: 33 > 32"), "{}", error.message);
+    }
+
+    #[test]
+    fn test_block_gas_limit() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let state = Arc::new(State::with_path(temp_dir.path().to_path_buf()));
+        state.set_gas_limit_target(Some(0));
+        state.produce_block(&Address::ZERO, vec![], true).unwrap();
+        let request = |method: &str, params: Vec<Value>| JsonRpcRequest {
+            jsonrpc: "2.0".to_string(),
+            method: method.to_string(),
+            params,
+            id: Some(serde_json::json!(1)),
+        };
+
+        // A lower target moves the limit down from the genesis value
+        let limit = state.get_block(1).unwrap().gas_limit;
+        assert!(limit < 30_000_000);
+        let block = handle_method(&request("eth_getBlockByNumber", vec![Value::String("latest".to_string())]), state.clone(), None, None, 1337, test_vm(), None, None).result.unwrap();
+        assert_eq!(block["gasLimit"], format!("0x{:x}", limit));
+
+        let stats = handle_method(&request("merklith_getChainStats", vec![]), state.clone(), None, None, 1337, test_vm(), None, None).result.unwrap();
+        assert_eq!(stats["gasLimit"], format!("0x{:x}", limit));
+        assert_eq!(stats["nextGasLimit"], format!("0x{:x}", state.next_gas_limit()));
+    }
+
+    #[test]
+    fn test_syncing() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let state = Arc::new(State::with_path(temp_dir.path().to_path_buf()));
+        state.produce_block(&Address::ZERO, vec![], true).unwrap();
+        let sync = SyncTracker::new();
+        let request = |method: &str| JsonRpcRequest {
+            jsonrpc: "2.0".to_string(),
+            method: method.to_string(),
+            params: vec![],
+            id: Some(serde_json::json!(1)),
+        };
+
+        let synced = handle_method(&request("eth_syncing"), state.clone(), None, None, 1337, test_vm(), Some(&sync), None).result.unwrap();
+        assert_eq!(synced, Value::Bool(false));
+
+        sync.observe_peer_head("peer_1", 0x20, state.block_number());
+        for method in ["eth_syncing", "merklith_syncing"] {
+            let progress = handle_method(&request(method), state.clone(), None, None, 1337, test_vm(), Some(&sync), None).result.unwrap();
+            assert_eq!(progress["startingBlock"], "0x1");
+            assert_eq!(progress["currentBlock"], "0x1");
+            assert_eq!(progress["highestBlock"], "0x20");
+        }
+    }
+
+    struct MockPeers(usize);
+
+    impl PeerSource for MockPeers {
+        fn connected_peers(&self) -> usize {
+            self.0
+        }
+
+        fn peers(&self) -> Vec<merklith_network::PeerInfo> {
+            (0..self.0)
+                .map(|i| merklith_network::PeerInfo { id: format!("peer{}", i), address: format!("10.0.0.{}:30303", i) })
+                .collect()
+        }
+    }
+
+    #[test]
+    fn test_peer_count() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let state = Arc::new(State::with_path(temp_dir.path().to_path_buf()));
+        let network = MockPeers(3);
+        let call = |method: &str, peers: Option<&dyn PeerSource>| {
+            let request = JsonRpcRequest {
+                jsonrpc: "2.0".to_string(),
+                method: method.to_string(),
+                params: vec![],
+                id: Some(serde_json::json!(1)),
+            };
+            handle_method(&request, state.clone(), None, None, 1337, test_vm(), None, peers).result.unwrap()
+        };
+
+        assert_eq!(call("net_peerCount", Some(&network)), "0x3");
+        assert_eq!(call("net_peerCount", None), "0x0");
+        assert_eq!(call("merklith_getChainStats", Some(&network))["peerCount"], 3);
+
+        let peers = call("merklith_getPeers", Some(&network));
+        let peers = peers.as_array().unwrap();
+        assert_eq!(peers.len(), 3);
+        assert_eq!(peers[1]["id"], "peer1");
+        assert_eq!(peers[1]["address"], "10.0.0.1:30303");
+    }
+
+    #[test]
+    fn test_debug_trace_transaction() {
+        use merklith_storage::PruningConfig;
+        use merklith_types::{SignedTransaction, Transaction};
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let state = Arc::new(State::with_pruning(
+            temp_dir.path().to_path_buf(),
+            PruningConfig::default().with_snapshot_interval(1),
+        ));
+        let sender = &State::devnet_accounts()[0];
+        let validator = Address::from_bytes([9u8; 20]);
+
+        // SSTORE 0x2a -> 1; SSTORE 0x07 -> 2; STOP
+        let code = vec![0x60, 0x2a, 0x60, 0x01, 0x55, 0x60, 0x07, 0x60, 0x02, 0x55, 0x00];
+        let contract = state.deploy_contract(&sender.address(), code).unwrap();
+        state.produce_block(&validator, vec![], true).unwrap();
+
+        let tx = Transaction::new(
+            state.chain_id(),
+            state.nonce(&sender.address()),
+            Some(contract),
+            U256::ZERO,
+            100_000,
+            U256::from(1_000_000_000u64),
+            U256::ZERO,
+        );
+        let (signature, public_key) = sender.sign_transaction(&tx);
+        let signed = SignedTransaction::new(tx, signature, public_key);
+        state.produce_block(&validator, vec![signed.clone()], false).unwrap();
+
+        let request = |hash: &str| JsonRpcRequest {
+            jsonrpc: "2.0".to_string(),
+            method: "debug_traceTransaction".to_string(),
+            params: vec![Value::String(hash.to_string())],
+            id: Some(serde_json::json!(1)),
+        };
+        let hash = format!("0x{}", hex::encode(signed.hash().as_bytes()));
+        let trace = handle_method(&request(&hash), state.clone(), None, None, 1337, test_vm(), None, None).result.unwrap();
+        assert_eq!(trace["failed"], false);
+        let writes: Vec<&Value> = trace["structLogs"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter(|log| log["storage"]["type"] == "write")
+            .collect();
+        assert_eq!(writes.len(), 2);
+        assert_eq!(writes[0]["op"], "SSTORE");
+        assert_eq!(writes[0]["storage"]["value"], format!("0x{}2a", "00".repeat(31)));
+        assert_eq!(writes[1]["storage"]["key"], format!("0x{}02", "00".repeat(31)));
+        assert!(writes[0]["gasCost"].as_u64().unwrap() > 0);
+
+        let unknown = format!("0x{}", "11".repeat(32));
+        let error = handle_method(&request(&unknown), state.clone(), None, None, 1337, test_vm(), None, None).error.unwrap();
+        assert_eq!(error.code, -32000);
+        let error = handle_method(&request("0xzz"), state, None, None, 1337, test_vm(), None, None).error.unwrap();
+        assert_eq!(error.code, -32602);
+    }
+
+    #[test]
+    fn test_contract_lifecycle() {
+        use merklith_types::{SignedTransaction, Transaction};
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let keypair = merklith_crypto::Keypair::from_seed(&[12u8; 32]);
+        let state = funded_state(temp_dir.path(), &keypair);
+        let pool = TransactionPool::default();
+        let from = serde_json::json!(keypair.address());
+        let call = |method: &str, params: Vec<Value>| {
+            let request = JsonRpcRequest {
+                jsonrpc: "2.0".to_string(),
+                method: method.to_string(),
+                params,
+                id: Some(serde_json::json!(1)),
+            };
+            handle_method(&request, state.clone(), Some(&pool), None, 1337, test_vm(), None, None)
+        };
+        let deploy = |code: &[u8]| {
+            let response = call("merklith_deployContract", vec![from.clone(), serde_json::json!(format!("0x{}", hex::encode(code)))]);
+            Value::String(response.result.unwrap().as_str().unwrap().to_string())
+        };
+
+        // A WASM contract returning the byte 0x2a, deployed and called over RPC
+        let wasm = wat::parse_str(
+            r#"(module
+                (memory (export "memory") 1)
+                (data (i32.const 0) "\2a")
+                (func (export "call") (result i64) (i64.const 1)))"#,
+        )
+        .unwrap();
+        let wasm_address = deploy(&wasm);
+        assert_eq!(call("merklith_getCode", vec![wasm_address.clone()]).result.unwrap(), format!("0x{}", hex::encode(&wasm)));
+        let result = call("eth_call", vec![serde_json::json!({ "to": wasm_address, "data": "0x" })]);
+        assert_eq!(result.result.unwrap(), "0x2a");
+
+        // WASM contracts have no storage host functions yet, so storage is
+        // written by a bytecode contract: SSTORE 0x01 -> 0x2a; STOP
+        let contract = deploy(&[0x60, 0x2a, 0x60, 0x01, 0x55, 0x00]);
+        let slot = serde_json::json!(format!("0x{}01", "00".repeat(31)));
+        let stored = || call("eth_getStorageAt", vec![contract.clone(), slot.clone(), serde_json::json!("latest")]).result.unwrap();
+        let empty = serde_json::json!(format!("0x{}", "00".repeat(32)));
+        assert_eq!(stored(), empty);
+
+        // eth_call runs the write without committing it
+        assert!(call("eth_call", vec![serde_json::json!({ "to": contract, "data": "0x" })]).error.is_none());
+        assert_eq!(stored(), empty);
+
+        // A mined transaction commits it
+        let to = parse_address(contract.as_str().unwrap()).unwrap();
+        let tx = Transaction::new(1337, state.nonce(&keypair.address()), Some(to), U256::ZERO, 100_000, U256::ONE, U256::ZERO);
+        let (signature, public_key) = keypair.sign_transaction(&tx);
+        let raw = borsh::to_vec(&SignedTransaction::new(tx, signature, public_key)).unwrap();
+        assert!(call("eth_sendRawTransaction", vec![serde_json::json!(format!("0x{}", hex::encode(raw)))]).error.is_none());
+        assert_eq!(stored(), empty);
+        mine(&state, &pool);
+        assert_eq!(stored(), serde_json::json!(format!("0x{}2a", "00".repeat(31))));
+    }
+
+    #[test]
+    fn test_error_data_serialization() {
+        let without = JsonRpcError { code: -32000, message: "failed".to_string(), data: None };
+        let json = serde_json::to_value(&without).unwrap();
+        assert!(json.get("data").is_none());
+
+        let with = nonce_error(3, 1);
+        let json = serde_json::to_value(&with).unwrap();
+        assert_eq!(json["code"], -32010);
+        assert_eq!(json["data"]["expected"], 3);
+        assert_eq!(json["data"]["got"], 1);
+
+        let json = serde_json::to_value(invalid_param("to", "Invalid address")).unwrap();
+        assert_eq!(json["data"]["param"], "to");
+
+        // Errors from peers that omit `data` still parse
+        let parsed: JsonRpcError = serde_json::from_str(r#"{"code":-32601,"message":"x"}"#).unwrap();
+        assert!(parsed.data.is_none());
+    }
+
+    #[test]
+    fn test_dispatch_records_metrics() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let state = Arc::new(State::with_path(temp_dir.path().to_path_buf()));
+        let registry = prometheus::Registry::new();
+        let metrics = RpcMetrics::new(&registry).unwrap();
+        let request = |method: &str| JsonRpcRequest {
+            jsonrpc: "2.0".to_string(),
+            method: method.to_string(),
+            params: vec![],
+            id: Some(serde_json::json!(1)),
+        };
+
+        for _ in 0..2 {
+            dispatch(&request("merklith_blockNumber"), state.clone(), None, None, 1337, test_vm(), None, None, Some(&metrics), Duration::from_secs(1));
+        }
+        dispatch(&request("no_such_method"), state, None, None, 1337, test_vm(), None, None, Some(&metrics), Duration::from_secs(1));
+
+        assert_eq!(metrics.request_count("merklith_blockNumber"), 2);
+        assert!(metrics.total_duration("merklith_blockNumber") > 0.0);
+        assert_eq!(metrics.request_count(metrics::UNKNOWN_METHOD), 1);
+        assert_eq!(metrics.request_count("no_such_method"), 0);
+
+        let families = registry.gather();
+        assert!(families.iter().any(|f| f.get_name() == "merklith_rpc_method_requests_total"));
+    }
+
+    fn test_context(max_body_size: usize) -> ServiceContext {
+        ServiceContext {
+            chain_id: 1337,
+            admin_token: None,
+            metrics: None,
+            pool: None,
+            contributions: None,
+            vm: Arc::new(MerklithVM::new().unwrap()),
+            sync: None,
+            peers: None,
+            faucet: None,
+            consensus: None,
+            network: None,
+            slow_request_threshold: Duration::from_secs(1),
+            max_body_size,
+            read_timeout: Duration::from_secs(1),
+            enabled_methods: None,
+            disabled_methods: Arc::new(HashSet::new()),
+            max_json_depth: 64,
+            max_json_elements: 10_000,
+            max_batch_size: 100,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_body_too_large() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let state = Arc::new(State::with_path(temp_dir.path().to_path_buf()));
+        let body = r#"{"jsonrpc":"2.0","method":"merklith_blockNumber","params":[],"id":1}"#;
+        let post = |body: hyper::Body| {
+            hyper::Request::post("/").body(body).unwrap()
+        };
+
+        let ok = handle_rpc_request(post(body.into()), state.clone(), test_context(1024), None).await.unwrap();
+        assert_eq!(ok.status(), hyper::StatusCode::OK);
+
+        // Rejected from Content-Length alone
+        let mut declared = post(body.into());
+        declared.headers_mut().insert(hyper::header::CONTENT_LENGTH, "4096".parse().unwrap());
+        let response = handle_rpc_request(declared, state.clone(), test_context(16), None).await.unwrap();
+        assert_eq!(response.status(), hyper::StatusCode::PAYLOAD_TOO_LARGE);
+
+        // Rejected while streaming a body without a length
+        let (mut sender, streamed) = hyper::Body::channel();
+        tokio::spawn(async move {
+            for _ in 0..8 {
+                if sender.send_data(bytes::Bytes::from(vec![b' '; 8])).await.is_err() {
+                    break;
+                }
+            }
+        });
+        let response = handle_rpc_request(post(streamed), state, test_context(16), None).await.unwrap();
+        assert_eq!(response.status(), hyper::StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[tokio::test]
+    async fn test_vm_created_once() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let state = Arc::new(State::with_path(temp_dir.path().to_path_buf()));
+        let deployer = Address::from_bytes([1u8; 20]);
+        let answer = state.deploy_contract(&deployer, vec![0x60, 0x2a, 0x00, 0x00]).unwrap();
+
+        let config = RpcServerConfig {
+            http_addr: "127.0.0.1:0".parse().unwrap(),
+            ws_addr: None,
+            ..RpcServerConfig::default()
+        };
+        let mut server = RpcServer::new(config, state.clone(), 1337);
+        assert!(server.vm().is_none());
+        server.start().await.unwrap();
+        let vm = server.vm().unwrap().clone();
+
+        // Restarting keeps the VM created at first startup
+        server.start().await.unwrap();
+        assert!(Arc::ptr_eq(server.vm().unwrap(), &vm));
+
+        // Each connection clones the context; calls borrow its VM without creating one
+        let context = ServiceContext { vm: vm.clone(), ..test_context(4096) };
+        let handles = Arc::strong_count(&vm);
+        for _ in 0..2 {
+            let body = format!(
+                r#"{{"jsonrpc":"2.0","method":"eth_call","params":[{{"to":"0x{}","data":"0x"}}],"id":1}}"#,
+                hex::encode(answer)
+            );
+            let request = hyper::Request::post("/").body(body.into()).unwrap();
+            let response = handle_rpc_request(request, state.clone(), context.clone(), None).await.unwrap();
+            let bytes = hyper::body::to_bytes(response.into_body()).await.unwrap();
+            let response: Value = serde_json::from_slice(&bytes).unwrap();
+            assert_eq!(response["result"], "0x2a");
+        }
+        assert_eq!(Arc::strong_count(&vm), handles);
+    }
+
+    #[tokio::test]
+    async fn test_json_complexity_limits() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let state = Arc::new(State::with_path(temp_dir.path().to_path_buf()));
+        let rejection = |response: hyper::Response<hyper::Body>| async move {
+            assert_eq!(response.status(), hyper::StatusCode::BAD_REQUEST);
+            let bytes = hyper::body::to_bytes(response.into_body()).await.unwrap();
+            serde_json::from_slice::<JsonRpcResponse>(&bytes).unwrap().error.unwrap()
+        };
+
+        let nested = format!(
+            r#"{{"jsonrpc":"2.0","method":"eth_chainId","params":[{}{}],"id":1}}"#,
+            "[".repeat(100),
+            "]".repeat(100),
+        );
+        let request = hyper::Request::post("/").body(nested.into()).unwrap();
+        let error = rejection(handle_rpc_request(request, state.clone(), test_context(4096), None).await.unwrap()).await;
+        assert_eq!(error.code, -32600);
+        assert!(error.message.contains("nested"));
+
+        let params = vec!["1"; 20].join(",");
+        let oversized = format!(r#"{{"jsonrpc":"2.0","method":"eth_chainId","params":[{}],"id":1}}"#, params);
+        let mut context = test_context(4096);
+        context.max_json_elements = 16;
+        let request = hyper::Request::post("/").body(oversized.clone().into()).unwrap();
+        let error = rejection(handle_rpc_request(request, state.clone(), context, None).await.unwrap()).await;
+        assert_eq!(error.code, -32600);
+        assert!(error.message.contains("elements"));
+
+        // Brackets and commas inside strings are not structure
+        let quoted = format!(r#"{{"jsonrpc":"2.0","method":"eth_chainId","params":["{}"],"id":1}}"#, "[,".repeat(100));
+        let request = hyper::Request::post("/").body(quoted.into()).unwrap();
+        let response = handle_rpc_request(request, state.clone(), test_context(4096), None).await.unwrap();
+        assert_eq!(response.status(), hyper::StatusCode::OK);
+
+        // Within the default limits
+        let request = hyper::Request::post("/").body(oversized.into()).unwrap();
+        let response = handle_rpc_request(request, state, test_context(4096), None).await.unwrap();
+        assert_eq!(response.status(), hyper::StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_batch_request() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let state = Arc::new(State::with_path(temp_dir.path().to_path_buf()));
+        let body = r#"[
+            {"jsonrpc":"2.0","method":"eth_chainId","params":[],"id":7},
+            {"jsonrpc":"2.0","method":"no_such_method","params":[],"id":8}
+        ]"#;
+        let request = hyper::Request::post("/").body(body.into()).unwrap();
+
+        let response = handle_rpc_request(request, state, test_context(4096), None).await.unwrap();
+        assert_eq!(response.status(), hyper::StatusCode::OK);
+
+        let bytes = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let responses: Vec<JsonRpcResponse> = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(responses.len(), 2);
+        assert_eq!(responses[0].id, Some(serde_json::json!(7)));
+        assert_eq!(responses[0].result, Some(serde_json::json!("0x539")));
+        assert_eq!(responses[1].id, Some(serde_json::json!(8)));
+        assert!(responses[1].error.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_batch_limits() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let state = Arc::new(State::with_path(temp_dir.path().to_path_buf()));
+        let call = r#"{"jsonrpc":"2.0","method":"eth_chainId","params":[],"id":1}"#;
+        let oversized = format!("[{}]", vec![call; 101].join(","));
+
+        for body in ["[]".to_string(), oversized] {
+            let request = hyper::Request::post("/").body(body.into()).unwrap();
+            let response = handle_rpc_request(request, state.clone(), test_context(1 << 20), None).await.unwrap();
+            assert_eq!(response.status(), hyper::StatusCode::OK);
+
+            let bytes = hyper::body::to_bytes(response.into_body()).await.unwrap();
+            let response: JsonRpcResponse = serde_json::from_slice(&bytes).unwrap();
+            assert_eq!(response.error.unwrap().code, -32600);
+        }
+    }
+
+    #[test]
+    fn test_connection_limit() {
+        let limiter = ConnectionLimiter::new(2);
+        let first = limiter.try_acquire();
+        let second = limiter.try_acquire();
+        assert!(first.is_some() && second.is_some());
+        assert!(limiter.try_acquire().is_none());
+
+        // Closing a connection frees its slot
+        drop(first);
+        assert!(limiter.try_acquire().is_some());
+
+        assert_eq!(too_many_connections().status(), hyper::StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[tokio::test]
+    async fn test_idle_connection_times_out() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let (client, server) = tokio::io::duplex(64);
+        let mut conn = IdleTimeout::new(server, Duration::from_millis(200));
+        let mut client = client;
+        let mut buf = [0u8; 4];
+
+        // Traffic keeps it open past the timeout
+        for _ in 0..3 {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            client.write_all(b"ping").await.unwrap();
+            conn.read_exact(&mut buf).await.unwrap();
+        }
+
+        let error = conn.read(&mut buf).await.unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::TimedOut);
+    }
+
+    #[tokio::test]
+    async fn test_method_allow_and_deny_lists() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let state = Arc::new(State::with_path(temp_dir.path().to_path_buf()));
+        let set = |methods: &[&str]| methods.iter().map(|m| m.to_string()).collect::<HashSet<_>>();
+        let call = |context: ServiceContext, method: &'static str| {
+            let state = state.clone();
+            async move {
+                let body = format!(r#"{{"jsonrpc":"2.0","method":"{}","params":[],"id":1}}"#, method);
+                let request = hyper::Request::post("/").body(body.into()).unwrap();
+                let response = handle_rpc_request(request, state, context, None).await.unwrap();
+                let bytes = hyper::body::to_bytes(response.into_body()).await.unwrap();
+                serde_json::from_slice::<JsonRpcResponse>(&bytes).unwrap()
+            }
+        };
+
+        let context = ServiceContext {
+            disabled_methods: Arc::new(set(&["merklith_createWallet", "debug_*"])),
+            ..test_context(4096)
+        };
+        let denied = call(context.clone(), "merklith_createWallet").await;
+        let error = denied.error.unwrap();
+        assert_eq!(error.code, -32601);
+        assert!(error.message.contains("disabled"));
+        assert_eq!(call(context.clone(), "debug_traceTransaction").await.error.unwrap().code, -32601);
+        assert_eq!(call(context, "eth_chainId").await.result, Some(serde_json::json!("0x539")));
+
+        // With an allowlist, anything not listed is disabled
+        let context = ServiceContext {
+            enabled_methods: Some(Arc::new(set(&["eth_*"]))),
+            ..test_context(4096)
+        };
+        assert_eq!(call(context.clone(), "eth_chainId").await.result, Some(serde_json::json!("0x539")));
+        assert_eq!(call(context, "merklith_chainId").await.error.unwrap().code, -32601);
+
+        // The denylist wins over the allowlist
+        let enabled = set(&["eth_*"]);
+        assert!(!is_method_enabled("eth_chainId", Some(&enabled), &set(&["eth_chainId"])));
+        assert!(is_method_enabled("net_version", None, &HashSet::new()));
+    }
+
+    #[test]
+    fn test_admin_methods_require_token() {
+        assert!(is_authorized("merklith_blockNumber", None, None));
+        assert!(!is_authorized("merklith_sweepDust", None, None));
+        assert!(!is_authorized("merklith_sweepDust", Some("Bearer secret"), None));
+        assert!(!is_authorized("merklith_sweepDust", None, Some("secret")));
+        assert!(!is_authorized("merklith_sweepDust", Some("Bearer wrong!"), Some("secret")));
+        assert!(!is_authorized("merklith_sweepDust", Some("secret"), Some("secret")));
+        assert!(is_authorized("merklith_sweepDust", Some("Bearer secret"), Some("secret")));
+    }
 }
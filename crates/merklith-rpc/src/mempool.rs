@@ -0,0 +1,125 @@
+//! Saving pending transactions across a restart
+//!
+//! `merklith_exportMempool` dumps the pool and the nonce-gap queue as raw
+//! transactions; a node started with a saved dump re-admits those that are
+//! still valid.
+
+use merklith_core::state_machine::State;
+use merklith_txpool::TransactionPool;
+
+use crate::{decode_signed_transaction, pending_nonce, validate_transaction};
+
+/// Outcome of [`import_mempool`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MempoolImport {
+    /// Added to the pool
+    pub admitted: usize,
+    /// Waiting for a nonce gap to fill
+    pub queued: usize,
+    /// Invalid, stale or unaffordable
+    pub dropped: usize,
+}
+
+/// Pooled then queued transactions, hex-encoded as for `eth_sendRawTransaction`
+pub fn export_mempool(state: &State, pool: Option<&TransactionPool>) -> Vec<String> {
+    let pooled = pool.map(|pool| pool.get_pending(usize::MAX)).unwrap_or_default();
+    pooled
+        .iter()
+        .chain(state.queued_transactions().iter())
+        .filter_map(|tx| borsh::to_vec(tx).ok())
+        .map(|bytes| format!("0x{}", hex::encode(bytes)))
+        .collect()
+}
+
+/// Re-admit exported transactions in order.
+///
+/// Each goes through the pool's acceptance rules again, so those with a
+/// nonce already used or that the sender can no longer pay for are dropped.
+/// Those ahead of the sender's next nonce are queued.
+pub fn import_mempool(raw_txs: &[String], state: &State, pool: &TransactionPool, chain_id: u64) -> MempoolImport {
+    let mut outcome = MempoolImport::default();
+    for raw_tx in raw_txs {
+        let result = decode_signed_transaction(raw_tx, pool.max_tx_size())
+            .and_then(|tx| validate_transaction(&tx, state, Some(pool), chain_id).map(|()| tx))
+            .map_err(|e| e.message)
+            .and_then(|tx| {
+                let sender = tx.sender();
+                if tx.tx.nonce > pending_nonce(state, Some(pool), &sender) {
+                    state.queue_transaction(tx).map(|()| false)
+                } else {
+                    pool.add_transaction(tx).map(|_| true).map_err(|e| e.to_string())
+                }
+            });
+        match result {
+            Ok(true) => outcome.admitted += 1,
+            Ok(false) => outcome.queued += 1,
+            Err(e) => {
+                tracing::debug!("Dropped imported transaction: {}", e);
+                outcome.dropped += 1;
+            }
+        }
+    }
+    outcome
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{handle_method, JsonRpcRequest};
+    use merklith_types::{Address, SignedTransaction, Transaction, U256};
+    use std::sync::Arc;
+
+    fn open_state(dir: &std::path::Path, keypair: &merklith_crypto::Keypair) -> Arc<State> {
+        let mut genesis = State::devnet_genesis();
+        genesis.chain_config.chain_id = 1337;
+        genesis.add_alloc(keypair.address(), U256::from(1_000_000u64));
+        Arc::new(State::with_genesis(
+            dir.to_path_buf(),
+            genesis,
+            merklith_storage::PruningConfig::archive(),
+        ))
+    }
+
+    #[test]
+    fn test_export_import_mempool() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let keypair = merklith_crypto::Keypair::from_seed(&[13u8; 32]);
+        let sign = |nonce: u64| {
+            let tx = Transaction::new(1337, nonce, Some(Address::from_bytes([1u8; 20])), U256::ONE, 21000, U256::ONE, U256::ZERO);
+            let (signature, public_key) = keypair.sign_transaction(&tx);
+            SignedTransaction::new(tx, signature, public_key)
+        };
+
+        let state = open_state(temp_dir.path(), &keypair);
+        let pool = TransactionPool::default();
+        pool.add_transaction(sign(0)).unwrap();
+        pool.add_transaction(sign(1)).unwrap();
+        state.queue_transaction(sign(3)).unwrap();
+
+        let request = JsonRpcRequest {
+            jsonrpc: "2.0".to_string(),
+            method: "merklith_exportMempool".to_string(),
+            params: vec![],
+            id: Some(serde_json::json!(1)),
+        };
+        let vm = merklith_vm::MerklithVM::new().unwrap();
+        let result = handle_method(&request, state.clone(), Some(&pool), None, 1337, &vm, None, None).result.unwrap();
+        let exported: Vec<String> = serde_json::from_value(result).unwrap();
+        assert_eq!(exported.len(), 3);
+
+        // Nonce 0 is mined before the restart, making its export stale
+        state.produce_block(&Address::from_bytes([0xAA; 20]), vec![sign(0)], false).unwrap();
+        drop(state);
+
+        let state = open_state(temp_dir.path(), &keypair);
+        let pool = TransactionPool::default();
+        let outcome = import_mempool(&exported, &state, &pool, 1337);
+        assert_eq!(outcome, MempoolImport { admitted: 1, queued: 1, dropped: 1 });
+        assert_eq!(pool.get_pending(10), vec![sign(1)]);
+        assert!(state.is_queued(&sign(3).hash()));
+
+        // Importing again admits nothing new
+        let again = import_mempool(&exported, &state, &pool, 1337);
+        assert_eq!(again.admitted, 0);
+    }
+}
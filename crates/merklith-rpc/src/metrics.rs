@@ -0,0 +1,61 @@
+//! Per-method RPC metrics.
+//!
+//! Request counts and latencies are labelled by method name and registered
+//! in a Prometheus registry, so they show up on the node's `/metrics` endpoint.
+
+use prometheus::{HistogramOpts, HistogramVec, IntCounterVec, Opts, Registry};
+use std::time::Duration;
+
+/// Label used for methods the server does not know, to bound label cardinality
+pub const UNKNOWN_METHOD: &str = "unknown";
+
+/// Per-method request counters and latency histograms.
+#[derive(Clone)]
+pub struct RpcMetrics {
+    requests: IntCounterVec,
+    duration: HistogramVec,
+}
+
+impl RpcMetrics {
+    /// Create the metrics and register them in `registry`.
+    pub fn new(registry: &Registry) -> Result<Self, prometheus::Error> {
+        let requests = IntCounterVec::new(
+            Opts::new(
+                "merklith_rpc_method_requests_total",
+                "RPC requests by method",
+            ),
+            &["method"],
+        )?;
+        registry.register(Box::new(requests.clone()))?;
+
+        let duration = HistogramVec::new(
+            HistogramOpts::new(
+                "merklith_rpc_method_duration_seconds",
+                "RPC request duration by method",
+            )
+            .buckets(vec![0.0005, 0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0, 5.0]),
+            &["method"],
+        )?;
+        registry.register(Box::new(duration.clone()))?;
+
+        Ok(Self { requests, duration })
+    }
+
+    /// Record one call of `method` that took `elapsed`.
+    pub fn observe(&self, method: &str, elapsed: Duration) {
+        self.requests.with_label_values(&[method]).inc();
+        self.duration
+            .with_label_values(&[method])
+            .observe(elapsed.as_secs_f64());
+    }
+
+    /// Number of calls recorded for `method`.
+    pub fn request_count(&self, method: &str) -> u64 {
+        self.requests.with_label_values(&[method]).get()
+    }
+
+    /// Total seconds spent in `method`.
+    pub fn total_duration(&self, method: &str) -> f64 {
+        self.duration.with_label_values(&[method]).get_sample_sum()
+    }
+}
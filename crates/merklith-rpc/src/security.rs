@@ -309,6 +309,11 @@ impl SecurityManager {
         self.rate_limiter.check_ip_rate(ip)
     }
 
+    /// Check per-address rate limit
+    pub fn check_address_rate(&self, address: &Address) -> Result<(), SecurityError> {
+        self.rate_limiter.check_address_rate(address)
+    }
+
     /// Cleanup old entries
     pub fn cleanup(&self) -> Result<(), SecurityError> {
         self.replay_protection.cleanup()
@@ -0,0 +1,334 @@
+//! Block submission for external validator software
+//!
+//! `merklith_submitBlock(blockHex)` takes a borsh-encoded block, checks its
+//! proposer with the consensus engine, executes it on top of the head and
+//! broadcasts it to peers. Only callers holding the admin token may submit.
+//! A block's attestation fields carry the committee's aggregate signature on
+//! its parent, verified before the block is executed.
+
+use merklith_consensus::{AggregateAttestation, ConsensusEngine, ContributionTracker};
+use merklith_core::state_machine::{ParentAttestation, State};
+use merklith_network::NetworkCommand;
+use merklith_txpool::pool::RemovalReason;
+use merklith_txpool::TransactionPool;
+use merklith_types::Block;
+use parking_lot::RwLock;
+use serde_json::Value;
+use tokio::sync::mpsc;
+
+use crate::{invalid_param, JsonRpcError, JsonRpcRequest, JsonRpcResponse};
+
+/// Largest accepted block encoding
+pub const MAX_BLOCK_SIZE: usize = 8 * 1024 * 1024;
+
+/// Validate, execute and broadcast the block in `block_hex`, returning
+/// its hash. A refused block leaves the state untouched. The proposer is
+/// credited in `contributions` as well as in the consensus engine.
+pub fn submit_block(
+    block_hex: &str,
+    state: &State,
+    pool: Option<&TransactionPool>,
+    consensus: &RwLock<ConsensusEngine>,
+    contributions: Option<&RwLock<ContributionTracker>>,
+    network: Option<&mpsc::Sender<NetworkCommand>>,
+) -> Result<[u8; 32], JsonRpcError> {
+    let block = decode_block(block_hex)?;
+    let rejected = |message: String| JsonRpcError { code: -32000, message, data: None };
+
+    // A block opening an epoch is judged by the new active set
+    if block.header.number == state.block_number() + 1 {
+        consensus.write().maybe_rotate(block.header.number);
+    }
+    consensus
+        .read()
+        .validate_block(&block)
+        .map_err(|e| rejected(format!("Block rejected: {}", e)))?;
+    let parent_hash = *block.header.parent_hash.as_bytes();
+    let parent_attestation =
+        AggregateAttestation::from_header(&block.header, block.header.number.saturating_sub(1), parent_hash);
+    if let Some(aggregate) = &parent_attestation {
+        if aggregate.signer_count() != block.header.attestation_count as usize {
+            return Err(rejected("Block rejected: attestation count does not match the bitfield".to_string()));
+        }
+        consensus
+            .read()
+            .verify_aggregate(aggregate)
+            .map_err(|e| rejected(format!("Block rejected: parent attestation: {}", e)))?;
+    }
+    let result = state
+        .import_block(&block)
+        .map_err(|e| rejected(format!("Block rejected: {}", e)))?;
+
+    if let Some(aggregate) = &parent_attestation {
+        state.attach_attestation(result.block_number, ParentAttestation {
+            signature: aggregate.signature.as_bytes().to_vec(),
+            bitfield: aggregate.bitfield.clone(),
+        });
+        if let Err(e) = consensus.write().import_aggregate(aggregate.clone()) {
+            tracing::warn!("Failed to keep the attestations of block #{}: {}", result.block_number - 1, e);
+        }
+    }
+    consensus
+        .write()
+        .observe_block(result.block_number, block.header.proposer, parent_attestation.as_ref());
+    if let Some(contributions) = contributions {
+        contributions.write().record_block_production(block.header.proposer, result.block_number);
+    }
+    if let Some(pool) = pool {
+        for tx in &block.transactions {
+            pool.remove_transaction(&tx.hash().to_string(), RemovalReason::Mined);
+        }
+    }
+    if let Some(network) = network {
+        let _ = network.try_send(NetworkCommand::BroadcastBlock {
+            number: result.block_number,
+            hash: result.block_hash,
+            parent_hash,
+        });
+    }
+    tracing::info!(
+        "Accepted submitted block #{} from {} ({} txs)",
+        result.block_number,
+        block.header.proposer,
+        result.transactions_count
+    );
+    Ok(result.block_hash)
+}
+
+fn decode_block(block_hex: &str) -> Result<Block, JsonRpcError> {
+    let invalid = |message: String| invalid_param("block", message);
+    let raw = block_hex.strip_prefix("0x").unwrap_or(block_hex);
+    if raw.len().div_ceil(2) > MAX_BLOCK_SIZE {
+        return Err(invalid(format!("Block exceeds {} bytes", MAX_BLOCK_SIZE)));
+    }
+    let bytes = hex::decode(raw).map_err(|_| invalid("Invalid block hex".to_string()))?;
+    borsh::from_slice(&bytes).map_err(|_| invalid("Invalid block payload (expected borsh Block)".to_string()))
+}
+
+/// Serve `merklith_submitBlock(blockHex)`
+pub(crate) fn handle_submit_block(
+    req: &JsonRpcRequest,
+    state: &State,
+    pool: Option<&TransactionPool>,
+    consensus: Option<&RwLock<ConsensusEngine>>,
+    contributions: Option<&RwLock<ContributionTracker>>,
+    network: Option<&mpsc::Sender<NetworkCommand>>,
+) -> JsonRpcResponse {
+    let result = consensus
+        .ok_or_else(|| JsonRpcError {
+            code: -32601,
+            message: "Block submission is not enabled on this node".to_string(),
+            data: None,
+        })
+        .and_then(|consensus| {
+            let block_hex = req.params.first()
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| invalid_param("block", "Expected a hex-encoded block"))?;
+            submit_block(block_hex, state, pool, consensus, contributions, network)
+        });
+    match result {
+        Ok(hash) => JsonRpcResponse {
+            jsonrpc: "2.0".to_string(),
+            result: Some(Value::String(format!("0x{}", hex::encode(hash)))),
+            error: None,
+            id: req.id.clone(),
+        },
+        Err(e) => JsonRpcResponse {
+            jsonrpc: "2.0".to_string(),
+            result: None,
+            error: Some(e),
+            id: req.id.clone(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use merklith_consensus::ValidatorSet;
+    use merklith_core::state_machine::transactions_root;
+    use merklith_crypto::Keypair;
+    use merklith_types::{Address, BlockHeader, Hash, SignedTransaction, Transaction, U256};
+
+    /// `block` signed by its proposer's `key`
+    fn signed(mut block: Block, key: &Keypair) -> Block {
+        block.header.proposer_signature = key.sign(block.header.signing_hash().as_bytes());
+        block
+    }
+
+    fn request(block: &Block) -> JsonRpcRequest {
+        JsonRpcRequest {
+            jsonrpc: "2.0".to_string(),
+            method: "merklith_submitBlock".to_string(),
+            params: vec![Value::String(format!("0x{}", hex::encode(borsh::to_vec(block).unwrap())))],
+            id: Some(serde_json::json!(1)),
+        }
+    }
+
+    #[test]
+    fn test_submit_block() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let genesis = State::devnet_genesis();
+        let chain_id = genesis.chain_config.chain_id;
+        let state = State::with_genesis(
+            temp_dir.path().to_path_buf(),
+            genesis,
+            merklith_storage::PruningConfig::archive(),
+        );
+        let key = Keypair::from_seed(&[0x77; 32]);
+        let validator = key.address();
+        let mut validators = ValidatorSet::new();
+        validators.add_validator(validator, 1000);
+        let consensus = RwLock::new(ConsensusEngine::new(validators, 2).with_proposer_key(validator, key.public_key()));
+        let contributions = RwLock::new(ContributionTracker::new());
+        let (network_tx, mut network_rx) = mpsc::channel(4);
+
+        let sender = &State::devnet_accounts()[0];
+        let recipient = Address::from_bytes([0x42; 20]);
+        let tx = Transaction::new(chain_id, 0, Some(recipient), U256::from(5u64), 21000, U256::ONE, U256::ZERO);
+        let (signature, public_key) = sender.sign_transaction(&tx);
+        let transactions = vec![SignedTransaction::new(tx, signature, public_key)];
+        let block_by = |proposer: Address| {
+            let mut header = BlockHeader::new(
+                state.block_hash(),
+                1,
+                state.get_block(0).unwrap().timestamp + 1,
+                state.next_gas_limit(),
+                proposer,
+            );
+            header.transactions_root = Hash::from_bytes(transactions_root(&transactions));
+            Block::new(header, transactions.clone())
+        };
+
+        // A block from someone outside the validator set changes nothing
+        let outsider = handle_submit_block(&request(&block_by(Address::from_bytes([0x66; 20]))), &state, None, Some(&consensus), Some(&contributions), Some(&network_tx));
+        assert_eq!(outsider.error.unwrap().code, -32000);
+        assert_eq!(state.block_number(), 0);
+        assert_eq!(state.balance(&recipient), U256::ZERO);
+        assert!(network_rx.try_recv().is_err());
+
+        // So does one its proposer did not sign
+        let unsigned = handle_submit_block(&request(&block_by(validator)), &state, None, Some(&consensus), Some(&contributions), Some(&network_tx));
+        assert_eq!(unsigned.error.unwrap().code, -32000);
+        assert_eq!(state.block_number(), 0);
+
+        let block = signed(block_by(validator), &key);
+        let accepted = handle_submit_block(&request(&block), &state, None, Some(&consensus), Some(&contributions), Some(&network_tx));
+        assert!(accepted.error.is_none(), "{:?}", accepted.error);
+        let head = state.get_block(1).unwrap();
+        // The block is known by the hash its proposer signed
+        assert_eq!(head.hash, *block.hash().as_bytes());
+        assert_eq!(state.block_hash(), block.hash());
+        assert_eq!(accepted.result.unwrap(), Value::String(format!("0x{}", hex::encode(head.hash))));
+        assert_eq!(head.proposer, validator);
+        assert_eq!(state.balance(&recipient), U256::from(5u64));
+        assert!(matches!(network_rx.try_recv(), Ok(NetworkCommand::BroadcastBlock { number: 1, .. })));
+        // The leaderboard sees the proposer
+        assert_eq!(contributions.read().get_top_contributors(1)[0].0, validator);
+
+        // The same block no longer extends the head
+        let replayed = handle_submit_block(&request(&block), &state, None, Some(&consensus), Some(&contributions), Some(&network_tx));
+        assert!(replayed.error.is_some());
+        assert_eq!(state.block_number(), 1);
+    }
+
+    #[test]
+    fn test_submitted_block_rotates_epoch() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let state = State::with_genesis(
+            temp_dir.path().to_path_buf(),
+            State::devnet_genesis(),
+            merklith_storage::PruningConfig::archive(),
+        );
+        let keys = [Keypair::from_seed(&[0x77; 32]), Keypair::from_seed(&[0x78; 32])];
+        let (dropped, kept) = (keys[0].address(), keys[1].address());
+        let mut validators = ValidatorSet::new();
+        validators.add_validator(dropped, 1000);
+        validators.add_validator(kept, 1000);
+        validators.contribution_tracker_mut().record_block_production(kept, 0);
+        let mut consensus = ConsensusEngine::new(validators, 2).with_epochs(1, 1);
+        for key in &keys {
+            consensus = consensus.with_proposer_key(key.address(), key.public_key());
+        }
+        let consensus = RwLock::new(consensus);
+        let block_by = |proposer: Address| {
+            let mut header = BlockHeader::new(
+                state.block_hash(),
+                1,
+                state.get_block(0).unwrap().timestamp + 1,
+                state.next_gas_limit(),
+                proposer,
+            );
+            header.transactions_root = Hash::from_bytes(transactions_root(&[]));
+            Block::new(header, Vec::new())
+        };
+
+        // Block 1 opens epoch 1, whose single seat goes to the contributor
+        let outside = handle_submit_block(&request(&signed(block_by(dropped), &keys[0])), &state, None, Some(&consensus), None, None);
+        assert!(outside.error.is_some());
+        assert_eq!(consensus.read().validator_set().epoch(), Some(1));
+        let accepted = handle_submit_block(&request(&signed(block_by(kept), &keys[1])), &state, None, Some(&consensus), None, None);
+        assert!(accepted.error.is_none(), "{:?}", accepted.error);
+        assert_eq!(state.block_number(), 1);
+    }
+
+    #[test]
+    fn test_submitted_parent_attestation_verified() {
+        use merklith_consensus::{proof_of_possession_message, Attestation, AttestationPool};
+        use merklith_crypto::BLSKeypair;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let state = State::with_genesis(
+            temp_dir.path().to_path_buf(),
+            State::devnet_genesis(),
+            merklith_storage::PruningConfig::archive(),
+        );
+        let signer = Keypair::from_seed(&[0x77; 32]);
+        let validator = signer.address();
+        let key = BLSKeypair::from_bytes(&[5u8; 32]).unwrap();
+        let proof = key.sign(&proof_of_possession_message(&key.public_key()));
+        let committee = vec![(validator, key.public_key(), proof)];
+        let mut validators = ValidatorSet::new();
+        validators.add_validator(validator, 1000);
+        let consensus = RwLock::new(
+            ConsensusEngine::new(validators, 2)
+                .with_proposer_key(validator, signer.public_key())
+                .with_finality_threshold(1)
+                .with_attestation_committee(committee.clone())
+                .unwrap(),
+        );
+
+        // The committee's attestation of genesis, aggregated elsewhere
+        let genesis_hash = *state.block_hash().as_bytes();
+        let mut attestation = Attestation::new(0, genesis_hash, validator, Vec::new());
+        attestation.signature = key.sign(&attestation.signing_message()).as_bytes().to_vec();
+        let mut pool = AttestationPool::new().with_committee(committee).unwrap();
+        assert!(pool.add_attestation(attestation));
+        let aggregate = pool.aggregate(0, genesis_hash).unwrap();
+
+        let block_with = |bitfield: Vec<u8>| {
+            let mut header = BlockHeader::new(
+                state.block_hash(),
+                1,
+                state.get_block(0).unwrap().timestamp + 1,
+                state.next_gas_limit(),
+                validator,
+            );
+            header.transactions_root = Hash::from_bytes(transactions_root(&[]));
+            AggregateAttestation { bitfield, ..aggregate.clone() }.apply_to(&mut header);
+            signed(Block::new(header, Vec::new()), &signer)
+        };
+
+        // Claiming a signer that did not sign leaves the chain as it was
+        let forged = handle_submit_block(&request(&block_with(vec![0x02])), &state, None, Some(&consensus), None, None);
+        assert_eq!(forged.error.unwrap().code, -32000);
+        assert_eq!(state.block_number(), 0);
+
+        let accepted = handle_submit_block(&request(&block_with(vec![0x01])), &state, None, Some(&consensus), None, None);
+        assert!(accepted.error.is_none(), "{:?}", accepted.error);
+        let carried = state.get_block(1).unwrap().parent_attestation.unwrap();
+        assert_eq!(carried.bitfield, vec![0x01]);
+        assert_eq!(carried.signature, aggregate.signature.as_bytes().to_vec());
+        assert!(consensus.read().is_finalized(0));
+    }
+}
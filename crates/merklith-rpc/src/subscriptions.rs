@@ -6,11 +6,19 @@
 //! - New logs
 //! - Syncing status
 
-use merklith_types::{Block, Hash, Transaction};
+#![allow(non_snake_case)]
+
+use futures::{SinkExt, StreamExt};
+use merklith_core::state_machine::{LogUpdate, State};
+use merklith_types::Hash;
 use serde::Serialize;
+use serde_json::Value;
 use std::collections::HashMap;
+use std::net::SocketAddr;
 use std::sync::atomic::{AtomicU64, Ordering};
-use tokio::sync::mpsc;
+use std::sync::Arc;
+use tokio::sync::{broadcast, mpsc, watch};
+use tokio_tungstenite::tungstenite::Message;
 
 /// Subscription type.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -27,7 +35,7 @@ pub enum SubscriptionType {
 
 impl SubscriptionType {
     /// Parse from string.
-    pub fn from_str(s: &str) -> Option<Self> {
+    pub fn parse(s: &str) -> Option<Self> {
         match s {
             "newHeads" => Some(Self::NewHeads),
             "newPendingTransactions" => Some(Self::NewPendingTransactions),
@@ -108,8 +116,12 @@ pub enum SubscriptionEvent {
         topics: Vec<String>,
         data: String,
         block_number: u64,
+        block_hash: Hash,
         transaction_hash: Hash,
+        transaction_index: u64,
         log_index: u64,
+        /// Set when the log's block was reorged out of the canonical chain
+        removed: bool,
     },
     /// Syncing status changed
     SyncingStatus {
@@ -126,7 +138,7 @@ pub enum SubscriptionResult {
     /// New block header
     BlockHeader {
         subscription: SubscriptionId,
-        result: BlockHeaderResult,
+        result: Box<BlockHeaderResult>,
     },
     /// Transaction hash
     TransactionHash {
@@ -251,17 +263,25 @@ impl SubscriptionManager {
         &self,
         event: &SubscriptionEvent,
     ) {
-        for subscription in self.subscriptions.values() {
-            if Self::should_send(subscription, event) {
-                let result = Self::create_result(&subscription.id, event
-                );
-                
-                // Send to subscriber (ignore errors)
-                let _ = subscription.sender.send(result).await;
-            }
+        for (sender, result) in self.deliveries(event) {
+            // Send to subscriber (ignore errors)
+            let _ = sender.send(result).await;
         }
     }
 
+    /// Results for every matching subscriber, to send once the manager is
+    /// no longer borrowed.
+    pub fn deliveries(
+        &self,
+        event: &SubscriptionEvent,
+    ) -> Vec<(mpsc::Sender<SubscriptionResult>, SubscriptionResult)> {
+        self.subscriptions
+            .values()
+            .filter(|subscription| Self::should_send(subscription, event))
+            .map(|subscription| (subscription.sender.clone(), Self::create_result(&subscription.id, event)))
+            .collect()
+    }
+
     /// Check if subscription should receive this event.
     fn should_send(
         subscription: &Subscription,
@@ -292,7 +312,7 @@ impl SubscriptionManager {
             SubscriptionEvent::NewBlock { hash, number, parent_hash } => {
                 SubscriptionResult::BlockHeader {
                     subscription: subscription_id.clone(),
-                    result: BlockHeaderResult {
+                    result: Box::new(BlockHeaderResult {
                         parentHash: format!("0x{}", hex::encode(parent_hash.as_bytes())),
                         sha3Uncles: "0x1dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d49347".to_string(),
                         miner: "0x0000000000000000000000000000000000000000".to_string(),
@@ -313,7 +333,7 @@ impl SubscriptionManager {
                         nonce: "0x0000000000000000".to_string(),
                         baseFeePerGas: Some("0x0".to_string()),
                         hash: format!("0x{}", hex::encode(hash.as_bytes())),
-                    },
+                    }),
                 }
             }
             SubscriptionEvent::NewTransaction { hash, .. } => {
@@ -322,7 +342,9 @@ impl SubscriptionManager {
                     result: format!("0x{}", hex::encode(hash.as_bytes())),
                 }
             }
-            SubscriptionEvent::NewLog { address, topics, data, block_number, transaction_hash, log_index } => {
+            SubscriptionEvent::NewLog {
+                address, topics, data, block_number, block_hash, transaction_hash, transaction_index, log_index, removed,
+            } => {
                 SubscriptionResult::LogEntry {
                     subscription: subscription_id.clone(),
                     result: LogResult {
@@ -331,10 +353,10 @@ impl SubscriptionManager {
                         data: data.clone(),
                         blockNumber: format!("0x{:x}", block_number),
                         transactionHash: format!("0x{}", hex::encode(transaction_hash.as_bytes())),
-                        transactionIndex: "0x0".to_string(),
-                        blockHash: "0x0000000000000000000000000000000000000000000000000000000000000000".to_string(),
+                        transactionIndex: format!("0x{:x}", transaction_index),
+                        blockHash: format!("0x{}", hex::encode(block_hash.as_bytes())),
                         logIndex: format!("0x{:x}", log_index),
-                        removed: false,
+                        removed: *removed,
                     },
                 }
             }
@@ -376,6 +398,199 @@ impl Default for SubscriptionManager {
     }
 }
 
+/// Subscriptions shared by every WebSocket connection.
+type SharedManager = Arc<parking_lot::Mutex<SubscriptionManager>>;
+
+/// Serve `eth_subscribe` / `eth_unsubscribe` over WebSocket on `addr` until
+/// `shutdown` changes or its sender is dropped. Returns the bound address.
+pub async fn serve(
+    addr: SocketAddr,
+    state: Arc<State>,
+    mut shutdown: watch::Receiver<()>,
+) -> std::io::Result<SocketAddr> {
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    let local_addr = listener.local_addr()?;
+    let manager: SharedManager = Arc::new(parking_lot::Mutex::new(SubscriptionManager::default()));
+
+    tokio::spawn(feed_chain(state.subscribe_logs(), manager.clone(), shutdown.clone()));
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = shutdown.changed() => break,
+                accepted = listener.accept() => match accepted {
+                    Ok((stream, _)) => {
+                        tokio::spawn(serve_connection(stream, manager.clone()));
+                    }
+                    Err(e) => tracing::warn!("WebSocket accept failed: {}", e),
+                },
+            }
+        }
+    });
+
+    Ok(local_addr)
+}
+
+/// Turn blocks entering and leaving the chain into subscription events.
+async fn feed_chain(
+    mut updates: broadcast::Receiver<LogUpdate>,
+    manager: SharedManager,
+    mut shutdown: watch::Receiver<()>,
+) {
+    loop {
+        let update = tokio::select! {
+            _ = shutdown.changed() => return,
+            update = updates.recv() => update,
+        };
+        let update = match update {
+            Ok(update) => update,
+            Err(broadcast::error::RecvError::Lagged(missed)) => {
+                tracing::warn!("WebSocket subscribers missed {} blocks", missed);
+                continue;
+            }
+            Err(broadcast::error::RecvError::Closed) => return,
+        };
+        for event in events_for(&update) {
+            let deliveries = manager.lock().deliveries(&event);
+            for (sender, result) in deliveries {
+                let _ = sender.send(result).await;
+            }
+        }
+    }
+}
+
+/// Events for one block update: its header when added, then every log it
+/// emitted, flagged `removed` when the block was reverted.
+fn events_for(update: &LogUpdate) -> Vec<SubscriptionEvent> {
+    let block = &update.block;
+    let block_hash = Hash::from_bytes(block.hash);
+    let mut events = Vec::new();
+    if !update.removed {
+        events.push(SubscriptionEvent::NewBlock {
+            hash: block_hash,
+            number: block.number,
+            parent_hash: Hash::from_bytes(block.parent_hash),
+        });
+    }
+    for receipt in &update.receipts {
+        for log in &receipt.logs {
+            events.push(SubscriptionEvent::NewLog {
+                address: format!("0x{}", log.address.to_hex()),
+                topics: log.topics.iter().map(|t| t.to_string()).collect(),
+                data: format!("0x{}", hex::encode(&log.data)),
+                block_number: log.block_number,
+                block_hash,
+                transaction_hash: receipt.tx_hash,
+                transaction_index: log.tx_index as u64,
+                log_index: log.log_index as u64,
+                removed: update.removed,
+            });
+        }
+    }
+    events
+}
+
+/// One WebSocket client: answers subscribe calls and forwards its results.
+async fn serve_connection(stream: tokio::net::TcpStream, manager: SharedManager) {
+    let mut ws = match tokio_tungstenite::accept_async(stream).await {
+        Ok(ws) => ws,
+        Err(e) => {
+            tracing::debug!("WebSocket handshake failed: {}", e);
+            return;
+        }
+    };
+    let (sender, mut results) = mpsc::channel(256);
+    let mut owned = Vec::new();
+
+    loop {
+        let outgoing = tokio::select! {
+            result = results.recv() => match result {
+                Some(result) => serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "method": "eth_subscription",
+                    "params": result,
+                }),
+                None => break,
+            },
+            incoming = ws.next() => match incoming {
+                Some(Ok(Message::Text(text))) => handle_call(&text, &manager, &sender, &mut owned),
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => continue,
+            },
+        };
+        if ws.send(Message::Text(outgoing.to_string())).await.is_err() {
+            break;
+        }
+    }
+
+    let mut manager = manager.lock();
+    for id in &owned {
+        manager.unsubscribe(id);
+    }
+}
+
+/// Answer one JSON-RPC call made over the WebSocket.
+fn handle_call(
+    text: &str,
+    manager: &SharedManager,
+    sender: &mpsc::Sender<SubscriptionResult>,
+    owned: &mut Vec<SubscriptionId>,
+) -> Value {
+    let request: Value = match serde_json::from_str(text) {
+        Ok(request) => request,
+        Err(_) => return rpc_error(Value::Null, -32700, "Parse error"),
+    };
+    let id = request.get("id").cloned().unwrap_or(Value::Null);
+    let params = request.get("params").and_then(Value::as_array).cloned().unwrap_or_default();
+
+    match request.get("method").and_then(Value::as_str) {
+        Some("eth_subscribe") => {
+            let subscription_type = match params.first().and_then(Value::as_str).and_then(SubscriptionType::parse) {
+                Some(t @ (SubscriptionType::Logs | SubscriptionType::NewHeads)) => t,
+                _ => return rpc_error(id, -32602, "Unsupported subscription"),
+            };
+            let filter = match subscription_type {
+                SubscriptionType::Logs => Some(parse_filter(params.get(1))),
+                _ => None,
+            };
+            let subscription = manager.lock().subscribe(subscription_type, filter, sender.clone());
+            owned.push(subscription.clone());
+            serde_json::json!({ "jsonrpc": "2.0", "id": id, "result": subscription })
+        }
+        Some("eth_unsubscribe") => {
+            let subscription = params.first().and_then(Value::as_str).unwrap_or_default().to_string();
+            // Only the connection that made a subscription may cancel it
+            let removed = match owned.iter().position(|owned| *owned == subscription) {
+                Some(index) => {
+                    owned.swap_remove(index);
+                    manager.lock().unsubscribe(&subscription)
+                }
+                None => false,
+            };
+            serde_json::json!({ "jsonrpc": "2.0", "id": id, "result": removed })
+        }
+        _ => rpc_error(id, -32601, "Method not found"),
+    }
+}
+
+/// Log filter from `{"address": "0x.." | ["0x..", ..]}`; addresses are
+/// matched in lowercase.
+fn parse_filter(filter: Option<&Value>) -> LogFilter {
+    let addresses = match filter.and_then(|f| f.get("address")) {
+        Some(Value::String(address)) => vec![address.to_lowercase()],
+        Some(Value::Array(addresses)) => addresses
+            .iter()
+            .filter_map(Value::as_str)
+            .map(str::to_lowercase)
+            .collect(),
+        _ => Vec::new(),
+    };
+    LogFilter { addresses, ..LogFilter::default() }
+}
+
+fn rpc_error(id: Value, code: i32, message: &str) -> Value {
+    serde_json::json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -424,22 +639,22 @@ mod tests {
     #[test]
     fn test_subscription_type_parsing() {
         assert_eq!(
-            SubscriptionType::from_str("newHeads"),
+            SubscriptionType::parse("newHeads"),
             Some(SubscriptionType::NewHeads)
         );
         assert_eq!(
-            SubscriptionType::from_str("newPendingTransactions"),
+            SubscriptionType::parse("newPendingTransactions"),
             Some(SubscriptionType::NewPendingTransactions)
         );
         assert_eq!(
-            SubscriptionType::from_str("logs"),
+            SubscriptionType::parse("logs"),
             Some(SubscriptionType::Logs)
         );
         assert_eq!(
-            SubscriptionType::from_str("syncing"),
+            SubscriptionType::parse("syncing"),
             Some(SubscriptionType::Syncing)
         );
-        assert_eq!(SubscriptionType::from_str("unknown"), None);
+        assert_eq!(SubscriptionType::parse("unknown"), None);
     }
 
     #[test]
@@ -459,4 +674,99 @@ mod tests {
 
         assert!(SubscriptionManager::should_send(&sub, &event));
     }
+
+    #[test]
+    fn test_removed_log_is_flagged() {
+        let event = SubscriptionEvent::NewLog {
+            address: "0x0000000000000000000000000000000000000001".to_string(),
+            topics: vec![],
+            data: "0x".to_string(),
+            block_number: 7,
+            block_hash: merklith_types::Hash::ZERO,
+            transaction_hash: merklith_types::Hash::ZERO,
+            transaction_index: 0,
+            log_index: 0,
+            removed: true,
+        };
+
+        let result = SubscriptionManager::create_result(&"0x1".to_string(), &event);
+        let json = serde_json::to_value(&result).unwrap();
+        assert_eq!(json["result"]["removed"], true);
+        assert_eq!(json["result"]["blockNumber"], "0x7");
+    }
+
+    async fn next_json(
+        ws: &mut tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>,
+    ) -> Value {
+        let message = tokio::time::timeout(std::time::Duration::from_secs(5), ws.next())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        serde_json::from_str(message.to_text().unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_reverted_logs_are_resent_removed() {
+        use merklith_storage::PruningConfig;
+        use merklith_types::{Address, Ed25519PublicKey, Ed25519Signature, GenesisConfig, SignedTransaction, U256};
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let public_key = Ed25519PublicKey::from_bytes([10u8; 32]);
+        let sender = public_key.to_address();
+        let validator = Address::from_bytes([0xAA; 20]);
+        let mut genesis = GenesisConfig::devnet();
+        genesis.add_alloc(sender, U256::from(1_000_000u64));
+        let state = Arc::new(State::with_genesis(temp_dir.path().to_path_buf(), genesis, PruningConfig::archive()));
+        // PUSH1 0x01 (topic); PUSH2 0xbeef (data); LOG1; STOP
+        let contract = state
+            .deploy_contract(&sender, vec![0x60, 0x01, 0x61, 0xbe, 0xef, 0xa1, 0x00])
+            .unwrap();
+        let other = state.deploy_contract(&sender, vec![0x00]).unwrap();
+        state.produce_block(&validator, vec![], true).unwrap();
+
+        let (_shutdown, shutdown_rx) = watch::channel(());
+        let addr = serve("127.0.0.1:0".parse().unwrap(), state.clone(), shutdown_rx).await.unwrap();
+        let (mut ws, _) = tokio_tungstenite::connect_async(format!("ws://{}", addr)).await.unwrap();
+
+        let subscribe = |id: u64, address: Address| serde_json::json!({
+            "jsonrpc": "2.0", "id": id, "method": "eth_subscribe",
+            "params": ["logs", { "address": [format!("0x{}", address.to_hex().to_uppercase())] }],
+        });
+        ws.send(Message::Text(subscribe(1, contract).to_string())).await.unwrap();
+        let subscription = next_json(&mut ws).await["result"].as_str().unwrap().to_string();
+        // A subscription to another contract sees none of these logs
+        ws.send(Message::Text(subscribe(2, other).to_string())).await.unwrap();
+        assert_eq!(next_json(&mut ws).await["id"], 2);
+
+        let tx = merklith_types::Transaction::new(
+            state.chain_id(), state.nonce(&sender), Some(contract), U256::ZERO, 100_000, U256::ONE, U256::ZERO,
+        );
+        let tx = SignedTransaction::new(tx, Ed25519Signature::from_bytes([0u8; 64]), public_key);
+        let block = state.produce_block(&validator, vec![tx], false).unwrap();
+        let block_hash = format!("0x{}", hex::encode(block.block_hash));
+
+        let added = next_json(&mut ws).await;
+        assert_eq!(added["method"], "eth_subscription");
+        assert_eq!(added["params"]["subscription"], subscription.as_str());
+        assert_eq!(added["params"]["result"]["removed"], false);
+        assert_eq!(added["params"]["result"]["blockHash"], block_hash.as_str());
+        assert_eq!(added["params"]["result"]["data"], "0xbeef");
+
+        state.revert_to(block.block_number - 1).unwrap();
+        let removed = next_json(&mut ws).await;
+        assert_eq!(removed["params"]["subscription"], subscription.as_str());
+        assert_eq!(removed["params"]["result"]["removed"], true);
+        assert_eq!(removed["params"]["result"]["blockHash"], block_hash.as_str());
+        assert_eq!(removed["params"]["result"]["transactionHash"], added["params"]["result"]["transactionHash"]);
+        assert_eq!(removed["params"]["result"]["logIndex"], added["params"]["result"]["logIndex"]);
+
+        // The next message answers the unsubscribe: the other contract's
+        // subscription got nothing
+        ws.send(Message::Text(serde_json::json!({
+            "jsonrpc": "2.0", "id": 3, "method": "eth_unsubscribe", "params": [subscription],
+        }).to_string())).await.unwrap();
+        let unsubscribed = next_json(&mut ws).await;
+        assert_eq!((unsubscribed["id"].clone(), unsubscribed["result"].clone()), (serde_json::json!(3), Value::Bool(true)));
+    }
 }
@@ -10,7 +10,8 @@
 
 use std::collections::HashMap;
 use std::net::IpAddr;
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
+use parking_lot::Mutex;
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use serde::{Serialize, Deserialize};
 
@@ -60,7 +61,6 @@ pub enum Severity {
 /// IP reputation tracking
 #[derive(Debug, Clone)]
 struct IpReputation {
-    ip: IpAddr,
     request_count: u32,
     failed_attempts: u32,
     last_request: Instant,
@@ -70,9 +70,8 @@ struct IpReputation {
 }
 
 impl IpReputation {
-    fn new(ip: IpAddr) -> Self {
+    fn new() -> Self {
         Self {
-            ip,
             request_count: 0,
             failed_attempts: 0,
             last_request: Instant::now(),
@@ -166,7 +165,6 @@ impl TokenBucket {
 /// Transaction spam detection
 #[derive(Debug)]
 struct TransactionPattern {
-    from: String,
     count: u32,
     first_seen: Instant,
     last_seen: Instant,
@@ -195,6 +193,12 @@ pub struct SecurityManager {
 
 use std::collections::HashSet;
 
+impl Default for SecurityManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl SecurityManager {
     pub fn new() -> Self {
         Self {
@@ -230,12 +234,12 @@ impl SecurityManager {
         request_size: usize,
     ) -> Result<(), SecurityError> {
         // Check whitelist
-        if self.whitelist.lock().unwrap().contains(&ip) {
+        if self.whitelist.lock().contains(&ip) {
             return Ok(());
         }
         
         // Check blacklist
-        if self.blacklist.lock().unwrap().contains(&ip) {
+        if self.blacklist.lock().contains(&ip) {
             self.log_event(
                 SecurityEventType::IpBlocked,
                 ip.to_string(),
@@ -247,8 +251,8 @@ impl SecurityManager {
         }
         
         // Check IP reputation
-        let mut reputation = self.ip_reputation.lock().unwrap();
-        let rep = reputation.entry(ip).or_insert_with(|| IpReputation::new(ip));
+        let mut reputation = self.ip_reputation.lock();
+        let rep = reputation.entry(ip).or_insert_with(IpReputation::new);
         
         if rep.is_blocked() {
             self.log_event(
@@ -262,7 +266,7 @@ impl SecurityManager {
         }
         
         // Check rate limit
-        let mut limiters = self.rate_limiters.lock().unwrap();
+        let mut limiters = self.rate_limiters.lock();
         let bucket = limiters.entry(ip).or_insert_with(|| {
             TokenBucket::new(self.rate_limit, self.burst_size)
         });
@@ -305,14 +309,13 @@ impl SecurityManager {
     pub fn validate_transaction(
         &self,
         from: &str,
-        to: &str,
+        _to: &str,
         value: u128,
         data: &[u8],
     ) -> Result<(), SecurityError> {
         // Check for spam patterns
-        let mut patterns = self.tx_patterns.lock().unwrap();
+        let mut patterns = self.tx_patterns.lock();
         let pattern = patterns.entry(from.to_string()).or_insert(TransactionPattern {
-            from: from.to_string(),
             count: 0,
             first_seen: Instant::now(),
             last_seen: Instant::now(),
@@ -380,8 +383,8 @@ impl SecurityManager {
         ip: IpAddr,
         reason: &str,
     ) {
-        let mut reputation = self.ip_reputation.lock().unwrap();
-        let rep = reputation.entry(ip).or_insert_with(|| IpReputation::new(ip));
+        let mut reputation = self.ip_reputation.lock();
+        let rep = reputation.entry(ip).or_insert_with(IpReputation::new);
         
         rep.record_failure();
         
@@ -399,18 +402,13 @@ impl SecurityManager {
         &self,
         ip: IpAddr,
     ) -> Result<(), SecurityError> {
-        let reputation = self.ip_reputation.lock().unwrap();
+        let mut reputation = self.ip_reputation.lock();
         
-        if let Some(rep) = reputation.get(&ip) {
+        if let Some(rep) = reputation.get_mut(&ip) {
             // Check for DDoS indicators
             if rep.request_count > 10000 || rep.reputation_score < -50 {
-                drop(reputation);
-                
                 // Block the IP
-                let mut rep_mut = self.ip_reputation.lock().unwrap();
-                if let Some(r) = rep_mut.get_mut(&ip) {
-                    r.block_for(Duration::from_secs(BLOCK_DURATION_SECONDS * 24)); // 24 hours
-                }
+                rep.block_for(Duration::from_secs(BLOCK_DURATION_SECONDS * 24)); // 24 hours
                 
                 self.log_event(
                     SecurityEventType::DDoSSuspected,
@@ -433,10 +431,10 @@ impl SecurityManager {
     
     /// Add IP to whitelist
     pub fn whitelist_ip(&self, ip: IpAddr) {
-        self.whitelist.lock().unwrap().insert(ip);
+        self.whitelist.lock().insert(ip);
         
         // Remove from blacklist if present
-        self.blacklist.lock().unwrap().remove(&ip);
+        self.blacklist.lock().remove(&ip);
         
         self.log_event(
             SecurityEventType::IpUnblocked,
@@ -449,11 +447,11 @@ impl SecurityManager {
     
     /// Add IP to blacklist
     pub fn blacklist_ip(&self, ip: IpAddr, duration: Duration) {
-        self.blacklist.lock().unwrap().insert(ip);
-        self.whitelist.lock().unwrap().remove(&ip);
+        self.blacklist.lock().insert(ip);
+        self.whitelist.lock().remove(&ip);
         
-        let mut reputation = self.ip_reputation.lock().unwrap();
-        let rep = reputation.entry(ip).or_insert_with(|| IpReputation::new(ip));
+        let mut reputation = self.ip_reputation.lock();
+        let rep = reputation.entry(ip).or_insert_with(IpReputation::new);
         rep.block_for(duration);
         
         self.log_event(
@@ -483,8 +481,8 @@ impl SecurityManager {
             action_taken,
         };
         
-        let mut log = self.event_log.lock().unwrap();
-        log.push(event);
+        let mut log = self.event_log.lock();
+        log.push(event.clone());
         
         // Keep only last 10000 events
         if log.len() > 10000 {
@@ -505,7 +503,7 @@ impl SecurityManager {
         &self,
         limit: usize,
     ) -> Vec<SecurityEvent> {
-        let log = self.event_log.lock().unwrap();
+        let log = self.event_log.lock();
         log.iter().rev().take(limit).cloned().collect()
     }
     
@@ -516,31 +514,28 @@ impl SecurityManager {
     ) -> Option<(i32, bool)> {
         self.ip_reputation
             .lock()
-            .unwrap()
             .get(&ip)
             .map(|rep| (rep.reputation_score, rep.is_blocked()))
     }
     
     /// Get stats
     pub fn get_stats(&self) -> SecurityStats {
+        let reputation = self.ip_reputation.lock();
         SecurityStats {
-            total_ips_tracked: self.ip_reputation.lock().unwrap().len(),
-            blocked_ips: self
-                .ip_reputation
-                .lock()
-                .unwrap()
+            total_ips_tracked: reputation.len(),
+            blocked_ips: reputation
                 .values()
                 .filter(|r| r.is_blocked())
                 .count(),
-            total_events: self.event_log.lock().unwrap().len(),
-            whitelisted_ips: self.whitelist.lock().unwrap().len(),
-            blacklisted_ips: self.blacklist.lock().unwrap().len(),
+            total_events: self.event_log.lock().len(),
+            whitelisted_ips: self.whitelist.lock().len(),
+            blacklisted_ips: self.blacklist.lock().len(),
         }
     }
     
     /// Clean up old entries (call periodically)
     pub fn cleanup(&self) {
-        let mut reputation = self.ip_reputation.lock().unwrap();
+        let mut reputation = self.ip_reputation.lock();
         let now = Instant::now();
         
         // Remove entries older than 24 hours that aren't blocked
@@ -548,10 +543,10 @@ impl SecurityManager {
             !rep.is_blocked() && now.duration_since(rep.last_request) < Duration::from_secs(86400)
         });
         
-        let mut limiters = self.rate_limiters.lock().unwrap();
+        let mut limiters = self.rate_limiters.lock();
         limiters.clear(); // Reset rate limiters periodically
         
-        let mut patterns = self.tx_patterns.lock().unwrap();
+        let mut patterns = self.tx_patterns.lock();
         let now = Instant::now();
         patterns.retain(|_, pattern| {
             now.duration_since(pattern.last_seen) < SPAM_DETECTION_WINDOW
@@ -640,4 +635,29 @@ mod tests {
         // Should detect spam after threshold
         // This would require more iterations to trigger
     }
+    
+    #[test]
+    fn test_requests_after_panic_holding_lock() {
+        let manager = Arc::new(SecurityManager::with_rate_limit(10, 5));
+        let ip = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+        
+        // A panic while the reputation lock is held must not disable the manager
+        let reputation = manager.ip_reputation.clone();
+        let result = std::thread::spawn(move || {
+            let _guard = reputation.lock();
+            panic!("panic while holding security lock");
+        })
+        .join();
+        assert!(result.is_err());
+        
+        let checker = manager.clone();
+        std::thread::spawn(move || checker.check_request(ip, 1000))
+            .join()
+            .unwrap()
+            .unwrap();
+        
+        manager.blacklist_ip(ip, Duration::from_secs(60));
+        assert!(manager.check_request(ip, 1000).is_err());
+        assert_eq!(manager.get_stats().blacklisted_ips, 1);
+    }
 }
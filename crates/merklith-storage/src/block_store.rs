@@ -1,6 +1,7 @@
 //! Block Store - Persistent block storage
 
 use crate::StorageError;
+use crate::pruning::{PruningConfig, PruningMode};
 use std::path::PathBuf;
 use std::fs;
 use std::sync::Arc;
@@ -12,16 +13,26 @@ pub struct BlockStore {
     path: PathBuf,
     blocks: Arc<RwLock<HashMap<u64, Vec<u8>>>>, // number -> raw data
     latest: Arc<RwLock<u64>>,
+    pruning: PruningConfig,
+    /// Bodies below this number have already been pruned
+    pruned_below: Arc<RwLock<u64>>,
 }
 
 impl BlockStore {
     pub fn new(path: &std::path::Path) -> Result<Self, StorageError> {
+        Self::with_pruning(path, PruningConfig::archive())
+    }
+    
+    /// Create a block store that drops bodies outside the retention window
+    pub fn with_pruning(path: &std::path::Path, pruning: PruningConfig) -> Result<Self, StorageError> {
         fs::create_dir_all(path).map_err(|e| StorageError::Io(e.to_string()))?;
         
         let store = Self {
             path: path.to_path_buf(),
             blocks: Arc::new(RwLock::new(HashMap::new())),
             latest: Arc::new(RwLock::new(0)),
+            pruning,
+            pruned_below: Arc::new(RwLock::new(0)),
         };
         
         store.load_from_disk()?;
@@ -34,10 +45,13 @@ impl BlockStore {
         self.blocks.write().insert(number, data.clone());
         
         // Update latest
-        let mut latest = self.latest.write();
-        if number > *latest {
-            *latest = number;
-        }
+        let head = {
+            let mut latest = self.latest.write();
+            if number > *latest {
+                *latest = number;
+            }
+            *latest
+        };
         
         // Persist to disk
         let block_file = self.path.join(format!("block_{:012}.bin", number));
@@ -52,6 +66,32 @@ impl BlockStore {
         fs::write(&latest_file, number.to_string()).map_err(|e| StorageError::Io(e.to_string()))?;
         
         tracing::debug!("Block #{} persisted to disk", number);
+        
+        self.prune(head)
+    }
+    
+    /// Drop block bodies that fell out of the retention window.
+    /// Hash files are kept so the header chain stays intact.
+    pub fn prune(&self, head: u64) -> Result<(), StorageError> {
+        if self.pruning.mode == PruningMode::Archive {
+            return Ok(());
+        }
+        
+        let start = self.pruning.window_start(head);
+        let mut pruned_below = self.pruned_below.write();
+        if start <= *pruned_below {
+            return Ok(());
+        }
+        
+        self.blocks.write().retain(|number, _| *number >= start);
+        for number in *pruned_below..start {
+            let block_file = self.path.join(format!("block_{:012}.bin", number));
+            if block_file.exists() {
+                fs::remove_file(&block_file).map_err(|e| StorageError::Io(e.to_string()))?;
+            }
+        }
+        
+        *pruned_below = start;
         Ok(())
     }
     
@@ -74,6 +114,13 @@ impl BlockStore {
         None
     }
     
+    /// Get block hash by number (kept even after the body is pruned)
+    pub fn get_hash(&self, number: u64) -> Option<[u8; 32]> {
+        let hash_file = self.path.join(format!("block_{:012}.hash", number));
+        let bytes = fs::read(&hash_file).ok()?;
+        bytes.try_into().ok()
+    }
+    
     /// Get latest block number
     pub fn latest_number(&self) -> u64 {
         *self.latest.read()
@@ -101,3 +148,38 @@ impl BlockStore {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn fill(store: &BlockStore, count: u64) {
+        for number in 1..=count {
+            store.add_block(number, [number as u8; 32], vec![number as u8]).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_archive_keeps_all_bodies() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = BlockStore::with_pruning(temp_dir.path(), PruningConfig::archive()).unwrap();
+        fill(&store, 20);
+
+        assert!(store.get_block(1).is_some());
+        assert!(store.get_block(20).is_some());
+    }
+
+    #[test]
+    fn test_pruned_bodies_are_dropped() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = BlockStore::with_pruning(temp_dir.path(), PruningConfig::full(5)).unwrap();
+        fill(&store, 20);
+
+        assert!(store.get_block(15).is_none());
+        assert!(store.get_block(16).is_some());
+        assert_eq!(store.count(), 5);
+        // Headers survive pruning
+        assert_eq!(store.get_hash(1), Some([1u8; 32]));
+    }
+}
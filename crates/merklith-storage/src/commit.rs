@@ -0,0 +1,73 @@
+//! When state is flushed to disk
+//!
+//! Writing the whole state after every mutation is the most durable choice
+//! and the slowest; validators can batch flushes per block count or time.
+
+use std::time::Duration;
+use serde::{Serialize, Deserialize};
+
+/// How often mutated state is written to disk
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CommitPolicy {
+    /// Flush after every mutation
+    #[default]
+    EveryWrite,
+    /// Flush once the head has advanced this many blocks since the last flush
+    EveryNBlocks(u64),
+    /// Flush on the first mutation at least this long after the last flush
+    Interval(#[serde(with = "duration_secs")] Duration),
+}
+
+impl CommitPolicy {
+    /// Whether a flush is due, given the blocks advanced and time passed
+    /// since the last one
+    pub fn is_due(&self, blocks_since_flush: u64, since_flush: Duration) -> bool {
+        match *self {
+            CommitPolicy::EveryWrite => true,
+            CommitPolicy::EveryNBlocks(n) => blocks_since_flush >= n,
+            CommitPolicy::Interval(interval) => since_flush >= interval,
+        }
+    }
+}
+
+/// Durations in config files are whole seconds
+mod duration_secs {
+    use std::time::Duration;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(duration.as_secs())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        u64::deserialize(deserializer).map(Duration::from_secs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_due() {
+        assert!(CommitPolicy::EveryWrite.is_due(0, Duration::ZERO));
+
+        let blocks = CommitPolicy::EveryNBlocks(5);
+        assert!(!blocks.is_due(4, Duration::from_secs(3600)));
+        assert!(blocks.is_due(5, Duration::ZERO));
+
+        let interval = CommitPolicy::Interval(Duration::from_secs(10));
+        assert!(!interval.is_due(100, Duration::from_secs(9)));
+        assert!(interval.is_due(0, Duration::from_secs(10)));
+    }
+
+    #[test]
+    fn test_serde() {
+        let policy: CommitPolicy = serde_json::from_str(r#"{"interval":30}"#).unwrap();
+        assert_eq!(policy, CommitPolicy::Interval(Duration::from_secs(30)));
+        let policy: CommitPolicy = serde_json::from_str(r#"{"every_n_blocks":5}"#).unwrap();
+        assert_eq!(policy, CommitPolicy::EveryNBlocks(5));
+        assert_eq!(serde_json::to_string(&CommitPolicy::EveryWrite).unwrap(), r#""every_write""#);
+    }
+}
@@ -2,6 +2,11 @@
 
 pub mod state_db;
 pub mod block_store;
+pub mod pruning;
+pub mod commit;
+
+pub use pruning::{PruningConfig, PruningMode};
+pub use commit::CommitPolicy;
 
 use std::path::{Path, PathBuf};
 use std::fs;
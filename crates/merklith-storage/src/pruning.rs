@@ -6,34 +6,120 @@
 //! - Archive node support
 //! - Incremental backups
 
-use std::collections::{HashMap, HashSet};
+use std::collections::HashSet;
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
-use merklith_types::{Hash, Block, U256, Address};
+use merklith_types::Hash;
 use serde::{Serialize, Deserialize};
 
+/// How much history a node keeps around
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PruningMode {
+    /// Keep every block body and every state snapshot (the default;
+    /// pruning is opt-in)
+    #[default]
+    Archive,
+    /// Keep recent block bodies and all state snapshots
+    Full,
+    /// Keep the latest finalized snapshot plus the retention window
+    Fast,
+}
+
 /// Pruning configuration
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct PruningConfig {
-    /// Enable pruning
-    pub enabled: bool,
-    /// Retain last N blocks
-    pub retain_blocks: u64,
-    /// Prune interval (blocks)
-    pub prune_interval: u64,
-    /// Archive mode (keep everything)
-    pub archive_mode: bool,
+    /// Pruning mode
+    pub mode: PruningMode,
+    /// Number of recent blocks kept in full
+    pub retention_blocks: u64,
+    /// Take a state snapshot every N blocks (0 disables snapshots)
+    pub snapshot_interval: u64,
 }
 
 impl Default for PruningConfig {
     fn default() -> Self {
         Self {
-            enabled: true,
-            retain_blocks: 10000, // ~17 hours worth
-            prune_interval: 1000,
-            archive_mode: false,
+            mode: PruningMode::Archive,
+            retention_blocks: 10000, // ~17 hours worth, once pruning is on
+            snapshot_interval: 1000,
+        }
+    }
+}
+
+impl PruningConfig {
+    /// Keep everything
+    pub fn archive() -> Self {
+        Self {
+            mode: PruningMode::Archive,
+            ..Default::default()
+        }
+    }
+
+    /// Keep bodies for the last `retention_blocks` and all state
+    pub fn full(retention_blocks: u64) -> Self {
+        Self {
+            mode: PruningMode::Full,
+            retention_blocks,
+            ..Default::default()
+        }
+    }
+
+    /// Keep the last `retention_blocks` plus the latest finalized snapshot
+    pub fn fast(retention_blocks: u64) -> Self {
+        Self {
+            mode: PruningMode::Fast,
+            retention_blocks,
+            ..Default::default()
+        }
+    }
+
+    /// Set snapshot interval
+    pub fn with_snapshot_interval(mut self, interval: u64) -> Self {
+        self.snapshot_interval = interval;
+        self
+    }
+
+    /// First block number inside the retention window at `head`
+    pub fn window_start(&self, head: u64) -> u64 {
+        if head < self.retention_blocks {
+            0
+        } else {
+            head - self.retention_blocks + 1
         }
     }
+
+    /// Whether the body of block `number` is kept when the chain is at `head`
+    pub fn keeps_body(&self, number: u64, head: u64) -> bool {
+        match self.mode {
+            PruningMode::Archive => true,
+            PruningMode::Full | PruningMode::Fast => number >= self.window_start(head),
+        }
+    }
+
+    /// Whether a state snapshot should be taken at block `number`
+    pub fn is_snapshot_height(&self, number: u64) -> bool {
+        self.snapshot_interval != 0 && number % self.snapshot_interval == 0
+    }
+
+    /// Snapshot heights that may be dropped when the chain is at `head`.
+    ///
+    /// In `Fast` mode the newest snapshot below the window is kept as the
+    /// finalized base the window is replayed from.
+    pub fn prunable_snapshots(&self, heights: &[u64], head: u64) -> Vec<u64> {
+        if self.mode != PruningMode::Fast {
+            return Vec::new();
+        }
+
+        let start = self.window_start(head);
+        let base = heights.iter().copied().filter(|h| *h < start).max();
+        heights
+            .iter()
+            .copied()
+            .filter(|h| *h < start && Some(*h) != base)
+            .collect()
+    }
 }
 
 /// State pruner
@@ -101,7 +187,7 @@ impl StatePruner {
         block_number: u64,
         current_height: u64,
     ) -> bool {
-        if !self.config.enabled || self.config.archive_mode {
+        if self.config.mode == PruningMode::Archive {
             return false;
         }
         
@@ -113,7 +199,7 @@ impl StatePruner {
         }
         
         // Check if past retention window
-        block_number + self.config.retain_blocks < current_height
+        block_number < self.config.window_start(current_height)
     }
 
     /// Prune old state
@@ -121,7 +207,7 @@ impl StatePruner {
         &self,
         current_height: u64,
     ) -> PruneResult {
-        if !self.config.enabled || self.config.archive_mode {
+        if self.config.mode == PruningMode::Archive {
             return PruneResult {
                 blocks_pruned: 0,
                 storage_freed_bytes: 0,
@@ -130,7 +216,7 @@ impl StatePruner {
         }
         
         let mut pruned = self.pruned_up_to.lock().unwrap();
-        let target_prune = current_height.saturating_sub(self.config.retain_blocks);
+        let target_prune = self.config.window_start(current_height).saturating_sub(1);
         
         if target_prune <= *pruned {
             return PruneResult {
@@ -365,11 +451,7 @@ mod tests {
 
     #[test]
     fn test_prune_logic() {
-        let config = PruningConfig {
-            enabled: true,
-            retain_blocks: 100,
-            ..Default::default()
-        };
+        let config = PruningConfig::full(100);
         let pruner = StatePruner::new(config);
         
         // Should prune blocks 1-900 when at height 1000
@@ -380,16 +462,44 @@ mod tests {
 
     #[test]
     fn test_archive_mode() {
-        let config = PruningConfig {
-            archive_mode: true,
-            ..Default::default()
-        };
+        let config = PruningConfig::archive();
         let pruner = StatePruner::new(config);
         
         let result = pruner.prune(10000);
         assert_eq!(result.blocks_pruned, 0);
     }
 
+    #[test]
+    fn test_pruning_modes() {
+        let heights = [0, 100, 200, 300, 400, 500];
+
+        let archive = PruningConfig::archive().with_snapshot_interval(100);
+        assert!(archive.keeps_body(1, 500));
+        assert!(archive.prunable_snapshots(&heights, 500).is_empty());
+
+        let full = PruningConfig::full(150).with_snapshot_interval(100);
+        assert!(!full.keeps_body(350, 500));
+        assert!(full.keeps_body(351, 500));
+        assert!(full.prunable_snapshots(&heights, 500).is_empty());
+
+        // Window starts at 351: 300 is the finalized base, 0-200 go away
+        let fast = PruningConfig::fast(150).with_snapshot_interval(100);
+        assert!(!fast.keeps_body(350, 500));
+        assert_eq!(fast.prunable_snapshots(&heights, 500), vec![0, 100, 200]);
+    }
+
+    #[test]
+    fn test_pruning_config_serde() {
+        // Nothing is pruned unless a mode is chosen
+        let config: PruningConfig = serde_json::from_str("{}").unwrap();
+        assert_eq!(config, PruningConfig::archive());
+        assert!(config.keeps_body(1, 1_000_000));
+
+        let config: PruningConfig = serde_json::from_str(r#"{"mode":"fast"}"#).unwrap();
+        assert_eq!(config.mode, PruningMode::Fast);
+        assert_eq!(config.retention_blocks, PruningConfig::default().retention_blocks);
+    }
+
     #[test]
     fn test_snapshot_manager() {
         let temp_dir = TempDir::new().unwrap();
@@ -135,6 +135,9 @@ impl TrieNode {
     }
 
     /// Decode a node from bytes.
+    ///
+    /// Nodes may come from untrusted peers during sync, so malformed input
+    /// is an error rather than a panic.
     pub fn decode(bytes: &[u8]) -> Result<Self, StorageError> {
         if bytes.is_empty() {
             return Ok(TrieNode::Empty);
@@ -155,7 +158,10 @@ impl TrieNode {
                 let sep_pos = bytes[1..].iter().position(|b| *b == 0)
                     .ok_or_else(|| StorageError::Deserialization("Invalid extension node".to_string()))?;
                 let prefix = Nibbles::from_bytes(&bytes[1..1+sep_pos]);
-                let child = Hash::from_slice(&bytes[1+sep_pos+1..1+sep_pos+1+32])
+                let child_bytes = bytes.get(1+sep_pos+1..)
+                    .filter(|child| child.len() == 32)
+                    .ok_or_else(|| StorageError::Deserialization("Invalid extension child".to_string()))?;
+                let child = Hash::from_slice(child_bytes)
                     .map_err(|e| StorageError::Deserialization(e.to_string()))?;
                 Ok(TrieNode::Extension { prefix, child })
             }
@@ -201,6 +207,7 @@ impl Default for TrieNode {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
 
     #[test]
     fn test_nibbles_from_bytes() {
@@ -253,6 +260,39 @@ mod tests {
         assert_eq!(branch, decoded);
     }
 
+    #[test]
+    fn test_decode_truncated() {
+        let ext = TrieNode::Extension {
+            prefix: Nibbles(vec![0x1, 0x2]),
+            child: Hash::compute(b"child"),
+        };
+        let encoded = ext.encode();
+        for len in 1..encoded.len() {
+            assert!(matches!(TrieNode::decode(&encoded[..len]), Err(StorageError::Deserialization(_))));
+        }
+
+        let branch = TrieNode::Branch {
+            children: Default::default(),
+            value: None,
+        }
+        .encode();
+        assert!(TrieNode::decode(&branch[..branch.len() - 1]).is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn prop_decode_never_panics(bytes in prop::collection::vec(any::<u8>(), 0..600)) {
+            let _ = TrieNode::decode(&bytes);
+        }
+
+        #[test]
+        fn prop_decode_tagged_never_panics(tag in 0u8..4, body in prop::collection::vec(any::<u8>(), 0..600)) {
+            let mut bytes = vec![tag];
+            bytes.extend(body);
+            let _ = TrieNode::decode(&bytes);
+        }
+    }
+
     #[test]
     fn test_empty_node() {
         let empty = TrieNode::Empty;
@@ -57,11 +57,8 @@ impl Trie {
         node_hash: &Hash,
         remaining: &Nibbles,
     ) -> Result<Option<Vec<u8>>, StorageError> {
-        if remaining.is_empty() {
-            // This shouldn't happen in normal traversal
-            return Ok(None);
-        }
-
+        // The path may run out at a leaf split off on a key's last nibble,
+        // so the node is checked even when nothing remains
         let node = self.get_node(node_hash)?;
 
         match node {
@@ -512,6 +509,8 @@ impl Trie {
 mod tests {
     use super::*;
     use crate::db::DatabaseConfig;
+    use proptest::prelude::*;
+    use std::collections::BTreeMap;
     use tempfile::TempDir;
 
     fn create_test_trie() -> (Trie, TempDir) {
@@ -588,4 +587,60 @@ mod tests {
         assert_eq!(trie.root, root_before);
         assert_eq!(trie.get(b"key2").unwrap(), None);
     }
+
+    /// Random maps of 32-byte keys, the width of state keys. Half differ
+    /// only in their last bytes, so they share long prefixes and split deep.
+    fn entries() -> impl Strategy<Value = BTreeMap<[u8; 32], Vec<u8>>> {
+        let close = (0u16..512).prop_map(|tail| {
+            let mut key = [0u8; 32];
+            key[30..].copy_from_slice(&tail.to_be_bytes());
+            key
+        });
+        let key = prop_oneof![any::<[u8; 32]>(), close];
+        prop::collection::btree_map(key, prop::collection::vec(any::<u8>(), 1..16), 1..32)
+    }
+
+    proptest! {
+        #[test]
+        fn prop_inserted_keys_read_back(entries in entries()) {
+            let (mut trie, _temp) = create_test_trie();
+            for (key, value) in &entries {
+                trie.insert(key, value.clone()).unwrap();
+            }
+            for (key, value) in &entries {
+                prop_assert_eq!(trie.get(key).unwrap(), Some(value.clone()));
+            }
+        }
+
+        #[test]
+        fn prop_root_independent_of_order(entries in entries(), seed in any::<u64>()) {
+            let (mut forward, _temp) = create_test_trie();
+            for (key, value) in &entries {
+                forward.insert(key, value.clone()).unwrap();
+            }
+
+            let mut shuffled: Vec<_> = entries.iter().collect();
+            shuffled.sort_by_key(|(key, _)| Hash::compute_multi(&[&seed.to_le_bytes(), &key[..]]));
+            let (mut reordered, _temp) = create_test_trie();
+            for (key, value) in shuffled {
+                reordered.insert(key, value.clone()).unwrap();
+            }
+            prop_assert_eq!(forward.root(), reordered.root());
+        }
+
+        #[test]
+        fn prop_delete_removes_only_target(entries in entries(), index in any::<prop::sample::Index>()) {
+            let (mut trie, _temp) = create_test_trie();
+            for (key, value) in &entries {
+                trie.insert(key, value.clone()).unwrap();
+            }
+            let target = *index.get(&entries.keys().collect::<Vec<_>>());
+            trie.delete(target).unwrap();
+
+            prop_assert_eq!(trie.get(target).unwrap(), None);
+            for (key, value) in entries.iter().filter(|(key, _)| *key != target) {
+                prop_assert_eq!(trie.get(key).unwrap(), Some(value.clone()));
+            }
+        }
+    }
 }
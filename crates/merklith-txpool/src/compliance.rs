@@ -0,0 +1,369 @@
+//! Address screening for regulated operators
+//!
+//! A [`ComplianceScreener`] refuses transactions whose sender or recipient
+//! is on a blocklist file, and optionally any listed address passed as an
+//! argument to a contract call. Call data is decoded as the chain's ABI
+//! encodes it: a 4-byte selector, the start of the BLAKE3 hash of the
+//! function signature, followed by the Borsh-encoded arguments. Denials are
+//! reported to an audit hook.
+
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::SystemTime;
+
+use borsh::BorshDeserialize;
+use merklith_types::{Address, Hash, SignedTransaction, U256};
+use parking_lot::{Mutex, RwLock};
+
+use crate::pipeline::{TxValidator, ValidationContext, ValidationError};
+
+/// Which part of a transaction matched the blocklist
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScreenedRole {
+    Sender,
+    Recipient,
+    /// An address argument in the call data of a contract call
+    CallArgument,
+}
+
+/// A refused transaction, as reported to the audit hook
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScreeningDenial {
+    /// Hash of the refused transaction
+    pub tx_hash: String,
+    /// Sender of the refused transaction
+    pub sender: Address,
+    /// Listed address that matched
+    pub address: Address,
+    /// Where the listed address appeared
+    pub role: ScreenedRole,
+}
+
+/// Token calls whose address arguments are screened by
+/// [`ComplianceScreener::with_call_data_screening`]
+pub const DEFAULT_SCREENED_CALLS: &[&str] = &[
+    "transfer(address,uint256)",
+    "approve(address,uint256)",
+    "transferFrom(address,address,uint256)",
+];
+
+/// Argument type in a function signature, as far as screening needs it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ArgType {
+    Address,
+    AddressList,
+    Uint256,
+    /// Fixed-size scalar of this many bytes
+    Fixed(usize),
+    /// Length-prefixed `bytes` or `string`
+    Bytes,
+}
+
+impl ArgType {
+    fn parse(ty: &str) -> Result<Self, String> {
+        Ok(match ty {
+            "address" => ArgType::Address,
+            "address[]" => ArgType::AddressList,
+            "uint256" | "int256" => ArgType::Uint256,
+            "bytes32" => ArgType::Fixed(32),
+            "bool" | "uint8" | "int8" => ArgType::Fixed(1),
+            "uint16" | "int16" => ArgType::Fixed(2),
+            "uint32" | "int32" => ArgType::Fixed(4),
+            "uint64" | "int64" => ArgType::Fixed(8),
+            "uint128" | "int128" => ArgType::Fixed(16),
+            "bytes" | "string" => ArgType::Bytes,
+            other => return Err(format!("Unsupported argument type '{}'", other)),
+        })
+    }
+}
+
+/// Selector and argument types of a signature such as `transfer(address,uint256)`
+fn parse_signature(signature: &str) -> Result<([u8; 4], Vec<ArgType>), String> {
+    let args = signature
+        .split_once('(')
+        .and_then(|(name, rest)| rest.strip_suffix(')').filter(|_| !name.is_empty()))
+        .ok_or_else(|| format!("Invalid function signature '{}'", signature))?;
+    let types = args
+        .split(',')
+        .map(str::trim)
+        .filter(|ty| !ty.is_empty())
+        .map(ArgType::parse)
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut selector = [0u8; 4];
+    selector.copy_from_slice(&Hash::compute(signature.as_bytes()).as_bytes()[..4]);
+    Ok((selector, types))
+}
+
+/// Address arguments of Borsh-encoded `args`, or None if they do not decode
+/// as `types`
+fn address_args(types: &[ArgType], mut args: &[u8]) -> Option<Vec<Address>> {
+    let mut addresses = Vec::new();
+    for ty in types {
+        match ty {
+            ArgType::Address => addresses.push(Address::deserialize(&mut args).ok()?),
+            ArgType::AddressList => addresses.extend(Vec::<Address>::deserialize(&mut args).ok()?),
+            ArgType::Uint256 => {
+                U256::deserialize(&mut args).ok()?;
+            }
+            ArgType::Fixed(size) => args = args.get(*size..)?,
+            ArgType::Bytes => {
+                Vec::<u8>::deserialize(&mut args).ok()?;
+            }
+        }
+    }
+    args.is_empty().then_some(addresses)
+}
+
+/// Receives every denial, e.g. to record an `AuditEventType::AccessDenied` event
+pub type AuditHook = Box<dyn Fn(&ScreeningDenial) + Send + Sync>;
+
+/// Blocklist screening, reloaded whenever the list file changes
+pub struct ComplianceScreener {
+    path: PathBuf,
+    blocked: RwLock<HashSet<Address>>,
+    /// Modification time of the file when it was last loaded
+    loaded_at: Mutex<Option<SystemTime>>,
+    /// Argument types of the contract calls screened, by selector
+    screened_calls: HashMap<[u8; 4], Vec<ArgType>>,
+    audit: Option<AuditHook>,
+}
+
+impl ComplianceScreener {
+    /// Load the blocklist at `path`: one hex or bech32 address per line,
+    /// with blank lines and `#` comments ignored
+    pub fn from_file(path: impl Into<PathBuf>) -> Result<Self, String> {
+        let screener = Self {
+            path: path.into(),
+            blocked: RwLock::new(HashSet::new()),
+            loaded_at: Mutex::new(None),
+            screened_calls: HashMap::new(),
+            audit: None,
+        };
+        screener.reload()?;
+        Ok(screener)
+    }
+
+    /// Also refuse the [`DEFAULT_SCREENED_CALLS`] when they pass a listed
+    /// address as an argument
+    pub fn with_call_data_screening(mut self) -> Self {
+        for signature in DEFAULT_SCREENED_CALLS {
+            let (selector, types) = parse_signature(signature).expect("default signatures parse");
+            self.screened_calls.insert(selector, types);
+        }
+        self
+    }
+
+    /// Also refuse calls to the function `signature`, e.g.
+    /// `bridge(address,uint64,uint256)`, passing a listed address
+    pub fn with_screened_call(mut self, signature: &str) -> Result<Self, String> {
+        let (selector, types) = parse_signature(signature)?;
+        self.screened_calls.insert(selector, types);
+        Ok(self)
+    }
+
+    /// Report denials to `hook`
+    pub fn with_audit(mut self, hook: impl Fn(&ScreeningDenial) + Send + Sync + 'static) -> Self {
+        self.audit = Some(Box::new(hook));
+        self
+    }
+
+    /// Re-read the list file, returning the number of listed addresses.
+    /// On error the previous list stays in force.
+    pub fn reload(&self) -> Result<usize, String> {
+        let modified = std::fs::metadata(&self.path).and_then(|m| m.modified()).ok();
+        let contents = std::fs::read_to_string(&self.path)
+            .map_err(|e| format!("Failed to read blocklist '{}': {}", self.path.display(), e))?;
+        let blocked = contents
+            .lines()
+            .map(|line| line.split('#').next().unwrap_or("").trim())
+            .filter(|line| !line.is_empty())
+            .map(|line| Address::from_str(line).map_err(|e| format!("Invalid blocklist address '{}': {}", line, e)))
+            .collect::<Result<HashSet<_>, _>>()?;
+
+        let count = blocked.len();
+        *self.blocked.write() = blocked;
+        *self.loaded_at.lock() = modified;
+        Ok(count)
+    }
+
+    /// Reload if the list file changed since it was last read
+    fn reload_if_modified(&self) {
+        let modified = std::fs::metadata(&self.path).and_then(|m| m.modified()).ok();
+        {
+            let mut loaded_at = self.loaded_at.lock();
+            if modified.is_none() || modified == *loaded_at {
+                return;
+            }
+            // Noted up front so a broken file is reported once, not per transaction
+            *loaded_at = modified;
+        }
+        if let Err(e) = self.reload() {
+            tracing::warn!("Keeping previous blocklist: {}", e);
+        }
+    }
+
+    /// Whether `address` is listed
+    pub fn is_blocked(&self, address: &Address) -> bool {
+        self.blocked.read().contains(address)
+    }
+
+    /// The first listed address involved in `tx`, and where it appears
+    pub fn screen(&self, tx: &SignedTransaction) -> Option<(Address, ScreenedRole)> {
+        let blocked = self.blocked.read();
+        let sender = tx.sender();
+        if blocked.contains(&sender) {
+            return Some((sender, ScreenedRole::Sender));
+        }
+        let to = tx.tx.to?;
+        if blocked.contains(&to) {
+            return Some((to, ScreenedRole::Recipient));
+        }
+        // Calls to other functions, or whose arguments do not decode, are
+        // not screened
+        let selector: [u8; 4] = tx.tx.data.get(..4)?.try_into().ok()?;
+        let types = self.screened_calls.get(&selector)?;
+        address_args(types, &tx.tx.data[4..])?
+            .into_iter()
+            .find(|address| blocked.contains(address))
+            .map(|address| (address, ScreenedRole::CallArgument))
+    }
+}
+
+impl TxValidator for ComplianceScreener {
+    fn name(&self) -> &str {
+        "compliance"
+    }
+
+    fn validate(&self, tx: &SignedTransaction, _ctx: &ValidationContext) -> Result<(), ValidationError> {
+        self.reload_if_modified();
+        let Some((address, role)) = self.screen(tx) else {
+            return Ok(());
+        };
+
+        let denial = ScreeningDenial {
+            tx_hash: tx.hash().to_string(),
+            sender: tx.sender(),
+            address,
+            role,
+        };
+        if let Some(audit) = &self.audit {
+            audit(&denial);
+        }
+        Err(ValidationError::Rejected {
+            rule: self.name().to_string(),
+            reason: format!("{:?} 0x{} is blocklisted", role, address.to_hex()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipeline::ValidationPipeline;
+    use merklith_core::state_machine::State;
+    use merklith_types::{Transaction, U256};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_blocklisted_recipient() {
+        let dir = std::env::temp_dir().join(format!("merklith_compliance_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let sender = merklith_crypto::Keypair::from_seed(&[3u8; 32]);
+        let sanctioned = Address::from_bytes([0x5a; 20]);
+        let list = dir.join("blocklist.txt");
+        std::fs::write(&list, format!("# sanctioned\n0x{}\n", sanctioned.to_hex())).unwrap();
+
+        let mut genesis = State::devnet_genesis();
+        genesis.add_alloc(sender.address(), U256::from(1_000_000u64));
+        let state = State::with_genesis(dir.join("state"), genesis, merklith_storage::PruningConfig::archive());
+        let ctx = ValidationContext {
+            state: &state,
+            chain_id: state.chain_id(),
+            min_gas_price: U256::ONE,
+            max_tx_size: crate::DEFAULT_MAX_TX_SIZE,
+        };
+        let sign = |to: Address, data: Vec<u8>| {
+            let tx = Transaction::new(state.chain_id(), 0, Some(to), U256::ONE, 100_000, U256::ONE, U256::ZERO)
+                .with_data(data);
+            let (signature, public_key) = sender.sign_transaction(&tx);
+            SignedTransaction::new(tx, signature, public_key)
+        };
+
+        let denials = Arc::new(Mutex::new(Vec::new()));
+        let recorded = denials.clone();
+        let screener = Arc::new(
+            ComplianceScreener::from_file(&list)
+                .unwrap()
+                .with_call_data_screening()
+                .with_audit(move |denial| recorded.lock().push(denial.clone())),
+        );
+        let pipeline = ValidationPipeline::default().with_validator(screener.clone());
+
+        let transfer = sign(sanctioned, Vec::new());
+        let error = pipeline.validate(&transfer, &ctx).unwrap_err();
+        assert!(matches!(error, ValidationError::Rejected { ref rule, .. } if rule == "compliance"));
+        assert_eq!(
+            *denials.lock(),
+            vec![ScreeningDenial {
+                tx_hash: transfer.hash().to_string(),
+                sender: sender.address(),
+                address: sanctioned,
+                role: ScreenedRole::Recipient,
+            }]
+        );
+
+        // A token transfer to the listed address through a contract call
+        let encode = |signature: &str, args: Vec<u8>| {
+            let mut data = parse_signature(signature).unwrap().0.to_vec();
+            data.extend(args);
+            data
+        };
+        let token = Address::from_bytes([0x10; 20]);
+        let other = Address::from_bytes([0x11; 20]);
+        let call_data = encode("transfer(address,uint256)", borsh::to_vec(&(sanctioned, U256::from(5u64))).unwrap());
+        let call = sign(token, call_data);
+        assert!(pipeline.validate(&call, &ctx).is_err());
+        assert_eq!(denials.lock()[1].role, ScreenedRole::CallArgument);
+        let call_data = encode(
+            "transferFrom(address,address,uint256)",
+            borsh::to_vec(&(other, sanctioned, U256::from(5u64))).unwrap(),
+        );
+        assert!(pipeline.validate(&sign(token, call_data), &ctx).is_err());
+        assert_eq!(denials.lock().len(), 3);
+
+        // Other functions are screened once registered
+        let args = borsh::to_vec(&(7u64, vec![other, sanctioned], "memo".to_string())).unwrap();
+        let call_data = encode("airdrop(uint64,address[],string)", args);
+        assert!(screener.screen(&sign(token, call_data.clone())).is_none());
+        let registered = ComplianceScreener::from_file(&list)
+            .unwrap()
+            .with_screened_call("airdrop(uint64,address[],string)")
+            .unwrap();
+        assert_eq!(
+            registered.screen(&sign(token, call_data)),
+            Some((sanctioned, ScreenedRole::CallArgument))
+        );
+        assert!(registered.with_screened_call("airdrop(tuple)").is_err());
+
+        // Arguments that do not decode are not matched as addresses
+        let mut call_data = encode("transfer(address,uint256)", borsh::to_vec(&(other, U256::ONE)).unwrap());
+        call_data.extend_from_slice(sanctioned.as_bytes());
+        assert!(screener.screen(&sign(token, call_data)).is_none());
+
+        // Delisting takes effect on reload, without rebuilding the pipeline
+        std::fs::write(&list, "# nobody\n").unwrap();
+        assert_eq!(screener.reload(), Ok(0));
+        assert!(!screener.is_blocked(&sanctioned));
+        pipeline.validate(&transfer, &ctx).unwrap();
+        assert_eq!(denials.lock().len(), 3);
+
+        // A broken list keeps the previous one in force
+        std::fs::write(&list, format!("0x{}\nnot-an-address\n", sanctioned.to_hex())).unwrap();
+        assert!(screener.reload().is_err());
+        pipeline.validate(&transfer, &ctx).unwrap();
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -2,15 +2,42 @@
 //!
 //! This module provides transaction pooling and validation.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
+use merklith_types::{Address, U256};
 use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+
+pub mod compliance;
+pub mod pipeline;
+pub use compliance::{ComplianceScreener, ScreenedRole, ScreeningDenial};
+pub use pipeline::{TxValidator, ValidationContext, ValidationError, ValidationPipeline};
+
+/// Default cap on an encoded transaction: 128KB of calldata plus room for
+/// the signature, key and other fields.
+pub const DEFAULT_MAX_TX_SIZE: usize = 129 * 1024;
+
+/// Minimum fee increase, in percent, for a transaction to replace a pooled
+/// one with the same sender and nonce
+pub const PRICE_BUMP_PERCENT: u64 = 10;
+
+/// Events buffered per subscriber before the slowest ones start lagging
+const POOL_EVENT_CAPACITY: usize = 1024;
 
 /// Pool configuration
 #[derive(Debug, Clone)]
 pub struct PoolConfig {
     pub max_size: usize,
     pub max_per_account: usize,
+    /// Lowest `max_fee_per_gas` accepted, in Spark
+    pub min_gas_price: U256,
+    /// Order in which pending transactions are handed to the block builder
+    pub ordering: OrderingPolicy,
+    /// Gas available per block, for inclusion estimates
+    pub block_gas_limit: u64,
+    /// Largest accepted transaction, in borsh-encoded bytes
+    pub max_tx_size: usize,
 }
 
 impl Default for PoolConfig {
@@ -18,16 +45,80 @@ impl Default for PoolConfig {
         Self {
             max_size: 5000,
             max_per_account: 100,
+            min_gas_price: U256::ONE,
+            ordering: OrderingPolicy::default(),
+            block_gas_limit: 30_000_000,
+            max_tx_size: DEFAULT_MAX_TX_SIZE,
         }
     }
 }
 
+/// Order of pending transactions.
+///
+/// `FeePriority` and `FairRoundRobin` keep each sender's transactions in
+/// nonce order; `Fifo` does so only for transactions that arrived in it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderingPolicy {
+    /// Arrival order, ignoring fees; a replacement keeps its predecessor's place
+    #[default]
+    Fifo,
+    /// Highest priority fee first
+    FeePriority,
+    /// One transaction per sender in turn, so no sender can fill a block
+    FairRoundRobin,
+}
+
+/// Where a pooled transaction stands in line for inclusion
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolPosition {
+    /// Zero-based rank under the active ordering policy
+    pub rank: usize,
+    /// Gas limit of the transactions ordered before it
+    pub gas_ahead: u64,
+    /// Blocks until inclusion if blocks are filled to the gas limit; 1 is the next block
+    pub estimated_blocks: u64,
+}
+
+/// Why a transaction left the pool
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RemovalReason {
+    /// Included in a block
+    Mined,
+    /// Superseded by a fee bump with the same sender and nonce
+    Replaced,
+    /// Its `valid_until_block` deadline passed
+    Expired,
+    /// Lowest-priced transaction, evicted to make room in a full pool
+    Evicted,
+    /// Removed for any other reason, e.g. rejected by the block producer
+    Dropped,
+}
+
+/// Change to the pool's contents, as seen by [`TransactionPool::subscribe`] receivers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PoolEvent {
+    /// Transaction admitted
+    Added { hash: String },
+    /// Transaction left the pool
+    Removed { hash: String, reason: RemovalReason },
+    /// `new_hash` took the place of `old_hash`, which is also reported as
+    /// removed with [`RemovalReason::Replaced`]
+    Replaced { old_hash: String, new_hash: String },
+}
+
 /// Transaction pool error
 #[derive(Debug, Clone)]
 pub enum PoolError {
     PoolFull,
     AccountLimit,
     InvalidTransaction(String),
+    /// `max_fee_per_gas` is below the pool's price floor
+    Underpriced { max_fee_per_gas: U256, min_gas_price: U256 },
+    /// Encoded transaction is larger than `max_tx_size`
+    Oversized { size: usize, max_tx_size: usize },
+    /// Same sender and nonce as a pooled transaction without a large enough fee bump
+    ReplacementUnderpriced { max_fee_per_gas: U256, required: U256 },
 }
 
 impl std::fmt::Display for PoolError {
@@ -36,6 +127,21 @@ impl std::fmt::Display for PoolError {
             PoolError::PoolFull => write!(f, "Transaction pool is full"),
             PoolError::AccountLimit => write!(f, "Account transaction limit reached"),
             PoolError::InvalidTransaction(e) => write!(f, "Invalid transaction: {}", e),
+            PoolError::Underpriced { max_fee_per_gas, min_gas_price } => write!(
+                f,
+                "Transaction underpriced: max fee per gas {} below minimum {}",
+                max_fee_per_gas, min_gas_price
+            ),
+            PoolError::Oversized { size, max_tx_size } => write!(
+                f,
+                "Transaction too large: {} bytes exceeds maximum {}",
+                size, max_tx_size
+            ),
+            PoolError::ReplacementUnderpriced { max_fee_per_gas, required } => write!(
+                f,
+                "Replacement transaction underpriced: max fee per gas {} below required {}",
+                max_fee_per_gas, required
+            ),
         }
     }
 }
@@ -46,35 +152,116 @@ impl std::error::Error for PoolError {}
 #[derive(Debug)]
 pub struct TransactionPool {
     config: PoolConfig,
-    transactions: Arc<Mutex<HashMap<String, merklith_types::Transaction>>>,
+    transactions: Arc<Mutex<HashMap<String, merklith_types::SignedTransaction>>>,
     pending: Arc<Mutex<Vec<String>>>,
+    /// Current price floor: the configured minimum or the base fee, if higher
+    min_gas_price: Arc<Mutex<U256>>,
+    events: broadcast::Sender<PoolEvent>,
+    /// Rules a transaction must pass before it is accepted
+    validation: ValidationPipeline,
 }
 
 impl TransactionPool {
     /// Create a new transaction pool
     pub fn new(config: PoolConfig) -> Self {
         Self {
+            min_gas_price: Arc::new(Mutex::new(config.min_gas_price)),
             config,
             transactions: Arc::new(Mutex::new(HashMap::new())),
             pending: Arc::new(Mutex::new(Vec::new())),
+            events: broadcast::channel(POOL_EVENT_CAPACITY).0,
+            validation: ValidationPipeline::default(),
+        }
+    }
+
+    /// Replace the acceptance rules, e.g. to append custom ones to the defaults
+    pub fn with_validation(mut self, validation: ValidationPipeline) -> Self {
+        self.validation = validation;
+        self
+    }
+
+    /// Acceptance rules run on incoming transactions
+    pub fn validation(&self) -> &ValidationPipeline {
+        &self.validation
+    }
+
+    /// Receive pool events from now on.
+    ///
+    /// Subscribers that fall more than 1024 events behind lose the oldest
+    /// ones and see `RecvError::Lagged`.
+    pub fn subscribe(&self) -> broadcast::Receiver<PoolEvent> {
+        self.events.subscribe()
+    }
+
+    fn publish(&self, event: PoolEvent) {
+        // Sending only fails when nobody is subscribed
+        let _ = self.events.send(event);
+    }
+
+    /// Current price floor
+    pub fn min_gas_price(&self) -> U256 {
+        *self.min_gas_price.lock()
+    }
+
+    /// Track the chain's base fee: the floor becomes the base fee, but
+    /// never drops below the configured minimum
+    pub fn set_base_fee(&self, base_fee: U256) {
+        *self.min_gas_price.lock() = base_fee.max(self.config.min_gas_price);
+    }
+
+    /// Reject transactions priced below the current floor
+    pub fn check_price(
+        &self,
+        tx: &merklith_types::SignedTransaction,
+    ) -> Result<(), PoolError> {
+        let min_gas_price = self.min_gas_price();
+        if tx.tx.max_fee_per_gas < min_gas_price {
+            return Err(PoolError::Underpriced {
+                max_fee_per_gas: tx.tx.max_fee_per_gas,
+                min_gas_price,
+            });
         }
+        Ok(())
     }
 
-    /// Add a transaction to the pool
+    /// Largest accepted transaction, in encoded bytes
+    pub fn max_tx_size(&self) -> usize {
+        self.config.max_tx_size
+    }
+
+    /// Reject transactions whose encoding exceeds `max_tx_size`
+    pub fn check_size(
+        &self,
+        tx: &merklith_types::SignedTransaction,
+    ) -> Result<(), PoolError> {
+        let size = borsh::object_length(tx)
+            .map_err(|e| PoolError::InvalidTransaction(e.to_string()))?;
+        if size > self.config.max_tx_size {
+            return Err(PoolError::Oversized {
+                size,
+                max_tx_size: self.config.max_tx_size,
+            });
+        }
+        Ok(())
+    }
+
+    /// Add a transaction to the pool.
+    ///
+    /// A transaction with the same sender and nonce as a pooled one replaces
+    /// it, keeping its place in line, if both fees are at least
+    /// [`PRICE_BUMP_PERCENT`] higher. When the pool is full, the lowest-priced
+    /// transaction is evicted if the new one pays more.
     pub fn add_transaction(
         &self,
-        tx: merklith_types::Transaction,
+        tx: merklith_types::SignedTransaction,
     ) -> Result<String, PoolError> {
+        self.check_size(&tx)?;
+        self.check_price(&tx)?;
+
         let mut transactions = self.transactions.lock();
         let mut pending = self.pending.lock();
 
-        // Check pool size
-        if transactions.len() >= self.config.max_size {
-            return Err(PoolError::PoolFull);
-        }
-
-        // Create a simple hash from nonce and chain_id
-        let hash = format!("tx_{}_{}", tx.nonce, tx.chain_id);
+        let hash = tx.hash().to_string();
 
         if transactions.contains_key(&hash) {
             return Err(PoolError::InvalidTransaction(
@@ -82,8 +269,50 @@ impl TransactionPool {
             ));
         }
 
+        let sender = tx.sender();
+        let existing = transactions
+            .iter()
+            .find(|(_, pooled)| pooled.tx.nonce == tx.tx.nonce && pooled.sender() == sender)
+            .map(|(hash, pooled)| (hash.clone(), pooled.tx.max_fee_per_gas, pooled.tx.max_priority_fee_per_gas));
+        if let Some((old_hash, max_fee, priority_fee)) = existing {
+            let bumped = |fee: U256| fee + fee * U256::from(PRICE_BUMP_PERCENT) / U256::from(100u64);
+            if tx.tx.max_fee_per_gas < bumped(max_fee) || tx.tx.max_priority_fee_per_gas < bumped(priority_fee) {
+                return Err(PoolError::ReplacementUnderpriced {
+                    max_fee_per_gas: tx.tx.max_fee_per_gas,
+                    required: bumped(max_fee),
+                });
+            }
+
+            transactions.remove(&old_hash);
+            transactions.insert(hash.clone(), tx);
+            if let Some(slot) = pending.iter_mut().find(|h| **h == old_hash) {
+                *slot = hash.clone();
+            }
+            self.publish(PoolEvent::Removed { hash: old_hash.clone(), reason: RemovalReason::Replaced });
+            self.publish(PoolEvent::Replaced { old_hash, new_hash: hash.clone() });
+            return Ok(hash);
+        }
+
+        // Make room by evicting the cheapest transaction, latest arrival first
+        if transactions.len() >= self.config.max_size {
+            let cheapest = pending
+                .iter()
+                .rev()
+                .filter_map(|h| transactions.get(h).map(|pooled| (h, pooled.tx.max_fee_per_gas)))
+                .min_by_key(|(_, max_fee)| *max_fee)
+                .filter(|(_, max_fee)| *max_fee < tx.tx.max_fee_per_gas)
+                .map(|(h, _)| h.clone());
+            let Some(evicted) = cheapest else {
+                return Err(PoolError::PoolFull);
+            };
+            transactions.remove(&evicted);
+            pending.retain(|h| *h != evicted);
+            self.publish(PoolEvent::Removed { hash: evicted, reason: RemovalReason::Evicted });
+        }
+
         transactions.insert(hash.clone(), tx);
         pending.push(hash.clone());
+        self.publish(PoolEvent::Added { hash: hash.clone() });
 
         Ok(hash)
     }
@@ -92,7 +321,7 @@ impl TransactionPool {
     pub fn get_transaction(
         &self,
         hash: &str,
-    ) -> Option<merklith_types::Transaction> {
+    ) -> Option<merklith_types::SignedTransaction> {
         let transactions = self.transactions.lock();
         transactions.get(hash).cloned()
     }
@@ -100,27 +329,146 @@ impl TransactionPool {
     /// Get pending transactions up to limit
     pub fn get_pending(&self,
         limit: usize,
-    ) -> Vec<merklith_types::Transaction> {
+    ) -> Vec<merklith_types::SignedTransaction> {
         let transactions = self.transactions.lock();
         let pending = self.pending.lock();
 
-        pending
+        let arrived = pending.iter().filter_map(|hash| transactions.get(hash));
+        match self.config.ordering {
+            OrderingPolicy::Fifo => arrived
+                .take(limit)  // Respect the limit to prevent unbounded memory growth
+                .cloned()
+                .collect(),
+            policy => order_by_sender(arrived, policy, limit),
+        }
+    }
+
+    /// Rank of a pooled transaction under the active ordering policy and
+    /// an estimate of the blocks until it is included
+    pub fn position(&self, hash: &str) -> Option<PoolPosition> {
+        let ordered = self.get_pending(usize::MAX);
+        let rank = ordered.iter().position(|tx| tx.hash().to_string() == hash)?;
+        let gas_ahead: u64 = ordered[..rank].iter().map(|tx| tx.tx.gas_limit).sum();
+        let gas_through = gas_ahead.saturating_add(ordered[rank].tx.gas_limit);
+
+        Some(PoolPosition {
+            rank,
+            gas_ahead,
+            estimated_blocks: gas_through.div_ceil(self.config.block_gas_limit.max(1)).max(1),
+        })
+    }
+
+    /// Drop transactions whose deadline is before `block_number`.
+    ///
+    /// Call before promoting pending transactions into block `block_number`.
+    /// Returns the hashes of the dropped transactions.
+    pub fn drop_expired(&self, block_number: u64) -> Vec<String> {
+        let mut transactions = self.transactions.lock();
+        let mut pending = self.pending.lock();
+
+        let expired: Vec<String> = transactions
             .iter()
-            .take(limit)  // Respect the limit to prevent unbounded memory growth
-            .filter_map(|hash| transactions.get(hash).cloned())
-            .collect()
+            .filter(|(_, tx)| tx.tx.is_expired(block_number))
+            .map(|(hash, _)| hash.clone())
+            .collect();
+
+        for hash in &expired {
+            transactions.remove(hash);
+            self.publish(PoolEvent::Removed { hash: hash.clone(), reason: RemovalReason::Expired });
+        }
+        pending.retain(|h| !expired.contains(h));
+
+        expired
     }
 
     /// Remove a transaction from the pool
     pub fn remove_transaction(&self,
-        hash: &str) {
+        hash: &str, reason: RemovalReason) {
         let mut transactions = self.transactions.lock();
         let mut pending = self.pending.lock();
 
-        transactions.remove(hash);
+        if transactions.remove(hash).is_some() {
+            self.publish(PoolEvent::Removed { hash: hash.to_string(), reason });
+        }
         pending.retain(|h| h != hash);
     }
 
+    /// Return transactions un-mined by a reorg to the pool.
+    ///
+    /// `state_nonce` gives each sender's next nonce in the rolled-back state.
+    /// Reverted transactions go back ahead of everything already pending, in
+    /// nonce order, and any pooled transaction whose nonce the rolled-back
+    /// state has already used is dropped. Reverted transactions were accepted
+    /// once, so the price floor is not re-checked. Returns the hashes
+    /// re-admitted.
+    pub fn on_reorg(
+        &self,
+        mut reverted_txs: Vec<merklith_types::SignedTransaction>,
+        state_nonce: impl Fn(&Address) -> u64,
+    ) -> Vec<String> {
+        let mut transactions = self.transactions.lock();
+        let mut pending = self.pending.lock();
+
+        let mut nonces: HashMap<Address, u64> = HashMap::new();
+        let mut next_nonce = |sender: Address| *nonces.entry(sender).or_insert_with(|| state_nonce(&sender));
+
+        let stale: Vec<String> = transactions
+            .iter()
+            .filter(|(_, tx)| tx.tx.nonce < next_nonce(tx.sender()))
+            .map(|(hash, _)| hash.clone())
+            .collect();
+        for hash in &stale {
+            transactions.remove(hash);
+            self.publish(PoolEvent::Removed { hash: hash.clone(), reason: RemovalReason::Mined });
+        }
+        pending.retain(|h| !stale.contains(h));
+
+        reverted_txs.sort_by_key(|tx| tx.tx.nonce);
+        let mut readmitted = Vec::new();
+        for tx in reverted_txs {
+            let hash = tx.hash().to_string();
+            if tx.tx.nonce < next_nonce(tx.sender()) || transactions.contains_key(&hash) {
+                continue;
+            }
+            if transactions.len() >= self.config.max_size {
+                break;
+            }
+            transactions.insert(hash.clone(), tx);
+            self.publish(PoolEvent::Added { hash: hash.clone() });
+            readmitted.push(hash);
+        }
+
+        let rest = std::mem::take(&mut *pending);
+        pending.extend(readmitted.iter().cloned());
+        pending.extend(rest);
+        readmitted
+    }
+
+    /// Number of pooled transactions sent by `sender`
+    pub fn pending_count_for(&self, sender: &Address) -> usize {
+        let transactions = self.transactions.lock();
+        transactions
+            .values()
+            .filter(|tx| tx.sender() == *sender)
+            .count()
+    }
+
+    /// Nonce after the run of pooled transactions from `sender` that starts
+    /// at `state_nonce`; pooled transactions past a gap are not counted
+    pub fn next_nonce(&self, sender: &Address, state_nonce: u64) -> u64 {
+        let transactions = self.transactions.lock();
+        let nonces: std::collections::HashSet<u64> = transactions
+            .values()
+            .filter(|tx| tx.sender() == *sender)
+            .map(|tx| tx.tx.nonce)
+            .collect();
+        let mut next = state_nonce;
+        while nonces.contains(&next) {
+            next += 1;
+        }
+        next
+    }
+
     /// Get pool size
     pub fn size(&self) -> usize {
         let transactions = self.transactions.lock();
@@ -128,6 +476,64 @@ impl TransactionPool {
     }
 }
 
+/// Interleave per-sender queues according to `policy`.
+///
+/// Senders keep their order of first arrival; fee ties go to the earlier sender.
+fn order_by_sender<'a>(
+    arrived: impl Iterator<Item = &'a merklith_types::SignedTransaction>,
+    policy: OrderingPolicy,
+    limit: usize,
+) -> Vec<merklith_types::SignedTransaction> {
+    let mut senders: HashMap<Address, usize> = HashMap::new();
+    let mut queues: Vec<Vec<&merklith_types::SignedTransaction>> = Vec::new();
+    for tx in arrived {
+        let index = *senders.entry(tx.sender()).or_insert_with(|| {
+            queues.push(Vec::new());
+            queues.len() - 1
+        });
+        queues[index].push(tx);
+    }
+    let mut queues: Vec<VecDeque<_>> = queues
+        .into_iter()
+        .map(|mut queue| {
+            queue.sort_by_key(|tx| tx.tx.nonce);
+            queue.into()
+        })
+        .collect();
+
+    let total = queues.iter().map(VecDeque::len).sum::<usize>().min(limit);
+    let mut ordered = Vec::with_capacity(total);
+    let mut turn = 0;
+    while ordered.len() < total {
+        let next = match policy {
+            OrderingPolicy::FeePriority => {
+                let mut best: Option<(usize, (U256, U256))> = None;
+                for (index, queue) in queues.iter().enumerate() {
+                    if let Some(tx) = queue.front() {
+                        let fee = (tx.tx.max_priority_fee_per_gas, tx.tx.max_fee_per_gas);
+                        if best.map_or(true, |(_, best_fee)| fee > best_fee) {
+                            best = Some((index, fee));
+                        }
+                    }
+                }
+                best.map(|(index, _)| index)
+            }
+            _ => {
+                while queues[turn % queues.len()].is_empty() {
+                    turn += 1;
+                }
+                turn += 1;
+                Some((turn - 1) % queues.len())
+            }
+        };
+        match next.and_then(|index| queues[index].pop_front()) {
+            Some(tx) => ordered.push(tx.clone()),
+            None => break,
+        }
+    }
+    ordered
+}
+
 impl Default for TransactionPool {
     fn default() -> Self {
         Self::new(PoolConfig::default())
@@ -135,7 +541,10 @@ impl Default for TransactionPool {
 }
 
 pub mod pool {
-    pub use super::{PoolConfig, PoolError, TransactionPool};
+    pub use super::{
+        OrderingPolicy, PoolConfig, PoolError, PoolEvent, PoolPosition, RemovalReason, TransactionPool,
+        DEFAULT_MAX_TX_SIZE, PRICE_BUMP_PERCENT,
+    };
 }
 
 // Re-export for convenience
@@ -144,10 +553,12 @@ pub use pool::*;
 #[cfg(test)]
 mod tests {
     use super::*;
-    use merklith_types::{Transaction, Address, U256};
+    use merklith_types::{
+        Address, Ed25519PublicKey, Ed25519Signature, SignedTransaction, Transaction, U256,
+    };
 
-    fn create_test_transaction(nonce: u64) -> Transaction {
-        Transaction::new(
+    fn create_test_transaction(nonce: u64) -> SignedTransaction {
+        let tx = Transaction::new(
             1, // chain_id
             nonce,
             Some(Address::ZERO),
@@ -155,6 +566,11 @@ mod tests {
             21000,
             U256::from(1u64),
             U256::from(1u64),
+        );
+        SignedTransaction::new(
+            tx,
+            Ed25519Signature::from_bytes([0u8; 64]),
+            Ed25519PublicKey::from_bytes([1u8; 32]),
         )
     }
 
@@ -182,8 +598,8 @@ mod tests {
         let hash = pool.add_transaction(tx.clone()).unwrap();
         let retrieved = pool.get_transaction(&hash).unwrap();
         
-        assert_eq!(retrieved.nonce, tx.nonce);
-        assert_eq!(retrieved.chain_id, tx.chain_id);
+        assert_eq!(retrieved.hash(), tx.hash());
+        assert_eq!(retrieved.tx.nonce, tx.tx.nonce);
     }
 
     #[test]
@@ -201,7 +617,7 @@ mod tests {
         let hash = pool.add_transaction(tx).unwrap();
         assert_eq!(pool.size(), 1);
         
-        pool.remove_transaction(&hash);
+        pool.remove_transaction(&hash, RemovalReason::Dropped);
         assert_eq!(pool.size(), 0);
         
         let retrieved = pool.get_transaction(&hash);
@@ -238,6 +654,10 @@ mod tests {
         let config = PoolConfig {
             max_size: 2,
             max_per_account: 100,
+            min_gas_price: U256::ONE,
+            ordering: OrderingPolicy::Fifo,
+            block_gas_limit: 30_000_000,
+            max_tx_size: DEFAULT_MAX_TX_SIZE,
         };
         let pool = TransactionPool::new(config);
         
@@ -248,6 +668,265 @@ mod tests {
         assert!(matches!(result, Err(PoolError::PoolFull)));
     }
 
+    #[test]
+    fn test_fee_bump_replacement() {
+        let pool = TransactionPool::default();
+        let mut events = pool.subscribe();
+        let priced = |fee: u64| {
+            let mut tx = create_test_transaction(0);
+            tx.tx.max_fee_per_gas = U256::from(fee);
+            tx.tx.max_priority_fee_per_gas = U256::from(fee);
+            tx
+        };
+
+        let original = pool.add_transaction(priced(100)).unwrap();
+        pool.add_transaction(create_test_transaction(1)).unwrap();
+        assert!(matches!(
+            pool.add_transaction(priced(109)),
+            Err(PoolError::ReplacementUnderpriced { required, .. }) if required == U256::from(110u64)
+        ));
+
+        let bumped = pool.add_transaction(priced(110)).unwrap();
+        assert_eq!(pool.size(), 2);
+        assert!(pool.get_transaction(&original).is_none());
+        // The replacement keeps the original's place in line
+        assert_eq!(pool.get_pending(10)[0].hash().to_string(), bumped);
+
+        assert!(matches!(events.try_recv().unwrap(), PoolEvent::Added { hash } if hash == original));
+        assert!(matches!(events.try_recv().unwrap(), PoolEvent::Added { .. }));
+        assert_eq!(
+            events.try_recv().unwrap(),
+            PoolEvent::Removed { hash: original.clone(), reason: RemovalReason::Replaced }
+        );
+        assert_eq!(events.try_recv().unwrap(), PoolEvent::Replaced { old_hash: original, new_hash: bumped });
+        assert!(events.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_pool_full_eviction() {
+        let pool = TransactionPool::new(PoolConfig { max_size: 2, ..PoolConfig::default() });
+        let priced = |nonce: u64, fee: u64| {
+            let mut tx = create_test_transaction(nonce);
+            tx.tx.max_fee_per_gas = U256::from(fee);
+            tx
+        };
+
+        pool.add_transaction(priced(0, 5)).unwrap();
+        let cheapest = pool.add_transaction(priced(1, 2)).unwrap();
+        let mut events = pool.subscribe();
+
+        // Paying no more than the cheapest pooled transaction is not enough
+        assert!(matches!(pool.add_transaction(priced(2, 2)), Err(PoolError::PoolFull)));
+
+        let added = pool.add_transaction(priced(2, 3)).unwrap();
+        assert_eq!(pool.size(), 2);
+        assert!(pool.get_transaction(&cheapest).is_none());
+        assert_eq!(
+            events.try_recv().unwrap(),
+            PoolEvent::Removed { hash: cheapest, reason: RemovalReason::Evicted }
+        );
+        assert_eq!(events.try_recv().unwrap(), PoolEvent::Added { hash: added.clone() });
+
+        // Removal reasons are passed through from the caller
+        pool.remove_transaction(&added, RemovalReason::Mined);
+        assert_eq!(events.try_recv().unwrap(), PoolEvent::Removed { hash: added, reason: RemovalReason::Mined });
+    }
+
+    #[test]
+    fn test_drop_expired() {
+        let pool = TransactionPool::new(PoolConfig::default());
+
+        let mut expiring = create_test_transaction(0);
+        expiring.tx = expiring.tx.with_valid_until_block(5);
+        let expiring_hash = pool.add_transaction(expiring).unwrap();
+        pool.add_transaction(create_test_transaction(1)).unwrap();
+
+        assert!(pool.drop_expired(5).is_empty());
+        assert_eq!(pool.size(), 2);
+
+        assert_eq!(pool.drop_expired(6), vec![expiring_hash.clone()]);
+        assert_eq!(pool.size(), 1);
+        assert!(pool.get_transaction(&expiring_hash).is_none());
+        assert_eq!(pool.get_pending(10).len(), 1);
+    }
+
+    #[test]
+    fn test_on_reorg() {
+        let pool = TransactionPool::new(PoolConfig::default());
+        let mined = create_test_transaction(0);
+        let mined_hash = pool.add_transaction(mined.clone()).unwrap();
+        pool.add_transaction(create_test_transaction(1)).unwrap();
+
+        // The block including nonce 0 is produced, then reorged out
+        pool.remove_transaction(&mined_hash, RemovalReason::Mined);
+        assert_eq!(pool.get_pending(10).len(), 1);
+
+        let readmitted = pool.on_reorg(vec![mined.clone()], |_| 0);
+        assert_eq!(readmitted, vec![mined_hash.clone()]);
+        let pending = pool.get_pending(10);
+        assert_eq!(pending.len(), 2);
+        assert_eq!(pending[0].hash(), mined.hash());
+        assert_eq!(pending[1].tx.nonce, 1);
+
+        // Re-applying the same reorg admits nothing twice
+        assert!(pool.on_reorg(vec![mined.clone()], |_| 0).is_empty());
+
+        // When the new chain already used nonce 0, it stays out and is dropped
+        let readmitted = pool.on_reorg(vec![mined], |_| 1);
+        assert!(readmitted.is_empty());
+        assert!(pool.get_transaction(&mined_hash).is_none());
+        assert_eq!(pool.size(), 1);
+    }
+
+    #[test]
+    fn test_min_gas_price() {
+        let pool = TransactionPool::new(PoolConfig {
+            min_gas_price: U256::from(10u64),
+            ..PoolConfig::default()
+        });
+        let priced = |nonce: u64, max_fee: u64| {
+            let mut tx = create_test_transaction(nonce);
+            tx.tx.max_fee_per_gas = U256::from(max_fee);
+            tx
+        };
+
+        assert!(matches!(
+            pool.add_transaction(priced(0, 9)),
+            Err(PoolError::Underpriced { .. })
+        ));
+        assert!(pool.add_transaction(priced(1, 10)).is_ok());
+
+        // The floor follows the base fee but not below the configured minimum
+        pool.set_base_fee(U256::from(20u64));
+        assert!(pool.add_transaction(priced(2, 15)).is_err());
+        pool.set_base_fee(U256::ONE);
+        assert_eq!(pool.min_gas_price(), U256::from(10u64));
+        assert!(pool.add_transaction(priced(3, 15)).is_ok());
+        assert_eq!(pool.size(), 2);
+    }
+
+    #[test]
+    fn test_max_tx_size() {
+        let with_data = |nonce: u64, len: usize| {
+            let mut tx = create_test_transaction(nonce);
+            tx.tx.data = vec![0xab; len];
+            tx
+        };
+        let base = borsh::object_length(&with_data(0, 0)).unwrap();
+        let pool = TransactionPool::new(PoolConfig {
+            max_tx_size: base + 1_000,
+            ..PoolConfig::default()
+        });
+
+        assert!(pool.add_transaction(with_data(0, 1_000)).is_ok());
+        assert!(matches!(
+            pool.add_transaction(with_data(1, 1_001)),
+            Err(PoolError::Oversized { size, max_tx_size }) if size == base + 1_001 && max_tx_size == base + 1_000
+        ));
+        assert_eq!(pool.size(), 1);
+    }
+
+    #[test]
+    fn test_pending_count_for() {
+        let pool = TransactionPool::default();
+        let sender = create_test_transaction(0).sender();
+        assert_eq!(pool.pending_count_for(&sender), 0);
+
+        pool.add_transaction(create_test_transaction(0)).unwrap();
+        pool.add_transaction(create_test_transaction(1)).unwrap();
+        assert_eq!(pool.pending_count_for(&sender), 2);
+        assert_eq!(pool.pending_count_for(&Address::ZERO), 0);
+    }
+
+    #[test]
+    fn test_next_nonce_stops_at_gap() {
+        let pool = TransactionPool::default();
+        let sender = create_test_transaction(0).sender();
+        assert_eq!(pool.next_nonce(&sender, 0), 0);
+
+        pool.add_transaction(create_test_transaction(0)).unwrap();
+        pool.add_transaction(create_test_transaction(1)).unwrap();
+        pool.add_transaction(create_test_transaction(3)).unwrap();
+        assert_eq!(pool.next_nonce(&sender, 0), 2);
+        // Once 0 is mined the run starts at the state nonce
+        assert_eq!(pool.next_nonce(&sender, 1), 2);
+        assert_eq!(pool.next_nonce(&sender, 3), 4);
+    }
+
+    #[test]
+    fn test_ordering_policies() {
+        // (sender key byte, nonce, priority fee) in arrival order
+        let arrivals = [(1u8, 1u64, 1u64), (1, 0, 1), (1, 2, 1), (2, 0, 5), (2, 1, 2), (3, 0, 3)];
+        let order = |ordering: OrderingPolicy| {
+            let pool = TransactionPool::new(PoolConfig { ordering, ..PoolConfig::default() });
+            for (key, nonce, tip) in arrivals {
+                let mut tx = create_test_transaction(nonce);
+                tx.tx.max_priority_fee_per_gas = U256::from(tip);
+                tx.tx.max_fee_per_gas = U256::from(tip + 1);
+                tx.public_key = Ed25519PublicKey::from_bytes([key; 32]);
+                pool.add_transaction(tx).unwrap();
+            }
+            let sender_key = |tx: &SignedTransaction| tx.public_key.as_bytes()[0];
+            pool.get_pending(10)
+                .iter()
+                .map(|tx| (sender_key(tx), tx.tx.nonce))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            order(OrderingPolicy::Fifo),
+            vec![(1, 1), (1, 0), (1, 2), (2, 0), (2, 1), (3, 0)]
+        );
+        assert_eq!(
+            order(OrderingPolicy::FeePriority),
+            vec![(2, 0), (3, 0), (2, 1), (1, 0), (1, 1), (1, 2)]
+        );
+        assert_eq!(
+            order(OrderingPolicy::FairRoundRobin),
+            vec![(1, 0), (2, 0), (3, 0), (1, 1), (2, 1), (1, 2)]
+        );
+    }
+
+    #[test]
+    fn test_ordering_respects_limit() {
+        let pool = TransactionPool::new(PoolConfig {
+            ordering: OrderingPolicy::FairRoundRobin,
+            ..PoolConfig::default()
+        });
+        for nonce in 0..5 {
+            pool.add_transaction(create_test_transaction(nonce)).unwrap();
+        }
+        let pending = pool.get_pending(3);
+        assert_eq!(pending.iter().map(|tx| tx.tx.nonce).collect::<Vec<_>>(), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_position() {
+        let pool = TransactionPool::new(PoolConfig {
+            ordering: OrderingPolicy::FeePriority,
+            block_gas_limit: 50_000,
+            ..PoolConfig::default()
+        });
+        let mut hashes = Vec::new();
+        for (key, tip) in [(1u8, 1u64), (2, 3), (3, 2)] {
+            let mut tx = create_test_transaction(0);
+            tx.tx.max_priority_fee_per_gas = U256::from(tip);
+            tx.tx.max_fee_per_gas = U256::from(tip + 1);
+            tx.public_key = Ed25519PublicKey::from_bytes([key; 32]);
+            hashes.push(pool.add_transaction(tx).unwrap());
+        }
+
+        // Two 21000-gas transfers fit in a block
+        let highest = pool.position(&hashes[1]).unwrap();
+        assert_eq!(highest, PoolPosition { rank: 0, gas_ahead: 0, estimated_blocks: 1 });
+        let middle = pool.position(&hashes[2]).unwrap();
+        assert_eq!(middle, PoolPosition { rank: 1, gas_ahead: 21_000, estimated_blocks: 1 });
+        let lowest = pool.position(&hashes[0]).unwrap();
+        assert_eq!(lowest, PoolPosition { rank: 2, gas_ahead: 42_000, estimated_blocks: 2 });
+
+        assert!(pool.position("unknown").is_none());
+    }
+
     #[test]
     fn test_pool_default() {
         let pool: TransactionPool = Default::default();
@@ -0,0 +1,336 @@
+//! Transaction acceptance rules
+//!
+//! A [`ValidationPipeline`] runs [`TxValidator`]s in order and stops at the
+//! first failure. The default set covers size, chain id, signature, expiry,
+//! fee floor, nonce and balance; operators can append their own rules.
+
+use merklith_core::state_machine::State;
+use merklith_types::{SignedTransaction, TransactionType, U256};
+
+/// Chain view a validator checks a transaction against
+pub struct ValidationContext<'a> {
+    /// Current chain state
+    pub state: &'a State,
+    /// Chain id transactions must be signed for
+    pub chain_id: u64,
+    /// Lowest accepted `max_fee_per_gas`
+    pub min_gas_price: U256,
+    /// Largest accepted transaction, in borsh-encoded bytes
+    pub max_tx_size: usize,
+}
+
+/// Why a transaction was refused
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationError {
+    /// Encoded transaction is larger than allowed
+    Oversized { size: usize, max_tx_size: usize },
+    /// Signed for another chain
+    WrongChainId { expected: u64, got: u64 },
+    /// Signature does not verify
+    InvalidSignature(String),
+    /// `valid_until_block` is before the next block
+    Expired { valid_until_block: u64, next_block: u64 },
+    /// `max_fee_per_gas` is below the price floor
+    Underpriced { max_fee_per_gas: U256, min_gas_price: U256 },
+    /// Nonce already used
+    NonceTooLow { expected: u64, got: u64 },
+    /// Sender cannot pay value plus maximum gas
+    InsufficientBalance { balance: U256, required: U256 },
+    /// Refused by a custom rule
+    Rejected { rule: String, reason: String },
+}
+
+impl std::fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ValidationError::Oversized { size, max_tx_size } => {
+                write!(f, "Transaction too large: {} bytes exceeds maximum {}", size, max_tx_size)
+            }
+            ValidationError::WrongChainId { expected, got } => {
+                write!(f, "Invalid chain_id: expected {}, got {}", expected, got)
+            }
+            ValidationError::InvalidSignature(e) => write!(f, "Invalid signature: {}", e),
+            ValidationError::Expired { valid_until_block, next_block } => write!(
+                f,
+                "Transaction expired: valid until block {}, next block is {}",
+                valid_until_block, next_block
+            ),
+            ValidationError::Underpriced { max_fee_per_gas, min_gas_price } => write!(
+                f,
+                "Transaction underpriced: max fee per gas {} below minimum {}",
+                max_fee_per_gas, min_gas_price
+            ),
+            ValidationError::NonceTooLow { expected, got } => {
+                write!(f, "nonce too low: expected {}, got {}", expected, got)
+            }
+            ValidationError::InsufficientBalance { balance, required } => {
+                write!(f, "Insufficient balance: have {}, need {}", balance, required)
+            }
+            ValidationError::Rejected { rule, reason } => write!(f, "Rejected by {}: {}", rule, reason),
+        }
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+/// One acceptance rule
+pub trait TxValidator: Send + Sync {
+    /// Short name, for logs and errors
+    fn name(&self) -> &str;
+
+    /// Accept `tx` or say why not
+    fn validate(&self, tx: &SignedTransaction, ctx: &ValidationContext) -> Result<(), ValidationError>;
+}
+
+/// Shared rules, so a caller can keep a handle to one in a pipeline
+impl<T: TxValidator + ?Sized> TxValidator for std::sync::Arc<T> {
+    fn name(&self) -> &str {
+        (**self).name()
+    }
+
+    fn validate(&self, tx: &SignedTransaction, ctx: &ValidationContext) -> Result<(), ValidationError> {
+        (**self).validate(tx, ctx)
+    }
+}
+
+/// Ordered acceptance rules
+pub struct ValidationPipeline {
+    validators: Vec<Box<dyn TxValidator>>,
+}
+
+impl ValidationPipeline {
+    /// A pipeline with no rules
+    pub fn empty() -> Self {
+        Self { validators: Vec::new() }
+    }
+
+    /// Append `validator`, run after those already added
+    pub fn with_validator(mut self, validator: impl TxValidator + 'static) -> Self {
+        self.validators.push(Box::new(validator));
+        self
+    }
+
+    /// Names of the rules, in the order they run
+    pub fn names(&self) -> Vec<&str> {
+        self.validators.iter().map(|v| v.name()).collect()
+    }
+
+    /// Run every rule in order, stopping at the first failure
+    pub fn validate(&self, tx: &SignedTransaction, ctx: &ValidationContext) -> Result<(), ValidationError> {
+        self.validators.iter().try_for_each(|validator| validator.validate(tx, ctx))
+    }
+}
+
+impl Default for ValidationPipeline {
+    fn default() -> Self {
+        Self::empty()
+            .with_validator(SizeValidator)
+            .with_validator(ChainIdValidator)
+            .with_validator(SignatureValidator)
+            .with_validator(ExpiryValidator)
+            .with_validator(FeeFloorValidator)
+            .with_validator(NonceValidator)
+            .with_validator(BalanceValidator)
+    }
+}
+
+impl std::fmt::Debug for ValidationPipeline {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.names()).finish()
+    }
+}
+
+/// Cancels of delayed transactions pay no gas, so fee, nonce and balance
+/// rules do not apply to them
+fn is_cancel(tx: &SignedTransaction) -> bool {
+    tx.tx.tx_type == TransactionType::CancelDelayed
+}
+
+/// Encoded size within `max_tx_size`
+pub struct SizeValidator;
+
+impl TxValidator for SizeValidator {
+    fn name(&self) -> &str {
+        "size"
+    }
+
+    fn validate(&self, tx: &SignedTransaction, ctx: &ValidationContext) -> Result<(), ValidationError> {
+        let size = borsh::object_length(tx).unwrap_or(usize::MAX);
+        if size > ctx.max_tx_size {
+            return Err(ValidationError::Oversized { size, max_tx_size: ctx.max_tx_size });
+        }
+        Ok(())
+    }
+}
+
+/// Signed for this chain
+pub struct ChainIdValidator;
+
+impl TxValidator for ChainIdValidator {
+    fn name(&self) -> &str {
+        "chain_id"
+    }
+
+    fn validate(&self, tx: &SignedTransaction, ctx: &ValidationContext) -> Result<(), ValidationError> {
+        if tx.tx.chain_id != ctx.chain_id {
+            return Err(ValidationError::WrongChainId { expected: ctx.chain_id, got: tx.tx.chain_id });
+        }
+        Ok(())
+    }
+}
+
+/// Ed25519 signature over the signing hash
+pub struct SignatureValidator;
+
+impl TxValidator for SignatureValidator {
+    fn name(&self) -> &str {
+        "signature"
+    }
+
+    fn validate(&self, tx: &SignedTransaction, _ctx: &ValidationContext) -> Result<(), ValidationError> {
+        merklith_crypto::ed25519_verify(&tx.public_key, tx.tx.signing_hash().as_bytes(), &tx.signature)
+            .map_err(|e| ValidationError::InvalidSignature(e.to_string()))
+    }
+}
+
+/// Still valid in the next block
+pub struct ExpiryValidator;
+
+impl TxValidator for ExpiryValidator {
+    fn name(&self) -> &str {
+        "expiry"
+    }
+
+    fn validate(&self, tx: &SignedTransaction, ctx: &ValidationContext) -> Result<(), ValidationError> {
+        let next_block = ctx.state.block_number() + 1;
+        match tx.tx.valid_until_block {
+            Some(valid_until_block) if tx.tx.is_expired(next_block) => {
+                Err(ValidationError::Expired { valid_until_block, next_block })
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// `max_fee_per_gas` at or above the price floor
+pub struct FeeFloorValidator;
+
+impl TxValidator for FeeFloorValidator {
+    fn name(&self) -> &str {
+        "fee_floor"
+    }
+
+    fn validate(&self, tx: &SignedTransaction, ctx: &ValidationContext) -> Result<(), ValidationError> {
+        if !is_cancel(tx) && tx.tx.max_fee_per_gas < ctx.min_gas_price {
+            return Err(ValidationError::Underpriced {
+                max_fee_per_gas: tx.tx.max_fee_per_gas,
+                min_gas_price: ctx.min_gas_price,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Nonce not yet used. Nonces ahead of the sender's are left to the
+/// acceptance path, which queues them.
+pub struct NonceValidator;
+
+impl TxValidator for NonceValidator {
+    fn name(&self) -> &str {
+        "nonce"
+    }
+
+    fn validate(&self, tx: &SignedTransaction, ctx: &ValidationContext) -> Result<(), ValidationError> {
+        let expected = ctx.state.nonce(&tx.sender());
+        if !is_cancel(tx) && tx.tx.nonce < expected {
+            return Err(ValidationError::NonceTooLow { expected, got: tx.tx.nonce });
+        }
+        Ok(())
+    }
+}
+
+/// Sender can pay the value and the maximum gas cost
+pub struct BalanceValidator;
+
+impl TxValidator for BalanceValidator {
+    fn name(&self) -> &str {
+        "balance"
+    }
+
+    fn validate(&self, tx: &SignedTransaction, ctx: &ValidationContext) -> Result<(), ValidationError> {
+        let balance = ctx.state.balance(&tx.sender());
+        let required = tx.max_cost();
+        if !is_cancel(tx) && balance < required {
+            return Err(ValidationError::InsufficientBalance { balance, required });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use merklith_types::{Address, Transaction};
+    use std::collections::HashSet;
+
+    /// Refuses transactions from listed senders
+    struct Blocklist(HashSet<Address>);
+
+    impl TxValidator for Blocklist {
+        fn name(&self) -> &str {
+            "blocklist"
+        }
+
+        fn validate(&self, tx: &SignedTransaction, _ctx: &ValidationContext) -> Result<(), ValidationError> {
+            if self.0.contains(&tx.sender()) {
+                return Err(ValidationError::Rejected {
+                    rule: self.name().to_string(),
+                    reason: format!("sender {} is blocked", tx.sender()),
+                });
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_custom_validator() {
+        let dir = std::env::temp_dir().join(format!("merklith_pipeline_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let allowed = merklith_crypto::Keypair::from_seed(&[1u8; 32]);
+        let blocked = merklith_crypto::Keypair::from_seed(&[2u8; 32]);
+        let mut genesis = State::devnet_genesis();
+        genesis.add_alloc(allowed.address(), U256::from(1_000_000u64));
+        genesis.add_alloc(blocked.address(), U256::from(1_000_000u64));
+        let state = State::with_genesis(dir.clone(), genesis, merklith_storage::PruningConfig::archive());
+        let ctx = ValidationContext {
+            state: &state,
+            chain_id: state.chain_id(),
+            min_gas_price: U256::ONE,
+            max_tx_size: crate::DEFAULT_MAX_TX_SIZE,
+        };
+        let sign = |keypair: &merklith_crypto::Keypair, nonce: u64| {
+            let tx = Transaction::new(state.chain_id(), nonce, Some(Address::from_bytes([9u8; 20])), U256::ONE, 21000, U256::ONE, U256::ZERO);
+            let (signature, public_key) = keypair.sign_transaction(&tx);
+            SignedTransaction::new(tx, signature, public_key)
+        };
+
+        let pipeline = ValidationPipeline::default().with_validator(Blocklist(HashSet::from([blocked.address()])));
+        assert_eq!(pipeline.names().last(), Some(&"blocklist"));
+        pipeline.validate(&sign(&allowed, 0), &ctx).unwrap();
+        let error = pipeline.validate(&sign(&blocked, 0), &ctx).unwrap_err();
+        assert!(matches!(error, ValidationError::Rejected { ref rule, .. } if rule == "blocklist"));
+
+        // Defaults run first and short-circuit
+        let mut tampered = sign(&blocked, 0);
+        tampered.tx.value = U256::from(2u64);
+        assert!(matches!(pipeline.validate(&tampered, &ctx), Err(ValidationError::InvalidSignature(_))));
+        let rich = sign(&allowed, 0);
+        let mut poor = rich.clone();
+        poor.tx.value = U256::from(10_000_000u64);
+        let (signature, _) = allowed.sign_transaction(&poor.tx);
+        poor.signature = signature;
+        assert!(matches!(pipeline.validate(&poor, &ctx), Err(ValidationError::InsufficientBalance { .. })));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -1,15 +1,34 @@
+use crate::address::Address;
 use crate::u256::U256;
+use std::collections::BTreeMap;
+
+/// Names of known protocol upgrades.
+pub mod upgrades {
+    /// Reprices cold storage reads in the VM gas schedule (200 -> 800).
+    pub const STORAGE_REPRICE: &str = "storage_reprice";
+    /// Raises the max base fee change per block from 5% to 12%.
+    pub const FAST_FEE_ADJUST: &str = "fast_fee_adjust";
+    /// Raises the committee share needed to finalize a block from 67% to 75%.
+    pub const SUPERMAJORITY_FINALITY: &str = "supermajority_finality";
+}
 
 /// Chain-level configuration parameters.
 /// These can be changed via governance (AIP).
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ChainConfig {
     pub chain_id: u64,
 
     // Block parameters
     pub block_time_ms: u64,              // 2000 (2 seconds)
-    pub gas_limit: u64,                   // 30_000_000
+    pub gas_limit: u64,                   // 30_000_000 (genesis block gas limit)
     pub gas_target: u64,                  // 15_000_000
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub gas_limit_adjustment_divisor: u64, // 1024 (max change 1/1024 per block, 0 = fixed, the serde default)
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub min_gas_limit: u64,               // 5_000_000
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub max_gas_limit: u64,               // 60_000_000 (0 = unbounded)
     pub base_fee_max_change_pct: u8,      // 5 (5% per block)
     pub max_extra_data_bytes: usize,      // 32
 
@@ -31,6 +50,12 @@ pub struct ChainConfig {
     pub max_base_fee: U256,              // 10_000 Spark per gas
     pub max_priority_fee_multiplier: u8, // 2 (max 2x base_fee)
     pub fee_guarantee_blocks: u64,       // 10 blocks validity
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub fee_burn_pct: u8,                // 0 (share of the base fee burned)
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub fee_treasury_pct: u8,            // 0 (share of unburned fees to treasury)
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub treasury_address: Address,
 
     // Governance
     pub aip_deposit: U256,               // 10_000 MERK
@@ -45,6 +70,10 @@ pub struct ChainConfig {
     pub invalid_block_slash_pct: u8,     // 10
     pub censoring_slash_pct: u8,         // 50
     pub collusion_slash_pct: u8,         // 30
+
+    // Upgrades: name -> activation block
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub upgrades: BTreeMap<String, u64>,
 }
 
 impl Default for ChainConfig {
@@ -61,6 +90,9 @@ impl ChainConfig {
             block_time_ms: 2000,
             gas_limit: 30_000_000,
             gas_target: 15_000_000,
+            gas_limit_adjustment_divisor: 1024,
+            min_gas_limit: 5_000_000,
+            max_gas_limit: 60_000_000,
             base_fee_max_change_pct: 5,
             max_extra_data_bytes: 32,
             epoch_length: 1000,
@@ -76,6 +108,9 @@ impl ChainConfig {
             max_base_fee: U256::from(10_000u64),
             max_priority_fee_multiplier: 2,
             fee_guarantee_blocks: 10,
+            fee_burn_pct: 0,
+            fee_treasury_pct: 0,
+            treasury_address: Address::ZERO,
             aip_deposit: U256::from(10_000u64) * U256::MERK,
             agp_deposit: U256::from(1_000u64) * U256::MERK,
             aep_deposit: U256::from(100_000u64) * U256::MERK,
@@ -86,6 +121,7 @@ impl ChainConfig {
             invalid_block_slash_pct: 10,
             censoring_slash_pct: 50,
             collusion_slash_pct: 30,
+            upgrades: BTreeMap::new(),
         }
     }
 
@@ -125,6 +161,132 @@ impl ChainConfig {
     pub fn is_valid_chain_id(&self) -> bool {
         self.chain_id != 0
     }
+
+    /// Schedule an upgrade to activate at `block`
+    pub fn with_upgrade(mut self, name: &str, block: u64) -> Self {
+        self.upgrades.insert(name.to_string(), block);
+        self
+    }
+
+    /// Whether upgrade `name` is active at `block`
+    pub fn is_active(&self, name: &str, block: u64) -> bool {
+        self.upgrades.get(name).is_some_and(|activation| block >= *activation)
+    }
+
+    /// Range of gas limits a block may pick after a parent with `parent_gas_limit`.
+    ///
+    /// Each block moves at most `1/gas_limit_adjustment_divisor` of its
+    /// parent's limit and stays within `min_gas_limit..=max_gas_limit`.
+    pub fn gas_limit_bounds(&self, parent_gas_limit: u64) -> (u64, u64) {
+        if self.gas_limit_adjustment_divisor == 0 {
+            return (self.gas_limit, self.gas_limit);
+        }
+        let max_delta = (parent_gas_limit / self.gas_limit_adjustment_divisor).max(1);
+        let max_gas_limit = if self.max_gas_limit == 0 { u64::MAX } else { self.max_gas_limit };
+        let lower = parent_gas_limit.saturating_sub(max_delta).max(self.min_gas_limit);
+        let upper = parent_gas_limit.saturating_add(max_delta).min(max_gas_limit);
+        (lower.min(upper), upper)
+    }
+
+    /// Gas limit of the block after a parent that used `parent_gas_used` of
+    /// `parent_gas_limit`.
+    ///
+    /// Like the EIP-1559 base fee, the limit follows utilization: a parent
+    /// above the target share (`gas_target / gas_limit`) of its limit raises
+    /// it, one below lowers it, and full or empty blocks move it by the
+    /// whole allowed delta. A proposer's `desired` limit overrides the rule
+    /// but is clamped to [`gas_limit_bounds`](Self::gas_limit_bounds).
+    pub fn next_gas_limit(&self, parent_gas_limit: u64, parent_gas_used: u64, desired: Option<u64>) -> u64 {
+        let (lower, upper) = self.gas_limit_bounds(parent_gas_limit);
+        if let Some(desired) = desired {
+            return desired.clamp(lower, upper);
+        }
+        if self.gas_limit_adjustment_divisor == 0 || self.gas_limit == 0 {
+            return parent_gas_limit.clamp(lower, upper);
+        }
+
+        let parent = parent_gas_limit as i128;
+        let target = (parent * self.gas_target as i128 / self.gas_limit as i128).clamp(1, parent.max(1));
+        let used = (parent_gas_used as i128).min(parent);
+        let max_delta = (parent / self.gas_limit_adjustment_divisor as i128).max(1);
+        // Scale the delta by how far usage is from the target, towards the full or empty end
+        let delta = if used >= target {
+            max_delta * (used - target) / (parent - target).max(1)
+        } else {
+            -(max_delta * (target - used) / target)
+        };
+        ((parent + delta).max(0) as u64).clamp(lower, upper)
+    }
+
+    /// Parameters in effect at `block`, with all activated upgrades applied
+    pub fn at_height(&self, block: u64) -> Self {
+        let mut config = self.clone();
+        if self.is_active(upgrades::FAST_FEE_ADJUST, block) {
+            config.base_fee_max_change_pct = 12;
+        }
+        if self.is_active(upgrades::SUPERMAJORITY_FINALITY, block) {
+            config.attestation_threshold_pct = 75;
+        }
+        config
+    }
+
+    /// Canonical byte encoding of every parameter, used for hashing
+    pub fn canonical_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(512);
+        out.extend_from_slice(&self.chain_id.to_le_bytes());
+
+        out.extend_from_slice(&self.block_time_ms.to_le_bytes());
+        out.extend_from_slice(&self.gas_limit.to_le_bytes());
+        out.extend_from_slice(&self.gas_target.to_le_bytes());
+        // Configs from before the limit adjusted leave these out, so a fixed
+        // limit (the serde default) encodes, and hashes, as it always did
+        if self.gas_limit_adjustment_divisor != 0 {
+            out.extend_from_slice(&self.gas_limit_adjustment_divisor.to_le_bytes());
+            out.extend_from_slice(&self.min_gas_limit.to_le_bytes());
+            out.extend_from_slice(&self.max_gas_limit.to_le_bytes());
+        }
+        out.push(self.base_fee_max_change_pct);
+        out.extend_from_slice(&(self.max_extra_data_bytes as u64).to_le_bytes());
+
+        out.extend_from_slice(&self.epoch_length.to_le_bytes());
+        out.extend_from_slice(&self.checkpoint_interval.to_le_bytes());
+        out.extend_from_slice(&self.committee_size.to_le_bytes());
+        out.extend_from_slice(&self.proposer_timeout_ms.to_le_bytes());
+        out.push(self.attestation_threshold_pct);
+
+        out.extend_from_slice(&self.min_stake.to_le_bytes());
+        out.extend_from_slice(&self.max_effective_stake.to_le_bytes());
+        out.extend_from_slice(&self.unbonding_period_blocks.to_le_bytes());
+        out.extend_from_slice(&self.stake_log_base.to_le_bytes());
+
+        out.extend_from_slice(&self.min_base_fee.to_le_bytes());
+        out.extend_from_slice(&self.max_base_fee.to_le_bytes());
+        out.push(self.max_priority_fee_multiplier);
+        out.extend_from_slice(&self.fee_guarantee_blocks.to_le_bytes());
+        out.push(self.fee_burn_pct);
+        out.push(self.fee_treasury_pct);
+        out.extend_from_slice(self.treasury_address.as_bytes());
+
+        out.extend_from_slice(&self.aip_deposit.to_le_bytes());
+        out.extend_from_slice(&self.agp_deposit.to_le_bytes());
+        out.extend_from_slice(&self.aep_deposit.to_le_bytes());
+        out.push(self.treasury_share_pct);
+        out.push(self.max_delegation_depth);
+
+        out.push(self.double_sign_slash_pct);
+        out.push(self.downtime_slash_pct_per_day);
+        out.push(self.invalid_block_slash_pct);
+        out.push(self.censoring_slash_pct);
+        out.push(self.collusion_slash_pct);
+
+        out.extend_from_slice(&(self.upgrades.len() as u64).to_le_bytes());
+        for (name, block) in &self.upgrades {
+            out.extend_from_slice(&(name.len() as u64).to_le_bytes());
+            out.extend_from_slice(name.as_bytes());
+            out.extend_from_slice(&block.to_le_bytes());
+        }
+        out
+    }
 }
 
 #[cfg(test)]
@@ -156,6 +318,45 @@ mod tests {
         assert_eq!(config.committee_size, 4);
     }
 
+    #[test]
+    fn test_gas_limit_adjustment() {
+        let config = ChainConfig::mainnet();
+        let max_delta = 30_000_000 / 1024;
+
+        // Sustained full blocks raise the limit by the full delta each time
+        let mut limit = config.gas_limit;
+        for _ in 0..10 {
+            let next = config.next_gas_limit(limit, limit, None);
+            assert_eq!(next, limit + limit / 1024);
+            limit = next;
+        }
+        assert!(limit > config.gas_limit);
+
+        // Empty blocks lower it, never by more than the delta
+        assert_eq!(config.next_gas_limit(30_000_000, 0, None), 30_000_000 - max_delta);
+        // Usage at the target leaves it unchanged
+        assert_eq!(config.next_gas_limit(30_000_000, 15_000_000, None), 30_000_000);
+        let half_up = config.next_gas_limit(30_000_000, 22_500_000, None);
+        assert!(half_up > 30_000_000 && half_up < 30_000_000 + max_delta);
+
+        // Proposers may nudge it, but only within the bounds
+        assert_eq!(config.next_gas_limit(30_000_000, 0, Some(40_000_000)), 30_000_000 + max_delta);
+        assert_eq!(config.next_gas_limit(30_000_000, 0, Some(30_000_100)), 30_000_100);
+        assert_eq!(config.next_gas_limit(config.min_gas_limit, 0, None), config.min_gas_limit);
+        assert_eq!(config.next_gas_limit(config.max_gas_limit, u64::MAX, None), config.max_gas_limit);
+
+        let mut fixed = config.clone();
+        fixed.gas_limit_adjustment_divisor = 0;
+        assert_eq!(fixed.next_gas_limit(30_000_000, 30_000_000, Some(1)), 30_000_000);
+        // A fixed limit encodes without the adjustment fields, whatever their values
+        fixed.min_gas_limit = 0;
+        fixed.max_gas_limit = 0;
+        assert_eq!(fixed.canonical_bytes().len(), config.canonical_bytes().len() - 24);
+        let mut bounded = fixed.clone();
+        bounded.max_gas_limit = 1;
+        assert_eq!(bounded.canonical_bytes(), fixed.canonical_bytes());
+    }
+
     #[test]
     fn test_attestation_threshold() {
         let config = ChainConfig::mainnet();
@@ -186,6 +387,32 @@ mod tests {
         assert_eq!(config.max_effective_stake, U256::from(100_000u64) * U256::MERK);
     }
 
+    #[test]
+    fn test_upgrade_activation() {
+        let config = ChainConfig::mainnet().with_upgrade(upgrades::FAST_FEE_ADJUST, 100);
+
+        assert!(!config.is_active(upgrades::FAST_FEE_ADJUST, 99));
+        assert!(config.is_active(upgrades::FAST_FEE_ADJUST, 100));
+        assert!(!config.is_active(upgrades::STORAGE_REPRICE, 1_000_000));
+
+        assert_eq!(config.at_height(99).base_fee_max_change_pct, 5);
+        assert_eq!(config.at_height(100).base_fee_max_change_pct, 12);
+
+        let config = config.with_upgrade(upgrades::SUPERMAJORITY_FINALITY, 200);
+        assert_eq!(config.at_height(199).attestation_threshold(10), 7);
+        assert_eq!(config.at_height(200).attestation_threshold(10), 8);
+    }
+
+    #[test]
+    fn test_upgrades_change_canonical_bytes() {
+        let base = ChainConfig::mainnet();
+        let upgraded = base.clone().with_upgrade(upgrades::STORAGE_REPRICE, 10);
+        let later = base.clone().with_upgrade(upgrades::STORAGE_REPRICE, 11);
+
+        assert_ne!(base.canonical_bytes(), upgraded.canonical_bytes());
+        assert_ne!(upgraded.canonical_bytes(), later.canonical_bytes());
+    }
+
     #[test]
     fn test_governance_deposits() {
         let config = ChainConfig::mainnet();
@@ -53,6 +53,9 @@ pub enum TypesError {
 
     #[error("Invalid nonce: {0}")]
     InvalidNonce(u64),
+
+    #[error("Invalid multisend: {0}")]
+    InvalidMultisend(String),
 }
 
 impl From<hex::FromHexError> for TypesError {
@@ -90,6 +90,58 @@ impl GenesisConfig {
         });
     }
 
+    /// Deterministic hash identifying this genesis.
+    ///
+    /// Covers the chain config, timestamp, extra data, every allocation and
+    /// every validator. Variable-length fields are length-prefixed so that
+    /// distinct configs can never encode to the same bytes.
+    pub fn genesis_hash(&self) -> Hash {
+        fn put_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+            out.extend_from_slice(&(bytes.len() as u64).to_le_bytes());
+            out.extend_from_slice(bytes);
+        }
+
+        let mut out = Vec::new();
+        out.extend_from_slice(b"MERKLITH_GENESIS_V1");
+        put_bytes(&mut out, &self.chain_config.canonical_bytes());
+        out.extend_from_slice(&self.timestamp.to_le_bytes());
+        put_bytes(&mut out, &self.extra_data);
+
+        out.extend_from_slice(&(self.alloc.len() as u64).to_le_bytes());
+        for alloc in &self.alloc {
+            out.extend_from_slice(alloc.address.as_bytes());
+            out.extend_from_slice(&alloc.balance.to_le_bytes());
+            match &alloc.code {
+                Some(code) => {
+                    out.push(1);
+                    put_bytes(&mut out, code);
+                }
+                None => out.push(0),
+            }
+            match &alloc.storage {
+                Some(storage) => {
+                    out.push(1);
+                    out.extend_from_slice(&(storage.len() as u64).to_le_bytes());
+                    for (key, value) in storage {
+                        out.extend_from_slice(key.as_bytes());
+                        put_bytes(&mut out, value);
+                    }
+                }
+                None => out.push(0),
+            }
+        }
+
+        out.extend_from_slice(&(self.validators.len() as u64).to_le_bytes());
+        for validator in &self.validators {
+            out.extend_from_slice(validator.address.as_bytes());
+            out.extend_from_slice(&validator.stake.to_le_bytes());
+            put_bytes(&mut out, validator.bls_public_key.as_bytes());
+            out.extend_from_slice(validator.ed25519_public_key.as_bytes());
+        }
+
+        Hash::compute(&out)
+    }
+
     /// Get mainnet genesis config
     pub fn mainnet() -> Self {
         Self {
@@ -171,4 +223,60 @@ mod tests {
         let devnet = GenesisConfig::devnet();
         assert_eq!(devnet.chain_config.chain_id, 1337);
     }
+
+    fn sample_genesis() -> GenesisConfig {
+        let mut config = GenesisConfig::devnet();
+        config.add_alloc(Address::from_bytes([1u8; 20]), U256::from(1000u64));
+        config.add_system_contract(
+            Address::from_bytes([2u8; 20]),
+            vec![0x00, 0x61, 0x73, 0x6d],
+            Some(vec![(Hash::ZERO, vec![1, 2, 3])]),
+        );
+        config.add_validator(
+            Address::from_bytes([3u8; 20]),
+            U256::from(5000u64),
+            BLSPublicKey::from_bytes(&[4u8; 48]).unwrap(),
+            Ed25519PublicKey::from_bytes([5u8; 32]),
+        );
+        config
+    }
+
+    #[test]
+    fn test_genesis_hash_deterministic() {
+        assert_eq!(sample_genesis().genesis_hash(), sample_genesis().genesis_hash());
+        assert_ne!(GenesisConfig::mainnet().genesis_hash(), GenesisConfig::testnet().genesis_hash());
+    }
+
+    #[test]
+    fn test_genesis_hash_sensitive_to_every_field() {
+        let base = sample_genesis().genesis_hash();
+        let mutations: Vec<fn(&mut GenesisConfig)> = vec![
+            |c| c.chain_config.chain_id += 1,
+            |c| c.chain_config.gas_limit += 1,
+            |c| c.chain_config.collusion_slash_pct += 1,
+            |c| c.chain_config.fee_burn_pct += 1,
+            |c| c.chain_config.treasury_address = Address::from_bytes([7u8; 20]),
+            |c| { c.chain_config.upgrades.insert("sample".to_string(), 1); },
+            |c| c.timestamp += 1,
+            |c| c.extra_data.push(0),
+            |c| c.alloc[0].address = Address::from_bytes([9u8; 20]),
+            |c| c.alloc[0].balance = U256::from(1001u64),
+            |c| c.alloc[0].code = Some(vec![]),
+            |c| c.alloc[1].code = None,
+            |c| c.alloc[1].storage = None,
+            |c| c.alloc[1].storage.as_mut().unwrap()[0].1.push(4),
+            |c| c.alloc.reverse(),
+            |c| c.validators[0].address = Address::from_bytes([9u8; 20]),
+            |c| c.validators[0].stake = U256::from(5001u64),
+            |c| c.validators[0].bls_public_key = BLSPublicKey::from_bytes(&[6u8; 48]).unwrap(),
+            |c| c.validators[0].ed25519_public_key = Ed25519PublicKey::from_bytes([6u8; 32]),
+            |c| c.validators.clear(),
+        ];
+
+        for (i, mutate) in mutations.iter().enumerate() {
+            let mut config = sample_genesis();
+            mutate(&mut config);
+            assert_ne!(config.genesis_hash(), base, "mutation {} did not change the hash", i);
+        }
+    }
 }
@@ -24,12 +24,17 @@ pub mod error;
 
 #[cfg(any(feature = "serde", feature = "borsh"))]
 mod serialization;
+#[cfg(test)]
+mod test_vectors;
 
 pub use address::Address;
 pub use hash::Hash;
 pub use u256::U256;
 pub use block::{Block, BlockHeader};
-pub use transaction::{Transaction, SignedTransaction, AccessListEntry, TransactionType};
+pub use transaction::{
+    Transaction, SignedTransaction, SponsoredTransaction, AccessListEntry, TransactionType,
+    MAX_MULTISEND_RECIPIENTS, SPONSOR_SIGNING_DOMAIN, TX_SIGNING_DOMAIN, TYPED_TX_SIGNING_DOMAIN,
+};
 pub use receipt::{TransactionReceipt, Log};
 pub use account::{Account, AccountType};
 pub use signature::{Ed25519Signature, Ed25519PublicKey, BLSSignature, BLSPublicKey};
@@ -41,7 +46,7 @@ pub use error::TypesError;
 pub mod prelude {
     pub use crate::{
         Address, Hash, U256, Block, BlockHeader,
-        Transaction, SignedTransaction, AccessListEntry, TransactionType,
+        Transaction, SignedTransaction, SponsoredTransaction, AccessListEntry, TransactionType,
         TransactionReceipt, Log,
         Account, AccountType,
         Ed25519Signature, Ed25519PublicKey,
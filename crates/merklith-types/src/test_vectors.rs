@@ -0,0 +1,120 @@
+//! Canonical encoding and hashing test vectors.
+//!
+//! Every vector pins the exact bytes produced for a fixed input. Nodes and
+//! external tooling must agree on these, so a change to any expected value
+//! is a consensus change and requires a network upgrade. Do not update a
+//! vector to make a test pass without one.
+
+use crate::{
+    Account, AccountType, Address, Block, BlockHeader, Ed25519PublicKey, Ed25519Signature, Hash,
+    SignedTransaction, Transaction, U256,
+};
+
+fn vector_tx() -> Transaction {
+    Transaction::new(
+        1337,
+        7,
+        Some(Address::from_bytes([0x11; 20])),
+        U256::from(1_000_000_000_000_000_000u64),
+        21_000,
+        U256::from(2_000_000_000u64),
+        U256::from(1_000_000_000u64),
+    )
+    .with_data(vec![0xde, 0xad, 0xbe, 0xef])
+}
+
+fn vector_header() -> BlockHeader {
+    let mut header = BlockHeader::new(
+        Hash::from_bytes([0x01; 32]),
+        42,
+        1_700_000_000,
+        30_000_000,
+        Address::from_bytes([0x22; 20]),
+    );
+    header.state_root = Hash::from_bytes([0x02; 32]);
+    header.transactions_root = Hash::from_bytes([0x03; 32]);
+    header.receipts_root = Hash::from_bytes([0x04; 32]);
+    header.gas_used = 21_000;
+    header.extra_data = b"merklith".to_vec();
+    header
+}
+
+#[test]
+fn test_hash_vector() {
+    assert_eq!(
+        Hash::compute(b"merklith").to_hex(),
+        "be3cfb249c49407205cb5de5a710ff0d702e6607bd5c21e7269b0440d4d52df1"
+    );
+    assert_eq!(Hash::ZERO.to_string(), format!("0x{}", "00".repeat(32)));
+}
+
+#[test]
+fn test_address_vector() {
+    let address = Address::from_bytes([0x11; 20]);
+    assert_eq!(address.to_hex(), "11".repeat(20));
+    assert_eq!(address.to_string(), "merk1zyg3zyg3zyg3zyg3zyg3zyg3zyg3zyg3djpn7f");
+    assert_eq!(address.to_string().parse::<Address>().unwrap(), address);
+}
+
+#[test]
+fn test_transaction_signing_hash_vector() {
+    let tx = vector_tx();
+    assert_eq!(
+        tx.signing_hash().to_hex(),
+        "3935a6fa19c5d4fd0a4435eb96f40bf77701f2a5f647a1a96cda462e3b4288e7"
+    );
+
+    // Contract creation encodes the recipient as 20 zero bytes
+    let create = Transaction { to: None, ..vector_tx() };
+    assert_eq!(
+        create.signing_hash().to_hex(),
+        "82c99d784544111776db1e31fb11eaa8498ad00ec8818d253285b3c370bf385e"
+    );
+
+    let signed = SignedTransaction::new(
+        tx,
+        Ed25519Signature::from_bytes([0x33; 64]),
+        Ed25519PublicKey::from_bytes([0x44; 32]),
+    );
+    assert_eq!(
+        signed.hash().to_hex(),
+        "e9d145e9db419ad33556d0a56b116d733c0df824532a631eefdcb48059ac8102"
+    );
+}
+
+#[test]
+fn test_block_hash_vector() {
+    let block = Block::new(vector_header(), vec![]);
+    assert_eq!(
+        block.hash().to_hex(),
+        "6cf0930396a4d47daffdae98d0eb713ce9bca93f16c0043925f4f4670989e218"
+    );
+    // Signatures are excluded from the block hash
+    let mut signed = block.clone();
+    signed.header.proposer_signature = Ed25519Signature::from_bytes([0x55; 64]);
+    assert_eq!(signed.hash(), block.hash());
+}
+
+#[test]
+#[cfg(feature = "borsh")]
+fn test_account_encoding_vector() {
+    let account = Account {
+        nonce: 3,
+        balance: U256::from(500u64),
+        code_hash: Hash::from_bytes([0x66; 32]),
+        storage_root: Hash::ZERO,
+        account_type: AccountType::Contract,
+    };
+    let encoded = borsh::to_vec(&account).unwrap();
+    assert_eq!(
+        hex::encode(&encoded),
+        concat!(
+            "0300000000000000",
+            "f401000000000000000000000000000000000000000000000000000000000000",
+            "6666666666666666666666666666666666666666666666666666666666666666",
+            "0000000000000000000000000000000000000000000000000000000000000000",
+            "01",
+        )
+    );
+    assert_eq!(borsh::from_slice::<Account>(&encoded).unwrap(), account);
+}
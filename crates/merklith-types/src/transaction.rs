@@ -5,6 +5,24 @@ use crate::signature::{Ed25519PublicKey, Ed25519Signature};
 use crate::u256::U256;
 use std::fmt;
 
+/// Domain tag prefixed to every transaction signing hash, so a transaction
+/// signature cannot be reused as a signature over another kind of message.
+pub const TX_SIGNING_DOMAIN: &[u8] = b"merklith-tx-v1";
+
+/// Domain tag for the sponsored, multisend and cancel types, followed by the
+/// type byte. It has the same length as [`TX_SIGNING_DOMAIN`], so neither
+/// message can be read as the other.
+pub const TYPED_TX_SIGNING_DOMAIN: &[u8] = b"merklith-tx-v2";
+
+/// Domain tag prefixed to the message a sponsor signs to pay for an intent.
+pub const SPONSOR_SIGNING_DOMAIN: &[u8] = b"merklith-sponsor-v1";
+
+/// Most recipients a single multisend may pay.
+pub const MAX_MULTISEND_RECIPIENTS: usize = 256;
+
+/// Encoded size of one multisend entry: 20-byte address, 32-byte LE amount.
+const MULTISEND_ENTRY_LEN: usize = Address::LEN + 32;
+
 /// Transaction type
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
 #[cfg_attr(feature = "borsh", derive(borsh::BorshSerialize, borsh::BorshDeserialize))]
@@ -16,6 +34,13 @@ pub enum TransactionType {
     Eip1559,
     /// Batch transaction
     Batch,
+    /// User intent whose gas is paid by a sponsor; only valid inside a
+    /// [`SponsoredTransaction`]
+    Sponsored,
+    /// Atomic transfer to many recipients, listed in `data`
+    Multisend,
+    /// Cancels one of the sender's delayed transactions; `data` is its hash
+    CancelDelayed,
 }
 
 /// Access list entry for warm storage slots
@@ -27,8 +52,11 @@ pub struct AccessListEntry {
 }
 
 /// Unsigned transaction data.
+///
+/// The borsh encoding only carries `valid_until_block` and
+/// `execute_after_block` when either is set, flagged in the type byte, so
+/// transactions encoded before they existed still decode.
 #[derive(Clone, Debug, PartialEq, Eq, Default)]
-#[cfg_attr(feature = "borsh", derive(borsh::BorshSerialize, borsh::BorshDeserialize))]
 pub struct Transaction {
     /// Transaction type
     pub tx_type: TransactionType,
@@ -50,6 +78,10 @@ pub struct Transaction {
     pub data: Vec<u8>,
     /// Access list for warm storage slots (optional optimization)
     pub access_list: Vec<AccessListEntry>,
+    /// Last block this transaction may be included in (None = no deadline)
+    pub valid_until_block: Option<u64>,
+    /// Block the transaction is held until and executed in (None = no delay)
+    pub execute_after_block: Option<u64>,
 }
 
 impl Transaction {
@@ -74,12 +106,97 @@ impl Transaction {
             max_priority_fee_per_gas,
             data: Vec::new(),
             access_list: Vec::new(),
+            valid_until_block: None,
+            execute_after_block: None,
+        }
+    }
+
+    /// Create a multisend paying each `(recipient, amount)` atomically.
+    ///
+    /// `value` is set to the sum of the amounts, so the sender is debited once.
+    pub fn multisend(
+        chain_id: u64,
+        nonce: u64,
+        transfers: &[(Address, U256)],
+        gas_limit: u64,
+        max_fee_per_gas: U256,
+        max_priority_fee_per_gas: U256,
+    ) -> Result<Self, TypesError> {
+        if transfers.is_empty() || transfers.len() > MAX_MULTISEND_RECIPIENTS {
+            return Err(TypesError::InvalidMultisend(format!(
+                "expected 1 to {} recipients, got {}",
+                MAX_MULTISEND_RECIPIENTS,
+                transfers.len()
+            )));
+        }
+        let mut value = U256::ZERO;
+        let mut data = Vec::with_capacity(4 + transfers.len() * MULTISEND_ENTRY_LEN);
+        data.extend_from_slice(&(transfers.len() as u32).to_le_bytes());
+        for (recipient, amount) in transfers {
+            value = value.checked_add(amount).ok_or(TypesError::U256Overflow)?;
+            data.extend_from_slice(recipient.as_bytes());
+            data.extend_from_slice(&amount.to_le_bytes());
         }
+
+        let mut tx = Self::new(chain_id, nonce, None, value, gas_limit, max_fee_per_gas, max_priority_fee_per_gas);
+        tx.tx_type = TransactionType::Multisend;
+        tx.data = data;
+        Ok(tx)
+    }
+
+    /// Decode the transfers of a multisend and check they add up to `value`
+    pub fn multisend_transfers(&self) -> Result<Vec<(Address, U256)>, TypesError> {
+        let invalid = |message: String| TypesError::InvalidMultisend(message);
+        if self.tx_type != TransactionType::Multisend {
+            return Err(invalid("not a multisend transaction".to_string()));
+        }
+
+        if self.data.len() < 4 {
+            return Err(invalid("missing recipient count".to_string()));
+        }
+        let (count, entries) = self.data.split_at(4);
+        let count = u32::from_le_bytes(count.try_into().expect("length checked")) as usize;
+        if count == 0 || count > MAX_MULTISEND_RECIPIENTS {
+            return Err(invalid(format!(
+                "expected 1 to {} recipients, got {}",
+                MAX_MULTISEND_RECIPIENTS, count
+            )));
+        }
+        if entries.len() != count * MULTISEND_ENTRY_LEN {
+            return Err(invalid(format!("data does not hold {} entries", count)));
+        }
+
+        let mut total = U256::ZERO;
+        let transfers: Vec<(Address, U256)> = entries
+            .chunks_exact(MULTISEND_ENTRY_LEN)
+            .map(|entry| {
+                let (recipient, amount) = entry.split_at(Address::LEN);
+                let recipient = Address::from_bytes(recipient.try_into().expect("entry length checked"));
+                let amount = U256::from_le_bytes(amount.try_into().expect("entry length checked"));
+                (recipient, amount)
+            })
+            .collect();
+        for (_, amount) in &transfers {
+            total = total.checked_add(amount).ok_or(TypesError::U256Overflow)?;
+        }
+        if total != self.value {
+            return Err(invalid(format!("amounts sum to {} but value is {}", total, self.value)));
+        }
+        Ok(transfers)
+    }
+
+    /// Create a transaction cancelling the sender's delayed transaction `target`
+    pub fn cancel_delayed(chain_id: u64, target: Hash) -> Self {
+        let mut tx = Self::new(chain_id, 0, None, U256::ZERO, 0, U256::ZERO, U256::ZERO);
+        tx.tx_type = TransactionType::CancelDelayed;
+        tx.data = target.as_bytes().to_vec();
+        tx
     }
 
     /// Check if this is a contract creation transaction
     pub fn is_create(&self) -> bool {
         self.to.is_none()
+            && !matches!(self.tx_type, TransactionType::Multisend | TransactionType::CancelDelayed)
     }
 
     /// Compute the hash that should be signed
@@ -87,6 +204,21 @@ impl Transaction {
         // Simple serialization for signing
         // In production, use a proper canonical serialization
         let mut data = Vec::new();
+        // Newer types commit to their type up front so a signature cannot be
+        // replayed as another type; the original types keep their encoding
+        let type_tag = match self.tx_type {
+            TransactionType::Legacy | TransactionType::Eip1559 | TransactionType::Batch => None,
+            TransactionType::Sponsored => Some(3),
+            TransactionType::Multisend => Some(4),
+            TransactionType::CancelDelayed => Some(5),
+        };
+        match type_tag {
+            Some(tag) => {
+                data.extend_from_slice(TYPED_TX_SIGNING_DOMAIN);
+                data.push(tag);
+            }
+            None => data.extend_from_slice(TX_SIGNING_DOMAIN),
+        }
         data.extend_from_slice(&self.chain_id.to_le_bytes());
         data.extend_from_slice(&self.nonce.to_le_bytes());
         if let Some(to) = self.to {
@@ -98,6 +230,13 @@ impl Transaction {
         data.extend_from_slice(&self.gas_limit.to_le_bytes());
         data.extend_from_slice(&self.max_fee_per_gas.to_le_bytes());
         data.extend_from_slice(&self.max_priority_fee_per_gas.to_le_bytes());
+        // One flag byte says which block bounds follow, so the layout never
+        // depends on the contents of `data`
+        let flags = u8::from(self.valid_until_block.is_some()) | (u8::from(self.execute_after_block.is_some()) << 1);
+        data.push(flags);
+        for block in [self.valid_until_block, self.execute_after_block].into_iter().flatten() {
+            data.extend_from_slice(&block.to_le_bytes());
+        }
         data.extend_from_slice(&self.data);
         Hash::compute(&data)
     }
@@ -113,6 +252,28 @@ impl Transaction {
         self.access_list = access_list;
         self
     }
+
+    /// Expire the transaction if it is not included by `block`
+    pub fn with_valid_until_block(mut self, block: u64) -> Self {
+        self.valid_until_block = Some(block);
+        self
+    }
+
+    /// Check if the transaction can no longer be included in `block_number`
+    pub fn is_expired(&self, block_number: u64) -> bool {
+        self.valid_until_block.is_some_and(|last| block_number > last)
+    }
+
+    /// Hold the transaction and execute it in `block`
+    pub fn with_execute_after_block(mut self, block: u64) -> Self {
+        self.execute_after_block = Some(block);
+        self
+    }
+
+    /// Check if the transaction is still delayed at `block_number`
+    pub fn is_premature(&self, block_number: u64) -> bool {
+        self.execute_after_block.is_some_and(|target| block_number < target)
+    }
 }
 
 /// Transaction with signature attached.
@@ -193,6 +354,65 @@ impl SignedTransaction {
     }
 }
 
+/// A user's signed intent submitted and paid for by a sponsor.
+///
+/// The user signs the inner transaction as usual, with type
+/// [`TransactionType::Sponsored`]. The sponsor signs
+/// [`sponsor_signing_hash`](Self::sponsor_signing_hash), which commits to the
+/// whole signed intent including its gas limit and fee caps. Value moves from
+/// the user; gas is charged to the sponsor.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "borsh", derive(borsh::BorshSerialize, borsh::BorshDeserialize))]
+pub struct SponsoredTransaction {
+    /// The user's signed intent
+    pub inner: SignedTransaction,
+    /// Sponsor signature over `sponsor_signing_hash(&inner)`
+    pub sponsor_signature: Ed25519Signature,
+    pub sponsor_public_key: Ed25519PublicKey,
+}
+
+impl SponsoredTransaction {
+    /// Wrap a signed intent with the sponsor's signature
+    pub fn new(
+        inner: SignedTransaction,
+        sponsor_signature: Ed25519Signature,
+        sponsor_public_key: Ed25519PublicKey,
+    ) -> Self {
+        Self {
+            inner,
+            sponsor_signature,
+            sponsor_public_key,
+        }
+    }
+
+    /// Message the sponsor signs to pay for `inner`
+    pub fn sponsor_signing_hash(inner: &SignedTransaction) -> Hash {
+        let mut data = Vec::new();
+        data.extend_from_slice(SPONSOR_SIGNING_DOMAIN);
+        data.extend_from_slice(inner.hash().as_bytes());
+        Hash::compute(&data)
+    }
+
+    /// Compute the transaction hash
+    pub fn hash(&self) -> Hash {
+        let mut data = Vec::new();
+        data.extend_from_slice(self.inner.hash().as_bytes());
+        data.extend_from_slice(self.sponsor_signature.as_bytes());
+        data.extend_from_slice(self.sponsor_public_key.as_bytes());
+        Hash::compute(&data)
+    }
+
+    /// Address whose value is moved
+    pub fn user(&self) -> Address {
+        self.inner.sender()
+    }
+
+    /// Address that pays the gas
+    pub fn sponsor(&self) -> Address {
+        self.sponsor_public_key.to_address()
+    }
+}
+
 impl fmt::Display for Transaction {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
@@ -314,4 +534,82 @@ mod tests {
 
         assert_eq!(entry.storage_keys.len(), 2);
     }
+
+    #[test]
+    fn test_valid_until_block() {
+        let tx = Transaction::new(
+            1, 0, Some(Address::ZERO), U256::ZERO, 21000, U256::ONE, U256::ONE,
+        );
+        assert!(!tx.is_expired(u64::MAX));
+
+        let bounded = tx.clone().with_valid_until_block(10);
+        assert!(!bounded.is_expired(10));
+        assert!(bounded.is_expired(11));
+
+        // The deadline is part of what gets signed
+        assert_ne!(tx.signing_hash(), bounded.signing_hash());
+        assert_ne!(bounded.signing_hash(), tx.with_valid_until_block(11).signing_hash());
+    }
+
+    #[test]
+    fn test_multisend_encoding() {
+        let transfers = vec![
+            (Address::from_bytes([1u8; 20]), U256::from(100u64)),
+            (Address::from_bytes([2u8; 20]), U256::from(250u64)),
+        ];
+        let tx = Transaction::multisend(1, 0, &transfers, 100_000, U256::ONE, U256::ONE).unwrap();
+        assert_eq!(tx.tx_type, TransactionType::Multisend);
+        assert_eq!(tx.value, U256::from(350u64));
+        assert!(!tx.is_create());
+        assert_eq!(tx.multisend_transfers().unwrap(), transfers);
+
+        // Amounts must add up to the debited value
+        let mut inflated = tx.clone();
+        inflated.value = U256::from(1u64);
+        assert!(inflated.multisend_transfers().is_err());
+
+        let mut truncated = tx;
+        truncated.data.pop();
+        assert!(truncated.multisend_transfers().is_err());
+        assert!(Transaction::multisend(1, 0, &[], 100_000, U256::ONE, U256::ONE).is_err());
+    }
+
+    #[test]
+    fn test_execute_after_block() {
+        let tx = Transaction::new(
+            1, 0, Some(Address::ZERO), U256::ZERO, 21000, U256::ONE, U256::ONE,
+        );
+        assert!(!tx.is_premature(0));
+
+        let delayed = tx.clone().with_execute_after_block(10);
+        assert!(delayed.is_premature(9));
+        assert!(!delayed.is_premature(10));
+        assert_ne!(tx.signing_hash(), delayed.signing_hash());
+
+        // Data shaped like the target block does not collide with it
+        let mut marker = vec![2];
+        marker.extend_from_slice(&10u64.to_le_bytes());
+        assert_ne!(tx.clone().with_data(marker).signing_hash(), delayed.signing_hash());
+
+        let cancel = Transaction::cancel_delayed(1, Hash::compute(b"target"));
+        assert!(!cancel.is_create());
+        assert_eq!(cancel.data, Hash::compute(b"target").as_bytes().to_vec());
+    }
+
+    #[test]
+    fn test_signing_hash_commits_to_type() {
+        let legacy = Transaction::new(
+            1, 0, Some(Address::ZERO), U256::ZERO, 21000, U256::ONE, U256::ONE,
+        );
+        let sponsored = Transaction { tx_type: TransactionType::Sponsored, ..legacy.clone() };
+        assert_ne!(legacy.signing_hash(), sponsored.signing_hash());
+
+        // A legacy payload ending in a type byte does not collide with the typed one
+        let padded = legacy.clone().with_data(vec![3]);
+        assert_ne!(padded.signing_hash(), sponsored.signing_hash());
+
+        // The original types keep their encoding
+        let eip1559 = Transaction { tx_type: TransactionType::Eip1559, ..legacy.clone() };
+        assert_eq!(legacy.signing_hash(), eip1559.signing_hash());
+    }
 }
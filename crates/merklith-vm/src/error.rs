@@ -21,6 +21,9 @@ pub enum VmError {
     #[error("Code size exceeded: {size} > {limit}")]
     CodeSizeExceeded { size: usize, limit: usize },
 
+    #[error("Return data too large: {size} > {limit}")]
+    ReturnDataTooLarge { size: usize, limit: usize },
+
     #[error("Reentrancy violation: {0}")]
     ReentrancyViolation(String),
 
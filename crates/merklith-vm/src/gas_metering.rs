@@ -1,3 +1,5 @@
+use merklith_types::chain_config::{upgrades, ChainConfig};
+
 /// Gas schedule for VM operations.
 #[derive(Debug, Clone, Copy)]
 pub struct GasSchedule {
@@ -33,6 +35,10 @@ pub struct GasSchedule {
 
     // Memory
     pub memory_per_page: u64,        // 3  (per 64KB page)
+    pub return_data_per_word: u64,   // 3  (per 32 bytes returned)
+
+    // Execution
+    pub gas_per_fuel: u64,           // 1  (per unit of wasmtime fuel)
 
     // TX
     pub tx_base: u64,                // 21,000
@@ -78,6 +84,10 @@ impl Default for GasSchedule {
 
             // Memory
             memory_per_page: 3,
+            return_data_per_word: 3,
+
+            // Execution
+            gas_per_fuel: 1,
 
             // TX
             tx_base: 21_000,
@@ -90,6 +100,17 @@ impl Default for GasSchedule {
     }
 }
 
+impl GasSchedule {
+    /// Schedule in effect at `block`, with activated upgrades applied.
+    pub fn for_block(config: &ChainConfig, block: u64) -> Self {
+        let mut schedule = Self::default();
+        if config.is_active(upgrades::STORAGE_REPRICE, block) {
+            schedule.storage_read_cold = 800;
+        }
+        schedule
+    }
+}
+
 /// Gas tracking during execution.
 #[derive(Debug, Clone)]
 pub struct GasTracker {
@@ -190,6 +211,12 @@ impl GasTracker {
         self.charge(cost)
     }
 
+    /// Charge gas for copying `len` bytes of return data out of the call.
+    pub fn charge_return_data(&mut self, len: usize) -> Result<(), crate::error::VmError> {
+        let cost = (len as u64).div_ceil(32) * self.schedule.return_data_per_word;
+        self.charge(cost)
+    }
+
     /// Refund gas (for storage deletion).
     pub fn refund(&mut self, amount: u64) {
         self.refunded += amount;
@@ -231,6 +258,19 @@ mod tests {
         assert_eq!(tracker.effective_gas(), 7_500);
     }
 
+    #[test]
+    fn test_storage_reprice_activation() {
+        let config = ChainConfig::mainnet().with_upgrade(upgrades::STORAGE_REPRICE, 50);
+
+        let mut before = GasTracker::new(100_000, GasSchedule::for_block(&config, 49));
+        before.charge_storage_read(true).unwrap();
+        assert_eq!(before.used(), 200);
+
+        let mut after = GasTracker::new(100_000, GasSchedule::for_block(&config, 50));
+        after.charge_storage_read(true).unwrap();
+        assert_eq!(after.used(), 800);
+    }
+
     #[test]
     fn test_storage_gas() {
         let mut tracker = GasTracker::with_default_schedule(100_000);
@@ -6,6 +6,7 @@
 //! - Host function API
 //! - Reentrancy protection
 //! - Precompiled contracts
+//! - Source verification against deployed code
 
 pub mod error;
 pub mod gas_metering;
@@ -13,13 +14,19 @@ pub mod runtime;
 pub mod reentrancy;
 pub mod wasm_runtime;
 pub mod merkle_trie;
+pub mod module_cache;
+pub mod tracer;
+pub mod verify;
 
 pub use error::VmError;
 pub use gas_metering::{GasSchedule, GasTracker};
 pub use runtime::{MerklithVM, ExecutionContext, ExecutionResult};
 pub use reentrancy::ReentrancyGuard;
-pub use wasm_runtime::{WasmRuntime, WasmRuntimeConfig, HostState, LogEntry};
+pub use wasm_runtime::{validate_wasm, FloatPolicy, WasmRuntime, WasmRuntimeConfig, HostState, LogEntry};
 pub use merkle_trie::{MerkleTrie, StateManager, TrieNode};
+pub use module_cache::{ModuleCache, ModuleCacheStats};
+pub use tracer::{StorageAccess, TraceStep};
+pub use verify::{verify_source, SourceVerification, VerifyError};
 
 /// VM version constant
 pub const VM_VERSION: u32 = 1;
@@ -36,6 +43,9 @@ pub const MAX_CODE_SIZE: usize = 128 * 1024;
 /// Maximum stack size (1024 items)
 pub const MAX_STACK_SIZE: usize = 1024;
 
+/// Maximum data a contract call may return (1 MB)
+pub const MAX_RETURN_DATA_BYTES: usize = 1024 * 1024;
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,167 @@
+//! Compiled WASM module cache.
+//!
+//! Compiling a module dominates the cost of a contract call, so modules are
+//! kept by code hash and reused. The cache is bounded; the least recently
+//! used module is evicted first.
+
+use std::collections::HashMap;
+
+use parking_lot::Mutex;
+use wasmtime::Module;
+
+use crate::error::VmError;
+
+/// Default number of modules kept.
+pub const DEFAULT_MODULE_CACHE_SIZE: usize = 256;
+
+/// Module cache counters.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ModuleCacheStats {
+    /// Lookups served without compiling
+    pub hits: u64,
+    /// Lookups that compiled the module
+    pub misses: u64,
+    /// Modules dropped to stay within capacity
+    pub evictions: u64,
+    /// Modules currently cached
+    pub entries: usize,
+}
+
+#[derive(Default)]
+struct CacheInner {
+    /// Module and the tick it was last used at
+    modules: HashMap<[u8; 32], (Module, u64)>,
+    tick: u64,
+    stats: ModuleCacheStats,
+}
+
+/// LRU cache of compiled modules keyed by code hash.
+pub struct ModuleCache {
+    capacity: usize,
+    inner: Mutex<CacheInner>,
+}
+
+impl ModuleCache {
+    /// Create a cache holding at most `capacity` modules.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            inner: Mutex::new(CacheInner::default()),
+        }
+    }
+
+    /// Return the module for `code_hash`, compiling it on a miss.
+    ///
+    /// The lock is not held while compiling, so two callers missing on the
+    /// same code may both compile it; the second insert wins.
+    pub fn get_or_compile(
+        &self,
+        code_hash: [u8; 32],
+        compile: impl FnOnce() -> Result<Module, VmError>,
+    ) -> Result<Module, VmError> {
+        {
+            let mut inner = self.inner.lock();
+            inner.tick += 1;
+            let tick = inner.tick;
+            if let Some((module, last_used)) = inner.modules.get_mut(&code_hash) {
+                *last_used = tick;
+                let module = module.clone();
+                inner.stats.hits += 1;
+                return Ok(module);
+            }
+            inner.stats.misses += 1;
+        }
+
+        let module = compile()?;
+
+        let mut inner = self.inner.lock();
+        if !inner.modules.contains_key(&code_hash) && inner.modules.len() >= self.capacity {
+            let oldest = inner
+                .modules
+                .iter()
+                .min_by_key(|(_, (_, last_used))| *last_used)
+                .map(|(hash, _)| *hash);
+            if let Some(oldest) = oldest {
+                inner.modules.remove(&oldest);
+                inner.stats.evictions += 1;
+            }
+        }
+        inner.tick += 1;
+        let tick = inner.tick;
+        inner.modules.insert(code_hash, (module.clone(), tick));
+        Ok(module)
+    }
+
+    /// Drop the module for `code_hash`, e.g. after a self-destruct or redeploy.
+    pub fn invalidate(&self, code_hash: &[u8; 32]) -> bool {
+        self.inner.lock().modules.remove(code_hash).is_some()
+    }
+
+    /// Drop every cached module.
+    pub fn clear(&self) {
+        self.inner.lock().modules.clear();
+    }
+
+    /// Hit, miss and eviction counts.
+    pub fn stats(&self) -> ModuleCacheStats {
+        let inner = self.inner.lock();
+        ModuleCacheStats {
+            entries: inner.modules.len(),
+            ..inner.stats
+        }
+    }
+}
+
+impl Default for ModuleCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_MODULE_CACHE_SIZE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasmtime::Engine;
+
+    fn module(engine: &Engine, name: &str) -> Module {
+        let wasm = wat::parse_str(format!(r#"(module (func (export "{}")))"#, name)).unwrap();
+        Module::new(engine, wasm).unwrap()
+    }
+
+    #[test]
+    fn test_lru_eviction() {
+        let engine = Engine::default();
+        let cache = ModuleCache::new(2);
+        let compile = |name: &'static str| {
+            let engine = engine.clone();
+            move || Ok(module(&engine, name))
+        };
+
+        cache.get_or_compile([1u8; 32], compile("a")).unwrap();
+        cache.get_or_compile([2u8; 32], compile("b")).unwrap();
+        // Touch the first so the second becomes least recently used
+        cache.get_or_compile([1u8; 32], compile("a")).unwrap();
+        cache.get_or_compile([3u8; 32], compile("c")).unwrap();
+
+        let stats = cache.stats();
+        assert_eq!(stats.entries, 2);
+        assert_eq!(stats.evictions, 1);
+        assert_eq!((stats.hits, stats.misses), (1, 3));
+
+        // The evicted module is compiled again
+        cache.get_or_compile([2u8; 32], compile("b")).unwrap();
+        assert_eq!(cache.stats().misses, 4);
+
+        assert!(cache.invalidate(&[2u8; 32]));
+        assert!(!cache.invalidate(&[2u8; 32]));
+        assert_eq!(cache.stats().entries, 1);
+    }
+
+    #[test]
+    fn test_compile_error_not_cached() {
+        let cache = ModuleCache::default();
+        let result = cache.get_or_compile([1u8; 32], || Err(VmError::CompilationError("bad".to_string())));
+        assert!(result.is_err());
+        assert_eq!(cache.stats().entries, 0);
+    }
+}
@@ -5,11 +5,14 @@
 use wasmtime::{Config, Engine};
 use bytes::Bytes;
 
-use merklith_types::{Address, U256};
+use merklith_types::{AccessListEntry, Address, ChainConfig, Hash, U256};
 use crate::error::VmError;
 use crate::gas_metering::{GasSchedule, GasTracker};
 #[allow(unused_imports)]
 use crate::reentrancy::ReentrancyGuard;
+use crate::module_cache::ModuleCacheStats;
+use crate::tracer::{opcode_name, settle_gas_costs, StorageAccess, TraceStep};
+use crate::wasm_runtime::{WasmRuntime, WasmRuntimeConfig};
 use crate::{MAX_CODE_SIZE, MAX_STACK_SIZE};
 
 /// Execution context for a contract call.
@@ -43,6 +46,11 @@ pub struct ExecutionContext {
     pub code: Bytes,
     /// Code hash
     pub code_hash: [u8; 32],
+    /// Storage of the called contract when execution starts, read by SLOAD
+    pub storage: std::collections::HashMap<[u8; 32], [u8; 32]>,
+    /// Record a step trace in the result. A traced call that fails returns
+    /// an unsuccessful result carrying the trace instead of an error.
+    pub trace: bool,
 }
 
 impl ExecutionContext {
@@ -69,6 +77,8 @@ impl ExecutionContext {
             input,
             code: Bytes::new(),
             code_hash: [0u8; 32],
+            storage: std::collections::HashMap::new(),
+            trace: false,
         }
     }
 
@@ -105,6 +115,8 @@ impl ExecutionContext {
             input: Bytes::new(),
             code,
             code_hash,
+            storage: std::collections::HashMap::new(),
+            trace: false,
         })
     }
 
@@ -127,6 +139,12 @@ impl ExecutionContext {
         self
     }
 
+    /// Set the contract storage visible to SLOAD.
+    pub fn with_storage(mut self, storage: std::collections::HashMap<[u8; 32], [u8; 32]>) -> Self {
+        self.storage = storage;
+        self
+    }
+
     /// Set value.
     pub fn with_value(mut self, value: U256) -> Self {
         self.value = value;
@@ -144,6 +162,12 @@ impl ExecutionContext {
         self.chain_id = chain_id;
         self
     }
+
+    /// Record a step trace of the execution.
+    pub fn with_tracing(mut self) -> Self {
+        self.trace = true;
+        self
+    }
 }
 
 /// Result of contract execution.
@@ -161,8 +185,12 @@ pub struct ExecutionResult {
     pub logs: Vec<LogEntry>,
     /// New contracts created
     pub created_contracts: Vec<(Address, Bytes)>,
+    /// Accounts and storage slots read, in first-access order
+    pub access_list: Vec<AccessListEntry>,
     /// State changes
     pub state_changes: StateChanges,
+    /// Executed steps, when the context asked for a trace
+    pub trace: Vec<TraceStep>,
 }
 
 /// A single log entry (event).
@@ -195,7 +223,9 @@ impl ExecutionResult {
             gas_refunded: 0,
             logs: Vec::new(),
             created_contracts: Vec::new(),
+            access_list: Vec::new(),
             state_changes: StateChanges::default(),
+            trace: Vec::new(),
         }
     }
 
@@ -208,7 +238,9 @@ impl ExecutionResult {
             gas_refunded: 0,
             logs: Vec::new(),
             created_contracts: Vec::new(),
+            access_list: Vec::new(),
             state_changes: StateChanges::default(),
+            trace: Vec::new(),
         }
     }
 
@@ -225,11 +257,36 @@ impl ExecutionResult {
     }
 }
 
+/// What the interpreter observed besides its return value.
+#[derive(Default)]
+struct ExecutionTrace {
+    logs: Vec<LogEntry>,
+    /// Storage keys read, without duplicates
+    accessed_slots: Vec<[u8; 32]>,
+    /// Slots written by SSTORE and their latest values
+    written_slots: std::collections::HashMap<[u8; 32], [u8; 32]>,
+    /// Executed steps, only recorded when tracing
+    steps: Vec<TraceStep>,
+}
+
+/// Maximum gas limit (30M)
+const MAX_GAS_LIMIT: u64 = 30_000_000;
+
+/// Minimum gas for a transfer
+const MIN_GAS_LIMIT: u64 = 21_000;
+
+/// Leading bytes of every WASM module.
+const WASM_MAGIC: [u8; 4] = [0x00, 0x61, 0x73, 0x6d];
+
 /// The main Merklith VM.
 pub struct MerklithVM {
-    #[allow(dead_code)]
-    engine: Engine,
+    /// Executes WASM contracts and caches their compiled modules
+    wasm: WasmRuntime,
     gas_schedule: GasSchedule,
+    /// When set, the gas schedule follows the chain's upgrade heights
+    chain_config: Option<ChainConfig>,
+    /// Largest return data a call may produce
+    max_return_data: usize,
 }
 
 impl MerklithVM {
@@ -240,23 +297,66 @@ impl MerklithVM {
             .wasm_bulk_memory(true)
             .wasm_multi_value(true)
             .wasm_reference_types(true)
+            .consume_fuel(true)
+            .cranelift_nan_canonicalization(true)
             .cranelift_opt_level(wasmtime::OptLevel::Speed);
 
         let engine = Engine::new(&config)
             .map_err(|e| VmError::ExecutionError(format!("Failed to create engine: {}", e)))?;
 
         Ok(Self {
-            engine,
+            wasm: WasmRuntime::with_engine(engine, Self::wasm_config()),
             gas_schedule: GasSchedule::default(),
+            chain_config: None,
+            max_return_data: crate::MAX_RETURN_DATA_BYTES,
         })
     }
 
+    fn wasm_config() -> WasmRuntimeConfig {
+        WasmRuntimeConfig {
+            gas_limit: MAX_GAS_LIMIT,
+            ..WasmRuntimeConfig::default()
+        }
+    }
+
     /// Create with custom gas schedule.
     pub fn with_gas_schedule(mut self, schedule: GasSchedule) -> Self {
         self.gas_schedule = schedule;
         self
     }
 
+    /// Derive the gas schedule from the chain config at each call's block.
+    pub fn with_chain_config(mut self, config: ChainConfig) -> Self {
+        self.chain_config = Some(config);
+        self
+    }
+
+    /// Limit the data a call may return. Larger return data fails the call
+    /// with [`VmError::ReturnDataTooLarge`].
+    pub fn with_max_return_data(mut self, bytes: usize) -> Self {
+        self.wasm = self.wasm.with_max_return_data(bytes);
+        self.max_return_data = bytes;
+        self
+    }
+
+    /// Gas schedule in effect at `block`.
+    pub fn gas_schedule_at(&self, block: u64) -> GasSchedule {
+        match &self.chain_config {
+            Some(config) => GasSchedule::for_block(config, block),
+            None => self.gas_schedule,
+        }
+    }
+
+    /// Compiled module cache hit and miss counts.
+    pub fn module_cache_stats(&self) -> ModuleCacheStats {
+        self.wasm.module_cache_stats()
+    }
+
+    /// Drop the compiled module for `code_hash`, e.g. after a self-destruct.
+    pub fn invalidate_code(&self, code_hash: &[u8; 32]) -> bool {
+        self.wasm.invalidate_code(code_hash)
+    }
+
     /// Execute a contract call.
     pub fn execute(
         &self,
@@ -270,9 +370,6 @@ impl MerklithVM {
         }
         
         // Validate gas limit
-        const MAX_GAS_LIMIT: u64 = 30_000_000; // Maximum gas limit (30M)
-        const MIN_GAS_LIMIT: u64 = 21_000; // Minimum gas for a transfer
-        
         if ctx.gas_limit > MAX_GAS_LIMIT {
             return Err(VmError::ExecutionError(
                 format!("Gas limit {} exceeds maximum {}", ctx.gas_limit, MAX_GAS_LIMIT)
@@ -286,7 +383,27 @@ impl MerklithVM {
         }
 
         // Create gas tracker
-        let mut gas_tracker = GasTracker::new(ctx.gas_limit, self.gas_schedule);
+        let mut gas_tracker = GasTracker::new(ctx.gas_limit, self.gas_schedule_at(ctx.block_number));
+
+        // WASM contracts run on the WASM runtime, which charges its own base cost
+        if ctx.code.starts_with(&WASM_MAGIC) {
+            let outcome = self.wasm.execute(&ctx.code, &ctx, &mut gas_tracker);
+            if !ctx.trace {
+                return outcome;
+            }
+            let step = TraceStep {
+                pc: 0,
+                op: "WASM",
+                gas: ctx.gas_limit,
+                gas_cost: gas_tracker.used(),
+                stack_depth: 0,
+                storage: None,
+            };
+            let mut result = outcome
+                .unwrap_or_else(|e| ExecutionResult::failure(e, gas_tracker.used()));
+            result.trace = vec![step];
+            return Ok(result);
+        }
         
         // Deduct base gas cost
         gas_tracker.charge(21000)?;
@@ -296,6 +413,7 @@ impl MerklithVM {
         
         // Check if this is a simple transfer (no code)
         if ctx.code.len() < 4 {
+            self.charge_return_data(&mut gas_tracker, ctx.input.len())?;
             return Ok(ExecutionResult::success(
                 ctx.input,
                 gas_tracker.used(),
@@ -303,12 +421,47 @@ impl MerklithVM {
         }
 
         // Simple bytecode interpreter
-        let result = self.interpret_bytecode(&ctx.code, &ctx.input, &mut gas_tracker)?;
+        let mut trace = ExecutionTrace::default();
+        // Copying out the return data is billed to the final step
+        let outcome = self
+            .interpret_bytecode(&ctx, &mut gas_tracker, &mut trace)
+            .and_then(|data| {
+                self.charge_return_data(&mut gas_tracker, data.len())?;
+                Ok(data)
+            });
+        settle_gas_costs(&mut trace.steps, gas_tracker.remaining());
+        let result = match outcome {
+            Ok(result) => result,
+            Err(e) if ctx.trace => {
+                let mut result = ExecutionResult::failure(e, gas_tracker.used());
+                result.trace = trace.steps;
+                return Ok(result);
+            }
+            Err(e) => return Err(e),
+        };
+
+        let mut result = ExecutionResult::success(result, gas_tracker.used());
+        result.logs = trace.logs;
+        result.trace = trace.steps;
+        // Zero clears a slot
+        result.state_changes.storage = trace
+            .written_slots
+            .into_iter()
+            .map(|(key, value)| ((ctx.contract_address, key), Some(value).filter(|v| *v != [0u8; 32])))
+            .collect();
+        result.access_list = vec![AccessListEntry {
+            address: ctx.contract_address,
+            storage_keys: trace.accessed_slots.into_iter().map(Hash::from_bytes).collect(),
+        }];
+        Ok(result)
+    }
 
-        Ok(ExecutionResult::success(
-            result,
-            gas_tracker.used(),
-        ))
+    /// Fail oversized return data, otherwise charge for copying it out
+    fn charge_return_data(&self, gas: &mut GasTracker, len: usize) -> Result<(), VmError> {
+        if len > self.max_return_data {
+            return Err(VmError::ReturnDataTooLarge { size: len, limit: self.max_return_data });
+        }
+        gas.charge_return_data(len)
     }
 
     /// Helper function to safely push to stack with size limit check
@@ -321,19 +474,39 @@ impl MerklithVM {
         Ok(())
     }
 
+    /// Left-pad a stack value to a 32-byte word, keeping the low-order bytes
+    fn to_word(value: &[u8]) -> [u8; 32] {
+        let mut word = [0u8; 32];
+        let len = value.len().min(32);
+        word[32 - len..].copy_from_slice(&value[value.len() - len..]);
+        word
+    }
+
     /// Simple bytecode interpreter
     fn interpret_bytecode(
         &self,
-        code: &[u8],
-        input: &[u8],
+        ctx: &ExecutionContext,
         gas: &mut GasTracker,
+        trace: &mut ExecutionTrace,
     ) -> Result<Bytes, VmError> {
+        let code: &[u8] = &ctx.code;
+        let input: &[u8] = &ctx.input;
         let mut pc = 0;
         let mut stack: Vec<Vec<u8>> = Vec::new();
         let mut memory: Vec<u8> = vec![0; 1024];
         
         while pc < code.len() {
             let opcode = code[pc];
+            if ctx.trace {
+                trace.steps.push(TraceStep {
+                    pc,
+                    op: opcode_name(opcode),
+                    gas: gas.remaining(),
+                    gas_cost: 0,
+                    stack_depth: stack.len(),
+                    storage: None,
+                });
+            }
             pc += 1;
             
             match opcode {
@@ -438,6 +611,45 @@ impl MerklithVM {
                         }
                     }
                 }
+                0x54 => {
+                    // SLOAD: cold slots cost more than ones already read
+                    let key = Self::to_word(&stack.pop().ok_or(VmError::ExecutionError("Stack underflow".to_string()))?);
+                    if trace.accessed_slots.contains(&key) {
+                        gas.charge(100)?;
+                    } else {
+                        gas.charge(2100)?;
+                        trace.accessed_slots.push(key);
+                    }
+                    let value = trace
+                        .written_slots
+                        .get(&key)
+                        .or_else(|| ctx.storage.get(&key))
+                        .copied()
+                        .unwrap_or([0u8; 32]);
+                    if let Some(step) = trace.steps.last_mut() {
+                        step.storage = Some(StorageAccess::Read { key, value });
+                    }
+                    Self::safe_push(&mut stack, value.to_vec())?;
+                }
+                0x55 => {
+                    // SSTORE: key on top of the stack, then the value
+                    if ctx.is_static {
+                        return Err(VmError::ExecutionError("SSTORE in static call".to_string()));
+                    }
+                    let key = Self::to_word(&stack.pop().ok_or(VmError::ExecutionError("Stack underflow".to_string()))?);
+                    let value = Self::to_word(&stack.pop().ok_or(VmError::ExecutionError("Stack underflow".to_string()))?);
+                    let current = trace
+                        .written_slots
+                        .get(&key)
+                        .or_else(|| ctx.storage.get(&key))
+                        .copied()
+                        .unwrap_or([0u8; 32]);
+                    gas.charge_storage_write(current == [0u8; 32])?;
+                    trace.written_slots.insert(key, value);
+                    if let Some(step) = trace.steps.last_mut() {
+                        step.storage = Some(StorageAccess::Write { key, value });
+                    }
+                }
                 0x60..=0x7F => {
                     // PUSH1-PUSH32
                     let n = (opcode - 0x5F) as usize;
@@ -447,6 +659,25 @@ impl MerklithVM {
                         pc += n;
                     }
                 }
+                0xA0..=0xA4 => {
+                    // LOG0-LOG4: data on top of the stack, then the topics
+                    let topic_count = (opcode - 0xA0) as usize;
+                    if ctx.is_static {
+                        return Err(VmError::ExecutionError("LOG in static call".to_string()));
+                    }
+                    let data = stack.pop().ok_or(VmError::ExecutionError("Stack underflow".to_string()))?;
+                    gas.charge(375 + 375 * topic_count as u64 + 8 * data.len() as u64)?;
+                    let mut topics = Vec::with_capacity(topic_count);
+                    for _ in 0..topic_count {
+                        let value = stack.pop().ok_or(VmError::ExecutionError("Stack underflow".to_string()))?;
+                        topics.push(Self::to_word(&value));
+                    }
+                    trace.logs.push(LogEntry {
+                        address: ctx.contract_address,
+                        topics,
+                        data: Bytes::from(data),
+                    });
+                }
                 0xF0 => {
                     // CREATE - deploy new contract
                     gas.charge(32000)?;
@@ -462,8 +693,8 @@ impl MerklithVM {
                     Self::safe_push(&mut stack, vec![1])?;
                 }
                 0xFD => {
-                    // REVERT
-                    return Err(VmError::ExecutionError("Revert".to_string()));
+                    // REVERT with the top of the stack as revert data
+                    return Err(VmError::Reverted { reason: stack.pop() });
                 }
                 0xFF => {
                     // SELFDESTRUCT
@@ -487,11 +718,15 @@ impl Default for MerklithVM {
         // Attempt to create VM, fall back to a basic instance on failure
         Self::new().unwrap_or_else(|e| {
             tracing::warn!("Failed to create default VM: {}, using fallback", e);
-            // Create a minimal fallback VM with default engine config
-            let engine = Engine::default();
+            // Create a minimal fallback VM that still meters fuel
+            let mut config = Config::new();
+            config.consume_fuel(true);
+            let engine = Engine::new(&config).unwrap_or_default();
             Self {
-                engine,
+                wasm: WasmRuntime::with_engine(engine, Self::wasm_config()),
                 gas_schedule: GasSchedule::default(),
+                chain_config: None,
+                max_return_data: crate::MAX_RETURN_DATA_BYTES,
             }
         })
     }
@@ -580,6 +815,112 @@ mod tests {
         assert_eq!(result.logs[0], log);
     }
 
+    #[test]
+    fn test_log_opcode() {
+        let vm = MerklithVM::new().unwrap();
+        let contract = Address::from_bytes([7u8; 20]);
+        // PUSH1 0x01 (topic); PUSH2 0xbeef (data); LOG1; STOP
+        let code = Bytes::from(vec![0x60, 0x01, 0x61, 0xbe, 0xef, 0xa1, 0x00]);
+        let ctx = ExecutionContext {
+            code,
+            ..ExecutionContext::new_call(contract, Address::ZERO, Address::ZERO, 100_000, Bytes::new())
+        };
+
+        let result = vm.execute(ctx.clone()).unwrap();
+        assert_eq!(result.logs.len(), 1);
+        assert_eq!(result.logs[0].address, contract);
+        let mut topic = [0u8; 32];
+        topic[31] = 1;
+        assert_eq!(result.logs[0].topics, vec![topic]);
+        assert_eq!(result.logs[0].data, Bytes::from(vec![0xbe, 0xef]));
+
+        assert!(vm.execute(ctx.as_static()).is_err());
+    }
+
+    #[test]
+    fn test_sload_access_list() {
+        let vm = MerklithVM::new().unwrap();
+        let contract = Address::from_bytes([8u8; 20]);
+        let mut slot_one = [0u8; 32];
+        slot_one[31] = 1;
+        let mut slot_two = [0u8; 32];
+        slot_two[31] = 2;
+        let storage = std::collections::HashMap::from([(slot_one, [0xaa; 32])]);
+
+        // SLOAD 1, SLOAD 2, SLOAD 1 again; POP POP leaves slot 1's value
+        let code = Bytes::from(vec![0x60, 0x01, 0x54, 0x60, 0x02, 0x54, 0x60, 0x01, 0x54, 0x50, 0x50, 0x00]);
+        let ctx = ExecutionContext {
+            code,
+            ..ExecutionContext::new_call(contract, Address::ZERO, Address::ZERO, 100_000, Bytes::new())
+        }
+        .with_storage(storage);
+
+        let result = vm.execute(ctx).unwrap();
+        assert_eq!(result.data, Bytes::from(vec![0xaa; 32]));
+        assert_eq!(result.access_list.len(), 1);
+        assert_eq!(result.access_list[0].address, contract);
+        assert_eq!(
+            result.access_list[0].storage_keys,
+            vec![Hash::from_bytes(slot_one), Hash::from_bytes(slot_two)]
+        );
+        // Two cold reads and one warm read on top of the base cost
+        assert!(result.gas_used >= 21_000 + 2 * 2100 + 100);
+    }
+
+    #[test]
+    fn test_sstore_trace() {
+        let vm = MerklithVM::new().unwrap();
+        let contract = Address::from_bytes([9u8; 20]);
+        let slot = |n: u8| {
+            let mut key = [0u8; 32];
+            key[31] = n;
+            key
+        };
+
+        // SSTORE 0x2a -> 1, SSTORE 0x07 -> 2, SLOAD 1; STOP
+        let code = Bytes::from(vec![
+            0x60, 0x2a, 0x60, 0x01, 0x55,
+            0x60, 0x07, 0x60, 0x02, 0x55,
+            0x60, 0x01, 0x54, 0x00,
+        ]);
+        let ctx = ExecutionContext {
+            code,
+            ..ExecutionContext::new_call(contract, Address::ZERO, Address::ZERO, 100_000, Bytes::new())
+        };
+
+        // Without tracing nothing is recorded, but the writes are
+        let result = vm.execute(ctx.clone()).unwrap();
+        assert!(result.trace.is_empty());
+        assert_eq!(result.data, Bytes::from(slot(0x2a).to_vec()));
+        assert_eq!(result.state_changes.storage.len(), 2);
+        assert_eq!(
+            result.state_changes.storage[&(contract, slot(2))],
+            Some(slot(0x07))
+        );
+
+        let result = vm.execute(ctx.clone().with_tracing()).unwrap();
+        let ops: Vec<&str> = result.trace.iter().map(|step| step.op).collect();
+        assert_eq!(ops, ["PUSH", "PUSH", "SSTORE", "PUSH", "PUSH", "SSTORE", "PUSH", "SLOAD", "STOP"]);
+        assert_eq!(result.trace[2].pc, 4);
+        assert_eq!(
+            result.trace[2].storage,
+            Some(StorageAccess::Write { key: slot(1), value: slot(0x2a) })
+        );
+        assert_eq!(result.trace[2].gas_cost, GasSchedule::default().storage_write_new);
+        assert_eq!(
+            result.trace[7].storage,
+            Some(StorageAccess::Read { key: slot(1), value: slot(0x2a) })
+        );
+        // Step costs add up to everything charged after the base cost
+        let traced: u64 = result.trace.iter().map(|step| step.gas_cost).sum();
+        assert_eq!(traced + 21_000, result.gas_used);
+
+        // A traced failure still returns the steps that ran
+        let failed = vm.execute(ctx.as_static().with_tracing()).unwrap();
+        assert!(!failed.success);
+        assert_eq!(failed.trace.last().unwrap().op, "SSTORE");
+    }
+
     #[test]
     fn test_state_changes() {
         let mut changes = StateChanges::default();
@@ -611,4 +952,22 @@ mod tests {
         // This might panic if VM creation fails, but that's acceptable for default()
         let _vm = MerklithVM::default();
     }
+
+    #[test]
+    fn test_wasm_module_cached_across_calls() {
+        let vm = MerklithVM::new().unwrap();
+        let code = wat::parse_str(r#"(module (func (export "call")))"#).unwrap();
+        let ctx = ExecutionContext {
+            code: Bytes::from(code.clone()),
+            ..ExecutionContext::new_call(Address::ZERO, Address::ZERO, Address::ZERO, 100_000, Bytes::new())
+        };
+
+        let _ = vm.execute(ctx.clone());
+        let _ = vm.execute(ctx);
+        let stats = vm.module_cache_stats();
+        assert_eq!((stats.misses, stats.hits), (1, 1));
+
+        assert!(vm.invalidate_code(blake3::hash(&code).as_bytes()));
+        assert_eq!(vm.module_cache_stats().entries, 0);
+    }
 }
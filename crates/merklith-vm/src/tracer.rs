@@ -0,0 +1,74 @@
+//! Execution tracing.
+//!
+//! With [`ExecutionContext::trace`](crate::ExecutionContext) set, the
+//! interpreter records one [`TraceStep`] per executed instruction, in the
+//! spirit of Geth's struct logger. WASM contracts make no host calls yet, so
+//! a WASM call is traced as a single step covering the whole run.
+
+/// Storage slot touched by a step.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StorageAccess {
+    /// SLOAD of `key`, which held `value`
+    Read { key: [u8; 32], value: [u8; 32] },
+    /// SSTORE of `value` into `key`
+    Write { key: [u8; 32], value: [u8; 32] },
+}
+
+/// One executed instruction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceStep {
+    /// Offset of the instruction in the code
+    pub pc: usize,
+    /// Instruction name, e.g. `SSTORE`
+    pub op: &'static str,
+    /// Gas left before the instruction ran
+    pub gas: u64,
+    /// Gas charged by the instruction
+    pub gas_cost: u64,
+    /// Stack depth before the instruction ran
+    pub stack_depth: usize,
+    /// Storage slot read or written, if any
+    pub storage: Option<StorageAccess>,
+}
+
+/// Name of an interpreter opcode.
+pub fn opcode_name(opcode: u8) -> &'static str {
+    match opcode {
+        0x00 => "STOP",
+        0x01 => "ADD",
+        0x02 => "MUL",
+        0x10 => "LT",
+        0x14 => "EQ",
+        0x35 => "CALLDATALOAD",
+        0x36 => "CALLDATASIZE",
+        0x50 => "POP",
+        0x51 => "MLOAD",
+        0x52 => "MSTORE",
+        0x54 => "SLOAD",
+        0x55 => "SSTORE",
+        0x60..=0x7F => "PUSH",
+        0xA0 => "LOG0",
+        0xA1 => "LOG1",
+        0xA2 => "LOG2",
+        0xA3 => "LOG3",
+        0xA4 => "LOG4",
+        0xF0 => "CREATE",
+        0xF1 => "CALL",
+        0xFD => "REVERT",
+        0xFF => "SELFDESTRUCT",
+        _ => "INVALID",
+    }
+}
+
+/// Fill in each step's gas cost from the gas left before the next one.
+pub(crate) fn settle_gas_costs(steps: &mut [TraceStep], gas_left: u64) {
+    let gas_after: Vec<u64> = steps
+        .iter()
+        .skip(1)
+        .map(|next| next.gas)
+        .chain(std::iter::once(gas_left))
+        .collect();
+    for (step, after) in steps.iter_mut().zip(gas_after) {
+        step.gas_cost = step.gas.saturating_sub(after);
+    }
+}
@@ -0,0 +1,97 @@
+//! Source verification.
+//!
+//! A contract is verified by rebuilding its source here and comparing the
+//! result with the code deployed on chain; code compiled by whoever asks
+//! for verification is never trusted.
+
+use merklith_types::Hash;
+use thiserror::Error;
+
+/// Compilers sources can be rebuilt with. `wat` turns the WebAssembly text
+/// format into the binary module the VM runs, deterministically.
+pub const SUPPORTED_COMPILERS: &[&str] = &["wat"];
+
+/// A source that rebuilds to the deployed code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceVerification {
+    pub compiler_version: String,
+    /// blake3 hash of the source text
+    pub source_hash: Hash,
+    /// blake3 hash of the rebuilt (and deployed) code
+    pub code_hash: Hash,
+}
+
+/// Why a source was not verified.
+#[derive(Debug, Error, Clone, PartialEq)]
+pub enum VerifyError {
+    #[error("Unsupported compiler: {0}")]
+    UnsupportedCompiler(String),
+
+    #[error("Source does not compile: {0}")]
+    Compile(String),
+
+    #[error("Compiled code {compiled} does not match deployed code {deployed}")]
+    Mismatch { compiled: Hash, deployed: Hash },
+}
+
+/// Rebuild `source` with `compiler_version` and check that it produces
+/// exactly `deployed_code`.
+pub fn verify_source(
+    compiler_version: &str,
+    source: &str,
+    deployed_code: &[u8],
+) -> Result<SourceVerification, VerifyError> {
+    let compiled = match compiler_version {
+        "wat" => wat::parse_str(source).map_err(|e| VerifyError::Compile(e.to_string()))?,
+        other => return Err(VerifyError::UnsupportedCompiler(other.to_string())),
+    };
+
+    let code_hash = Hash::compute(&compiled);
+    let deployed = Hash::compute(deployed_code);
+    if code_hash != deployed {
+        return Err(VerifyError::Mismatch { compiled: code_hash, deployed });
+    }
+
+    Ok(SourceVerification {
+        compiler_version: compiler_version.to_string(),
+        source_hash: Hash::compute(source.as_bytes()),
+        code_hash,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = r#"(module (func (export "answer") (result i32) i32.const 42))"#;
+
+    #[test]
+    fn test_matching_source_verifies() {
+        let deployed = wat::parse_str(SOURCE).unwrap();
+
+        let verification = verify_source("wat", SOURCE, &deployed).unwrap();
+        assert_eq!(verification.code_hash, Hash::compute(&deployed));
+        assert_eq!(verification.source_hash, Hash::compute(SOURCE.as_bytes()));
+        assert_eq!(verification.compiler_version, "wat");
+
+        // Formatting does not change the module, so it verifies too
+        let reformatted = SOURCE.replace(' ', "\n  ");
+        assert!(verify_source("wat", &reformatted, &deployed).is_ok());
+    }
+
+    #[test]
+    fn test_mismatching_source_rejected() {
+        let deployed = wat::parse_str(SOURCE).unwrap();
+
+        let other = SOURCE.replace("42", "43");
+        assert!(matches!(
+            verify_source("wat", &other, &deployed),
+            Err(VerifyError::Mismatch { deployed: hash, .. }) if hash == Hash::compute(&deployed)
+        ));
+        assert!(matches!(verify_source("wat", "(module", &deployed), Err(VerifyError::Compile(_))));
+        assert_eq!(
+            verify_source("solc-0.8.20", SOURCE, &deployed),
+            Err(VerifyError::UnsupportedCompiler("solc-0.8.20".to_string()))
+        );
+    }
+}
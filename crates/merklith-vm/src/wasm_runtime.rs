@@ -1,11 +1,92 @@
 //! WASM Runtime for MERKLITH VM
-//! 
-//! Simplified WASM runtime - production-ready version
+//!
+//! Contracts export a `call` function taking no arguments and returning an
+//! `i64` that packs the return data location in the exported `memory`:
+//! offset in the high 32 bits, length in the low 32 bits.
+//!
+//! Execution is metered with wasmtime fuel, which counts executed
+//! instructions and so is identical on every node. Each unit of fuel costs
+//! `GasSchedule::gas_per_fuel` gas. Memory growth costs
+//! `GasSchedule::memory_per_page` per 64KB page and may not exceed
+//! `MAX_MEMORY_BYTES`. Return data may not exceed
+//! `WasmRuntimeConfig::max_return_data` and costs
+//! `GasSchedule::return_data_per_word` per 32 bytes.
+//!
+//! Floating-point NaN bit patterns differ across hardware, so by default
+//! modules containing any floating-point instruction are rejected when they
+//! are compiled. See [`FloatPolicy`].
 
 use crate::error::VmError;
 use crate::gas_metering::GasTracker;
+use crate::module_cache::{ModuleCache, ModuleCacheStats, DEFAULT_MODULE_CACHE_SIZE};
 use crate::runtime::{ExecutionContext, ExecutionResult};
+use crate::{MAX_CODE_SIZE, MAX_MEMORY_BYTES, MAX_RETURN_DATA_BYTES};
 use merklith_types::{Address, Hash};
+use bytes::Bytes;
+use wasmtime::{Config, Engine, Instance, Module, ResourceLimiter, Store, Trap};
+
+/// Exported entry point of a contract.
+const ENTRY_POINT: &str = "call";
+
+/// WASM page size in bytes.
+const PAGE_SIZE: usize = 65_536;
+
+/// Charges gas for memory growth and enforces the memory cap.
+struct MemoryMeter {
+    max_bytes: usize,
+    gas_per_page: u64,
+    /// Gas available when execution started
+    gas_budget: u64,
+    /// Gas charged for growth so far
+    gas_charged: u64,
+    /// Why growth was refused, if it was
+    error: Option<VmError>,
+}
+
+impl ResourceLimiter for MemoryMeter {
+    fn memory_growing(
+        &mut self,
+        current: usize,
+        desired: usize,
+        _maximum: Option<usize>,
+    ) -> wasmtime::Result<bool> {
+        if desired > self.max_bytes {
+            let error = VmError::MemoryLimitExceeded {
+                size: desired,
+                limit: self.max_bytes,
+            };
+            self.error = Some(error.clone());
+            return Err(wasmtime::Error::msg(error.to_string()));
+        }
+
+        let pages = desired.saturating_sub(current).div_ceil(PAGE_SIZE) as u64;
+        let charged = self.gas_charged.saturating_add(pages.saturating_mul(self.gas_per_page));
+        if charged > self.gas_budget {
+            let error = VmError::OutOfGas {
+                used: charged,
+                limit: self.gas_budget,
+            };
+            self.error = Some(error.clone());
+            return Err(wasmtime::Error::msg(error.to_string()));
+        }
+        self.gas_charged = charged;
+        Ok(true)
+    }
+
+    fn table_growing(&mut self, _current: u32, _desired: u32, _maximum: Option<u32>) -> wasmtime::Result<bool> {
+        Ok(true)
+    }
+}
+
+/// How the runtime treats floating-point instructions.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FloatPolicy {
+    /// Refuse to compile modules that use floating point
+    #[default]
+    Reject,
+    /// Allow floating point; NaN results are canonicalized by the engine
+    Canonicalize,
+}
 
 /// WASM Runtime configuration
 #[derive(Debug, Clone)]
@@ -13,6 +94,12 @@ pub struct WasmRuntimeConfig {
     pub max_memory_pages: u32,
     pub gas_limit: u64,
     pub debug_mode: bool,
+    /// Number of compiled modules kept in the cache
+    pub module_cache_size: usize,
+    /// Floating-point handling
+    pub float_policy: FloatPolicy,
+    /// Largest return data a call may produce
+    pub max_return_data: usize,
 }
 
 impl Default for WasmRuntimeConfig {
@@ -21,6 +108,9 @@ impl Default for WasmRuntimeConfig {
             max_memory_pages: 1024,
             gas_limit: 10_000_000,
             debug_mode: false,
+            module_cache_size: DEFAULT_MODULE_CACHE_SIZE,
+            float_policy: FloatPolicy::default(),
+            max_return_data: MAX_RETURN_DATA_BYTES,
         }
     }
 }
@@ -28,11 +118,61 @@ impl Default for WasmRuntimeConfig {
 /// WASM Runtime
 pub struct WasmRuntime {
     config: WasmRuntimeConfig,
+    engine: Engine,
+    modules: ModuleCache,
 }
 
 impl WasmRuntime {
     pub fn new(config: WasmRuntimeConfig) -> Result<Self, VmError> {
-        Ok(Self { config })
+        let mut engine_config = Config::new();
+        engine_config
+            .consume_fuel(true)
+            .cranelift_nan_canonicalization(true);
+        let engine = Engine::new(&engine_config)
+            .map_err(|e| VmError::ExecutionError(format!("Failed to create engine: {}", e)))?;
+        Ok(Self::with_engine(engine, config))
+    }
+
+    /// Create a runtime compiling with an existing engine.
+    ///
+    /// The engine must have fuel consumption and NaN canonicalization enabled.
+    pub fn with_engine(engine: Engine, config: WasmRuntimeConfig) -> Self {
+        let modules = ModuleCache::new(config.module_cache_size);
+        Self {
+            config,
+            engine,
+            modules,
+        }
+    }
+
+    /// Set the largest return data a call may produce.
+    pub fn with_max_return_data(mut self, bytes: usize) -> Self {
+        self.config.max_return_data = bytes;
+        self
+    }
+
+    /// Compile `code`, reusing the cached module for the same code hash.
+    pub fn compile(&self, code: &[u8]) -> Result<Module, VmError> {
+        let code_hash = *blake3::hash(code).as_bytes();
+        self.modules.get_or_compile(code_hash, || {
+            if self.config.float_policy == FloatPolicy::Reject {
+                reject_floats(code)?;
+            }
+            Module::new(&self.engine, code).map_err(|e| VmError::InvalidWasm(e.to_string()))
+        })
+    }
+
+    /// Drop the cached module for `code_hash`.
+    ///
+    /// Changed code hashes differently and never hits a stale entry, so this
+    /// only frees the memory of code that is gone, e.g. after a self-destruct.
+    pub fn invalidate_code(&self, code_hash: &[u8; 32]) -> bool {
+        self.modules.invalidate(code_hash)
+    }
+
+    /// Module cache hit and miss counts.
+    pub fn module_cache_stats(&self) -> ModuleCacheStats {
+        self.modules.stats()
     }
 
     /// Execute contract
@@ -73,7 +213,7 @@ impl WasmRuntime {
             );
         }
 
-        let max_bytes = (self.config.max_memory_pages as usize) * 65_536;
+        let max_bytes = (self.config.max_memory_pages as usize) * PAGE_SIZE;
         if code.len() > max_bytes {
             return Err(VmError::MemoryLimitExceeded {
                 size: code.len(),
@@ -81,18 +221,136 @@ impl WasmRuntime {
             });
         }
 
-        Err(VmError::ExecutionError(
-            "WASM execution engine is not enabled in this build".to_string(),
-        ))
+        let module = self.compile(code)?;
+        let data = self.run(&module, gas_tracker)?;
+        Ok(ExecutionResult::success(data, gas_tracker.used()))
+    }
+
+    /// Run the entry point of `module` on the gas left in `gas_tracker`.
+    fn run(&self, module: &Module, gas_tracker: &mut GasTracker) -> Result<Bytes, VmError> {
+        let gas_per_fuel = gas_tracker.schedule().gas_per_fuel.max(1);
+        let fuel = gas_tracker.remaining() / gas_per_fuel;
+
+        let meter = MemoryMeter {
+            max_bytes: MAX_MEMORY_BYTES.min(self.config.max_memory_pages as usize * PAGE_SIZE),
+            gas_per_page: gas_tracker.schedule().memory_per_page,
+            gas_budget: gas_tracker.remaining(),
+            gas_charged: 0,
+            error: None,
+        };
+        let mut store = Store::new(&self.engine, meter);
+        store.limiter(|meter| meter);
+        store.set_fuel(fuel)?;
+
+        let outcome = Instance::new(&mut store, module, &[]).and_then(|instance| {
+            let entry = instance.get_typed_func::<(), i64>(&mut store, ENTRY_POINT)?;
+            entry.call(&mut store, ()).map(|packed| (instance, packed))
+        });
+
+        // Running out of fuel or growth gas burns all remaining gas; otherwise
+        // charge what was consumed, whether or not the call succeeded
+        let meter_error = store.data_mut().error.take();
+        let out_of_gas = matches!(
+            outcome.as_ref().err().and_then(|e| e.downcast_ref::<Trap>()),
+            Some(Trap::OutOfFuel)
+        ) || matches!(meter_error, Some(VmError::OutOfGas { .. }));
+        if out_of_gas {
+            gas_tracker.charge(gas_tracker.remaining())?;
+            return Err(VmError::OutOfGas {
+                used: gas_tracker.used(),
+                limit: gas_tracker.limit(),
+            });
+        }
+        let consumed = fuel - store.get_fuel().unwrap_or(0);
+        gas_tracker.charge(consumed * gas_per_fuel)?;
+        gas_tracker.charge(store.data().gas_charged)?;
+        if let Some(error) = meter_error {
+            return Err(error);
+        }
+
+        let (instance, packed) = outcome.map_err(|e| match e.downcast_ref::<Trap>() {
+            Some(trap) => VmError::Trap(trap.to_string()),
+            None => VmError::ExecutionError(e.to_string()),
+        })?;
+
+        let offset = (packed as u64 >> 32) as usize;
+        let len = (packed as u64 & 0xffff_ffff) as usize;
+        if len == 0 {
+            return Ok(Bytes::new());
+        }
+        if len > self.config.max_return_data {
+            return Err(VmError::ReturnDataTooLarge {
+                size: len,
+                limit: self.config.max_return_data,
+            });
+        }
+        gas_tracker.charge_return_data(len)?;
+        let memory = instance
+            .get_memory(&mut store, "memory")
+            .ok_or(VmError::InvalidMemoryAccess)?;
+        let data = memory
+            .data(&store)
+            .get(offset..offset + len)
+            .ok_or(VmError::InvalidMemoryAccess)?;
+        Ok(Bytes::copy_from_slice(data))
     }
 }
 
+/// Check that `code` is a well-formed contract the default runtime accepts.
+///
+/// Used before code is stored, so a bad module is refused up front rather
+/// than failing on every call.
+pub fn validate_wasm(code: &[u8]) -> Result<(), VmError> {
+    if code.len() > MAX_CODE_SIZE {
+        return Err(VmError::CodeSizeExceeded {
+            size: code.len(),
+            limit: MAX_CODE_SIZE,
+        });
+    }
+    if !code.starts_with(&[0x00, 0x61, 0x73, 0x6d]) {
+        return Err(VmError::InvalidWasm("Missing WASM magic bytes".to_string()));
+    }
+    wasmparser::validate(code).map_err(|e| VmError::InvalidWasm(e.to_string()))?;
+    reject_floats(code)
+}
+
+/// Fail if any function body uses a floating-point instruction.
+///
+/// Float-typed locals and signatures are harmless on their own; every way of
+/// producing or operating on a float value is an `F32*`/`F64*` operator or a
+/// conversion naming one.
+fn reject_floats(code: &[u8]) -> Result<(), VmError> {
+    let invalid = |e: wasmparser::BinaryReaderError| VmError::InvalidWasm(e.to_string());
+    for payload in wasmparser::Parser::new(0).parse_all(code) {
+        let wasmparser::Payload::CodeSectionEntry(body) = payload.map_err(invalid)? else {
+            continue;
+        };
+        let mut operators = body.get_operators_reader().map_err(invalid)?;
+        while !operators.eof() {
+            let operator = format!("{:?}", operators.read().map_err(invalid)?);
+            let name = operator.split([' ', '{', '(']).next().unwrap_or_default();
+            if name.contains("F32") || name.contains("F64") {
+                return Err(VmError::InvalidWasm(format!(
+                    "Floating-point instruction {} is not allowed",
+                    name
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Most topics a log may carry, as with LOG4
+pub const MAX_LOG_TOPICS: usize = 4;
+
 /// Host state for WASM execution
 #[derive(Debug)]
 pub struct HostState {
     pub contract_address: Address,
     pub caller: Address,
     pub gas_tracker: GasTracker,
+    /// Logs emitted by the contract during the call
+    pub logs: Vec<LogEntry>,
 }
 
 impl HostState {
@@ -105,7 +363,32 @@ impl HostState {
             contract_address,
             caller,
             gas_tracker,
+            logs: Vec::new(),
+        }
+    }
+
+    /// Emit a log from the contract, charged like the LOG opcodes
+    pub fn log(&mut self, topics: Vec<Hash>, data: Vec<u8>) -> Result<(), VmError> {
+        if topics.len() > MAX_LOG_TOPICS {
+            return Err(VmError::ExecutionError(format!(
+                "A log takes at most {} topics, got {}",
+                MAX_LOG_TOPICS,
+                topics.len()
+            )));
         }
+        let schedule = *self.gas_tracker.schedule();
+        self.gas_tracker.charge(
+            schedule.log_base
+                + schedule.log_per_topic * topics.len() as u64
+                + schedule.log_per_byte * data.len() as u64,
+        )?;
+        self.logs.push(LogEntry { topics, data });
+        Ok(())
+    }
+
+    /// Take the logs emitted so far
+    pub fn take_logs(&mut self) -> Vec<LogEntry> {
+        std::mem::take(&mut self.logs)
     }
 }
 
@@ -119,6 +402,7 @@ pub struct LogEntry {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::gas_metering::GasSchedule;
 
     #[test]
     fn test_wasm_runtime_creation() {
@@ -164,4 +448,261 @@ mod tests {
 
         assert!(matches!(result, Err(VmError::InvalidWasm(_))));
     }
+
+    fn wasm_ctx(code: &[u8]) -> ExecutionContext {
+        ExecutionContext {
+            code: bytes::Bytes::copy_from_slice(code),
+            ..ExecutionContext::new_call(Address::ZERO, Address::ZERO, Address::ZERO, 100_000, bytes::Bytes::new())
+        }
+    }
+
+    #[test]
+    fn test_fuel_gas_deterministic() {
+        let code = wat::parse_str(
+            r#"(module
+                (memory (export "memory") 1)
+                (data (i32.const 16) "done")
+                (func (export "call") (result i64)
+                    (local $i i32)
+                    (loop $l
+                        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+                        (br_if $l (i32.lt_u (local.get $i) (i32.const 100))))
+                    (i64.const 0x0000001000000004)))"#,
+        )
+        .unwrap();
+        let ctx = wasm_ctx(&code);
+
+        let mut gas_used = Vec::new();
+        for _ in 0..2 {
+            let runtime = WasmRuntime::new(WasmRuntimeConfig::default()).unwrap();
+            let mut gas_tracker = GasTracker::with_default_schedule(100_000);
+            let result = runtime.execute(&code, &ctx, &mut gas_tracker).unwrap();
+            assert_eq!(&result.data[..], b"done");
+            gas_used.push(result.gas_used);
+        }
+        assert_eq!(gas_used[0], gas_used[1]);
+
+        // Execution costs more than the fixed charges and initial memory page
+        let schedule = GasSchedule::default();
+        let fixed = schedule.tx_base
+            + (code.len() as u64).div_ceil(32) * schedule.tx_per_data_nonzero_byte
+            + schedule.memory_per_page
+            + schedule.return_data_per_word;
+        assert!(gas_used[0] > fixed);
+
+        // A higher fuel price scales only the execution part
+        let runtime = WasmRuntime::new(WasmRuntimeConfig::default()).unwrap();
+        let schedule = GasSchedule { gas_per_fuel: 2, ..GasSchedule::default() };
+        let mut gas_tracker = GasTracker::new(100_000, schedule);
+        let result = runtime.execute(&code, &ctx, &mut gas_tracker).unwrap();
+        assert_eq!(result.gas_used - fixed, 2 * (gas_used[0] - fixed));
+    }
+
+    #[test]
+    fn test_out_of_fuel_is_out_of_gas() {
+        let code = wat::parse_str(r#"(module (func (export "call") (result i64) (loop $l (br $l)) (i64.const 0)))"#).unwrap();
+        let runtime = WasmRuntime::new(WasmRuntimeConfig::default()).unwrap();
+        let mut gas_tracker = GasTracker::with_default_schedule(50_000);
+
+        let result = runtime.execute(&code, &wasm_ctx(&code), &mut gas_tracker);
+        assert_eq!(result.unwrap_err(), VmError::OutOfGas { used: 50_000, limit: 50_000 });
+        assert_eq!(gas_tracker.used(), 50_000);
+    }
+
+    fn grow_contract(pages: u32) -> Vec<u8> {
+        wat::parse_str(format!(
+            r#"(module
+                (memory (export "memory") 0)
+                (func (export "call") (result i64)
+                    (drop (memory.grow (i32.const {})))
+                    (i64.const 0)))"#,
+            pages
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_memory_growth_gas() {
+        let code = grow_contract(4);
+        let ctx = wasm_ctx(&code);
+        let runtime = WasmRuntime::new(WasmRuntimeConfig::default()).unwrap();
+
+        let gas_at = |memory_per_page: u64| {
+            let schedule = GasSchedule { memory_per_page, ..GasSchedule::default() };
+            let mut gas_tracker = GasTracker::new(1_000_000, schedule);
+            runtime.execute(&code, &ctx, &mut gas_tracker).unwrap().gas_used
+        };
+        // Four pages grown, each 1000 gas dearer
+        assert_eq!(gas_at(1_003) - gas_at(3), 4 * 1_000);
+
+        // Growth the remaining gas cannot pay for runs out of gas
+        let schedule = GasSchedule { memory_per_page: 1_000_000, ..GasSchedule::default() };
+        let mut gas_tracker = GasTracker::new(1_000_000, schedule);
+        let result = runtime.execute(&code, &ctx, &mut gas_tracker);
+        assert!(matches!(result, Err(VmError::OutOfGas { .. })));
+        assert_eq!(gas_tracker.used(), 1_000_000);
+    }
+
+    #[test]
+    fn test_memory_growth_cap() {
+        let max_pages = (MAX_MEMORY_BYTES / PAGE_SIZE) as u32;
+        let runtime = WasmRuntime::new(WasmRuntimeConfig::default()).unwrap();
+
+        let code = grow_contract(max_pages);
+        let mut gas_tracker = GasTracker::with_default_schedule(1_000_000);
+        assert!(runtime.execute(&code, &wasm_ctx(&code), &mut gas_tracker).is_ok());
+
+        let code = grow_contract(max_pages + 1);
+        let mut gas_tracker = GasTracker::with_default_schedule(1_000_000);
+        let result = runtime.execute(&code, &wasm_ctx(&code), &mut gas_tracker);
+        assert_eq!(
+            result.unwrap_err(),
+            VmError::MemoryLimitExceeded {
+                size: MAX_MEMORY_BYTES + PAGE_SIZE,
+                limit: MAX_MEMORY_BYTES,
+            }
+        );
+    }
+
+    fn return_contract(pages: u32, len: u32) -> Vec<u8> {
+        wat::parse_str(format!(
+            r#"(module
+                (memory (export "memory") {})
+                (func (export "call") (result i64) (i64.const {})))"#,
+            pages, len
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_return_data_limit() {
+        // 2 MB of zeroed memory, all of it returned
+        let code = return_contract(32, 32 * PAGE_SIZE as u32);
+        let runtime = WasmRuntime::new(WasmRuntimeConfig::default()).unwrap();
+        let mut gas_tracker = GasTracker::with_default_schedule(1_000_000);
+        let result = runtime.execute(&code, &wasm_ctx(&code), &mut gas_tracker);
+        assert_eq!(
+            result.unwrap_err(),
+            VmError::ReturnDataTooLarge { size: 32 * PAGE_SIZE, limit: MAX_RETURN_DATA_BYTES }
+        );
+
+        let runtime = WasmRuntime::new(WasmRuntimeConfig::default()).unwrap().with_max_return_data(64);
+        let code = return_contract(1, 65);
+        let mut gas_tracker = GasTracker::with_default_schedule(1_000_000);
+        let result = runtime.execute(&code, &wasm_ctx(&code), &mut gas_tracker);
+        assert_eq!(result.unwrap_err(), VmError::ReturnDataTooLarge { size: 65, limit: 64 });
+
+        // Return data is charged per word
+        let gas_for = |len: u32| {
+            let code = return_contract(1, len);
+            let mut gas_tracker = GasTracker::with_default_schedule(1_000_000);
+            let result = runtime.execute(&code, &wasm_ctx(&code), &mut gas_tracker).unwrap();
+            assert_eq!(result.data.len(), len as usize);
+            result.gas_used
+        };
+        assert_eq!(gas_for(64) - gas_for(32), GasSchedule::default().return_data_per_word);
+    }
+
+    #[test]
+    fn test_float_policy() {
+        // Stores the bits of 0.0 / 0.0 and returns them
+        let code = wat::parse_str(
+            r#"(module
+                (memory (export "memory") 1)
+                (func (export "call") (result i64)
+                    (i64.store (i32.const 0)
+                        (i64.reinterpret_f64 (f64.div (f64.const 0) (f64.const 0))))
+                    (i64.const 8)))"#,
+        )
+        .unwrap();
+        let ctx = wasm_ctx(&code);
+
+        let runtime = WasmRuntime::new(WasmRuntimeConfig::default()).unwrap();
+        let mut gas_tracker = GasTracker::with_default_schedule(100_000);
+        let result = runtime.execute(&code, &ctx, &mut gas_tracker);
+        assert!(matches!(result, Err(VmError::InvalidWasm(msg)) if msg.contains("F64Const")));
+
+        let config = WasmRuntimeConfig {
+            float_policy: FloatPolicy::Canonicalize,
+            ..WasmRuntimeConfig::default()
+        };
+        let results: Vec<_> = (0..2)
+            .map(|_| {
+                let runtime = WasmRuntime::new(config.clone()).unwrap();
+                let mut gas_tracker = GasTracker::with_default_schedule(100_000);
+                runtime.execute(&code, &ctx, &mut gas_tracker).unwrap().data
+            })
+            .collect();
+        assert_eq!(results[0], results[1]);
+        let bits = u64::from_le_bytes(results[0][..].try_into().unwrap());
+        assert!(f64::from_bits(bits).is_nan());
+    }
+
+    #[test]
+    fn test_validate_wasm() {
+        let code = grow_contract(1);
+        assert!(validate_wasm(&code).is_ok());
+        assert!(matches!(validate_wasm(&[0x00, 0x61, 0x73, 0x6d, 0xff]), Err(VmError::InvalidWasm(_))));
+        assert!(matches!(validate_wasm(&[0x60, 0x01]), Err(VmError::InvalidWasm(_))));
+        assert!(matches!(
+            validate_wasm(&vec![0u8; MAX_CODE_SIZE + 1]),
+            Err(VmError::CodeSizeExceeded { .. })
+        ));
+    }
+
+    #[test]
+    fn test_second_call_skips_compilation() {
+        let runtime = WasmRuntime::new(WasmRuntimeConfig::default()).unwrap();
+        let code = wat::parse_str(
+            r#"(module
+                (memory 1)
+                (func (export "call") (result i32)
+                    (i32.add (i32.const 1) (i32.const 2))))"#,
+        )
+        .unwrap();
+
+        let start = std::time::Instant::now();
+        runtime.compile(&code).unwrap();
+        let first = start.elapsed();
+        let start = std::time::Instant::now();
+        runtime.compile(&code).unwrap();
+        let second = start.elapsed();
+
+        let stats = runtime.module_cache_stats();
+        assert_eq!((stats.misses, stats.hits, stats.entries), (1, 1, 1));
+        assert!(second < first, "cached lookup {:?} not faster than compile {:?}", second, first);
+
+        // Malformed modules are rejected and not cached
+        assert!(matches!(
+            runtime.compile(&[0x00, 0x61, 0x73, 0x6d, 0xff]),
+            Err(VmError::InvalidWasm(_))
+        ));
+        assert_eq!(runtime.module_cache_stats().entries, 1);
+
+        assert!(runtime.invalidate_code(blake3::hash(&code).as_bytes()));
+        runtime.compile(&code).unwrap();
+        assert_eq!(runtime.module_cache_stats().misses, 3);
+    }
+
+    #[test]
+    fn test_host_log_charges_gas() {
+        let mut host = HostState::new(
+            Address::ZERO,
+            Address::ZERO,
+            GasTracker::with_default_schedule(100_000),
+        );
+        let schedule = GasSchedule::default();
+
+        host.log(vec![Hash::ZERO, Hash::ZERO], vec![1, 2, 3]).unwrap();
+        assert_eq!(
+            host.gas_tracker.used(),
+            schedule.log_base + schedule.log_per_topic * 2 + schedule.log_per_byte * 3
+        );
+
+        assert!(host.log(vec![Hash::ZERO; MAX_LOG_TOPICS + 1], vec![]).is_err());
+        let logs = host.take_logs();
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0].data, vec![1, 2, 3]);
+        assert!(host.logs.is_empty());
+    }
 }
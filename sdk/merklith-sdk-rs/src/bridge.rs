@@ -0,0 +1,269 @@
+//! Bridge contract monitoring for relayers.
+//!
+//! Watches the logs of the bridge in `contracts/examples/src/bridge.rs` and
+//! yields typed cross-chain requests. A watcher can resume after the last
+//! processed block, so a restarted relayer neither misses nor repeats events.
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use futures::{Stream, StreamExt};
+use merklith_types::{Address, Hash, U256};
+
+use crate::client::Client;
+use crate::contract::decode_return;
+use crate::errors::{Result, SdkError};
+use crate::events::event_signature_to_topic;
+use crate::types::{BlockId, Filter, Log, LogEvent};
+
+/// Topic signature of [`BridgeEvent`] logs.
+pub const BRIDGE_EVENT: &str =
+    "BridgeEvent(uint8,uint64,uint64,address,uint256,address,address,bytes32,uint64)";
+
+/// Topic signature of [`BridgeRequest`] logs.
+pub const BRIDGE_REQUEST: &str =
+    "BridgeRequest(uint64,uint64,address,uint256,address,address,uint64,bytes[])";
+
+/// Bridge direction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub enum BridgeDirection {
+    /// Lock on source, mint on destination
+    Lock,
+    /// Burn on source, unlock on destination
+    Unlock,
+}
+
+/// Lock or unlock performed by the bridge.
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct BridgeEvent {
+    pub direction: BridgeDirection,
+    pub from_chain: u64,
+    pub to_chain: u64,
+    pub token: Address,
+    pub amount: U256,
+    pub sender: Address,
+    pub recipient: Address,
+    pub tx_hash: Hash,
+    pub nonce: u64,
+}
+
+/// Validator signature on a bridge request.
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct ValidatorSignature {
+    pub validator: Address,
+    pub signature: Vec<u8>,
+}
+
+/// Cross-chain transfer request.
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct BridgeRequest {
+    pub from_chain: u64,
+    pub to_chain: u64,
+    pub token: Address,
+    pub amount: U256,
+    pub sender: Address,
+    pub recipient: Address,
+    pub nonce: u64,
+    pub signatures: Vec<ValidatorSignature>,
+}
+
+/// Decoded bridge log payload.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BridgeLog {
+    /// A completed lock or unlock
+    Event(BridgeEvent),
+    /// A transfer request awaiting relay
+    Request(BridgeRequest),
+}
+
+/// A bridge log with the fields a relayer acts on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WatchedBridgeEvent {
+    /// Bridge nonce identifying the request
+    pub request_id: u64,
+    /// Amount bridged
+    pub amount: U256,
+    /// Destination chain
+    pub to_chain: u64,
+    /// Recipient on the destination chain
+    pub recipient: Address,
+    /// Block containing the log
+    pub block_number: u64,
+    /// Log index within the block
+    pub log_index: u64,
+    /// Full decoded payload
+    pub log: BridgeLog,
+}
+
+/// Decode a bridge contract log.
+pub fn decode_bridge_log(log: &Log) -> Result<WatchedBridgeEvent> {
+    let topic = log
+        .topics
+        .first()
+        .ok_or_else(|| SdkError::Contract("Log has no topics".to_string()))?;
+
+    let decoded = if *topic == event_signature_to_topic(BRIDGE_EVENT) {
+        BridgeLog::Event(decode_return(&log.data)?)
+    } else if *topic == event_signature_to_topic(BRIDGE_REQUEST) {
+        BridgeLog::Request(decode_return(&log.data)?)
+    } else {
+        return Err(SdkError::Contract("Log is not a bridge event".to_string()));
+    };
+
+    let (request_id, amount, to_chain, recipient) = match &decoded {
+        BridgeLog::Event(e) => (e.nonce, e.amount, e.to_chain, e.recipient),
+        BridgeLog::Request(r) => (r.nonce, r.amount, r.to_chain, r.recipient),
+    };
+
+    Ok(WatchedBridgeEvent {
+        request_id,
+        amount,
+        to_chain,
+        recipient,
+        block_number: log.block_number,
+        log_index: log.log_index,
+        log: decoded,
+    })
+}
+
+/// Watches a bridge contract for cross-chain requests.
+pub struct BridgeWatcher {
+    client: Client,
+    bridge: Address,
+    next_block: u64,
+}
+
+impl BridgeWatcher {
+    /// Watch `bridge` from genesis.
+    pub fn new(client: Client, bridge: Address) -> Self {
+        Self {
+            client,
+            bridge,
+            next_block: 0,
+        }
+    }
+
+    /// Skip everything up to and including `last_processed_block`.
+    pub fn resume_from(mut self, last_processed_block: u64) -> Self {
+        self.next_block = last_processed_block.saturating_add(1);
+        self
+    }
+
+    /// First block not yet processed.
+    pub fn next_block(&self) -> u64 {
+        self.next_block
+    }
+
+    /// Fetch bridge events from the next unprocessed block up to the chain head.
+    ///
+    /// Logs that do not decode as bridge events are skipped.
+    pub async fn poll(&mut self) -> Result<Vec<WatchedBridgeEvent>> {
+        let head = self.client.get_block_number().await?;
+        if head < self.next_block {
+            return Ok(Vec::new());
+        }
+
+        let filter = Filter::new()
+            .address(self.bridge)
+            .from_block(BlockId::Number(self.next_block))
+            .to_block(BlockId::Number(head));
+        let from = self.next_block;
+        let mut events: Vec<WatchedBridgeEvent> = self
+            .client
+            .get_logs(&filter)
+            .await?
+            .iter()
+            .filter(|log| log.block_number >= from && log.block_number <= head)
+            .filter_map(|log| decode_bridge_log(log).ok())
+            .collect();
+        events.sort_by_key(|e| (e.block_number, e.log_index));
+
+        self.next_block = head + 1;
+        Ok(events)
+    }
+
+    /// Catch up with [`poll`](Self::poll), then stream live bridge events.
+    ///
+    /// The live subscription is opened before catching up, and anything the
+    /// catch-up already covered is dropped from it. Logs removed by a reorg
+    /// are skipped; use [`Client::subscribe_logs`] to act on them.
+    pub async fn watch(mut self) -> Result<impl Stream<Item = WatchedBridgeEvent>> {
+        let live = self
+            .client
+            .subscribe_logs(&Filter::new().address(self.bridge))
+            .await?;
+        let backlog = self.poll().await?;
+        let resume = self.next_block;
+
+        let live = live.filter_map(move |event| {
+            let event = match event {
+                LogEvent::Added(log) if log.block_number >= resume => decode_bridge_log(&log).ok(),
+                _ => None,
+            };
+            futures::future::ready(event)
+        });
+        Ok(futures::stream::iter(backlog).chain(live))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn request_log(block_number: u64, nonce: u64) -> serde_json::Value {
+        let request = BridgeRequest {
+            from_chain: 1337,
+            to_chain: 1,
+            token: Address::from_bytes([7u8; 20]),
+            amount: U256::from(5_000u64),
+            sender: Address::from_bytes([1u8; 20]),
+            recipient: Address::from_bytes([2u8; 20]),
+            nonce,
+            signatures: vec![],
+        };
+        json!({
+            "address": format!("0x{}", hex::encode([9u8; 20])),
+            "topics": [format!("0x{}", hex::encode(event_signature_to_topic(BRIDGE_REQUEST)))],
+            "data": format!("0x{}", hex::encode(borsh::to_vec(&request).unwrap())),
+            "blockNumber": format!("0x{:x}", block_number),
+            "logIndex": "0x0",
+        })
+    }
+
+    #[tokio::test]
+    async fn test_bridge_watcher_resume() {
+        let url = crate::test_utils::mock_rpc(|method, params| match method {
+            "eth_blockNumber" => Ok(json!("0x14")),
+            "eth_getLogs" => {
+                assert_eq!(params[0]["fromBlock"], "0xb");
+                assert_eq!(params[0]["toBlock"], "0x14");
+                // Block 5 is already processed and must be ignored
+                let unrelated = json!({
+                    "topics": [format!("0x{}", hex::encode([0u8; 32]))],
+                    "data": "0x",
+                    "blockNumber": "0xc",
+                });
+                Ok(json!([request_log(5, 1), request_log(12, 2), unrelated]))
+            }
+            _ => Err(format!("unexpected method {}", method)),
+        })
+        .await;
+
+        let bridge = Address::from_bytes([9u8; 20]);
+        let mut watcher = BridgeWatcher::new(Client::new(url), bridge).resume_from(10);
+        assert_eq!(watcher.next_block(), 11);
+
+        let events = watcher.poll().await.unwrap();
+        assert_eq!(events.len(), 1);
+        let event = &events[0];
+        assert_eq!(event.request_id, 2);
+        assert_eq!(event.amount, U256::from(5_000u64));
+        assert_eq!(event.to_chain, 1);
+        assert_eq!(event.recipient, Address::from_bytes([2u8; 20]));
+        assert_eq!(event.block_number, 12);
+        assert!(matches!(event.log, BridgeLog::Request(_)));
+
+        // Nothing new until the head moves past the last poll
+        assert_eq!(watcher.next_block(), 21);
+        assert!(watcher.poll().await.unwrap().is_empty());
+    }
+}
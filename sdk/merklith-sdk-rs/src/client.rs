@@ -3,10 +3,20 @@
 use merklith_types::{Address, Hash, SignedTransaction, Transaction, U256};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
+use std::sync::Arc;
 use std::time::Duration;
+use tokio::sync::OnceCell;
 
 use crate::errors::{Result, SdkError};
+use crate::subscription::{Subscription, WsConnection};
 use crate::types::*;
+use crate::wallet::Wallet;
+
+/// Percentage added on top of `eth_estimateGas` by [`Client::prepare_transaction`].
+pub const GAS_ESTIMATE_BUFFER_PERCENT: u64 = 20;
+
+/// Most calls [`Client::send_batch`] puts in one JSON-RPC batch (the node's default limit).
+pub const MAX_BATCH_CALLS: usize = 100;
 
 /// RPC request.
 #[derive(Debug, Serialize)]
@@ -39,6 +49,8 @@ pub struct Client {
     http: reqwest::Client,
     url: String,
     chain_id: Option<u64>,
+    ws_url: Option<String>,
+    ws: Arc<OnceCell<WsConnection>>,
 }
 
 impl Client {
@@ -53,6 +65,8 @@ impl Client {
             http,
             url: url.into(),
             chain_id: None,
+            ws_url: None,
+            ws: Arc::new(OnceCell::new()),
         }
     }
 
@@ -69,6 +83,13 @@ impl Client {
         self
     }
 
+    /// Set the WebSocket endpoint used for subscriptions.
+    pub fn with_ws_url(mut self, ws_url: impl Into<String>) -> Self {
+        self.ws_url = Some(ws_url.into());
+        self.ws = Arc::new(OnceCell::new());
+        self
+    }
+
     /// Get chain ID.
     pub async fn chain_id(&self,
     ) -> Result<u64> {
@@ -109,6 +130,19 @@ impl Client {
         parse_hex_u64(&hex)
     }
 
+    /// Get the next nonce, counting transactions still in the pool.
+    pub async fn get_pending_nonce(
+        &self,
+        address: &Address,
+    ) -> Result<u64> {
+        let addr_hex = format_address(address);
+        let hex: String = self.request(
+            "eth_getTransactionCount",
+            json!([addr_hex, "pending"]),
+        ).await?;
+        parse_hex_u64(&hex)
+    }
+
     /// Get gas price.
     pub async fn get_gas_price(&self,
     ) -> Result<U256> {
@@ -181,6 +215,40 @@ impl Client {
         parse_hex_u64(&hex)
     }
 
+    /// Build a ready-to-sign transaction from `wallet`.
+    ///
+    /// Fetches the pending nonce, chain id and gas price, and sets the gas
+    /// limit to the node's estimate plus [`GAS_ESTIMATE_BUFFER_PERCENT`].
+    pub async fn prepare_transaction(
+        &self,
+        wallet: &Wallet,
+        to: Option<Address>,
+        value: U256,
+        data: Vec<u8>,
+    ) -> Result<Transaction> {
+        let chain_id = match self.chain_id {
+            Some(id) => id,
+            None => self.chain_id().await?,
+        };
+        let nonce = self.get_pending_nonce(&wallet.address()).await?;
+        let gas_price = self.get_gas_price().await?;
+
+        let mut tx = Transaction::new(chain_id, nonce, to, value, 0, gas_price, U256::ZERO)
+            .with_data(data);
+
+        // Leave the gas cap to the node while estimating
+        let mut tx_json = transaction_to_json(&tx);
+        tx_json["from"] = json!(format_address(&wallet.address()));
+        if let Some(obj) = tx_json.as_object_mut() {
+            obj.remove("gas");
+        }
+        let hex: String = self.request("eth_estimateGas", json!([tx_json])).await?;
+        let estimate = parse_hex_u64(&hex)?;
+
+        tx.gas_limit = estimate.saturating_add(estimate * GAS_ESTIMATE_BUFFER_PERCENT / 100);
+        Ok(tx)
+    }
+
     /// Call contract (read-only).
     pub async fn call(
         &self,
@@ -229,6 +297,87 @@ impl Client {
         parse_hash(&hash_hex)
     }
 
+    /// Sign and submit several transactions from one wallet.
+    ///
+    /// The starting nonce is fetched once and assigned sequentially, and the
+    /// transactions go out in JSON-RPC batches of at most [`MAX_BATCH_CALLS`].
+    /// Hashes are returned in the order of `txs`.
+    pub async fn send_batch(
+        &self,
+        wallet: &Wallet,
+        txs: Vec<TxRequest>,
+    ) -> Result<Vec<Hash>> {
+        if txs.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let chain_id = match self.chain_id {
+            Some(id) => id,
+            None => self.chain_id().await?,
+        };
+        let start_nonce = self.get_pending_nonce(&wallet.address()).await?;
+        let gas_price = if txs.iter().any(|tx| tx.max_fee_per_gas.is_none()) {
+            Some(self.get_gas_price().await?)
+        } else {
+            None
+        };
+
+        let mut calls = Vec::with_capacity(txs.len());
+        for (i, request) in txs.into_iter().enumerate() {
+            let tx = Transaction::new(
+                chain_id,
+                start_nonce + i as u64,
+                request.to,
+                request.value,
+                request.gas_limit,
+                request.max_fee_per_gas.or(gas_price).unwrap_or(U256::ZERO),
+                U256::ZERO,
+            )
+            .with_data(request.data);
+            let signed = wallet.sign_transaction(tx)?;
+            let tx_bytes = borsh::to_vec(&signed)
+                .map_err(|e| SdkError::Serialization(e.to_string()))?;
+            calls.push(("eth_sendRawTransaction", json!([format!("0x{}", hex::encode(tx_bytes))])));
+        }
+
+        let mut hashes = Vec::with_capacity(calls.len());
+        let mut calls = calls.into_iter().peekable();
+        while calls.peek().is_some() {
+            let chunk: Vec<_> = calls.by_ref().take(MAX_BATCH_CALLS).collect();
+            let results = match self.request_batch(chunk).await {
+                Ok(results) => results,
+                Err(e) if hashes.is_empty() => return Err(e),
+                Err(e) => {
+                    return Err(SdkError::Batch {
+                        failed_index: hashes.len(),
+                        succeeded: hashes,
+                        message: e.to_string(),
+                    });
+                }
+            };
+            for result in results {
+                let hash = result.and_then(|value| {
+                    value
+                        .as_str()
+                        .ok_or_else(|| SdkError::Serialization("Transaction hash is not a string".to_string()))
+                        .and_then(parse_hash)
+                });
+                match hash {
+                    Ok(hash) => hashes.push(hash),
+                    Err(e) => {
+                        return Err(SdkError::Batch {
+                            failed_index: hashes.len(),
+                            succeeded: hashes,
+                            message: e.to_string(),
+                        });
+                    }
+                }
+            }
+        }
+
+        Ok(hashes)
+    }
+
     /// Get logs.
     pub async fn get_logs(
         &self,
@@ -245,6 +394,36 @@ impl Client {
             .collect()
     }
 
+    /// Stream new block headers.
+    ///
+    /// Subscriptions share one WebSocket connection and are re-established
+    /// after a reconnect. Dropping the stream unsubscribes.
+    pub async fn subscribe_new_heads(&self) -> Result<Subscription<BlockHeader>> {
+        self.ws_connection()
+            .await?
+            .subscribe(json!(["newHeads"]), parse_block_header)
+            .await
+    }
+
+    /// Stream logs matching `filter`.
+    ///
+    /// Logs from blocks that are later reorged out are delivered again as
+    /// [`LogEvent::Removed`].
+    pub async fn subscribe_logs(&self, filter: &Filter) -> Result<Subscription<LogEvent>> {
+        self.ws_connection()
+            .await?
+            .subscribe(json!(["logs", filter_to_json(filter)]), parse_log_event)
+            .await
+    }
+
+    /// Shared WebSocket connection, opened on first use.
+    async fn ws_connection(&self) -> Result<&WsConnection> {
+        let url = self.ws_url.as_deref().ok_or_else(|| {
+            SdkError::Connection("WebSocket URL not configured; use with_ws_url".to_string())
+        })?;
+        self.ws.get_or_try_init(|| WsConnection::connect(url)).await
+    }
+
     /// Wait for transaction receipt.
     pub async fn wait_for_transaction(
         &self,
@@ -300,6 +479,63 @@ impl Client {
             SdkError::Rpc("Empty result".to_string())
         })
     }
+
+    /// Make a JSON-RPC batch request, returning one result per call in order.
+    async fn request_batch(
+        &self,
+        calls: Vec<(&str, serde_json::Value)>,
+    ) -> Result<Vec<Result<serde_json::Value>>> {
+        let count = calls.len();
+        let requests: Vec<RpcRequest> = calls
+            .into_iter()
+            .enumerate()
+            .map(|(id, (method, params))| RpcRequest {
+                jsonrpc: "2.0".to_string(),
+                method: method.to_string(),
+                params,
+                id: id as u64,
+            })
+            .collect();
+
+        let response_text = self.http
+            .post(&self.url)
+            .json(&requests)
+            .send()
+            .await?
+            .text()
+            .await?;
+
+        let responses: Vec<BatchResponse> = serde_json::from_str(&response_text)
+            .map_err(|e| SdkError::Serialization(format!("Failed to parse batch response: {}", e)))?;
+
+        // Responses may arrive in any order; match them up by id
+        let mut results: Vec<Result<serde_json::Value>> = (0..count)
+            .map(|_| Err(SdkError::Rpc("Missing batch response".to_string())))
+            .collect();
+        for response in responses {
+            let Some(slot) = response.id.and_then(|id| results.get_mut(id as usize)) else {
+                continue;
+            };
+            *slot = match (response.error, response.result) {
+                (Some(error), _) => Err(SdkError::Rpc(format!("{}: {}", error.code, error.message))),
+                (None, Some(result)) => Ok(result),
+                (None, None) => Err(SdkError::Rpc("Empty result".to_string())),
+            };
+        }
+
+        Ok(results)
+    }
+}
+
+/// One response in a batch.
+#[derive(Debug, Deserialize)]
+struct BatchResponse {
+    #[serde(default)]
+    id: Option<u64>,
+    #[serde(default)]
+    result: Option<serde_json::Value>,
+    #[serde(default)]
+    error: Option<RpcError>,
 }
 
 /// Format address as hex.
@@ -332,8 +568,13 @@ fn parse_hex_u256(hex: &str) -> Result<U256> {
     if hex.is_empty() {
         return Ok(U256::ZERO);
     }
+    // Quantities are not zero-padded ("0x1")
+    let hex = if hex.len() % 2 == 1 { format!("0{}", hex) } else { hex.to_string() };
     let bytes = hex::decode(hex)
         .map_err(|e| SdkError::Serialization(e.to_string()))?;
+    if bytes.len() > 32 {
+        return Err(SdkError::Serialization("Quantity exceeds 256 bits".to_string()));
+    }
     
     let mut padded = [0u8; 32];
     padded[32 - bytes.len()..].copy_from_slice(&bytes);
@@ -467,7 +708,61 @@ fn parse_receipt(value: serde_json::Value) -> Result<TransactionReceipt> {
     })
 }
 
+/// Parse block header.
+fn parse_block_header(value: serde_json::Value) -> Result<BlockHeader> {
+    let field_u64 = |name: &str| -> Result<u64> {
+        value
+            .get(name)
+            .and_then(|v| v.as_str())
+            .map(parse_hex_u64)
+            .transpose()
+            .map(|n| n.unwrap_or(0))
+    };
+
+    let hash = value
+        .get("hash")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| SdkError::Serialization("header.hash missing".to_string()))?;
+
+    let parent_hash = value
+        .get("parentHash")
+        .and_then(|v| v.as_str())
+        .map(parse_hash32)
+        .transpose()?
+        .unwrap_or([0u8; 32]);
+
+    let miner = value
+        .get("miner")
+        .and_then(|v| v.as_str())
+        .map(parse_address)
+        .transpose()?
+        .unwrap_or(Address::ZERO);
+
+    Ok(BlockHeader {
+        number: field_u64("number")?,
+        hash: parse_hash32(hash)?,
+        parent_hash,
+        timestamp: field_u64("timestamp")?,
+        gas_limit: field_u64("gasLimit")?,
+        gas_used: field_u64("gasUsed")?,
+        miner,
+    })
+}
+
 /// Parse log.
+fn parse_log_event(value: serde_json::Value) -> Result<LogEvent> {
+    let removed = value
+        .get("removed")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    let log = parse_log(value)?;
+    Ok(if removed {
+        LogEvent::Removed(log)
+    } else {
+        LogEvent::Added(log)
+    })
+}
+
 fn parse_log(value: serde_json::Value) -> Result<Log> {
     let address = value
         .get("address")
@@ -572,4 +867,140 @@ mod tests {
         let formatted = format_address(&addr);
         assert_eq!(formatted, "0x0000000000000000000000000000000000000000");
     }
+
+    fn decode_raw(params: &serde_json::Value) -> SignedTransaction {
+        let raw = params[0].as_str().unwrap().trim_start_matches("0x");
+        borsh::from_slice(&hex::decode(raw).unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_send_batch() {
+        use std::sync::{Arc, Mutex};
+
+        let submitted = Arc::new(Mutex::new(Vec::new()));
+        let log = submitted.clone();
+        let url = crate::test_utils::mock_rpc(move |method, params| match method {
+            "eth_chainId" => Ok(json!("0x539")),
+            "eth_getTransactionCount" => {
+                assert_eq!(params[1], "pending");
+                Ok(json!("0x5"))
+            }
+            "eth_gasPrice" => Ok(json!("0x3b9aca00")),
+            "eth_sendRawTransaction" => {
+                let signed = decode_raw(params);
+                log.lock().unwrap().push(signed.clone());
+                Ok(json!(format_hash(&signed.hash())))
+            }
+            _ => Err(format!("unexpected method {}", method)),
+        })
+        .await;
+
+        let client = Client::new(url);
+        let wallet = Wallet::from_private_key(&[1u8; 32]).unwrap();
+        let txs: Vec<TxRequest> = (1..=3u64)
+            .map(|i| TxRequest::transfer(Address::from_bytes([i as u8; 20]), U256::from(i)))
+            .collect();
+
+        let hashes = client.send_batch(&wallet, txs).await.unwrap();
+
+        let submitted = submitted.lock().unwrap();
+        assert_eq!(submitted.len(), 3);
+        for (i, signed) in submitted.iter().enumerate() {
+            assert_eq!(signed.tx.nonce, 5 + i as u64);
+            assert_eq!(signed.tx.chain_id, 1337);
+            assert_eq!(signed.tx.to, Some(Address::from_bytes([i as u8 + 1; 20])));
+            assert_eq!(hashes[i], signed.hash());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_send_batch_splits_large_batches() {
+        let url = crate::test_utils::mock_rpc(|method, params| match method {
+            "eth_getTransactionCount" => Ok(json!("0x0")),
+            "eth_sendRawTransaction" => Ok(json!(format_hash(&decode_raw(params).hash()))),
+            _ => Err(format!("unexpected method {}", method)),
+        })
+        .await;
+
+        let client = Client::new(url).with_chain_id(1337);
+        let wallet = Wallet::from_private_key(&[1u8; 32]).unwrap();
+        let count = MAX_BATCH_CALLS + 50;
+        let txs: Vec<TxRequest> = (0..count)
+            .map(|_| {
+                TxRequest::transfer(Address::from_bytes([2u8; 20]), U256::from(1u64))
+                    .max_fee_per_gas(U256::from(1u64))
+            })
+            .collect();
+
+        let hashes = client.send_batch(&wallet, txs).await.unwrap();
+        assert_eq!(hashes.len(), count);
+    }
+
+    #[tokio::test]
+    async fn test_prepare_transaction() {
+        let wallet = Wallet::from_private_key(&[1u8; 32]).unwrap();
+        let from = format_address(&wallet.address());
+        let url = crate::test_utils::mock_rpc(move |method, params| match method {
+            "eth_chainId" => Ok(json!("0x539")),
+            "eth_getTransactionCount" => Ok(json!("0x2")),
+            "eth_gasPrice" => Ok(json!("0x3b9aca00")),
+            "eth_estimateGas" => {
+                assert_eq!(params[0]["from"], json!(from));
+                assert_eq!(params[0]["data"], "0xabcd");
+                Ok(json!("0xc350"))
+            }
+            _ => Err(format!("unexpected method {}", method)),
+        })
+        .await;
+
+        let client = Client::new(url);
+        let to = Address::from_bytes([9u8; 20]);
+        let tx = client
+            .prepare_transaction(&wallet, Some(to), U256::from(7u64), vec![0xab, 0xcd])
+            .await
+            .unwrap();
+
+        // 50_000 estimated plus the 20% buffer
+        assert_eq!(tx.gas_limit, 60_000);
+        assert_eq!(tx.nonce, 2);
+        assert_eq!(tx.chain_id, 1337);
+        assert_eq!(tx.max_fee_per_gas, U256::from(1_000_000_000u64));
+        assert_eq!(tx.to, Some(to));
+        assert!(wallet.sign_transaction(tx).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_send_batch_partial_failure() {
+        let url = crate::test_utils::mock_rpc(|method, params| match method {
+            "eth_getTransactionCount" => Ok(json!("0x0")),
+            "eth_sendRawTransaction" => {
+                let signed = decode_raw(params);
+                if signed.tx.nonce == 1 {
+                    Err("insufficient funds".to_string())
+                } else {
+                    Ok(json!(format_hash(&signed.hash())))
+                }
+            }
+            _ => Err(format!("unexpected method {}", method)),
+        })
+        .await;
+
+        let client = Client::new(url).with_chain_id(1337);
+        let wallet = Wallet::from_private_key(&[1u8; 32]).unwrap();
+        let txs: Vec<TxRequest> = (0..3u8)
+            .map(|i| {
+                TxRequest::transfer(Address::from_bytes([i; 20]), U256::from(1u64))
+                    .max_fee_per_gas(U256::from(1u64))
+            })
+            .collect();
+
+        match client.send_batch(&wallet, txs).await {
+            Err(SdkError::Batch { succeeded, failed_index, message }) => {
+                assert_eq!(succeeded.len(), 1);
+                assert_eq!(failed_index, 1);
+                assert!(message.contains("insufficient funds"));
+            }
+            other => panic!("expected batch error, got {:?}", other),
+        }
+    }
 }
@@ -1,10 +1,34 @@
 //! Contract interaction helpers.
 
-use merklith_types::{Address, Transaction, U256};
+use borsh::{BorshDeserialize, BorshSerialize};
+use merklith_types::{Address, Hash, Transaction, U256};
 
 use crate::client::Client;
 use crate::errors::{Result, SdkError};
 use crate::types::{CallOptions, TxOptions};
+use crate::wallet::Wallet;
+
+/// Compute a function selector: the first 4 bytes of the BLAKE3 hash of its signature.
+pub fn function_selector(signature: &str) -> [u8; 4] {
+    let hash = blake3::hash(signature.as_bytes());
+    let mut selector = [0u8; 4];
+    selector.copy_from_slice(&hash.as_bytes()[..4]);
+    selector
+}
+
+/// Encode a call: the selector followed by the Borsh-encoded arguments.
+pub fn encode_call<A: BorshSerialize>(signature: &str, args: &A) -> Result<Vec<u8>> {
+    let mut data = function_selector(signature).to_vec();
+    args.serialize(&mut data)
+        .map_err(|e| SdkError::Serialization(e.to_string()))?;
+    Ok(data)
+}
+
+/// Decode a Borsh-encoded return value.
+pub fn decode_return<T: BorshDeserialize>(data: &[u8]) -> Result<T> {
+    borsh::from_slice(data)
+        .map_err(|e| SdkError::Contract(format!("Invalid return data: {}", e)))
+}
 
 /// Contract interface.
 pub struct Contract {
@@ -37,6 +61,37 @@ impl Contract {
         self.address
     }
 
+    /// Get the client used by this contract.
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// Call a method by signature and decode its return value.
+    pub async fn call_method<A: BorshSerialize, T: BorshDeserialize>(
+        &self,
+        signature: &str,
+        args: &A,
+    ) -> Result<T> {
+        let data = encode_call(signature, args)?;
+        let output = self.call(data, CallOptions::default()).await?;
+        decode_return(&output)
+    }
+
+    /// Sign and send a method call from `wallet`.
+    pub async fn send_method<A: BorshSerialize>(
+        &self,
+        wallet: &Wallet,
+        signature: &str,
+        args: &A,
+    ) -> Result<Hash> {
+        let data = encode_call(signature, args)?;
+        let tx = self.client
+            .prepare_transaction(wallet, Some(self.address), U256::ZERO, data)
+            .await?;
+        let signed = wallet.sign_transaction(tx)?;
+        self.client.send_signed_transaction(&signed).await
+    }
+
     /// Call a contract method (read-only).
     pub async fn call(
         &self,
@@ -171,4 +226,14 @@ mod tests {
         // Just ensure it compiles
         assert!(!builder.bytecode.is_empty());
     }
+
+    #[test]
+    fn test_encode_call() {
+        let owner = Address::from_bytes([7u8; 20]);
+        let data = encode_call("balanceOf(address)", &(owner,)).unwrap();
+
+        assert_eq!(&data[..4], &function_selector("balanceOf(address)"));
+        assert_eq!(&data[4..], owner.as_bytes());
+        assert_ne!(function_selector("balanceOf(address)"), function_selector("totalSupply()"));
+    }
 }
@@ -0,0 +1,154 @@
+//! ERC20 token helpers.
+//!
+//! Typed wrapper for the token in `contracts/examples/src/erc20.rs`.
+
+use merklith_types::{Address, Hash, U256};
+
+use crate::client::Client;
+use crate::contract::Contract;
+use crate::errors::Result;
+use crate::wallet::Wallet;
+
+/// ERC20 token contract.
+pub struct Erc20 {
+    contract: Contract,
+}
+
+impl Erc20 {
+    /// Create a wrapper for the token at `address`.
+    pub fn new(client: Client, address: Address) -> Self {
+        Self {
+            contract: Contract::new(client, address),
+        }
+    }
+
+    /// Get the underlying contract.
+    pub fn contract(&self) -> &Contract {
+        &self.contract
+    }
+
+    /// Token name.
+    pub async fn name(&self) -> Result<String> {
+        self.contract.call_method("name()", &()).await
+    }
+
+    /// Token symbol.
+    pub async fn symbol(&self) -> Result<String> {
+        self.contract.call_method("symbol()", &()).await
+    }
+
+    /// Token decimals.
+    pub async fn decimals(&self) -> Result<u8> {
+        self.contract.call_method("decimals()", &()).await
+    }
+
+    /// Total supply.
+    pub async fn total_supply(&self) -> Result<U256> {
+        self.contract.call_method("totalSupply()", &()).await
+    }
+
+    /// Balance of `owner`.
+    pub async fn balance_of(&self, owner: Address) -> Result<U256> {
+        self.contract.call_method("balanceOf(address)", &(owner,)).await
+    }
+
+    /// Amount `spender` may transfer on behalf of `owner`.
+    pub async fn allowance(&self, owner: Address, spender: Address) -> Result<U256> {
+        self.contract
+            .call_method("allowance(address,address)", &(owner, spender))
+            .await
+    }
+
+    /// Transfer `amount` from the wallet to `to`.
+    pub async fn transfer(&self, wallet: &Wallet, to: Address, amount: U256) -> Result<Hash> {
+        self.contract
+            .send_method(wallet, "transfer(address,uint256)", &(to, amount))
+            .await
+    }
+
+    /// Allow `spender` to transfer up to `amount` from the wallet.
+    pub async fn approve(&self, wallet: &Wallet, spender: Address, amount: U256) -> Result<Hash> {
+        self.contract
+            .send_method(wallet, "approve(address,uint256)", &(spender, amount))
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::contract::function_selector;
+    use merklith_types::SignedTransaction;
+    use serde_json::json;
+
+    fn borsh_hex<T: borsh::BorshSerialize>(value: &T) -> serde_json::Value {
+        json!(format!("0x{}", hex::encode(borsh::to_vec(value).unwrap())))
+    }
+
+    fn call_data(params: &serde_json::Value) -> Vec<u8> {
+        hex::decode(params[0]["data"].as_str().unwrap().trim_start_matches("0x")).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_erc20_reads() {
+        let owner = Address::from_bytes([1u8; 20]);
+        let spender = Address::from_bytes([2u8; 20]);
+        let url = crate::test_utils::mock_rpc(move |method, params| {
+            assert_eq!(method, "eth_call");
+            let data = call_data(params);
+            let (selector, args) = data.split_at(4);
+            if selector == function_selector("balanceOf(address)") {
+                assert_eq!(args, owner.as_bytes());
+                Ok(borsh_hex(&U256::from(1_500u64)))
+            } else if selector == function_selector("allowance(address,address)") {
+                assert_eq!(args, [owner.as_bytes().as_slice(), spender.as_bytes()].concat());
+                Ok(borsh_hex(&U256::from(40u64)))
+            } else if selector == function_selector("symbol()") {
+                Ok(borsh_hex(&"MERK".to_string()))
+            } else if selector == function_selector("decimals()") {
+                Ok(borsh_hex(&18u8))
+            } else {
+                Err("unknown selector".to_string())
+            }
+        })
+        .await;
+
+        let token = Erc20::new(Client::new(url), Address::from_bytes([9u8; 20]));
+        assert_eq!(token.balance_of(owner).await.unwrap(), U256::from(1_500u64));
+        assert_eq!(token.allowance(owner, spender).await.unwrap(), U256::from(40u64));
+        assert_eq!(token.symbol().await.unwrap(), "MERK");
+        assert_eq!(token.decimals().await.unwrap(), 18);
+        assert!(token.name().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_erc20_transfer() {
+        let to = Address::from_bytes([3u8; 20]);
+        let token_address = Address::from_bytes([9u8; 20]);
+        let url = crate::test_utils::mock_rpc(move |method, params| match method {
+            "eth_chainId" => Ok(json!("0x539")),
+            "eth_getTransactionCount" => Ok(json!("0x0")),
+            "eth_gasPrice" => Ok(json!("0x1")),
+            "eth_estimateGas" => Ok(json!("0x7530")),
+            "eth_sendRawTransaction" => {
+                let raw = params[0].as_str().unwrap().trim_start_matches("0x");
+                let signed: SignedTransaction = borsh::from_slice(&hex::decode(raw).unwrap()).unwrap();
+                assert_eq!(signed.tx.to, Some(token_address));
+
+                let (selector, args) = signed.tx.data.split_at(4);
+                assert_eq!(selector, function_selector("transfer(address,uint256)"));
+                let (recipient, amount): (Address, U256) = borsh::from_slice(args).unwrap();
+                assert_eq!(recipient, to);
+                assert_eq!(amount, U256::from(250u64));
+                Ok(json!(signed.hash().to_string()))
+            }
+            _ => Err(format!("unexpected method {}", method)),
+        })
+        .await;
+
+        let wallet = Wallet::from_private_key(&[1u8; 32]).unwrap();
+        let token = Erc20::new(Client::new(url), token_address);
+        let hash = token.transfer(&wallet, to, U256::from(250u64)).await.unwrap();
+        assert_ne!(hash, Hash::ZERO);
+    }
+}
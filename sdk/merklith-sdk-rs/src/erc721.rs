@@ -0,0 +1,181 @@
+//! ERC721 token helpers.
+//!
+//! Typed wrapper for the NFT in `contracts/examples/src/erc721.rs`.
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use merklith_types::{Address, Hash};
+
+use crate::client::Client;
+use crate::contract::{decode_return, Contract};
+use crate::errors::{Result, SdkError};
+use crate::events::{event_signature_to_topic, EventDecoder, EventEncoder};
+use crate::types::Log;
+use crate::wallet::Wallet;
+
+/// Signature of the NFT transfer event.
+pub const TRANSFER_EVENT: &str = "Transfer(address,address,uint256)";
+
+/// Signature of the NFT approval event.
+pub const APPROVAL_EVENT: &str = "Approval(address,address,uint256)";
+
+/// NFT transfer event.
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct NftTransferEvent {
+    pub from: Address,
+    pub to: Address,
+    pub token_id: u64,
+}
+
+/// NFT approval event.
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct NftApprovalEvent {
+    pub owner: Address,
+    pub approved: Address,
+    pub token_id: u64,
+}
+
+/// Decode the Borsh data of a log whose first topic is `signature`.
+fn decode_event<T: BorshDeserialize>(log: &Log, signature: &str) -> Result<T> {
+    if log.topics.first() != Some(&event_signature_to_topic(signature)) {
+        return Err(SdkError::Contract(format!("Log is not a {} event", signature)));
+    }
+    decode_return(&log.data)
+}
+
+fn encode_event<T: BorshSerialize>(event: &T, signature: &str) -> (Vec<[u8; 32]>, Vec<u8>) {
+    let data = borsh::to_vec(event).unwrap_or_default();
+    (vec![event_signature_to_topic(signature)], data)
+}
+
+impl EventDecoder for NftTransferEvent {
+    fn decode(log: &Log) -> Result<Self> {
+        decode_event(log, TRANSFER_EVENT)
+    }
+}
+
+impl EventEncoder for NftTransferEvent {
+    fn encode(&self) -> (Vec<[u8; 32]>, Vec<u8>) {
+        encode_event(self, TRANSFER_EVENT)
+    }
+}
+
+impl EventDecoder for NftApprovalEvent {
+    fn decode(log: &Log) -> Result<Self> {
+        decode_event(log, APPROVAL_EVENT)
+    }
+}
+
+impl EventEncoder for NftApprovalEvent {
+    fn encode(&self) -> (Vec<[u8; 32]>, Vec<u8>) {
+        encode_event(self, APPROVAL_EVENT)
+    }
+}
+
+/// ERC721 token contract.
+pub struct Erc721 {
+    contract: Contract,
+}
+
+impl Erc721 {
+    /// Create a wrapper for the collection at `address`.
+    pub fn new(client: Client, address: Address) -> Self {
+        Self {
+            contract: Contract::new(client, address),
+        }
+    }
+
+    /// Get the underlying contract.
+    pub fn contract(&self) -> &Contract {
+        &self.contract
+    }
+
+    /// Owner of `token_id`.
+    pub async fn owner_of(&self, token_id: u64) -> Result<Address> {
+        self.contract.call_method("ownerOf(uint256)", &(token_id,)).await
+    }
+
+    /// Number of tokens held by `owner`.
+    pub async fn balance_of(&self, owner: Address) -> Result<u64> {
+        self.contract.call_method("balanceOf(address)", &(owner,)).await
+    }
+
+    /// Metadata URI of `token_id`.
+    pub async fn token_uri(&self, token_id: u64) -> Result<String> {
+        self.contract.call_method("tokenURI(uint256)", &(token_id,)).await
+    }
+
+    /// Transfer `token_id` from `from` to `to`, signed by the wallet.
+    pub async fn transfer_from(
+        &self,
+        wallet: &Wallet,
+        from: Address,
+        to: Address,
+        token_id: u64,
+    ) -> Result<Hash> {
+        self.contract
+            .send_method(wallet, "transferFrom(address,address,uint256)", &(from, to, token_id))
+            .await
+    }
+
+    /// Approve `to` to transfer `token_id`.
+    pub async fn approve(&self, wallet: &Wallet, to: Address, token_id: u64) -> Result<Hash> {
+        self.contract
+            .send_method(wallet, "approve(address,uint256)", &(to, token_id))
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::contract::function_selector;
+    use serde_json::json;
+
+    fn log_from(event: &impl EventEncoder) -> Log {
+        let (topics, data) = event.encode();
+        Log {
+            address: Address::from_bytes([9u8; 20]),
+            topics,
+            data,
+            block_number: 10,
+            transaction_hash: [0u8; 32],
+            log_index: 0,
+        }
+    }
+
+    #[test]
+    fn test_decode_transfer_event() {
+        let event = NftTransferEvent {
+            from: Address::ZERO,
+            to: Address::from_bytes([4u8; 20]),
+            token_id: 42,
+        };
+        let log = log_from(&event);
+
+        assert_eq!(NftTransferEvent::decode(&log).unwrap(), event);
+        // An approval log is not mistaken for a transfer
+        let approval = log_from(&NftApprovalEvent {
+            owner: Address::from_bytes([4u8; 20]),
+            approved: Address::from_bytes([5u8; 20]),
+            token_id: 42,
+        });
+        assert!(NftTransferEvent::decode(&approval).is_err());
+        assert_eq!(NftApprovalEvent::decode(&approval).unwrap().token_id, 42);
+    }
+
+    #[tokio::test]
+    async fn test_owner_of() {
+        let owner = Address::from_bytes([4u8; 20]);
+        let url = crate::test_utils::mock_rpc(move |method, params| {
+            assert_eq!(method, "eth_call");
+            let data = hex::decode(params[0]["data"].as_str().unwrap().trim_start_matches("0x")).unwrap();
+            assert_eq!(&data[..4], &function_selector("ownerOf(uint256)"));
+            assert_eq!(&data[4..], &42u64.to_le_bytes());
+            Ok(json!(format!("0x{}", hex::encode(borsh::to_vec(&owner).unwrap()))))
+        })
+        .await;
+
+        let nft = Erc721::new(Client::new(url), Address::from_bytes([9u8; 20]));
+        assert_eq!(nft.owner_of(42).await.unwrap(), owner);
+    }
+}
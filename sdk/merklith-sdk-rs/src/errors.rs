@@ -1,5 +1,6 @@
 //! Error types for the SDK.
 
+use merklith_types::Hash;
 use thiserror::Error;
 
 /// SDK result type.
@@ -43,6 +44,20 @@ pub enum SdkError {
     /// Timeout
     #[error("Timeout: {0}")]
     Timeout(String),
+
+    /// Batch submission failed part-way
+    ///
+    /// `succeeded` holds the hashes of the transactions before `failed_index`;
+    /// later ones depend on the failed nonce and are not reported.
+    #[error("Batch failed at transaction {failed_index}: {message}")]
+    Batch {
+        /// Hashes of the transactions accepted before the failure
+        succeeded: Vec<Hash>,
+        /// Index of the first failed transaction
+        failed_index: usize,
+        /// Error for the failed transaction
+        message: String,
+    },
 }
 
 impl From<reqwest::Error> for SdkError {
@@ -14,16 +14,27 @@
 //! }
 //! ```
 
+pub mod bridge;
 pub mod client;
 pub mod contract;
+pub mod erc20;
+pub mod erc721;
 pub mod errors;
 pub mod events;
+pub mod subscription;
 pub mod types;
 pub mod wallet;
 
+#[cfg(any(test, feature = "test-utils"))]
+pub mod test_utils;
+
+pub use bridge::BridgeWatcher;
 pub use client::Client;
 pub use contract::Contract;
+pub use erc20::Erc20;
+pub use erc721::Erc721;
 pub use errors::{SdkError, Result};
+pub use subscription::Subscription;
 pub use types::*;
 pub use wallet::Wallet;
 
@@ -0,0 +1,443 @@
+//! WebSocket subscriptions (`eth_subscribe`).
+//!
+//! All subscriptions of a [`Client`](crate::Client) share one WebSocket
+//! connection. Notifications are routed by subscription id, and every live
+//! subscription is re-established if the connection drops.
+
+use futures::stream::{SplitSink, SplitStream};
+use futures::{SinkExt, Stream, StreamExt};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio::sync::{mpsc, oneshot};
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+use crate::errors::{Result, SdkError};
+
+/// Delay before reconnecting after the connection drops.
+pub const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+type WsStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+enum Command {
+    Subscribe {
+        params: Value,
+        notifications: mpsc::UnboundedSender<Value>,
+        reply: oneshot::Sender<Result<u64>>,
+    },
+    Unsubscribe {
+        id: u64,
+    },
+}
+
+/// Handle to the background task owning the WebSocket connection.
+#[derive(Debug, Clone)]
+pub(crate) struct WsConnection {
+    commands: mpsc::UnboundedSender<Command>,
+}
+
+impl WsConnection {
+    /// Connect to `url` and spawn the connection task.
+    pub(crate) async fn connect(url: &str) -> Result<Self> {
+        let (ws, _) = tokio_tungstenite::connect_async(url)
+            .await
+            .map_err(|e| SdkError::Connection(e.to_string()))?;
+
+        let (commands, receiver) = mpsc::unbounded_channel();
+        tokio::spawn(run(url.to_string(), ws, receiver));
+        Ok(Self { commands })
+    }
+
+    /// Subscribe with `params`, yielding each notification's `result` parsed by `parse`.
+    pub(crate) async fn subscribe<T>(
+        &self,
+        params: Value,
+        parse: fn(Value) -> Result<T>,
+    ) -> Result<Subscription<T>> {
+        let (notifications, receiver) = mpsc::unbounded_channel();
+        let (reply, confirmed) = oneshot::channel();
+        self.commands
+            .send(Command::Subscribe { params, notifications, reply })
+            .map_err(|_| SdkError::Connection("Subscription connection closed".to_string()))?;
+
+        let id = confirmed
+            .await
+            .map_err(|_| SdkError::Connection("Subscription connection closed".to_string()))??;
+
+        Ok(Subscription {
+            id,
+            notifications: receiver,
+            commands: self.commands.clone(),
+            parse,
+            _marker: PhantomData,
+        })
+    }
+}
+
+/// Stream of notifications for one subscription.
+///
+/// Dropping it sends `eth_unsubscribe`.
+pub struct Subscription<T> {
+    id: u64,
+    notifications: mpsc::UnboundedReceiver<Value>,
+    commands: mpsc::UnboundedSender<Command>,
+    parse: fn(Value) -> Result<T>,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Stream for Subscription<T> {
+    type Item = T;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        loop {
+            match self.notifications.poll_recv(cx) {
+                Poll::Ready(Some(value)) => match (self.parse)(value) {
+                    Ok(item) => return Poll::Ready(Some(item)),
+                    // Skip notifications we cannot decode
+                    Err(_) => continue,
+                },
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+impl<T> Drop for Subscription<T> {
+    fn drop(&mut self) {
+        let _ = self.commands.send(Command::Unsubscribe { id: self.id });
+    }
+}
+
+/// A subscription that outlives reconnects.
+struct Active {
+    params: Value,
+    notifications: mpsc::UnboundedSender<Value>,
+    /// Pending until the first subscription is confirmed
+    reply: Option<oneshot::Sender<Result<u64>>>,
+    /// Id assigned by the node on the current connection
+    server_id: Option<String>,
+}
+
+/// Connection state, keyed by our own stable subscription ids.
+#[derive(Default)]
+struct Router {
+    subscriptions: HashMap<u64, Active>,
+    /// Node subscription id -> our id, for the current connection
+    server_ids: HashMap<String, u64>,
+    /// In-flight `eth_subscribe` request id -> our id
+    pending: HashMap<u64, u64>,
+    next_id: u64,
+    next_request: u64,
+}
+
+impl Router {
+    fn insert(
+        &mut self,
+        params: Value,
+        notifications: mpsc::UnboundedSender<Value>,
+        reply: oneshot::Sender<Result<u64>>,
+    ) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.subscriptions.insert(id, Active {
+            params,
+            notifications,
+            reply: Some(reply),
+            server_id: None,
+        });
+        id
+    }
+
+    fn request(&mut self, method: &str, params: Value) -> (u64, Message) {
+        let id = self.next_request;
+        self.next_request += 1;
+        let request = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+        (id, Message::Text(request.to_string()))
+    }
+
+    fn subscribe_request(&mut self, id: u64) -> Option<Message> {
+        let params = self.subscriptions.get(&id)?.params.clone();
+        let (request_id, message) = self.request("eth_subscribe", params);
+        self.pending.insert(request_id, id);
+        Some(message)
+    }
+
+    fn handle(&mut self, text: &str) {
+        let Ok(message) = serde_json::from_str::<Value>(text) else {
+            return;
+        };
+
+        if message["method"] == "eth_subscription" {
+            let params = &message["params"];
+            let id = params["subscription"]
+                .as_str()
+                .and_then(|server_id| self.server_ids.get(server_id));
+            if let Some(active) = id.and_then(|id| self.subscriptions.get(id)) {
+                let _ = active.notifications.send(params["result"].clone());
+            }
+            return;
+        }
+
+        let Some(id) = message["id"].as_u64().and_then(|request| self.pending.remove(&request)) else {
+            return;
+        };
+        let Some(active) = self.subscriptions.get_mut(&id) else {
+            return;
+        };
+
+        match message["result"].as_str() {
+            Some(server_id) => {
+                active.server_id = Some(server_id.to_string());
+                self.server_ids.insert(server_id.to_string(), id);
+                if let Some(reply) = active.reply.take() {
+                    let _ = reply.send(Ok(id));
+                }
+            }
+            None => {
+                let error = message["error"]["message"]
+                    .as_str()
+                    .unwrap_or("Invalid subscription response")
+                    .to_string();
+                if let Some(active) = self.subscriptions.remove(&id) {
+                    if let Some(reply) = active.reply {
+                        let _ = reply.send(Err(SdkError::Rpc(error)));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Own the connection: route notifications, serve commands, and reconnect.
+async fn run(url: String, ws: WsStream, mut commands: mpsc::UnboundedReceiver<Command>) {
+    let mut router = Router::default();
+    let mut ws = Some(ws);
+
+    loop {
+        let connection = match ws.take() {
+            Some(ws) => ws,
+            None => {
+                tokio::time::sleep(RECONNECT_DELAY).await;
+
+                // Queue commands sent while disconnected
+                loop {
+                    match commands.try_recv() {
+                        Ok(Command::Subscribe { params, notifications, reply }) => {
+                            router.insert(params, notifications, reply);
+                        }
+                        Ok(Command::Unsubscribe { id }) => {
+                            router.subscriptions.remove(&id);
+                        }
+                        Err(mpsc::error::TryRecvError::Empty) => break,
+                        Err(mpsc::error::TryRecvError::Disconnected) => return,
+                    }
+                }
+
+                match tokio_tungstenite::connect_async(url.as_str()).await {
+                    Ok((ws, _)) => ws,
+                    Err(_) => continue,
+                }
+            }
+        };
+        let (mut sink, mut stream) = connection.split();
+
+        // Re-establish every live subscription on the new connection
+        router.server_ids.clear();
+        router.pending.clear();
+        let ids: Vec<u64> = router.subscriptions.keys().copied().collect();
+        let mut resubscribed = true;
+        for id in ids {
+            if let Some(active) = router.subscriptions.get_mut(&id) {
+                active.server_id = None;
+            }
+            if let Some(message) = router.subscribe_request(id) {
+                resubscribed &= sink.send(message).await.is_ok();
+            }
+        }
+
+        if resubscribed && !serve(&mut router, &mut sink, &mut stream, &mut commands).await {
+            let _ = sink.close().await;
+            return;
+        }
+    }
+}
+
+/// Serve one connection. Returns false once every handle is gone, true on disconnect.
+async fn serve(
+    router: &mut Router,
+    sink: &mut SplitSink<WsStream, Message>,
+    stream: &mut SplitStream<WsStream>,
+    commands: &mut mpsc::UnboundedReceiver<Command>,
+) -> bool {
+    loop {
+        tokio::select! {
+            command = commands.recv() => match command {
+                Some(Command::Subscribe { params, notifications, reply }) => {
+                    let id = router.insert(params, notifications, reply);
+                    if let Some(message) = router.subscribe_request(id) {
+                        if sink.send(message).await.is_err() {
+                            return true;
+                        }
+                    }
+                }
+                Some(Command::Unsubscribe { id }) => {
+                    let server_id = router.subscriptions.remove(&id).and_then(|active| active.server_id);
+                    if let Some(server_id) = server_id {
+                        router.server_ids.remove(&server_id);
+                        let (_, message) = router.request("eth_unsubscribe", json!([server_id]));
+                        if sink.send(message).await.is_err() {
+                            return true;
+                        }
+                    }
+                }
+                // Streams hold a sender too, so this means every handle is gone
+                None => return false,
+            },
+            message = stream.next() => match message {
+                Some(Ok(Message::Text(text))) => router.handle(&text),
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return true,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Client;
+    use tokio::net::TcpListener;
+
+    type ServerStream = WebSocketStream<TcpStream>;
+
+    fn head(number: u64) -> Value {
+        json!({
+            "number": format!("0x{:x}", number),
+            "hash": format!("0x{}", hex::encode([number as u8; 32])),
+            "parentHash": format!("0x{}", hex::encode([number as u8 - 1; 32])),
+            "timestamp": "0x64",
+            "gasLimit": "0x1c9c380",
+            "gasUsed": "0x5208",
+            "miner": format!("0x{}", hex::encode([1u8; 20])),
+        })
+    }
+
+    async fn expect_call(ws: &mut ServerStream, method: &str) -> Value {
+        loop {
+            if let Message::Text(text) = ws.next().await.unwrap().unwrap() {
+                let call: Value = serde_json::from_str(&text).unwrap();
+                assert_eq!(call["method"], method);
+                return call;
+            }
+        }
+    }
+
+    async fn confirm(ws: &mut ServerStream, call: &Value, server_id: &str) {
+        let reply = json!({ "jsonrpc": "2.0", "id": call["id"], "result": server_id });
+        ws.send(Message::Text(reply.to_string())).await.unwrap();
+    }
+
+    async fn notify(ws: &mut ServerStream, server_id: &str, result: Value) {
+        let notification = json!({
+            "jsonrpc": "2.0",
+            "method": "eth_subscription",
+            "params": { "subscription": server_id, "result": result },
+        });
+        ws.send(Message::Text(notification.to_string())).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_new_heads() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        let (unsubscribed, unsubscribe_params) = oneshot::channel();
+
+        tokio::spawn(async move {
+            // First connection drops right after one notification
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut ws = tokio_tungstenite::accept_async(socket).await.unwrap();
+            let call = expect_call(&mut ws, "eth_subscribe").await;
+            assert_eq!(call["params"], json!(["newHeads"]));
+            confirm(&mut ws, &call, "0xa").await;
+            notify(&mut ws, "0xa", head(1)).await;
+            drop(ws);
+
+            // The client reconnects and resubscribes under a new id
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut ws = tokio_tungstenite::accept_async(socket).await.unwrap();
+            let call = expect_call(&mut ws, "eth_subscribe").await;
+            assert_eq!(call["params"], json!(["newHeads"]));
+            confirm(&mut ws, &call, "0xb").await;
+            notify(&mut ws, "0xb", head(2)).await;
+
+            let call = expect_call(&mut ws, "eth_unsubscribe").await;
+            let _ = unsubscribed.send(call["params"].clone());
+        });
+
+        let client = Client::new("http://127.0.0.1:1").with_ws_url(url);
+        let mut heads = client.subscribe_new_heads().await.unwrap();
+
+        let timeout = Duration::from_secs(10);
+        let first = tokio::time::timeout(timeout, heads.next()).await.unwrap().unwrap();
+        assert_eq!(first.number, 1);
+        assert_eq!(first.hash, [1u8; 32]);
+        assert_eq!(first.gas_used, 21_000);
+
+        let second = tokio::time::timeout(timeout, heads.next()).await.unwrap().unwrap();
+        assert_eq!(second.number, 2);
+        assert_eq!(second.parent_hash, [1u8; 32]);
+
+        // Dropping the stream unsubscribes the current server id
+        drop(heads);
+        let params = tokio::time::timeout(timeout, unsubscribe_params).await.unwrap().unwrap();
+        assert_eq!(params, json!(["0xb"]));
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_logs_reorg() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+
+        tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut ws = tokio_tungstenite::accept_async(socket).await.unwrap();
+            let call = expect_call(&mut ws, "eth_subscribe").await;
+            assert_eq!(call["params"][0], "logs");
+            confirm(&mut ws, &call, "0xa").await;
+
+            let mut log = json!({
+                "address": format!("0x{}", hex::encode([9u8; 20])),
+                "topics": [],
+                "data": "0x01",
+                "blockNumber": "0x5",
+                "transactionHash": format!("0x{}", hex::encode([3u8; 32])),
+                "logIndex": "0x0",
+                "removed": false,
+            });
+            notify(&mut ws, "0xa", log.clone()).await;
+            // Block 5 is reorged out
+            log["removed"] = json!(true);
+            notify(&mut ws, "0xa", log).await;
+            let _ = expect_call(&mut ws, "eth_unsubscribe").await;
+        });
+
+        let client = Client::new("http://127.0.0.1:1").with_ws_url(url);
+        let mut logs = client.subscribe_logs(&crate::Filter::new()).await.unwrap();
+
+        let timeout = Duration::from_secs(10);
+        let added = tokio::time::timeout(timeout, logs.next()).await.unwrap().unwrap();
+        assert!(matches!(added, crate::LogEvent::Added(_)));
+
+        let removed = tokio::time::timeout(timeout, logs.next()).await.unwrap().unwrap();
+        assert!(removed.is_removed());
+        assert_eq!(removed.log().block_number, 5);
+        assert_eq!(removed.log().transaction_hash, added.log().transaction_hash);
+        assert_eq!(removed.log().data, vec![1]);
+    }
+}
@@ -0,0 +1,88 @@
+//! Mock JSON-RPC server for tests of the SDK and its users.
+//!
+//! Enabled for dependents with the `test-utils` feature.
+
+use crate::client::MAX_BATCH_CALLS;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+type Handler = dyn Fn(&str, &Value) -> Result<Value, String> + Send + Sync;
+
+/// Serve JSON-RPC over HTTP on a local port, answering each call with `handler`.
+///
+/// Batches are answered call by call, in order; like the node, empty batches and
+/// batches over [`MAX_BATCH_CALLS`] get a single error. Returns the server URL.
+pub async fn mock_rpc<F>(handler: F) -> String
+where
+    F: Fn(&str, &Value) -> Result<Value, String> + Send + Sync + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let handler: Arc<Handler> = Arc::new(handler);
+
+    tokio::spawn(async move {
+        while let Ok((mut socket, _)) = listener.accept().await {
+            let handler = handler.clone();
+            tokio::spawn(async move {
+                let body = read_body(&mut socket).await;
+                let call: Value = serde_json::from_str(&body).unwrap();
+                let response = match call.as_array() {
+                    Some(calls) if calls.is_empty() || calls.len() > MAX_BATCH_CALLS => json!({
+                        "jsonrpc": "2.0",
+                        "id": null,
+                        "error": { "code": -32600, "message": "Invalid request: batch size" },
+                    }),
+                    Some(calls) => Value::Array(calls.iter().map(|c| answer(&*handler, c)).collect()),
+                    None => answer(&*handler, &call),
+                }
+                .to_string();
+
+                let reply = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    response.len(),
+                    response
+                );
+                socket.write_all(reply.as_bytes()).await.unwrap();
+            });
+        }
+    });
+
+    url
+}
+
+fn answer(handler: &Handler, call: &Value) -> Value {
+    let method = call["method"].as_str().unwrap_or_default();
+    match handler(method, &call["params"]) {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": call["id"], "result": result }),
+        Err(message) => json!({
+            "jsonrpc": "2.0",
+            "id": call["id"],
+            "error": { "code": -32000, "message": message },
+        }),
+    }
+}
+
+async fn read_body(socket: &mut TcpStream) -> String {
+    let mut request = Vec::new();
+    let mut buf = [0u8; 4096];
+    loop {
+        let n = socket.read(&mut buf).await.unwrap();
+        request.extend_from_slice(&buf[..n]);
+        let text = String::from_utf8_lossy(&request).to_string();
+        if let Some(split) = text.find("\r\n\r\n") {
+            let content_length = text[..split]
+                .lines()
+                .find_map(|line| {
+                    line.to_ascii_lowercase()
+                        .strip_prefix("content-length:")
+                        .map(|v| v.trim().parse::<usize>().unwrap())
+                })
+                .unwrap_or(0);
+            if request.len() >= split + 4 + content_length {
+                return text[split + 4..split + 4 + content_length].to_string();
+            }
+        }
+    }
+}
@@ -98,6 +98,52 @@ impl TxOptions {
     }
 }
 
+/// A transaction to be sequenced and signed by the client.
+#[derive(Debug, Clone)]
+pub struct TxRequest {
+    /// Recipient (None for contract creation)
+    pub to: Option<Address>,
+    /// Value
+    pub value: U256,
+    /// Call data
+    pub data: Vec<u8>,
+    /// Gas limit
+    pub gas_limit: u64,
+    /// Max fee per gas (fetched from the node if not set)
+    pub max_fee_per_gas: Option<U256>,
+}
+
+impl TxRequest {
+    /// Create a plain value transfer.
+    pub fn transfer(to: Address, value: U256) -> Self {
+        Self {
+            to: Some(to),
+            value,
+            data: Vec::new(),
+            gas_limit: 21_000,
+            max_fee_per_gas: None,
+        }
+    }
+
+    /// Set call data.
+    pub fn data(mut self, data: Vec<u8>) -> Self {
+        self.data = data;
+        self
+    }
+
+    /// Set gas limit.
+    pub fn gas_limit(mut self, limit: u64) -> Self {
+        self.gas_limit = limit;
+        self
+    }
+
+    /// Set max fee per gas.
+    pub fn max_fee_per_gas(mut self, fee: U256) -> Self {
+        self.max_fee_per_gas = Some(fee);
+        self
+    }
+}
+
 /// Contract call options.
 #[derive(Debug, Clone, Default)]
 pub struct CallOptions {
@@ -160,6 +206,25 @@ pub struct TransactionReceipt {
     pub logs: Vec<Log>,
 }
 
+/// Block header, as delivered by `newHeads` subscriptions.
+#[derive(Debug, Clone)]
+pub struct BlockHeader {
+    /// Block number
+    pub number: u64,
+    /// Block hash
+    pub hash: [u8; 32],
+    /// Parent hash
+    pub parent_hash: [u8; 32],
+    /// Timestamp (Unix seconds)
+    pub timestamp: u64,
+    /// Gas limit
+    pub gas_limit: u64,
+    /// Gas used
+    pub gas_used: u64,
+    /// Block producer
+    pub miner: Address,
+}
+
 /// Log entry.
 #[derive(Debug, Clone)]
 pub struct Log {
@@ -177,6 +242,29 @@ pub struct Log {
     pub log_index: u64,
 }
 
+/// Log delivered by a subscription.
+#[derive(Debug, Clone)]
+pub enum LogEvent {
+    /// Log included in a canonical block
+    Added(Log),
+    /// Previously added log whose block was reorged out; undo its effects
+    Removed(Log),
+}
+
+impl LogEvent {
+    /// The log, whether added or removed.
+    pub fn log(&self) -> &Log {
+        match self {
+            LogEvent::Added(log) | LogEvent::Removed(log) => log,
+        }
+    }
+
+    /// Whether the log was reorged out.
+    pub fn is_removed(&self) -> bool {
+        matches!(self, LogEvent::Removed(_))
+    }
+}
+
 /// Filter for event logs.
 #[derive(Debug, Clone, Default)]
 pub struct Filter {
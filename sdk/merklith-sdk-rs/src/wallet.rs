@@ -21,7 +21,15 @@ impl Wallet {
 
     /// Load wallet from private key bytes.
     pub fn from_bytes(bytes: &[u8; 32]) -> Result<Self> {
-        let keypair = Ed25519Keypair::from_seed(bytes);
+        Self::from_private_key(bytes)
+    }
+
+    /// Load wallet from a private key, deriving its address.
+    ///
+    /// Use [`Wallet::verify_address`] to confirm the key belongs to the
+    /// expected account before using it.
+    pub fn from_private_key(key: &[u8; 32]) -> Result<Self> {
+        let keypair = Ed25519Keypair::from_seed(key);
         let address = keypair.address();
         Ok(Self { keypair, address })
     }
@@ -47,6 +55,11 @@ impl Wallet {
         self.address
     }
 
+    /// Check that this wallet's key derives the expected address.
+    pub fn verify_address(&self, expected: Address) -> bool {
+        self.address == expected
+    }
+
     /// Sign a transaction.
     pub fn sign_transaction(
         &self,
@@ -117,4 +130,18 @@ mod tests {
         let signature = wallet.sign_message(message).unwrap();
         assert!(!signature.is_empty());
     }
+
+    #[test]
+    fn test_wallet_verify_address() {
+        let key = [1u8; 32];
+        let wallet = Wallet::from_private_key(&key).unwrap();
+
+        let expected = "0x83561adb398fd87f8e7ed8331bff2fcb945733cc".parse::<Address>().unwrap();
+        assert_eq!(wallet.address(), expected);
+        assert!(wallet.verify_address(expected));
+
+        // A different key does not match
+        let other = Wallet::from_private_key(&[2u8; 32]).unwrap();
+        assert!(!other.verify_address(expected));
+    }
 }
@@ -0,0 +1,170 @@
+//! Route handlers
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use merklith_types::{Address, Hash};
+use merklith_vm::{verify_source, VerifyError};
+use serde_json::{json, Value};
+
+use crate::models::{ContractResponse, ContractVerification, VerifyContractRequest};
+use crate::AppState;
+
+type ApiError = (StatusCode, String);
+
+/// Table holding verification records when a database is configured;
+/// created once at startup by `init_schema`
+const CONTRACT_VERIFICATIONS_SCHEMA: &str = "CREATE TABLE IF NOT EXISTS contract_verifications (
+    address TEXT PRIMARY KEY,
+    compiler_version TEXT NOT NULL,
+    source_hash TEXT NOT NULL,
+    code_hash TEXT NOT NULL,
+    verified_at BIGINT NOT NULL
+)";
+
+/// Create the tables the handlers read and write
+pub async fn init_schema(pool: &sqlx::PgPool) -> Result<(), sqlx::Error> {
+    sqlx::query(CONTRACT_VERIFICATIONS_SCHEMA).execute(pool).await?;
+    Ok(())
+}
+
+/// `GET /api/contracts/:address` - deployed code, plus the verification
+/// record if the contract has been verified
+pub async fn get_contract(
+    State(state): State<AppState>,
+    Path(address): Path<String>,
+) -> Result<Json<ContractResponse>, ApiError> {
+    let address = normalize_address(&address)?;
+    let code = fetch_code(&state.rpc_url, &address).await?;
+    if code.is_empty() {
+        return Err((StatusCode::NOT_FOUND, format!("No contract at {}", address)));
+    }
+
+    // A record only counts while the deployed code still matches it
+    let code_hash = Hash::compute(&code).to_hex();
+    let verification = load_verification(&state, &address)
+        .await?
+        .filter(|record| record.code_hash == code_hash);
+    Ok(Json(ContractResponse {
+        address,
+        bytecode: format!("0x{}", hex::encode(&code)),
+        verified: verification.is_some(),
+        verification,
+    }))
+}
+
+/// `POST /api/contracts/:address/verify` - rebuild the submitted source and
+/// record the contract as verified if it produces the deployed code.
+/// A source that builds to different code is rejected with 422.
+pub async fn verify_contract(
+    State(state): State<AppState>,
+    Path(address): Path<String>,
+    Json(request): Json<VerifyContractRequest>,
+) -> Result<Json<ContractVerification>, ApiError> {
+    let address = normalize_address(&address)?;
+    let code = fetch_code(&state.rpc_url, &address).await?;
+    if code.is_empty() {
+        return Err((StatusCode::NOT_FOUND, format!("No contract at {}", address)));
+    }
+
+    let verified = verify_source(&request.compiler_version, &request.source, &code).map_err(|e| match e {
+        VerifyError::Mismatch { .. } => (StatusCode::UNPROCESSABLE_ENTITY, e.to_string()),
+        VerifyError::UnsupportedCompiler(_) | VerifyError::Compile(_) => (StatusCode::BAD_REQUEST, e.to_string()),
+    })?;
+    let record = ContractVerification {
+        address,
+        compiler_version: verified.compiler_version,
+        source_hash: verified.source_hash.to_hex(),
+        code_hash: verified.code_hash.to_hex(),
+        verified_at: chrono::Utc::now().timestamp(),
+    };
+    store_verification(&state, &record).await?;
+    Ok(Json(record))
+}
+
+fn decode_hex(s: &str) -> Result<Vec<u8>, hex::FromHexError> {
+    hex::decode(s.strip_prefix("0x").unwrap_or(s))
+}
+
+/// Lowercase `0x`-prefixed form, so records are keyed the same way however
+/// the address was written
+fn normalize_address(address: &str) -> Result<String, ApiError> {
+    address
+        .parse::<Address>()
+        .map(|a| format!("0x{}", a.to_hex()))
+        .map_err(|_| (StatusCode::BAD_REQUEST, format!("Invalid address: {}", address)))
+}
+
+/// Deployed code at `address` from the node
+async fn fetch_code(rpc_url: &str, address: &str) -> Result<Vec<u8>, ApiError> {
+    let unavailable = |e: String| (StatusCode::BAD_GATEWAY, format!("Node request failed: {}", e));
+    let body: Value = reqwest::Client::new()
+        .post(rpc_url)
+        .json(&json!({ "jsonrpc": "2.0", "method": "eth_getCode", "params": [address, "latest"], "id": 1 }))
+        .send()
+        .await
+        .map_err(|e| unavailable(e.to_string()))?
+        .json()
+        .await
+        .map_err(|e| unavailable(e.to_string()))?;
+    if let Some(error) = body.get("error") {
+        return Err(unavailable(error.to_string()));
+    }
+    let code = body.get("result").and_then(Value::as_str).unwrap_or("0x");
+    decode_hex(code).map_err(|e| unavailable(e.to_string()))
+}
+
+/// Save `record`, replacing an earlier verification of the same contract
+async fn store_verification(state: &AppState, record: &ContractVerification) -> Result<(), ApiError> {
+    let Some(db) = &state.db_pool else {
+        state.verifications.write().await.insert(record.address.clone(), record.clone());
+        return Ok(());
+    };
+    let pool = db.read().await;
+    sqlx::query(
+        "INSERT INTO contract_verifications (address, compiler_version, source_hash, code_hash, verified_at)
+         VALUES ($1, $2, $3, $4, $5)
+         ON CONFLICT (address) DO UPDATE SET
+             compiler_version = EXCLUDED.compiler_version,
+             source_hash = EXCLUDED.source_hash,
+             code_hash = EXCLUDED.code_hash,
+             verified_at = EXCLUDED.verified_at",
+    )
+    .bind(&record.address)
+    .bind(&record.compiler_version)
+    .bind(&record.source_hash)
+    .bind(&record.code_hash)
+    .bind(record.verified_at)
+    .execute(&*pool)
+    .await
+    .map_err(database_error)?;
+    Ok(())
+}
+
+async fn load_verification(state: &AppState, address: &str) -> Result<Option<ContractVerification>, ApiError> {
+    let Some(db) = &state.db_pool else {
+        return Ok(state.verifications.read().await.get(address).cloned());
+    };
+    let pool = db.read().await;
+    let row: Option<(String, String, String, String, i64)> = sqlx::query_as(
+        "SELECT address, compiler_version, source_hash, code_hash, verified_at
+         FROM contract_verifications WHERE address = $1",
+    )
+    .bind(address)
+    .fetch_optional(&*pool)
+    .await
+    .map_err(database_error)?;
+    Ok(row.map(|(address, compiler_version, source_hash, code_hash, verified_at)| ContractVerification {
+        address,
+        compiler_version,
+        source_hash,
+        code_hash,
+        verified_at,
+    }))
+}
+
+fn database_error(e: sqlx::Error) -> ApiError {
+    (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e))
+}
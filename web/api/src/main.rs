@@ -6,6 +6,7 @@ use axum::{
     Json,
 };
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use tower_http::cors::{CorsLayer, Any};
@@ -25,6 +26,8 @@ pub struct AppState {
     pub cache: Arc<Cache>,
     pub rpc_url: String,
     pub db_pool: Option<Arc<RwLock<sqlx::PgPool>>>,
+    /// Contract verifications by address, used when no database is configured
+    pub verifications: Arc<RwLock<HashMap<String, ContractVerification>>>,
 }
 
 #[tokio::main]
@@ -39,10 +42,21 @@ async fn main() {
     // Initialize cache
     let cache = Arc::new(Cache::new());
     
+    // Connect to Postgres when configured, creating tables up front
+    let db_pool = match std::env::var("DATABASE_URL") {
+        Ok(url) => {
+            let pool = sqlx::PgPool::connect(&url).await.expect("Failed to connect to database");
+            handlers::init_schema(&pool).await.expect("Failed to create database tables");
+            Some(Arc::new(RwLock::new(pool)))
+        }
+        Err(_) => None,
+    };
+    
     let state = AppState {
         cache,
         rpc_url,
-        db_pool: None,
+        db_pool,
+        verifications: Arc::new(RwLock::new(HashMap::new())),
     };
     
     // Build router
@@ -66,6 +80,10 @@ async fn main() {
         .route("/api/accounts/:address/transactions", get(get_account_transactions))
         .route("/api/accounts/:address/balance", get(get_account_balance))
         
+        // Contracts
+        .route("/api/contracts/:address", get(get_contract))
+        .route("/api/contracts/:address/verify", post(verify_contract))
+        
         // Validators
         .route("/api/validators", get(get_validators))
         .route("/api/validators/:address", get(get_validator))
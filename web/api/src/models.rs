@@ -0,0 +1,36 @@
+//! Request and response bodies
+
+use serde::{Deserialize, Serialize};
+
+/// Body of `POST /api/contracts/:address/verify`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VerifyContractRequest {
+    /// Compiler to rebuild the source with; see `merklith_vm::verify::SUPPORTED_COMPILERS`
+    pub compiler_version: String,
+    /// Contract source
+    pub source: String,
+}
+
+/// A contract whose deployed code was rebuilt from its source
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContractVerification {
+    pub address: String,
+    pub compiler_version: String,
+    pub source_hash: String,
+    /// blake3 hash of the deployed code, hex
+    pub code_hash: String,
+    /// Unix timestamp of the verification
+    pub verified_at: i64,
+}
+
+/// Body of `GET /api/contracts/:address`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContractResponse {
+    pub address: String,
+    /// Deployed code, hex
+    pub bytecode: String,
+    pub verified: bool,
+    /// Present once the contract has been verified
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verification: Option<ContractVerification>,
+}